@@ -0,0 +1,329 @@
+//! Sound output through ALSA's default device.
+//!
+//! `libasound.so.2` is loaded when the output opens rather than linked, so
+//! builds need no ALSA headers and machines without it fall back to
+//! `LogOutput`. There are no sound files: every sound is synthesized from its
+//! material's timbre when played, and a thread mixes the playing sounds into
+//! the device.
+
+use std::f32::consts::TAU;
+use std::ffi::{CStr, c_char, c_int, c_long, c_uint, c_ulong, c_void};
+use std::ptr;
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::thread;
+
+use libloading::Library;
+
+use crate::audio::{AudioOutput, PlayedSound, SoundAction, SoundMaterial};
+use crate::rand::Rng;
+
+const SAMPLE_RATE: u32 = 44_100;
+/// Frames mixed and written at a time, about 12 ms.
+const PERIOD_FRAMES: usize = 512;
+/// Device buffer ALSA is asked for; short enough that sounds stay in step
+/// with the actions that make them.
+const LATENCY_MICROS: c_uint = 50_000;
+
+const SND_PCM_STREAM_PLAYBACK: c_int = 0;
+const SND_PCM_FORMAT_S16_LE: c_int = 2;
+const SND_PCM_ACCESS_RW_INTERLEAVED: c_int = 3;
+
+type SndPcm = c_void;
+
+/// The few `libasound` entry points the output uses.
+struct Alsa {
+    open: unsafe extern "C" fn(*mut *mut SndPcm, *const c_char, c_int, c_int) -> c_int,
+    set_params:
+        unsafe extern "C" fn(*mut SndPcm, c_int, c_int, c_uint, c_uint, c_int, c_uint) -> c_int,
+    writei: unsafe extern "C" fn(*mut SndPcm, *const c_void, c_ulong) -> c_long,
+    recover: unsafe extern "C" fn(*mut SndPcm, c_int, c_int) -> c_int,
+    close: unsafe extern "C" fn(*mut SndPcm) -> c_int,
+    strerror: unsafe extern "C" fn(c_int) -> *const c_char,
+    /// Keeps the functions above loaded.
+    _library: Library,
+}
+
+impl Alsa {
+    fn load() -> Result<Self, String> {
+        // SAFETY: libasound runs no initialisation with preconditions when
+        // loaded, and each symbol is given its C signature from asoundlib.h.
+        unsafe {
+            let library = Library::new("libasound.so.2").map_err(|err| err.to_string())?;
+            macro_rules! symbol {
+                ($name:literal) => {
+                    *library
+                        .get(concat!($name, "\0").as_bytes())
+                        .map_err(|err| err.to_string())?
+                };
+            }
+            Ok(Self {
+                open: symbol!("snd_pcm_open"),
+                set_params: symbol!("snd_pcm_set_params"),
+                writei: symbol!("snd_pcm_writei"),
+                recover: symbol!("snd_pcm_recover"),
+                close: symbol!("snd_pcm_close"),
+                strerror: symbol!("snd_strerror"),
+                _library: library,
+            })
+        }
+    }
+
+    fn error(&self, code: c_int) -> String {
+        // SAFETY: snd_strerror returns a static string for any code.
+        unsafe { CStr::from_ptr((self.strerror)(code)) }
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+/// An open playback device, mono at `SAMPLE_RATE`.
+struct Device {
+    alsa: Alsa,
+    pcm: *mut SndPcm,
+}
+
+// SAFETY: the handle is used by one thread at a time; after opening it
+// moves to the mixing thread for good.
+unsafe impl Send for Device {}
+
+impl Device {
+    fn open() -> Result<Self, String> {
+        let alsa = Alsa::load()?;
+        let mut pcm = ptr::null_mut();
+        // SAFETY: `pcm` receives the handle, and the name is NUL-terminated.
+        let result =
+            unsafe { (alsa.open)(&mut pcm, c"default".as_ptr(), SND_PCM_STREAM_PLAYBACK, 0) };
+        if result < 0 {
+            return Err(alsa.error(result));
+        }
+        let device = Self { alsa, pcm };
+        // SAFETY: `pcm` was just opened.
+        let result = unsafe {
+            (device.alsa.set_params)(
+                device.pcm,
+                SND_PCM_FORMAT_S16_LE,
+                SND_PCM_ACCESS_RW_INTERLEAVED,
+                1,
+                SAMPLE_RATE,
+                1,
+                LATENCY_MICROS,
+            )
+        };
+        if result < 0 {
+            return Err(device.alsa.error(result));
+        }
+        Ok(device)
+    }
+
+    /// Writes `frames`, recovering from underruns, which happen whenever
+    /// nothing has played for a while.
+    fn write(&self, frames: &[i16]) -> Result<(), String> {
+        let mut written = 0;
+        while written < frames.len() {
+            let rest = &frames[written..];
+            // SAFETY: `rest` holds `rest.len()` mono frames.
+            let result = unsafe {
+                (self.alsa.writei)(self.pcm, rest.as_ptr().cast(), rest.len() as c_ulong)
+            };
+            if result >= 0 {
+                written += result as usize;
+                continue;
+            }
+            // SAFETY: as above; recovery re-prepares the same handle.
+            let recovered = unsafe { (self.alsa.recover)(self.pcm, result as c_int, 1) };
+            if recovered < 0 {
+                return Err(self.alsa.error(recovered));
+            }
+        }
+        Ok(())
+    }
+
+    /// Mixes sounds from `sounds` into the device until the sender is gone.
+    fn run(self, sounds: Receiver<Vec<f32>>) {
+        let mut voices: Vec<(Vec<f32>, usize)> = Vec::new();
+        let mut mixed = [0.0f32; PERIOD_FRAMES];
+        let mut frames = [0i16; PERIOD_FRAMES];
+        loop {
+            // Sleep until there is something to play.
+            if voices.is_empty() {
+                match sounds.recv() {
+                    Ok(samples) => voices.push((samples, 0)),
+                    Err(_) => return,
+                }
+            }
+            loop {
+                match sounds.try_recv() {
+                    Ok(samples) => voices.push((samples, 0)),
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => return,
+                }
+            }
+
+            mixed.fill(0.0);
+            for (samples, position) in &mut voices {
+                let rest = &samples[*position..];
+                let count = rest.len().min(PERIOD_FRAMES);
+                for (out, sample) in mixed.iter_mut().zip(&rest[..count]) {
+                    *out += sample;
+                }
+                *position += count;
+            }
+            voices.retain(|(samples, position)| *position < samples.len());
+            for (frame, sample) in frames.iter_mut().zip(mixed) {
+                *frame = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+            }
+            if let Err(err) = self.write(&frames) {
+                log::warn!("Sound device failed ({err}); no more sounds will play");
+                return;
+            }
+        }
+    }
+}
+
+impl Drop for Device {
+    fn drop(&mut self) {
+        // SAFETY: the handle is open and not used again.
+        unsafe { (self.alsa.close)(self.pcm) };
+    }
+}
+
+/// Plays sounds on ALSA's default device from a mixing thread.
+pub struct AlsaOutput {
+    sounds: Sender<Vec<f32>>,
+}
+
+impl AlsaOutput {
+    pub fn open() -> Result<Self, String> {
+        let device = Device::open()?;
+        let (sounds, receiver) = mpsc::channel();
+        thread::Builder::new()
+            .name("audio".into())
+            .spawn(move || device.run(receiver))
+            .map_err(|err| err.to_string())?;
+        Ok(Self { sounds })
+    }
+}
+
+impl AudioOutput for AlsaOutput {
+    fn play(&mut self, sound: &PlayedSound) {
+        // A mixing thread that stopped has already said why.
+        let _ = self.sounds.send(synthesize(sound, SAMPLE_RATE));
+    }
+}
+
+/// How a material sounds: a few decaying partials over filtered noise.
+struct Timbre {
+    /// Frequencies in Hz at pitch 1.0, loudest first.
+    partials: &'static [f32],
+    /// Share of noise in the mix.
+    noise: f32,
+    /// One-pole low-pass factor for the noise; lower is duller.
+    brightness: f32,
+    /// Envelope decay rate per second.
+    decay: f32,
+}
+
+const fn timbre(material: SoundMaterial) -> Timbre {
+    match material {
+        SoundMaterial::Silent => Timbre {
+            partials: &[],
+            noise: 0.0,
+            brightness: 0.0,
+            decay: 1.0,
+        },
+        SoundMaterial::Stone => Timbre {
+            partials: &[170.0, 410.0],
+            noise: 0.7,
+            brightness: 0.35,
+            decay: 22.0,
+        },
+        SoundMaterial::Dirt => Timbre {
+            partials: &[110.0],
+            noise: 0.9,
+            brightness: 0.12,
+            decay: 18.0,
+        },
+        SoundMaterial::Glass => Timbre {
+            partials: &[1760.0, 2650.0, 4120.0],
+            noise: 0.3,
+            brightness: 0.8,
+            decay: 14.0,
+        },
+        SoundMaterial::Metal => Timbre {
+            partials: &[520.0, 1430.0, 2750.0],
+            noise: 0.15,
+            brightness: 0.6,
+            decay: 9.0,
+        },
+        SoundMaterial::Wood => Timbre {
+            partials: &[290.0, 620.0],
+            noise: 0.45,
+            brightness: 0.25,
+            decay: 28.0,
+        },
+    }
+}
+
+/// Mono samples of `sound` at `sample_rate`, at its pitch and volume.
+fn synthesize(sound: &PlayedSound, sample_rate: u32) -> Vec<f32> {
+    let timbre = timbre(sound.material);
+    let seconds = match sound.action {
+        SoundAction::Break => 0.3,
+        SoundAction::Place => 0.18,
+        SoundAction::Step => 0.1,
+    } / sound.pitch;
+    let count = (seconds * sample_rate as f32) as usize;
+    let mut rng = Rng::new(count as u64);
+    let mut noise = 0.0;
+    (0..count)
+        .map(|index| {
+            let t = index as f32 / sample_rate as f32;
+            // A 2 ms attack keeps the start from clicking.
+            let envelope = (t * 500.0).min(1.0) * (-t * timbre.decay * sound.pitch).exp();
+            let tone: f32 = timbre
+                .partials
+                .iter()
+                .enumerate()
+                .map(|(rank, frequency)| {
+                    (TAU * frequency * sound.pitch * t).sin() / (rank + 1) as f32
+                })
+                .sum();
+            noise += (2.0 * rng.unit() - 1.0 - noise) * timbre.brightness;
+            let sample = tone * (1.0 - timbre.noise) + noise * timbre.noise;
+            sample * envelope * sound.volume
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec3;
+
+    use super::*;
+    use crate::audio::SoundCategory;
+
+    fn sound(material: SoundMaterial, pitch: f32, volume: f32) -> PlayedSound {
+        PlayedSound {
+            material,
+            action: SoundAction::Break,
+            category: SoundCategory::Blocks,
+            position: Vec3::ZERO,
+            volume,
+            pitch,
+        }
+    }
+
+    #[test]
+    fn sounds_fade_out_and_scale_with_pitch_and_volume() {
+        let peak = |samples: &[f32]| samples.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+        let stone = synthesize(&sound(SoundMaterial::Stone, 1.0, 1.0), SAMPLE_RATE);
+        assert_eq!(stone.len(), (0.3 * SAMPLE_RATE as f32) as usize);
+        assert!(peak(&stone) > 0.1 && peak(&stone) <= 1.5);
+        assert!(peak(&stone[stone.len() - 100..]) < 0.01);
+
+        let high = synthesize(&sound(SoundMaterial::Stone, 2.0, 1.0), SAMPLE_RATE);
+        assert!(high.len().abs_diff(stone.len() / 2) <= 1);
+        let quiet = synthesize(&sound(SoundMaterial::Stone, 1.0, 0.5), SAMPLE_RATE);
+        assert!((peak(&quiet) - peak(&stone) / 2.0).abs() < 1e-4);
+    }
+}
@@ -4,6 +4,12 @@ use winit::window::WindowBuilder;
 
 pub mod state;
 
+/// The app shows a loading screen until startup work finishes, then runs.
+enum Phase {
+    Loading(Box<state::LoadingState>),
+    Running(Box<state::AppState>),
+}
+
 pub async fn run() {
     let event_loop = EventLoop::new();
     let window = WindowBuilder::new()
@@ -11,38 +17,46 @@ pub async fn run() {
         .build(&event_loop)
         .expect("Failed to create window");
 
-    let mut app_state = state::AppState::new(window).await;
+    let loading = state::LoadingState::new(window).await;
+    let mut phase = Some(Phase::Loading(Box::new(loading)));
 
-    event_loop.run(move |event, _, control_flow| {
+    event_loop.run(move |event, target, control_flow| {
         *control_flow = ControlFlow::Poll;
 
+        if let Some(Phase::Loading(loading)) = &mut phase {
+            handle_loading_event(loading, &event, control_flow);
+            if loading.update()
+                && let Some(Phase::Loading(loading)) = phase.take()
+            {
+                phase = Some(Phase::Running(Box::new(loading.finish())));
+            }
+            return;
+        }
+        let Some(Phase::Running(app_state)) = &mut phase else {
+            return;
+        };
+
         match event {
             Event::WindowEvent {
                 ref event,
                 window_id,
-            } if window_id == app_state.window().id() => {
-                if !app_state.input(event) {
-                    match event {
-                        WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
-                        WindowEvent::KeyboardInput { input, .. } => {
-                            if input.state == winit::event::ElementState::Pressed
-                                && let Some(winit::event::VirtualKeyCode::Escape) =
-                                    input.virtual_keycode
-                                && app_state.handle_escape()
-                            {
-                                *control_flow = ControlFlow::Exit;
-                            }
-                        }
-                        WindowEvent::Resized(physical_size) => {
-                            app_state.resize(*physical_size);
-                        }
-                        WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
-                            app_state.resize(**new_inner_size);
-                        }
-                        _ => {}
-                    }
-                }
+            } if app_state.is_debug_window(window_id) => app_state.debug_window_event(event),
+            Event::RedrawRequested(window_id) if app_state.is_debug_window(window_id) => {
+                app_state.render_debug_window();
             }
+            Event::WindowEvent {
+                ref event,
+                window_id,
+            } if window_id == app_state.window().id() && !app_state.input(event) => match event {
+                WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
+                WindowEvent::Resized(physical_size) => {
+                    app_state.resize(*physical_size);
+                }
+                WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
+                    app_state.resize(**new_inner_size);
+                }
+                _ => {}
+            },
             Event::DeviceEvent { ref event, .. } => {
                 app_state.device_input(event);
             }
@@ -58,11 +72,49 @@ pub async fn run() {
                 }
             }
             Event::MainEventsCleared => {
-                state::sleep_on_main_events(&app_state);
+                state::sleep_on_main_events(app_state);
+                app_state.sync_debug_window(target);
                 app_state.window().request_redraw();
             }
-            Event::LoopDestroyed => {}
+            Event::LoopDestroyed => app_state.save_world(),
             _ => {}
         }
+        if app_state.quit_requested() {
+            *control_flow = ControlFlow::Exit;
+        }
+        if matches!(event, Event::LoopDestroyed) {
+            // Not every platform drops the event loop's state on exit, and
+            // a GPU trace is only finished once the device is dropped.
+            phase = None;
+        }
     });
 }
+
+fn handle_loading_event(
+    loading: &mut state::LoadingState,
+    event: &Event<()>,
+    control_flow: &mut ControlFlow,
+) {
+    match event {
+        Event::WindowEvent { event, window_id } if *window_id == loading.window().id() => {
+            match event {
+                WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
+                WindowEvent::Resized(physical_size) => loading.resize(*physical_size),
+                WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
+                    loading.resize(**new_inner_size)
+                }
+                _ => {}
+            }
+        }
+        Event::RedrawRequested(window_id) if *window_id == loading.window().id() => {
+            match loading.render() {
+                Ok(_) => {}
+                Err(wgpu::SurfaceError::Lost) => loading.resize(loading.window().inner_size()),
+                Err(wgpu::SurfaceError::OutOfMemory) => *control_flow = ControlFlow::Exit,
+                Err(err) => log::warn!("Render error: {err:?}"),
+            }
+        }
+        Event::MainEventsCleared => loading.window().request_redraw(),
+        _ => {}
+    }
+}
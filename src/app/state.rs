@@ -1,68 +1,264 @@
 use std::{fmt::Write, time::Instant};
 
-use glam::{IVec3, Vec3};
-use wgpu::util::DeviceExt;
+use glam::{IVec3, Vec2, Vec3};
 use winit::dpi::PhysicalSize;
 use winit::event::{
     DeviceEvent, ElementState, MouseButton, MouseScrollDelta, VirtualKeyCode, WindowEvent,
 };
-use winit::window::{CursorGrabMode, Window};
+use winit::event_loop::EventLoopWindowTarget;
+use winit::window::{CursorGrabMode, Window, WindowId};
 
-use crate::block::{BLOCK_AIR, BlockKind};
-use crate::camera::{Camera, CameraUniform, Projection};
-use crate::config::{self, AppConfig, RenderMethodSetting};
+use crate::audio::AudioEngine;
+use crate::biome;
+use crate::block::{BlockKind, SNOW_MAX_LAYERS};
+use crate::camera::{Camera, Projection};
+use crate::combat::{self, ATTACK_REACH, AttackCooldown};
+use crate::commands::{CameraFeed, Command};
+use crate::config::{self, AppConfig, LightingSettings, PresentModeSetting, WaterQuality};
+use crate::console::{CommandConsole, ConsoleKey};
+use crate::controls_card::{CONTROLS_CARD_KEY, ControlsCard};
+use crate::coords::{BlockPos, ChunkPos};
+use crate::damage_numbers::DamageNumbers;
+use crate::death::{DeathCause, DeathChoice, DeathScreen};
+use crate::debug_window::{DebugWindow, DebugWindowContent};
+use crate::determinism::{DivergenceChecker, FIXED_TIMESTEP, StateHasher};
+#[cfg(feature = "dev-ui")]
+use crate::dev_ui::{DevSettings, DevUi, DevUiFrame};
+use crate::dimension::Dimension;
+use crate::entity::{Entities, EntityKind};
+use crate::events::{EventBus, GameEvent, Notice};
 use crate::fps::FpsCounter;
-use crate::hotbar::Hotbar;
-use crate::input::{CameraController, MouseState};
-use crate::physics::{MovementMode, PlayerPhysics};
+use crate::free_camera;
+use crate::gpu_trace::GpuTrace;
+use crate::gui::{GuiLayer, HotbarLayer, InventoryLayer, Skin};
+use crate::health::{Health, HitResult, MAX_HEALTH};
+use crate::input::{CameraController, MouseState, MovementIntent};
+use crate::inventory::{HOTBAR_SLOTS, Inventory, RadialPicker};
+use crate::inventory_screen::InventoryScreen;
+use crate::item::{ItemKind, ItemStack};
+use crate::loading::{RegionLoader, StartupData, StartupLoader};
+use crate::material_editor::MaterialEditor;
+use crate::mining::{self, BlockBreaker};
+use crate::mob::MobDirector;
+use crate::pause_menu::{MenuSettings, PauseChoice, PauseMenu};
+use crate::physics::{MovementMode, PLAYER_EYE_HEIGHT, PlayerPhysics};
+use crate::portal::{Portal, Portals};
+use crate::rand::Stream;
 use crate::raycast::pick_block;
-use crate::render::{FrameContext, RasterRenderer, RayTraceRenderer, RenderTimings, Renderer};
-use crate::text::DebugOverlay;
-use crate::texture::TextureAtlas;
-use crate::world::{ChunkCoord, World, chunk_coord_from_block};
-
-const CHUNK_LOAD_RADIUS: i32 = 4;
-const CHUNK_VERTICAL_RADIUS: i32 = 1;
-const CHUNK_UNLOAD_MARGIN: i32 = 1;
+use crate::render::{
+    CameraBinding, FrameContext, PictureInPicture, PipelineCache, RenderTarget, RenderTimings,
+    Renderer, RendererContext, RendererEntry, RendererKind, choose_surface_format, debug_group,
+    find_renderer, labeled_view, msaa_sample_count, next_renderer, rebase_origin, relative_camera,
+    renderer_names, surface_format_choices, warm_up_pipelines,
+};
+use crate::save::{WorldInfo, WorldSave};
+use crate::screenshot::Screenshots;
+use crate::sky::Sky;
+use crate::subtitles::SubtitleFeed;
+use crate::terrain::{self, TerrainGenerator, WORLD_MIN_Y};
+use crate::text::{DebugOverlay, OverlayContent};
+use crate::texture::AtlasRegistry;
+use crate::ticking::{BlockTicker, MovingBlock};
+use crate::toasts::ToastFeed;
+use crate::ui::{UiFrame, UiStack};
+use crate::world::{CHUNK_SIZE, World, chunk_coords_loaded_around};
+
 const INTERACTION_DISTANCE: f32 = 6.0;
+/// Chunks out from the camera the debug window's chunk map shows.
+const DEBUG_WINDOW_MAP_RADIUS: i32 = 8;
+/// How far below the world floor a falling player counts as lost to the void.
+const VOID_DEPTH: f32 = 32.0;
+/// Health lost each time the void swallows the player.
+const VOID_DAMAGE: f32 = 6.0;
+/// Screen pixels per GUI pixel in menus, before the accessibility text scale.
+const GUI_SCALE: f32 = 2.0;
+const LOADING_CLEAR_COLOR: wgpu::Color = wgpu::Color {
+    r: 0.02,
+    g: 0.02,
+    b: 0.03,
+    a: 1.0,
+};
+
+/// Window and GPU state that exists before the world does.
+///
+/// Presents a progress screen while [`StartupLoader`] decodes assets and
+/// generates the spawn area, then becomes an [`AppState`] via [`Self::finish`].
+pub struct LoadingState {
+    window: Window,
+    surface: wgpu::Surface,
+    instance: wgpu::Instance,
+    adapter: wgpu::Adapter,
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    surface_config: wgpu::SurfaceConfiguration,
+    size: PhysicalSize<u32>,
+    config: AppConfig,
+    camera: Camera,
+    start_chunk: ChunkPos,
+    debug_overlay: DebugOverlay,
+    loader: StartupLoader,
+    loaded: Option<StartupData>,
+    final_stage_presented: bool,
+    started: Instant,
+    gpu_trace: Option<GpuTrace>,
+}
 
 pub struct AppState {
     window: Window,
     surface: wgpu::Surface,
+    /// Kept to create surfaces for further windows.
+    instance: wgpu::Instance,
+    adapter: wgpu::Adapter,
     device: wgpu::Device,
     queue: wgpu::Queue,
     surface_config: wgpu::SurfaceConfiguration,
+    /// The settings as loaded, brought up to date and written back whenever
+    /// the settings screen closes.
+    config: AppConfig,
+    /// Present mode asked for; `surface_config` holds the one the surface
+    /// supports.
+    present_mode: PresentModeSetting,
     size: PhysicalSize<u32>,
     camera: Camera,
     projection: Projection,
-    camera_uniform: CameraUniform,
-    camera_buffer: wgpu::Buffer,
-    camera_bind_group: wgpu::BindGroup,
+    camera_binding: CameraBinding,
+    /// World block position the renderers draw relative to; see
+    /// [`rebase_origin`].
+    render_origin: IVec3,
+    pipeline_cache: PipelineCache,
     camera_controller: CameraController,
     mouse_state: MouseState,
-    debug_overlay: DebugOverlay,
+    /// The HUD, bottom layer first.
+    ui: UiStack,
+    /// Developer tools drawn over the HUD.
+    #[cfg(feature = "dev-ui")]
+    dev_ui: DevUi,
+    /// The detached debug window, while open.
+    debug_window: Option<DebugWindow>,
+    /// Set by its hotkey; the event loop opens or closes the window.
+    debug_window_toggled: bool,
     fps_counter: FpsCounter,
     last_frame: Instant,
     last_frame_time: f32,
+    /// Seconds since the world was opened.
+    world_time: f32,
+    sky: Sky,
+    fast_forward_time_key: VirtualKeyCode,
+    /// Held down, the fast-forward key runs the day by quickly.
+    fast_forwarding: bool,
     world: World,
-    _block_atlas: TextureAtlas,
+    atlases: AtlasRegistry,
     renderer: Box<dyn Renderer>,
-    loaded_chunk_center: ChunkCoord,
+    /// Registry name of `renderer`.
+    renderer_name: &'static str,
+    /// Kept to build renderers switched to at runtime.
+    camera_bind_group_layout: wgpu::BindGroupLayout,
+    /// Samples per pixel of the rasterizer's world pass, as the adapter
+    /// allows.
+    msaa_samples: u32,
+    picture_in_picture: PictureInPicture,
+    /// What the picture-in-picture inset shows, while it is on.
+    inset: Option<InsetView>,
+    loaded_chunk_center: ChunkPos,
     chunk_radius: i32,
     chunk_vertical_radius: i32,
     chunk_unload_margin: i32,
     player: PlayerPhysics,
-    hotbar: Hotbar,
-    pending_break: bool,
+    health: Health,
+    /// The dimension `world` holds.
+    dimension: Dimension,
+    /// Camera position the player respawns at, in the current dimension.
+    spawn_point: Vec3,
+    /// Feet position set with `/sethome`, persisted in the current
+    /// dimension's save.
+    home: Option<Vec3>,
+    portals: Portals,
+    /// The current dimension's files of the world save.
+    world_save: WorldSave,
+    world_info: WorldInfo,
+    keep_inventory: bool,
+    /// Shown from the player's death until they respawn, spectate or quit.
+    death_screen: Option<DeathScreen>,
+    /// Open from Escape until the player resumes; the world holds still.
+    pause_menu: Option<PauseMenu>,
+    controls_card: ControlsCard,
+    /// Screen pixels per GUI pixel in menus.
+    gui_scale: f32,
+    quit_requested: bool,
+    console: CommandConsole,
+    pending_teleport: Option<PendingTeleport>,
+    block_ticker: BlockTicker,
+    entities: Entities,
+    mobs: MobDirector,
+    /// Present only in deterministic mode.
+    determinism: Option<DivergenceChecker>,
+    events: EventBus,
+    audio: AudioEngine,
+    /// Present only when subtitles are enabled in the accessibility settings.
+    subtitles: Option<SubtitleFeed>,
+    toasts: ToastFeed,
+    /// The wgpu trace being recorded, if the config asked for one.
+    gpu_trace: Option<GpuTrace>,
+    screenshots: Screenshots,
+    /// Set by the screenshot key; the next frame drawn is captured.
+    screenshot_requested: bool,
+    screenshot_key: VirtualKeyCode,
+    /// The player's own view while the camera flies free of it, restored
+    /// when the camera snaps back.
+    free_camera: Option<Camera>,
+    free_camera_key: VirtualKeyCode,
+    inventory: Inventory,
+    /// Open from the inventory key until it or Escape is pressed again.
+    inventory_screen: Option<InventoryScreen>,
+    inventory_key: VirtualKeyCode,
+    /// Swaps to the next registered renderer.
+    renderer_key: VirtualKeyCode,
+    render_distance_up_key: VirtualKeyCode,
+    render_distance_down_key: VirtualKeyCode,
+    block_picker: RadialPicker,
+    block_picker_key: VirtualKeyCode,
+    material_editor: MaterialEditor,
+    breaker: BlockBreaker,
+    attack_cooldown: AttackCooldown,
+    /// Present only when damage numbers are enabled in the config.
+    damage_numbers: Option<DamageNumbers>,
+    pending_attack: bool,
     pending_place: bool,
     pending_pick: bool,
+    gamma: f32,
+    light_shafts: bool,
+    path_tracing: bool,
+    ray_bounces: u32,
+    water_quality: WaterQuality,
+    lighting: LightingSettings,
+    reduced_motion: bool,
 }
 
-impl AppState {
+/// A camera feed for the picture-in-picture inset.
+enum InsetView {
+    /// Behind the player, following them.
+    Rear,
+    /// Fixed where `/camera here` was run.
+    Fixed(Camera),
+}
+
+/// A teleport waiting for its destination's chunks to generate.
+struct PendingTeleport {
+    /// Camera position to move to.
+    destination: Vec3,
+    /// Set when the destination is in another dimension, whose chunks
+    /// replace the loaded world's on arrival.
+    dimension: Option<Dimension>,
+    /// Shown in the console once the player arrives.
+    arrival: String,
+    loader: RegionLoader,
+}
+
+impl LoadingState {
     pub async fn new(window: Window) -> Self {
         let size = window.inner_size();
         let config = AppConfig::load();
-
+        let gpu_trace = GpuTrace::start(&config.gpu_trace);
         let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
             backends: wgpu::Backends::PRIMARY,
             dx12_shader_compiler: Default::default(),
@@ -82,6 +278,15 @@ impl AppState {
         if adapter_features.contains(wgpu::Features::TIMESTAMP_QUERY) {
             required_features |= wgpu::Features::TIMESTAMP_QUERY;
         }
+        // Lets MSAA use every sample count the adapter supports, rather than
+        // only the 4x WebGPU guarantees.
+        required_features |=
+            adapter_features & wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES;
+        // Enable whichever block-compression families exist so KTX2 atlases can load.
+        required_features |= adapter_features
+            & (wgpu::Features::TEXTURE_COMPRESSION_BC
+                | wgpu::Features::TEXTURE_COMPRESSION_ETC2
+                | wgpu::Features::TEXTURE_COMPRESSION_ASTC);
         let (device, queue) = adapter
             .request_device(
                 &wgpu::DeviceDescriptor {
@@ -89,23 +294,24 @@ impl AppState {
                     features: required_features,
                     limits: wgpu::Limits::default(),
                 },
-                None,
+                gpu_trace.as_ref().map(GpuTrace::directory),
             )
             .await
             .expect("Failed to create device");
+        if let Some(trace) = &gpu_trace {
+            trace.write_notes(&adapter.get_info());
+        }
 
         let surface_caps = surface.get_capabilities(&adapter);
-        let surface_format = surface_caps
-            .formats
-            .iter()
-            .copied()
-            .find(|f| f.is_srgb())
-            .unwrap_or(surface_caps.formats[0]);
+        let surface_format = choose_surface_format(&surface_caps.formats, config.hdr_output);
         let present_mode = choose_present_mode(&surface_caps.present_modes, config.present_mode);
         let alpha_mode = surface_caps.alpha_modes[0];
 
+        // Copying the surface is what screenshots take; not every platform
+        // allows it.
+        let copy_usage = surface_caps.usages & wgpu::TextureUsages::COPY_SRC;
         let surface_config = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | copy_usage,
             format: surface_format,
             width: size.width,
             height: size.height,
@@ -115,25 +321,177 @@ impl AppState {
         };
         surface.configure(&device, &surface_config);
 
-        let camera = Camera::new(Vec3::new(0.0, 24.0, 60.0), -90.0, -20.0);
+        let terrain = TerrainGenerator::new(config.world_preset, config.world_seed);
+        let spawn = terrain::find_spawn(terrain);
+        let camera = Camera::new(spawn + Vec3::Y * PLAYER_EYE_HEIGHT, -90.0, 0.0);
+        let start_chunk = BlockPos(camera.position.floor().as_ivec3()).chunk();
+        let atlas_dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/textures");
+        let loader = StartupLoader::spawn(
+            device.features(),
+            atlas_dir,
+            terrain,
+            start_chunk,
+            config.render_distance,
+            config.vertical_render_distance,
+        );
+        let mut debug_overlay = DebugOverlay::new(&device, &queue, surface_config.format);
+        debug_overlay.set_style(config.accessibility.overlay_style());
+
+        Self {
+            window,
+            surface,
+            instance,
+            adapter,
+            device,
+            queue,
+            surface_config,
+            size,
+            config,
+            camera,
+            start_chunk,
+            debug_overlay,
+            loader,
+            loaded: None,
+            final_stage_presented: false,
+            started: Instant::now(),
+            gpu_trace,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn window(&self) -> &Window {
+        &self.window
+    }
+
+    #[allow(dead_code)]
+    pub fn resize(&mut self, new_size: PhysicalSize<u32>) {
+        if new_size.width == 0 || new_size.height == 0 {
+            return;
+        }
+
+        self.size = new_size;
+        self.surface_config.width = new_size.width;
+        self.surface_config.height = new_size.height;
+        self.surface.configure(&self.device, &self.surface_config);
+    }
+
+    /// Polls the background loader. Returns true once [`Self::finish`] can run.
+    ///
+    /// The frame after the worker completes is still drawn so the pipeline
+    /// stage is visible before the main thread blocks on building them.
+    #[allow(dead_code)]
+    pub fn update(&mut self) -> bool {
+        if self.loaded.is_some() {
+            return self.final_stage_presented;
+        }
+        match self.loader.poll() {
+            Some(Ok(data)) => {
+                log::info!(
+                    "Startup assets ready in {:.2} s",
+                    self.started.elapsed().as_secs_f32()
+                );
+                self.loader.begin_pipelines();
+                self.loaded = Some(data);
+            }
+            Some(Err(err)) => panic!("Failed to load startup assets: {err}"),
+            None => {}
+        }
+        false
+    }
+
+    #[allow(dead_code)]
+    pub fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
+        let viewport = [self.size.width, self.size.height];
+        let status = self.loader.formatted_status();
+        self.debug_overlay.prepare(
+            &self.device,
+            &self.queue,
+            viewport,
+            &OverlayContent {
+                text: &status,
+                ..Default::default()
+            },
+        );
+
+        let output = self.surface.get_current_texture()?;
+        let view = labeled_view(&output.texture, "Window surface view");
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Loading screen encoder"),
+            });
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Loading screen clear"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(LOADING_CLEAR_COLOR),
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: None,
+        });
+        self.debug_overlay.render(&mut encoder, &view);
+
+        self.queue.submit(std::iter::once(encoder.finish()));
+        output.present();
+        self.final_stage_presented = self.loaded.is_some();
+        Ok(())
+    }
+
+    /// Uploads the loaded data and builds the renderer, blocking on the
+    /// worker if it has not finished yet.
+    pub fn finish(mut self) -> AppState {
+        let data = match self.loaded.take() {
+            Some(data) => data,
+            None => self
+                .loader
+                .wait()
+                .unwrap_or_else(|err| panic!("Failed to load startup assets: {err}")),
+        };
+        AppState::from_startup(self, data)
+    }
+}
+
+impl AppState {
+    /// Blocking startup without a loading screen, used by the benchmark.
+    #[allow(dead_code)]
+    pub async fn new(window: Window) -> Self {
+        LoadingState::new(window).await.finish()
+    }
+
+    fn from_startup(loading: LoadingState, data: StartupData) -> Self {
+        let LoadingState {
+            window,
+            surface,
+            instance,
+            adapter,
+            device,
+            queue,
+            surface_config,
+            size,
+            config,
+            camera,
+            start_chunk,
+            mut debug_overlay,
+            started,
+            gpu_trace,
+            ..
+        } = loading;
+        debug_overlay.set_crosshair_visible(true);
+        let StartupData { atlases, world } = data;
+        let surface_format = surface_config.format;
+
         let mut projection = Projection::new(
             surface_config.width,
             surface_config.height,
-            60.0,
-            0.1,
-            200.0,
+            config.fov,
+            config.znear,
+            config.zfar,
         );
         projection.resize(surface_config.width, surface_config.height);
 
-        let mut camera_uniform = CameraUniform::new();
-        camera_uniform.update(&camera, &projection);
-
-        let camera_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Camera buffer"),
-            contents: bytemuck::cast_slice(&[camera_uniform]),
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-        });
-
         let camera_bind_group_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
                 label: Some("Camera bind group layout"),
@@ -149,83 +507,216 @@ impl AppState {
                 }],
             });
 
-        let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Camera bind group"),
-            layout: &camera_bind_group_layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: camera_buffer.as_entire_binding(),
-            }],
-        });
-
-        let atlas_path =
-            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/textures/blocks.json");
-        let block_atlas =
-            TextureAtlas::load(&device, &queue, atlas_path).expect("Failed to load block atlas");
+        let camera_binding = CameraBinding::new(&device, &camera_bind_group_layout, "Camera");
+        let render_origin = rebase_origin(IVec3::ZERO, camera.position);
+        camera_binding.update(
+            &queue,
+            &relative_camera(&camera, render_origin),
+            &projection,
+        );
 
-        let mut world = World::new();
-        let start_chunk = chunk_coord_from_block(IVec3::new(
-            camera.position.x.floor() as i32,
-            camera.position.y.floor() as i32,
-            camera.position.z.floor() as i32,
+        let atlases = AtlasRegistry::upload(&device, &queue, atlases);
+        let block_atlas = atlases.blocks();
+        let text_scale = config.accessibility.overlay_style().text_scale;
+        let gui_scale = (GUI_SCALE * text_scale).round().max(1.0);
+        let mut toasts = ToastFeed::new(gui_scale);
+        if gpu_trace.is_some() {
+            toasts.notify(Notice::GpuTraceRecording);
+        }
+        let skin = Skin::new(&atlases);
+        let mut ui = UiStack::default();
+        ui.push(HotbarLayer::new(
+            &device,
+            &queue,
+            surface_format,
+            &atlases,
+            skin.clone(),
+            gui_scale,
         ));
-        populate_world_chunks(
-            &mut world,
-            start_chunk,
-            CHUNK_LOAD_RADIUS,
-            CHUNK_VERTICAL_RADIUS,
-        );
+        ui.push(debug_overlay);
+        ui.push(InventoryLayer::new(
+            &device,
+            &queue,
+            surface_format,
+            &atlases,
+            skin.clone(),
+        ));
+        ui.push(GuiLayer::new(
+            &device,
+            &queue,
+            surface_format,
+            &atlases,
+            skin,
+        ));
+        #[cfg(feature = "dev-ui")]
+        let dev_ui = DevUi::new(&device, surface_format, &window);
 
-        let renderer: Box<dyn Renderer> = match config.render_method {
-            RenderMethodSetting::Rasterized => Box::new(RasterRenderer::new(
+        let msaa_samples = msaa_sample_count(&adapter, &device, config.msaa);
+        let pipeline_start = Instant::now();
+        let pipeline_cache = PipelineCache::default();
+        // Both formats `hdr_output` picks between are compiled, so switching
+        // between them never waits on shaders.
+        let surface_formats = surface.get_capabilities(&adapter).formats;
+        let (sdr_format, hdr_format) = surface_format_choices(&surface_formats);
+        for format in std::iter::once(sdr_format).chain(hdr_format) {
+            warm_up_pipelines(
                 &device,
-                &queue,
-                &surface_config,
-                &world,
-                &block_atlas,
+                &pipeline_cache,
+                format,
+                msaa_samples,
                 &camera_bind_group_layout,
-            )),
-            RenderMethodSetting::RayTraced => Box::new(RayTraceRenderer::new(
-                &device,
-                &queue,
-                surface_format,
-                &block_atlas,
-            )),
-        };
+            );
+        }
+        log::info!(
+            "Compiled {} pipelines in {:.1} ms",
+            pipeline_cache.len(),
+            pipeline_start.elapsed().as_secs_f32() * 1000.0
+        );
+
+        let renderer_entry =
+            find_renderer(config.render_method).expect("config only names registered renderers");
+        let renderer = (renderer_entry.create)(&RendererContext {
+            device: &device,
+            queue: &queue,
+            surface_config: &surface_config,
+            world: &world,
+            block_atlas,
+            camera_bind_group_layout: &camera_bind_group_layout,
+            pipeline_cache: &pipeline_cache,
+            ao_strength: config.lighting.ao_strength,
+            msaa_samples,
+        });
+
+        let picture_in_picture = PictureInPicture::new(
+            &device,
+            renderer.output_format(),
+            &camera_bind_group_layout,
+            config.znear,
+            config.zfar,
+        );
 
-        let debug_overlay = DebugOverlay::new(&device, &queue, surface_config.format);
-        let player = PlayerPhysics::from_camera(camera.position);
+        let mut player = PlayerPhysics::from_camera(camera.position);
+        let spawn_point = camera.position;
+        let world_save = WorldSave::new(&world.terrain().world_name());
+        let home = world_save.load_home();
+        let portals = Portals::load(&world_save);
+        let world_info = world_save.load_info().unwrap_or_else(|| {
+            let info = WorldInfo {
+                hardcore: config.hardcore,
+                spectator_only: false,
+            };
+            if let Err(err) = world_save.store_info(&info) {
+                log::warn!("Failed to save world info: {}", err);
+            }
+            info
+        });
+        if world_info.spectator_only {
+            player.set_mode(MovementMode::Spectator);
+        }
+        log::info!(
+            "Startup finished in {:.2} s",
+            started.elapsed().as_secs_f32()
+        );
 
         Self {
             window,
             surface,
+            instance,
+            adapter,
             device,
             queue,
             surface_config,
+            present_mode: config.present_mode,
             size,
             camera,
             projection,
-            camera_uniform,
-            camera_buffer,
-            camera_bind_group,
+            camera_binding,
+            render_origin,
+            pipeline_cache,
             camera_controller: CameraController::new(10.0, 90.0, config.key_bindings.clone()),
             mouse_state: MouseState::new(config.mouse_sensitivity, config.max_fps),
-            debug_overlay,
+            ui,
+            #[cfg(feature = "dev-ui")]
+            dev_ui,
+            debug_window: None,
+            debug_window_toggled: false,
             fps_counter: FpsCounter::default(),
             last_frame: Instant::now(),
             last_frame_time: 0.0,
+            world_time: 0.0,
+            sky: Sky::new(config.lighting.sun_direction, config.lighting.day_length),
+            fast_forward_time_key: config.key_bindings.fast_forward_time,
+            fast_forwarding: false,
             world,
-            _block_atlas: block_atlas,
+            atlases,
             renderer,
+            renderer_name: renderer_entry.name,
+            camera_bind_group_layout,
+            msaa_samples,
+            picture_in_picture,
+            inset: None,
             loaded_chunk_center: start_chunk,
-            chunk_radius: CHUNK_LOAD_RADIUS,
-            chunk_vertical_radius: CHUNK_VERTICAL_RADIUS,
-            chunk_unload_margin: CHUNK_UNLOAD_MARGIN,
+            chunk_radius: config.render_distance,
+            chunk_vertical_radius: config.vertical_render_distance,
+            chunk_unload_margin: config.unload_margin,
             player,
-            hotbar: Hotbar::new(),
-            pending_break: false,
+            health: Health::new(MAX_HEALTH),
+            dimension: Dimension::Overworld,
+            spawn_point,
+            home,
+            portals,
+            world_save,
+            world_info,
+            keep_inventory: config.keep_inventory,
+            death_screen: None,
+            pause_menu: None,
+            controls_card: ControlsCard::new(&config.key_bindings, config.first_run),
+            gui_scale,
+            quit_requested: false,
+            console: CommandConsole::new(),
+            pending_teleport: None,
+            block_ticker: BlockTicker::new(Stream::RandomTicks.rng(config.world_seed)),
+            entities: Entities::new(),
+            mobs: MobDirector::new(Stream::Mobs.rng(config.world_seed)),
+            determinism: config.determinism.enabled.then(|| {
+                DivergenceChecker::new(
+                    config.determinism.record_checksums.as_deref(),
+                    config.determinism.verify_checksums.as_deref(),
+                )
+            }),
+            events: EventBus::new(),
+            audio: AudioEngine::new(config.volume, Stream::Effects.rng(config.world_seed)),
+            subtitles: config.accessibility.subtitles.then(SubtitleFeed::new),
+            toasts,
+            gpu_trace,
+            screenshots: Screenshots::new(),
+            screenshot_requested: false,
+            screenshot_key: config.key_bindings.screenshot,
+            free_camera: None,
+            free_camera_key: config.key_bindings.free_camera,
+            inventory: Inventory::new(),
+            inventory_screen: None,
+            inventory_key: config.key_bindings.inventory,
+            renderer_key: config.key_bindings.switch_renderer,
+            render_distance_up_key: config.key_bindings.render_distance_up,
+            render_distance_down_key: config.key_bindings.render_distance_down,
+            block_picker: RadialPicker::new(),
+            block_picker_key: config.key_bindings.block_picker,
+            material_editor: MaterialEditor::load(),
+            breaker: BlockBreaker::new(),
+            attack_cooldown: AttackCooldown::new(),
+            damage_numbers: config.damage_numbers.then(DamageNumbers::new),
+            pending_attack: false,
             pending_place: false,
             pending_pick: false,
+            gamma: config.gamma,
+            light_shafts: config.light_shafts,
+            path_tracing: config.path_tracing,
+            ray_bounces: config.ray_bounces,
+            water_quality: config.water_quality,
+            lighting: config.lighting,
+            reduced_motion: config.accessibility.reduced_motion,
+            config,
         }
     }
 
@@ -233,11 +724,32 @@ impl AppState {
         &self.window
     }
 
+    /// Moves the player's feet to `feet` as `/tp` does, generating the
+    /// chunks around it in the background first if they are not loaded.
+    #[allow(dead_code)]
+    pub fn teleport_feet(&mut self, feet: Vec3) {
+        let arrival = format!("Teleported to {:.1} {:.1} {:.1}", feet.x, feet.y, feet.z);
+        self.begin_teleport(feet + Vec3::Y * PLAYER_EYE_HEIGHT, arrival);
+    }
+
+    /// Hides the controls card without counting it as read, for runs that
+    /// nobody watches, such as the benchmark.
+    #[allow(dead_code)]
+    pub fn hide_controls_card(&mut self) {
+        self.controls_card.hide();
+    }
+
     #[allow(dead_code)]
     pub fn camera_controller_mut(&mut self) -> &mut CameraController {
         &mut self.camera_controller
     }
 
+    /// The seconds each update simulates in deterministic mode.
+    #[allow(dead_code)]
+    pub fn fixed_timestep(&self) -> Option<f32> {
+        self.determinism.is_some().then_some(FIXED_TIMESTEP)
+    }
+
     #[allow(dead_code)]
     pub fn last_frame_seconds(&self) -> f32 {
         self.last_frame_time
@@ -273,29 +785,135 @@ impl AppState {
         self.surface_config.height = new_size.height;
         self.surface.configure(&self.device, &self.surface_config);
         self.projection.resize(new_size.width, new_size.height);
-        self.camera_uniform.update(&self.camera, &self.projection);
-        self.queue.write_buffer(
-            &self.camera_buffer,
-            0,
-            bytemuck::cast_slice(&[self.camera_uniform]),
-        );
+        self.update_camera_binding();
         self.renderer
             .resize(&self.device, &self.queue, &self.surface_config);
     }
 
     pub fn input(&mut self, event: &WindowEvent) -> bool {
+        #[cfg(feature = "dev-ui")]
+        if self.dev_ui.input(event) {
+            if self.dev_ui.is_visible() {
+                self.set_mouse_capture(false);
+                self.camera_controller.release_keys();
+                self.fast_forwarding = false;
+            }
+            return true;
+        }
+        if self.ui.input(event) {
+            return true;
+        }
+        if self.death_screen.is_some() {
+            return self.death_screen_input(event);
+        }
+        if self.pause_menu.is_some() {
+            return self.pause_menu_input(event);
+        }
+        if self.inventory_screen.is_some() {
+            return self.inventory_screen_input(event);
+        }
+        if self.controls_card.is_visible() && self.controls_card_input(event) {
+            return true;
+        }
         match event {
             WindowEvent::KeyboardInput { input, .. } => {
                 if let Some(key) = input.virtual_keycode {
                     let is_pressed = input.state == ElementState::Pressed;
-                    if is_pressed {
-                        if let Some(index) = Self::hotbar_digit_index(key) {
-                            self.hotbar.select_index(index);
-                            return true;
+                    if self.console.is_open() {
+                        if is_pressed && let ConsoleKey::Submit(line) = self.console.handle_key(key)
+                        {
+                            self.run_command(&line);
+                        }
+                        return true;
+                    }
+                    if is_pressed && self.material_editor.handle_key(key) {
+                        return true;
+                    }
+                    if is_pressed && key == VirtualKeyCode::Slash {
+                        self.console.open();
+                        self.camera_controller.release_keys();
+                        self.fast_forwarding = false;
+                        return true;
+                    }
+                    if key == self.block_picker_key {
+                        if is_pressed && !self.block_picker.is_open() {
+                            self.block_picker.open(HOTBAR_SLOTS);
+                        } else if !is_pressed && let Some(index) = self.block_picker.close() {
+                            self.inventory.select_index(index);
+                        }
+                        return true;
+                    }
+                    if is_pressed && let Some(index) = Self::hotbar_digit_index(key) {
+                        self.inventory.select_index(index);
+                        return true;
+                    }
+                    if is_pressed && key == VirtualKeyCode::Escape {
+                        self.open_pause_menu();
+                        return true;
+                    }
+                    if is_pressed && key == self.inventory_key && self.can_interact() {
+                        self.open_inventory_screen();
+                        return true;
+                    }
+                    if is_pressed && key == self.renderer_key {
+                        let entry = next_renderer(self.renderer_name);
+                        self.switch_renderer(entry);
+                        self.events
+                            .publish(GameEvent::Notice(Notice::RendererSwitched(entry.name)));
+                        return true;
+                    }
+                    if is_pressed
+                        && (key == self.render_distance_up_key
+                            || key == self.render_distance_down_key)
+                    {
+                        let step = if key == self.render_distance_up_key {
+                            1
+                        } else {
+                            -1
+                        };
+                        let radius = (self.chunk_radius + step).clamp(
+                            *config::RENDER_DISTANCE_RANGE.start(),
+                            *config::RENDER_DISTANCE_RANGE.end(),
+                        );
+                        if radius != self.chunk_radius {
+                            self.set_render_distance(radius);
+                        }
+                        return true;
+                    }
+                    if is_pressed && key == VirtualKeyCode::F9 {
+                        match &mut self.gpu_trace {
+                            Some(trace) => trace.finish_early(),
+                            None => self.events.publish(GameEvent::Notice(Notice::GpuTraceOff)),
+                        }
+                        return true;
+                    }
+                    if is_pressed && key == CONTROLS_CARD_KEY {
+                        self.controls_card.toggle();
+                        return true;
+                    }
+                    if is_pressed && key == self.screenshot_key {
+                        if self
+                            .surface_config
+                            .usage
+                            .contains(wgpu::TextureUsages::COPY_SRC)
+                        {
+                            self.screenshot_requested = true;
+                        } else {
+                            self.events
+                                .publish(GameEvent::Notice(Notice::ScreenshotUnavailable));
                         }
+                        return true;
+                    }
+                    if key == self.fast_forward_time_key {
+                        self.fast_forwarding = is_pressed;
+                        return true;
+                    }
+                    if is_pressed && key == self.free_camera_key {
+                        self.toggle_free_camera();
+                        return true;
                     }
-                    if is_pressed && key == VirtualKeyCode::Escape && self.mouse_state.captured {
-                        self.set_mouse_capture(false);
+                    if is_pressed && key == VirtualKeyCode::F12 {
+                        self.debug_window_toggled = true;
                         return true;
                     }
                     if is_pressed && key == VirtualKeyCode::F {
@@ -308,44 +926,41 @@ impl AppState {
                     false
                 }
             }
+            WindowEvent::ReceivedCharacter(ch) if self.console.is_open() => {
+                self.console.push_char(*ch);
+                true
+            }
             WindowEvent::MouseInput { state, button, .. } => {
                 let pressed = *state == ElementState::Pressed;
                 match button {
-                    MouseButton::Left => {
-                        if pressed {
-                            if !self.mouse_state.captured {
-                                self.set_mouse_capture(true);
-                                return true;
-                            }
-                            self.pending_break = true;
-                            true
-                        } else {
-                            false
+                    MouseButton::Left if pressed => {
+                        if !self.mouse_state.captured {
+                            self.set_mouse_capture(true);
+                            return true;
                         }
+                        self.breaker.press();
+                        self.pending_attack = true;
+                        true
                     }
-                    MouseButton::Right => {
-                        if pressed {
-                            if !self.mouse_state.captured {
-                                self.set_mouse_capture(true);
-                                return true;
-                            }
-                            self.pending_place = true;
-                            true
-                        } else {
-                            false
+                    MouseButton::Left => {
+                        self.breaker.release();
+                        true
+                    }
+                    MouseButton::Right if pressed => {
+                        if !self.mouse_state.captured {
+                            self.set_mouse_capture(true);
+                            return true;
                         }
+                        self.pending_place = true;
+                        true
                     }
-                    MouseButton::Middle => {
-                        if pressed {
-                            if !self.mouse_state.captured {
-                                self.set_mouse_capture(true);
-                                return true;
-                            }
-                            self.pending_pick = true;
-                            true
-                        } else {
-                            false
+                    MouseButton::Middle if pressed => {
+                        if !self.mouse_state.captured {
+                            self.set_mouse_capture(true);
+                            return true;
                         }
+                        self.pending_pick = true;
+                        true
                     }
                     _ => false,
                 }
@@ -364,13 +979,16 @@ impl AppState {
                 };
                 if amount.abs() > f32::EPSILON {
                     let offset = if amount > 0.0 { -1 } else { 1 };
-                    self.hotbar.cycle(offset as isize);
+                    self.inventory.cycle(offset as isize);
                     true
                 } else {
                     false
                 }
             }
             WindowEvent::Focused(false) => {
+                self.block_picker.cancel();
+                self.fast_forwarding = false;
+                self.breaker.release();
                 self.set_mouse_capture(false);
                 false
             }
@@ -379,6 +997,17 @@ impl AppState {
     }
 
     pub fn device_input(&mut self, event: &DeviceEvent) {
+        if self.death_screen.is_some() {
+            return;
+        }
+        if self.block_picker.is_open() {
+            // The picker takes the mouse so the view holds still while choosing.
+            if let DeviceEvent::MouseMotion { delta } = event {
+                self.block_picker
+                    .steer(Vec2::new(delta.0 as f32, delta.1 as f32));
+            }
+            return;
+        }
         self.mouse_state.handle_device_event(
             event,
             self.mouse_state.sensitivity,
@@ -390,155 +1019,633 @@ impl AppState {
         let now = Instant::now();
         let dt = now - self.last_frame;
         self.last_frame = now;
-        let dt_seconds = dt.as_secs_f32();
-
-        self.camera_controller
-            .update_orientation(&mut self.camera, dt_seconds);
-        let movement_intent = self.camera_controller.movement_input(&self.camera);
-        self.player
-            .update(&self.world, dt_seconds, &movement_intent);
-        self.camera.position = self.player.camera_position();
-        self.camera_uniform.update(&self.camera, &self.projection);
-        self.queue.write_buffer(
-            &self.camera_buffer,
-            0,
-            bytemuck::cast_slice(&[self.camera_uniform]),
-        );
+        let frame_seconds = dt.as_secs_f32();
+        let paused = self.pause_menu.is_some();
+        let dt_seconds = if paused {
+            0.0
+        } else if self.determinism.is_some() {
+            FIXED_TIMESTEP
+        } else {
+            frame_seconds
+        };
+        self.world_time += dt_seconds;
+        self.sky.advance(dt_seconds, self.fast_forwarding);
+
+        self.finish_pending_teleport();
+        for notice in self.screenshots.poll(&self.device) {
+            self.events.publish(GameEvent::Notice(notice));
+        }
+        // The dead hold still behind the death screen.
+        if self.death_screen.is_none() {
+            self.camera_controller
+                .update_orientation(&mut self.camera, dt_seconds);
+            let movement_intent = self.camera_controller.movement_intent(&self.camera);
+            if self.free_camera.is_some() {
+                // The body is left to its physics while the camera flies,
+                // which never strays out of the chunks loaded around it.
+                let reach = (self.chunk_radius * CHUNK_SIZE as i32) as f32;
+                free_camera::fly(
+                    &mut self.camera,
+                    &movement_intent,
+                    dt_seconds,
+                    self.player.camera_position(),
+                    reach,
+                );
+                self.player
+                    .update(&self.world, dt_seconds, &MovementIntent::default());
+            } else {
+                self.player
+                    .update(&self.world, dt_seconds, &movement_intent);
+            }
+            if self.can_interact()
+                && self.player.feet_position().y < WORLD_MIN_Y as f32 - VOID_DEPTH
+            {
+                self.fall_into_void();
+            }
+            self.enter_portal();
+        }
+        if self.free_camera.is_none() {
+            self.camera.position = self.player.camera_position();
+            if !self.reduced_motion {
+                self.camera.position += self.player.view_bob();
+            }
+        }
+        self.update_camera_binding();
 
-        let fps = self.fps_counter.update(dt_seconds);
-        self.last_frame_time = dt_seconds;
-        let pos = self.camera.position;
+        let fps = self.fps_counter.update(frame_seconds);
+        self.last_frame_time = frame_seconds;
+        // Chunks stream around the player, wherever the free camera is.
+        let pos = self.player.camera_position();
         let block_pos = IVec3::new(
             pos.x.floor() as i32,
             pos.y.floor() as i32,
             pos.z.floor() as i32,
         );
-        let cam_chunk = chunk_coord_from_block(block_pos);
+        let cam_chunk = BlockPos(block_pos).chunk();
         if cam_chunk != self.loaded_chunk_center {
-            self.world.ensure_chunks_in_radius(
-                cam_chunk,
-                self.chunk_radius,
-                self.chunk_vertical_radius,
-            );
-            let unload_radius = self.chunk_radius + self.chunk_unload_margin;
-            let unload_vertical = self.chunk_vertical_radius + self.chunk_unload_margin;
-            self.world
-                .unload_chunks_outside(cam_chunk, unload_radius, unload_vertical);
-            self.loaded_chunk_center = cam_chunk;
-        }
-        self.process_interactions();
-        let chunk_count = self.world.chunk_count();
-        let gpu_blocks = self
-            .renderer
-            .timings()
-            .map(|timings| timings.solid_blocks)
-            .unwrap_or(0);
-
-        let mut chunk_grid = String::new();
-        let grid_radius = 2;
-        let _ = writeln!(&mut chunk_grid, "Chunk grid (X/Z):");
-        for dz in (-grid_radius..=grid_radius).rev() {
-            chunk_grid.push(' ');
-            for dx in -grid_radius..=grid_radius {
-                let coord = ChunkCoord {
-                    x: cam_chunk.x + dx,
-                    y: cam_chunk.y,
-                    z: cam_chunk.z + dz,
-                };
-                let marker = if dx == 0 && dz == 0 {
-                    'C'
-                } else if self.world.chunk(coord).is_some() {
-                    '#'
-                } else {
-                    '.'
-                };
-                chunk_grid.push(marker);
-                if dx != grid_radius {
-                    chunk_grid.push(' ');
-                }
+            self.stream_chunks(cam_chunk);
+        }
+        self.health.update(dt_seconds);
+        self.attack_cooldown.update(dt_seconds);
+        self.process_interactions(dt_seconds);
+        if self.player.take_step() {
+            let feet = self.player.feet_position();
+            let below = (feet - Vec3::Y * 0.05).floor().as_ivec3();
+            self.events.publish(GameEvent::PlayerStep {
+                position: feet,
+                block: self.world.block_info(below).kind,
+            });
+        }
+        self.block_ticker.sync(&self.world, &self.world_save);
+        self.block_ticker
+            .update(&mut self.world, &mut self.events, dt_seconds);
+        self.entities.sync(&self.world, &self.world_save);
+        let hunted = self.can_interact();
+        let contact_damage = self.mobs.update(
+            &mut self.entities,
+            &self.world,
+            self.player.body(),
+            hunted,
+            dt_seconds,
+        );
+        self.entities.update(&self.world, dt_seconds);
+        if contact_damage > 0.0 {
+            self.take_mob_damage(contact_damage);
+        }
+        if hunted {
+            self.entities
+                .pick_up_items(self.player.body(), |stack| self.inventory.collect(stack));
+        }
+        if !paused && let Some(mut checker) = self.determinism.take() {
+            let checksum = self.simulation_checksum(&mut checker);
+            checker.check(checksum);
+            self.determinism = Some(checker);
+        }
+        if self.material_editor.take_saved() {
+            self.events
+                .publish(GameEvent::Notice(Notice::MaterialsSaved));
+        }
+        let listener = self.camera.position;
+        for event in self.events.drain() {
+            let played = self.audio.handle(&event, listener);
+            if let (Some(subtitles), Some(sound)) = (&mut self.subtitles, played) {
+                subtitles.push(&sound);
             }
-            chunk_grid.push('\n');
+            if let GameEvent::Notice(notice) = event {
+                self.toasts.notify(notice);
+            }
+        }
+        // Real time, so toasts still come and go while the game is paused.
+        self.toasts.update(frame_seconds);
+        if let Some(subtitles) = &mut self.subtitles {
+            subtitles.update(dt_seconds);
+        }
+        if let Some(damage_numbers) = &mut self.damage_numbers {
+            damage_numbers.update(dt_seconds);
+        }
+        self.console.update(dt_seconds);
+        #[cfg(feature = "dev-ui")]
+        self.run_dev_ui(frame_seconds);
+        self.material_editor.poll_block_files(self.atlases.blocks());
+        if self.material_editor.take_dirty() {
+            self.world
+                .update_emission(self.material_editor.definitions());
+            self.renderer
+                .update_materials(&self.queue, self.material_editor.definitions());
+        }
+        let timings = self.renderer.timings().unwrap_or_default();
+        if let Some(debug_window) = &mut self.debug_window {
+            debug_window.record_frame(frame_seconds * 1000.0, timings.total_ms);
+        }
+        let mut gpu_blocks = format!("{:>7}", timings.solid_blocks);
+        if self.path_tracing && self.renderer.kind() == RendererKind::RayTraced {
+            let _ = write!(
+                &mut gpu_blocks,
+                " (path traced, {} samples)",
+                timings.samples
+            );
+        }
+        if let Some(radius) = timings.traced_radius {
+            let _ = write!(
+                &mut gpu_blocks,
+                " (buffers full, tracing {radius} chunks out)"
+            );
+        }
+        if let Some(traversal) = timings.traversal {
+            let _ = write!(
+                &mut gpu_blocks,
+                " ({:.1} steps/ray)",
+                traversal.steps_per_ray()
+            );
+        }
+        let mut chunk_line = format!("{:>3}", self.world.chunk_count());
+        if self.renderer.kind() == RendererKind::Rasterized {
+            let _ = write!(
+                &mut chunk_line,
+                " (drawn {}, culled {})",
+                timings.drawn_chunks, timings.culled_chunks
+            );
         }
-        let _ = writeln!(&mut chunk_grid, "C=current chunk, #=loaded");
+
+        let chunk_grid = chunk_map(&self.world, cam_chunk, 2);
 
         let mode_label = match self.player.mode() {
             MovementMode::Fly => "Fly",
             MovementMode::Walk => "Walk",
+            MovementMode::Spectator => "Spectator",
+        };
+        let mode_label = if self.free_camera.is_some() {
+            format!("{mode_label} (free camera)")
+        } else {
+            mode_label.to_string()
+        };
+        let world_label = if self.world_info.hardcore {
+            format!("{} (hardcore)", self.world.terrain().world_name())
+        } else {
+            self.world.terrain().world_name()
         };
 
-        let selected_block = self.hotbar.selected();
-        let selected_name = selected_block.display_name();
-        let hotbar_line = self.hotbar.formatted_slots();
-        let debug_text = format!(
+        let selected_name = self
+            .inventory
+            .selected()
+            .map_or("Empty".to_string(), |stack| stack.label());
+        let target_line = pick_block(
+            &self.world,
+            self.camera.position,
+            self.camera.forward(),
+            INTERACTION_DISTANCE,
+        )
+        .map_or("None".to_string(), |hit| {
+            let block = self.world.block_info(hit.block);
+            // The target itself is usually opaque and dark; show the light
+            // of the cell a placed block would go in.
+            let front = self.world.block_info(hit.placement_position());
+            format!(
+                "{} (id {}) at {} {} {}, light {}/{} in front",
+                block.kind.display_name(),
+                block.id,
+                hit.block.x,
+                hit.block.y,
+                hit.block.z,
+                front.sky_light,
+                front.block_light
+            )
+        });
+        let (hour, minute) = self.sky.clock();
+        let mut debug_text = format!(
             r#"
 Renderer: {}
 Mode: {}
 FPS: {:>5.1}
+Present: {}, cap {}
 Frame: {:>6.2} ms
+GPU: {}
 POS: {:+5.1} {:+5.1} {:+5.1}
+Time: {:02}:{:02}, day {}
 Chunk: {:+4} {:+4} {:+4}
-Chunks: {:>3}
-GPU Blocks: {:>7}
+Biome: {}
+Health: {:>4.1}/{}
+Mobs: {:>2}
+World: {}, {}
+Chunks: {}
+Render distance: {} (vertical {}, terrain to y {}, unload +{})
+Pipelines: {:>2}
+Block updates: {:>5}
+GPU Blocks: {}
 Selected: {}
-Hotbar: {}
+Target: {}
 {}
 "#,
             self.renderer.kind().as_str(),
             mode_label,
             fps,
+            self.present_mode_label(),
+            self.frame_cap_label(),
             self.last_frame_time * 1000.0,
+            self.gpu_time_label(&timings),
             pos.x,
             pos.y,
             pos.z,
+            hour,
+            minute,
+            self.sky.day(),
             cam_chunk.x,
             cam_chunk.y,
             cam_chunk.z,
-            chunk_count,
+            biome::biome_at(pos.x.floor() as i32, pos.z.floor() as i32).name(),
+            self.health.current(),
+            MAX_HEALTH,
+            self.entities.count(EntityKind::Lurker),
+            world_label,
+            self.dimension.name(),
+            chunk_line,
+            self.chunk_radius,
+            self.chunk_vertical_radius,
+            self.world.terrain().height_range().end(),
+            self.chunk_unload_margin,
+            self.pipeline_cache.len(),
+            self.block_ticker.pending_updates(),
             gpu_blocks,
             selected_name,
-            hotbar_line,
+            target_line,
             chunk_grid.trim_end(),
         );
+        if self.material_editor.is_visible() {
+            debug_text.push('\n');
+            debug_text.push_str(&self.material_editor.formatted_panel());
+        }
+        let subtitle_lines = self
+            .subtitles
+            .as_ref()
+            .map(|subtitles| subtitles.lines(&self.camera))
+            .unwrap_or_default();
+        let mut console_lines = self.console.lines();
+        if let Some(pending) = &self.pending_teleport {
+            console_lines.push(pending.loader.formatted_status());
+        }
         let viewport = [self.size.width, self.size.height];
-        self.debug_overlay
-            .prepare(&self.device, &self.queue, viewport, &debug_text);
+        let floating_labels = self
+            .damage_numbers
+            .as_ref()
+            .map(|numbers| numbers.labels(&self.camera, &self.projection, viewport))
+            .unwrap_or_default();
+        let dialog = match &self.death_screen {
+            Some(screen) => screen.lines(),
+            None if self.controls_card.is_visible() => self.controls_card.lines().to_vec(),
+            None => Vec::new(),
+        };
+        let content = OverlayContent {
+            text: &debug_text,
+            subtitles: &subtitle_lines,
+            console: &console_lines,
+            break_progress: self.breaker.progress(),
+            attack_cooldown: self.attack_cooldown.progress(),
+            floating_labels: &floating_labels,
+            radial_menu: self.block_picker.menu(&self.inventory),
+            dialog: &dialog,
+            graphs: &[],
+        };
+        if let Some(menu) = &mut self.pause_menu {
+            menu.layout(viewport);
+        }
+        if let Some(screen) = &mut self.inventory_screen {
+            screen.layout(viewport);
+        }
+        // Below the picture-in-picture inset, which shares the corner.
+        let toast_top = if self.inset.is_some() {
+            PictureInPicture::covered_height(viewport) as f32
+        } else {
+            0.0
+        };
+        let toasts = self.toasts.layout(viewport, toast_top);
+        let frame = UiFrame {
+            viewport,
+            content: &content,
+            inventory: &self.inventory,
+            inventory_screen: self.inventory_screen.as_ref(),
+            atlases: &self.atlases,
+            gui: self.pause_menu.as_ref().map(PauseMenu::gui),
+            toasts: &toasts,
+        };
+        self.ui.prepare(&self.device, &self.queue, &frame);
     }
 
     pub fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
         let output = self.surface.get_current_texture()?;
-        let view = output
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
+        let view = labeled_view(&output.texture, "Window surface view");
         let mut encoder = self
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor {
                 label: Some("Render encoder"),
             });
 
+        let mut moving_blocks = self.block_ticker.moving_blocks();
+        moving_blocks.extend(self.entities.mob_models());
+        if self.free_camera.is_some() {
+            moving_blocks.extend(free_camera::player_model(self.player.feet_position()));
+        }
+        let items: Vec<MovingBlock> = self.entities.item_models().collect();
+        let sky = self.dimension.sky_light(&self.sky);
+        let lighting = LightingSettings {
+            sun_direction: sky.light_direction,
+            ..self.lighting
+        };
+
+        // The inset goes first, in its own submission, so its camera uniforms
+        // are not overwritten by the main view's before it draws.
+        if let Some(inset_camera) = self.inset_camera() {
+            let inset_camera = relative_camera(&inset_camera, self.render_origin);
+            self.picture_in_picture
+                .set_camera(&self.queue, &inset_camera);
+            let mut inset_encoder =
+                self.device
+                    .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                        label: Some("Picture-in-picture encoder"),
+                    });
+            let inset_ctx = FrameContext {
+                device: &self.device,
+                queue: &self.queue,
+                primary: false,
+                world: &self.world,
+                origin: self.render_origin,
+                camera: &inset_camera,
+                projection: self.picture_in_picture.projection(),
+                camera_binding: self.picture_in_picture.camera_binding(),
+                gamma: self.gamma,
+                light_shafts: self.light_shafts,
+                path_tracing: self.path_tracing,
+                ray_bounces: self.ray_bounces,
+                water_quality: self.water_quality,
+                lighting,
+                sky,
+                moving_blocks: &moving_blocks,
+                items: &items,
+                time: self.world_time,
+            };
+            debug_group(&mut inset_encoder, "Picture-in-picture view", |encoder| {
+                self.renderer
+                    .render(encoder, self.picture_in_picture.target(), &inset_ctx)
+            });
+            self.queue.submit(std::iter::once(inset_encoder.finish()));
+        }
+
+        let camera = relative_camera(&self.camera, self.render_origin);
         let frame_ctx = FrameContext {
             device: &self.device,
             queue: &self.queue,
-            surface_config: &self.surface_config,
+            primary: true,
             world: &self.world,
-            camera: &self.camera,
+            origin: self.render_origin,
+            camera: &camera,
             projection: &self.projection,
-            camera_bind_group: &self.camera_bind_group,
+            camera_binding: &self.camera_binding,
+            gamma: self.gamma,
+            light_shafts: self.light_shafts,
+            path_tracing: self.path_tracing,
+            ray_bounces: self.ray_bounces,
+            water_quality: self.water_quality,
+            lighting,
+            sky,
+            moving_blocks: &moving_blocks,
+            items: &items,
+            time: self.world_time,
         };
 
-        self.renderer.render(&mut encoder, &view, &frame_ctx);
-        self.debug_overlay.render(&mut encoder, &view);
+        let target = RenderTarget {
+            view: &view,
+            size: (self.surface_config.width, self.surface_config.height),
+        };
+        debug_group(&mut encoder, "World view", |encoder| {
+            self.renderer.render(encoder, target, &frame_ctx)
+        });
+        if self.inset.is_some() {
+            debug_group(&mut encoder, "Picture-in-picture composite", |encoder| {
+                self.picture_in_picture.composite(
+                    encoder,
+                    &view,
+                    [self.surface_config.width, self.surface_config.height],
+                )
+            });
+        }
+        debug_group(&mut encoder, "HUD", |encoder| {
+            self.ui.render(encoder, &view)
+        });
+        #[cfg(feature = "dev-ui")]
+        debug_group(&mut encoder, "Developer UI", |encoder| {
+            self.dev_ui.render(encoder, &view)
+        });
+        if std::mem::take(&mut self.screenshot_requested) {
+            self.screenshots
+                .capture(&self.device, &mut encoder, &output.texture);
+        }
 
         self.queue.submit(std::iter::once(encoder.finish()));
         output.present();
+        if let Some(trace) = &mut self.gpu_trace
+            && trace.frame_presented()
+        {
+            self.quit_requested = true;
+        }
         Ok(())
     }
 
-    pub fn handle_escape(&mut self) -> bool {
-        if self.mouse_state.captured {
-            self.set_mouse_capture(false);
-            false
+    #[cfg(feature = "dev-ui")]
+    fn run_dev_ui(&mut self, frame_seconds: f32) {
+        let frame = DevUiFrame {
+            settings: DevSettings {
+                gamma: &mut self.gamma,
+                light_shafts: &mut self.light_shafts,
+                path_tracing: &mut self.path_tracing,
+                water_quality: &mut self.water_quality,
+            },
+            materials: &mut self.material_editor,
+            console: &self.console,
+            renderer: self.renderer.kind(),
+            timings: self.renderer.timings().unwrap_or_default(),
+            frame_ms: frame_seconds * 1000.0,
+            viewport: [self.surface_config.width, self.surface_config.height],
+        };
+        if let Some(line) = self
+            .dev_ui
+            .run(&self.device, &self.queue, &self.window, frame)
+        {
+            self.run_command(&line);
+        }
+    }
+
+    /// Moves the render origin along if the camera has travelled far from
+    /// it, and uploads the camera relative to it.
+    fn update_camera_binding(&mut self) {
+        let origin = rebase_origin(self.render_origin, self.camera.position);
+        if origin != self.render_origin {
+            log::debug!("Render origin moved to {origin}");
+            self.render_origin = origin;
+        }
+        self.camera_binding.update(
+            &self.queue,
+            &relative_camera(&self.camera, origin),
+            &self.projection,
+        );
+    }
+
+    /// Where the picture-in-picture inset looks from this frame, if it is on.
+    fn inset_camera(&self) -> Option<Camera> {
+        match self.inset.as_ref()? {
+            InsetView::Rear => Some(Camera::new(
+                self.camera.position,
+                self.camera.yaw + 180.0,
+                -self.camera.pitch,
+            )),
+            InsetView::Fixed(camera) => Some(camera.clone()),
+        }
+    }
+
+    /// Saves what is kept only in memory while its chunks are loaded.
+    pub fn save_world(&mut self) {
+        self.entities.save_all(&self.world_save);
+        self.block_ticker.save_all(&self.world_save);
+        if self.world.save_dirty() {
+            self.events.publish(GameEvent::Notice(Notice::WorldSaved));
+        }
+    }
+
+    /// Whether the player chose to quit, or a GPU trace has all its frames.
+    pub fn quit_requested(&self) -> bool {
+        self.quit_requested
+    }
+
+    pub fn is_debug_window(&self, window_id: WindowId) -> bool {
+        self.debug_window
+            .as_ref()
+            .is_some_and(|debug_window| debug_window.id() == window_id)
+    }
+
+    /// Handles an event of the debug window; closing it only closes that window.
+    pub fn debug_window_event(&mut self, event: &WindowEvent) {
+        match event {
+            WindowEvent::CloseRequested => self.debug_window = None,
+            WindowEvent::Resized(size) => {
+                if let Some(debug_window) = &mut self.debug_window {
+                    debug_window.resize(&self.device, *size);
+                }
+            }
+            WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
+                if let Some(debug_window) = &mut self.debug_window {
+                    debug_window.resize(&self.device, **new_inner_size);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Opens or closes the debug window after its hotkey, and schedules its
+    /// next redraw. Windows can only be created from the event loop.
+    pub fn sync_debug_window(&mut self, target: &EventLoopWindowTarget<()>) {
+        if std::mem::take(&mut self.debug_window_toggled) {
+            self.debug_window = match self.debug_window.take() {
+                Some(_) => None,
+                None => DebugWindow::open(
+                    target,
+                    &self.instance,
+                    &self.adapter,
+                    &self.device,
+                    &self.queue,
+                ),
+            };
+        }
+        if let Some(debug_window) = &self.debug_window {
+            debug_window.request_redraw();
+        }
+    }
+
+    pub fn render_debug_window(&mut self) {
+        if self.debug_window.is_none() {
+            return;
+        }
+        let timings = self.renderer.timings().unwrap_or_default();
+        let position = self.camera.position;
+        let mut stats = format!(
+            "Renderer: {}\nFrame: {:>6.2} ms\nRenderer CPU: {:>6.2} ms\nGPU: {}\n",
+            self.renderer.kind().as_str(),
+            self.last_frame_time * 1000.0,
+            timings.total_ms,
+            self.gpu_time_label(&timings),
+        );
+        if self.renderer.kind() == RendererKind::Rasterized {
+            let _ = writeln!(
+                &mut stats,
+                "Chunk meshes: {} drawn, {} culled",
+                timings.drawn_chunks, timings.culled_chunks
+            );
         } else {
-            true
+            let _ = writeln!(&mut stats, "Voxels: {}", timings.voxels);
+            if let Some(radius) = timings.traced_radius {
+                let _ = writeln!(&mut stats, "Buffers full: tracing {radius} chunks out");
+            }
+            if self.path_tracing {
+                let _ = writeln!(&mut stats, "Path-traced samples: {}", timings.samples);
+            }
+            if let Some(traversal) = timings.traversal {
+                let _ = writeln!(
+                    &mut stats,
+                    "Traversal: {:.1} steps/ray over {} rays, {} empty regions skipped",
+                    traversal.steps_per_ray(),
+                    traversal.rays,
+                    traversal.skips
+                );
+            }
+        }
+        let _ = write!(
+            &mut stats,
+            "Loaded chunks: {}\nBlock updates: {}\nMobs: {}  Items: {}\nPipelines: {}\nPOS: {:+.1} {:+.1} {:+.1}",
+            self.world.chunk_count(),
+            self.block_ticker.pending_updates(),
+            self.entities.count(EntityKind::Lurker),
+            self.entities.count(EntityKind::Item),
+            self.pipeline_cache.len(),
+            position.x,
+            position.y,
+            position.z,
+        );
+        let map = chunk_map(
+            &self.world,
+            self.loaded_chunk_center,
+            DEBUG_WINDOW_MAP_RADIUS,
+        );
+        let content = DebugWindowContent {
+            stats: &stats,
+            chunk_map: &map,
+        };
+        let Some(debug_window) = &mut self.debug_window else {
+            return;
+        };
+        match debug_window.render(&self.device, &self.queue, &content) {
+            Ok(()) => {}
+            Err(wgpu::SurfaceError::Lost) => {
+                let size = debug_window.inner_size();
+                debug_window.resize(&self.device, size);
+            }
+            Err(err) => log::warn!("Debug window render error: {err:?}"),
         }
     }
 
@@ -547,8 +1654,667 @@ Hotbar: {}
         self.mouse_state.frame_sleep(elapsed);
     }
 
-    fn process_interactions(&mut self) {
-        if !(self.pending_break || self.pending_place || self.pending_pick) {
+    /// Hurts the player and puts them back at the spawn point, unless the
+    /// fall kills them.
+    fn fall_into_void(&mut self) {
+        if self.health.damage(VOID_DAMAGE) {
+            self.die(DeathCause::Void);
+            return;
+        }
+        log::info!(
+            "Fell into the void; health {:.1}/{}",
+            self.health.current(),
+            MAX_HEALTH
+        );
+        self.teleport(self.spawn_point);
+    }
+
+    /// Hurts the player for a lurker's hit.
+    fn take_mob_damage(&mut self, amount: f32) {
+        match self.health.hit(amount) {
+            HitResult::Ignored => {}
+            HitResult::Hurt => log::info!(
+                "Hit by a lurker; health {:.1}/{}",
+                self.health.current(),
+                MAX_HEALTH
+            ),
+            HitResult::Killed => self.die(DeathCause::Lurker),
+        }
+    }
+
+    /// Whether the player is alive and in the world rather than watching it.
+    fn can_interact(&self) -> bool {
+        self.death_screen.is_none() && self.player.mode() != MovementMode::Spectator
+    }
+
+    /// Drops the inventory where the player fell, unless the config keeps it,
+    /// and opens the death screen. Death ends a hardcore world for good.
+    fn die(&mut self, cause: DeathCause) {
+        log::info!("{}", cause.message());
+        self.close_inventory_screen();
+        if self.free_camera.is_some() {
+            self.toggle_free_camera();
+        }
+        if !self.keep_inventory {
+            let feet = self.player.feet_position();
+            self.entities
+                .drop_items(feet + Vec3::Y * 0.5, self.inventory.take_all());
+        }
+        if self.world_info.hardcore {
+            self.world_info.spectator_only = true;
+            if let Err(err) = self.world_save.store_info(&self.world_info) {
+                log::warn!("Failed to save world info: {}", err);
+            }
+        }
+        self.death_screen = Some(DeathScreen::new(cause, self.world_info.hardcore));
+        self.console.close();
+        self.block_picker.cancel();
+        self.breaker.release();
+        self.camera_controller.release_keys();
+        self.fast_forwarding = false;
+        self.pending_attack = false;
+        self.pending_place = false;
+        self.pending_pick = false;
+        self.set_mouse_capture(false);
+    }
+
+    /// Closes the controls card on its key, Escape, Enter or a click, which
+    /// also captures the cursor. Other input passes through to the game.
+    fn controls_card_input(&mut self, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::KeyboardInput { input, .. }
+                if input.state == ElementState::Pressed
+                    && matches!(
+                        input.virtual_keycode,
+                        Some(CONTROLS_CARD_KEY | VirtualKeyCode::Escape | VirtualKeyCode::Return)
+                    ) =>
+            {
+                self.dismiss_controls_card();
+                true
+            }
+            WindowEvent::MouseInput {
+                state: ElementState::Pressed,
+                ..
+            } => {
+                self.dismiss_controls_card();
+                self.set_mouse_capture(true);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Hides the controls card. Closing it on the first launch writes
+    /// `config.json`, so the card only opens by itself once.
+    fn dismiss_controls_card(&mut self) {
+        self.controls_card.hide();
+        if self.config.first_run {
+            self.config.first_run = false;
+            self.save_settings();
+        }
+    }
+
+    /// Takes every input while the death screen is up, so only its options
+    /// do anything.
+    fn death_screen_input(&mut self, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::KeyboardInput { input, .. } => {
+                if input.state == ElementState::Pressed
+                    && let Some(key) = input.virtual_keycode
+                    && let Some(screen) = &self.death_screen
+                    && let Some(choice) = screen.handle_key(key)
+                {
+                    self.leave_death_screen(choice);
+                }
+                true
+            }
+            WindowEvent::ReceivedCharacter(_)
+            | WindowEvent::MouseInput { .. }
+            | WindowEvent::MouseWheel { .. } => true,
+            _ => false,
+        }
+    }
+
+    fn open_inventory_screen(&mut self) {
+        self.inventory_screen = Some(InventoryScreen::new(self.gui_scale, self.inventory_key));
+        self.block_picker.cancel();
+        self.breaker.release();
+        self.camera_controller.release_keys();
+        self.fast_forwarding = false;
+        self.set_mouse_capture(false);
+    }
+
+    /// Puts the carried stack back, dropping it at the player's feet if the
+    /// inventory is full.
+    fn close_inventory_screen(&mut self) {
+        let Some(screen) = self.inventory_screen.take() else {
+            return;
+        };
+        if let Some(stack) = screen.close(&mut self.inventory) {
+            let feet = self.player.feet_position();
+            self.entities.drop_items(feet + Vec3::Y * 0.5, [stack]);
+        }
+    }
+
+    /// Takes every input while the inventory screen is open.
+    fn inventory_screen_input(&mut self, event: &WindowEvent) -> bool {
+        let Some(screen) = &mut self.inventory_screen else {
+            return false;
+        };
+        if screen.input(event, &mut self.inventory) {
+            self.close_inventory_screen();
+            self.set_mouse_capture(true);
+        }
+        matches!(
+            event,
+            WindowEvent::KeyboardInput { .. }
+                | WindowEvent::ReceivedCharacter(_)
+                | WindowEvent::CursorMoved { .. }
+                | WindowEvent::MouseInput { .. }
+                | WindowEvent::MouseWheel { .. }
+        )
+    }
+
+    fn open_pause_menu(&mut self) {
+        self.pause_menu = Some(PauseMenu::new(
+            self.gui_scale,
+            self.world.terrain().world_name(),
+        ));
+        self.block_picker.cancel();
+        self.breaker.release();
+        self.camera_controller.release_keys();
+        self.fast_forwarding = false;
+        self.set_mouse_capture(false);
+    }
+
+    /// Takes every input while the pause menu is open and applies what the
+    /// player changes in it.
+    fn pause_menu_input(&mut self, event: &WindowEvent) -> bool {
+        let Some(menu) = &mut self.pause_menu else {
+            return false;
+        };
+        let mut present_mode = self.present_mode;
+        let mut renderer = self.renderer_name;
+        let mut render_distance = self.chunk_radius;
+        let settings = MenuSettings {
+            gamma: &mut self.gamma,
+            mouse_sensitivity: &mut self.mouse_state.sensitivity,
+            light_shafts: &mut self.light_shafts,
+            shadows: &mut self.lighting.shadows,
+            water_quality: &mut self.water_quality,
+            present_mode: &mut present_mode,
+            max_fps: &mut self.mouse_state.max_fps,
+            renderer: &mut renderer,
+            fov: &mut self.projection.fovy,
+            render_distance: &mut render_distance,
+        };
+        let choice = menu.input(event, settings);
+        if present_mode != self.present_mode {
+            self.set_present_mode(present_mode);
+        }
+        if renderer != self.renderer_name
+            && let Some(entry) = find_renderer(renderer)
+        {
+            self.switch_renderer(entry);
+        }
+        if render_distance != self.chunk_radius {
+            self.set_render_distance(render_distance);
+        }
+        match choice {
+            Some(PauseChoice::Resume) => {
+                self.pause_menu = None;
+                self.set_mouse_capture(true);
+            }
+            Some(PauseChoice::Quit) => self.quit_requested = true,
+            Some(PauseChoice::SaveSettings) => self.save_settings(),
+            None => {}
+        }
+        matches!(
+            event,
+            WindowEvent::KeyboardInput { .. }
+                | WindowEvent::ReceivedCharacter(_)
+                | WindowEvent::CursorMoved { .. }
+                | WindowEvent::MouseInput { .. }
+                | WindowEvent::MouseWheel { .. }
+        )
+    }
+
+    fn leave_death_screen(&mut self, choice: DeathChoice) {
+        let Some(screen) = self.death_screen.take() else {
+            return;
+        };
+        match choice {
+            DeathChoice::Respawn => {
+                self.health.restore();
+                self.teleport(self.spawn_point);
+            }
+            DeathChoice::Spectate => {
+                self.health.restore();
+                self.player.set_mode(MovementMode::Spectator);
+                // There is nothing to watch in the void.
+                if screen.cause() == DeathCause::Void {
+                    self.teleport(self.spawn_point);
+                }
+            }
+            DeathChoice::Quit => {
+                self.quit_requested = true;
+                return;
+            }
+        }
+        self.set_mouse_capture(true);
+    }
+
+    /// Detaches the camera from the player where it is, or snaps it back to
+    /// the player's eyes, looking where they looked when it left.
+    fn toggle_free_camera(&mut self) {
+        match self.free_camera.take() {
+            Some(view) => {
+                self.camera.yaw = view.yaw;
+                self.camera.pitch = view.pitch;
+                self.camera.position = self.player.camera_position();
+            }
+            None => self.free_camera = Some(self.camera.clone()),
+        }
+        self.breaker.release();
+        log::info!(
+            "Free camera {}",
+            if self.free_camera.is_some() {
+                "on"
+            } else {
+                "off"
+            }
+        );
+    }
+
+    fn teleport(&mut self, camera_position: Vec3) {
+        self.player.teleport(camera_position);
+        self.camera.position = camera_position;
+    }
+
+    /// Moves the player to `camera_position` once the chunks around it are
+    /// loaded, generating missing ones in the background first so the player
+    /// never lands in ungenerated terrain.
+    fn begin_teleport(&mut self, camera_position: Vec3, arrival: String) {
+        self.begin_travel(self.dimension, camera_position, arrival);
+    }
+
+    /// [`begin_teleport`](Self::begin_teleport) to `camera_position` in
+    /// `dimension`. Another dimension's chunks load into a world of their
+    /// own, which replaces the current one on arrival.
+    fn begin_travel(&mut self, dimension: Dimension, camera_position: Vec3, arrival: String) {
+        let center = BlockPos(camera_position.floor().as_ivec3()).chunk();
+        if dimension == self.dimension {
+            let loaded = chunk_coords_loaded_around(
+                self.world.terrain(),
+                center,
+                self.chunk_radius,
+                self.chunk_vertical_radius,
+            )
+            .all(|coord| self.world.chunk(coord).is_some());
+            if loaded {
+                self.pending_teleport = None;
+                self.teleport(camera_position);
+                self.console.message(arrival);
+                return;
+            }
+        }
+        let (terrain, storage, dimension) = if dimension == self.dimension {
+            (self.world.terrain(), self.world.storage(), None)
+        } else {
+            (
+                self.dimension_terrain(dimension),
+                self.world_save.in_dimension(dimension).region_storage(),
+                Some(dimension),
+            )
+        };
+        self.pending_teleport = Some(PendingTeleport {
+            destination: camera_position,
+            dimension,
+            arrival,
+            loader: RegionLoader::spawn(
+                terrain,
+                storage,
+                center,
+                self.chunk_radius,
+                self.chunk_vertical_radius,
+            ),
+        });
+    }
+
+    /// The generator of `dimension` of the configured world.
+    fn dimension_terrain(&self, dimension: Dimension) -> TerrainGenerator {
+        dimension.terrain(TerrainGenerator::new(
+            self.config.world_preset,
+            self.config.world_seed,
+        ))
+    }
+
+    /// Leaves the current dimension for `dimension`, whose chunks `world`
+    /// holds: saves what is left behind, and starts the new dimension's
+    /// entities, block updates and renderer afresh.
+    fn enter_dimension(&mut self, dimension: Dimension, world: World, center: ChunkPos) {
+        self.entities.save_all(&self.world_save);
+        self.block_ticker.save_all(&self.world_save);
+        self.world.save_dirty();
+        self.world = world;
+        self.world
+            .update_emission(self.material_editor.definitions());
+        self.dimension = dimension;
+        self.world_save = self.world_save.in_dimension(dimension);
+        self.home = self.world_save.load_home();
+        self.spawn_point = terrain::find_spawn(self.world.terrain()) + Vec3::Y * PLAYER_EYE_HEIGHT;
+        self.entities = Entities::new();
+        self.block_ticker = BlockTicker::new(Stream::RandomTicks.rng(self.config.world_seed));
+        self.breaker.release();
+        self.loaded_chunk_center = center;
+        let entry = find_renderer(self.renderer_name).expect("renderer names are registered");
+        self.switch_renderer(entry);
+    }
+
+    fn finish_pending_teleport(&mut self) {
+        let Some(pending) = &self.pending_teleport else {
+            return;
+        };
+        let Some(result) = pending.loader.poll() else {
+            return;
+        };
+        let pending = self.pending_teleport.take().unwrap();
+        match result {
+            Ok(region) => {
+                match pending.dimension {
+                    Some(dimension) => {
+                        let center = BlockPos(pending.destination.floor().as_ivec3()).chunk();
+                        self.enter_dimension(dimension, region, center);
+                    }
+                    None => self.world.absorb(region),
+                }
+                self.teleport(pending.destination);
+                self.console.message(pending.arrival);
+            }
+            Err(err) => {
+                log::warn!("Failed to load teleport destination: {}", err);
+                self.console.message("Failed to load destination");
+            }
+        }
+    }
+
+    /// Builds a portal in front of the player, facing them, and pairs it
+    /// with the last one if that was waiting for a partner.
+    fn build_portal(&mut self) {
+        if !self.can_interact() {
+            self.console.message("Spectators cannot build portals");
+            return;
+        }
+        let portal = Portal::in_front_of(
+            self.player.feet_position(),
+            self.camera.forward(),
+            self.dimension,
+        );
+        for (position, kind) in portal.blocks() {
+            self.ensure_chunk_for_block(position);
+            let _ = self
+                .block_ticker
+                .set_block(&mut self.world, &mut self.events, position, kind);
+        }
+        let linked = self.portals.add(portal);
+        if let Err(err) = self.portals.store(&self.world_save) {
+            log::warn!("Failed to save portals: {}", err);
+        }
+        self.console.message(if linked {
+            "Portal built and linked to the last one"
+        } else {
+            "Portal built; build another with /portal to link them"
+        });
+    }
+
+    /// Sends the player through the portal they walked into, once the
+    /// chunks around its partner are loaded.
+    fn enter_portal(&mut self) {
+        if self.pending_teleport.is_some() {
+            return;
+        }
+        let feet = self.player.feet_position().floor().as_ivec3();
+        let Some(exit) = [feet, feet + IVec3::Y]
+            .into_iter()
+            .find_map(|cell| self.portals.destination(self.dimension, cell))
+        else {
+            return;
+        };
+        self.begin_travel(
+            exit.dimension,
+            exit.exit_feet() + Vec3::Y * PLAYER_EYE_HEIGHT,
+            "Travelled through the portal".to_string(),
+        );
+    }
+
+    fn run_command(&mut self, line: &str) {
+        if line.trim().is_empty() {
+            return;
+        }
+        match Command::parse(line) {
+            Ok(Command::Spawn) => {
+                self.begin_teleport(self.spawn_point, "Teleported to spawn".to_string());
+            }
+            Ok(Command::SetHome) => {
+                let feet = self.player.feet_position();
+                match self.world_save.store_home(feet) {
+                    Ok(()) => {
+                        self.home = Some(feet);
+                        self.console.message(format!(
+                            "Home set to {:.1} {:.1} {:.1}",
+                            feet.x, feet.y, feet.z
+                        ));
+                    }
+                    Err(err) => {
+                        log::warn!("Failed to save home: {}", err);
+                        self.console.message("Failed to save home");
+                    }
+                }
+            }
+            Ok(Command::Home) => match self.home {
+                Some(feet) => {
+                    self.begin_teleport(
+                        feet + Vec3::Y * PLAYER_EYE_HEIGHT,
+                        "Teleported home".to_string(),
+                    );
+                }
+                None => self.console.message("No home set; use /sethome first"),
+            },
+            Ok(Command::Portal) => self.build_portal(),
+            Ok(Command::Dimension(dimension)) if dimension == self.dimension => self
+                .console
+                .message(format!("Already in the {}", dimension.name())),
+            Ok(Command::Dimension(dimension)) => {
+                let spawn = terrain::find_spawn(self.dimension_terrain(dimension))
+                    + Vec3::Y * PLAYER_EYE_HEIGHT;
+                self.begin_travel(
+                    dimension,
+                    spawn,
+                    format!("Entered the {}", dimension.name()),
+                );
+            }
+            Ok(Command::Teleport(feet)) => self.teleport_feet(feet),
+            Ok(Command::Camera(feed)) => {
+                let (inset, message) = match feed {
+                    CameraFeed::Rear => (Some(InsetView::Rear), "Showing the rear view"),
+                    CameraFeed::Here => (
+                        Some(InsetView::Fixed(self.camera.clone())),
+                        "Camera placed here",
+                    ),
+                    CameraFeed::Off => (None, "Camera feed off"),
+                };
+                self.inset = inset;
+                self.console.message(message);
+            }
+            Ok(Command::Save) => self.save_world(),
+            Ok(Command::Renderer(None)) => self.console.message(format!(
+                "Renderer: {} (available: {})",
+                self.renderer_name,
+                renderer_names()
+            )),
+            Ok(Command::Renderer(Some(name))) => match find_renderer(&name) {
+                Some(entry) => {
+                    self.switch_renderer(entry);
+                    self.console
+                        .message(format!("Switched to the {} renderer", entry.name));
+                }
+                None => self.console.message(format!(
+                    "Unknown renderer '{}'; available: {}",
+                    name,
+                    renderer_names()
+                )),
+            },
+            Ok(Command::Present(setting)) => {
+                self.set_present_mode(setting);
+                self.console
+                    .message(format!("Present mode: {}", self.present_mode_label()));
+            }
+            Ok(Command::MaxFps(max_fps)) => {
+                self.mouse_state.max_fps = max_fps;
+                self.console
+                    .message(format!("Frame cap: {}", self.frame_cap_label()));
+            }
+            Err(err) => self.console.message(err),
+        }
+    }
+
+    /// The surface's present mode, named as `present_mode` names it, and
+    /// the requested one if the surface could not use it.
+    fn present_mode_label(&self) -> String {
+        let active = match self.surface_config.present_mode {
+            wgpu::PresentMode::Fifo => "vsync",
+            wgpu::PresentMode::Mailbox => "mailbox",
+            wgpu::PresentMode::Immediate => "immediate",
+            _ => "other",
+        };
+        let requested = self.present_mode.as_str();
+        if active == requested {
+            active.to_string()
+        } else {
+            format!("{active} ({requested} unsupported)")
+        }
+    }
+
+    /// The renderer's GPU time for its two stretches of the frame, or why
+    /// there is none.
+    fn gpu_time_label(&self, timings: &RenderTimings) -> String {
+        if !self
+            .device
+            .features()
+            .contains(wgpu::Features::TIMESTAMP_QUERY)
+        {
+            return "no timestamp queries".to_string();
+        }
+        let (compute, present) = match self.renderer.kind() {
+            RendererKind::Rasterized => ("scene", "post"),
+            RendererKind::RayTraced => ("trace", "blit"),
+        };
+        format!(
+            "{:>6.2} ms {}, {:>6.2} ms {}",
+            timings.gpu_compute_ms, compute, timings.gpu_present_ms, present
+        )
+    }
+
+    fn frame_cap_label(&self) -> String {
+        self.mouse_state
+            .max_fps
+            .map_or("none".to_string(), |fps| format!("{fps:.0} FPS"))
+    }
+
+    /// Loads the chunks within the render distance of `center` and unloads
+    /// those beyond it and its margin.
+    fn stream_chunks(&mut self, center: ChunkPos) {
+        self.world
+            .ensure_chunks_in_radius(center, self.chunk_radius, self.chunk_vertical_radius);
+        let unload_radius = self.chunk_radius + self.chunk_unload_margin;
+        let unload_vertical = self.chunk_vertical_radius + self.chunk_unload_margin;
+        self.world
+            .unload_chunks_outside(center, unload_radius, unload_vertical);
+        self.loaded_chunk_center = center;
+    }
+
+    fn set_render_distance(&mut self, radius: i32) {
+        self.chunk_radius = radius;
+        self.stream_chunks(self.loaded_chunk_center);
+    }
+
+    /// Copies the live settings into the config and writes it to
+    /// `config.json`.
+    fn save_settings(&mut self) {
+        let config = &mut self.config;
+        config.mouse_sensitivity = self.mouse_state.sensitivity;
+        config.present_mode = self.present_mode;
+        config.max_fps = self.mouse_state.max_fps;
+        config.render_method = self.renderer_name;
+        config.gamma = self.gamma;
+        config.fov = self.projection.fovy;
+        config.render_distance = self.chunk_radius;
+        config.light_shafts = self.light_shafts;
+        config.path_tracing = self.path_tracing;
+        config.water_quality = self.water_quality;
+        config.lighting.shadows = self.lighting.shadows;
+        match config.save() {
+            Ok(()) => self
+                .events
+                .publish(GameEvent::Notice(Notice::SettingsSaved)),
+            Err(err) => log::warn!("Failed to save config.json: {}", err),
+        }
+    }
+
+    /// Reconfigures the surface with `setting`, or the closest mode it
+    /// supports.
+    fn set_present_mode(&mut self, setting: PresentModeSetting) {
+        let available = self.surface.get_capabilities(&self.adapter).present_modes;
+        self.present_mode = setting;
+        self.surface_config.present_mode = choose_present_mode(&available, setting);
+        self.surface.configure(&self.device, &self.surface_config);
+    }
+
+    /// Replaces the renderer with a fresh one built by `entry`, carrying over
+    /// the edited materials.
+    fn switch_renderer(&mut self, entry: &'static RendererEntry) {
+        self.renderer = (entry.create)(&RendererContext {
+            device: &self.device,
+            queue: &self.queue,
+            surface_config: &self.surface_config,
+            world: &self.world,
+            block_atlas: self.atlases.blocks(),
+            camera_bind_group_layout: &self.camera_bind_group_layout,
+            pipeline_cache: &self.pipeline_cache,
+            ao_strength: self.lighting.ao_strength,
+            msaa_samples: self.msaa_samples,
+        });
+        self.renderer_name = entry.name;
+        self.renderer
+            .update_materials(&self.queue, self.material_editor.definitions());
+    }
+
+    /// Hash of everything the simulation advances: blocks, the player and
+    /// the entities.
+    fn simulation_checksum(&self, checker: &mut DivergenceChecker) -> u64 {
+        let mut hasher = StateHasher::new();
+        hasher.write_u64(checker.world_checksum(&self.world));
+        let body = self.player.body();
+        hasher.write_vec3(body.position);
+        hasher.write_vec3(body.velocity);
+        hasher.write(&self.health.current().to_bits().to_le_bytes());
+        for entity in self.entities.iter() {
+            hasher.write_vec3(entity.body.position);
+            hasher.write_vec3(entity.body.velocity);
+            hasher.write(&entity.health.current().to_bits().to_le_bytes());
+        }
+        hasher.finish()
+    }
+
+    fn process_interactions(&mut self, dt: f32) {
+        if !self.can_interact() || self.free_camera.is_some() {
+            self.breaker.release();
+            self.pending_attack = false;
+            self.pending_place = false;
+            self.pending_pick = false;
+            return;
+        }
+        if !(self.breaker.is_held() || self.pending_place || self.pending_pick) {
             return;
         }
 
@@ -560,52 +2326,135 @@ Hotbar: {}
             INTERACTION_DISTANCE,
         );
 
-        if self.pending_pick {
-            if let Some(hit) = hit.as_ref() {
-                let kind =
-                    BlockKind::from_id(self.world.block_at(hit.block.x, hit.block.y, hit.block.z));
-                if kind != BlockKind::Air {
-                    let _ = self.hotbar.select_block(kind);
+        if std::mem::take(&mut self.pending_attack) {
+            let reach = hit
+                .as_ref()
+                .map_or(ATTACK_REACH, |hit| hit.distance.min(ATTACK_REACH));
+            if let Some((entity, _)) = self.entities.pick(self.camera.position, forward, reach) {
+                // Hitting an entity takes the click; holding it breaks nothing.
+                self.breaker.release();
+                let tool = self
+                    .inventory
+                    .selected()
+                    .and_then(|stack| stack.item.as_tool());
+                let strength = self.attack_cooldown.swing();
+                let damage = combat::attack_damage(tool) * strength;
+                let result = combat::strike(entity, self.player.feet_position(), damage, strength);
+                if result != HitResult::Ignored
+                    && let Some(damage_numbers) = &mut self.damage_numbers
+                {
+                    let (_, top) = entity.body.bounds();
+                    damage_numbers.push(entity.body.position.with_y(top.y), damage);
                 }
             }
         }
 
-        if self.pending_break {
-            if let Some(hit) = hit.as_ref() {
-                let _ = self.world.set_block(hit.block, BLOCK_AIR);
+        if self.pending_pick
+            && let Some(hit) = hit.as_ref()
+        {
+            let kind = self.world.block_info(hit.block).kind.placed_state();
+            if kind != BlockKind::Air {
+                let _ = self.inventory.select_block(kind);
             }
         }
 
-        if self.pending_place {
-            if let Some(hit) = hit.as_ref() {
+        let tool = self
+            .inventory
+            .selected()
+            .and_then(|stack| stack.item.as_tool());
+        let target = hit.as_ref().and_then(|hit| {
+            let kind = self.world.block_info(hit.block).kind;
+            mining::break_time(kind, tool).map(|time| (hit.block, time))
+        });
+        if let Some(position) = self.breaker.update(target, dt) {
+            let kind = self.world.block_info(position).kind;
+            let broken = self.block_ticker.set_block(
+                &mut self.world,
+                &mut self.events,
+                position,
+                BlockKind::Air,
+            );
+            if broken && mining::can_harvest(kind, tool) {
+                let drop = ItemStack::new(ItemKind::Block(kind.placed_state()), 1);
+                if !self.inventory.collect(drop) {
+                    self.entities
+                        .drop_items(position.as_vec3() + Vec3::splat(0.5), [drop]);
+                }
+            }
+        }
+
+        if self.pending_place
+            && let Some(hit) = hit.as_ref()
+        {
+            let selected = self
+                .inventory
+                .selected()
+                .and_then(|stack| stack.item.as_block());
+            let hit_kind = self.world.block_info(hit.block).kind;
+            if let BlockKind::Lever(on) = hit_kind {
+                let _ = self.block_ticker.set_block(
+                    &mut self.world,
+                    &mut self.events,
+                    hit.block,
+                    BlockKind::Lever(!on),
+                );
+            } else if let (Some(BlockKind::SnowLayer(_)), BlockKind::SnowLayer(layers)) =
+                (selected, hit_kind)
+            {
+                // Snow piles onto existing snow until the cell is full.
+                if layers < SNOW_MAX_LAYERS && !self.player.overlaps_block(hit.block) {
+                    let _ = self.block_ticker.set_block(
+                        &mut self.world,
+                        &mut self.events,
+                        hit.block,
+                        BlockKind::SnowLayer(layers + 1),
+                    );
+                }
+            } else if let Some(selected) = selected {
                 let target = hit.placement_position();
                 self.ensure_chunk_for_block(target);
-                if self.can_place_block(target) {
-                    let block_id = self.hotbar.selected().id();
-                    let _ = self.world.set_block(target, block_id);
+                if self.can_place_block(target) && self.has_support(selected, target) {
+                    let placed = selected.facing(hit.face);
+                    let _ = self.block_ticker.set_block(
+                        &mut self.world,
+                        &mut self.events,
+                        target,
+                        placed,
+                    );
                 }
             }
         }
 
-        self.pending_break = false;
         self.pending_place = false;
         self.pending_pick = false;
     }
 
     fn ensure_chunk_for_block(&mut self, position: IVec3) {
-        let chunk_coord = chunk_coord_from_block(position);
+        let chunk_coord = BlockPos(position).chunk();
         if self.world.chunk(chunk_coord).is_none() {
             self.world.ensure_chunk(chunk_coord);
         }
     }
 
     fn can_place_block(&self, position: IVec3) -> bool {
-        if BlockKind::from_id(self.world.block_at(position.x, position.y, position.z)).is_solid() {
+        let existing = self.world.block_info(position).kind;
+        if existing.is_solid() && !existing.is_liquid() {
             return false;
         }
         !self.player.overlaps_block(position)
     }
 
+    /// Crops can only be planted on farmland and wire only lies on full blocks.
+    fn has_support(&self, kind: BlockKind, position: IVec3) -> bool {
+        let below = self.world.block_info(position - IVec3::Y).kind;
+        match kind {
+            BlockKind::Wheat(_) => below == BlockKind::Farmland,
+            BlockKind::Flower(_) => matches!(below, BlockKind::Grass | BlockKind::Dirt),
+            BlockKind::Wire(_) => below.is_full_cube(),
+            _ => true,
+        }
+    }
+
     fn hotbar_digit_index(key: VirtualKeyCode) -> Option<usize> {
         match key {
             VirtualKeyCode::Key1 => Some(0),
@@ -645,10 +2494,6 @@ Hotbar: {}
     }
 }
 
-fn populate_world_chunks(world: &mut World, center: ChunkCoord, radius: i32, vertical: i32) {
-    world.ensure_chunks_in_radius(center, radius, vertical);
-}
-
 fn choose_present_mode(
     available: &[wgpu::PresentMode],
     requested: config::PresentModeSetting,
@@ -677,6 +2522,37 @@ fn choose_present_mode(
         .unwrap_or(wgpu::PresentMode::Fifo)
 }
 
+/// Top-down map of the loaded chunks in `center`'s layer, `radius` chunks
+/// out, north up.
+fn chunk_map(world: &World, center: ChunkPos, radius: i32) -> String {
+    let mut map = String::new();
+    let _ = writeln!(&mut map, "Chunk grid (X/Z):");
+    for dz in (-radius..=radius).rev() {
+        map.push(' ');
+        for dx in -radius..=radius {
+            let coord = ChunkPos {
+                x: center.x + dx,
+                y: center.y,
+                z: center.z + dz,
+            };
+            let marker = if dx == 0 && dz == 0 {
+                'C'
+            } else if world.chunk(coord).is_some() {
+                '#'
+            } else {
+                '.'
+            };
+            map.push(marker);
+            if dx != radius {
+                map.push(' ');
+            }
+        }
+        map.push('\n');
+    }
+    let _ = writeln!(&mut map, "C=current chunk, #=loaded");
+    map
+}
+
 pub fn sleep_on_main_events(state: &AppState) {
     state.sleep_if_needed();
 }
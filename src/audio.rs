@@ -0,0 +1,207 @@
+use glam::Vec3;
+
+use crate::block::BlockKind;
+use crate::events::{ChangeCause, GameEvent};
+use crate::rand::Rng;
+
+/// Sounds farther than this from the listener are not played.
+pub const HEARING_DISTANCE: f32 = 24.0;
+/// Each sound's pitch is scaled by a random factor within this fraction of 1.0.
+const PITCH_VARIATION: f32 = 0.1;
+
+/// Family of sounds a block makes, set per block in the registry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SoundMaterial {
+    Silent,
+    Stone,
+    Dirt,
+    Glass,
+    Metal,
+    Wood,
+}
+
+impl SoundMaterial {
+    pub const fn name(self) -> &'static str {
+        match self {
+            SoundMaterial::Silent => "silence",
+            SoundMaterial::Stone => "stone",
+            SoundMaterial::Dirt => "dirt",
+            SoundMaterial::Glass => "glass",
+            SoundMaterial::Metal => "metal",
+            SoundMaterial::Wood => "wood",
+        }
+    }
+
+    /// Pitch before random variation; denser materials sound lower.
+    const fn base_pitch(self) -> f32 {
+        match self {
+            SoundMaterial::Silent => 1.0,
+            SoundMaterial::Stone => 0.9,
+            SoundMaterial::Dirt => 0.8,
+            SoundMaterial::Glass => 1.3,
+            SoundMaterial::Metal => 1.1,
+            SoundMaterial::Wood => 0.85,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SoundAction {
+    Break,
+    Place,
+    Step,
+}
+
+impl SoundAction {
+    const fn base_volume(self) -> f32 {
+        match self {
+            SoundAction::Break => 1.0,
+            SoundAction::Place => 0.8,
+            SoundAction::Step => 0.35,
+        }
+    }
+}
+
+/// Mixer channel a sound plays on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SoundCategory {
+    Blocks,
+    Footsteps,
+}
+
+/// Volume levels in `0.0..=1.0`; a category's level is scaled by `master`.
+#[derive(Clone, Copy, Debug)]
+pub struct VolumeMixer {
+    pub master: f32,
+    pub blocks: f32,
+    pub footsteps: f32,
+}
+
+impl VolumeMixer {
+    pub fn volume(&self, category: SoundCategory) -> f32 {
+        let level = match category {
+            SoundCategory::Blocks => self.blocks,
+            SoundCategory::Footsteps => self.footsteps,
+        };
+        self.master * level
+    }
+}
+
+impl Default for VolumeMixer {
+    fn default() -> Self {
+        Self {
+            master: 1.0,
+            blocks: 1.0,
+            footsteps: 0.6,
+        }
+    }
+}
+
+/// A mixed sound, ready for an output to play.
+#[derive(Clone, Copy, Debug)]
+pub struct PlayedSound {
+    pub material: SoundMaterial,
+    pub action: SoundAction,
+    pub category: SoundCategory,
+    pub position: Vec3,
+    /// Final gain after the mixer and distance falloff.
+    pub volume: f32,
+    pub pitch: f32,
+}
+
+/// Receives every sound the engine decides to play.
+pub trait AudioOutput {
+    fn play(&mut self, sound: &PlayedSound);
+}
+
+/// Logs sounds at debug level; used when there is no sound device.
+pub struct LogOutput;
+
+impl AudioOutput for LogOutput {
+    fn play(&mut self, sound: &PlayedSound) {
+        log::debug!(
+            "Sound {} {:?} on {:?} at {:.1?} (volume {:.2}, pitch {:.2})",
+            sound.material.name(),
+            sound.action,
+            sound.category,
+            sound.position,
+            sound.volume,
+            sound.pitch
+        );
+    }
+}
+
+fn device_output() -> Box<dyn AudioOutput> {
+    #[cfg(all(feature = "alsa", target_os = "linux"))]
+    match crate::alsa::AlsaOutput::open() {
+        Ok(output) => return Box::new(output),
+        Err(err) => log::warn!("No sound device ({err}); sounds will only be logged"),
+    }
+    Box::new(LogOutput)
+}
+
+/// Turns game events into sounds: picks each block's material from the
+/// registry, varies the pitch, and mixes the volume by category and distance.
+pub struct AudioEngine {
+    mixer: VolumeMixer,
+    output: Box<dyn AudioOutput>,
+    rng: Rng,
+}
+
+impl AudioEngine {
+    /// `rng` varies the pitch; see `Stream::Effects`. Sounds play on the
+    /// sound device when the `alsa` feature can open one.
+    pub fn new(mixer: VolumeMixer, rng: Rng) -> Self {
+        Self {
+            mixer,
+            output: device_output(),
+            rng,
+        }
+    }
+
+    /// Plays the sound for `event`, if it makes one audible at `listener`,
+    /// and returns it.
+    pub fn handle(&mut self, event: &GameEvent, listener: Vec3) -> Option<PlayedSound> {
+        let (kind, action, category, position) = match *event {
+            // Only the player's own edits make noise; simulated changes (crops,
+            // signals, falling sand) would drown everything else out.
+            GameEvent::BlockChanged {
+                cause: ChangeCause::Simulation,
+                ..
+            } => return None,
+            GameEvent::BlockChanged {
+                position, old, new, ..
+            } => {
+                let (kind, action) = if new == BlockKind::Air {
+                    (old, SoundAction::Break)
+                } else {
+                    (new, SoundAction::Place)
+                };
+                let center = position.as_vec3() + Vec3::splat(0.5);
+                (kind, action, SoundCategory::Blocks, center)
+            }
+            GameEvent::PlayerStep { position, block } => {
+                (block, SoundAction::Step, SoundCategory::Footsteps, position)
+            }
+            GameEvent::Notice(_) => return None,
+        };
+
+        let material = kind.definition().sound;
+        let falloff = 1.0 - position.distance(listener) / HEARING_DISTANCE;
+        let volume = action.base_volume() * self.mixer.volume(category) * falloff;
+        if material == SoundMaterial::Silent || volume <= 0.0 {
+            return None;
+        }
+        let variation = 1.0 + PITCH_VARIATION * (2.0 * self.rng.unit() - 1.0);
+        let sound = PlayedSound {
+            material,
+            action,
+            category,
+            position,
+            volume,
+            pitch: material.base_pitch() * variation,
+        };
+        self.output.play(&sound);
+        Some(sound)
+    }
+}
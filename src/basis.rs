@@ -0,0 +1,778 @@
+//! Transcoder for Basis Universal ETC1S textures, which KTX2 files carry
+//! under BasisLZ supercompression.
+//!
+//! Every block of an ETC1S image is an ETC1 block whose two halves share one
+//! color and intensity table, drawn from a codebook of endpoints, with its
+//! sixteen 2-bit selectors drawn from a codebook of selector patterns. The
+//! codebooks and the Huffman tables the slices are coded with live in the
+//! file's global data; each mip level's slice is a stream of codebook
+//! indices, predicted from neighbouring blocks. Transcoding writes the
+//! blocks out either as ETC1, which is valid ETC2 RGB, or as RGBA8 pixels.
+
+use std::io;
+
+/// ETC1 intensity modifiers, by table and by selector in ascending order.
+const INTENSITIES: [[i32; 4]; 8] = [
+    [-8, -2, 2, 8],
+    [-17, -5, 5, 17],
+    [-29, -9, 9, 29],
+    [-42, -13, 13, 42],
+    [-60, -18, 18, 60],
+    [-80, -24, 24, 80],
+    [-106, -33, 33, 106],
+    [-183, -47, 47, 183],
+];
+/// ETC1 pixel index of each ascending selector.
+const ETC1_PIXEL_INDICES: [u16; 4] = [3, 2, 0, 1];
+
+const MAX_CODE_SIZE: usize = 16;
+/// Order the code-length code sizes are stored in, likeliest first.
+const CODE_LENGTH_ORDER: [usize; 21] = [
+    17, 18, 19, 20, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15, 16,
+];
+const SMALL_ZERO_RUN: u32 = 17;
+const BIG_ZERO_RUN: u32 = 18;
+const SMALL_REPEAT: u32 = 19;
+
+/// Endpoint prediction symbol that repeats the previous one for a run.
+const ENDPOINT_PRED_REPEAT: u32 = 256;
+const ENDPOINT_PRED_MIN_REPEAT: u32 = 3;
+const ENDPOINT_PRED_COUNT_VLC_BITS: u32 = 4;
+const SELECTOR_RLE_MIN: u32 = 3;
+const SELECTOR_RLE_SYMBOLS: u32 = 64;
+
+/// `imageFlags` bit of images predicted from the previous one, as in video.
+const P_FRAME: u32 = 0x02;
+const GLOBAL_HEADER_LEN: usize = 20;
+const IMAGE_DESC_LEN: usize = 20;
+
+/// What [`BasisLz::transcode`] writes each block as.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Etc1sTarget {
+    /// 8-byte ETC1 blocks, uploadable as ETC2 RGB; alpha is dropped.
+    Etc1,
+    /// Four bytes per pixel, cropped to the image size.
+    Rgba8,
+}
+
+/// The global data of a BasisLZ-supercompressed KTX2 file.
+pub struct BasisLz {
+    endpoints: Vec<Endpoint>,
+    /// Each pattern's rows, with a pixel's selector in bits `2 * x`.
+    selectors: Vec<[u8; 4]>,
+    tables: SliceTables,
+    images: Vec<ImageDesc>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Endpoint {
+    /// 5-bit base color.
+    color: [u8; 3],
+    /// Row of `INTENSITIES`.
+    intensity: u8,
+}
+
+struct SliceTables {
+    endpoint_pred: Huffman,
+    endpoint_delta: Huffman,
+    selector: Huffman,
+    selector_rle: Huffman,
+    selector_history: usize,
+}
+
+#[derive(Clone, Copy)]
+struct ImageDesc {
+    flags: u32,
+    rgb: (usize, usize),
+    alpha: (usize, usize),
+}
+
+impl BasisLz {
+    /// Parses global data describing `image_count` images, one per mip level
+    /// for a single-layer 2D texture.
+    pub fn parse(data: &[u8], image_count: usize) -> io::Result<Self> {
+        if data.len() < GLOBAL_HEADER_LEN {
+            return Err(invalid("BasisLZ global data is truncated"));
+        }
+        let endpoint_count = read_u16(data, 0) as usize;
+        let selector_count = read_u16(data, 2) as usize;
+        let lengths = [4, 8, 12].map(|at| read_u32(data, at) as usize);
+        if endpoint_count == 0 || selector_count == 0 {
+            return Err(invalid("BasisLZ codebooks are empty"));
+        }
+
+        let descs_end = image_count
+            .checked_mul(IMAGE_DESC_LEN)
+            .and_then(|len| len.checked_add(GLOBAL_HEADER_LEN))
+            .filter(|&end| end <= data.len())
+            .ok_or_else(|| invalid("BasisLZ image descriptions are truncated"))?;
+        let images = data[GLOBAL_HEADER_LEN..descs_end]
+            .chunks_exact(IMAGE_DESC_LEN)
+            .map(|desc| ImageDesc {
+                flags: read_u32(desc, 0),
+                rgb: (read_u32(desc, 4) as usize, read_u32(desc, 8) as usize),
+                alpha: (read_u32(desc, 12) as usize, read_u32(desc, 16) as usize),
+            })
+            .collect();
+
+        let mut sections = Vec::with_capacity(3);
+        let mut start = descs_end;
+        for length in lengths {
+            let section = start
+                .checked_add(length)
+                .and_then(|end| data.get(start..end))
+                .ok_or_else(|| invalid("BasisLZ global data is truncated"))?;
+            sections.push(section);
+            start += length;
+        }
+
+        Ok(Self {
+            endpoints: decode_endpoints(sections[0], endpoint_count)?,
+            selectors: decode_selectors(sections[1], selector_count)?,
+            tables: decode_tables(sections[2])?,
+            images,
+        })
+    }
+
+    /// Whether any image carries an alpha slice.
+    pub fn has_alpha(&self) -> bool {
+        self.images.iter().any(|image| image.alpha.1 > 0)
+    }
+
+    /// Transcodes image `index`, whose slices lie in `level` (its mip level's
+    /// bytes), of `width` x `height` pixels.
+    pub fn transcode(
+        &self,
+        index: usize,
+        level: &[u8],
+        width: u32,
+        height: u32,
+        target: Etc1sTarget,
+    ) -> io::Result<Vec<u8>> {
+        let image = self
+            .images
+            .get(index)
+            .ok_or_else(|| invalid(format!("BasisLZ image {index} is not described")))?;
+        if image.flags & P_FRAME != 0 {
+            return Err(invalid("BasisLZ video frames are not supported"));
+        }
+        let blocks_x = width.div_ceil(4) as usize;
+        let blocks_y = height.div_ceil(4) as usize;
+        let slice = |(offset, length): (usize, usize)| {
+            offset
+                .checked_add(length)
+                .and_then(|end| level.get(offset..end))
+                .ok_or_else(|| invalid(format!("BasisLZ slice of image {index} lies outside it")))
+        };
+        let rgb = self.decode_slice(slice(image.rgb)?, blocks_x, blocks_y)?;
+
+        match target {
+            Etc1sTarget::Etc1 => Ok(rgb
+                .iter()
+                .flat_map(|&(endpoint, selector)| etc1_block(endpoint, selector))
+                .collect()),
+            Etc1sTarget::Rgba8 => {
+                let alpha = match image.alpha {
+                    (_, 0) => None,
+                    range => Some(self.decode_slice(slice(range)?, blocks_x, blocks_y)?),
+                };
+                let (width, height) = (width as usize, height as usize);
+                let mut pixels = vec![255; width * height * 4];
+                for (block, &(endpoint, selector)) in rgb.iter().enumerate() {
+                    let (block_x, block_y) = (block % blocks_x * 4, block / blocks_x * 4);
+                    let colors = block_colors(endpoint);
+                    let alphas = alpha.as_ref().map(|alpha| {
+                        let (endpoint, selector) = alpha[block];
+                        (block_colors(endpoint), selector)
+                    });
+                    for y in 0..4.min(height - block_y) {
+                        for x in 0..4.min(width - block_x) {
+                            let at = ((block_y + y) * width + block_x + x) * 4;
+                            let color = colors[selector_at(selector, x, y)];
+                            pixels[at..at + 3].copy_from_slice(&color[..3]);
+                            // Alpha slices are coded as grayscale.
+                            if let Some((alpha_colors, alpha_selector)) = alphas {
+                                pixels[at + 3] = alpha_colors[selector_at(alpha_selector, x, y)][1];
+                            }
+                        }
+                    }
+                }
+                Ok(pixels)
+            }
+        }
+    }
+
+    /// The endpoint and selector pattern of every block of a slice, in
+    /// row-major order.
+    fn decode_slice(
+        &self,
+        data: &[u8],
+        blocks_x: usize,
+        blocks_y: usize,
+    ) -> io::Result<Vec<(Endpoint, [u8; 4])>> {
+        let tables = &self.tables;
+        let total_blocks = blocks_x * blocks_y;
+        let history_first = self.selectors.len() as u32;
+        let history_rle = history_first + tables.selector_history as u32;
+
+        let mut reader = BitReader::new(data);
+        let mut history = SelectorHistory::new(tables.selector_history);
+        let mut selector_run = 0;
+        // Per row parity: each block's endpoint and, on even rows, the
+        // prediction bits left for the odd row below.
+        let mut rows = [
+            vec![(0usize, 0u32); blocks_x],
+            vec![(0usize, 0u32); blocks_x],
+        ];
+        let mut pred_bits = 0;
+        let mut previous_pred = 0;
+        let mut pred_repeat = 0;
+        let mut previous_endpoint = 0;
+        let mut blocks = Vec::with_capacity(total_blocks);
+
+        for block_y in 0..blocks_y {
+            let row = block_y & 1;
+            for block_x in 0..blocks_x {
+                // One symbol predicts a 2x2 group of blocks, 2 bits each.
+                if block_x & 1 == 0 && row == 0 {
+                    if pred_repeat > 0 {
+                        pred_repeat -= 1;
+                        pred_bits = previous_pred;
+                    } else {
+                        pred_bits = reader.symbol(&tables.endpoint_pred)?;
+                        if pred_bits == ENDPOINT_PRED_REPEAT {
+                            pred_repeat = reader.vlc(ENDPOINT_PRED_COUNT_VLC_BITS)?
+                                + ENDPOINT_PRED_MIN_REPEAT
+                                - 1;
+                            pred_bits = previous_pred;
+                        } else {
+                            previous_pred = pred_bits;
+                        }
+                    }
+                    rows[row ^ 1][block_x].1 = pred_bits >> 4;
+                } else if block_x & 1 == 0 {
+                    pred_bits = rows[row][block_x].1;
+                }
+                let pred = pred_bits & 3;
+                pred_bits >>= 2;
+
+                let endpoint = match pred {
+                    0 if block_x > 0 => previous_endpoint,
+                    1 if block_y > 0 => rows[row ^ 1][block_x].0,
+                    2 if block_x > 0 && block_y > 0 => rows[row ^ 1][block_x - 1].0,
+                    3 => {
+                        let mut endpoint =
+                            previous_endpoint + reader.symbol(&tables.endpoint_delta)? as usize;
+                        if endpoint >= self.endpoints.len() {
+                            endpoint -= self.endpoints.len();
+                        }
+                        endpoint
+                    }
+                    _ => return Err(invalid("BasisLZ endpoint predicted from outside the image")),
+                };
+                if endpoint >= self.endpoints.len() {
+                    return Err(invalid("BasisLZ endpoint index is out of range"));
+                }
+                rows[row][block_x].0 = endpoint;
+                previous_endpoint = endpoint;
+
+                let selector = if selector_run > 0 {
+                    selector_run -= 1;
+                    history.get(0)?
+                } else {
+                    match reader.symbol(&tables.selector)? {
+                        symbol if symbol == history_rle => {
+                            let run = reader.symbol(&tables.selector_rle)?;
+                            selector_run = if run == SELECTOR_RLE_SYMBOLS - 1 {
+                                reader.vlc(7)? + SELECTOR_RLE_MIN
+                            } else {
+                                run + SELECTOR_RLE_MIN
+                            } as usize;
+                            if selector_run > total_blocks {
+                                return Err(invalid("BasisLZ selector run is too long"));
+                            }
+                            selector_run -= 1;
+                            history.get(0)?
+                        }
+                        symbol if symbol >= history_first => {
+                            let index = (symbol - history_first) as usize;
+                            let selector = history.get(index)?;
+                            history.use_index(index);
+                            selector
+                        }
+                        symbol => {
+                            let selector = symbol as usize;
+                            if selector >= self.selectors.len() {
+                                return Err(invalid("BasisLZ selector index is out of range"));
+                            }
+                            history.add(selector);
+                            selector
+                        }
+                    }
+                };
+                blocks.push((self.endpoints[endpoint], self.selectors[selector]));
+            }
+        }
+        reader.finish()?;
+        Ok(blocks)
+    }
+}
+
+/// The four colors a block's selectors pick from, as RGBA.
+fn block_colors(endpoint: Endpoint) -> [[u8; 4]; 4] {
+    let base = endpoint
+        .color
+        .map(|channel| (channel << 3 | channel >> 2) as i32);
+    INTENSITIES[endpoint.intensity as usize]
+        .map(|modifier| base.map(|channel| (channel + modifier).clamp(0, 255) as u8))
+        .map(|[r, g, b]| [r, g, b, 255])
+}
+
+fn selector_at(selector: [u8; 4], x: usize, y: usize) -> usize {
+    (selector[y] >> (2 * x) & 3) as usize
+}
+
+/// The block as ETC1 in differential mode with a zero delta, so both halves
+/// share the endpoint.
+fn etc1_block(endpoint: Endpoint, selector: [u8; 4]) -> [u8; 8] {
+    let [r, g, b] = endpoint.color.map(|channel| channel << 3);
+    let table = endpoint.intensity;
+    let (mut msb, mut lsb) = (0u16, 0u16);
+    for y in 0..4 {
+        for x in 0..4 {
+            // Pixels are numbered down the columns.
+            let index = ETC1_PIXEL_INDICES[selector_at(selector, x, y)];
+            let bit = x * 4 + y;
+            msb |= (index >> 1) << bit;
+            lsb |= (index & 1) << bit;
+        }
+    }
+    let [msb_hi, msb_lo] = msb.to_be_bytes();
+    let [lsb_hi, lsb_lo] = lsb.to_be_bytes();
+    [
+        r,
+        g,
+        b,
+        table << 5 | table << 2 | 0b10,
+        msb_hi,
+        msb_lo,
+        lsb_hi,
+        lsb_lo,
+    ]
+}
+
+fn decode_endpoints(data: &[u8], count: usize) -> io::Result<Vec<Endpoint>> {
+    let mut reader = BitReader::new(data);
+    // Each channel's delta is coded by one of three models, picked by how
+    // dark the previous endpoint's channel was.
+    let color_models = [reader.huffman()?, reader.huffman()?, reader.huffman()?];
+    let intensity_model = reader.huffman()?;
+    let grayscale = reader.bits(1) == 1;
+
+    let mut color = [16u32; 3];
+    let mut intensity = 0;
+    let mut endpoints = Vec::with_capacity(count);
+    for _ in 0..count {
+        intensity = (intensity + reader.symbol(&intensity_model)?) & 7;
+        for channel in &mut color[..if grayscale { 1 } else { 3 }] {
+            let model = match *channel {
+                0..=9 => &color_models[0],
+                10..=20 => &color_models[1],
+                _ => &color_models[2],
+            };
+            *channel = (*channel + reader.symbol(model)?) & 31;
+        }
+        if grayscale {
+            color = [color[0]; 3];
+        }
+        endpoints.push(Endpoint {
+            color: color.map(|channel| channel as u8),
+            intensity: intensity as u8,
+        });
+    }
+    reader.finish()?;
+    Ok(endpoints)
+}
+
+fn decode_selectors(data: &[u8], count: usize) -> io::Result<Vec<[u8; 4]>> {
+    let mut reader = BitReader::new(data);
+    if reader.bits(1) == 1 {
+        return Err(invalid(
+            "BasisLZ global selector codebooks are not supported",
+        ));
+    }
+    if reader.bits(1) == 1 {
+        return Err(invalid(
+            "BasisLZ hybrid selector codebooks are not supported",
+        ));
+    }
+    let raw = reader.bits(1) == 1;
+    let delta_model = if raw { None } else { Some(reader.huffman()?) };
+
+    let mut rows = [0u8; 4];
+    let mut selectors = Vec::with_capacity(count);
+    for index in 0..count {
+        for row in &mut rows {
+            // Rows are coded as deltas from the previous pattern's.
+            *row = match &delta_model {
+                Some(model) if index > 0 => *row ^ reader.symbol(model)? as u8,
+                _ => reader.bits(8) as u8,
+            };
+        }
+        selectors.push(rows);
+    }
+    reader.finish()?;
+    Ok(selectors)
+}
+
+fn decode_tables(data: &[u8]) -> io::Result<SliceTables> {
+    let mut reader = BitReader::new(data);
+    let tables = SliceTables {
+        endpoint_pred: reader.huffman()?,
+        endpoint_delta: reader.huffman()?,
+        selector: reader.huffman()?,
+        selector_rle: reader.huffman()?,
+        selector_history: reader.bits(13) as usize,
+    };
+    reader.finish()?;
+    Ok(tables)
+}
+
+/// Recently used selector patterns, nudged towards the front as they are
+/// reused.
+struct SelectorHistory {
+    values: Vec<usize>,
+    rover: usize,
+}
+
+impl SelectorHistory {
+    fn new(size: usize) -> Self {
+        Self {
+            values: vec![0; size],
+            rover: size / 2,
+        }
+    }
+
+    fn get(&self, index: usize) -> io::Result<usize> {
+        self.values
+            .get(index)
+            .copied()
+            .ok_or_else(|| invalid("BasisLZ selector history index is out of range"))
+    }
+
+    fn add(&mut self, value: usize) {
+        if self.values.is_empty() {
+            return;
+        }
+        self.values[self.rover] = value;
+        self.rover += 1;
+        if self.rover == self.values.len() {
+            self.rover = self.values.len() / 2;
+        }
+    }
+
+    fn use_index(&mut self, index: usize) {
+        if index > 0 {
+            self.values.swap(index / 2, index);
+        }
+    }
+}
+
+/// Canonical Huffman code, read first bit first as its most significant.
+struct Huffman {
+    /// Codes of each length.
+    counts: [u16; MAX_CODE_SIZE + 1],
+    /// Symbols ordered by code length, then value.
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(sizes: &[u8]) -> io::Result<Self> {
+        let mut counts = [0u16; MAX_CODE_SIZE + 1];
+        for &size in sizes {
+            let count = counts
+                .get_mut(size as usize)
+                .ok_or_else(|| invalid("Huffman code is too long"))?;
+            *count += 1;
+        }
+        counts[0] = 0;
+        let mut left = 1i32;
+        for &count in &counts[1..] {
+            left = (left << 1) - count as i32;
+            if left < 0 {
+                return Err(invalid("Huffman table is oversubscribed"));
+            }
+        }
+        let mut symbols: Vec<u16> = (0..sizes.len() as u16)
+            .filter(|&symbol| sizes[symbol as usize] > 0)
+            .collect();
+        symbols.sort_by_key(|&symbol| sizes[symbol as usize]);
+        Ok(Self { counts, symbols })
+    }
+}
+
+/// Reads bits least significant first, as BasisLZ streams are written.
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    buffer: u64,
+    buffered: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self {
+            bytes,
+            position: 0,
+            buffer: 0,
+            buffered: 0,
+        }
+    }
+
+    /// The next `count` (at most 32) bits; zeros past the end, which
+    /// [`BitReader::finish`] reports.
+    fn bits(&mut self, count: u32) -> u32 {
+        while self.buffered < count {
+            let byte = self.bytes.get(self.position).copied().unwrap_or(0);
+            self.position += 1;
+            self.buffer |= (byte as u64) << self.buffered;
+            self.buffered += 8;
+        }
+        let value = self.buffer & ((1 << count) - 1);
+        self.buffer >>= count;
+        self.buffered -= count;
+        value as u32
+    }
+
+    /// A number written in `chunk_bits` pieces, each followed by a bit that
+    /// says whether another piece follows.
+    fn vlc(&mut self, chunk_bits: u32) -> io::Result<u32> {
+        let mut value = 0u32;
+        let mut shift = 0;
+        loop {
+            let chunk = self.bits(chunk_bits + 1);
+            value |= (chunk & ((1 << chunk_bits) - 1)) << shift;
+            if chunk >> chunk_bits == 0 {
+                return Ok(value);
+            }
+            shift += chunk_bits;
+            if shift >= 32 {
+                return Err(invalid("BasisLZ variable-length number is too long"));
+            }
+        }
+    }
+
+    fn symbol(&mut self, table: &Huffman) -> io::Result<u32> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for &count in &table.counts[1..] {
+            code |= self.bits(1) as i32;
+            let count = count as i32;
+            if code - first < count {
+                return Ok(table.symbols[(index + code - first) as usize] as u32);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(invalid("invalid Huffman code"))
+    }
+
+    /// A Huffman table, its code sizes themselves Huffman-coded.
+    fn huffman(&mut self) -> io::Result<Huffman> {
+        let symbol_count = self.bits(14) as usize;
+        if symbol_count == 0 {
+            return Huffman::new(&[]);
+        }
+        let code_length_count = self.bits(5) as usize;
+        if !(1..=CODE_LENGTH_ORDER.len()).contains(&code_length_count) {
+            return Err(invalid("Huffman table has a bad code length count"));
+        }
+        let mut code_length_sizes = [0u8; CODE_LENGTH_ORDER.len()];
+        for &code in &CODE_LENGTH_ORDER[..code_length_count] {
+            code_length_sizes[code] = self.bits(3) as u8;
+        }
+        let code_lengths = Huffman::new(&code_length_sizes)?;
+
+        let mut sizes = vec![0u8; symbol_count];
+        let mut filled = 0;
+        while filled < symbol_count {
+            match self.symbol(&code_lengths)? {
+                size @ 0..=16 => {
+                    sizes[filled] = size as u8;
+                    filled += 1;
+                }
+                SMALL_ZERO_RUN => filled += self.bits(3) as usize + 3,
+                BIG_ZERO_RUN => filled += self.bits(7) as usize + 11,
+                code => {
+                    let run = if code == SMALL_REPEAT {
+                        self.bits(2) + 3
+                    } else {
+                        self.bits(7) + 7
+                    } as usize;
+                    let previous = match filled.checked_sub(1).map(|last| sizes[last]) {
+                        Some(size) if size > 0 => size,
+                        _ => return Err(invalid("Huffman table repeats a missing code size")),
+                    };
+                    let run = sizes
+                        .get_mut(filled..filled + run)
+                        .ok_or_else(|| invalid("Huffman table runs past its symbols"))?;
+                    run.fill(previous);
+                    filled += run.len();
+                }
+            }
+        }
+        if filled != symbol_count {
+            return Err(invalid("Huffman table runs past its symbols"));
+        }
+        Huffman::new(&sizes)
+    }
+
+    /// Fails if reading ran past the end of the data.
+    fn finish(&self) -> io::Result<()> {
+        if self.position > self.bytes.len() {
+            return Err(invalid("BasisLZ data is truncated"));
+        }
+        Ok(())
+    }
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes(bytes[offset..offset + 2].try_into().unwrap())
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+fn invalid(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes bits least significant first, mirroring `BitReader`.
+    #[derive(Default)]
+    struct BitWriter {
+        bytes: Vec<u8>,
+        bits: usize,
+    }
+
+    impl BitWriter {
+        fn push(&mut self, value: u32, count: u32) {
+            for bit in 0..count {
+                if self.bits.is_multiple_of(8) {
+                    self.bytes.push(0);
+                }
+                self.bytes[self.bits / 8] |= ((value >> bit & 1) as u8) << (self.bits % 8);
+                self.bits += 1;
+            }
+        }
+
+        /// A table whose only code, a single 0 bit, is `symbol`.
+        fn single_symbol_table(&mut self, symbol: u32) {
+            self.push(symbol + 1, 14);
+            // Code sizes 0 and 1 get 1-bit codes, every other none.
+            self.push(19, 5);
+            for code in &CODE_LENGTH_ORDER[..19] {
+                self.push(u32::from(*code <= 1), 3);
+            }
+            for used in 0..=symbol {
+                self.push(u32::from(used == symbol), 1);
+            }
+        }
+    }
+
+    /// Two endpoints, two selector patterns, and tables that code every
+    /// block as a delta of 1 from the previous endpoint, or its left
+    /// neighbour's, with selector pattern 0.
+    fn global_data(image: (u32, u32)) -> Vec<u8> {
+        let mut endpoints = BitWriter::default();
+        for _ in 0..3 {
+            endpoints.single_symbol_table(4);
+        }
+        endpoints.single_symbol_table(2);
+        endpoints.push(0, 1);
+        // Every symbol is the single 0 bit: each endpoint steps the
+        // intensity by 2 and each channel by 4 from the last.
+        endpoints.push(0, 8);
+
+        let mut selectors = BitWriter::default();
+        selectors.push(0b100, 3);
+        for row in [0b11_10_01_00; 4].into_iter().chain([0xff; 4]) {
+            selectors.push(row, 8);
+        }
+
+        let mut tables = BitWriter::default();
+        // Left neighbour over a delta, from the low bits up.
+        tables.single_symbol_table(0b00_11);
+        tables.single_symbol_table(1);
+        tables.single_symbol_table(0);
+        tables.push(0, 14);
+        tables.push(0, 13);
+
+        let mut data = Vec::new();
+        data.extend_from_slice(&2u16.to_le_bytes());
+        data.extend_from_slice(&2u16.to_le_bytes());
+        for section in [&endpoints, &selectors, &tables] {
+            data.extend_from_slice(&(section.bytes.len() as u32).to_le_bytes());
+        }
+        data.extend_from_slice(&0u32.to_le_bytes());
+        for word in [0, image.0, image.1, 0, 0] {
+            data.extend_from_slice(&word.to_le_bytes());
+        }
+        for section in [endpoints, selectors, tables] {
+            data.extend_from_slice(&section.bytes);
+        }
+        data
+    }
+
+    #[test]
+    fn etc1s_slices_transcode_to_rgba_and_etc1() {
+        // Both blocks of a 6x4 image, three symbols of one 0 bit each.
+        let slice = [0u8];
+        let basis = BasisLz::parse(&global_data((0, 1)), 1).unwrap();
+        assert!(!basis.has_alpha());
+
+        let pixels = basis
+            .transcode(0, &slice, 6, 4, Etc1sTarget::Rgba8)
+            .unwrap();
+        assert_eq!(pixels.len(), 6 * 4 * 4);
+        // Endpoint 1: color 24 (198 in 8 bits) with intensity table 4; the
+        // selectors step through the table across each block's columns.
+        let row: Vec<u8> = pixels[..6 * 4].chunks(4).map(|pixel| pixel[0]).collect();
+        assert_eq!(row, [138, 180, 216, 255, 138, 180]);
+        assert!(
+            pixels
+                .chunks(4)
+                .all(|pixel| pixel[0] == pixel[2] && pixel[3] == 255)
+        );
+
+        let blocks = basis.transcode(0, &slice, 6, 4, Etc1sTarget::Etc1).unwrap();
+        let block = [
+            192,
+            192,
+            192,
+            4 << 5 | 4 << 2 | 0b10,
+            0x00,
+            0xff,
+            0xf0,
+            0x0f,
+        ];
+        assert_eq!(blocks, [block, block].concat());
+    }
+
+    #[test]
+    fn damaged_etc1s_data_is_rejected() {
+        let data = global_data((0, 1));
+        assert!(BasisLz::parse(&data[..data.len() - 1], 1).is_err());
+        assert!(BasisLz::parse(&data, 2).is_err());
+
+        let basis = BasisLz::parse(&data, 1).unwrap();
+        // The slice runs out of bits, and lies past the end of its level.
+        assert!(basis.transcode(0, &[], 6, 4, Etc1sTarget::Rgba8).is_err());
+        let basis = BasisLz::parse(&global_data((1, 1)), 1).unwrap();
+        assert!(basis.transcode(0, &[0], 6, 4, Etc1sTarget::Rgba8).is_err());
+    }
+}
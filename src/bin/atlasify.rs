@@ -3,27 +3,46 @@ use std::fs;
 use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 
-use image::GenericImageView;
+use image::{GenericImageView, Rgba, RgbaImage};
+
+const USAGE: &str =
+    "Usage: atlasify <input.png|tile_dir> <output.json> [tile_size] [--padding <pixels>]";
 
 fn main() -> io::Result<()> {
-    let args: Vec<String> = env::args().collect();
-    if args.len() < 3 || args.len() > 4 {
-        eprintln!("Usage: atlasify <input.png> <output.json> [tile_size]");
+    let mut args = Vec::new();
+    let mut padding = 0u32;
+    let mut raw_args = env::args().skip(1);
+    while let Some(arg) = raw_args.next() {
+        if arg == "--padding" || arg == "--extrude" {
+            padding = raw_args
+                .next()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or_else(|| {
+                    eprintln!("{arg} expects a non-negative pixel count");
+                    std::process::exit(1);
+                });
+        } else {
+            args.push(arg);
+        }
+    }
+
+    if args.len() < 2 || args.len() > 3 {
+        eprintln!("{USAGE}");
         std::process::exit(1);
     }
 
-    let input_path = Path::new(&args[1]);
-    let output_path = Path::new(&args[2]);
-    let tile_size: u32 = if args.len() == 4 {
-        args[3].parse().unwrap_or_else(|_| {
+    let input_path = Path::new(&args[0]);
+    let output_path = Path::new(&args[1]);
+    let tile_size: Option<u32> = if args.len() == 3 {
+        Some(args[2].parse().unwrap_or_else(|_| {
             eprintln!("Tile size must be a positive integer");
             std::process::exit(1);
-        })
+        }))
     } else {
-        16
+        None
     };
 
-    if tile_size == 0 {
+    if tile_size == Some(0) {
         eprintln!("Tile size must be greater than zero");
         std::process::exit(1);
     }
@@ -33,6 +52,23 @@ fn main() -> io::Result<()> {
         std::process::exit(1);
     }
 
+    if let Some(parent) = output_path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        fs::create_dir_all(parent)?;
+    }
+
+    if input_path.is_dir() {
+        pack_directory(input_path, output_path, tile_size, padding)
+    } else if padding > 0 {
+        pad_sheet(input_path, output_path, tile_size.unwrap_or(16), padding)
+    } else {
+        describe_sheet(input_path, output_path, tile_size.unwrap_or(16))
+    }
+}
+
+/// Writes metadata for an existing, pre-arranged tile sheet.
+fn describe_sheet(input_path: &Path, output_path: &Path, tile_size: u32) -> io::Result<()> {
     let texture_name = input_path
         .file_name()
         .and_then(|f| f.to_str())
@@ -43,31 +79,13 @@ fn main() -> io::Result<()> {
             )
         })?;
 
-    if let Some(parent) = output_path.parent()
-        && !parent.as_os_str().is_empty()
-    {
-        fs::create_dir_all(parent)?;
-    }
-
-    let target_texture_path = output_path
-        .parent()
-        .map(Path::to_path_buf)
-        .unwrap_or_else(|| PathBuf::from("."))
-        .join(texture_name);
+    let target_texture_path = output_dir(output_path).join(texture_name);
 
     if target_texture_path != input_path {
         fs::copy(input_path, &target_texture_path)?;
     }
 
-    let image = image::open(&target_texture_path).map_err(|err| {
-        io::Error::new(
-            io::ErrorKind::InvalidInput,
-            format!(
-                "failed to open image {}: {err}",
-                target_texture_path.display()
-            ),
-        )
-    })?;
+    let image = open_image(&target_texture_path)?;
     let (width, height) = image.dimensions();
 
     if width % tile_size != 0 || height % tile_size != 0 {
@@ -82,13 +100,7 @@ fn main() -> io::Result<()> {
         "texture": texture_name,
         "tile_size": tile_size,
     });
-
-    let mut file = fs::File::create(output_path)?;
-    writeln!(
-        file,
-        "{}\n",
-        serde_json::to_string_pretty(&metadata).unwrap()
-    )?;
+    write_metadata(output_path, &metadata)?;
 
     println!(
         "Wrote metadata {} (tiles: {} x {})",
@@ -99,3 +111,231 @@ fn main() -> io::Result<()> {
 
     Ok(())
 }
+
+/// Re-lays out a tile sheet with extruded gutters, keeping each tile's grid position.
+fn pad_sheet(
+    input_path: &Path,
+    output_path: &Path,
+    tile_size: u32,
+    padding: u32,
+) -> io::Result<()> {
+    let sheet = open_image(input_path)?.to_rgba8();
+    let (width, height) = sheet.dimensions();
+    if width % tile_size != 0 || height % tile_size != 0 {
+        eprintln!(
+            "Image dimensions {}x{} are not divisible by tile size {}",
+            width, height, tile_size
+        );
+        std::process::exit(1);
+    }
+
+    let texture_path = atlas_texture_path(output_path)?;
+    if texture_path == input_path {
+        eprintln!(
+            "Padded output would overwrite the source sheet {}; choose a different output name",
+            input_path.display()
+        );
+        std::process::exit(1);
+    }
+
+    let columns = width / tile_size;
+    let rows = height / tile_size;
+    let stride = tile_size + padding * 2;
+    let mut atlas = RgbaImage::new(columns * stride, rows * stride);
+    for y in 0..rows {
+        for x in 0..columns {
+            let tile = image::imageops::crop_imm(
+                &sheet,
+                x * tile_size,
+                y * tile_size,
+                tile_size,
+                tile_size,
+            )
+            .to_image();
+            blit_extruded(&mut atlas, &tile, x * stride, y * stride, padding);
+        }
+    }
+
+    save_atlas(&atlas, &texture_path)?;
+    let metadata = serde_json::json!({
+        "texture": file_name(&texture_path)?,
+        "tile_size": tile_size,
+        "padding": padding,
+    });
+    write_metadata(output_path, &metadata)?;
+
+    println!(
+        "Wrote padded atlas {} ({} px gutters, tiles: {} x {}) and {}",
+        texture_path.display(),
+        padding,
+        columns,
+        rows,
+        output_path.display()
+    );
+
+    Ok(())
+}
+
+/// Packs every PNG in `input_dir` into a power-of-two atlas and writes a
+/// manifest mapping each file stem to its tile coordinates.
+fn pack_directory(
+    input_dir: &Path,
+    output_path: &Path,
+    tile_size: Option<u32>,
+    padding: u32,
+) -> io::Result<()> {
+    let mut tile_paths: Vec<PathBuf> = fs::read_dir(input_dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("png"))
+        })
+        .collect();
+    tile_paths.sort();
+
+    if tile_paths.is_empty() {
+        eprintln!("No PNG tiles found in {}", input_dir.display());
+        std::process::exit(1);
+    }
+
+    let mut tiles = Vec::with_capacity(tile_paths.len());
+    for path in &tile_paths {
+        let name = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("tile {} has no usable name", path.display()),
+                )
+            })?
+            .to_string();
+        tiles.push((name, open_image(path)?.to_rgba8()));
+    }
+
+    let tile_size = tile_size.unwrap_or_else(|| tiles[0].1.width());
+    for (name, image) in &tiles {
+        if image.dimensions() != (tile_size, tile_size) {
+            eprintln!(
+                "Tile '{}' is {}x{}; expected {}x{}",
+                name,
+                image.width(),
+                image.height(),
+                tile_size,
+                tile_size
+            );
+            std::process::exit(1);
+        }
+    }
+
+    let count = tiles.len() as u32;
+    let stride = tile_size + padding * 2;
+    let columns = ((count as f32).sqrt().ceil() as u32).next_power_of_two();
+    let rows = count.div_ceil(columns);
+    let width = (columns * stride).next_power_of_two();
+    let height = (rows * stride).next_power_of_two();
+
+    let mut atlas = RgbaImage::new(width, height);
+    let mut manifest = serde_json::Map::new();
+    for (index, (name, image)) in tiles.iter().enumerate() {
+        let x = index as u32 % columns;
+        let y = index as u32 / columns;
+        blit_extruded(&mut atlas, image, x * stride, y * stride, padding);
+        if manifest
+            .insert(name.clone(), serde_json::json!([x, y]))
+            .is_some()
+        {
+            eprintln!("Duplicate tile name '{}'", name);
+            std::process::exit(1);
+        }
+    }
+
+    let texture_path = atlas_texture_path(output_path)?;
+    save_atlas(&atlas, &texture_path)?;
+
+    let metadata = serde_json::json!({
+        "texture": file_name(&texture_path)?,
+        "tile_size": tile_size,
+        "padding": padding,
+        "tiles": manifest,
+    });
+    write_metadata(output_path, &metadata)?;
+
+    println!(
+        "Packed {} tiles into {} ({}x{}) and wrote {}",
+        count,
+        texture_path.display(),
+        width,
+        height,
+        output_path.display()
+    );
+
+    Ok(())
+}
+
+/// Copies `tile` into `atlas` with its top-left gutter corner at `(x, y)`,
+/// repeating the tile's edge pixels `padding` times on every side.
+fn blit_extruded(atlas: &mut RgbaImage, tile: &RgbaImage, x: u32, y: u32, padding: u32) {
+    let (tile_w, tile_h) = tile.dimensions();
+    let pad = padding as i64;
+    for dy in -pad..tile_h as i64 + pad {
+        for dx in -pad..tile_w as i64 + pad {
+            let src_x = dx.clamp(0, tile_w as i64 - 1) as u32;
+            let src_y = dy.clamp(0, tile_h as i64 - 1) as u32;
+            let pixel: Rgba<u8> = *tile.get_pixel(src_x, src_y);
+            atlas.put_pixel(x + (dx + pad) as u32, y + (dy + pad) as u32, pixel);
+        }
+    }
+}
+
+/// The packed texture lives next to the metadata and shares its stem.
+fn atlas_texture_path(output_path: &Path) -> io::Result<PathBuf> {
+    let stem = output_path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "output path must have a file name",
+            )
+        })?;
+    Ok(output_dir(output_path).join(format!("{stem}.png")))
+}
+
+fn file_name(path: &Path) -> io::Result<&str> {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path must have a file name"))
+}
+
+fn save_atlas(atlas: &RgbaImage, path: &Path) -> io::Result<()> {
+    atlas
+        .save(path)
+        .map_err(|err| io::Error::other(format!("failed to write atlas {}: {err}", path.display())))
+}
+
+fn open_image(path: &Path) -> io::Result<image::DynamicImage> {
+    image::open(path).map_err(|err| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("failed to open image {}: {err}", path.display()),
+        )
+    })
+}
+
+fn output_dir(output_path: &Path) -> PathBuf {
+    output_path
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+fn write_metadata(output_path: &Path, metadata: &serde_json::Value) -> io::Result<()> {
+    let mut file = fs::File::create(output_path)?;
+    writeln!(
+        file,
+        "{}\n",
+        serde_json::to_string_pretty(metadata).unwrap()
+    )
+}
@@ -1,51 +1,220 @@
 #![allow(dead_code)]
 
+#[cfg(all(feature = "alsa", target_os = "linux"))]
+#[path = "../alsa.rs"]
+mod alsa;
 #[path = "../app/state.rs"]
 mod app_state;
+#[path = "../audio.rs"]
+mod audio;
+#[path = "../basis.rs"]
+mod basis;
+#[path = "../biome.rs"]
+mod biome;
 #[path = "../block.rs"]
 mod block;
+#[path = "../block_files.rs"]
+mod block_files;
 #[path = "../camera.rs"]
 mod camera;
+#[path = "../combat.rs"]
+mod combat;
+#[path = "../commands.rs"]
+mod commands;
 #[path = "../config.rs"]
 mod config;
+#[path = "../console.rs"]
+mod console;
+#[path = "../controls_card.rs"]
+mod controls_card;
+#[path = "../coords.rs"]
+mod coords;
+#[path = "../damage_numbers.rs"]
+mod damage_numbers;
+#[path = "../death.rs"]
+mod death;
+#[path = "../debug_window.rs"]
+mod debug_window;
+#[path = "../decoration.rs"]
+mod decoration;
+#[path = "../determinism.rs"]
+mod determinism;
+#[cfg(feature = "dev-ui")]
+#[path = "../dev_ui/mod.rs"]
+mod dev_ui;
+#[path = "../dimension.rs"]
+mod dimension;
+#[path = "../entity.rs"]
+mod entity;
+#[path = "../events.rs"]
+mod events;
 #[path = "../fps.rs"]
 mod fps;
-#[path = "../hotbar.rs"]
-mod hotbar;
+#[path = "../free_camera.rs"]
+mod free_camera;
+#[path = "../gpu_trace.rs"]
+mod gpu_trace;
+#[path = "../gui/mod.rs"]
+mod gui;
+#[path = "../health.rs"]
+mod health;
 #[path = "../input.rs"]
 mod input;
+#[path = "../inventory.rs"]
+mod inventory;
+#[path = "../inventory_screen.rs"]
+mod inventory_screen;
+#[path = "../item.rs"]
+mod item;
+#[path = "../ktx2.rs"]
+mod ktx2;
+#[path = "../light.rs"]
+mod light;
+#[path = "../loading.rs"]
+mod loading;
+#[path = "../material_editor.rs"]
+mod material_editor;
+#[path = "../mining.rs"]
+mod mining;
+#[path = "../mob.rs"]
+mod mob;
+#[path = "../pathfinding.rs"]
+mod pathfinding;
+#[path = "../pause_menu.rs"]
+mod pause_menu;
 #[path = "../physics.rs"]
 mod physics;
+#[path = "../piston.rs"]
+mod piston;
+#[path = "../portal.rs"]
+mod portal;
+#[path = "../rand.rs"]
+mod rand;
 #[path = "../raycast.rs"]
 mod raycast;
 #[path = "../render/mod.rs"]
 mod render;
+#[path = "../save.rs"]
+mod save;
+#[path = "../screenshot.rs"]
+mod screenshot;
+#[path = "../sky.rs"]
+mod sky;
+#[path = "../subtitles.rs"]
+mod subtitles;
+#[cfg(test)]
+#[path = "../temp_dir.rs"]
+mod temp_dir;
+#[path = "../terrain.rs"]
+mod terrain;
 #[path = "../text.rs"]
 mod text;
 #[path = "../texture.rs"]
 mod texture;
-#[path = "../world.rs"]
+#[path = "../ticking.rs"]
+mod ticking;
+#[path = "../toasts.rs"]
+mod toasts;
+#[path = "../ui.rs"]
+mod ui;
+#[path = "../world/mod.rs"]
 mod world;
 
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
 
 use app_state::{AppState, sleep_on_main_events};
-use config::{AppConfig, KeyBindings, PresentModeSetting};
-use input::CameraController;
+use config::{AppConfig, PresentModeSetting};
+use glam::Vec3;
+use input::{CameraController, MovementKeys};
 use render::RendererKind;
+use serde::{Deserialize, Serialize};
 use winit::event::{Event, WindowEvent};
 use winit::event_loop::{ControlFlow, EventLoop};
 use winit::window::WindowBuilder;
 
+const USAGE: &str = "Usage: benchmark [--script <path.json>] [--output <results.json|results.csv>] [--baseline <old.json>] [--threshold <percent>]";
+
+/// How much slower than the baseline a metric may get before it counts as a
+/// regression, in percent.
+const DEFAULT_REGRESSION_THRESHOLD: f32 = 10.0;
+
 fn main() {
     env_logger::init();
-    run_benchmark();
+    let options = BenchmarkOptions::from_args();
+    run_benchmark(options);
+}
+
+struct BenchmarkOptions {
+    /// The camera path flown, and the file it came from if not the
+    /// built-in one.
+    script: (Option<PathBuf>, Vec<ScriptSegment>),
+    /// Where to write the per-frame samples and the summary; CSV when the
+    /// extension is `.csv`, JSON otherwise.
+    output: Option<PathBuf>,
+    /// An earlier run's JSON results to compare this run against.
+    baseline: Option<(PathBuf, BenchmarkSummary)>,
+    threshold_percent: f32,
+}
+
+impl BenchmarkOptions {
+    fn from_args() -> Self {
+        let mut options = Self {
+            script: (None, default_segments()),
+            output: None,
+            baseline: None,
+            threshold_percent: DEFAULT_REGRESSION_THRESHOLD,
+        };
+        let mut raw_args = std::env::args().skip(1);
+        while let Some(arg) = raw_args.next() {
+            let mut value = || {
+                raw_args.next().unwrap_or_else(|| {
+                    eprintln!("{arg} expects a value\n{USAGE}");
+                    std::process::exit(1);
+                })
+            };
+            match arg.as_str() {
+                "--script" => {
+                    let path = PathBuf::from(value());
+                    let segments = load_script(&path).unwrap_or_else(|err| {
+                        eprintln!("Could not read script {}: {err}", path.display());
+                        std::process::exit(1);
+                    });
+                    options.script = (Some(path), segments);
+                }
+                "--output" => options.output = Some(PathBuf::from(value())),
+                "--baseline" => {
+                    let path = PathBuf::from(value());
+                    let summary = load_baseline(&path).unwrap_or_else(|err| {
+                        eprintln!("Could not read baseline {}: {err}", path.display());
+                        std::process::exit(1);
+                    });
+                    options.baseline = Some((path, summary));
+                }
+                "--threshold" => {
+                    options.threshold_percent = value()
+                        .parse()
+                        .ok()
+                        .filter(|percent: &f32| percent.is_finite() && *percent >= 0.0)
+                        .unwrap_or_else(|| {
+                            eprintln!("--threshold expects a non-negative percentage");
+                            std::process::exit(1);
+                        });
+                }
+                _ => {
+                    eprintln!("{USAGE}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        options
+    }
 }
 
-fn run_benchmark() {
+fn run_benchmark(mut options: BenchmarkOptions) {
     let app_config = AppConfig::load();
-    let key_bindings = app_config.key_bindings.clone();
-    let mouse_sensitivity = app_config.mouse_sensitivity;
 
     let event_loop = EventLoop::new();
     let window = WindowBuilder::new()
@@ -54,8 +223,10 @@ fn run_benchmark() {
         .expect("Failed to create benchmark window");
 
     let mut app_state = pollster::block_on(AppState::new(window));
+    app_state.hide_controls_card();
 
-    let mut script = BenchmarkScript::new(key_bindings.clone());
+    let (script_path, segments) = std::mem::take(&mut options.script);
+    let mut script = BenchmarkScript::new(segments);
     let script_duration = script.total_duration();
     let padding_seconds = 2.0;
     let target_duration = Duration::from_secs_f32(script_duration + padding_seconds);
@@ -64,10 +235,13 @@ fn run_benchmark() {
     let benchmark_start = last_tick;
 
     println!(
-        "Benchmark: {:.1}s scripted path across {} segments ({} renderer).",
+        "Benchmark: {:.1}s scripted path across {} segments ({} renderer, {}).",
         target_duration.as_secs_f32(),
         script.segment_count(),
         app_state.renderer_kind().as_str(),
+        script_path.map_or("built-in script".to_string(), |path| path
+            .display()
+            .to_string()),
     );
 
     event_loop.run(move |event, _, control_flow| {
@@ -86,13 +260,35 @@ fn run_benchmark() {
             }
             Event::RedrawRequested(window_id) if window_id == app_state.window().id() => {
                 let now = Instant::now();
-                let dt = now.saturating_duration_since(last_tick).as_secs_f32();
+                // In deterministic mode the script moves in lockstep with the
+                // simulation, so every run flies the same path tick for tick.
+                let dt = app_state
+                    .fixed_timestep()
+                    .unwrap_or_else(|| now.saturating_duration_since(last_tick).as_secs_f32());
                 last_tick = now;
 
-                script.advance(dt, app_state.camera_controller_mut(), mouse_sensitivity);
+                if let Some(feet) = script.advance(dt, app_state.camera_controller_mut()) {
+                    app_state.teleport_feet(feet);
+                }
 
                 app_state.update();
 
+                let finish = |app_state: &AppState, metrics: &BenchmarkMetrics| {
+                    let summary = metrics.summary(
+                        benchmark_start.elapsed().as_secs_f32(),
+                        app_state.renderer_kind(),
+                        app_state.surface_size(),
+                        app_config.present_mode,
+                        script.segment_count(),
+                    );
+                    ControlFlow::ExitWithCode(report(
+                        metrics,
+                        summary.as_ref(),
+                        app_state.renderer_kind(),
+                        &options,
+                    ))
+                };
+
                 match app_state.render() {
                     Ok(_) => {}
                     Err(wgpu::SurfaceError::Lost) => {
@@ -100,14 +296,7 @@ fn run_benchmark() {
                     }
                     Err(wgpu::SurfaceError::OutOfMemory) => {
                         eprintln!("Render device ran out of memory; ending benchmark early.");
-                        metrics.print_summary(
-                            benchmark_start.elapsed().as_secs_f32(),
-                            app_state.renderer_kind(),
-                            app_state.surface_size(),
-                            app_config.present_mode,
-                            script.segment_count(),
-                        );
-                        *control_flow = ControlFlow::Exit;
+                        *control_flow = finish(&app_state, &metrics);
                         return;
                     }
                     Err(err) => {
@@ -117,20 +306,14 @@ fn run_benchmark() {
 
                 let timings = app_state.renderer_timings();
                 metrics.record(
+                    benchmark_start.elapsed().as_secs_f32(),
                     app_state.last_frame_seconds(),
                     app_state.chunk_count(),
                     timings,
                 );
 
                 if benchmark_start.elapsed() >= target_duration {
-                    metrics.print_summary(
-                        benchmark_start.elapsed().as_secs_f32(),
-                        app_state.renderer_kind(),
-                        app_state.surface_size(),
-                        app_config.present_mode,
-                        script.segment_count(),
-                    );
-                    *control_flow = ControlFlow::Exit;
+                    *control_flow = finish(&app_state, &metrics);
                 }
             }
             Event::MainEventsCleared => {
@@ -142,73 +325,176 @@ fn run_benchmark() {
     });
 }
 
-#[derive(Clone, Copy, Default)]
-struct MovementState {
-    forward: bool,
-    backward: bool,
-    left: bool,
-    right: bool,
-    up: bool,
-    down: bool,
-}
-
-impl MovementState {
-    fn with_forward(mut self, enabled: bool) -> Self {
-        self.forward = enabled;
-        self
-    }
-
-    fn with_backward(mut self, enabled: bool) -> Self {
-        self.backward = enabled;
-        self
-    }
+/// Prints the summary, writes the results file and compares against the
+/// baseline, as `options` ask. Returns the exit code: nonzero when the
+/// results could not be written or a metric regressed.
+fn report(
+    metrics: &BenchmarkMetrics,
+    summary: Option<&BenchmarkSummary>,
+    renderer: RendererKind,
+    options: &BenchmarkOptions,
+) -> i32 {
+    let Some(summary) = summary else {
+        println!("Benchmark finished with no recorded frames.");
+        return 0;
+    };
+    summary.print(renderer);
 
-    fn with_left(mut self, enabled: bool) -> Self {
-        self.left = enabled;
-        self
-    }
-
-    fn with_right(mut self, enabled: bool) -> Self {
-        self.right = enabled;
-        self
+    let mut code = 0;
+    if let Some(path) = &options.output {
+        match write_results(path, summary, &metrics.samples) {
+            Ok(()) => println!("Results written to {}.", path.display()),
+            Err(err) => {
+                eprintln!("Could not write results to {}: {err}", path.display());
+                code = 1;
+            }
+        }
     }
-
-    fn with_up(mut self, enabled: bool) -> Self {
-        self.up = enabled;
-        self
+    if let Some((path, baseline)) = &options.baseline {
+        if baseline.renderer != summary.renderer || baseline.resolution != summary.resolution {
+            println!(
+                "Note: the baseline ran {} @ {}x{}; comparing anyway.",
+                baseline.renderer, baseline.resolution[0], baseline.resolution[1]
+            );
+        }
+        let regressions = regressions(summary, baseline, options.threshold_percent);
+        if regressions.is_empty() {
+            println!(
+                "No regressions beyond {:.1}% against {}.",
+                options.threshold_percent,
+                path.display()
+            );
+        } else {
+            println!(
+                "Regressions beyond {:.1}% against {}:",
+                options.threshold_percent,
+                path.display()
+            );
+            for regression in &regressions {
+                println!(
+                    "- {}: {:.4} -> {:.4} ms (+{:.1}%)",
+                    regression.metric,
+                    regression.baseline_ms,
+                    regression.current_ms,
+                    regression.percent
+                );
+            }
+            code = 1;
+        }
     }
+    code
 }
 
 struct ScriptSegment {
     duration: f32,
-    movement: MovementState,
+    movement: MovementKeys,
+    /// Degrees per second.
     yaw_rate: f32,
     pitch_rate: f32,
+    /// Feet position the player is moved to as the segment starts.
+    teleport: Option<Vec3>,
 }
 
 impl ScriptSegment {
-    fn new(duration: f32, movement: MovementState, yaw_rate: f32, pitch_rate: f32) -> Self {
+    fn new(duration: f32, movement: MovementKeys, yaw_rate: f32, pitch_rate: f32) -> Self {
         Self {
             duration: duration.max(0.0),
             movement,
             yaw_rate,
             pitch_rate,
+            teleport: None,
         }
     }
 }
 
+/// A script file: `{"segments": [...]}`, flown in order.
+#[derive(Deserialize)]
+struct RawScript {
+    segments: Vec<RawSegment>,
+}
+
+#[derive(Deserialize)]
+struct RawSegment {
+    /// Seconds.
+    duration: f32,
+    /// Keys held throughout: any of `forward`, `backward`, `left`, `right`,
+    /// `up` and `down`.
+    #[serde(default)]
+    movement: Vec<String>,
+    #[serde(default)]
+    yaw_rate: f32,
+    #[serde(default)]
+    pitch_rate: f32,
+    #[serde(default)]
+    teleport: Option<[f32; 3]>,
+}
+
+/// Reads a script file's segments, rejecting ones the benchmark could not
+/// fly.
+fn load_script(path: &Path) -> io::Result<Vec<ScriptSegment>> {
+    parse_script(&fs::read_to_string(path)?)
+}
+
+fn parse_script(contents: &str) -> io::Result<Vec<ScriptSegment>> {
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+    let script: RawScript =
+        serde_json::from_str(contents).map_err(|err| invalid(err.to_string()))?;
+    if script.segments.is_empty() {
+        return Err(invalid("the script has no segments".into()));
+    }
+    script
+        .segments
+        .into_iter()
+        .enumerate()
+        .map(|(index, raw)| {
+            if !raw.duration.is_finite() || raw.duration < 0.0 {
+                return Err(invalid(format!(
+                    "segment {index} has an invalid duration {}",
+                    raw.duration
+                )));
+            }
+            if !raw.yaw_rate.is_finite() || !raw.pitch_rate.is_finite() {
+                return Err(invalid(format!("segment {index} has an invalid turn rate")));
+            }
+            let mut movement = MovementKeys::default();
+            for name in &raw.movement {
+                let key = match name.to_ascii_lowercase().as_str() {
+                    "forward" => &mut movement.forward,
+                    "backward" => &mut movement.backward,
+                    "left" => &mut movement.left,
+                    "right" => &mut movement.right,
+                    "up" => &mut movement.up,
+                    "down" => &mut movement.down,
+                    _ => {
+                        return Err(invalid(format!(
+                            "segment {index} has an unknown movement \"{name}\""
+                        )));
+                    }
+                };
+                *key = true;
+            }
+            let teleport = raw.teleport.map(Vec3::from_array);
+            if teleport.is_some_and(|feet| !feet.is_finite()) {
+                return Err(invalid(format!("segment {index} has an invalid teleport")));
+            }
+            Ok(ScriptSegment {
+                teleport,
+                ..ScriptSegment::new(raw.duration, movement, raw.yaw_rate, raw.pitch_rate)
+            })
+        })
+        .collect()
+}
+
 struct BenchmarkScript {
     segments: Vec<ScriptSegment>,
-    key_bindings: KeyBindings,
     current: usize,
     elapsed_in_segment: f32,
 }
 
 impl BenchmarkScript {
-    fn new(key_bindings: KeyBindings) -> Self {
+    fn new(segments: Vec<ScriptSegment>) -> Self {
         Self {
-            segments: default_segments(),
-            key_bindings,
+            segments,
             current: 0,
             elapsed_in_segment: 0.0,
         }
@@ -222,13 +508,21 @@ impl BenchmarkScript {
         self.segments.len()
     }
 
-    fn advance(&mut self, mut dt: f32, controller: &mut CameraController, sensitivity: f32) {
+    /// Flies the script `dt` seconds on. Returns where to teleport the
+    /// player, if a segment with a teleport started; the last one wins when
+    /// several did.
+    fn advance(&mut self, mut dt: f32, controller: &mut CameraController) -> Option<Vec3> {
+        let mut teleport = None;
         while dt > 0.0 {
             if self.current >= self.segments.len() {
-                Self::apply_movement(controller, &self.key_bindings, &MovementState::default());
+                controller.set_movement_keys(MovementKeys::default());
                 break;
             }
 
+            if self.elapsed_in_segment == 0.0 {
+                teleport = self.segments[self.current].teleport.or(teleport);
+            }
+
             let segment_duration = self.segments[self.current].duration;
             if segment_duration <= 0.0 {
                 self.current += 1;
@@ -243,19 +537,13 @@ impl BenchmarkScript {
             }
 
             let segment = &self.segments[self.current];
-            Self::apply_movement(controller, &self.key_bindings, &segment.movement);
+            controller.set_movement_keys(segment.movement);
 
             let remaining = (segment_duration - self.elapsed_in_segment).max(0.0);
             let step = dt.min(remaining);
 
             if step > 0.0 {
-                Self::apply_rotation(
-                    controller,
-                    sensitivity,
-                    segment.yaw_rate,
-                    segment.pitch_rate,
-                    step,
-                );
+                controller.turn(segment.yaw_rate * step, segment.pitch_rate * step);
                 self.elapsed_in_segment += step;
                 dt -= step;
             } else {
@@ -267,64 +555,54 @@ impl BenchmarkScript {
                 self.elapsed_in_segment = 0.0;
             }
         }
-    }
-
-    fn apply_movement(
-        controller: &mut CameraController,
-        bindings: &KeyBindings,
-        movement: &MovementState,
-    ) {
-        controller.process_keyboard(bindings.forward, movement.forward);
-        controller.process_keyboard(bindings.backward, movement.backward);
-        controller.process_keyboard(bindings.left, movement.left);
-        controller.process_keyboard(bindings.right, movement.right);
-        controller.process_keyboard(bindings.up, movement.up);
-        controller.process_keyboard(bindings.down, movement.down);
-    }
-
-    fn apply_rotation(
-        controller: &mut CameraController,
-        sensitivity: f32,
-        yaw_rate: f32,
-        pitch_rate: f32,
-        dt: f32,
-    ) {
-        if !sensitivity.is_finite() || sensitivity <= 0.0 {
-            return;
-        }
-        let yaw_delta = yaw_rate * dt;
-        let pitch_delta = pitch_rate * dt;
-        if yaw_delta.abs() < 1e-4 && pitch_delta.abs() < 1e-4 {
-            return;
-        }
-        let dx = yaw_delta / sensitivity;
-        let dy = -pitch_delta / sensitivity;
-        controller.add_mouse_delta((dx, dy), sensitivity);
+        teleport
     }
 }
 
 fn default_segments() -> Vec<ScriptSegment> {
+    let forward = MovementKeys {
+        forward: true,
+        ..MovementKeys::default()
+    };
     vec![
-        ScriptSegment::new(3.5, MovementState::default().with_forward(true), 18.0, 0.0),
+        ScriptSegment::new(3.5, forward, 18.0, 0.0),
         ScriptSegment::new(
             3.0,
-            MovementState::default().with_forward(true).with_right(true),
+            MovementKeys {
+                right: true,
+                ..forward
+            },
             26.0,
             -6.0,
         ),
         ScriptSegment::new(
             3.5,
-            MovementState::default().with_forward(true).with_up(true),
+            MovementKeys {
+                up: true,
+                ..forward
+            },
             -22.0,
             4.0,
         ),
         ScriptSegment::new(
             3.0,
-            MovementState::default().with_backward(true).with_left(true),
+            MovementKeys {
+                backward: true,
+                left: true,
+                ..MovementKeys::default()
+            },
             35.0,
             -8.0,
         ),
-        ScriptSegment::new(4.0, MovementState::default().with_up(true), 90.0, 12.0),
+        ScriptSegment::new(
+            4.0,
+            MovementKeys {
+                up: true,
+                ..MovementKeys::default()
+            },
+            90.0,
+            12.0,
+        ),
     ]
 }
 
@@ -333,11 +611,61 @@ struct BenchmarkMetrics {
     frame_times: Vec<f32>,
     chunk_counts: Vec<usize>,
     timings: TimingStats,
+    samples: Vec<FrameSample>,
+}
+
+/// One frame of the run, as the results file lists it. The render timings
+/// are missing for frames the renderer reported none for.
+#[derive(Serialize)]
+struct FrameSample {
+    /// Seconds since the benchmark started.
+    time_s: f32,
+    frame_ms: f32,
+    chunks: usize,
+    total_ms: Option<f32>,
+    scene_ms: Option<f32>,
+    uniforms_ms: Option<f32>,
+    compute_ms: Option<f32>,
+    present_ms: Option<f32>,
+    gpu_compute_ms: Option<f32>,
+    gpu_present_ms: Option<f32>,
+    voxels: Option<u32>,
+    drawn_chunks: Option<u32>,
+    culled_chunks: Option<u32>,
+    steps_per_ray: Option<f32>,
+}
+
+impl FrameSample {
+    const CSV_HEADER: &'static str = "time_s,frame_ms,chunks,total_ms,scene_ms,uniforms_ms,compute_ms,present_ms,gpu_compute_ms,gpu_present_ms,voxels,drawn_chunks,culled_chunks,steps_per_ray";
+
+    fn csv_row(&self) -> String {
+        fn cell<T: std::fmt::Display>(value: Option<T>) -> String {
+            value.map_or(String::new(), |value| value.to_string())
+        }
+        [
+            self.time_s.to_string(),
+            self.frame_ms.to_string(),
+            self.chunks.to_string(),
+            cell(self.total_ms),
+            cell(self.scene_ms),
+            cell(self.uniforms_ms),
+            cell(self.compute_ms),
+            cell(self.present_ms),
+            cell(self.gpu_compute_ms),
+            cell(self.gpu_present_ms),
+            cell(self.voxels),
+            cell(self.drawn_chunks),
+            cell(self.culled_chunks),
+            cell(self.steps_per_ray),
+        ]
+        .join(",")
+    }
 }
 
 impl BenchmarkMetrics {
     fn record(
         &mut self,
+        time: f32,
         frame_time: f32,
         chunk_count: usize,
         timings: Option<render::RenderTimings>,
@@ -349,19 +677,37 @@ impl BenchmarkMetrics {
         if let Some(timing) = timings {
             self.timings.record(timing);
         }
+        self.samples.push(FrameSample {
+            time_s: time,
+            frame_ms: frame_time * 1000.0,
+            chunks: chunk_count,
+            total_ms: timings.map(|t| t.total_ms),
+            scene_ms: timings.map(|t| t.scene_ms),
+            uniforms_ms: timings.map(|t| t.uniforms_ms),
+            compute_ms: timings.map(|t| t.compute_ms),
+            present_ms: timings.map(|t| t.present_ms),
+            gpu_compute_ms: timings.map(|t| t.gpu_compute_ms),
+            gpu_present_ms: timings.map(|t| t.gpu_present_ms),
+            voxels: timings.map(|t| t.voxels),
+            drawn_chunks: timings.map(|t| t.drawn_chunks),
+            culled_chunks: timings.map(|t| t.culled_chunks),
+            steps_per_ray: timings
+                .and_then(|t| t.traversal)
+                .map(|traversal| traversal.steps_per_ray()),
+        });
     }
 
-    fn print_summary(
+    /// The run's summary, or `None` if no frame was recorded.
+    fn summary(
         &self,
         elapsed: f32,
         renderer: RendererKind,
         resolution: (u32, u32),
         present_mode: PresentModeSetting,
         segments: usize,
-    ) {
+    ) -> Option<BenchmarkSummary> {
         if self.frame_times.is_empty() {
-            println!("Benchmark finished with no recorded frames.");
-            return;
+            return None;
         }
 
         let total_frames = self.frame_times.len();
@@ -391,35 +737,77 @@ impl BenchmarkMetrics {
             (min_c, max_c, avg_c)
         };
 
+        Some(BenchmarkSummary {
+            renderer: renderer.as_str().to_string(),
+            resolution: [resolution.0, resolution.1],
+            present_mode: present_mode.as_str().to_string(),
+            segments,
+            elapsed_s: elapsed,
+            frames: total_frames,
+            runtime_s: total_time,
+            avg_frame_ms: avg_frame * 1000.0,
+            p95_frame_ms: p95_frame * 1000.0,
+            min_frame_ms: min_frame * 1000.0,
+            max_frame_ms: max_frame * 1000.0,
+            avg_fps: average_fps,
+            chunks_avg: chunk_avg,
+            chunks_min: chunk_min,
+            chunks_max: chunk_max,
+            timings: (self.timings.samples > 0).then(|| self.timings.averages()),
+            voxels_max: self.timings.voxels_max,
+        })
+    }
+}
+
+/// What a run measured overall; the results file's `summary`, and what
+/// `--baseline` reads back from an earlier run's.
+#[derive(Serialize, Deserialize)]
+struct BenchmarkSummary {
+    renderer: String,
+    resolution: [u32; 2],
+    present_mode: String,
+    segments: usize,
+    elapsed_s: f32,
+    frames: usize,
+    /// Sum of the recorded frame times.
+    runtime_s: f32,
+    avg_frame_ms: f32,
+    p95_frame_ms: f32,
+    min_frame_ms: f32,
+    max_frame_ms: f32,
+    avg_fps: f32,
+    chunks_avg: f32,
+    chunks_min: usize,
+    chunks_max: usize,
+    /// Averages of the renderer's timings, if it reported any.
+    timings: Option<TimingAverages>,
+    voxels_max: u32,
+}
+
+impl BenchmarkSummary {
+    fn print(&self, renderer: RendererKind) {
         println!(
             "Benchmark complete: {:.1}s, {} frames, {} segments.",
-            elapsed, total_frames, segments
+            self.elapsed_s, self.frames, self.segments
         );
         println!(
             "- Renderer: {} @ {}x{} (present: {})",
-            renderer.as_str(),
-            resolution.0,
-            resolution.1,
-            present_mode_label(present_mode)
+            self.renderer, self.resolution[0], self.resolution[1], self.present_mode
         );
         println!(
             "- Frame ms: avg {:>5.4} | p95 {:>5.4} | min {:>5.4} | max {:>5.4}",
-            avg_frame * 1000.0,
-            p95_frame * 1000.0,
-            min_frame * 1000.0,
-            max_frame * 1000.0
+            self.avg_frame_ms, self.p95_frame_ms, self.min_frame_ms, self.max_frame_ms
         );
         println!(
             "- FPS: avg {:>5.1} | runtime {:.2}s",
-            average_fps, total_time
+            self.avg_fps, self.runtime_s
         );
         println!(
             "- Loaded chunks: avg {:>5.1} | min {:>3} | max {:>3}",
-            chunk_avg, chunk_min, chunk_max
+            self.chunks_avg, self.chunks_min, self.chunks_max
         );
 
-        if self.timings.samples > 0 {
-            let averages = self.timings.averages();
+        if let Some(averages) = &self.timings {
             println!(
                 "- Render timings avg ms: total {:>5.4} | prep {:>5.4} | uniforms {:>5.4} | compute {:>5.4} | present {:>5.4}",
                 averages.total,
@@ -428,16 +816,135 @@ impl BenchmarkMetrics {
                 averages.compute,
                 averages.present
             );
-            println!(
-                "- GPU timings avg ms: compute {:>5.4} | blit {:>5.4}",
-                averages.gpu_compute, averages.gpu_present
-            );
-            println!(
-                "- Voxels traced: avg {:>8.0} | max {:>8}",
-                averages.voxels_avg, self.timings.voxels_max
-            );
+            // Both stay zero without timestamp queries.
+            if averages.gpu_compute > 0.0 || averages.gpu_present > 0.0 {
+                let (compute, present) = match renderer {
+                    RendererKind::RayTraced => ("compute", "blit"),
+                    RendererKind::Rasterized => ("scene", "post"),
+                };
+                println!(
+                    "- GPU timings avg ms: {} {:>5.4} | {} {:>5.4}",
+                    compute, averages.gpu_compute, present, averages.gpu_present
+                );
+            }
+            match renderer {
+                RendererKind::RayTraced => {
+                    println!(
+                        "- Voxels traced: avg {:>8.0} | max {:>8}",
+                        averages.voxels_avg, self.voxels_max
+                    );
+                    if averages.steps_per_ray > 0.0 {
+                        println!("- Traversal: {:>5.1} steps/ray", averages.steps_per_ray);
+                    }
+                }
+                RendererKind::Rasterized => {
+                    println!(
+                        "- Chunk meshes: drawn avg {:>5.1} | culled avg {:>5.1}",
+                        averages.drawn_chunks, averages.culled_chunks
+                    );
+                }
+            }
         }
     }
+
+    /// The metrics compared against a baseline, in milliseconds where lower
+    /// is better. Zero means the run did not measure it.
+    fn compared_metrics(&self) -> [(&'static str, f32); 5] {
+        let timings = self.timings.as_ref();
+        let average = |read: fn(&TimingAverages) -> f32| timings.map_or(0.0, read);
+        [
+            ("avg frame", self.avg_frame_ms),
+            ("p95 frame", self.p95_frame_ms),
+            ("render total", average(|t| t.total)),
+            ("GPU compute", average(|t| t.gpu_compute)),
+            ("GPU present", average(|t| t.gpu_present)),
+        ]
+    }
+}
+
+/// The results file: the summary, then every frame.
+#[derive(Serialize)]
+struct BenchmarkResults<'a> {
+    summary: &'a BenchmarkSummary,
+    frames: &'a [FrameSample],
+}
+
+/// Writes the results to `path`: JSON, or CSV when the extension is `.csv`.
+/// The CSV has one row per frame, after the summary in `#` comment lines.
+fn write_results(
+    path: &Path,
+    summary: &BenchmarkSummary,
+    frames: &[FrameSample],
+) -> io::Result<()> {
+    if let Some(parent) = path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        fs::create_dir_all(parent)?;
+    }
+    let is_csv = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("csv"));
+    let mut file = io::BufWriter::new(fs::File::create(path)?);
+    if is_csv {
+        let summary = serde_json::to_value(summary).map_err(io::Error::other)?;
+        if let serde_json::Value::Object(fields) = summary {
+            for (key, value) in fields {
+                writeln!(file, "# {key}: {value}")?;
+            }
+        }
+        writeln!(file, "{}", FrameSample::CSV_HEADER)?;
+        for frame in frames {
+            writeln!(file, "{}", frame.csv_row())?;
+        }
+    } else {
+        serde_json::to_writer_pretty(&mut file, &BenchmarkResults { summary, frames })
+            .map_err(io::Error::other)?;
+        writeln!(file)?;
+    }
+    file.flush()
+}
+
+/// Reads the summary out of an earlier run's JSON results.
+fn load_baseline(path: &Path) -> io::Result<BenchmarkSummary> {
+    #[derive(Deserialize)]
+    struct Baseline {
+        summary: BenchmarkSummary,
+    }
+    let contents = fs::read_to_string(path)?;
+    let baseline: Baseline = serde_json::from_str(&contents)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    Ok(baseline.summary)
+}
+
+/// A metric that got slower than the baseline by more than the threshold.
+struct Regression {
+    metric: &'static str,
+    baseline_ms: f32,
+    current_ms: f32,
+    /// How much slower, in percent of the baseline.
+    percent: f32,
+}
+
+/// Every metric both runs measured that `current` is more than
+/// `threshold_percent` slower at than `baseline`.
+fn regressions(
+    current: &BenchmarkSummary,
+    baseline: &BenchmarkSummary,
+    threshold_percent: f32,
+) -> Vec<Regression> {
+    current
+        .compared_metrics()
+        .into_iter()
+        .zip(baseline.compared_metrics())
+        .filter(|&((_, current_ms), (_, baseline_ms))| current_ms > 0.0 && baseline_ms > 0.0)
+        .map(|((metric, current_ms), (_, baseline_ms))| Regression {
+            metric,
+            baseline_ms,
+            current_ms,
+            percent: (current_ms - baseline_ms) / baseline_ms * 100.0,
+        })
+        .filter(|regression| regression.percent > threshold_percent)
+        .collect()
 }
 
 #[derive(Default)]
@@ -452,6 +959,11 @@ struct TimingStats {
     gpu_present_ms: f64,
     voxels_total: u64,
     voxels_max: u32,
+    drawn_chunks_total: u64,
+    culled_chunks_total: u64,
+    /// Grid steps and rays over the frames that reported traversal counts.
+    traversal_steps: u64,
+    traversal_rays: u64,
 }
 
 impl TimingStats {
@@ -466,6 +978,12 @@ impl TimingStats {
         self.gpu_present_ms += timings.gpu_present_ms as f64;
         self.voxels_total = self.voxels_total.saturating_add(timings.voxels as u64);
         self.voxels_max = self.voxels_max.max(timings.voxels);
+        self.drawn_chunks_total += timings.drawn_chunks as u64;
+        self.culled_chunks_total += timings.culled_chunks as u64;
+        if let Some(traversal) = timings.traversal {
+            self.traversal_steps += traversal.steps;
+            self.traversal_rays += u64::from(traversal.rays);
+        }
     }
 
     fn averages(&self) -> TimingAverages {
@@ -482,11 +1000,14 @@ impl TimingStats {
             gpu_compute: (self.gpu_compute_ms * inv) as f32,
             gpu_present: (self.gpu_present_ms * inv) as f32,
             voxels_avg: self.voxels_total as f64 * inv,
+            drawn_chunks: (self.drawn_chunks_total as f64 * inv) as f32,
+            culled_chunks: (self.culled_chunks_total as f64 * inv) as f32,
+            steps_per_ray: (self.traversal_steps as f64 / self.traversal_rays.max(1) as f64) as f32,
         }
     }
 }
 
-#[derive(Default)]
+#[derive(Default, Serialize, Deserialize)]
 struct TimingAverages {
     total: f32,
     scene: f32,
@@ -496,12 +1017,86 @@ struct TimingAverages {
     gpu_compute: f32,
     gpu_present: f32,
     voxels_avg: f64,
+    drawn_chunks: f32,
+    culled_chunks: f32,
+    /// Zero when the renderer counted no traversal, or in results from
+    /// before it did.
+    #[serde(default)]
+    steps_per_ray: f32,
 }
 
-fn present_mode_label(mode: PresentModeSetting) -> &'static str {
-    match mode {
-        PresentModeSetting::Immediate => "immediate",
-        PresentModeSetting::Mailbox => "mailbox",
-        PresentModeSetting::VSync => "vsync",
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp_dir::TempDir;
+
+    fn summary(avg_frame_ms: f32, gpu_compute: f32) -> BenchmarkSummary {
+        let mut metrics = BenchmarkMetrics::default();
+        metrics.record(0.0, avg_frame_ms / 1000.0, 1, None);
+        let mut summary = metrics
+            .summary(
+                1.0,
+                RendererKind::Rasterized,
+                (640, 480),
+                PresentModeSetting::VSync,
+                1,
+            )
+            .unwrap();
+        summary.timings = Some(TimingAverages {
+            total: 2.0,
+            gpu_compute,
+            ..TimingAverages::default()
+        });
+        summary
+    }
+
+    #[test]
+    fn only_metrics_slower_than_the_threshold_regress() {
+        let baseline = summary(10.0, 0.0);
+        let current = summary(10.5, 4.0);
+        assert!(regressions(&current, &baseline, 10.0).is_empty());
+
+        let slower = summary(12.0, 4.0);
+        let found = regressions(&slower, &baseline, 10.0);
+        let metrics: Vec<&str> = found.iter().map(|regression| regression.metric).collect();
+        // The GPU time is new rather than slower, since the baseline has none.
+        assert_eq!(metrics, ["avg frame", "p95 frame"]);
+        assert!((found[0].percent - 20.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn script_files_teleport_as_their_segment_starts() {
+        let segments = parse_script(
+            r#"{"segments": [
+                {"duration": 1.0, "movement": ["forward", "up"], "yaw_rate": 30.0},
+                {"duration": 2.0, "teleport": [100.5, 40.0, -8.5]}
+            ]}"#,
+        )
+        .unwrap();
+        assert!(segments[0].movement.forward && segments[0].movement.up);
+        assert_eq!(segments[1].pitch_rate, 0.0);
+
+        let mut controller = CameraController::new(1.0, 1.0, config::KeyBindings::default());
+        let mut script = BenchmarkScript::new(segments);
+        assert_eq!(script.advance(0.5, &mut controller), None);
+        assert_eq!(
+            script.advance(0.75, &mut controller),
+            Some(Vec3::new(100.5, 40.0, -8.5))
+        );
+        assert_eq!(script.advance(0.5, &mut controller), None);
+
+        let unknown = r#"{"segments": [{"duration": 1.0, "movement": ["sideways"]}]}"#;
+        assert!(parse_script(unknown).is_err());
+        assert!(parse_script(r#"{"segments": []}"#).is_err());
+    }
+
+    #[test]
+    fn json_results_read_back_as_a_baseline() {
+        let dir = TempDir::new("benchmark");
+        let path = dir.path().join("results.json");
+        write_results(&path, &summary(12.0, 4.0), &[]).unwrap();
+        let baseline = load_baseline(&path).unwrap();
+        assert_eq!(baseline.avg_frame_ms, 12.0);
+        assert!(regressions(&summary(12.0, 4.0), &baseline, 0.0).is_empty());
     }
 }
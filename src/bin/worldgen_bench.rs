@@ -0,0 +1,338 @@
+//! Generates chunks with a world generator across a thread pool and reports
+//! the throughput, the allocations, and where the time goes by stage.
+//!
+//! Only world generation is used of the game modules pulled in below.
+#![allow(dead_code, unused_imports)]
+
+#[cfg(all(feature = "alsa", target_os = "linux"))]
+#[path = "../alsa.rs"]
+mod alsa;
+#[path = "../audio.rs"]
+mod audio;
+#[path = "../basis.rs"]
+mod basis;
+#[path = "../biome.rs"]
+mod biome;
+#[path = "../block.rs"]
+mod block;
+#[path = "../block_files.rs"]
+mod block_files;
+#[path = "../camera.rs"]
+mod camera;
+#[path = "../combat.rs"]
+mod combat;
+#[path = "../commands.rs"]
+mod commands;
+#[path = "../config.rs"]
+mod config;
+#[path = "../console.rs"]
+mod console;
+#[path = "../controls_card.rs"]
+mod controls_card;
+#[path = "../coords.rs"]
+mod coords;
+#[path = "../damage_numbers.rs"]
+mod damage_numbers;
+#[path = "../death.rs"]
+mod death;
+#[path = "../debug_window.rs"]
+mod debug_window;
+#[path = "../decoration.rs"]
+mod decoration;
+#[path = "../determinism.rs"]
+mod determinism;
+#[cfg(feature = "dev-ui")]
+#[path = "../dev_ui/mod.rs"]
+mod dev_ui;
+#[path = "../dimension.rs"]
+mod dimension;
+#[path = "../entity.rs"]
+mod entity;
+#[path = "../events.rs"]
+mod events;
+#[path = "../fps.rs"]
+mod fps;
+#[path = "../free_camera.rs"]
+mod free_camera;
+#[path = "../gpu_trace.rs"]
+mod gpu_trace;
+#[path = "../gui/mod.rs"]
+mod gui;
+#[path = "../health.rs"]
+mod health;
+#[path = "../input.rs"]
+mod input;
+#[path = "../inventory.rs"]
+mod inventory;
+#[path = "../inventory_screen.rs"]
+mod inventory_screen;
+#[path = "../item.rs"]
+mod item;
+#[path = "../ktx2.rs"]
+mod ktx2;
+#[path = "../light.rs"]
+mod light;
+#[path = "../loading.rs"]
+mod loading;
+#[path = "../material_editor.rs"]
+mod material_editor;
+#[path = "../mining.rs"]
+mod mining;
+#[path = "../mob.rs"]
+mod mob;
+#[path = "../pathfinding.rs"]
+mod pathfinding;
+#[path = "../pause_menu.rs"]
+mod pause_menu;
+#[path = "../physics.rs"]
+mod physics;
+#[path = "../piston.rs"]
+mod piston;
+#[path = "../portal.rs"]
+mod portal;
+#[path = "../rand.rs"]
+mod rand;
+#[path = "../raycast.rs"]
+mod raycast;
+#[path = "../render/mod.rs"]
+mod render;
+#[path = "../save.rs"]
+mod save;
+#[path = "../screenshot.rs"]
+mod screenshot;
+#[path = "../sky.rs"]
+mod sky;
+#[path = "../subtitles.rs"]
+mod subtitles;
+#[cfg(test)]
+#[path = "../temp_dir.rs"]
+mod temp_dir;
+#[path = "../terrain.rs"]
+mod terrain;
+#[path = "../text.rs"]
+mod text;
+#[path = "../texture.rs"]
+mod texture;
+#[path = "../ticking.rs"]
+mod ticking;
+#[path = "../toasts.rs"]
+mod toasts;
+#[path = "../ui.rs"]
+mod ui;
+#[path = "../world/mod.rs"]
+mod world;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use config::AppConfig;
+use coords::{ChunkPos, chunk_layers};
+use rayon::prelude::*;
+use terrain::{TerrainGenerator, TerrainPreset};
+use world::{ChunkGenerator, GenerationTimings};
+
+const USAGE: &str =
+    "Usage: worldgen_bench [--preset <name>] [--seed <n>] [--chunks <n>] [--threads <n>]";
+
+const DEFAULT_CHUNKS: usize = 2048;
+
+/// Counts every allocation the generators make, on any thread.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+static ALLOCATED_BYTES: AtomicU64 = AtomicU64::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count_allocation(layout.size());
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        count_allocation(layout.size());
+        unsafe { System.alloc_zeroed(layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count_allocation(new_size);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn count_allocation(size: usize) {
+    ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    ALLOCATED_BYTES.fetch_add(size as u64, Ordering::Relaxed);
+}
+
+fn main() {
+    env_logger::init();
+    let options = BenchOptions::from_args();
+    let terrain = TerrainGenerator::new(options.preset, options.seed);
+    let coords = chunk_coords(terrain, options.chunks);
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(options.threads)
+        .build()
+        .unwrap_or_else(|err| {
+            eprintln!("Could not start {} worker threads: {err}", options.threads);
+            std::process::exit(1);
+        });
+
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let bytes = ALLOCATED_BYTES.load(Ordering::Relaxed);
+    let started = Instant::now();
+    let timings = pool.install(|| generate(terrain, &coords));
+    let elapsed = started.elapsed();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+    let bytes = ALLOCATED_BYTES.load(Ordering::Relaxed) - bytes;
+
+    let chunks = coords.len();
+    println!(
+        "Generated {chunks} chunks of {} (seed {}) on {} thread{} in {:.2} s: {:.1} chunks/s",
+        terrain.world_name(),
+        terrain.seed,
+        options.threads,
+        if options.threads == 1 { "" } else { "s" },
+        elapsed.as_secs_f64(),
+        chunks as f64 / elapsed.as_secs_f64().max(f64::EPSILON)
+    );
+    println!(
+        "Allocations: {allocations} ({:.1} per chunk), {:.1} MiB ({:.1} KiB per chunk)",
+        allocations as f64 / chunks as f64,
+        bytes as f64 / (1024.0 * 1024.0),
+        bytes as f64 / 1024.0 / chunks as f64
+    );
+    print_stages(&timings, chunks);
+}
+
+struct BenchOptions {
+    preset: TerrainPreset,
+    seed: u64,
+    chunks: usize,
+    threads: usize,
+}
+
+impl BenchOptions {
+    /// The generator selected in config.json, unless overridden.
+    fn from_args() -> Self {
+        let config = AppConfig::load();
+        let mut options = Self {
+            preset: config.world_preset,
+            seed: config.world_seed,
+            chunks: DEFAULT_CHUNKS,
+            threads: std::thread::available_parallelism().map_or(1, |threads| threads.get()),
+        };
+        let mut raw_args = std::env::args().skip(1);
+        while let Some(arg) = raw_args.next() {
+            let mut value = || {
+                raw_args.next().unwrap_or_else(|| {
+                    eprintln!("{arg} expects a value\n{USAGE}");
+                    std::process::exit(1);
+                })
+            };
+            let invalid = |expected: &str| -> ! {
+                eprintln!("{arg} expects {expected}");
+                std::process::exit(1);
+            };
+            match arg.as_str() {
+                "--preset" => {
+                    options.preset = TerrainPreset::from_name(&value()).unwrap_or_else(|| {
+                        invalid("default, amplified, floating_islands or benchmark")
+                    });
+                }
+                "--seed" => {
+                    options.seed = value()
+                        .parse()
+                        .unwrap_or_else(|_| invalid("a whole number"));
+                }
+                "--chunks" => {
+                    options.chunks = value()
+                        .parse()
+                        .ok()
+                        .filter(|&chunks| chunks > 0)
+                        .unwrap_or_else(|| invalid("a positive chunk count"));
+                }
+                "--threads" => {
+                    options.threads = value()
+                        .parse()
+                        .ok()
+                        .filter(|&threads| threads > 0)
+                        .unwrap_or_else(|| invalid("a positive thread count"));
+                }
+                _ => {
+                    eprintln!("{USAGE}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        options
+    }
+}
+
+/// `count` chunks in a square of columns around the origin, each column
+/// with every layer the terrain generates blocks in, so the stages see the
+/// same mix of surface, underground and sky chunks a world loads. A column's
+/// layers are adjacent, so one worker usually generates all of them and
+/// plans the column's trees once, as the world does.
+fn chunk_coords(terrain: TerrainGenerator, count: usize) -> Vec<ChunkPos> {
+    let layers: Vec<i32> = chunk_layers(terrain.height_range()).rev().collect();
+    let columns = count.div_ceil(layers.len());
+    let side = (columns as f64).sqrt().ceil() as i32;
+    (0..side * side)
+        .map(|index| (index % side - side / 2, index / side - side / 2))
+        .flat_map(|(x, z)| layers.iter().map(move |&y| ChunkPos::new(x, y, z)))
+        .take(count)
+        .collect()
+}
+
+/// Generates every chunk in `coords` on the current thread pool and sums
+/// the stage timings of all workers.
+fn generate(terrain: TerrainGenerator, coords: &[ChunkPos]) -> GenerationTimings {
+    coords
+        .par_iter()
+        .fold(
+            || ChunkGenerator::new(terrain),
+            |mut generator, &coord| {
+                black_box(generator.generate(coord));
+                generator
+            },
+        )
+        .map(|generator| generator.timings)
+        .reduce(GenerationTimings::default, |a, b| GenerationTimings {
+            terrain: a.terrain + b.terrain,
+            caves: a.caves + b.caves,
+            decoration: a.decoration + b.decoration,
+        })
+}
+
+/// Each stage's time summed over the workers, per chunk, and its share of
+/// the total.
+fn print_stages(timings: &GenerationTimings, chunks: usize) {
+    let stages = [
+        ("terrain", timings.terrain),
+        ("caves", timings.caves),
+        ("decoration", timings.decoration),
+    ];
+    let total: Duration = stages.iter().map(|(_, time)| *time).sum();
+    println!(
+        "{:<12}{:>12}{:>14}{:>8}",
+        "stage", "thread-s", "us/chunk", "share"
+    );
+    for (name, time) in stages {
+        println!(
+            "{:<12}{:>12.3}{:>14.1}{:>7.1}%",
+            name,
+            time.as_secs_f64(),
+            time.as_secs_f64() * 1e6 / chunks as f64,
+            100.0 * time.as_secs_f64() / total.as_secs_f64().max(f64::EPSILON)
+        );
+    }
+}
@@ -0,0 +1,233 @@
+use std::f32::consts::PI;
+
+use crate::block::BlockKind;
+
+/// Which colormap, if any, tints a block face.
+///
+/// The atlas art is authored for temperate climates, so tints are multipliers
+/// centred on 1.0 rather than full colors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BiomeTint {
+    None,
+    Grass,
+    Foliage,
+}
+
+impl BiomeTint {
+    /// Stable id shared with the ray tracer's block metadata.
+    pub const fn gpu_id(self) -> u32 {
+        match self {
+            BiomeTint::None => 0,
+            BiomeTint::Grass => 1,
+            BiomeTint::Foliage => 2,
+        }
+    }
+}
+
+/// Columns colder than this are covered in snow.
+pub const SNOW_TEMPERATURE: f32 = 0.3;
+/// Columns between `SNOW_TEMPERATURE` and this are mountains.
+const MOUNTAIN_TEMPERATURE: f32 = 0.45;
+/// Warmer columns drier than this are desert.
+const DESERT_HUMIDITY: f32 = 0.35;
+/// Half the climate range over which terrain shapes blend into each other
+/// at a biome border, so the ground does not step there.
+const BIOME_BLEND: f32 = 0.05;
+/// Mountain ground at or above this height is bare stone.
+const MOUNTAIN_ROCK_HEIGHT: i32 = 18;
+
+/// Broad kind of land at a column, chosen by its climate. It picks the
+/// column's surface blocks, how rugged its terrain is, and what decorates it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Biome {
+    Plains,
+    Desert,
+    Mountains,
+    Snow,
+}
+
+impl Biome {
+    pub fn from_climate(climate: Climate) -> Self {
+        if climate.temperature < SNOW_TEMPERATURE {
+            Biome::Snow
+        } else if climate.temperature < MOUNTAIN_TEMPERATURE {
+            Biome::Mountains
+        } else if climate.humidity < DESERT_HUMIDITY {
+            Biome::Desert
+        } else {
+            Biome::Plains
+        }
+    }
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Biome::Plains => "Plains",
+            Biome::Desert => "Desert",
+            Biome::Mountains => "Mountains",
+            Biome::Snow => "Snow",
+        }
+    }
+
+    /// Top block of dry ground at `height`.
+    pub fn surface(self, height: i32) -> BlockKind {
+        match self {
+            Biome::Desert => BlockKind::Sand,
+            Biome::Mountains if height >= MOUNTAIN_ROCK_HEIGHT => BlockKind::Stone,
+            Biome::Plains | Biome::Mountains | Biome::Snow => BlockKind::Grass,
+        }
+    }
+
+    /// Ground in the few layers under the surface block.
+    pub fn subsurface(self) -> BlockKind {
+        match self {
+            Biome::Desert => BlockKind::Sand,
+            Biome::Plains | Biome::Mountains | Biome::Snow => BlockKind::Dirt,
+        }
+    }
+
+    const fn shape(self) -> TerrainShape {
+        match self {
+            Biome::Plains => TerrainShape {
+                base: 0.0,
+                amplitude: 1.0,
+            },
+            // Low dunes, mostly clear of the sea.
+            Biome::Desert => TerrainShape {
+                base: 1.0,
+                amplitude: 0.5,
+            },
+            Biome::Mountains => TerrainShape {
+                base: 6.0,
+                amplitude: 2.0,
+            },
+            Biome::Snow => TerrainShape {
+                base: 1.0,
+                amplitude: 1.3,
+            },
+        }
+    }
+}
+
+/// The tallest shape any blend of biomes can take: the greatest base and
+/// amplitude among them, since blend weights always sum to one.
+pub fn tallest_shape() -> TerrainShape {
+    [Biome::Plains, Biome::Desert, Biome::Mountains, Biome::Snow]
+        .into_iter()
+        .map(Biome::shape)
+        .fold(
+            TerrainShape {
+                base: 0.0,
+                amplitude: 0.0,
+            },
+            |tallest, shape| TerrainShape {
+                base: tallest.base.max(shape.base),
+                amplitude: tallest.amplitude.max(shape.amplitude),
+            },
+        )
+}
+
+/// The biome of column (`x`, `z`).
+pub fn biome_at(x: i32, z: i32) -> Biome {
+    Biome::from_climate(climate_at(x, z))
+}
+
+/// How a biome bends the terrain's height noise: the ground is raised by
+/// `base` blocks and the noise scaled by `amplitude`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TerrainShape {
+    pub base: f32,
+    pub amplitude: f32,
+}
+
+/// Terrain shape of column (`x`, `z`): its biome's, blended with the
+/// neighbouring biomes' near a border.
+///
+/// The weights follow the same thresholds as [`Biome::from_climate`], each
+/// eased over `BIOME_BLEND` on either side, and always sum to one.
+pub fn terrain_shape_at(x: i32, z: i32) -> TerrainShape {
+    let climate = climate_at(x, z);
+    let below = |value: f32, threshold: f32| {
+        1.0 - smoothstep(threshold - BIOME_BLEND, threshold + BIOME_BLEND, value)
+    };
+    let snow = below(climate.temperature, SNOW_TEMPERATURE);
+    let mountains = (1.0 - snow) * below(climate.temperature, MOUNTAIN_TEMPERATURE);
+    let warm = 1.0 - snow - mountains;
+    let desert = warm * below(climate.humidity, DESERT_HUMIDITY);
+    let plains = warm - desert;
+    let weighted = [
+        (Biome::Plains, plains),
+        (Biome::Desert, desert),
+        (Biome::Mountains, mountains),
+        (Biome::Snow, snow),
+    ];
+    weighted.into_iter().fold(
+        TerrainShape {
+            base: 0.0,
+            amplitude: 0.0,
+        },
+        |total, (biome, weight)| {
+            let shape = biome.shape();
+            TerrainShape {
+                base: total.base + shape.base * weight,
+                amplitude: total.amplitude + shape.amplitude * weight,
+            }
+        },
+    )
+}
+
+fn smoothstep(edge0: f32, edge1: f32, value: f32) -> f32 {
+    let t = ((value - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Slowly varying climate at a column; both values are in `0.0..=1.0`.
+#[derive(Clone, Copy, Debug)]
+pub struct Climate {
+    pub temperature: f32,
+    pub humidity: f32,
+}
+
+/// Samples the climate field. Mirrored by `climate_at` in
+/// `raytrace_compute.wgsl`; keep the two in sync.
+pub fn climate_at(x: i32, z: i32) -> Climate {
+    let fx = x as f32 / 96.0;
+    let fz = z as f32 / 96.0;
+    let temperature = 0.5 + 0.3 * (fx * PI * 0.5).sin() + 0.2 * (fz * PI * 0.35 + 1.3).cos();
+    let humidity = 0.5 + 0.3 * (fz * PI * 0.45).sin() + 0.2 * ((fx + fz) * PI * 0.3).cos();
+    Climate {
+        temperature: temperature.clamp(0.0, 1.0),
+        humidity: humidity.clamp(0.0, 1.0),
+    }
+}
+
+// Colormap corners as (temperature, humidity): cold, hot & dry, hot & wet.
+const GRASS_COLD: [f32; 3] = [0.78, 0.92, 0.86];
+const GRASS_DRY: [f32; 3] = [1.08, 0.96, 0.62];
+const GRASS_LUSH: [f32; 3] = [0.82, 1.05, 0.72];
+const FOLIAGE_COLD: [f32; 3] = [0.72, 0.88, 0.84];
+const FOLIAGE_DRY: [f32; 3] = [1.0, 0.9, 0.55];
+const FOLIAGE_LUSH: [f32; 3] = [0.7, 1.0, 0.62];
+
+/// Color multiplier for a tinted face at the given climate.
+///
+/// Like a triangular colormap, humidity only matters as far as it is warm
+/// enough for it to: cold columns blend towards the cold corner regardless.
+pub fn tint_color(tint: BiomeTint, climate: Climate) -> [f32; 3] {
+    let (cold, dry, lush) = match tint {
+        BiomeTint::None => return [1.0; 3],
+        BiomeTint::Grass => (GRASS_COLD, GRASS_DRY, GRASS_LUSH),
+        BiomeTint::Foliage => (FOLIAGE_COLD, FOLIAGE_DRY, FOLIAGE_LUSH),
+    };
+    let warm_weight = climate.temperature;
+    let wet_weight = climate.humidity * warm_weight;
+    let dry_weight = warm_weight - wet_weight;
+    let cold_weight = 1.0 - warm_weight;
+    std::array::from_fn(|channel| {
+        cold[channel] * cold_weight + dry[channel] * dry_weight + lush[channel] * wet_weight
+    })
+}
+
+/// Convenience for meshers: the tint of `tint` at column (`x`, `z`).
+pub fn tint_at(tint: BiomeTint, x: i32, z: i32) -> [f32; 3] {
+    tint_color(tint, climate_at(x, z))
+}
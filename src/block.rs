@@ -1,6 +1,12 @@
 use glam::IVec3;
 
+use crate::audio::SoundMaterial;
+use crate::biome::BiomeTint;
+use crate::item::{ToolTier, ToolType};
+use crate::piston;
 use crate::texture::TileId;
+use crate::ticking;
+use crate::world::World;
 
 pub type BlockId = u8;
 
@@ -11,6 +17,63 @@ pub const BLOCK_STONE: BlockId = 3;
 pub const BLOCK_LAMP: BlockId = 4;
 pub const BLOCK_GLASS: BlockId = 5;
 pub const BLOCK_METAL: BlockId = 6;
+/// Ids `BLOCK_SNOW_LAYER..=BLOCK_SNOW_LAYER + 7` hold one to eight snow layers.
+pub const BLOCK_SNOW_LAYER: BlockId = 7;
+pub const SNOW_MAX_LAYERS: u8 = 8;
+const BLOCK_SNOW_LAYER_LAST: BlockId = BLOCK_SNOW_LAYER + SNOW_MAX_LAYERS - 1;
+pub const BLOCK_FARMLAND: BlockId = 15;
+/// Ids `BLOCK_WHEAT..=BLOCK_WHEAT + WHEAT_MAX_STAGE` hold the wheat growth stages.
+pub const BLOCK_WHEAT: BlockId = 16;
+pub const WHEAT_MAX_STAGE: u8 = 3;
+const BLOCK_WHEAT_LAST: BlockId = BLOCK_WHEAT + WHEAT_MAX_STAGE;
+pub const BLOCK_SAND: BlockId = 20;
+pub const BLOCK_LEVER: BlockId = 21;
+const BLOCK_LEVER_ON: BlockId = BLOCK_LEVER + 1;
+/// Ids `BLOCK_WIRE..=BLOCK_WIRE + MAX_SIGNAL` hold wire carrying each signal strength.
+pub const BLOCK_WIRE: BlockId = 23;
+/// Strength of a signal at its source; each wire step loses one.
+pub const MAX_SIGNAL: u8 = 15;
+const BLOCK_WIRE_LAST: BlockId = BLOCK_WIRE + MAX_SIGNAL;
+pub const BLOCK_SIGNAL_LAMP: BlockId = 39;
+const BLOCK_SIGNAL_LAMP_LIT: BlockId = BLOCK_SIGNAL_LAMP + 1;
+/// Ids `BLOCK_PISTON..BLOCK_PISTON + 12` hold a piston per facing, retracted
+/// then extended.
+pub const BLOCK_PISTON: BlockId = 41;
+const BLOCK_PISTON_LAST: BlockId = BLOCK_PISTON + 11;
+/// Ids `BLOCK_PISTON_HEAD..BLOCK_PISTON_HEAD + 6` hold a piston head per facing.
+pub const BLOCK_PISTON_HEAD: BlockId = 53;
+const BLOCK_PISTON_HEAD_LAST: BlockId = BLOCK_PISTON_HEAD + 5;
+pub const BLOCK_GRAVEL: BlockId = 59;
+/// Ids `BLOCK_FLOWER..BLOCK_FLOWER + FLOWER_VARIETIES` hold each flower variety.
+pub const BLOCK_FLOWER: BlockId = 60;
+pub const FLOWER_VARIETIES: u8 = 2;
+const BLOCK_FLOWER_LAST: BlockId = BLOCK_FLOWER + FLOWER_VARIETIES - 1;
+/// Unbreakable floor at `terrain::WORLD_MIN_Y`.
+pub const BLOCK_BEDROCK: BlockId = 62;
+/// Body and head of a lurker; only drawn as part of the mob, never placed.
+pub const BLOCK_LURKER_BODY: BlockId = 63;
+pub const BLOCK_LURKER_HEAD: BlockId = 64;
+/// Drawn for dropped items that are not blocks; never placed.
+pub const BLOCK_ITEM_BUNDLE: BlockId = 65;
+/// Still water; generated up to `terrain::SEA_LEVEL`.
+pub const BLOCK_WATER: BlockId = 66;
+/// Ids `BLOCK_FLOWING_WATER..BLOCK_FLOWING_WATER + WATER_MAX_FLOW` hold water
+/// that has flowed one to `WATER_MAX_FLOW` blocks from its source.
+pub const BLOCK_FLOWING_WATER: BlockId = 67;
+/// How far water spreads sideways from a source before it stops.
+pub const WATER_MAX_FLOW: u8 = 7;
+const BLOCK_FLOWING_WATER_LAST: BlockId = BLOCK_FLOWING_WATER + WATER_MAX_FLOW - 1;
+pub const BLOCK_LOG: BlockId = 74;
+pub const BLOCK_LEAVES: BlockId = 75;
+/// Body and head of the player's model; only drawn while the free camera is
+/// away from the player, never placed.
+pub const BLOCK_PLAYER_BODY: BlockId = 76;
+pub const BLOCK_PLAYER_HEAD: BlockId = 77;
+/// Frame and glowing sheet of a portal; built by `/portal`, never placed by
+/// hand.
+pub const BLOCK_PORTAL_FRAME: BlockId = 78;
+pub const BLOCK_PORTAL: BlockId = 79;
+const BLOCK_COUNT: usize = BLOCK_PORTAL as usize + 1;
 
 #[repr(u8)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -24,10 +87,24 @@ pub enum FaceDirection {
 }
 
 impl FaceDirection {
+    /// Every direction, in index order.
+    pub const ALL: [FaceDirection; 6] = [
+        FaceDirection::NegX,
+        FaceDirection::PosX,
+        FaceDirection::NegY,
+        FaceDirection::PosY,
+        FaceDirection::NegZ,
+        FaceDirection::PosZ,
+    ];
+
     pub const fn index(self) -> usize {
         self as usize
     }
 
+    pub const fn opposite(self) -> FaceDirection {
+        FaceDirection::ALL[self.index() ^ 1]
+    }
+
     pub const fn normal(self) -> IVec3 {
         match self {
             FaceDirection::NegX => IVec3::new(-1, 0, 0),
@@ -40,6 +117,60 @@ impl FaceDirection {
     }
 }
 
+/// Geometry a block is drawn and traced with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlockShape {
+    /// An axis-aligned box filling the bottom `height` of the cell.
+    Cube,
+    /// Two crossed, alpha-tested quads along the cell diagonals (plants).
+    /// Has no collision; uses the `NegX` face tile.
+    Cross,
+    /// A see-through box that fills the cell like `Cube` but has no collision
+    /// and only shows faces where it meets something other than itself.
+    Liquid,
+}
+
+impl BlockShape {
+    /// Stable id shared with the ray tracer's block metadata.
+    pub const fn gpu_id(self) -> u32 {
+        match self {
+            BlockShape::Cube => 0,
+            BlockShape::Cross => 1,
+            BlockShape::Liquid => 2,
+        }
+    }
+}
+
+/// One edit requested by a scheduled update.
+#[derive(Clone, Copy, Debug)]
+pub enum BlockChange {
+    /// Replaces the block at a position right away.
+    Set(IVec3, BlockKind),
+    /// Slides `kind` into `to` from the neighbouring cell against `direction`
+    /// over `ticks`, drawing it in between, and places it once it arrives.
+    /// The source cell is left alone; clear it with a `Set` if needed.
+    Move {
+        to: IVec3,
+        direction: IVec3,
+        kind: BlockKind,
+        ticks: u64,
+    },
+}
+
+/// Block changes produced by a scheduled update, applied in order.
+pub type BlockChanges = Vec<BlockChange>;
+
+/// Registry hook run when a block's scheduled update comes due.
+pub type ScheduledUpdateHook = fn(&World, IVec3, BlockKind) -> BlockChanges;
+
+/// A block's response to itself or a neighbour changing: after `delay` ticks
+/// `hook` runs, provided the block is still there.
+#[derive(Clone, Copy)]
+pub struct ScheduledUpdate {
+    pub delay: u64,
+    pub hook: ScheduledUpdateHook,
+}
+
 #[derive(Clone, Copy)]
 pub struct BlockDefinition {
     pub solid: bool,
@@ -51,13 +182,50 @@ pub struct BlockDefinition {
     pub transmission: f32,
     pub ior: f32,
     pub transmission_tint: f32,
+    /// Fraction of the cell filled from the bottom; below 1.0 the block is a
+    /// partial block that neither occludes its neighbours nor fills its cell.
+    pub height: f32,
+    pub shape: BlockShape,
     pub face_tiles: [TileId; 6],
+    /// Biome colormap applied per face, in the same order as `face_tiles`.
+    pub face_tints: [BiomeTint; 6],
+    /// Alpha-test the face textures, so transparent texels leave holes.
+    pub cutout: bool,
+    /// Whether the player can break the block.
+    pub breakable: bool,
+    /// Seconds to break by hand; 0.0 breaks instantly.
+    pub hardness: f32,
+    /// Tool type that breaks the block faster.
+    pub tool: Option<ToolType>,
+    /// Lowest tier of `tool` the block drops for; `None` drops for anything.
+    pub harvest_tier: Option<ToolTier>,
+    /// How the block sounds when broken, placed, or walked on.
+    pub sound: SoundMaterial,
+    pub on_scheduled_update: Option<ScheduledUpdate>,
 }
 
 impl BlockDefinition {
     pub const fn tile_for_face(&self, face: FaceDirection) -> TileId {
         self.face_tiles[face.index()]
     }
+
+    pub const fn tint_for_face(&self, face: FaceDirection) -> BiomeTint {
+        self.face_tints[face.index()]
+    }
+
+    /// Solid and full height, so faces pressed against it can be culled.
+    pub const fn is_full_cube(&self) -> bool {
+        self.solid && self.height >= 1.0 && matches!(self.shape, BlockShape::Cube)
+    }
+
+    /// Whether entities collide with the block's box.
+    pub const fn has_collision(&self) -> bool {
+        self.solid && matches!(self.shape, BlockShape::Cube)
+    }
+
+    pub const fn is_liquid(&self) -> bool {
+        self.solid && matches!(self.shape, BlockShape::Liquid)
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -69,9 +237,69 @@ pub enum BlockKind {
     Lamp,
     Metal,
     Glass,
+    /// Thin snow cover; the layer count (1..=8) is encoded in the block id.
+    SnowLayer(u8),
+    Farmland,
+    /// Growing wheat; the stage (0..=`WHEAT_MAX_STAGE`) is encoded in the block id.
+    Wheat(u8),
+    /// Falls while there is air beneath it.
+    Sand,
+    /// Signal source the player flips; `true` when on.
+    Lever(bool),
+    /// Carries a signal whose strength (0..=`MAX_SIGNAL`) is encoded in the id.
+    Wire(u8),
+    /// Lights up while a powered wire or lever touches it; `true` when lit.
+    SignalLamp(bool),
+    /// Pushes the blocks in front of it while powered; the facing and whether
+    /// it is extended are encoded in the id.
+    Piston(FaceDirection, bool),
+    /// Front of an extended piston facing the same way.
+    PistonHead(FaceDirection),
+    /// Falls like sand; scattered in patches by world decoration.
+    Gravel,
+    /// Decorative plant on grass or dirt; the variety (0 = poppy,
+    /// 1 = dandelion) is encoded in the block id.
+    Flower(u8),
+    /// Unbreakable floor of the world.
+    Bedrock,
+    /// Lower block of a lurker's model.
+    LurkerBody,
+    /// Upper block of a lurker's model.
+    LurkerHead,
+    /// Model of a dropped item that is not a block.
+    ItemBundle,
+    /// See-through and swum through rather than stood on; a source that
+    /// water flows out of.
+    Water,
+    /// Water spread from a source; the distance flowed (1..=`WATER_MAX_FLOW`)
+    /// is encoded in the block id, and the water gets shallower with it.
+    FlowingWater(u8),
+    /// Tree trunk; placed by world decoration.
+    Log,
+    /// Tree canopy, tinted by the biome's foliage colour.
+    Leaves,
+    /// Legs and torso of the player's model.
+    PlayerBody,
+    /// Head of the player's model.
+    PlayerHead,
+    /// Rim of a portal.
+    PortalFrame,
+    /// Inside of a portal; walked into to travel to the portal it links to.
+    Portal,
 }
 
 impl BlockKind {
+    /// Every kind, in block id order.
+    pub const ALL: [BlockKind; BLOCK_COUNT] = {
+        let mut all = [BlockKind::Air; BLOCK_COUNT];
+        let mut id = 0;
+        while id < BLOCK_COUNT {
+            all[id] = BlockKind::from_id(id as BlockId);
+            id += 1;
+        }
+        all
+    };
+
     pub const fn id(self) -> BlockId {
         match self {
             BlockKind::Air => BLOCK_AIR,
@@ -81,10 +309,35 @@ impl BlockKind {
             BlockKind::Lamp => BLOCK_LAMP,
             BlockKind::Metal => BLOCK_METAL,
             BlockKind::Glass => BLOCK_GLASS,
+            BlockKind::SnowLayer(layers) => BLOCK_SNOW_LAYER + layers - 1,
+            BlockKind::Farmland => BLOCK_FARMLAND,
+            BlockKind::Wheat(stage) => BLOCK_WHEAT + stage,
+            BlockKind::Sand => BLOCK_SAND,
+            BlockKind::Lever(on) => BLOCK_LEVER + on as BlockId,
+            BlockKind::Wire(signal) => BLOCK_WIRE + signal,
+            BlockKind::SignalLamp(lit) => BLOCK_SIGNAL_LAMP + lit as BlockId,
+            BlockKind::Piston(facing, extended) => {
+                BLOCK_PISTON + facing as BlockId * 2 + extended as BlockId
+            }
+            BlockKind::PistonHead(facing) => BLOCK_PISTON_HEAD + facing as BlockId,
+            BlockKind::Gravel => BLOCK_GRAVEL,
+            BlockKind::Flower(variety) => BLOCK_FLOWER + variety,
+            BlockKind::Bedrock => BLOCK_BEDROCK,
+            BlockKind::LurkerBody => BLOCK_LURKER_BODY,
+            BlockKind::LurkerHead => BLOCK_LURKER_HEAD,
+            BlockKind::ItemBundle => BLOCK_ITEM_BUNDLE,
+            BlockKind::Water => BLOCK_WATER,
+            BlockKind::FlowingWater(distance) => BLOCK_FLOWING_WATER + distance - 1,
+            BlockKind::Log => BLOCK_LOG,
+            BlockKind::Leaves => BLOCK_LEAVES,
+            BlockKind::PlayerBody => BLOCK_PLAYER_BODY,
+            BlockKind::PlayerHead => BLOCK_PLAYER_HEAD,
+            BlockKind::PortalFrame => BLOCK_PORTAL_FRAME,
+            BlockKind::Portal => BLOCK_PORTAL,
         }
     }
 
-    pub fn from_id(id: BlockId) -> Self {
+    pub const fn from_id(id: BlockId) -> Self {
         match id {
             BLOCK_GRASS => BlockKind::Grass,
             BLOCK_DIRT => BlockKind::Dirt,
@@ -92,6 +345,40 @@ impl BlockKind {
             BLOCK_LAMP => BlockKind::Lamp,
             BLOCK_METAL => BlockKind::Metal,
             BLOCK_GLASS => BlockKind::Glass,
+            BLOCK_SNOW_LAYER..=BLOCK_SNOW_LAYER_LAST => {
+                BlockKind::SnowLayer(id - BLOCK_SNOW_LAYER + 1)
+            }
+            BLOCK_FARMLAND => BlockKind::Farmland,
+            BLOCK_WHEAT..=BLOCK_WHEAT_LAST => BlockKind::Wheat(id - BLOCK_WHEAT),
+            BLOCK_SAND => BlockKind::Sand,
+            BLOCK_LEVER => BlockKind::Lever(false),
+            BLOCK_LEVER_ON => BlockKind::Lever(true),
+            BLOCK_WIRE..=BLOCK_WIRE_LAST => BlockKind::Wire(id - BLOCK_WIRE),
+            BLOCK_SIGNAL_LAMP => BlockKind::SignalLamp(false),
+            BLOCK_SIGNAL_LAMP_LIT => BlockKind::SignalLamp(true),
+            BLOCK_PISTON..=BLOCK_PISTON_LAST => {
+                let offset = id - BLOCK_PISTON;
+                BlockKind::Piston(FaceDirection::ALL[(offset / 2) as usize], offset % 2 == 1)
+            }
+            BLOCK_PISTON_HEAD..=BLOCK_PISTON_HEAD_LAST => {
+                BlockKind::PistonHead(FaceDirection::ALL[(id - BLOCK_PISTON_HEAD) as usize])
+            }
+            BLOCK_GRAVEL => BlockKind::Gravel,
+            BLOCK_FLOWER..=BLOCK_FLOWER_LAST => BlockKind::Flower(id - BLOCK_FLOWER),
+            BLOCK_BEDROCK => BlockKind::Bedrock,
+            BLOCK_LURKER_BODY => BlockKind::LurkerBody,
+            BLOCK_LURKER_HEAD => BlockKind::LurkerHead,
+            BLOCK_ITEM_BUNDLE => BlockKind::ItemBundle,
+            BLOCK_WATER => BlockKind::Water,
+            BLOCK_FLOWING_WATER..=BLOCK_FLOWING_WATER_LAST => {
+                BlockKind::FlowingWater(id - BLOCK_FLOWING_WATER + 1)
+            }
+            BLOCK_LOG => BlockKind::Log,
+            BLOCK_LEAVES => BlockKind::Leaves,
+            BLOCK_PLAYER_BODY => BlockKind::PlayerBody,
+            BLOCK_PLAYER_HEAD => BlockKind::PlayerHead,
+            BLOCK_PORTAL_FRAME => BlockKind::PortalFrame,
+            BLOCK_PORTAL => BlockKind::Portal,
             _ => BlockKind::Air,
         }
     }
@@ -108,6 +395,65 @@ impl BlockKind {
         self.definition().tile_for_face(face)
     }
 
+    pub fn tint_for_face(self, face: FaceDirection) -> BiomeTint {
+        self.definition().tint_for_face(face)
+    }
+
+    pub fn height(self) -> f32 {
+        self.definition().height
+    }
+
+    pub fn is_full_cube(self) -> bool {
+        self.definition().is_full_cube()
+    }
+
+    pub fn has_collision(self) -> bool {
+        self.definition().has_collision()
+    }
+
+    pub fn is_liquid(self) -> bool {
+        self.definition().is_liquid()
+    }
+
+    pub fn is_breakable(self) -> bool {
+        self.definition().breakable
+    }
+
+    /// The state a block of this type is placed in, and the one picking it
+    /// selects: every snow depth, crop stage, signal state, and piston part
+    /// maps to one slot.
+    pub const fn placed_state(self) -> BlockKind {
+        match self {
+            BlockKind::Piston(..) | BlockKind::PistonHead(_) => {
+                BlockKind::Piston(FaceDirection::PosY, false)
+            }
+            BlockKind::SnowLayer(_) => BlockKind::SnowLayer(1),
+            BlockKind::Wheat(_) => BlockKind::Wheat(0),
+            BlockKind::Lever(_) => BlockKind::Lever(false),
+            BlockKind::Wire(_) => BlockKind::Wire(0),
+            BlockKind::SignalLamp(_) => BlockKind::SignalLamp(false),
+            BlockKind::FlowingWater(_) => BlockKind::Water,
+            kind => kind,
+        }
+    }
+
+    /// Turns directional blocks to face away from the clicked `face`.
+    pub const fn facing(self, face: FaceDirection) -> BlockKind {
+        match self {
+            BlockKind::Piston(_, extended) => BlockKind::Piston(face, extended),
+            kind => kind,
+        }
+    }
+
+    /// Signal strength this block feeds into adjacent wire and lamps.
+    pub const fn signal_output(self) -> u8 {
+        match self {
+            BlockKind::Lever(true) => MAX_SIGNAL,
+            BlockKind::Wire(signal) => signal,
+            _ => 0,
+        }
+    }
+
     pub const fn display_name(self) -> &'static str {
         match self {
             BlockKind::Air => "Air",
@@ -117,6 +463,32 @@ impl BlockKind {
             BlockKind::Lamp => "Lamp",
             BlockKind::Metal => "Metal",
             BlockKind::Glass => "Glass",
+            BlockKind::SnowLayer(layers) => SNOW_LAYER_NAMES[(layers - 1) as usize],
+            BlockKind::Farmland => "Farmland",
+            BlockKind::Wheat(stage) => WHEAT_NAMES[stage as usize],
+            BlockKind::Sand => "Sand",
+            BlockKind::Lever(false) => "Lever",
+            BlockKind::Lever(true) => "Lever (On)",
+            BlockKind::Wire(signal) => WIRE_NAMES[signal as usize],
+            BlockKind::SignalLamp(false) => "Signal Lamp",
+            BlockKind::SignalLamp(true) => "Signal Lamp (Lit)",
+            BlockKind::Piston(_, false) => "Piston",
+            BlockKind::Piston(_, true) => "Piston (Extended)",
+            BlockKind::PistonHead(_) => "Piston Head",
+            BlockKind::Gravel => "Gravel",
+            BlockKind::Flower(variety) => FLOWER_NAMES[variety as usize],
+            BlockKind::Bedrock => "Bedrock",
+            BlockKind::LurkerBody => "Lurker Body",
+            BlockKind::LurkerHead => "Lurker Head",
+            BlockKind::ItemBundle => "Item Bundle",
+            BlockKind::Water => "Water",
+            BlockKind::FlowingWater(distance) => FLOWING_WATER_NAMES[(distance - 1) as usize],
+            BlockKind::Log => "Log",
+            BlockKind::Leaves => "Leaves",
+            BlockKind::PlayerBody => "Player Body",
+            BlockKind::PlayerHead => "Player Head",
+            BlockKind::PortalFrame => "Portal Frame",
+            BlockKind::Portal => "Portal",
         }
     }
 }
@@ -133,8 +505,114 @@ const TILE_LAMP: TileId = TileId { x: 4, y: 0 };
 const TILE_AIR: TileId = TileId { x: 0, y: 0 };
 const TILE_GLASS: TileId = TileId { x: 5, y: 0 };
 const TILE_METAL: TileId = TileId { x: 6, y: 0 };
+const TILE_SNOW: TileId = TileId { x: 7, y: 0 };
+const TILE_FARMLAND: TileId = TileId { x: 0, y: 1 };
+const TILE_WHEAT: [TileId; WHEAT_MAX_STAGE as usize + 1] = [
+    TileId { x: 1, y: 1 },
+    TileId { x: 2, y: 1 },
+    TileId { x: 3, y: 1 },
+    TileId { x: 4, y: 1 },
+];
+const TILE_SAND: TileId = TileId { x: 5, y: 1 };
+const TILE_LEVER_OFF: TileId = TileId { x: 6, y: 1 };
+const TILE_LEVER_ON: TileId = TileId { x: 7, y: 1 };
+const TILE_WIRE_OFF: TileId = TileId { x: 0, y: 2 };
+const TILE_WIRE_ON: TileId = TileId { x: 1, y: 2 };
+const TILE_SIGNAL_LAMP_OFF: TileId = TileId { x: 2, y: 2 };
+const TILE_SIGNAL_LAMP_ON: TileId = TileId { x: 3, y: 2 };
+const TILE_PISTON_SIDE: TileId = TileId { x: 4, y: 2 };
+const TILE_PISTON_FRONT: TileId = TileId { x: 5, y: 2 };
+const TILE_PISTON_BACK: TileId = TileId { x: 6, y: 2 };
+const TILE_PISTON_OPEN: TileId = TileId { x: 7, y: 2 };
+const TILE_GRAVEL: TileId = TileId { x: 0, y: 3 };
+const TILE_FLOWER: [TileId; FLOWER_VARIETIES as usize] =
+    [TileId { x: 1, y: 3 }, TileId { x: 2, y: 3 }];
+const TILE_BEDROCK: TileId = TileId { x: 3, y: 3 };
+const TILE_LURKER_BODY: TileId = TileId { x: 4, y: 3 };
+const TILE_LURKER_HEAD: TileId = TileId { x: 5, y: 3 };
+const TILE_ITEM_BUNDLE: TileId = TileId { x: 6, y: 3 };
+const TILE_WATER: TileId = TileId { x: 7, y: 3 };
+const TILE_LOG_SIDE: TileId = TileId { x: 0, y: 4 };
+const TILE_LOG_TOP: TileId = TileId { x: 1, y: 4 };
+const TILE_LEAVES: TileId = TileId { x: 2, y: 4 };
+const TILE_PLAYER_BODY: TileId = TileId { x: 3, y: 4 };
+const TILE_PLAYER_HEAD: TileId = TileId { x: 4, y: 4 };
+const TILE_PORTAL_FRAME: TileId = TileId { x: 5, y: 4 };
+const TILE_PORTAL: TileId = TileId { x: 6, y: 4 };
+
+/// Ticks between wheat growth stages (30 seconds at 20 ticks per second).
+const WHEAT_GROWTH_TICKS: u64 = 600;
+/// Ticks a falling block takes to drop one cell.
+const FALL_TICKS: u64 = 2;
+/// Ticks for a signal change to cross one block of wire or reach a lamp.
+const SIGNAL_TICKS: u64 = 1;
+/// Ticks before a plant that lost its ground pops off.
+const UPROOT_TICKS: u64 = 1;
+/// Ticks for water to flow one block.
+const WATER_FLOW_TICKS: u64 = 5;
+
+const SNOW_LAYER_NAMES: [&str; SNOW_MAX_LAYERS as usize] = [
+    "Snow Layer 1",
+    "Snow Layer 2",
+    "Snow Layer 3",
+    "Snow Layer 4",
+    "Snow Layer 5",
+    "Snow Layer 6",
+    "Snow Layer 7",
+    "Snow Layer 8",
+];
+
+const WHEAT_NAMES: [&str; WHEAT_MAX_STAGE as usize + 1] = [
+    "Wheat Stage 1",
+    "Wheat Stage 2",
+    "Wheat Stage 3",
+    "Wheat Stage 4",
+];
+
+const FLOWER_NAMES: [&str; FLOWER_VARIETIES as usize] = ["Poppy", "Dandelion"];
+
+const FLOWING_WATER_NAMES: [&str; WATER_MAX_FLOW as usize] = [
+    "Flowing Water 1",
+    "Flowing Water 2",
+    "Flowing Water 3",
+    "Flowing Water 4",
+    "Flowing Water 5",
+    "Flowing Water 6",
+    "Flowing Water 7",
+];
+
+const WIRE_NAMES: [&str; MAX_SIGNAL as usize + 1] = [
+    "Wire",
+    "Wire (Signal 1)",
+    "Wire (Signal 2)",
+    "Wire (Signal 3)",
+    "Wire (Signal 4)",
+    "Wire (Signal 5)",
+    "Wire (Signal 6)",
+    "Wire (Signal 7)",
+    "Wire (Signal 8)",
+    "Wire (Signal 9)",
+    "Wire (Signal 10)",
+    "Wire (Signal 11)",
+    "Wire (Signal 12)",
+    "Wire (Signal 13)",
+    "Wire (Signal 14)",
+    "Wire (Signal 15)",
+];
+
+const NO_TINT: [BiomeTint; 6] = [BiomeTint::None; 6];
+const GRASS_TOP_TINT: [BiomeTint; 6] = [
+    BiomeTint::None,
+    BiomeTint::None,
+    BiomeTint::None,
+    BiomeTint::Grass,
+    BiomeTint::None,
+    BiomeTint::None,
+];
+
+const FOLIAGE_TINT: [BiomeTint; 6] = [BiomeTint::Foliage; 6];
 
-const BLOCK_DEFINITIONS: [BlockDefinition; 7] = [
+const BLOCK_DEFINITIONS: [BlockDefinition; BLOCK_COUNT] = [
     BlockDefinition {
         // Air
         solid: false,
@@ -146,7 +624,17 @@ const BLOCK_DEFINITIONS: [BlockDefinition; 7] = [
         transmission: 0.0,
         ior: 1.0,
         transmission_tint: 0.0,
+        height: 1.0,
+        shape: BlockShape::Cube,
         face_tiles: [TILE_AIR; 6],
+        face_tints: NO_TINT,
+        cutout: false,
+        breakable: true,
+        hardness: 0.0,
+        tool: None,
+        harvest_tier: None,
+        sound: SoundMaterial::Silent,
+        on_scheduled_update: None,
     },
     BlockDefinition {
         // Grass
@@ -159,6 +647,8 @@ const BLOCK_DEFINITIONS: [BlockDefinition; 7] = [
         transmission: 0.0,
         ior: 1.0,
         transmission_tint: 0.0,
+        height: 1.0,
+        shape: BlockShape::Cube,
         face_tiles: [
             TILE_GRASS_SIDE,
             TILE_GRASS_SIDE,
@@ -167,6 +657,14 @@ const BLOCK_DEFINITIONS: [BlockDefinition; 7] = [
             TILE_GRASS_SIDE,
             TILE_GRASS_SIDE,
         ],
+        face_tints: GRASS_TOP_TINT,
+        cutout: false,
+        breakable: true,
+        hardness: 0.9,
+        tool: Some(ToolType::Shovel),
+        harvest_tier: None,
+        sound: SoundMaterial::Dirt,
+        on_scheduled_update: None,
     },
     BlockDefinition {
         // Dirt
@@ -179,7 +677,17 @@ const BLOCK_DEFINITIONS: [BlockDefinition; 7] = [
         transmission: 0.0,
         ior: 1.0,
         transmission_tint: 0.0,
+        height: 1.0,
+        shape: BlockShape::Cube,
         face_tiles: [TILE_DIRT; 6],
+        face_tints: NO_TINT,
+        cutout: false,
+        breakable: true,
+        hardness: 0.75,
+        tool: Some(ToolType::Shovel),
+        harvest_tier: None,
+        sound: SoundMaterial::Dirt,
+        on_scheduled_update: None,
     },
     BlockDefinition {
         // Stone
@@ -192,7 +700,17 @@ const BLOCK_DEFINITIONS: [BlockDefinition; 7] = [
         transmission: 0.0,
         ior: 1.0,
         transmission_tint: 0.0,
+        height: 1.0,
+        shape: BlockShape::Cube,
         face_tiles: [TILE_STONE; 6],
+        face_tints: NO_TINT,
+        cutout: false,
+        breakable: true,
+        hardness: 3.0,
+        tool: Some(ToolType::Pickaxe),
+        harvest_tier: Some(ToolTier::Wooden),
+        sound: SoundMaterial::Stone,
+        on_scheduled_update: None,
     },
     BlockDefinition {
         // Lamp
@@ -205,7 +723,40 @@ const BLOCK_DEFINITIONS: [BlockDefinition; 7] = [
         transmission: 0.0,
         ior: 1.2,
         transmission_tint: 0.0,
+        height: 1.0,
+        shape: BlockShape::Cube,
         face_tiles: [TILE_LAMP; 6],
+        face_tints: NO_TINT,
+        cutout: false,
+        breakable: true,
+        hardness: 0.45,
+        tool: None,
+        harvest_tier: None,
+        sound: SoundMaterial::Glass,
+        on_scheduled_update: None,
+    },
+    BlockDefinition {
+        // Glass
+        solid: true,
+        luminance: 0.0,
+        specular: 0.06,
+        diffuse: 0.05,
+        roughness: 0.05,
+        metallic: 0.0,
+        transmission: 0.95,
+        ior: 1.45,
+        transmission_tint: 0.85,
+        height: 1.0,
+        shape: BlockShape::Cube,
+        face_tiles: [TILE_GLASS; 6],
+        face_tints: NO_TINT,
+        cutout: false,
+        breakable: true,
+        hardness: 0.45,
+        tool: None,
+        harvest_tier: None,
+        sound: SoundMaterial::Glass,
+        on_scheduled_update: None,
     },
     BlockDefinition {
         // Metal
@@ -218,19 +769,584 @@ const BLOCK_DEFINITIONS: [BlockDefinition; 7] = [
         transmission: 0.0,
         ior: 1.0,
         transmission_tint: 0.0,
+        height: 1.0,
+        shape: BlockShape::Cube,
         face_tiles: [TILE_METAL; 6],
+        face_tints: NO_TINT,
+        cutout: false,
+        breakable: true,
+        hardness: 5.0,
+        tool: Some(ToolType::Pickaxe),
+        harvest_tier: Some(ToolTier::Stone),
+        sound: SoundMaterial::Metal,
+        on_scheduled_update: None,
     },
+    snow_layer(1),
+    snow_layer(2),
+    snow_layer(3),
+    snow_layer(4),
+    snow_layer(5),
+    snow_layer(6),
+    snow_layer(7),
+    snow_layer(8),
     BlockDefinition {
-        // Glass
+        // Farmland
+        solid: true,
+        luminance: 0.0,
+        specular: 0.02,
+        diffuse: 0.8,
+        roughness: 0.9,
+        metallic: 0.0,
+        transmission: 0.0,
+        ior: 1.0,
+        transmission_tint: 0.0,
+        height: 15.0 / 16.0,
+        shape: BlockShape::Cube,
+        face_tiles: [
+            TILE_DIRT,
+            TILE_DIRT,
+            TILE_DIRT,
+            TILE_FARMLAND,
+            TILE_DIRT,
+            TILE_DIRT,
+        ],
+        face_tints: NO_TINT,
+        cutout: false,
+        breakable: true,
+        hardness: 0.9,
+        tool: Some(ToolType::Shovel),
+        harvest_tier: None,
+        sound: SoundMaterial::Dirt,
+        on_scheduled_update: None,
+    },
+    wheat(0),
+    wheat(1),
+    wheat(2),
+    wheat(3),
+    BlockDefinition {
+        // Sand
+        solid: true,
+        luminance: 0.0,
+        specular: 0.03,
+        diffuse: 0.85,
+        roughness: 0.9,
+        metallic: 0.0,
+        transmission: 0.0,
+        ior: 1.0,
+        transmission_tint: 0.0,
+        height: 1.0,
+        shape: BlockShape::Cube,
+        face_tiles: [TILE_SAND; 6],
+        face_tints: NO_TINT,
+        cutout: false,
+        breakable: true,
+        hardness: 0.75,
+        tool: Some(ToolType::Shovel),
+        harvest_tier: None,
+        sound: SoundMaterial::Dirt,
+        on_scheduled_update: Some(ScheduledUpdate {
+            delay: FALL_TICKS,
+            hook: ticking::fall,
+        }),
+    },
+    lever(false),
+    lever(true),
+    wire(0),
+    wire(1),
+    wire(2),
+    wire(3),
+    wire(4),
+    wire(5),
+    wire(6),
+    wire(7),
+    wire(8),
+    wire(9),
+    wire(10),
+    wire(11),
+    wire(12),
+    wire(13),
+    wire(14),
+    wire(15),
+    signal_lamp(false),
+    signal_lamp(true),
+    piston(FaceDirection::NegX, false),
+    piston(FaceDirection::NegX, true),
+    piston(FaceDirection::PosX, false),
+    piston(FaceDirection::PosX, true),
+    piston(FaceDirection::NegY, false),
+    piston(FaceDirection::NegY, true),
+    piston(FaceDirection::PosY, false),
+    piston(FaceDirection::PosY, true),
+    piston(FaceDirection::NegZ, false),
+    piston(FaceDirection::NegZ, true),
+    piston(FaceDirection::PosZ, false),
+    piston(FaceDirection::PosZ, true),
+    piston_head(FaceDirection::NegX),
+    piston_head(FaceDirection::PosX),
+    piston_head(FaceDirection::NegY),
+    piston_head(FaceDirection::PosY),
+    piston_head(FaceDirection::NegZ),
+    piston_head(FaceDirection::PosZ),
+    BlockDefinition {
+        // Gravel
+        solid: true,
+        luminance: 0.0,
+        specular: 0.04,
+        diffuse: 0.75,
+        roughness: 0.85,
+        metallic: 0.0,
+        transmission: 0.0,
+        ior: 1.0,
+        transmission_tint: 0.0,
+        height: 1.0,
+        shape: BlockShape::Cube,
+        face_tiles: [TILE_GRAVEL; 6],
+        face_tints: NO_TINT,
+        cutout: false,
+        breakable: true,
+        hardness: 0.9,
+        tool: Some(ToolType::Shovel),
+        harvest_tier: None,
+        sound: SoundMaterial::Dirt,
+        on_scheduled_update: Some(ScheduledUpdate {
+            delay: FALL_TICKS,
+            hook: ticking::fall,
+        }),
+    },
+    flower(0),
+    flower(1),
+    BlockDefinition {
+        // Bedrock
         solid: true,
         luminance: 0.0,
         specular: 0.06,
-        diffuse: 0.05,
-        roughness: 0.05,
+        diffuse: 0.5,
+        roughness: 0.7,
         metallic: 0.0,
-        transmission: 0.95,
-        ior: 1.45,
-        transmission_tint: 0.85,
-        face_tiles: [TILE_GLASS; 6],
+        transmission: 0.0,
+        ior: 1.0,
+        transmission_tint: 0.0,
+        height: 1.0,
+        shape: BlockShape::Cube,
+        face_tiles: [TILE_BEDROCK; 6],
+        face_tints: NO_TINT,
+        cutout: false,
+        breakable: false,
+        hardness: 0.0,
+        tool: None,
+        harvest_tier: None,
+        sound: SoundMaterial::Stone,
+        on_scheduled_update: None,
+    },
+    model_part(TILE_LURKER_BODY),
+    model_part(TILE_LURKER_HEAD),
+    model_part(TILE_ITEM_BUNDLE),
+    water(0),
+    water(1),
+    water(2),
+    water(3),
+    water(4),
+    water(5),
+    water(6),
+    water(7),
+    BlockDefinition {
+        // Log
+        solid: true,
+        luminance: 0.0,
+        specular: 0.04,
+        diffuse: 0.8,
+        roughness: 0.75,
+        metallic: 0.0,
+        transmission: 0.0,
+        ior: 1.0,
+        transmission_tint: 0.0,
+        height: 1.0,
+        shape: BlockShape::Cube,
+        face_tiles: [
+            TILE_LOG_SIDE,
+            TILE_LOG_SIDE,
+            TILE_LOG_TOP,
+            TILE_LOG_TOP,
+            TILE_LOG_SIDE,
+            TILE_LOG_SIDE,
+        ],
+        face_tints: NO_TINT,
+        cutout: false,
+        breakable: true,
+        hardness: 2.0,
+        tool: Some(ToolType::Axe),
+        harvest_tier: None,
+        sound: SoundMaterial::Wood,
+        on_scheduled_update: None,
+    },
+    BlockDefinition {
+        // Leaves
+        solid: true,
+        luminance: 0.0,
+        specular: 0.03,
+        diffuse: 0.8,
+        roughness: 0.8,
+        metallic: 0.0,
+        transmission: 0.0,
+        ior: 1.0,
+        transmission_tint: 0.0,
+        height: 1.0,
+        shape: BlockShape::Cube,
+        face_tiles: [TILE_LEAVES; 6],
+        face_tints: FOLIAGE_TINT,
+        cutout: false,
+        breakable: true,
+        hardness: 0.2,
+        tool: None,
+        harvest_tier: None,
+        sound: SoundMaterial::Dirt,
+        on_scheduled_update: None,
+    },
+    model_part(TILE_PLAYER_BODY),
+    model_part(TILE_PLAYER_HEAD),
+    BlockDefinition {
+        // Portal Frame
+        solid: true,
+        luminance: 0.0,
+        specular: 0.2,
+        diffuse: 0.5,
+        roughness: 0.3,
+        metallic: 0.0,
+        transmission: 0.0,
+        ior: 1.0,
+        transmission_tint: 0.0,
+        height: 1.0,
+        shape: BlockShape::Cube,
+        face_tiles: [TILE_PORTAL_FRAME; 6],
+        face_tints: NO_TINT,
+        cutout: false,
+        breakable: true,
+        hardness: 8.0,
+        tool: Some(ToolType::Pickaxe),
+        harvest_tier: Some(ToolTier::Iron),
+        sound: SoundMaterial::Stone,
+        on_scheduled_update: None,
+    },
+    BlockDefinition {
+        // Portal: a liquid for its looks, a see-through sheet that can be
+        // walked into and clicked through.
+        solid: true,
+        luminance: 0.75,
+        specular: 0.05,
+        diffuse: 0.6,
+        roughness: 0.1,
+        metallic: 0.0,
+        transmission: 0.6,
+        ior: 1.0,
+        transmission_tint: 0.8,
+        height: 1.0,
+        shape: BlockShape::Liquid,
+        face_tiles: [TILE_PORTAL; 6],
+        face_tints: NO_TINT,
+        cutout: false,
+        breakable: false,
+        hardness: 0.0,
+        tool: None,
+        harvest_tier: None,
+        sound: SoundMaterial::Silent,
+        on_scheduled_update: None,
     },
 ];
+
+/// A block only drawn as part of an entity's model.
+const fn model_part(tile: TileId) -> BlockDefinition {
+    BlockDefinition {
+        solid: true,
+        luminance: 0.0,
+        specular: 0.1,
+        diffuse: 0.85,
+        roughness: 0.6,
+        metallic: 0.0,
+        transmission: 0.0,
+        ior: 1.0,
+        transmission_tint: 0.0,
+        height: 1.0,
+        shape: BlockShape::Cube,
+        face_tiles: [tile; 6],
+        face_tints: NO_TINT,
+        cutout: false,
+        breakable: false,
+        hardness: 0.0,
+        tool: None,
+        harvest_tier: None,
+        sound: SoundMaterial::Silent,
+        on_scheduled_update: None,
+    }
+}
+
+/// Water `distance` blocks from its source, 0 being the source itself.
+const fn water(distance: u8) -> BlockDefinition {
+    BlockDefinition {
+        solid: true,
+        luminance: 0.0,
+        specular: 0.02,
+        diffuse: 0.15,
+        roughness: 0.05,
+        metallic: 0.0,
+        transmission: 0.9,
+        ior: 1.33,
+        transmission_tint: 0.4,
+        height: 1.0 - distance as f32 / (WATER_MAX_FLOW + 1) as f32,
+        shape: BlockShape::Liquid,
+        face_tiles: [TILE_WATER; 6],
+        face_tints: NO_TINT,
+        cutout: false,
+        breakable: false,
+        hardness: 0.0,
+        tool: None,
+        harvest_tier: None,
+        sound: SoundMaterial::Silent,
+        on_scheduled_update: Some(ScheduledUpdate {
+            delay: WATER_FLOW_TICKS,
+            hook: ticking::flow_water,
+        }),
+    }
+}
+
+const fn snow_layer(layers: u8) -> BlockDefinition {
+    BlockDefinition {
+        solid: true,
+        luminance: 0.0,
+        specular: 0.05,
+        diffuse: 0.95,
+        roughness: 0.8,
+        metallic: 0.0,
+        transmission: 0.0,
+        ior: 1.0,
+        transmission_tint: 0.0,
+        height: layers as f32 / SNOW_MAX_LAYERS as f32,
+        shape: BlockShape::Cube,
+        face_tiles: [TILE_SNOW; 6],
+        face_tints: NO_TINT,
+        cutout: false,
+        breakable: true,
+        hardness: 0.15,
+        tool: Some(ToolType::Shovel),
+        harvest_tier: None,
+        sound: SoundMaterial::Dirt,
+        on_scheduled_update: None,
+    }
+}
+
+const fn wheat(stage: u8) -> BlockDefinition {
+    BlockDefinition {
+        solid: true,
+        luminance: 0.0,
+        specular: 0.02,
+        diffuse: 0.85,
+        roughness: 0.8,
+        metallic: 0.0,
+        transmission: 0.0,
+        ior: 1.0,
+        transmission_tint: 0.0,
+        height: 1.0,
+        shape: BlockShape::Cross,
+        face_tiles: [TILE_WHEAT[stage as usize]; 6],
+        face_tints: NO_TINT,
+        cutout: true,
+        breakable: true,
+        hardness: 0.0,
+        tool: None,
+        harvest_tier: None,
+        sound: SoundMaterial::Dirt,
+        on_scheduled_update: Some(ScheduledUpdate {
+            delay: WHEAT_GROWTH_TICKS,
+            hook: ticking::grow_crop,
+        }),
+    }
+}
+
+const fn flower(variety: u8) -> BlockDefinition {
+    BlockDefinition {
+        solid: true,
+        luminance: 0.0,
+        specular: 0.02,
+        diffuse: 0.85,
+        roughness: 0.8,
+        metallic: 0.0,
+        transmission: 0.0,
+        ior: 1.0,
+        transmission_tint: 0.0,
+        height: 1.0,
+        shape: BlockShape::Cross,
+        face_tiles: [TILE_FLOWER[variety as usize]; 6],
+        face_tints: NO_TINT,
+        cutout: true,
+        breakable: true,
+        hardness: 0.0,
+        tool: None,
+        harvest_tier: None,
+        sound: SoundMaterial::Dirt,
+        on_scheduled_update: Some(ScheduledUpdate {
+            delay: UPROOT_TICKS,
+            hook: ticking::uproot,
+        }),
+    }
+}
+
+const fn lever(on: bool) -> BlockDefinition {
+    BlockDefinition {
+        solid: true,
+        luminance: 0.0,
+        specular: 0.1,
+        diffuse: 0.6,
+        roughness: 0.5,
+        metallic: 0.0,
+        transmission: 0.0,
+        ior: 1.0,
+        transmission_tint: 0.0,
+        height: 0.25,
+        shape: BlockShape::Cube,
+        face_tiles: [if on { TILE_LEVER_ON } else { TILE_LEVER_OFF }; 6],
+        face_tints: NO_TINT,
+        cutout: false,
+        breakable: true,
+        hardness: 0.75,
+        tool: Some(ToolType::Axe),
+        harvest_tier: None,
+        sound: SoundMaterial::Stone,
+        on_scheduled_update: None,
+    }
+}
+
+const fn wire(signal: u8) -> BlockDefinition {
+    BlockDefinition {
+        solid: true,
+        // Powered wire glows faintly in proportion to its signal.
+        luminance: signal as f32 / MAX_SIGNAL as f32 * 0.5,
+        specular: 0.02,
+        diffuse: 0.8,
+        roughness: 0.9,
+        metallic: 0.0,
+        transmission: 0.0,
+        ior: 1.0,
+        transmission_tint: 0.0,
+        height: 1.0 / 16.0,
+        shape: BlockShape::Cube,
+        face_tiles: [if signal > 0 {
+            TILE_WIRE_ON
+        } else {
+            TILE_WIRE_OFF
+        }; 6],
+        face_tints: NO_TINT,
+        cutout: true,
+        breakable: true,
+        hardness: 0.0,
+        tool: None,
+        harvest_tier: None,
+        sound: SoundMaterial::Stone,
+        on_scheduled_update: Some(ScheduledUpdate {
+            delay: SIGNAL_TICKS,
+            hook: ticking::propagate_signal,
+        }),
+    }
+}
+
+const fn signal_lamp(lit: bool) -> BlockDefinition {
+    BlockDefinition {
+        solid: true,
+        luminance: if lit { 8.0 } else { 0.0 },
+        specular: 0.08,
+        diffuse: 0.9,
+        roughness: 0.6,
+        metallic: 0.0,
+        transmission: 0.0,
+        ior: 1.2,
+        transmission_tint: 0.0,
+        height: 1.0,
+        shape: BlockShape::Cube,
+        face_tiles: [if lit {
+            TILE_SIGNAL_LAMP_ON
+        } else {
+            TILE_SIGNAL_LAMP_OFF
+        }; 6],
+        face_tints: NO_TINT,
+        cutout: false,
+        breakable: true,
+        hardness: 0.45,
+        tool: None,
+        harvest_tier: None,
+        sound: SoundMaterial::Glass,
+        on_scheduled_update: Some(ScheduledUpdate {
+            delay: SIGNAL_TICKS,
+            hook: ticking::update_signal_lamp,
+        }),
+    }
+}
+
+/// Side tiles everywhere except the front and back faces.
+const fn facing_tiles(facing: FaceDirection, front: TileId, back: TileId) -> [TileId; 6] {
+    let mut tiles = [TILE_PISTON_SIDE; 6];
+    tiles[facing.index()] = front;
+    tiles[facing.opposite().index()] = back;
+    tiles
+}
+
+const fn piston(facing: FaceDirection, extended: bool) -> BlockDefinition {
+    BlockDefinition {
+        solid: true,
+        luminance: 0.0,
+        specular: 0.1,
+        diffuse: 0.7,
+        roughness: 0.6,
+        metallic: 0.0,
+        transmission: 0.0,
+        ior: 1.0,
+        transmission_tint: 0.0,
+        height: 1.0,
+        shape: BlockShape::Cube,
+        face_tiles: facing_tiles(
+            facing,
+            if extended {
+                TILE_PISTON_OPEN
+            } else {
+                TILE_PISTON_FRONT
+            },
+            TILE_PISTON_BACK,
+        ),
+        face_tints: NO_TINT,
+        cutout: false,
+        breakable: true,
+        hardness: 2.25,
+        tool: Some(ToolType::Pickaxe),
+        harvest_tier: None,
+        sound: SoundMaterial::Stone,
+        on_scheduled_update: Some(ScheduledUpdate {
+            delay: SIGNAL_TICKS,
+            hook: piston::update_piston,
+        }),
+    }
+}
+
+const fn piston_head(facing: FaceDirection) -> BlockDefinition {
+    BlockDefinition {
+        solid: true,
+        luminance: 0.0,
+        specular: 0.1,
+        diffuse: 0.7,
+        roughness: 0.6,
+        metallic: 0.0,
+        transmission: 0.0,
+        ior: 1.0,
+        transmission_tint: 0.0,
+        height: 1.0,
+        shape: BlockShape::Cube,
+        face_tiles: facing_tiles(facing, TILE_PISTON_FRONT, TILE_PISTON_OPEN),
+        face_tints: NO_TINT,
+        cutout: false,
+        breakable: true,
+        hardness: 2.25,
+        tool: Some(ToolType::Pickaxe),
+        harvest_tier: None,
+        sound: SoundMaterial::Stone,
+        on_scheduled_update: Some(ScheduledUpdate {
+            delay: SIGNAL_TICKS,
+            hook: piston::update_piston_head,
+        }),
+    }
+}
@@ -0,0 +1,444 @@
+//! Block definition files in `assets/blocks/`, one per block and named after
+//! it (`glass.json`, `signal_lamp_lit.json`), which override its material
+//! values and face tiles. They are the only place block overrides live: the
+//! material editor saves its edits into them too.
+//!
+//! A watcher thread rescans the directory while the game runs, so a saved
+//! edit shows up within a second without a restart.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use log::warn;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::block::{BlockDefinition, BlockKind, FaceDirection};
+use crate::material_editor::MaterialValues;
+use crate::texture::{TextureAtlas, TileId};
+
+/// How often the watcher checks the directory for changed files.
+const RESCAN_INTERVAL: Duration = Duration::from_millis(500);
+
+/// One block's overrides. Every field is optional; what a file leaves out
+/// keeps the built-in value.
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct BlockFile {
+    #[serde(flatten)]
+    materials: MaterialValues,
+    /// Name of the block atlas tile drawn on every face, as listed in
+    /// `assets/textures/blocks.json`.
+    tile: Option<String>,
+    /// A tile name per face in `FaceDirection` order (-X, +X, -Y, +Y, -Z,
+    /// +Z); takes precedence over `tile`.
+    face_tiles: Option<[String; 6]>,
+}
+
+impl BlockFile {
+    /// Applies the file over `definition`, looking tiles up by name in
+    /// `atlas`. A name the atlas does not list is logged and leaves that
+    /// face's tile as it was.
+    pub fn apply(&self, definition: &mut BlockDefinition, atlas: &TextureAtlas) {
+        self.apply_with(definition, |name| atlas.tile(name));
+    }
+
+    fn apply_with(&self, definition: &mut BlockDefinition, tile: impl Fn(&str) -> Option<TileId>) {
+        self.materials.apply(definition);
+        let names = match (&self.face_tiles, &self.tile) {
+            (Some(names), _) => names.each_ref(),
+            (None, Some(name)) => [name; 6],
+            (None, None) => return,
+        };
+        for (face_tile, name) in definition.face_tiles.iter_mut().zip(names) {
+            match tile(name) {
+                Some(id) => *face_tile = id,
+                None => warn!("Block atlas has no tile named '{}'", name),
+            }
+        }
+    }
+}
+
+/// A block file that appeared, changed or went away.
+pub struct BlockFileChange {
+    pub kind: BlockKind,
+    pub path: PathBuf,
+    /// `None` once the file is gone.
+    pub file: Option<BlockFile>,
+}
+
+pub struct BlockFiles {
+    directory: PathBuf,
+    /// Changes found by the watcher thread, if it is running.
+    changes: Option<Receiver<BlockFileChange>>,
+}
+
+impl BlockFiles {
+    /// Starts watching `assets/blocks/`. The first scan reports every file
+    /// already there.
+    pub fn new() -> Self {
+        let mut files = Self::at(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("assets/blocks"));
+        let (sender, receiver) = mpsc::channel();
+        let watcher = Watcher::new(files.directory.clone());
+        match thread::Builder::new()
+            .name("block-files".into())
+            .spawn(move || watcher.run(sender))
+        {
+            Ok(_) => files.changes = Some(receiver),
+            Err(err) => warn!("Failed to start the block file watcher: {}", err),
+        }
+        files
+    }
+
+    fn at(directory: PathBuf) -> Self {
+        Self {
+            directory,
+            changes: None,
+        }
+    }
+
+    /// Files added, edited or removed since the last call, as far as the
+    /// watcher has found them. Never blocks.
+    pub fn poll(&mut self) -> Vec<BlockFileChange> {
+        self.changes
+            .as_ref()
+            .map(|changes| changes.try_iter().collect())
+            .unwrap_or_default()
+    }
+
+    /// Where `kind`'s block file lives, whether or not it exists.
+    pub fn path(&self, kind: BlockKind) -> PathBuf {
+        self.directory.join(format!("{}.json", file_stem(kind)))
+    }
+
+    /// Writes `materials` into `kind`'s block file, creating it if needed.
+    /// Everything else the file sets, such as its tiles, is kept; a file
+    /// that does not parse is left alone and reported.
+    pub fn store_materials(&self, kind: BlockKind, materials: &MaterialValues) -> io::Result<()> {
+        let path = self.path(kind);
+        let mut object = match fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice::<Map<String, Value>>(&bytes)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Map::new(),
+            Err(err) => return Err(err),
+        };
+        if let Value::Object(values) = serde_json::to_value(materials)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?
+        {
+            object.extend(values);
+        }
+        let json = serde_json::to_string_pretty(&object)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        fs::create_dir_all(&self.directory)?;
+        fs::write(path, json + "\n")
+    }
+}
+
+/// Scans the blocks directory from its own thread so the game never waits
+/// on the file system.
+struct Watcher {
+    directory: PathBuf,
+    /// Modification time of the directory at the last full read. Adding,
+    /// removing or renaming a file changes it; editing one in place does not.
+    directory_stamp: Option<SystemTime>,
+    /// Modification time of every file found by the last scan.
+    stamps: HashMap<PathBuf, SystemTime>,
+}
+
+impl Watcher {
+    fn new(directory: PathBuf) -> Self {
+        Self {
+            directory,
+            directory_stamp: None,
+            stamps: HashMap::new(),
+        }
+    }
+
+    /// Scans every `RESCAN_INTERVAL` until the receiving end is dropped.
+    fn run(mut self, changes: Sender<BlockFileChange>) {
+        loop {
+            for change in self.scan() {
+                if changes.send(change).is_err() {
+                    return;
+                }
+            }
+            thread::sleep(RESCAN_INTERVAL);
+        }
+    }
+
+    fn scan(&mut self) -> Vec<BlockFileChange> {
+        let directory_stamp = modified(&self.directory);
+        let stamps = if directory_stamp.is_some() && directory_stamp == self.directory_stamp {
+            // The same files as last time; only their contents can differ.
+            self.stamps
+                .keys()
+                .filter_map(|path| Some((path.clone(), modified(path)?)))
+                .collect()
+        } else {
+            self.directory_stamp = directory_stamp;
+            self.read_stamps()
+        };
+
+        let mut changes = Vec::new();
+        for (path, modified) in &stamps {
+            if self.stamps.get(path) == Some(modified) {
+                continue;
+            }
+            let Some(kind) = block_for_path(path) else {
+                warn!("Block file {} names no block", path.display());
+                continue;
+            };
+            // A file caught half written fails to parse; its next save
+            // changes the stamp again and is picked up then.
+            match read_block_file(path) {
+                Ok(file) => changes.push(BlockFileChange {
+                    kind,
+                    path: path.clone(),
+                    file: Some(file),
+                }),
+                Err(err) => warn!(
+                    "Failed to load block file {}: {}; keeping its last values",
+                    path.display(),
+                    err
+                ),
+            }
+        }
+        for path in self.stamps.keys() {
+            if !stamps.contains_key(path)
+                && let Some(kind) = block_for_path(path)
+            {
+                changes.push(BlockFileChange {
+                    kind,
+                    path: path.clone(),
+                    file: None,
+                });
+            }
+        }
+        self.stamps = stamps;
+        changes
+    }
+
+    /// Modification times of the `.json` files in the directory.
+    fn read_stamps(&self) -> HashMap<PathBuf, SystemTime> {
+        let mut stamps = HashMap::new();
+        if let Ok(entries) = fs::read_dir(&self.directory) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.extension().is_none_or(|extension| extension != "json") {
+                    continue;
+                }
+                if let Some(modified) = modified(&path) {
+                    stamps.insert(path, modified);
+                }
+            }
+        }
+        stamps
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// The file name stem of `kind`'s block file: its display name in lower
+/// case, with every run of other characters turned into one underscore.
+/// Pistons and piston heads share a name across facings, so theirs ends in
+/// the facing as well (`piston_extended_pos_x`).
+pub fn file_stem(kind: BlockKind) -> String {
+    let mut stem = String::new();
+    for word in kind
+        .display_name()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+    {
+        if !stem.is_empty() {
+            stem.push('_');
+        }
+        stem.push_str(&word.to_ascii_lowercase());
+    }
+    if let BlockKind::Piston(facing, _) | BlockKind::PistonHead(facing) = kind {
+        stem.push('_');
+        stem.push_str(match facing {
+            FaceDirection::NegX => "neg_x",
+            FaceDirection::PosX => "pos_x",
+            FaceDirection::NegY => "neg_y",
+            FaceDirection::PosY => "pos_y",
+            FaceDirection::NegZ => "neg_z",
+            FaceDirection::PosZ => "pos_z",
+        });
+    }
+    stem
+}
+
+fn block_for_path(path: &Path) -> Option<BlockKind> {
+    let stem = path.file_stem()?.to_str()?;
+    BlockKind::ALL
+        .into_iter()
+        .find(|&kind| kind != BlockKind::Air && file_stem(kind).eq_ignore_ascii_case(stem))
+}
+
+fn read_block_file(path: &Path) -> io::Result<BlockFile> {
+    serde_json::from_slice(&fs::read(path)?)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp_dir::TempDir;
+
+    /// A few entries of the block atlas manifest.
+    fn tile(name: &str) -> Option<TileId> {
+        let [x, y] = match name {
+            "dirt" => [2, 0],
+            "stone" => [3, 0],
+            "wheat_2" => [3, 1],
+            _ => return None,
+        };
+        Some(TileId { x, y })
+    }
+
+    #[test]
+    fn files_are_named_after_blocks_and_override_what_they_set() {
+        assert_eq!(file_stem(BlockKind::SignalLamp(true)), "signal_lamp_lit");
+        assert_eq!(
+            block_for_path(Path::new("assets/blocks/Glass.json")),
+            Some(BlockKind::Glass)
+        );
+        assert_eq!(block_for_path(Path::new("assets/blocks/air.json")), None);
+        assert_eq!(
+            block_for_path(Path::new("assets/blocks/piston_extended_neg_z.json")),
+            Some(BlockKind::Piston(FaceDirection::NegZ, true))
+        );
+
+        let file: BlockFile =
+            serde_json::from_str(r#"{ "roughness": 0.25, "tile": "wheat_2" }"#).unwrap();
+        let mut definition = *BlockKind::Metal.definition();
+        file.apply_with(&mut definition, tile);
+        assert_eq!(definition.roughness, 0.25);
+        assert_eq!(definition.face_tiles, [TileId { x: 3, y: 1 }; 6]);
+        assert_eq!(definition.metallic, BlockKind::Metal.definition().metallic);
+
+        let file: BlockFile = serde_json::from_str(
+            r#"{ "tile": "wheat_2", "face_tiles": ["stone", "stone", "dirt", "missing", "stone", "stone"] }"#,
+        )
+        .unwrap();
+        let mut definition = *BlockKind::Metal.definition();
+        file.apply_with(&mut definition, tile);
+        let [stone, dirt, metal] = [
+            TileId { x: 3, y: 0 },
+            TileId { x: 2, y: 0 },
+            TileId { x: 6, y: 0 },
+        ];
+        assert_eq!(
+            definition.face_tiles,
+            [stone, stone, dirt, metal, stone, stone]
+        );
+    }
+
+    #[test]
+    fn every_block_has_a_file_of_its_own() {
+        let mut stems = HashMap::new();
+        for kind in BlockKind::ALL {
+            if kind == BlockKind::Air {
+                continue;
+            }
+            if let Some(other) = stems.insert(file_stem(kind), kind) {
+                panic!("{kind:?} and {other:?} share {}.json", file_stem(kind));
+            }
+        }
+    }
+
+    #[test]
+    fn the_watcher_reports_files_that_appear_change_and_go() {
+        let dir = TempDir::new("block-files");
+        let blocks = dir.path().join("blocks");
+        let mut watcher = Watcher::new(blocks.clone());
+        assert!(watcher.scan().is_empty());
+
+        fs::create_dir_all(&blocks).unwrap();
+        let path = blocks.join("glass.json");
+        fs::write(&path, r#"{ "roughness": 0.25 }"#).unwrap();
+        fs::write(blocks.join("notes.txt"), "ignored").unwrap();
+        let changes = watcher.scan();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].kind, BlockKind::Glass);
+        assert!(changes[0].file.is_some());
+        assert!(watcher.scan().is_empty());
+
+        // An edit in place leaves the directory's stamp alone.
+        fs::write(&path, r#"{ "roughness": 0.5 }"#).unwrap();
+        let later = SystemTime::now() + Duration::from_secs(5);
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+        let changes = watcher.scan();
+        assert_eq!(changes.len(), 1);
+        let mut definition = *BlockKind::Glass.definition();
+        changes[0]
+            .file
+            .as_ref()
+            .unwrap()
+            .apply_with(&mut definition, tile);
+        assert_eq!(definition.roughness, 0.5);
+
+        fs::remove_file(&path).unwrap();
+        let changes = watcher.scan();
+        assert_eq!(changes.len(), 1);
+        assert!(changes[0].file.is_none());
+    }
+
+    #[test]
+    fn stored_materials_keep_the_rest_of_the_file() {
+        let dir = TempDir::new("block-files");
+        let files = BlockFiles::at(dir.path().join("blocks"));
+        let mut definition = *BlockKind::Glass.definition();
+        definition.roughness = 0.5;
+        files
+            .store_materials(
+                BlockKind::Glass,
+                &MaterialValues::from_definition(&definition),
+            )
+            .unwrap();
+
+        let path = files.path(BlockKind::Glass);
+        let mut json: Map<String, Value> =
+            serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+        json.insert("tile".into(), "wheat_2".into());
+        fs::write(&path, serde_json::to_vec(&json).unwrap()).unwrap();
+        definition.roughness = 0.75;
+        files
+            .store_materials(
+                BlockKind::Glass,
+                &MaterialValues::from_definition(&definition),
+            )
+            .unwrap();
+
+        let file = read_block_file(&path).unwrap();
+        let mut loaded = *BlockKind::Glass.definition();
+        file.apply_with(&mut loaded, tile);
+        assert_eq!(loaded.roughness, 0.75);
+        assert_eq!(loaded.face_tiles, [TileId { x: 3, y: 1 }; 6]);
+
+        fs::write(&path, "{ not json").unwrap();
+        assert!(
+            files
+                .store_materials(
+                    BlockKind::Glass,
+                    &MaterialValues::from_definition(&definition)
+                )
+                .is_err()
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{ not json");
+    }
+}
@@ -1,4 +1,4 @@
-use glam::{Mat4, Vec3};
+use glam::{Mat4, Vec3, Vec4};
 
 #[derive(Clone)]
 pub struct Camera {
@@ -60,8 +60,10 @@ impl Projection {
         }
     }
 
+    /// Reverse-Z projection: the near plane maps to depth 1 and the far plane
+    /// to 0, which spreads float depth precision evenly over distance.
     pub fn matrix(&self) -> Mat4 {
-        Mat4::perspective_rh_gl(self.fovy.to_radians(), self.aspect, self.znear, self.zfar)
+        Mat4::perspective_rh(self.fovy.to_radians(), self.aspect, self.zfar, self.znear)
     }
 }
 
@@ -83,3 +85,37 @@ impl CameraUniform {
         self.view_proj = view_proj.to_cols_array_2d();
     }
 }
+
+/// The volume a view-projection matrix sees, as six inward-facing planes.
+#[derive(Clone, Copy)]
+pub struct Frustum {
+    /// Normal in xyz and offset in w; a point `p` is inside a plane when
+    /// `plane.xyz · p + plane.w >= 0`.
+    planes: [Vec4; 6],
+}
+
+impl Frustum {
+    /// The clip planes of `view_proj`, for wgpu's 0..1 depth range.
+    pub fn from_view_proj(view_proj: Mat4) -> Self {
+        let [x, y, z, w] = [0, 1, 2, 3].map(|row| view_proj.row(row));
+        let planes =
+            [w + x, w - x, w + y, w - y, z, w - z].map(|plane| plane / plane.truncate().length());
+        Self { planes }
+    }
+
+    pub fn planes(&self) -> [[f32; 4]; 6] {
+        self.planes.map(|plane| plane.to_array())
+    }
+
+    /// Whether any of the box from `min` to `max` may be visible. Boxes near
+    /// a frustum corner can pass without being inside, which only costs a
+    /// wasted draw.
+    pub fn intersects_aabb(&self, min: Vec3, max: Vec3) -> bool {
+        self.planes.iter().all(|plane| {
+            // The corner farthest along the plane's normal.
+            let normal = plane.truncate();
+            let corner = Vec3::select(normal.cmpge(Vec3::ZERO), max, min);
+            normal.dot(corner) + plane.w >= 0.0
+        })
+    }
+}
@@ -0,0 +1,76 @@
+use glam::Vec3;
+
+use crate::entity::Entity;
+use crate::health::HitResult;
+use crate::item::ToolType;
+use crate::mining::HeldTool;
+
+/// Furthest an entity can be hit from, measured from the eye.
+pub const ATTACK_REACH: f32 = 4.0;
+/// Seconds for the attack to fully recharge after a swing.
+const ATTACK_COOLDOWN: f32 = 0.6;
+/// Strength of a swing made straight after the last one.
+const MIN_ATTACK_STRENGTH: f32 = 0.2;
+const FIST_DAMAGE: f32 = 1.0;
+/// Horizontal and upward speed given to an entity by a full-strength hit.
+const KNOCKBACK_SPEED: f32 = 6.0;
+const KNOCKBACK_LIFT: f32 = 5.0;
+
+/// Damage of a full-strength hit with `tool`; every tier adds one point.
+pub fn attack_damage(tool: HeldTool) -> f32 {
+    match tool {
+        Some((tool_type, tier)) => {
+            let base = match tool_type {
+                ToolType::Axe => 4.0,
+                ToolType::Pickaxe => 3.0,
+                ToolType::Shovel => 2.5,
+            };
+            base + tier as u8 as f32
+        }
+        None => FIST_DAMAGE,
+    }
+}
+
+/// Recharge between melee swings; swinging early hits softer.
+pub struct AttackCooldown {
+    elapsed: f32,
+}
+
+impl AttackCooldown {
+    pub fn new() -> Self {
+        Self {
+            elapsed: ATTACK_COOLDOWN,
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.elapsed = (self.elapsed + dt).min(ATTACK_COOLDOWN);
+    }
+
+    /// Fraction recharged, for the HUD indicator; `None` once full.
+    pub fn progress(&self) -> Option<f32> {
+        (self.elapsed < ATTACK_COOLDOWN).then(|| self.elapsed / ATTACK_COOLDOWN)
+    }
+
+    /// Starts a swing; returns its strength in `MIN_ATTACK_STRENGTH..=1.0`.
+    pub fn swing(&mut self) -> f32 {
+        let charge = self.elapsed / ATTACK_COOLDOWN;
+        self.elapsed = 0.0;
+        MIN_ATTACK_STRENGTH + (1.0 - MIN_ATTACK_STRENGTH) * charge * charge
+    }
+}
+
+/// Hits `entity` for `damage`, knocking it away from `attacker` in
+/// proportion to the swing's `strength` unless the hit was ignored.
+pub fn strike(entity: &mut Entity, attacker: Vec3, damage: f32, strength: f32) -> HitResult {
+    let result = entity.health.hit(damage);
+    if result != HitResult::Ignored {
+        let away = (entity.body.position - attacker)
+            .with_y(0.0)
+            .normalize_or_zero();
+        entity
+            .body
+            .knock_back((away * KNOCKBACK_SPEED + Vec3::Y * KNOCKBACK_LIFT) * strength);
+    }
+    result
+}
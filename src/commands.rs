@@ -0,0 +1,115 @@
+use glam::Vec3;
+
+use crate::config::{self, PresentModeSetting};
+use crate::dimension::Dimension;
+
+/// A parsed chat command.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    /// Teleport to the world spawn.
+    Spawn,
+    /// Remember the current position as home.
+    SetHome,
+    /// Teleport to the saved home.
+    Home,
+    /// Build a portal in front of the player, linked to the last one built
+    /// if that is still unpaired.
+    Portal,
+    /// Travel to the spawn of the named dimension.
+    Dimension(Dimension),
+    /// Teleport so the player's feet are at this position.
+    Teleport(Vec3),
+    /// Show or hide the picture-in-picture camera feed.
+    Camera(CameraFeed),
+    /// Write the world's edits to disk now rather than on exit.
+    Save,
+    /// Switch to the named renderer, or list them without a name.
+    Renderer(Option<String>),
+    /// Reconfigure the window with another present mode.
+    Present(PresentModeSetting),
+    /// Cap the frame rate, or lift the cap with `None`.
+    MaxFps(Option<f32>),
+}
+
+/// What `/camera` puts in the picture-in-picture inset.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CameraFeed {
+    /// Looking behind the player, following them.
+    Rear,
+    /// A camera fixed where the player stands, looking where they look.
+    Here,
+    /// No inset.
+    Off,
+}
+
+impl Command {
+    /// Parses a console line such as `/sethome`. The leading slash is optional.
+    pub fn parse(line: &str) -> Result<Command, String> {
+        let line = line.trim();
+        let line = line.strip_prefix('/').unwrap_or(line);
+        let mut words = line.split_whitespace();
+        let Some(name) = words.next() else {
+            return Err("Empty command".to_string());
+        };
+        let command = match name.to_ascii_lowercase().as_str() {
+            "spawn" => Command::Spawn,
+            "sethome" => Command::SetHome,
+            "home" => Command::Home,
+            "save" => Command::Save,
+            "portal" => Command::Portal,
+            "renderer" => Command::Renderer(words.next().map(str::to_string)),
+            "dimension" => {
+                let usage = || "Usage: /dimension overworld|void".to_string();
+                let word = words.next().ok_or_else(usage)?;
+                let dimension = Dimension::from_name(&word.to_ascii_lowercase());
+                Command::Dimension(dimension.ok_or_else(usage)?)
+            }
+            "tp" => {
+                let usage = || "Usage: /tp <x> <y> <z>".to_string();
+                let mut coordinate = || -> Result<f32, String> {
+                    let word = words.next().ok_or_else(usage)?;
+                    parse_coordinate(word)
+                };
+                Command::Teleport(Vec3::new(coordinate()?, coordinate()?, coordinate()?))
+            }
+            "camera" => {
+                let usage = || "Usage: /camera rear|here|off".to_string();
+                let word = words.next().ok_or_else(usage)?;
+                let feed = match word.to_ascii_lowercase().as_str() {
+                    "rear" => CameraFeed::Rear,
+                    "here" => CameraFeed::Here,
+                    "off" => CameraFeed::Off,
+                    _ => return Err(usage()),
+                };
+                Command::Camera(feed)
+            }
+            "present" => {
+                let usage = || "Usage: /present vsync|mailbox|immediate".to_string();
+                let word = words.next().ok_or_else(usage)?;
+                Command::Present(PresentModeSetting::parse(word).ok_or_else(usage)?)
+            }
+            "maxfps" => {
+                let usage = || "Usage: /maxfps <fps>|off".to_string();
+                let word = words.next().ok_or_else(usage)?;
+                if word.eq_ignore_ascii_case("off") {
+                    Command::MaxFps(None)
+                } else {
+                    let fps = word.parse::<f32>().ok().and_then(config::parse_max_fps);
+                    Command::MaxFps(Some(fps.ok_or_else(usage)?))
+                }
+            }
+            _ => return Err(format!("Unknown command /{name}")),
+        };
+        if words.next().is_some() {
+            return Err(format!("Too many arguments for /{name}"));
+        }
+        Ok(command)
+    }
+}
+
+fn parse_coordinate(word: &str) -> Result<f32, String> {
+    match word.parse::<f32>() {
+        Ok(value) if value.is_finite() => Ok(value),
+        _ => Err(format!("Invalid coordinate '{word}'")),
+    }
+}
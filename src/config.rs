@@ -1,12 +1,52 @@
 use std::fs;
 use std::io;
+use std::ops::RangeInclusive;
 use std::path::PathBuf;
 
+use glam::Vec3;
 use log::warn;
 use serde::Deserialize;
+use serde_json::{Map, Value};
 use winit::event::VirtualKeyCode;
 
+use crate::audio::VolumeMixer;
+use crate::render::{DEFAULT_RENDERER, find_renderer, renderer_names};
+use crate::terrain::{BenchmarkScene, TerrainPreset};
+use crate::text::{OverlayStyle, UiPalette};
+
 const DEFAULT_SENSITIVITY: f32 = 0.05;
+const DEFAULT_GAMMA: f32 = 2.2;
+const DEFAULT_ZNEAR: f32 = 0.1;
+const DEFAULT_ZFAR: f32 = 200.0;
+const DEFAULT_FOV: f32 = 60.0;
+/// Vertical field of view the config and settings screen accept, in degrees.
+pub const FOV_RANGE: RangeInclusive<f32> = 30.0..=110.0;
+const DEFAULT_RENDER_DISTANCE: i32 = 4;
+/// Render distances the config and settings screen accept, in chunks.
+pub const RENDER_DISTANCE_RANGE: RangeInclusive<i32> = 2..=12;
+const DEFAULT_VERTICAL_RENDER_DISTANCE: i32 = 1;
+const VERTICAL_RENDER_DISTANCE_RANGE: RangeInclusive<i32> = 1..=4;
+const DEFAULT_UNLOAD_MARGIN: i32 = 1;
+const UNLOAD_MARGIN_RANGE: RangeInclusive<i32> = 0..=4;
+const DEFAULT_RAY_BOUNCES: u32 = 2;
+const RAY_BOUNCES_RANGE: RangeInclusive<u32> = 1..=8;
+const DEFAULT_MSAA: u32 = 1;
+const MSAA_SAMPLE_COUNTS: [u32; 4] = [1, 2, 4, 8];
+const DEFAULT_WORLD_SEED: u64 = 0;
+/// Direction towards the sun at noon unless the config picks another; the
+/// WGSL shaders only see the sun of the moment through their uniforms.
+const DEFAULT_SUN_DIRECTION: Vec3 = Vec3::new(0.279_508_5, 0.838_525_4, 0.465_846_9);
+const DEFAULT_SHADOW_DISTANCE: f32 = 96.0;
+const DEFAULT_AO_STRENGTH: f32 = 0.8;
+/// Seconds a whole day and night takes.
+const DEFAULT_DAY_LENGTH: f32 = 1200.0;
+const DAY_LENGTH_RANGE: RangeInclusive<f32> = 60.0..=7200.0;
+/// Relative to the project root, like the saves.
+const DEFAULT_GPU_TRACE_DIRECTORY: &str = "traces";
+/// Traces grow by every buffer upload, so captures are kept short.
+const MAX_GPU_TRACE_FRAMES: u32 = 600;
+/// Overlay text scale used by `large_text`.
+const LARGE_TEXT_SCALE: f32 = 2.0;
 
 #[derive(Clone)]
 pub struct AppConfig {
@@ -14,7 +54,51 @@ pub struct AppConfig {
     pub key_bindings: KeyBindings,
     pub present_mode: PresentModeSetting,
     pub max_fps: Option<f32>,
-    pub render_method: RenderMethodSetting,
+    /// Name of a registered renderer.
+    pub render_method: &'static str,
+    pub gamma: f32,
+    /// Present to an HDR surface where the display offers one, passing
+    /// lighting brighter than SDR white through instead of clamping it.
+    pub hdr_output: bool,
+    pub znear: f32,
+    pub zfar: f32,
+    /// Vertical field of view in degrees.
+    pub fov: f32,
+    /// Chunks loaded around the player in each horizontal direction.
+    pub render_distance: i32,
+    /// Chunk layers loaded above and below the player's.
+    pub vertical_render_distance: i32,
+    /// How many chunks past the render distance a chunk may get before it
+    /// unloads, so walking back and forth over a border does not reload it.
+    pub unload_margin: i32,
+    pub light_shafts: bool,
+    /// Accumulate path-traced samples while the view is still; ray tracer only.
+    pub path_tracing: bool,
+    /// Most reflection or refraction bounces a ray makes past the surface it
+    /// first hits; ray tracer only.
+    pub ray_bounces: u32,
+    /// Samples per pixel the rasterizer takes; 1 turns MSAA off. Lowered at
+    /// startup to what the adapter supports.
+    pub msaa: u32,
+    pub water_quality: WaterQuality,
+    /// Float the damage of each hit above the entity it landed on.
+    pub damage_numbers: bool,
+    /// Keep the inventory on death instead of dropping it where the player died.
+    pub keep_inventory: bool,
+    /// Make newly created worlds hardcore; existing worlds keep their flag.
+    pub hardcore: bool,
+    pub volume: VolumeMixer,
+    pub world_preset: TerrainPreset,
+    /// Seed all terrain noise is drawn from; the same seed and preset always
+    /// generate the same world.
+    pub world_seed: u64,
+    pub accessibility: AccessibilitySettings,
+    pub determinism: DeterminismSettings,
+    pub lighting: LightingSettings,
+    pub gpu_trace: GpuTraceSettings,
+    /// No `config.json` existed when this loaded, so the game is being
+    /// launched for the first time.
+    pub first_run: bool,
 }
 
 impl AppConfig {
@@ -28,7 +112,10 @@ impl AppConfig {
                     AppConfig::default()
                 }
             },
-            Err(err) if err.kind() == io::ErrorKind::NotFound => AppConfig::default(),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => AppConfig {
+                first_run: true,
+                ..AppConfig::default()
+            },
             Err(err) => {
                 warn!("Failed to read config file {}: {}", path.display(), err);
                 AppConfig::default()
@@ -36,6 +123,43 @@ impl AppConfig {
         }
     }
 
+    /// Writes the settings the game can change while running into
+    /// `config.json`, keeping every other key in the file as it is.
+    pub fn save(&self) -> io::Result<()> {
+        let path = default_config_path();
+        let mut root = match fs::read(&path) {
+            // A file that does not parse is left alone rather than replaced.
+            Ok(bytes) => serde_json::from_slice::<Map<String, Value>>(&bytes)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Map::new(),
+            Err(err) => return Err(err),
+        };
+        root.insert("mouse_sensitivity".into(), json_f32(self.mouse_sensitivity));
+        root.insert("present_mode".into(), self.present_mode.as_str().into());
+        match self.max_fps {
+            Some(fps) => root.insert("max_fps".into(), json_f32(fps)),
+            None => root.remove("max_fps"),
+        };
+        root.insert("render_method".into(), self.render_method.into());
+        root.insert("gamma".into(), json_f32(self.gamma));
+        root.insert("fov".into(), json_f32(self.fov));
+        root.insert("render_distance".into(), self.render_distance.into());
+        root.insert("light_shafts".into(), self.light_shafts.into());
+        root.insert("path_tracing".into(), self.path_tracing.into());
+        root.insert("ray_bounces".into(), self.ray_bounces.into());
+        root.insert("water_quality".into(), self.water_quality.as_str().into());
+        let lighting = root
+            .entry("lighting")
+            .or_insert_with(|| Value::Object(Map::new()));
+        if let Value::Object(lighting) = lighting {
+            lighting.insert("shadows".into(), self.lighting.shadows.into());
+        }
+
+        let mut json = serde_json::to_string_pretty(&root).map_err(io::Error::other)?;
+        json.push('\n');
+        fs::write(&path, json)
+    }
+
     fn from_raw(raw: RawConfig) -> Self {
         let defaults = KeyBindings::default();
         let key_bindings = KeyBindings {
@@ -45,6 +169,29 @@ impl AppConfig {
             right: parse_key(raw.keymap.move_right.as_deref(), defaults.right),
             up: parse_key(raw.keymap.move_up.as_deref(), defaults.up),
             down: parse_key(raw.keymap.move_down.as_deref(), defaults.down),
+            block_picker: parse_key(
+                raw.keymap.open_block_picker.as_deref(),
+                defaults.block_picker,
+            ),
+            inventory: parse_key(raw.keymap.open_inventory.as_deref(), defaults.inventory),
+            switch_renderer: parse_key(
+                raw.keymap.switch_renderer.as_deref(),
+                defaults.switch_renderer,
+            ),
+            render_distance_up: parse_key(
+                raw.keymap.render_distance_up.as_deref(),
+                defaults.render_distance_up,
+            ),
+            render_distance_down: parse_key(
+                raw.keymap.render_distance_down.as_deref(),
+                defaults.render_distance_down,
+            ),
+            screenshot: parse_key(raw.keymap.screenshot.as_deref(), defaults.screenshot),
+            free_camera: parse_key(raw.keymap.free_camera.as_deref(), defaults.free_camera),
+            fast_forward_time: parse_key(
+                raw.keymap.fast_forward_time.as_deref(),
+                defaults.fast_forward_time,
+            ),
         };
 
         let mut sensitivity = raw.mouse_sensitivity.unwrap_or(DEFAULT_SENSITIVITY);
@@ -57,22 +204,161 @@ impl AppConfig {
         }
 
         let present_mode = PresentModeSetting::from_raw(raw.present_mode);
-        let render_method = RenderMethodSetting::from_raw(raw.render_method);
+        let render_method = parse_render_method(raw.render_method.as_deref());
         let max_fps = raw.max_fps.and_then(|v| {
-            if v.is_finite() && v > 0.0 {
-                Some(v.min(2400.0))
-            } else {
+            let fps = parse_max_fps(v);
+            if fps.is_none() {
                 warn!("Invalid max_fps {}; ignoring", v);
-                None
             }
+            fps
         });
 
+        let gamma = match raw.gamma {
+            Some(v) if v.is_finite() && (1.0..=3.0).contains(&v) => v,
+            Some(v) => {
+                warn!("Invalid gamma {}; falling back to {}", v, DEFAULT_GAMMA);
+                DEFAULT_GAMMA
+            }
+            None => DEFAULT_GAMMA,
+        };
+
+        let znear = match raw.znear {
+            Some(v) if v.is_finite() && v > 0.0 => v,
+            Some(v) => {
+                warn!("Invalid znear {}; falling back to {}", v, DEFAULT_ZNEAR);
+                DEFAULT_ZNEAR
+            }
+            None => DEFAULT_ZNEAR,
+        };
+        let zfar = match raw.zfar {
+            Some(v) if v.is_finite() && v > znear => v,
+            Some(v) => {
+                warn!(
+                    "Invalid zfar {} (must exceed znear {}); falling back to {}",
+                    v,
+                    znear,
+                    DEFAULT_ZFAR.max(znear * 2.0)
+                );
+                DEFAULT_ZFAR.max(znear * 2.0)
+            }
+            None => DEFAULT_ZFAR.max(znear * 2.0),
+        };
+
+        let fov = match raw.fov {
+            Some(v) if FOV_RANGE.contains(&v) => v,
+            Some(v) => {
+                warn!("Invalid fov {}; falling back to {}", v, DEFAULT_FOV);
+                DEFAULT_FOV
+            }
+            None => DEFAULT_FOV,
+        };
+        let render_distance = match raw.render_distance {
+            Some(v) if RENDER_DISTANCE_RANGE.contains(&v) => v,
+            Some(v) => {
+                warn!(
+                    "Invalid render_distance {}; falling back to {}",
+                    v, DEFAULT_RENDER_DISTANCE
+                );
+                DEFAULT_RENDER_DISTANCE
+            }
+            None => DEFAULT_RENDER_DISTANCE,
+        };
+        let vertical_render_distance = match raw.vertical_render_distance {
+            Some(v) if VERTICAL_RENDER_DISTANCE_RANGE.contains(&v) => v,
+            Some(v) => {
+                warn!(
+                    "Invalid vertical_render_distance {}; falling back to {}",
+                    v, DEFAULT_VERTICAL_RENDER_DISTANCE
+                );
+                DEFAULT_VERTICAL_RENDER_DISTANCE
+            }
+            None => DEFAULT_VERTICAL_RENDER_DISTANCE,
+        };
+        let unload_margin = match raw.unload_margin {
+            Some(v) if UNLOAD_MARGIN_RANGE.contains(&v) => v,
+            Some(v) => {
+                warn!(
+                    "Invalid unload_margin {}; falling back to {}",
+                    v, DEFAULT_UNLOAD_MARGIN
+                );
+                DEFAULT_UNLOAD_MARGIN
+            }
+            None => DEFAULT_UNLOAD_MARGIN,
+        };
+        let ray_bounces = match raw.ray_bounces {
+            Some(v) if RAY_BOUNCES_RANGE.contains(&v) => v,
+            Some(v) => {
+                warn!(
+                    "Invalid ray_bounces {}; falling back to {}",
+                    v, DEFAULT_RAY_BOUNCES
+                );
+                DEFAULT_RAY_BOUNCES
+            }
+            None => DEFAULT_RAY_BOUNCES,
+        };
+        let msaa = match raw.msaa {
+            Some(v) if MSAA_SAMPLE_COUNTS.contains(&v) => v,
+            Some(v) => {
+                warn!(
+                    "Invalid msaa {} (must be 1, 2, 4 or 8); falling back to {}",
+                    v, DEFAULT_MSAA
+                );
+                DEFAULT_MSAA
+            }
+            None => DEFAULT_MSAA,
+        };
+
+        let mixer_defaults = VolumeMixer::default();
+        let volume = VolumeMixer {
+            master: parse_volume("master", raw.volume.master, mixer_defaults.master),
+            blocks: parse_volume("blocks", raw.volume.blocks, mixer_defaults.blocks),
+            footsteps: parse_volume("footsteps", raw.volume.footsteps, mixer_defaults.footsteps),
+        };
+
+        let world_preset = match raw.world_preset.as_deref() {
+            Some(name) => TerrainPreset::from_name(name).unwrap_or_else(|| {
+                warn!("Unknown world_preset '{}'; falling back to default", name);
+                TerrainPreset::Default
+            }),
+            None => TerrainPreset::Default,
+        };
+        let world_preset = match world_preset {
+            TerrainPreset::Benchmark(_) => {
+                TerrainPreset::Benchmark(parse_benchmark_scene(raw.benchmark_scene))
+            }
+            preset => preset,
+        };
+
         Self {
             mouse_sensitivity: sensitivity,
             key_bindings,
             present_mode,
             max_fps,
             render_method,
+            gamma,
+            hdr_output: raw.hdr_output.unwrap_or(false),
+            znear,
+            zfar,
+            fov,
+            render_distance,
+            vertical_render_distance,
+            unload_margin,
+            light_shafts: raw.light_shafts.unwrap_or(true),
+            path_tracing: raw.path_tracing.unwrap_or(false),
+            ray_bounces,
+            msaa,
+            water_quality: WaterQuality::from_raw(raw.water_quality),
+            damage_numbers: raw.damage_numbers.unwrap_or(true),
+            keep_inventory: raw.keep_inventory.unwrap_or(false),
+            hardcore: raw.hardcore.unwrap_or(false),
+            volume,
+            world_preset,
+            world_seed: raw.world_seed.unwrap_or(DEFAULT_WORLD_SEED),
+            accessibility: AccessibilitySettings::from_raw(raw.accessibility),
+            determinism: DeterminismSettings::from_raw(raw.determinism),
+            lighting: LightingSettings::from_raw(raw.lighting),
+            gpu_trace: GpuTraceSettings::from_raw(raw.gpu_trace),
+            first_run: false,
         }
     }
 }
@@ -84,11 +370,269 @@ impl Default for AppConfig {
             key_bindings: KeyBindings::default(),
             present_mode: PresentModeSetting::VSync,
             max_fps: None,
-            render_method: RenderMethodSetting::Rasterized,
+            render_method: DEFAULT_RENDERER,
+            gamma: DEFAULT_GAMMA,
+            hdr_output: false,
+            znear: DEFAULT_ZNEAR,
+            zfar: DEFAULT_ZFAR,
+            fov: DEFAULT_FOV,
+            render_distance: DEFAULT_RENDER_DISTANCE,
+            vertical_render_distance: DEFAULT_VERTICAL_RENDER_DISTANCE,
+            unload_margin: DEFAULT_UNLOAD_MARGIN,
+            light_shafts: true,
+            path_tracing: false,
+            ray_bounces: DEFAULT_RAY_BOUNCES,
+            msaa: DEFAULT_MSAA,
+            water_quality: WaterQuality::ScreenSpace,
+            damage_numbers: true,
+            keep_inventory: false,
+            hardcore: false,
+            volume: VolumeMixer::default(),
+            world_preset: TerrainPreset::Default,
+            world_seed: DEFAULT_WORLD_SEED,
+            accessibility: AccessibilitySettings::default(),
+            determinism: DeterminismSettings::default(),
+            lighting: LightingSettings::default(),
+            gpu_trace: GpuTraceSettings::default(),
+            first_run: false,
+        }
+    }
+}
+
+/// Overlay colors and motion options for players who need them.
+#[derive(Clone, Copy)]
+pub struct AccessibilitySettings {
+    pub palette: UiPalette,
+    pub high_contrast_crosshair: bool,
+    pub large_text: bool,
+    /// Disables camera motion the player did not ask for, such as view bobbing.
+    pub reduced_motion: bool,
+    /// Captions for nearby sounds in the bottom-right corner.
+    pub subtitles: bool,
+}
+
+impl AccessibilitySettings {
+    fn from_raw(raw: RawAccessibility) -> Self {
+        let mut palette = match raw
+            .palette
+            .as_ref()
+            .map(|s| s.trim().to_ascii_lowercase())
+            .as_deref()
+        {
+            Some("default") | None => UiPalette::DEFAULT,
+            Some("colorblind") | Some("color-blind") => UiPalette::COLORBLIND,
+            Some("high_contrast") | Some("high-contrast") => UiPalette::HIGH_CONTRAST,
+            Some(other) => {
+                warn!("Unknown palette '{}'; falling back to default", other);
+                UiPalette::DEFAULT
+            }
+        };
+        palette.text = parse_color("text_color", raw.text_color.as_deref(), palette.text);
+        palette.backdrop = parse_color(
+            "backdrop_color",
+            raw.backdrop_color.as_deref(),
+            palette.backdrop,
+        );
+        palette.crosshair = parse_color(
+            "crosshair_color",
+            raw.crosshair_color.as_deref(),
+            palette.crosshair,
+        );
+
+        Self {
+            palette,
+            high_contrast_crosshair: raw.high_contrast_crosshair.unwrap_or(false),
+            large_text: raw.large_text.unwrap_or(false),
+            reduced_motion: raw.reduced_motion.unwrap_or(false),
+            subtitles: raw.subtitles.unwrap_or(false),
+        }
+    }
+
+    pub fn overlay_style(&self) -> OverlayStyle {
+        OverlayStyle {
+            palette: self.palette,
+            text_scale: if self.large_text {
+                LARGE_TEXT_SCALE
+            } else {
+                1.0
+            },
+            high_contrast_crosshair: self.high_contrast_crosshair,
+        }
+    }
+}
+
+impl Default for AccessibilitySettings {
+    fn default() -> Self {
+        Self {
+            palette: UiPalette::DEFAULT,
+            high_contrast_crosshair: false,
+            large_text: false,
+            reduced_motion: false,
+            subtitles: false,
+        }
+    }
+}
+
+/// Reproducible simulation, for checking that a run replays exactly.
+#[derive(Clone, Default)]
+pub struct DeterminismSettings {
+    /// Advance the simulation by a fixed step every frame instead of the
+    /// frame's duration, and checksum the state after every tick.
+    pub enabled: bool,
+    /// Where to write this run's per-tick checksums.
+    pub record_checksums: Option<PathBuf>,
+    /// Checksums of an earlier run to compare this one against.
+    pub verify_checksums: Option<PathBuf>,
+}
+
+impl DeterminismSettings {
+    fn from_raw(raw: RawDeterminism) -> Self {
+        // Relative paths are taken from the project root, like the saves.
+        let resolve = |path: Option<String>| {
+            path.map(|path| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(path))
+        };
+        let record_checksums = resolve(raw.record_checksums);
+        let verify_checksums = resolve(raw.verify_checksums);
+        let enabled = raw.enabled.unwrap_or(false);
+        if !enabled && (record_checksums.is_some() || verify_checksums.is_some()) {
+            warn!("Checksum files are only used with determinism.enabled; ignoring them");
+        }
+        Self {
+            enabled,
+            record_checksums,
+            verify_checksums,
+        }
+    }
+}
+
+/// The sun both renderers light the world with.
+#[derive(Clone, Copy)]
+pub struct LightingSettings {
+    /// Unit vector pointing towards the sun; always above the horizon. The
+    /// configured one is the sun at noon; each frame's copy holds the light
+    /// of the moment, from `sky::SkyLight::light_direction`.
+    pub sun_direction: Vec3,
+    /// Seconds a whole day and night takes.
+    pub day_length: f32,
+    /// Shadow-mapped sunlight in the rasterizer.
+    pub shadows: bool,
+    /// How far from the camera, in blocks, terrain still receives shadows.
+    pub shadow_distance: f32,
+    /// How much (0..=1) the rasterizer darkens block corners closed in by
+    /// their neighbours.
+    pub ao_strength: f32,
+}
+
+impl LightingSettings {
+    fn from_raw(raw: RawLighting) -> Self {
+        let sun_direction = match raw.sun_direction.map(Vec3::from_array) {
+            Some(dir) if dir.is_finite() && dir.y > 0.0 => dir.normalize(),
+            Some(dir) => {
+                warn!(
+                    "Invalid sun_direction {:?} (must point above the horizon); falling back to default",
+                    dir.to_array()
+                );
+                DEFAULT_SUN_DIRECTION
+            }
+            None => DEFAULT_SUN_DIRECTION,
+        };
+        let shadow_distance = match raw.shadow_distance {
+            Some(distance) if (16.0..=256.0).contains(&distance) => distance,
+            Some(distance) => {
+                warn!(
+                    "Invalid shadow_distance {} (must be 16 to 256); falling back to {}",
+                    distance, DEFAULT_SHADOW_DISTANCE
+                );
+                DEFAULT_SHADOW_DISTANCE
+            }
+            None => DEFAULT_SHADOW_DISTANCE,
+        };
+        let ao_strength = match raw.ao_strength {
+            Some(strength) if (0.0..=1.0).contains(&strength) => strength,
+            Some(strength) => {
+                warn!(
+                    "Invalid ao_strength {} (must be 0 to 1); falling back to {}",
+                    strength, DEFAULT_AO_STRENGTH
+                );
+                DEFAULT_AO_STRENGTH
+            }
+            None => DEFAULT_AO_STRENGTH,
+        };
+        let day_length = match raw.day_length {
+            Some(length) if DAY_LENGTH_RANGE.contains(&length) => length,
+            Some(length) => {
+                warn!(
+                    "Invalid day_length {} (must be {} to {} seconds); falling back to {}",
+                    length,
+                    DAY_LENGTH_RANGE.start(),
+                    DAY_LENGTH_RANGE.end(),
+                    DEFAULT_DAY_LENGTH
+                );
+                DEFAULT_DAY_LENGTH
+            }
+            None => DEFAULT_DAY_LENGTH,
+        };
+        Self {
+            sun_direction,
+            day_length,
+            shadows: raw.shadows.unwrap_or(true),
+            shadow_distance,
+            ao_strength,
         }
     }
 }
 
+impl Default for LightingSettings {
+    fn default() -> Self {
+        Self {
+            sun_direction: DEFAULT_SUN_DIRECTION,
+            day_length: DEFAULT_DAY_LENGTH,
+            shadows: true,
+            shadow_distance: DEFAULT_SHADOW_DISTANCE,
+            ao_strength: DEFAULT_AO_STRENGTH,
+        }
+    }
+}
+
+/// A wgpu API trace of the first frames, for reproducing GPU bugs
+/// elsewhere; see `gpu_trace`.
+#[derive(Clone)]
+pub struct GpuTraceSettings {
+    /// Frames to record before the game closes; 0 records nothing.
+    pub frames: u32,
+    /// Each capture goes into its own directory under this one.
+    pub directory: PathBuf,
+}
+
+impl GpuTraceSettings {
+    fn from_raw(raw: RawGpuTrace) -> Self {
+        let frames = match raw.frames {
+            Some(frames) if frames > MAX_GPU_TRACE_FRAMES => {
+                warn!(
+                    "Invalid gpu_trace.frames {} (must be at most {}); falling back to {}",
+                    frames, MAX_GPU_TRACE_FRAMES, MAX_GPU_TRACE_FRAMES
+                );
+                MAX_GPU_TRACE_FRAMES
+            }
+            Some(frames) => frames,
+            None => 0,
+        };
+        let directory = raw
+            .directory
+            .unwrap_or_else(|| DEFAULT_GPU_TRACE_DIRECTORY.to_string());
+        Self {
+            frames,
+            directory: PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(directory),
+        }
+    }
+}
+
+impl Default for GpuTraceSettings {
+    fn default() -> Self {
+        Self::from_raw(RawGpuTrace::default())
+    }
+}
+
 #[derive(Clone)]
 pub struct KeyBindings {
     pub forward: VirtualKeyCode,
@@ -97,6 +641,22 @@ pub struct KeyBindings {
     pub right: VirtualKeyCode,
     pub up: VirtualKeyCode,
     pub down: VirtualKeyCode,
+    /// Held to show the radial block picker.
+    pub block_picker: VirtualKeyCode,
+    /// Opens and closes the inventory screen.
+    pub inventory: VirtualKeyCode,
+    /// Swaps to the next registered renderer.
+    pub switch_renderer: VirtualKeyCode,
+    /// Load one more chunk in each direction.
+    pub render_distance_up: VirtualKeyCode,
+    /// Load one fewer chunk in each direction.
+    pub render_distance_down: VirtualKeyCode,
+    /// Saves a screenshot to `screenshots/`.
+    pub screenshot: VirtualKeyCode,
+    /// Detaches the camera from the player, and snaps it back.
+    pub free_camera: VirtualKeyCode,
+    /// Held to run the day and night by quickly.
+    pub fast_forward_time: VirtualKeyCode,
 }
 
 impl KeyBindings {
@@ -108,6 +668,14 @@ impl KeyBindings {
             right: VirtualKeyCode::D,
             up: VirtualKeyCode::Space,
             down: VirtualKeyCode::LShift,
+            block_picker: VirtualKeyCode::Tab,
+            inventory: VirtualKeyCode::E,
+            switch_renderer: VirtualKeyCode::F4,
+            render_distance_up: VirtualKeyCode::Equals,
+            render_distance_down: VirtualKeyCode::Minus,
+            screenshot: VirtualKeyCode::F2,
+            free_camera: VirtualKeyCode::F5,
+            fast_forward_time: VirtualKeyCode::T,
         }
     }
 }
@@ -120,6 +688,30 @@ struct RawConfig {
     present_mode: Option<String>,
     max_fps: Option<f32>,
     render_method: Option<String>,
+    gamma: Option<f32>,
+    hdr_output: Option<bool>,
+    znear: Option<f32>,
+    zfar: Option<f32>,
+    fov: Option<f32>,
+    render_distance: Option<i32>,
+    vertical_render_distance: Option<i32>,
+    unload_margin: Option<i32>,
+    light_shafts: Option<bool>,
+    path_tracing: Option<bool>,
+    ray_bounces: Option<u32>,
+    msaa: Option<u32>,
+    water_quality: Option<String>,
+    damage_numbers: Option<bool>,
+    keep_inventory: Option<bool>,
+    hardcore: Option<bool>,
+    volume: RawVolume,
+    world_preset: Option<String>,
+    world_seed: Option<u64>,
+    benchmark_scene: RawBenchmarkScene,
+    accessibility: RawAccessibility,
+    determinism: RawDeterminism,
+    lighting: RawLighting,
+    gpu_trace: RawGpuTrace,
 }
 
 impl Default for RawConfig {
@@ -130,10 +722,87 @@ impl Default for RawConfig {
             present_mode: Some("vsync".into()),
             max_fps: None,
             render_method: Some("rasterized".into()),
+            gamma: Some(DEFAULT_GAMMA),
+            hdr_output: Some(false),
+            znear: Some(DEFAULT_ZNEAR),
+            zfar: Some(DEFAULT_ZFAR),
+            fov: Some(DEFAULT_FOV),
+            render_distance: Some(DEFAULT_RENDER_DISTANCE),
+            vertical_render_distance: Some(DEFAULT_VERTICAL_RENDER_DISTANCE),
+            unload_margin: Some(DEFAULT_UNLOAD_MARGIN),
+            light_shafts: Some(true),
+            path_tracing: Some(false),
+            ray_bounces: Some(DEFAULT_RAY_BOUNCES),
+            msaa: Some(DEFAULT_MSAA),
+            water_quality: Some("low".into()),
+            damage_numbers: Some(true),
+            keep_inventory: Some(false),
+            hardcore: Some(false),
+            volume: RawVolume::default(),
+            world_preset: None,
+            world_seed: Some(DEFAULT_WORLD_SEED),
+            benchmark_scene: RawBenchmarkScene::default(),
+            accessibility: RawAccessibility::default(),
+            determinism: RawDeterminism::default(),
+            lighting: RawLighting::default(),
+            gpu_trace: RawGpuTrace::default(),
         }
     }
 }
 
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct RawVolume {
+    master: Option<f32>,
+    blocks: Option<f32>,
+    footsteps: Option<f32>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct RawAccessibility {
+    palette: Option<String>,
+    text_color: Option<String>,
+    backdrop_color: Option<String>,
+    crosshair_color: Option<String>,
+    high_contrast_crosshair: Option<bool>,
+    large_text: Option<bool>,
+    reduced_motion: Option<bool>,
+    subtitles: Option<bool>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct RawDeterminism {
+    enabled: Option<bool>,
+    record_checksums: Option<String>,
+    verify_checksums: Option<String>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct RawBenchmarkScene {
+    solid_fraction: Option<f32>,
+    lamp_density: Option<f32>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct RawLighting {
+    sun_direction: Option<[f32; 3]>,
+    day_length: Option<f32>,
+    shadows: Option<bool>,
+    shadow_distance: Option<f32>,
+    ao_strength: Option<f32>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct RawGpuTrace {
+    frames: Option<u32>,
+    directory: Option<String>,
+}
+
 #[derive(Default, Deserialize)]
 #[serde(default)]
 struct RawKeyMap {
@@ -143,6 +812,79 @@ struct RawKeyMap {
     move_right: Option<String>,
     move_up: Option<String>,
     move_down: Option<String>,
+    open_block_picker: Option<String>,
+    open_inventory: Option<String>,
+    switch_renderer: Option<String>,
+    render_distance_up: Option<String>,
+    render_distance_down: Option<String>,
+    screenshot: Option<String>,
+    free_camera: Option<String>,
+    fast_forward_time: Option<String>,
+}
+
+fn parse_render_method(name: Option<&str>) -> &'static str {
+    let Some(name) = name else {
+        return DEFAULT_RENDERER;
+    };
+    match find_renderer(name) {
+        Some(entry) => entry.name,
+        None => {
+            warn!(
+                "Unknown render_method '{}' (expected one of {}); falling back to {}",
+                name,
+                renderer_names(),
+                DEFAULT_RENDERER
+            );
+            DEFAULT_RENDERER
+        }
+    }
+}
+
+fn parse_volume(name: &str, value: Option<f32>, fallback: f32) -> f32 {
+    match value {
+        Some(v) if v.is_finite() && (0.0..=1.0).contains(&v) => v,
+        Some(v) => {
+            warn!(
+                "Invalid {} volume {}; falling back to {}",
+                name, v, fallback
+            );
+            fallback
+        }
+        None => fallback,
+    }
+}
+
+/// Parses `#rrggbb` or `#rrggbbaa` into straight RGBA.
+fn parse_color(name: &str, value: Option<&str>, fallback: [f32; 4]) -> [f32; 4] {
+    let Some(value) = value else {
+        return fallback;
+    };
+
+    let digits = value.trim().trim_start_matches('#');
+    let channels = match digits.len() {
+        6 | 8 if digits.is_ascii() => (0..digits.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&digits[i..i + 2], 16))
+            .collect::<Result<Vec<_>, _>>()
+            .ok(),
+        _ => None,
+    };
+    match channels {
+        Some(channels) => {
+            let mut color = [1.0; 4];
+            for (slot, channel) in color.iter_mut().zip(channels) {
+                *slot = channel as f32 / 255.0;
+            }
+            color
+        }
+        None => {
+            warn!(
+                "Invalid {} '{}'; expected #rrggbb or #rrggbbaa",
+                name, value
+            );
+            fallback
+        }
+    }
 }
 
 fn parse_key(name: Option<&str>, fallback: VirtualKeyCode) -> VirtualKeyCode {
@@ -229,15 +971,74 @@ fn key_from_str(name: &str) -> Option<VirtualKeyCode> {
         "DOWN" => Some(VirtualKeyCode::Down),
         "LEFT" => Some(VirtualKeyCode::Left),
         "RIGHT" => Some(VirtualKeyCode::Right),
+        "F1" => Some(VirtualKeyCode::F1),
+        "F2" => Some(VirtualKeyCode::F2),
+        "F3" => Some(VirtualKeyCode::F3),
+        "F4" => Some(VirtualKeyCode::F4),
+        "F5" => Some(VirtualKeyCode::F5),
+        "F6" => Some(VirtualKeyCode::F6),
+        "F7" => Some(VirtualKeyCode::F7),
+        "F8" => Some(VirtualKeyCode::F8),
+        "F9" => Some(VirtualKeyCode::F9),
+        "F10" => Some(VirtualKeyCode::F10),
+        "F11" => Some(VirtualKeyCode::F11),
+        "F12" => Some(VirtualKeyCode::F12),
+        "-" | "MINUS" => Some(VirtualKeyCode::Minus),
+        "=" | "EQUALS" | "PLUS" => Some(VirtualKeyCode::Equals),
         _ => None,
     }
 }
 
+/// The `benchmark` preset's field, with fractions outside 0..=1 replaced by
+/// the defaults.
+fn parse_benchmark_scene(raw: RawBenchmarkScene) -> BenchmarkScene {
+    let defaults = BenchmarkScene::default();
+    let permille = |name: &str, value: Option<f32>, fallback: u16| match value {
+        Some(v) if (0.0..=1.0).contains(&v) => (v * 1000.0).round() as u16,
+        Some(v) => {
+            warn!(
+                "Invalid benchmark_scene.{} {}; falling back to {}",
+                name,
+                v,
+                fallback as f32 / 1000.0
+            );
+            fallback
+        }
+        None => fallback,
+    };
+    BenchmarkScene {
+        solid_permille: permille(
+            "solid_fraction",
+            raw.solid_fraction,
+            defaults.solid_permille,
+        ),
+        lamp_permille: permille("lamp_density", raw.lamp_density, defaults.lamp_permille),
+    }
+}
+
+/// `value` as JSON, written as its shortest `f32` decimal (0.05 rather than
+/// 0.05000000074505806).
+fn json_f32(value: f32) -> Value {
+    value
+        .to_string()
+        .parse::<f64>()
+        .map_or(Value::Null, Value::from)
+}
+
 fn default_config_path() -> PathBuf {
     PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("config.json")
 }
 
-#[derive(Clone, Copy)]
+/// Highest `max_fps` accepted; anything above is clamped to it.
+const MAX_FPS_LIMIT: f32 = 2400.0;
+
+/// The frame cap for a requested `fps`, or `None` if it is not a positive
+/// number.
+pub fn parse_max_fps(fps: f32) -> Option<f32> {
+    (fps.is_finite() && fps > 0.0).then(|| fps.min(MAX_FPS_LIMIT))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum PresentModeSetting {
     Immediate,
     Mailbox,
@@ -246,43 +1047,74 @@ pub enum PresentModeSetting {
 
 impl PresentModeSetting {
     fn from_raw(raw: Option<String>) -> Self {
-        match raw
-            .as_ref()
-            .map(|s| s.trim().to_ascii_lowercase())
-            .as_deref()
-        {
-            Some("immediate") | Some("unlocked") | Some("off") => Self::Immediate,
-            Some("mailbox") | Some("relaxed") => Self::Mailbox,
-            Some("vsync") | Some("fifo") | Some("on") | None => Self::VSync,
-            Some(other) => {
-                warn!("Unknown present_mode '{}'; falling back to vsync", other);
+        match raw.as_deref() {
+            None => Self::VSync,
+            Some(name) => Self::parse(name).unwrap_or_else(|| {
+                warn!("Unknown present_mode '{}'; falling back to vsync", name);
                 Self::VSync
-            }
+            }),
+        }
+    }
+
+    /// The mode called `name`, ignoring case, as `present_mode` and
+    /// `/present` accept it.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "immediate" | "unlocked" | "off" => Some(Self::Immediate),
+            "mailbox" | "relaxed" => Some(Self::Mailbox),
+            "vsync" | "fifo" | "on" => Some(Self::VSync),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Immediate => "immediate",
+            Self::Mailbox => "mailbox",
+            Self::VSync => "vsync",
+        }
+    }
+
+    /// The mode after this one, for settings that cycle through them.
+    pub fn next(self) -> Self {
+        match self {
+            Self::VSync => Self::Mailbox,
+            Self::Mailbox => Self::Immediate,
+            Self::Immediate => Self::VSync,
         }
     }
 }
 
-#[derive(Clone, Copy)]
-pub enum RenderMethodSetting {
-    Rasterized,
-    RayTraced,
+/// How the rasterizer reflects the world in water.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WaterQuality {
+    /// Marches reflected rays through the rendered frame; cheap, but only
+    /// reflects what is already on screen.
+    ScreenSpace,
+    /// Renders the world mirrored about sea level at half resolution.
+    Planar,
 }
 
-impl RenderMethodSetting {
-    pub fn from_raw(raw: Option<String>) -> Self {
+impl WaterQuality {
+    /// The name `water_quality` takes for it.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::ScreenSpace => "low",
+            Self::Planar => "high",
+        }
+    }
+
+    fn from_raw(raw: Option<String>) -> Self {
         match raw
             .as_ref()
             .map(|s| s.trim().to_ascii_lowercase())
             .as_deref()
         {
-            Some("raytraced") | Some("ray-traced") | Some("raytrace") => Self::RayTraced,
-            Some("raster") | Some("rasterized") | Some("mesh") | None => Self::Rasterized,
+            Some("low") | Some("ssr") | Some("screen_space") | None => Self::ScreenSpace,
+            Some("high") | Some("planar") => Self::Planar,
             Some(other) => {
-                warn!(
-                    "Unknown render_method '{}'; falling back to rasterized",
-                    other
-                );
-                Self::Rasterized
+                warn!("Unknown water_quality '{}'; falling back to low", other);
+                Self::ScreenSpace
             }
         }
     }
@@ -0,0 +1,105 @@
+use std::collections::VecDeque;
+
+use winit::event::VirtualKeyCode;
+
+/// Seconds a feedback line stays on screen.
+const MESSAGE_LIFETIME: f32 = 5.0;
+/// Oldest feedback lines are dropped beyond this many.
+const MAX_MESSAGES: usize = 6;
+/// Longest command line accepted, in characters.
+const MAX_INPUT: usize = 64;
+
+/// Result of a key press while the console is open.
+pub enum ConsoleKey {
+    /// The key edited the line or closed the console.
+    Handled,
+    /// Return was pressed; the console closed with this line.
+    Submit(String),
+}
+
+/// One-line command prompt opened with `/`, plus the feedback lines commands
+/// print.
+///
+/// Typed text arrives through `ReceivedCharacter`, so the prompt starts empty
+/// and the `/` that opened it is the first character typed into it.
+pub struct CommandConsole {
+    input: Option<String>,
+    messages: VecDeque<(String, f32)>,
+}
+
+impl CommandConsole {
+    pub fn new() -> Self {
+        Self {
+            input: None,
+            messages: VecDeque::new(),
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.input.is_some()
+    }
+
+    pub fn open(&mut self) {
+        self.input = Some(String::new());
+    }
+
+    pub fn close(&mut self) {
+        self.input = None;
+    }
+
+    pub fn handle_key(&mut self, key: VirtualKeyCode) -> ConsoleKey {
+        match key {
+            VirtualKeyCode::Escape => self.close(),
+            VirtualKeyCode::Back => {
+                if let Some(input) = &mut self.input {
+                    input.pop();
+                }
+            }
+            VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter => {
+                if let Some(line) = self.input.take() {
+                    return ConsoleKey::Submit(line);
+                }
+            }
+            _ => {}
+        }
+        ConsoleKey::Handled
+    }
+
+    pub fn push_char(&mut self, ch: char) {
+        if let Some(input) = &mut self.input
+            && !ch.is_control()
+            && input.chars().count() < MAX_INPUT
+        {
+            input.push(ch);
+        }
+    }
+
+    /// Prints a feedback line above the prompt.
+    pub fn message(&mut self, text: impl Into<String>) {
+        if self.messages.len() == MAX_MESSAGES {
+            self.messages.pop_front();
+        }
+        self.messages.push_back((text.into(), 0.0));
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        for (_, age) in &mut self.messages {
+            *age += dt;
+        }
+        self.messages.retain(|(_, age)| *age < MESSAGE_LIFETIME);
+    }
+
+    /// Feedback lines still on screen, oldest first.
+    pub fn messages(&self) -> impl Iterator<Item = &str> {
+        self.messages.iter().map(|(text, _)| text.as_str())
+    }
+
+    /// Display lines, oldest first, ending with the prompt while open.
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines: Vec<String> = self.messages().map(str::to_owned).collect();
+        if let Some(input) = &self.input {
+            lines.push(format!("> {input}_"));
+        }
+        lines
+    }
+}
@@ -0,0 +1,142 @@
+use winit::event::VirtualKeyCode;
+
+use crate::config::KeyBindings;
+
+/// Key that shows and hides the card.
+pub const CONTROLS_CARD_KEY: VirtualKeyCode = VirtualKeyCode::F1;
+
+/// Card listing the controls, with the keys as currently bound, shown over
+/// the world on the first launch and whenever `F1` is pressed.
+///
+/// Rows are padded to one width, so the dialog's centered lines line up as
+/// two columns: keys on the left, what they do on the right.
+pub struct ControlsCard {
+    lines: Vec<String>,
+    visible: bool,
+}
+
+impl ControlsCard {
+    pub fn new(keys: &KeyBindings, visible: bool) -> Self {
+        let movement = [keys.forward, keys.left, keys.backward, keys.right]
+            .map(key_label)
+            .join(" ");
+        let rows = [
+            (movement, "move"),
+            (key_label(keys.up), "jump, swim and fly up"),
+            (key_label(keys.down), "fly down"),
+            ("F".to_string(), "switch walking and flying"),
+            (
+                key_label(keys.free_camera),
+                "detach the camera, and snap it back",
+            ),
+            ("Mouse".to_string(), "look, once the cursor is captured"),
+            ("Click".to_string(), "capture the cursor"),
+            ("Esc".to_string(), "release the cursor and pause"),
+            ("Left click".to_string(), "break blocks and attack"),
+            ("Right click".to_string(), "place the selected block"),
+            ("Middle click".to_string(), "pick the looked-at block"),
+            ("Wheel, 1-9".to_string(), "select a hotbar slot"),
+            (key_label(keys.block_picker), "hold for the block picker"),
+            (key_label(keys.inventory), "open the inventory"),
+            ("/".to_string(), "open the command prompt"),
+            (key_label(keys.switch_renderer), "switch renderer"),
+            (
+                format!(
+                    "{} {}",
+                    key_label(keys.render_distance_up),
+                    key_label(keys.render_distance_down)
+                ),
+                "change the render distance",
+            ),
+            (key_label(keys.screenshot), "save a screenshot"),
+            (
+                key_label(keys.fast_forward_time),
+                "hold to speed up the day",
+            ),
+        ];
+        let key_width = rows.iter().map(|(key, _)| key.len()).max().unwrap_or(0);
+        let action_width = rows
+            .iter()
+            .map(|(_, action)| action.len())
+            .max()
+            .unwrap_or(0);
+
+        let mut lines = vec!["Controls".to_string(), String::new()];
+        lines.extend(
+            rows.iter()
+                .map(|(key, action)| format!("{key:>key_width$}  {action:<action_width$}")),
+        );
+        lines.push(String::new());
+        lines.push(format!(
+            "[{}] shows this again   [Esc] or click to close",
+            key_label(CONTROLS_CARD_KEY)
+        ));
+        Self { lines, visible }
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    pub fn hide(&mut self) {
+        self.visible = false;
+    }
+
+    /// Centered lines of the card, top first.
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+}
+
+/// How the card names `key`: its `VirtualKeyCode` name, or the character
+/// it types where that reads better.
+fn key_label(key: VirtualKeyCode) -> String {
+    let label = match key {
+        VirtualKeyCode::LShift => "Left Shift",
+        VirtualKeyCode::RShift => "Right Shift",
+        VirtualKeyCode::LControl => "Left Ctrl",
+        VirtualKeyCode::RControl => "Right Ctrl",
+        VirtualKeyCode::LAlt => "Left Alt",
+        VirtualKeyCode::RAlt => "Right Alt",
+        VirtualKeyCode::Return => "Enter",
+        VirtualKeyCode::Escape => "Esc",
+        VirtualKeyCode::Equals => "=",
+        VirtualKeyCode::Minus => "-",
+        VirtualKeyCode::Slash => "/",
+        VirtualKeyCode::Key0 => "0",
+        VirtualKeyCode::Key1 => "1",
+        VirtualKeyCode::Key2 => "2",
+        VirtualKeyCode::Key3 => "3",
+        VirtualKeyCode::Key4 => "4",
+        VirtualKeyCode::Key5 => "5",
+        VirtualKeyCode::Key6 => "6",
+        VirtualKeyCode::Key7 => "7",
+        VirtualKeyCode::Key8 => "8",
+        VirtualKeyCode::Key9 => "9",
+        _ => return format!("{key:?}"),
+    };
+    label.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rows_show_the_bound_keys_in_aligned_columns() {
+        let keys = KeyBindings {
+            inventory: VirtualKeyCode::I,
+            ..KeyBindings::default()
+        };
+        let card = ControlsCard::new(&keys, true);
+        let rows = &card.lines()[2..card.lines().len() - 2];
+        assert!(rows.iter().all(|row| row.len() == rows[0].len()));
+        for expected in ["W A S D  move", "I  open the inventory"] {
+            assert!(rows.iter().any(|row| row.trim() == expected), "{expected}");
+        }
+    }
+}
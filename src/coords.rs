@@ -0,0 +1,207 @@
+//! Typed positions on the block grid and the conversions between them.
+//!
+//! World positions of blocks are plain `IVec3`s almost everywhere; wrapping
+//! one in a [`BlockPos`] is how code finds the chunk holding it and the cell
+//! within that chunk, so the rounding towards negative infinity those need
+//! lives here and nowhere else.
+
+use std::ops::RangeInclusive;
+
+use glam::IVec3;
+
+use crate::block::FaceDirection;
+use crate::world::CHUNK_SIZE;
+
+const SIZE: i32 = CHUNK_SIZE as i32;
+
+/// Offsets to the six cells sharing a face with a cell.
+pub const FACE_NEIGHBORS: [IVec3; 6] = [
+    IVec3::X,
+    IVec3::NEG_X,
+    IVec3::Y,
+    IVec3::NEG_Y,
+    IVec3::Z,
+    IVec3::NEG_Z,
+];
+
+/// The four of [`FACE_NEIGHBORS`] on the cell's own layer.
+pub const HORIZONTAL_NEIGHBORS: [IVec3; 4] = [IVec3::X, IVec3::NEG_X, IVec3::Z, IVec3::NEG_Z];
+
+/// A block's cell in world space.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BlockPos(pub IVec3);
+
+impl BlockPos {
+    pub const fn new(x: i32, y: i32, z: i32) -> Self {
+        Self(IVec3::new(x, y, z))
+    }
+
+    /// The chunk holding this cell.
+    pub fn chunk(self) -> ChunkPos {
+        let chunk = self.0.div_euclid(IVec3::splat(SIZE));
+        ChunkPos::new(chunk.x, chunk.y, chunk.z)
+    }
+
+    /// This cell within its chunk.
+    pub fn local(self) -> LocalPos {
+        let local = self.0.rem_euclid(IVec3::splat(SIZE));
+        LocalPos::new(local.x as usize, local.y as usize, local.z as usize)
+    }
+
+    /// The chunk holding this cell and the cell within it.
+    pub fn split(self) -> (ChunkPos, LocalPos) {
+        (self.chunk(), self.local())
+    }
+
+    /// The cell across `face`.
+    pub fn neighbor(self, face: FaceDirection) -> Self {
+        Self(self.0 + face.normal())
+    }
+}
+
+impl From<IVec3> for BlockPos {
+    fn from(position: IVec3) -> Self {
+        Self(position)
+    }
+}
+
+impl From<BlockPos> for IVec3 {
+    fn from(position: BlockPos) -> Self {
+        position.0
+    }
+}
+
+/// A chunk's place in the grid of chunks: chunk (1, 0, -1) holds the blocks
+/// from (16, 0, -16) to (31, 15, -1).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ChunkPos {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+impl ChunkPos {
+    pub const fn new(x: i32, y: i32, z: i32) -> Self {
+        Self { x, y, z }
+    }
+
+    /// World position of the chunk's lowest block.
+    pub fn min_block(self) -> IVec3 {
+        IVec3::new(self.x, self.y, self.z) * SIZE
+    }
+
+    /// World position of `local` in this chunk.
+    pub fn block(self, local: LocalPos) -> IVec3 {
+        self.min_block() + local.as_ivec3()
+    }
+
+    /// The chunk across `face`.
+    pub fn neighbor(self, face: FaceDirection) -> Self {
+        let normal = face.normal();
+        Self::new(self.x + normal.x, self.y + normal.y, self.z + normal.z)
+    }
+
+    /// Where the rasterizer places the chunk's mesh, relative to the render
+    /// origin. It is centred on the chunk's x and z, so it sits half a chunk
+    /// below `min_block` along both.
+    pub fn origin(self, render_origin: IVec3) -> [f32; 3] {
+        let half = CHUNK_SIZE as f32 / 2.0;
+        let min = (self.min_block() - render_origin).as_vec3();
+        [min.x - half, min.y, min.z - half]
+    }
+}
+
+/// The layers of chunks holding blocks whose y is in `heights`.
+pub fn chunk_layers(heights: RangeInclusive<i32>) -> RangeInclusive<i32> {
+    let layer = |y| BlockPos::new(0, y, 0).chunk().y;
+    layer(*heights.start())..=layer(*heights.end())
+}
+
+/// A cell within its chunk; every axis is in `0..CHUNK_SIZE`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct LocalPos {
+    x: u8,
+    y: u8,
+    z: u8,
+}
+
+impl LocalPos {
+    pub fn new(x: usize, y: usize, z: usize) -> Self {
+        debug_assert!(
+            x < CHUNK_SIZE && y < CHUNK_SIZE && z < CHUNK_SIZE,
+            "({x}, {y}, {z}) is outside a chunk"
+        );
+        Self {
+            x: x as u8,
+            y: y as u8,
+            z: z as u8,
+        }
+    }
+
+    /// Inverse of [`Self::index`].
+    pub fn from_index(index: usize) -> Self {
+        Self::new(
+            index % CHUNK_SIZE,
+            index / (CHUNK_SIZE * CHUNK_SIZE),
+            index / CHUNK_SIZE % CHUNK_SIZE,
+        )
+    }
+
+    /// Index of the cell in a chunk's per-cell arrays: x fastest, then z,
+    /// then y.
+    pub fn index(self) -> usize {
+        self.x as usize + CHUNK_SIZE * (self.z as usize + CHUNK_SIZE * self.y as usize)
+    }
+
+    /// Every cell of a chunk, in index order.
+    pub fn all() -> impl Iterator<Item = Self> {
+        (0..CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE).map(Self::from_index)
+    }
+
+    pub fn x(self) -> usize {
+        self.x as usize
+    }
+
+    pub fn y(self) -> usize {
+        self.y as usize
+    }
+
+    pub fn z(self) -> usize {
+        self.z as usize
+    }
+
+    pub fn as_ivec3(self) -> IVec3 {
+        IVec3::new(self.x as i32, self.y as i32, self.z as i32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    proptest! {
+        #[test]
+        fn split_positions_rebuild_the_same_block(x in -100..100i32, y in -100..100i32, z in -100..100i32) {
+            let position = BlockPos::new(x, y, z);
+            let (chunk, local) = position.split();
+            prop_assert_eq!(chunk.block(local), position.0);
+            prop_assert_eq!(LocalPos::from_index(local.index()), local);
+            prop_assert!(local.index() < CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE);
+        }
+    }
+
+    #[test]
+    fn blocks_below_zero_round_down_into_the_previous_chunk() {
+        let (chunk, local) = BlockPos::new(-1, 0, -16).split();
+        assert_eq!(chunk, ChunkPos::new(-1, 0, -1));
+        assert_eq!(local.as_ivec3(), IVec3::new(15, 0, 0));
+    }
+
+    #[test]
+    fn height_ranges_cover_every_layer_they_touch() {
+        assert_eq!(chunk_layers(-1..=16), -1..=1);
+        assert_eq!(chunk_layers(0..=15), 0..=0);
+    }
+}
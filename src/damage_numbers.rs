@@ -0,0 +1,77 @@
+use glam::Vec3;
+
+use crate::camera::{Camera, Projection};
+use crate::text::FloatingLabel;
+
+/// Seconds a number stays up, fading out over the second half.
+const LIFETIME: f32 = 0.9;
+/// Blocks per second a number drifts upwards.
+const RISE_SPEED: f32 = 1.2;
+const COLOR: [f32; 4] = [1.0, 0.35, 0.25, 1.0];
+
+struct Popup {
+    position: Vec3,
+    text: String,
+    age: f32,
+}
+
+/// Damage dealt to entities, floating up from where it landed.
+pub struct DamageNumbers {
+    popups: Vec<Popup>,
+}
+
+impl DamageNumbers {
+    pub fn new() -> Self {
+        Self { popups: Vec::new() }
+    }
+
+    pub fn push(&mut self, position: Vec3, amount: f32) {
+        let text = if amount.fract().abs() < 0.05 {
+            format!("{amount:.0}")
+        } else {
+            format!("{amount:.1}")
+        };
+        self.popups.push(Popup {
+            position,
+            text,
+            age: 0.0,
+        });
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        for popup in &mut self.popups {
+            popup.age += dt;
+            popup.position.y += RISE_SPEED * dt;
+        }
+        self.popups.retain(|popup| popup.age < LIFETIME);
+    }
+
+    /// Screen-space labels for the numbers in front of `camera`.
+    pub fn labels(
+        &self,
+        camera: &Camera,
+        projection: &Projection,
+        viewport: [u32; 2],
+    ) -> Vec<FloatingLabel> {
+        let view_proj = projection.matrix() * camera.view_matrix();
+        self.popups
+            .iter()
+            .filter_map(|popup| {
+                let clip = view_proj * popup.position.extend(1.0);
+                if clip.w <= 0.0 {
+                    return None;
+                }
+                let ndc = clip.truncate() / clip.w;
+                let fade = (2.0 * (1.0 - popup.age / LIFETIME)).min(1.0);
+                Some(FloatingLabel {
+                    text: popup.text.clone(),
+                    position: [
+                        (ndc.x * 0.5 + 0.5) * viewport[0] as f32,
+                        (0.5 - ndc.y * 0.5) * viewport[1] as f32,
+                    ],
+                    color: [COLOR[0], COLOR[1], COLOR[2], COLOR[3] * fade],
+                })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,71 @@
+use winit::event::VirtualKeyCode;
+
+/// What killed the player.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeathCause {
+    Void,
+    Lurker,
+}
+
+impl DeathCause {
+    pub fn message(self) -> &'static str {
+        match self {
+            DeathCause::Void => "Fell out of the world",
+            DeathCause::Lurker => "Killed by a lurker",
+        }
+    }
+}
+
+/// Option picked on the death screen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeathChoice {
+    /// Back to the spawn point at full health.
+    Respawn,
+    /// Keep watching the ended hardcore world as a spectator.
+    Spectate,
+    Quit,
+}
+
+/// Screen shown over the world after the player dies, until they pick what
+/// to do next.
+///
+/// A hardcore world cannot be respawned in, so it offers spectating instead.
+pub struct DeathScreen {
+    cause: DeathCause,
+    hardcore: bool,
+}
+
+impl DeathScreen {
+    pub fn new(cause: DeathCause, hardcore: bool) -> Self {
+        Self { cause, hardcore }
+    }
+
+    pub fn cause(&self) -> DeathCause {
+        self.cause
+    }
+
+    /// Centered lines of the screen, top first.
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![
+            "You died!".to_string(),
+            self.cause.message().to_string(),
+            String::new(),
+        ];
+        if self.hardcore {
+            lines.push("Hardcore: this world is over".to_string());
+            lines.push("[S] Spectate   [Q] Quit".to_string());
+        } else {
+            lines.push("[R] Respawn   [Q] Quit".to_string());
+        }
+        lines
+    }
+
+    pub fn handle_key(&self, key: VirtualKeyCode) -> Option<DeathChoice> {
+        match key {
+            VirtualKeyCode::R if !self.hardcore => Some(DeathChoice::Respawn),
+            VirtualKeyCode::S if self.hardcore => Some(DeathChoice::Spectate),
+            VirtualKeyCode::Q => Some(DeathChoice::Quit),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,296 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use winit::dpi::{LogicalSize, PhysicalSize};
+use winit::event_loop::EventLoopWindowTarget;
+use winit::window::{Window, WindowBuilder, WindowId};
+
+use crate::render;
+use crate::text::{BarGraph, DebugOverlay, OverlayContent};
+
+/// Samples kept for each graph, one per frame.
+const GRAPH_SAMPLES: usize = 240;
+const GRAPH_WIDTH: f32 = 360.0;
+const GRAPH_HEIGHT: f32 = 80.0;
+/// Frame time at the top of the graphs.
+const GRAPH_MAX_MS: f32 = 50.0;
+/// Marks 60 and 30 FPS on the graphs.
+const GRAPH_GUIDES_MS: [f32; 2] = [1000.0 / 60.0, 1000.0 / 30.0];
+/// The window redraws at most this often; it shows trends, not every frame.
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+/// Log lines kept for the window.
+const LOG_CAPACITY: usize = 200;
+const CLEAR_COLOR: wgpu::Color = wgpu::Color {
+    r: 0.04,
+    g: 0.04,
+    b: 0.06,
+    a: 1.0,
+};
+
+static LOG_LINES: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// Installs the logger: `env_logger` as configured by `RUST_LOG`, plus a
+/// copy of this crate's info messages and everyone's warnings for the debug
+/// window, whatever `RUST_LOG` says.
+pub fn init_logging() {
+    let inner = env_logger::Builder::from_default_env().build();
+    log::set_max_level(inner.filter().max(LevelFilter::Info));
+    log::set_boxed_logger(Box::new(CapturingLogger { inner })).expect("logger already installed");
+}
+
+struct CapturingLogger {
+    inner: env_logger::Logger,
+}
+
+impl CapturingLogger {
+    fn captures(metadata: &Metadata) -> bool {
+        metadata.level() <= Level::Warn
+            || (metadata.level() <= Level::Info
+                && metadata.target().starts_with(env!("CARGO_CRATE_NAME")))
+    }
+}
+
+impl Log for CapturingLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.inner.enabled(metadata) || Self::captures(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if self.inner.matches(record) {
+            self.inner.log(record);
+        }
+        if Self::captures(record.metadata())
+            && let Ok(mut lines) = LOG_LINES.lock()
+        {
+            if lines.len() == LOG_CAPACITY {
+                lines.pop_front();
+            }
+            lines.push_back(format!("{:<5} {}", record.level(), record.args()));
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+/// The newest `count` captured log lines, oldest first.
+pub fn recent_log_lines(count: usize) -> Vec<String> {
+    let Ok(lines) = LOG_LINES.lock() else {
+        return Vec::new();
+    };
+    lines
+        .iter()
+        .skip(lines.len().saturating_sub(count))
+        .cloned()
+        .collect()
+}
+
+/// What the game shows in the debug window besides its own graphs and log.
+pub struct DebugWindowContent<'a> {
+    /// Counters and timings, one per line.
+    pub stats: &'a str,
+    /// The chunk map, drawn beside the stats.
+    pub chunk_map: &'a str,
+}
+
+/// A second window for development sessions, with frame time graphs, a
+/// chunk map, and the log, so the main window can stay clean.
+///
+/// It shares the game's device and event loop; only its surface and text
+/// overlay are its own.
+pub struct DebugWindow {
+    /// Declared before the window so it is dropped first.
+    surface: wgpu::Surface,
+    window: Window,
+    surface_config: wgpu::SurfaceConfiguration,
+    overlay: DebugOverlay,
+    /// Milliseconds per frame, oldest first.
+    frame_ms: VecDeque<f32>,
+    /// CPU milliseconds the renderer spent per frame, oldest first.
+    render_ms: VecDeque<f32>,
+    last_redraw: Instant,
+}
+
+impl DebugWindow {
+    pub fn open(
+        target: &EventLoopWindowTarget<()>,
+        instance: &wgpu::Instance,
+        adapter: &wgpu::Adapter,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Option<Self> {
+        let window = match WindowBuilder::new()
+            .with_title("Rustcraft debug")
+            .with_inner_size(LogicalSize::new(960.0, 720.0))
+            .build(target)
+        {
+            Ok(window) => window,
+            Err(err) => {
+                log::warn!("Failed to open the debug window: {}", err);
+                return None;
+            }
+        };
+        // SAFETY: the surface is stored beside the window and dropped before it.
+        let surface = match unsafe { instance.create_surface(&window) } {
+            Ok(surface) => surface,
+            Err(err) => {
+                log::warn!("Failed to create the debug window surface: {}", err);
+                return None;
+            }
+        };
+        let caps = surface.get_capabilities(adapter);
+        let Some(&fallback_format) = caps.formats.first() else {
+            log::warn!("The debug window surface is not supported by the adapter");
+            return None;
+        };
+        // Same choice as the main window, so the overlay colors match.
+        let format = caps
+            .formats
+            .iter()
+            .copied()
+            .find(|format| !format.is_srgb())
+            .unwrap_or(fallback_format);
+        let size = window.inner_size();
+        let surface_config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format,
+            width: size.width.max(1),
+            height: size.height.max(1),
+            present_mode: wgpu::PresentMode::Fifo,
+            alpha_mode: caps.alpha_modes[0],
+            view_formats: vec![],
+        };
+        surface.configure(device, &surface_config);
+
+        Some(Self {
+            surface,
+            window,
+            surface_config,
+            overlay: DebugOverlay::new(device, queue, format),
+            frame_ms: VecDeque::with_capacity(GRAPH_SAMPLES),
+            render_ms: VecDeque::with_capacity(GRAPH_SAMPLES),
+            last_redraw: Instant::now(),
+        })
+    }
+
+    pub fn id(&self) -> WindowId {
+        self.window.id()
+    }
+
+    /// Adds a frame to the graphs.
+    pub fn record_frame(&mut self, frame_ms: f32, render_ms: f32) {
+        for (samples, value) in [
+            (&mut self.frame_ms, frame_ms),
+            (&mut self.render_ms, render_ms),
+        ] {
+            if samples.len() == GRAPH_SAMPLES {
+                samples.pop_front();
+            }
+            samples.push_back(value);
+        }
+    }
+
+    pub fn resize(&mut self, device: &wgpu::Device, size: PhysicalSize<u32>) {
+        if size.width == 0 || size.height == 0 {
+            return;
+        }
+        self.surface_config.width = size.width;
+        self.surface_config.height = size.height;
+        self.surface.configure(device, &self.surface_config);
+    }
+
+    /// Asks for a redraw unless the window was drawn recently.
+    pub fn request_redraw(&self) {
+        if self.last_redraw.elapsed() >= REDRAW_INTERVAL {
+            self.window.request_redraw();
+        }
+    }
+
+    pub fn render(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        content: &DebugWindowContent,
+    ) -> Result<(), wgpu::SurfaceError> {
+        self.last_redraw = Instant::now();
+        let viewport = [self.surface_config.width, self.surface_config.height];
+        let line_height = self.overlay.line_height();
+        let [left, top] = self.overlay.text_origin();
+
+        // Stats, then the chunk map; graphs stacked on the right; the log
+        // fills the rest of the window from the bottom up.
+        let text = format!("{}\n\n{}", content.stats.trim_end(), content.chunk_map);
+        let text_rows = text.lines().count();
+        let graph_left = viewport[0] as f32 - left - GRAPH_WIDTH;
+        let graph_top = top + line_height;
+        let frame_values = graph_values(&self.frame_ms);
+        let render_values = graph_values(&self.render_ms);
+        let guides = GRAPH_GUIDES_MS.map(|ms| ms / GRAPH_MAX_MS);
+        let frame_label = format!("Frame ms (max {:.0})", GRAPH_MAX_MS);
+        let render_label = "Renderer CPU ms";
+        let graphs = [
+            BarGraph {
+                label: &frame_label,
+                origin: [graph_left, graph_top],
+                size: [GRAPH_WIDTH, GRAPH_HEIGHT],
+                values: &frame_values,
+                guides: &guides,
+            },
+            BarGraph {
+                label: render_label,
+                origin: [graph_left, graph_top + GRAPH_HEIGHT + line_height * 2.0],
+                size: [GRAPH_WIDTH, GRAPH_HEIGHT],
+                values: &render_values,
+                guides: &guides,
+            },
+        ];
+        let text_bottom = top + (text_rows + 1) as f32 * line_height;
+        let log_rows = ((viewport[1] as f32 - text_bottom - top) / line_height).max(0.0) as usize;
+        let log = recent_log_lines(log_rows);
+
+        self.overlay.prepare(
+            device,
+            queue,
+            viewport,
+            &OverlayContent {
+                text: &text,
+                console: &log,
+                graphs: &graphs,
+                ..Default::default()
+            },
+        );
+
+        let output = self.surface.get_current_texture()?;
+        let view = render::labeled_view(&output.texture, "Debug window surface view");
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Debug window encoder"),
+        });
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Debug window clear"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(CLEAR_COLOR),
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: None,
+        });
+        self.overlay.render(&mut encoder, &view);
+        queue.submit(std::iter::once(encoder.finish()));
+        output.present();
+        Ok(())
+    }
+
+    pub fn inner_size(&self) -> PhysicalSize<u32> {
+        self.window.inner_size()
+    }
+}
+
+fn graph_values(samples: &VecDeque<f32>) -> Vec<f32> {
+    samples.iter().map(|ms| ms / GRAPH_MAX_MS).collect()
+}
@@ -0,0 +1,331 @@
+use glam::IVec3;
+
+use crate::biome::{self, Biome, Climate};
+use crate::block::{BlockKind, FLOWER_VARIETIES};
+use crate::coords::{BlockPos, ChunkPos};
+use crate::rand::{Rng, Stream};
+use crate::terrain::{TerrainGenerator, snow_layers};
+use crate::world::CHUNK_SIZE;
+
+/// Furthest a feature reaches from its origin column, in blocks. Chunks look
+/// this far into neighbouring columns for features overlapping them.
+const MAX_FEATURE_RADIUS: i32 = 3;
+/// Furthest a tree's canopy reaches sideways from its trunk, in blocks.
+pub const TREE_RADIUS: i32 = 2;
+const MIN_TRUNK: i32 = 4;
+const MAX_TRUNK: i32 = 6;
+/// Highest a tree reaches above the ground it roots in: its tallest trunk
+/// and the two canopy layers over the trunk's top.
+pub const TREE_HEIGHT: i32 = MAX_TRUNK + 2;
+
+/// A small surface feature placed after the base terrain.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Feature {
+    /// One to three half-buried stone blobs.
+    Boulder,
+    /// A ragged disc of surface blocks turned to gravel.
+    GravelPatch,
+    /// A handful of flowers of one variety.
+    Flowers,
+}
+
+#[derive(Clone, Copy)]
+pub struct WeightedFeature {
+    pub feature: Feature,
+    pub weight: u32,
+}
+
+/// What a region decorates itself with.
+pub struct DecorationTable {
+    /// Feature placement attempts per chunk column.
+    pub attempts: u32,
+    /// Chance in `0.0..=1.0` that an attempt places anything.
+    pub density: f32,
+    pub features: &'static [WeightedFeature],
+    /// Tree placement attempts per chunk column; each roots where it lands
+    /// on grass.
+    pub trees: u32,
+}
+
+impl DecorationTable {
+    fn pick(&self, roll: u32) -> Option<Feature> {
+        let total: u32 = self.features.iter().map(|entry| entry.weight).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut roll = roll % total;
+        for entry in self.features {
+            if roll < entry.weight {
+                return Some(entry.feature);
+            }
+            roll -= entry.weight;
+        }
+        None
+    }
+}
+
+const fn weighted(feature: Feature, weight: u32) -> WeightedFeature {
+    WeightedFeature { feature, weight }
+}
+
+const SNOWY_TABLE: DecorationTable = DecorationTable {
+    attempts: 2,
+    density: 0.5,
+    features: &[
+        weighted(Feature::Boulder, 3),
+        weighted(Feature::GravelPatch, 1),
+    ],
+    trees: 1,
+};
+
+const DRY_TABLE: DecorationTable = DecorationTable {
+    attempts: 3,
+    density: 0.6,
+    features: &[
+        weighted(Feature::Boulder, 2),
+        weighted(Feature::GravelPatch, 3),
+        weighted(Feature::Flowers, 1),
+    ],
+    trees: 0,
+};
+
+/// Sparse: mountains already have plenty of rock showing.
+const MOUNTAIN_TABLE: DecorationTable = DecorationTable {
+    attempts: 2,
+    density: 0.35,
+    features: &[
+        weighted(Feature::Boulder, 2),
+        weighted(Feature::GravelPatch, 2),
+        weighted(Feature::Flowers, 1),
+    ],
+    trees: 1,
+};
+
+const TEMPERATE_TABLE: DecorationTable = DecorationTable {
+    attempts: 3,
+    density: 0.5,
+    features: &[
+        weighted(Feature::Boulder, 1),
+        weighted(Feature::GravelPatch, 1),
+        weighted(Feature::Flowers, 3),
+    ],
+    trees: 2,
+};
+
+const LUSH_TABLE: DecorationTable = DecorationTable {
+    attempts: 4,
+    density: 0.7,
+    features: &[weighted(Feature::Boulder, 1), weighted(Feature::Flowers, 6)],
+    trees: 5,
+};
+
+/// Decoration table for the biome at a feature's origin column; the wetter
+/// plains get the lush one.
+pub fn table_for(climate: Climate) -> &'static DecorationTable {
+    match Biome::from_climate(climate) {
+        Biome::Snow => &SNOWY_TABLE,
+        Biome::Mountains => &MOUNTAIN_TABLE,
+        Biome::Desert => &DRY_TABLE,
+        Biome::Plains if climate.humidity > 0.65 => &LUSH_TABLE,
+        Biome::Plains => &TEMPERATE_TABLE,
+    }
+}
+
+/// Block edits of every feature overlapping the chunk at `coord`, restricted
+/// to that chunk.
+///
+/// Features are a pure function of their origin column, so each chunk
+/// regenerates the parts of its neighbours' features that reach into it and
+/// features line up across chunk borders whatever order chunks load in.
+pub fn chunk_decorations(terrain: TerrainGenerator, coord: ChunkPos) -> Vec<(IVec3, BlockKind)> {
+    let size = CHUNK_SIZE as i32;
+    let (column_x, column_z) = (coord.x, coord.z);
+    let reach = (MAX_FEATURE_RADIUS + size - 1) / size;
+
+    let mut edits = Vec::new();
+    for dz in -reach..=reach {
+        for dx in -reach..=reach {
+            place_column_features(
+                terrain,
+                column_x + dx,
+                column_z + dz,
+                &mut |position, kind| {
+                    if BlockPos(position).chunk() == coord {
+                        edits.push((position, kind));
+                    }
+                },
+            );
+        }
+    }
+    edits
+}
+
+fn place_column_features(
+    terrain: TerrainGenerator,
+    column_x: i32,
+    column_z: i32,
+    emit: &mut impl FnMut(IVec3, BlockKind),
+) {
+    let size = CHUNK_SIZE as i32;
+    let mut rng = Stream::Decoration.column(terrain.seed, column_x, column_z);
+    let table = column_table(column_x, column_z);
+    for _ in 0..table.attempts {
+        let x = column_x * size + rng.below(size as u32) as i32;
+        let z = column_z * size + rng.below(size as u32) as i32;
+        let roll = rng.next();
+        if rng.unit() >= table.density {
+            continue;
+        }
+        let Some(feature) = table.pick(roll) else {
+            continue;
+        };
+        let Some(height) = terrain.surface_height(x, z) else {
+            continue;
+        };
+        let origin = IVec3::new(x, height, z);
+        match feature {
+            Feature::Boulder => place_boulder(origin, &mut rng, emit),
+            Feature::GravelPatch => place_gravel_patch(terrain, origin, &mut rng, emit),
+            Feature::Flowers => place_flowers(terrain, origin, &mut rng, emit),
+        }
+    }
+}
+
+/// Trees rooted in chunk column (`column_x`, `column_z`), trunks first.
+/// Canopies reach up to `TREE_RADIUS` blocks into the neighbouring columns.
+pub fn column_trees(
+    terrain: TerrainGenerator,
+    column_x: i32,
+    column_z: i32,
+    emit: &mut impl FnMut(IVec3, BlockKind),
+) {
+    let size = CHUNK_SIZE as i32;
+    let mut rng = Stream::Trees.column(terrain.seed, column_x, column_z);
+    for _ in 0..column_table(column_x, column_z).trees {
+        let x = column_x * size + rng.below(size as u32) as i32;
+        let z = column_z * size + rng.below(size as u32) as i32;
+        let trunk = MIN_TRUNK + rng.below((MAX_TRUNK - MIN_TRUNK + 1) as u32) as i32;
+        let shape = rng.next();
+        // Like flowers, trees only root in grass.
+        if let Some(height) = terrain.surface_height(x, z)
+            && terrain.block_at(x, height, z) == BlockKind::Grass.id()
+        {
+            place_tree(IVec3::new(x, height + 1, z), trunk, shape, emit);
+        }
+    }
+}
+
+/// A trunk `trunk` blocks tall from `base`, under two wide canopy layers
+/// around its top and two narrow ones over it. Bits of `shape` pick which
+/// corners of the wide layers are trimmed.
+fn place_tree(base: IVec3, trunk: i32, shape: u32, emit: &mut impl FnMut(IVec3, BlockKind)) {
+    for y in 0..trunk {
+        emit(base + IVec3::Y * y, BlockKind::Log);
+    }
+    let crown = base.y + trunk - 1;
+    let mut corner = 0;
+    for layer in -1..=2 {
+        let radius = if layer <= 0 { TREE_RADIUS } else { 1 };
+        for z in -radius..=radius {
+            for x in -radius..=radius {
+                if x.abs() == radius && z.abs() == radius {
+                    // The top layer is always a plus.
+                    let trimmed = layer == 2 || radius == TREE_RADIUS && shape >> corner & 1 == 1;
+                    corner += 1;
+                    if trimmed {
+                        continue;
+                    }
+                }
+                // The trunk's own cells are logs.
+                if (x, z) == (0, 0) && layer <= 0 {
+                    continue;
+                }
+                emit(
+                    IVec3::new(base.x + x, crown + layer, base.z + z),
+                    BlockKind::Leaves,
+                );
+            }
+        }
+    }
+}
+
+fn place_boulder(origin: IVec3, rng: &mut Rng, emit: &mut impl FnMut(IVec3, BlockKind)) {
+    let blobs = 1 + rng.below(3);
+    for _ in 0..blobs {
+        let center = origin + IVec3::new(rng.below(3) as i32 - 1, 1, rng.below(3) as i32 - 1);
+        let radius = 1.0 + rng.unit() * 0.8;
+        let reach = radius.ceil() as i32;
+        for y in -reach..=reach {
+            for z in -reach..=reach {
+                for x in -reach..=reach {
+                    let offset = IVec3::new(x, y, z);
+                    if offset.as_vec3().length() <= radius {
+                        emit(center + offset, BlockKind::Stone);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn place_gravel_patch(
+    terrain: TerrainGenerator,
+    origin: IVec3,
+    rng: &mut Rng,
+    emit: &mut impl FnMut(IVec3, BlockKind),
+) {
+    let radius = 2.0 + rng.unit();
+    let reach = radius.ceil() as i32;
+    for z in -reach..=reach {
+        for x in -reach..=reach {
+            let distance = ((x * x + z * z) as f32).sqrt();
+            // Ragged edge: cells near the rim are kept only some of the time.
+            if distance > radius || distance > radius - 1.0 && rng.unit() < 0.5 {
+                continue;
+            }
+            let world_x = origin.x + x;
+            let world_z = origin.z + z;
+            if let Some(height) = terrain.surface_height(world_x, world_z) {
+                emit(IVec3::new(world_x, height, world_z), BlockKind::Gravel);
+            }
+        }
+    }
+}
+
+fn place_flowers(
+    terrain: TerrainGenerator,
+    origin: IVec3,
+    rng: &mut Rng,
+    emit: &mut impl FnMut(IVec3, BlockKind),
+) {
+    let variety = rng.below(FLOWER_VARIETIES as u32) as u8;
+    let count = 3 + rng.below(4);
+    for _ in 0..count {
+        let world_x = origin.x + rng.below(5) as i32 - 2;
+        let world_z = origin.z + rng.below(5) as i32 - 2;
+        // Snow covers the cell a flower would grow in.
+        if snow_layers(world_x, world_z).is_some() {
+            continue;
+        }
+        // Flowers only root in grass, which also keeps them off beaches and
+        // out of the sea.
+        if let Some(height) = terrain.surface_height(world_x, world_z)
+            && terrain.block_at(world_x, height, world_z) == BlockKind::Grass.id()
+        {
+            emit(
+                IVec3::new(world_x, height + 1, world_z),
+                BlockKind::Flower(variety),
+            );
+        }
+    }
+}
+
+/// Decoration table of a chunk column. There is one per column, sampled at
+/// its centre, so every chunk asking about the column agrees on what it holds.
+fn column_table(column_x: i32, column_z: i32) -> &'static DecorationTable {
+    let size = CHUNK_SIZE as i32;
+    table_for(biome::climate_at(
+        column_x * size + size / 2,
+        column_z * size + size / 2,
+    ))
+}
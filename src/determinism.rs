@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use glam::Vec3;
+use log::{info, warn};
+
+use crate::coords::ChunkPos;
+use crate::world::World;
+
+/// Seconds every frame advances the simulation by in deterministic mode,
+/// whatever the frame actually took.
+pub const FIXED_TIMESTEP: f32 = 1.0 / 60.0;
+
+/// 64-bit FNV-1a, which is stable across runs and platforms, unlike std's
+/// randomly keyed hasher.
+pub struct StateHasher(u64);
+
+impl StateHasher {
+    pub fn new() -> Self {
+        Self(0xCBF2_9CE4_8422_2325)
+    }
+
+    pub fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01B3);
+        }
+    }
+
+    pub fn write_u64(&mut self, value: u64) {
+        self.write(&value.to_le_bytes());
+    }
+
+    /// Hashes the exact bits, so any drift in a float shows up.
+    pub fn write_vec3(&mut self, value: Vec3) {
+        for axis in value.to_array() {
+            self.write(&axis.to_bits().to_le_bytes());
+        }
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Per-tick checksums of the simulation, recorded to a file and compared
+/// against one from an earlier run.
+///
+/// Two runs from the same world, seed and inputs in deterministic mode must
+/// produce the same checksums tick for tick; the first tick where they
+/// differ is where the simulations diverged.
+pub struct DivergenceChecker {
+    tick: u64,
+    record: Option<BufWriter<File>>,
+    reference: Option<Vec<u64>>,
+    diverged: bool,
+    /// Block hashes of each chunk, keyed by the `Chunk::revision` they were taken at.
+    chunk_hashes: HashMap<ChunkPos, (u64, u64)>,
+}
+
+impl DivergenceChecker {
+    pub fn new(record: Option<&Path>, verify: Option<&Path>) -> Self {
+        let record = record.and_then(|path| match create_record(path) {
+            Ok(file) => Some(file),
+            Err(err) => {
+                warn!("Failed to create checksum file {}: {}", path.display(), err);
+                None
+            }
+        });
+        let reference = verify.and_then(|path| match read_reference(path) {
+            Ok(checksums) => Some(checksums),
+            Err(err) => {
+                warn!("Failed to read checksum file {}: {}", path.display(), err);
+                None
+            }
+        });
+        Self {
+            tick: 0,
+            record,
+            reference,
+            diverged: false,
+            chunk_hashes: HashMap::new(),
+        }
+    }
+
+    /// Checksums the state at the end of a tick, then records and verifies it.
+    pub fn check(&mut self, checksum: u64) {
+        self.tick += 1;
+        if let Some(record) = &mut self.record
+            && let Err(err) = writeln!(record, "{} {:016x}", self.tick, checksum)
+        {
+            warn!("Failed to record checksum: {}", err);
+            self.record = None;
+        }
+
+        let Some(reference) = &self.reference else {
+            return;
+        };
+        if self.diverged {
+            return;
+        }
+        match reference.get(self.tick as usize - 1) {
+            Some(&expected) if expected != checksum => {
+                warn!(
+                    "Simulation diverged from the reference at tick {}: expected {:016x}, got {:016x}",
+                    self.tick, expected, checksum
+                );
+                self.diverged = true;
+            }
+            Some(_) if self.tick as usize == reference.len() => {
+                info!("Simulation matched all {} reference ticks", reference.len());
+            }
+            _ => {}
+        }
+    }
+
+    /// Hashes every loaded chunk's blocks in coordinate order, rehashing only
+    /// the chunks that changed since the last call.
+    pub fn world_checksum(&mut self, world: &World) -> u64 {
+        self.chunk_hashes
+            .retain(|coord, _| world.chunk(*coord).is_some());
+        let mut coords: Vec<ChunkPos> = world.iter_chunks().map(|(coord, _)| *coord).collect();
+        coords.sort_by_key(|coord| (coord.x, coord.y, coord.z));
+
+        let mut hasher = StateHasher::new();
+        for coord in coords {
+            let chunk = world.chunk(coord).expect("coordinate of a loaded chunk");
+            let hash = match self.chunk_hashes.get(&coord) {
+                Some(&(revision, hash)) if revision == chunk.revision() => hash,
+                _ => {
+                    let mut chunk_hasher = StateHasher::new();
+                    chunk_hasher.write(chunk.blocks());
+                    let hash = chunk_hasher.finish();
+                    self.chunk_hashes.insert(coord, (chunk.revision(), hash));
+                    hash
+                }
+            };
+            for axis in [coord.x, coord.y, coord.z] {
+                hasher.write(&axis.to_le_bytes());
+            }
+            hasher.write_u64(hash);
+        }
+        hasher.finish()
+    }
+}
+
+fn create_record(path: &Path) -> io::Result<BufWriter<File>> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    Ok(BufWriter::new(File::create(path)?))
+}
+
+/// Reads a file written by `DivergenceChecker::check`: one `tick checksum`
+/// line per tick, in order.
+fn read_reference(path: &Path) -> io::Result<Vec<u64>> {
+    let text = fs::read_to_string(path)?;
+    text.lines()
+        .map(|line| {
+            line.split_whitespace()
+                .nth(1)
+                .and_then(|checksum| u64::from_str_radix(checksum, 16).ok())
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("malformed checksum line '{line}'"),
+                    )
+                })
+        })
+        .collect()
+}
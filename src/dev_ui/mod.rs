@@ -0,0 +1,316 @@
+//! Developer tools drawn with egui over the game and its HUD: settings,
+//! material editing, renderer timings and the command console.
+//!
+//! Only built with the `dev-ui` feature.
+
+mod painter;
+
+use std::collections::VecDeque;
+
+use egui::{Color32, Context, Sense, Shape, Stroke};
+use winit::event::{ElementState, KeyboardInput, VirtualKeyCode, WindowEvent};
+use winit::window::Window;
+
+use crate::block::BlockKind;
+use crate::config::WaterQuality;
+use crate::console::CommandConsole;
+use crate::debug_window::recent_log_lines;
+use crate::material_editor::{MaterialEditor, MaterialField};
+use crate::render::{RenderTimings, RendererKind};
+
+use painter::EguiPainter;
+
+const TOGGLE_KEY: VirtualKeyCode = VirtualKeyCode::F10;
+/// Frames kept for the profiler's frame time graph.
+const FRAME_HISTORY: usize = 240;
+/// Frame time at the top of the profiler's graph.
+const GRAPH_MAX_MS: f32 = 50.0;
+const CONSOLE_LOG_LINES: usize = 40;
+
+/// Runtime settings the dev UI may change, borrowed from the game each frame.
+pub struct DevSettings<'a> {
+    pub gamma: &'a mut f32,
+    pub light_shafts: &'a mut bool,
+    pub path_tracing: &'a mut bool,
+    pub water_quality: &'a mut WaterQuality,
+}
+
+/// What the dev UI shows and edits this frame.
+pub struct DevUiFrame<'a> {
+    pub settings: DevSettings<'a>,
+    pub materials: &'a mut MaterialEditor,
+    pub console: &'a CommandConsole,
+    pub renderer: RendererKind,
+    pub timings: RenderTimings,
+    pub frame_ms: f32,
+    pub viewport: [u32; 2],
+}
+
+/// The egui context, its winit input state, and the windows built each frame
+/// while the UI is shown with F10.
+pub struct DevUi {
+    context: Context,
+    state: egui_winit::State,
+    painter: EguiPainter,
+    visible: bool,
+    frame_times: VecDeque<f32>,
+    command: String,
+}
+
+impl DevUi {
+    pub fn new(
+        device: &wgpu::Device,
+        surface_format: wgpu::TextureFormat,
+        window: &Window,
+    ) -> Self {
+        let mut state = egui_winit::State::new(window);
+        state.set_pixels_per_point(window.scale_factor() as f32);
+        state.set_max_texture_side(device.limits().max_texture_dimension_2d as usize);
+        Self {
+            context: Context::default(),
+            state,
+            painter: EguiPainter::new(device, surface_format),
+            visible: false,
+            frame_times: VecDeque::with_capacity(FRAME_HISTORY),
+            command: String::new(),
+        }
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Toggles the UI on F10 and, while shown, passes events to egui.
+    /// Returns `true` when egui wants the event for itself.
+    pub fn input(&mut self, event: &WindowEvent) -> bool {
+        if let WindowEvent::KeyboardInput {
+            input:
+                KeyboardInput {
+                    state: ElementState::Pressed,
+                    virtual_keycode: Some(TOGGLE_KEY),
+                    ..
+                },
+            ..
+        } = event
+        {
+            self.visible = !self.visible;
+            return true;
+        }
+        if !self.visible {
+            return false;
+        }
+        self.state.on_event(&self.context, event).consumed
+    }
+
+    /// Builds this frame's windows and uploads what they draw. Returns a
+    /// command line submitted from the console window, if any.
+    pub fn run(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        window: &Window,
+        mut frame: DevUiFrame,
+    ) -> Option<String> {
+        if self.frame_times.len() == FRAME_HISTORY {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(frame.frame_ms);
+        if !self.visible {
+            return None;
+        }
+
+        let input = self.state.take_egui_input(window);
+        let context = self.context.clone();
+        let mut submitted = None;
+        let output = context.run(input, |ctx| {
+            settings_window(ctx, &mut frame);
+            materials_window(ctx, frame.materials);
+            self.profiler_window(ctx, &frame);
+            submitted = self.console_window(ctx, frame.console);
+        });
+        self.state
+            .handle_platform_output(window, &self.context, output.platform_output);
+
+        let primitives = self.context.tessellate(output.shapes);
+        self.painter.prepare(
+            device,
+            queue,
+            &primitives,
+            &output.textures_delta,
+            frame.viewport,
+            self.context.pixels_per_point(),
+        );
+        submitted
+    }
+
+    /// Draws the UI over everything else in `view`.
+    pub fn render(&self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
+        if !self.visible {
+            return;
+        }
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Dev UI pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: None,
+        });
+        self.painter.draw(&mut pass);
+    }
+
+    fn profiler_window(&self, ctx: &Context, frame: &DevUiFrame) {
+        egui::Window::new("Profiler")
+            .default_pos([16.0, 360.0])
+            .show(ctx, |ui| {
+                let timings = &frame.timings;
+                ui.label(format!(
+                    "{}: {:.2} ms frame, {:.2} ms renderer CPU",
+                    frame.renderer.as_str(),
+                    frame.frame_ms,
+                    timings.total_ms
+                ));
+                egui::Grid::new("profiler_timings").show(ui, |ui| {
+                    let rows = [
+                        ("Scene upload", timings.scene_ms),
+                        ("Uniforms", timings.uniforms_ms),
+                        ("Compute", timings.compute_ms),
+                        ("Present", timings.present_ms),
+                        ("GPU compute", timings.gpu_compute_ms),
+                        ("GPU present", timings.gpu_present_ms),
+                    ];
+                    for (label, ms) in rows {
+                        ui.label(label);
+                        ui.label(format!("{ms:>6.2} ms"));
+                        ui.end_row();
+                    }
+                });
+                if frame.renderer == RendererKind::Rasterized {
+                    ui.label(format!(
+                        "Chunks: {} drawn, {} culled",
+                        timings.drawn_chunks, timings.culled_chunks
+                    ));
+                } else {
+                    ui.label(format!(
+                        "Voxels: {}  Samples: {}",
+                        timings.voxels, timings.samples
+                    ));
+                    if let Some(traversal) = timings.traversal {
+                        ui.label(format!(
+                            "Traversal: {:.1} steps/ray, {} skips",
+                            traversal.steps_per_ray(),
+                            traversal.skips
+                        ));
+                    }
+                }
+
+                let (rect, _) = ui.allocate_exact_size(egui::vec2(260.0, 64.0), Sense::hover());
+                let painter = ui.painter_at(rect);
+                painter.rect_filled(rect, 2.0, Color32::from_black_alpha(140));
+                let step = rect.width() / (FRAME_HISTORY - 1) as f32;
+                let points = self
+                    .frame_times
+                    .iter()
+                    .enumerate()
+                    .map(|(index, ms)| {
+                        let height = (ms / GRAPH_MAX_MS).min(1.0) * rect.height();
+                        egui::pos2(rect.left() + index as f32 * step, rect.bottom() - height)
+                    })
+                    .collect();
+                painter.add(Shape::line(points, Stroke::new(1.0, Color32::LIGHT_GREEN)));
+            });
+    }
+
+    fn console_window(&mut self, ctx: &Context, console: &CommandConsole) -> Option<String> {
+        let mut submitted = None;
+        egui::Window::new("Console")
+            .default_pos([320.0, 360.0])
+            .default_width(420.0)
+            .show(ctx, |ui| {
+                egui::ScrollArea::vertical()
+                    .max_height(180.0)
+                    .stick_to_bottom(true)
+                    .show(ui, |ui| {
+                        for line in recent_log_lines(CONSOLE_LOG_LINES) {
+                            ui.monospace(line);
+                        }
+                        for message in console.messages() {
+                            ui.monospace(message);
+                        }
+                    });
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut self.command)
+                        .hint_text("/command")
+                        .desired_width(f32::INFINITY),
+                );
+                if response.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter)) {
+                    let line = std::mem::take(&mut self.command);
+                    if !line.trim().is_empty() {
+                        submitted = Some(line);
+                    }
+                    response.request_focus();
+                }
+            });
+        submitted
+    }
+}
+
+fn settings_window(ctx: &Context, frame: &mut DevUiFrame) {
+    let settings = &mut frame.settings;
+    egui::Window::new("Settings")
+        .default_pos([16.0, 16.0])
+        .show(ctx, |ui| {
+            ui.add(egui::Slider::new(&mut *settings.gamma, 1.0..=3.0).text("Gamma"));
+            ui.checkbox(&mut *settings.light_shafts, "Light shafts");
+            ui.add_enabled(
+                frame.renderer == RendererKind::RayTraced,
+                egui::Checkbox::new(&mut *settings.path_tracing, "Path tracing"),
+            );
+            ui.horizontal(|ui| {
+                ui.label("Water");
+                ui.radio_value(
+                    &mut *settings.water_quality,
+                    WaterQuality::ScreenSpace,
+                    "Low",
+                );
+                ui.radio_value(&mut *settings.water_quality, WaterQuality::Planar, "High");
+            });
+        });
+}
+
+fn materials_window(ctx: &Context, materials: &mut MaterialEditor) {
+    egui::Window::new("Materials")
+        .default_pos([16.0, 150.0])
+        .show(ctx, |ui| {
+            let mut selected = materials.selected_block();
+            egui::ComboBox::from_label("Block")
+                .selected_text(selected.display_name())
+                .show_ui(ui, |ui| {
+                    for &kind in BlockKind::ALL
+                        .iter()
+                        .filter(|&&kind| kind != BlockKind::Air)
+                    {
+                        ui.selectable_value(&mut selected, kind, kind.display_name());
+                    }
+                });
+            materials.select_block(selected);
+
+            for field in MaterialField::ALL {
+                let (min, max) = field.range();
+                let mut value = materials.value(field);
+                if ui
+                    .add(egui::Slider::new(&mut value, min..=max).text(field.label()))
+                    .changed()
+                {
+                    materials.set_value(field, value);
+                }
+            }
+            if ui.button("Save").clicked() {
+                materials.save();
+            }
+        });
+}
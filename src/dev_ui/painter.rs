@@ -0,0 +1,326 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use egui::epaint::{ClippedPrimitive, ImageData, ImageDelta, Primitive, Vertex};
+use egui::{TextureId, TexturesDelta};
+
+use crate::render;
+use crate::ui::UiBatch;
+
+/// Draws egui's tessellated output with wgpu: its meshes flattened into one
+/// [`UiBatch`], then drawn a run at a time with each run's texture and clip
+/// rect.
+pub struct EguiPainter {
+    pipeline: wgpu::RenderPipeline,
+    texture_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    screen_buffer: wgpu::Buffer,
+    screen_bind_group: wgpu::BindGroup,
+    textures: HashMap<TextureId, EguiTexture>,
+    batch: UiBatch<Vertex>,
+    draws: Vec<MeshDraw>,
+}
+
+struct EguiTexture {
+    texture: wgpu::Texture,
+    bind_group: wgpu::BindGroup,
+}
+
+/// One mesh's vertices in the batch.
+struct MeshDraw {
+    texture: TextureId,
+    /// Scissor rect in physical pixels: x, y, width, height.
+    scissor: [u32; 4],
+    vertices: Range<u32>,
+}
+
+impl EguiPainter {
+    pub fn new(device: &wgpu::Device, surface_format: wgpu::TextureFormat) -> Self {
+        let screen_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Egui screen buffer"),
+            size: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let screen_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Egui screen bind group layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let screen_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Egui screen bind group"),
+            layout: &screen_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: screen_buffer.as_entire_binding(),
+            }],
+        });
+        let texture_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Egui texture bind group layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Egui sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let source = include_str!("painter.wgsl").replace(
+            "const DECODE_SRGB: bool = false;",
+            &format!(
+                "const DECODE_SRGB: bool = {};",
+                render::surface_takes_linear(surface_format)
+            ),
+        );
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Egui shader"),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Egui pipeline layout"),
+            bind_group_layouts: &[&screen_layout, &texture_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Egui pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: std::mem::size_of::<Vertex>() as wgpu::BufferAddress,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &wgpu::vertex_attr_array![
+                        0 => Float32x2,
+                        1 => Float32x2,
+                        2 => Unorm8x4
+                    ],
+                }],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: surface_format,
+                    blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        Self {
+            pipeline,
+            texture_layout,
+            sampler,
+            screen_buffer,
+            screen_bind_group,
+            textures: HashMap::new(),
+            batch: UiBatch::new(device, "Egui vertex buffer"),
+            draws: Vec::new(),
+        }
+    }
+
+    /// Applies texture changes and uploads the frame's meshes. Textures freed
+    /// this frame are dropped at once, since egui no longer references them.
+    pub fn prepare(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        primitives: &[ClippedPrimitive],
+        textures: &TexturesDelta,
+        size: [u32; 2],
+        pixels_per_point: f32,
+    ) {
+        for (id, delta) in &textures.set {
+            self.update_texture(device, queue, *id, delta);
+        }
+        for id in &textures.free {
+            self.textures.remove(id);
+        }
+
+        let points = [
+            size[0] as f32 / pixels_per_point,
+            size[1] as f32 / pixels_per_point,
+        ];
+        queue.write_buffer(
+            &self.screen_buffer,
+            0,
+            bytemuck::cast_slice(&[points[0], points[1], 0.0, 0.0]),
+        );
+
+        self.batch.clear();
+        self.draws.clear();
+        for primitive in primitives {
+            let Primitive::Mesh(mesh) = &primitive.primitive else {
+                continue;
+            };
+            let clip = primitive.clip_rect;
+            let min_x = (clip.min.x * pixels_per_point)
+                .round()
+                .clamp(0.0, size[0] as f32) as u32;
+            let min_y = (clip.min.y * pixels_per_point)
+                .round()
+                .clamp(0.0, size[1] as f32) as u32;
+            let max_x = (clip.max.x * pixels_per_point)
+                .round()
+                .clamp(0.0, size[0] as f32) as u32;
+            let max_y = (clip.max.y * pixels_per_point)
+                .round()
+                .clamp(0.0, size[1] as f32) as u32;
+            if max_x <= min_x || max_y <= min_y || mesh.indices.is_empty() {
+                continue;
+            }
+
+            let start = self.batch.vertex_count() as u32;
+            self.batch.extend(
+                mesh.indices
+                    .iter()
+                    .map(|&index| mesh.vertices[index as usize]),
+            );
+            self.draws.push(MeshDraw {
+                texture: mesh.texture_id,
+                scissor: [min_x, min_y, max_x - min_x, max_y - min_y],
+                vertices: start..self.batch.vertex_count() as u32,
+            });
+        }
+        self.batch.upload(device, queue);
+    }
+
+    pub fn draw<'a>(&'a self, pass: &mut wgpu::RenderPass<'a>) {
+        if self.batch.is_empty() {
+            return;
+        }
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, &self.screen_bind_group, &[]);
+        for draw in &self.draws {
+            let Some(texture) = self.textures.get(&draw.texture) else {
+                continue;
+            };
+            let [x, y, width, height] = draw.scissor;
+            pass.set_scissor_rect(x, y, width, height);
+            pass.set_bind_group(1, &texture.bind_group, &[]);
+            self.batch.draw_range(pass, draw.vertices.clone());
+        }
+    }
+
+    fn update_texture(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        id: TextureId,
+        delta: &ImageDelta,
+    ) {
+        let [width, height] = delta.image.size();
+        let pixels: Vec<u8> = match &delta.image {
+            ImageData::Color(image) => image.pixels.iter().flat_map(|c| c.to_array()).collect(),
+            ImageData::Font(image) => image
+                .srgba_pixels(None)
+                .flat_map(|c| c.to_array())
+                .collect(),
+        };
+        let size = wgpu::Extent3d {
+            width: width as u32,
+            height: height as u32,
+            depth_or_array_layers: 1,
+        };
+
+        let origin = match delta.pos {
+            Some([x, y]) => wgpu::Origin3d {
+                x: x as u32,
+                y: y as u32,
+                z: 0,
+            },
+            None => {
+                let texture = self.create_texture(device, size);
+                self.textures.insert(id, texture);
+                wgpu::Origin3d::ZERO
+            }
+        };
+        let Some(texture) = self.textures.get(&id) else {
+            log::warn!("Egui patched unknown texture {id:?}");
+            return;
+        };
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &texture.texture,
+                mip_level: 0,
+                origin,
+                aspect: wgpu::TextureAspect::All,
+            },
+            &pixels,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4 * size.width),
+                rows_per_image: Some(size.height),
+            },
+            size,
+        );
+    }
+
+    fn create_texture(&self, device: &wgpu::Device, size: wgpu::Extent3d) -> EguiTexture {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Egui texture"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            // egui colors are gamma encoded and blended as they are.
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let view = render::labeled_view(&texture, "Egui texture view");
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Egui texture bind group"),
+            layout: &self.texture_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+            ],
+        });
+        EguiTexture {
+            texture,
+            bind_group,
+        }
+    }
+}
@@ -0,0 +1,53 @@
+// Replaced with `true` when the surface takes linear color (sRGB or HDR):
+// egui's colors are already gamma encoded, so they are decoded first.
+const DECODE_SRGB: bool = false;
+
+struct ScreenUniform {
+    // xy: window size in egui points.
+    size: vec4<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> screen: ScreenUniform;
+
+@group(1) @binding(0)
+var egui_texture: texture_2d<f32>;
+
+@group(1) @binding(1)
+var egui_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+}
+
+@vertex
+fn vs_main(
+    @location(0) position: vec2<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) color: vec4<f32>,
+) -> VertexOutput {
+    var out: VertexOutput;
+    let ndc = position / screen.size.xy * 2.0 - 1.0;
+    out.position = vec4<f32>(ndc.x, -ndc.y, 0.0, 1.0);
+    out.uv = uv;
+    out.color = color;
+    return out;
+}
+
+fn srgb_to_linear(color: vec3<f32>) -> vec3<f32> {
+    let low = color / 12.92;
+    let high = pow((color + 0.055) / 1.055, vec3<f32>(2.4));
+    return select(high, low, color <= vec3<f32>(0.04045));
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    // Both the vertex color and the texture are premultiplied by alpha.
+    var color = input.color * textureSample(egui_texture, egui_sampler, input.uv);
+    if (DECODE_SRGB && color.a > 0.0) {
+        color = vec4<f32>(srgb_to_linear(color.rgb / color.a) * color.a, color.a);
+    }
+    return color;
+}
@@ -0,0 +1,93 @@
+//! The dimensions a world is made of. Each one generates its own terrain,
+//! keeps its own chunks, entities and home under its own save directory,
+//! and has its own sky; the player is in one at a time and crosses between
+//! them through portals or with `/dimension`.
+
+use glam::Vec3;
+use serde::{Deserialize, Serialize};
+
+use crate::sky::{Sky, SkyLight};
+use crate::terrain::{TerrainGenerator, TerrainPreset};
+
+/// Light of the void's unchanging sky: a faint violet glow from everywhere
+/// and nothing direct, so lamps do most of the lighting.
+const VOID_AMBIENT: Vec3 = Vec3::new(0.12, 0.08, 0.18);
+const VOID_ZENITH: Vec3 = Vec3::new(0.004, 0.0, 0.012);
+const VOID_HORIZON: Vec3 = Vec3::new(0.05, 0.015, 0.08);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Dimension {
+    /// The configured terrain under the day/night cycle.
+    #[default]
+    Overworld,
+    /// Shards of rock floating in the dark, with no sun or moon.
+    Void,
+}
+
+impl Dimension {
+    pub const ALL: [Dimension; 2] = [Dimension::Overworld, Dimension::Void];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|dimension| dimension.name() == name)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Dimension::Overworld => "overworld",
+            Dimension::Void => "void",
+        }
+    }
+
+    /// The generator for this dimension of the world whose overworld
+    /// `overworld` generates; every dimension shares its seed.
+    pub fn terrain(self, overworld: TerrainGenerator) -> TerrainGenerator {
+        match self {
+            Dimension::Overworld => overworld,
+            Dimension::Void => TerrainGenerator::new(TerrainPreset::Void, overworld.seed),
+        }
+    }
+
+    /// The sky over this dimension while `sky` runs the overworld's day.
+    pub fn sky_light(self, sky: &Sky) -> SkyLight {
+        match self {
+            Dimension::Overworld => sky.light(),
+            Dimension::Void => SkyLight {
+                sun: Vec3::ZERO,
+                light_direction: Vec3::Y,
+                light_color: Vec3::ZERO,
+                ambient: VOID_AMBIENT,
+                zenith: VOID_ZENITH,
+                horizon: VOID_HORIZON,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::{BLOCK_AIR, BlockKind};
+    use crate::terrain::find_spawn;
+
+    #[test]
+    fn the_void_spawns_on_its_platform_under_a_dark_sky() {
+        let overworld = TerrainGenerator::new(TerrainPreset::Default, 7);
+        assert_eq!(Dimension::Overworld.terrain(overworld), overworld);
+        let void = Dimension::Void.terrain(overworld);
+        assert_eq!(void.seed, 7);
+        assert!(!void.preset.has_heightfield());
+
+        let spawn = find_spawn(void).floor().as_ivec3();
+        let block = |y| void.block_at(spawn.x, y, spawn.z);
+        assert_eq!(block(spawn.y - 1), BlockKind::Stone.id());
+        assert_eq!(block(spawn.y), BLOCK_AIR);
+        assert_eq!(block(spawn.y + 1), BLOCK_AIR);
+
+        let light = Dimension::Void.sky_light(&Sky::new(Vec3::Y, 1200.0));
+        assert_eq!(light.light_color, Vec3::ZERO);
+        assert_eq!(Dimension::from_name("void"), Some(Dimension::Void));
+    }
+}
@@ -0,0 +1,446 @@
+use std::collections::{HashMap, HashSet};
+use std::f32::consts::TAU;
+
+use glam::Vec3;
+
+use crate::block::BlockKind;
+use crate::coords::{BlockPos, ChunkPos};
+use crate::health::Health;
+use crate::item::{ItemKind, ItemStack};
+use crate::mob::Pursuit;
+use crate::physics::Body;
+use crate::raycast::ray_box_distance;
+use crate::save::{EntityRecord, ItemRecord, WorldSave};
+use crate::terrain::WORLD_MIN_Y;
+use crate::ticking::MovingBlock;
+use crate::world::World;
+
+/// Edge length of a dropped item's model and box.
+const ITEM_SIZE: f32 = 0.25;
+/// Seconds before a dropped item can be picked up.
+const ITEM_PICKUP_DELAY: f32 = 0.5;
+/// How far past the player's box items are picked up from.
+const ITEM_PICKUP_REACH: Vec3 = Vec3::new(1.0, 0.5, 1.0);
+/// Seconds a dropped item lies in loaded chunks before it despawns.
+const ITEM_LIFETIME: f32 = 300.0;
+/// Fraction of its sliding speed a grounded item loses per second.
+const ITEM_FRICTION: f32 = 6.0;
+/// Speed items scatter at when several are dropped together.
+const ITEM_SCATTER_SPEED: f32 = 2.0;
+const ITEM_SCATTER_LIFT: f32 = 3.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EntityKind {
+    /// Hostile mob that spawns in the dark and chases the player.
+    Lurker,
+    /// A stack lying in the world until someone picks it up.
+    Item,
+}
+
+impl EntityKind {
+    const fn max_health(self) -> f32 {
+        match self {
+            EntityKind::Lurker => 12.0,
+            EntityKind::Item => 1.0,
+        }
+    }
+
+    const fn half_width(self) -> f32 {
+        match self {
+            EntityKind::Lurker => 0.4,
+            EntityKind::Item => ITEM_SIZE * 0.5,
+        }
+    }
+
+    const fn height(self) -> f32 {
+        match self {
+            EntityKind::Lurker => 1.9,
+            EntityKind::Item => ITEM_SIZE,
+        }
+    }
+
+    /// Whether the player can hit it.
+    const fn is_attackable(self) -> bool {
+        matches!(self, EntityKind::Lurker)
+    }
+
+    /// Name stored in saves.
+    const fn name(self) -> &'static str {
+        match self {
+            EntityKind::Lurker => "lurker",
+            EntityKind::Item => "item",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "lurker" => Some(EntityKind::Lurker),
+            "item" => Some(EntityKind::Item),
+            _ => None,
+        }
+    }
+}
+
+/// The stack carried by an item entity.
+pub struct DroppedItem {
+    pub stack: ItemStack,
+    pickup_delay: f32,
+    /// Seconds spent lying in loaded chunks.
+    age: f32,
+}
+
+/// Something other than the player that moves through the world.
+pub struct Entity {
+    pub kind: EntityKind,
+    pub body: Body,
+    pub health: Health,
+    /// Chase state, for hostile mobs.
+    pub pursuit: Option<Pursuit>,
+    /// The stack, for items.
+    pub item: Option<DroppedItem>,
+}
+
+impl Entity {
+    /// An entity standing with its feet at `position`.
+    pub fn new(kind: EntityKind, position: Vec3) -> Self {
+        Self {
+            kind,
+            body: Body::new(position, kind.half_width(), kind.height()),
+            health: Health::new(kind.max_health()),
+            pursuit: (kind == EntityKind::Lurker).then(Pursuit::default),
+            item: None,
+        }
+    }
+
+    fn to_record(&self) -> EntityRecord {
+        EntityRecord {
+            kind: self.kind.name().to_string(),
+            position: self.body.position.to_array(),
+            velocity: self.body.velocity.to_array(),
+            health: self.health.current(),
+            item: self.item.as_ref().map(|item| ItemRecord {
+                id: item.stack.item.id(),
+                count: item.stack.count,
+                data: item.stack.data,
+                age: item.age,
+            }),
+        }
+    }
+
+    /// The saved entity, or `None` if the save names something unknown.
+    fn from_record(record: &EntityRecord) -> Option<Self> {
+        let kind = EntityKind::from_name(&record.kind)?;
+        let mut entity = Entity::new(kind, Vec3::from_array(record.position));
+        entity.body.velocity = Vec3::from_array(record.velocity);
+        entity.health.set(record.health);
+        if kind == EntityKind::Item {
+            let saved = record.item.as_ref()?;
+            let mut stack = ItemStack::new(ItemKind::from_id(saved.id)?, saved.count);
+            stack.data = saved.data;
+            entity.item = Some(DroppedItem {
+                stack,
+                pickup_delay: 0.0,
+                age: saved.age,
+            });
+        }
+        Some(entity)
+    }
+
+    /// Blocks drawn for the entity, bottom first, each with its minimum
+    /// corner relative to the feet and its edge length.
+    fn model(&self) -> Vec<(BlockKind, Vec3, f32)> {
+        match self.kind {
+            EntityKind::Lurker => vec![
+                (BlockKind::LurkerBody, Vec3::new(-0.5, 0.0, -0.5), 1.0),
+                (BlockKind::LurkerHead, Vec3::new(-0.5, 1.0, -0.5), 1.0),
+            ],
+            EntityKind::Item => {
+                let block = self
+                    .item
+                    .as_ref()
+                    .and_then(|item| item.stack.item.as_block())
+                    .unwrap_or(BlockKind::ItemBundle);
+                let half = ITEM_SIZE * 0.5;
+                vec![(block, Vec3::new(-half, 0.0, -half), ITEM_SIZE)]
+            }
+        }
+    }
+}
+
+/// Every entity in the loaded part of the world.
+///
+/// Entities live in memory while their chunk is loaded and in the world
+/// save, one file per chunk, while it is not; `sync` moves them across as
+/// chunks load and unload.
+#[derive(Default)]
+pub struct Entities {
+    entities: Vec<Entity>,
+    /// Loaded chunks whose saved entities have been restored.
+    restored: HashSet<ChunkPos>,
+}
+
+impl Entities {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn spawn(&mut self, entity: Entity) {
+        self.entities.push(entity);
+    }
+
+    /// Drops `stacks` as item entities at `position`, scattered outwards
+    /// when there is more than one.
+    pub fn drop_items(&mut self, position: Vec3, stacks: impl IntoIterator<Item = ItemStack>) {
+        let stacks: Vec<ItemStack> = stacks.into_iter().collect();
+        let count = stacks.len();
+        for (index, stack) in stacks.into_iter().enumerate() {
+            let mut item = Entity::new(EntityKind::Item, position);
+            if count > 1 {
+                let angle = index as f32 / count as f32 * TAU;
+                item.body.velocity = Vec3::new(
+                    angle.cos() * ITEM_SCATTER_SPEED,
+                    ITEM_SCATTER_LIFT,
+                    angle.sin() * ITEM_SCATTER_SPEED,
+                );
+            }
+            item.item = Some(DroppedItem {
+                stack,
+                pickup_delay: ITEM_PICKUP_DELAY,
+                age: 0.0,
+            });
+            self.spawn(item);
+        }
+    }
+
+    pub fn count(&self, kind: EntityKind) -> usize {
+        self.entities
+            .iter()
+            .filter(|entity| entity.kind == kind)
+            .count()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Entity> {
+        self.entities.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Entity> {
+        self.entities.iter_mut()
+    }
+
+    /// Nearest entity whose box the ray from `origin` along the unit-length
+    /// `direction` enters within `max_distance`, with that distance.
+    pub fn pick(
+        &mut self,
+        origin: Vec3,
+        direction: Vec3,
+        max_distance: f32,
+    ) -> Option<(&mut Entity, f32)> {
+        self.entities
+            .iter_mut()
+            .filter(|entity| entity.kind.is_attackable())
+            .filter_map(|entity| {
+                let (min, max) = entity.body.bounds();
+                ray_box_distance(origin, direction, min, max)
+                    .filter(|&distance| distance <= max_distance)
+                    .map(|distance| (entity, distance))
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+    }
+
+    /// Moves every entity under gravity by `dt` seconds, after whatever
+    /// steered them this frame, and despawns the dead, items that lay
+    /// around too long, and anything that fell out of the world.
+    pub fn update(&mut self, world: &World, dt: f32) {
+        for entity in &mut self.entities {
+            entity.health.update(dt);
+            entity.body.fall(dt);
+            if let Some(item) = &mut entity.item {
+                item.pickup_delay = (item.pickup_delay - dt).max(0.0);
+                item.age += dt;
+                if entity.body.on_ground {
+                    let slowdown = (1.0 - ITEM_FRICTION * dt).max(0.0);
+                    entity.body.velocity.x *= slowdown;
+                    entity.body.velocity.z *= slowdown;
+                }
+            }
+            let walks = entity.kind != EntityKind::Item;
+            entity.body.apply_movement(world, dt, walks);
+        }
+        self.entities.retain(|entity| {
+            let expired = entity
+                .item
+                .as_ref()
+                .is_some_and(|item| item.age >= ITEM_LIFETIME);
+            // Nothing below the world floor ever lands again.
+            let fell_out = entity.body.position.y < WORLD_MIN_Y as f32;
+            !(entity.health.is_dead() || expired || fell_out)
+        });
+    }
+
+    /// Keeps only the entities `keep` accepts.
+    pub fn retain(&mut self, keep: impl FnMut(&Entity) -> bool) {
+        self.entities.retain(keep);
+    }
+
+    /// Restores the saved entities of newly loaded chunks and saves, then
+    /// drops, the ones in chunks that are no longer loaded.
+    pub fn sync(&mut self, world: &World, save: &WorldSave) {
+        let mut leaving: HashMap<ChunkPos, Vec<EntityRecord>> = HashMap::new();
+        self.entities.retain(|entity| {
+            let coord = BlockPos(entity.body.position.floor().as_ivec3()).chunk();
+            if world.chunk(coord).is_some() {
+                return true;
+            }
+            leaving.entry(coord).or_default().push(entity.to_record());
+            false
+        });
+
+        let unloaded: Vec<ChunkPos> = self
+            .restored
+            .iter()
+            .copied()
+            .filter(|&coord| world.chunk(coord).is_none())
+            .collect();
+        for coord in unloaded {
+            self.restored.remove(&coord);
+            let records = leaving.remove(&coord).unwrap_or_default();
+            if let Err(err) = save.store_entities(coord, &records) {
+                log::warn!("Failed to save entities of chunk {:?}: {}", coord, err);
+            }
+        }
+        // Entities that wandered off the loaded area join whatever their
+        // chunk already had saved.
+        for (coord, records) in leaving {
+            let mut saved = save.load_entities(coord);
+            saved.extend(records);
+            if let Err(err) = save.store_entities(coord, &saved) {
+                log::warn!("Failed to save entities of chunk {:?}: {}", coord, err);
+            }
+        }
+
+        let mut loaded: Vec<ChunkPos> = world
+            .iter_chunks()
+            .map(|(&coord, _)| coord)
+            .filter(|coord| !self.restored.contains(coord))
+            .collect();
+        // Restore in a fixed order, since entities update in list order.
+        loaded.sort_by_key(|coord| (coord.x, coord.y, coord.z));
+        for coord in loaded {
+            self.restored.insert(coord);
+            self.entities.extend(
+                save.load_entities(coord)
+                    .iter()
+                    .filter_map(Entity::from_record),
+            );
+        }
+    }
+
+    /// Writes the entities of every loaded chunk to the save, as on exit.
+    pub fn save_all(&self, save: &WorldSave) {
+        let mut by_chunk: HashMap<ChunkPos, Vec<EntityRecord>> = self
+            .restored
+            .iter()
+            .map(|&coord| (coord, Vec::new()))
+            .collect();
+        for entity in &self.entities {
+            let coord = BlockPos(entity.body.position.floor().as_ivec3()).chunk();
+            by_chunk.entry(coord).or_default().push(entity.to_record());
+        }
+        for (coord, records) in by_chunk {
+            if let Err(err) = save.store_entities(coord, &records) {
+                log::warn!("Failed to save entities of chunk {:?}: {}", coord, err);
+            }
+        }
+    }
+
+    /// Hands every item within reach of `body` to `collect`, removing the
+    /// ones it accepts.
+    pub fn pick_up_items(&mut self, body: &Body, mut collect: impl FnMut(ItemStack) -> bool) {
+        let (min, max) = body.bounds();
+        let (min, max) = (min - ITEM_PICKUP_REACH, max + ITEM_PICKUP_REACH);
+        self.entities.retain(|entity| {
+            let Some(item) = &entity.item else {
+                return true;
+            };
+            let position = entity.body.position;
+            let in_reach = position.cmpge(min).all() && position.cmple(max).all();
+            !(in_reach && item.pickup_delay <= 0.0 && collect(item.stack))
+        });
+    }
+
+    /// Model blocks of every mob, drawn by the renderers alongside the
+    /// blocks that are sliding between cells.
+    pub fn mob_models(&self) -> impl Iterator<Item = MovingBlock> + '_ {
+        self.models(false)
+    }
+
+    /// Model blocks of every dropped item.
+    pub fn item_models(&self) -> impl Iterator<Item = MovingBlock> + '_ {
+        self.models(true)
+    }
+
+    fn models(&self, items: bool) -> impl Iterator<Item = MovingBlock> + '_ {
+        self.entities
+            .iter()
+            .filter(move |entity| entity.item.is_some() == items)
+            .flat_map(|entity| {
+                let feet = entity.body.position;
+                entity
+                    .model()
+                    .into_iter()
+                    .map(move |(kind, corner, scale)| MovingBlock {
+                        kind,
+                        position: feet + corner,
+                        scale,
+                    })
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp_dir::TempDir;
+
+    #[test]
+    fn entities_keep_their_state_through_a_save() {
+        let dir = TempDir::new("entity");
+        let save = WorldSave::at(dir.path().to_path_buf());
+        let coord = ChunkPos::new(0, 0, 0);
+
+        let mut lurker = Entity::new(EntityKind::Lurker, Vec3::new(3.5, 4.0, 5.5));
+        lurker.body.velocity = Vec3::new(0.25, -1.0, 0.0);
+        lurker.health.hit(7.3);
+        let mut item = Entity::new(EntityKind::Item, Vec3::new(1.5, 2.0, 1.5));
+        let mut stack = ItemStack::new(ItemKind::Stick, 5);
+        stack.data = 3;
+        item.item = Some(DroppedItem {
+            stack,
+            pickup_delay: ITEM_PICKUP_DELAY,
+            age: 12.5,
+        });
+        let mut overhealed = lurker.to_record();
+        overhealed.health = EntityKind::Lurker.max_health() + 10.0;
+        save.store_entities(coord, &[lurker.to_record(), item.to_record(), overhealed])
+            .unwrap();
+
+        let loaded: Vec<Entity> = save
+            .load_entities(coord)
+            .iter()
+            .filter_map(Entity::from_record)
+            .collect();
+        assert_eq!(loaded.len(), 3);
+        assert_eq!(loaded[0].kind, EntityKind::Lurker);
+        assert_eq!(loaded[0].body.position, lurker.body.position);
+        assert_eq!(loaded[0].body.velocity, lurker.body.velocity);
+        assert_eq!(loaded[0].health.current(), lurker.health.current());
+        assert!(!loaded[0].health.is_invulnerable());
+
+        let restored = loaded[1].item.as_ref().unwrap();
+        assert_eq!(restored.stack, stack);
+        assert_eq!(restored.age, 12.5);
+        assert_eq!(restored.pickup_delay, 0.0);
+
+        assert_eq!(loaded[2].health.current(), EntityKind::Lurker.max_health());
+    }
+}
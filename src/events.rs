@@ -0,0 +1,71 @@
+use glam::{IVec3, Vec3};
+
+use crate::block::BlockKind;
+
+/// What caused a block change.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChangeCause {
+    /// Broken, placed, or toggled by the player.
+    Player,
+    /// Made by the block ticker (growth, falling, signals, pistons).
+    Simulation,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum GameEvent {
+    BlockChanged {
+        position: IVec3,
+        old: BlockKind,
+        new: BlockKind,
+        cause: ChangeCause,
+    },
+    /// The walking player completed a stride on `block`.
+    PlayerStep { position: Vec3, block: BlockKind },
+    /// Something finished that the player should hear about.
+    Notice(Notice),
+}
+
+/// Outcomes shown to the player as toasts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Notice {
+    /// Edited chunks and entities were written to the save.
+    WorldSaved,
+    /// The material editor wrote its edits to block files.
+    MaterialsSaved,
+    /// The settings screen wrote `config.json`.
+    SettingsSaved,
+    /// The renderer hotkey switched to the named renderer.
+    RendererSwitched(&'static str),
+    /// A GPU trace is being recorded; the game closes once it is done.
+    GpuTraceRecording,
+    /// The GPU trace hotkey was pressed with no trace being recorded.
+    GpuTraceOff,
+    /// A screenshot was written to `screenshots/`.
+    ScreenshotSaved,
+    /// A screenshot could not be read back or written.
+    ScreenshotFailed,
+    /// The screenshot key was pressed, but the surface cannot be copied.
+    ScreenshotUnavailable,
+}
+
+/// Events published while a frame is simulated, drained once per frame by
+/// whatever reacts to them (the audio engine and the toasts).
+#[derive(Default)]
+pub struct EventBus {
+    events: Vec<GameEvent>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn publish(&mut self, event: GameEvent) {
+        self.events.push(event);
+    }
+
+    /// Takes every event published since the last drain, oldest first.
+    pub fn drain(&mut self) -> impl Iterator<Item = GameEvent> + '_ {
+        self.events.drain(..)
+    }
+}
@@ -0,0 +1,49 @@
+//! A camera that leaves the player's eyes and flies on its own, through
+//! blocks, for looking over builds and watching the player's physics from
+//! outside. The body stays where it was, still falling and pushed about, and
+//! is drawn as a model while the camera is away; the free camera key snaps
+//! the view back to its eyes.
+
+use glam::Vec3;
+
+use crate::block::BlockKind;
+use crate::camera::Camera;
+use crate::input::MovementIntent;
+use crate::physics::PLAYER_HEIGHT;
+use crate::ticking::MovingBlock;
+
+/// Edge of each block of the player's model: three of them stack to the
+/// player's height.
+const MODEL_PART: f32 = PLAYER_HEIGHT / 3.0;
+
+/// Flies `camera` for `dt` seconds as `movement` asks, along the view and
+/// straight up or down, staying within `reach` blocks of `anchor`.
+pub fn fly(camera: &mut Camera, movement: &MovementIntent, dt: f32, anchor: Vec3, reach: f32) {
+    let mut desired = movement.wish_dir;
+    if movement.ascend {
+        desired += Vec3::Y;
+    }
+    if movement.descend {
+        desired -= Vec3::Y;
+    }
+    let position = camera.position + desired.normalize_or_zero() * movement.speed * dt;
+    camera.position = anchor + (position - anchor).clamp_length_max(reach);
+}
+
+/// The player's body as drawn while the camera is away from it: legs, torso
+/// and head stacked on `feet`.
+pub fn player_model(feet: Vec3) -> impl Iterator<Item = MovingBlock> {
+    let corner = feet - Vec3::new(MODEL_PART * 0.5, 0.0, MODEL_PART * 0.5);
+    [
+        BlockKind::PlayerBody,
+        BlockKind::PlayerBody,
+        BlockKind::PlayerHead,
+    ]
+    .into_iter()
+    .enumerate()
+    .map(move |(index, kind)| MovingBlock {
+        kind,
+        position: corner + Vec3::Y * (MODEL_PART * index as f32),
+        scale: MODEL_PART,
+    })
+}
@@ -0,0 +1,125 @@
+//! wgpu API traces, so a GPU bug seen on a player's machine can be replayed
+//! on a maintainer's.
+//!
+//! wgpu records every call made on a device from its creation until it is
+//! dropped, and cannot start or stop a trace in between. A capture therefore
+//! covers startup and the first `gpu_trace.frames` frames, after which the
+//! game closes so the device is dropped and the trace finished. Recording
+//! needs a build with the `gpu-trace` feature; other builds only log that
+//! the setting is ignored.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use log::{info, warn};
+
+use crate::config::GpuTraceSettings;
+
+/// Written next to the trace for whoever receives it.
+const NOTES_FILE: &str = "NOTES.txt";
+
+/// A capture in progress.
+pub struct GpuTrace {
+    directory: PathBuf,
+    frames: u32,
+    frames_left: u32,
+}
+
+impl GpuTrace {
+    /// Makes a fresh directory for a capture of `settings.frames` frames, or
+    /// returns `None` when tracing is off or cannot be recorded.
+    pub fn start(settings: &GpuTraceSettings) -> Option<Self> {
+        if settings.frames == 0 {
+            return None;
+        }
+        if !cfg!(feature = "gpu-trace") {
+            warn!(
+                "gpu_trace.frames is set, but this build lacks the gpu-trace feature; ignoring it"
+            );
+            return None;
+        }
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        let directory = settings.directory.join(format!("trace-{stamp}"));
+        if let Err(err) = fs::create_dir_all(&directory) {
+            warn!(
+                "Failed to create GPU trace directory {}: {}",
+                directory.display(),
+                err
+            );
+            return None;
+        }
+        info!(
+            "Recording a GPU trace of {} frames into {}",
+            settings.frames,
+            directory.display()
+        );
+        Some(Self {
+            directory,
+            frames: settings.frames,
+            frames_left: settings.frames,
+        })
+    }
+
+    /// Where wgpu writes the trace; passed to `Adapter::request_device`.
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// Describes the machine and how to replay the trace, for the bug report.
+    pub fn write_notes(&self, adapter: &wgpu::AdapterInfo) {
+        let notes = format!(
+            "Rustcraft {version} GPU trace of startup and {frames} frames.\n\
+             \n\
+             Adapter: {name} ({device_type:?})\n\
+             Backend: {backend:?}\n\
+             Driver: {driver} {driver_info}\n\
+             Vendor/device: {vendor:#06x}/{device:#06x}\n\
+             \n\
+             Zip this whole directory and attach it to the bug report.\n\
+             \n\
+             To replay it, check out wgpu 0.17 and run from its player directory:\n\
+             \x20   cargo run --features winit --bin play -- <path to this directory>\n\
+             The replay needs the same backend as above.\n",
+            version = env!("CARGO_PKG_VERSION"),
+            frames = self.frames,
+            name = adapter.name,
+            device_type = adapter.device_type,
+            backend = adapter.backend,
+            driver = adapter.driver,
+            driver_info = adapter.driver_info,
+            vendor = adapter.vendor,
+            device = adapter.device,
+        );
+        let path = self.directory.join(NOTES_FILE);
+        if let Err(err) = fs::write(&path, notes) {
+            warn!("Failed to write {}: {}", path.display(), err);
+        }
+    }
+
+    /// Counts a presented frame; true once the last one is in and the game
+    /// should close to finish the trace.
+    pub fn frame_presented(&mut self) -> bool {
+        self.frames_left = self.frames_left.saturating_sub(1);
+        self.frames_left == 0
+    }
+
+    /// Stops counting so the next frame is the last, for the hotkey.
+    pub fn finish_early(&mut self) {
+        self.frames_left = self.frames_left.min(1);
+    }
+}
+
+impl Drop for GpuTrace {
+    /// The device goes with the rest of the game, finishing the trace
+    /// however the game closed.
+    fn drop(&mut self) {
+        info!(
+            "GPU trace finished in {}; see {} there for how to share it",
+            self.directory.display(),
+            NOTES_FILE
+        );
+    }
+}
@@ -0,0 +1,280 @@
+use super::Rect;
+use super::skin::{Skin, Sprite};
+use super::sprites::{Sheet, SpriteBatcher};
+use crate::biome::{self, Climate};
+use crate::inventory_screen::InventoryScreen;
+use crate::item::{ItemIcon, ItemStack};
+use crate::texture::{AtlasKind, AtlasRegistry, AtlasTile};
+use crate::ui::{UiFrame, UiLayer};
+
+/// Sizes in GUI pixels, which are `scale` screen pixels each.
+const SLOT_SIZE: f32 = 20.0;
+const ICON_SIZE: f32 = 16.0;
+/// Gap between the bar and the bottom and sides of the screen.
+const BAR_MARGIN: f32 = 4.0;
+/// How far the selection frame sits outside the selected slot.
+const SELECTION_OUTSET: f32 = 1.0;
+/// Climate whose colormap tints block icons, so grass looks like temperate grass.
+const ICON_CLIMATE: Climate = Climate {
+    temperature: 0.6,
+    humidity: 0.5,
+};
+const TEXT_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+const TEXT_SHADOW: [f32; 4] = [0.0, 0.0, 0.0, 0.8];
+/// Tint over the whole screen behind the inventory.
+const SCREEN_SHADE: [f32; 4] = [0.0, 0.0, 0.0, 0.5];
+/// Wash over the slot under the cursor.
+const HOVER_TINT: [f32; 4] = [1.0, 1.0, 1.0, 0.25];
+const TITLE: &str = "Inventory";
+
+/// Draws the hotbar along the bottom of the screen: a row of slots with each
+/// stack's icon, sampled from the block or items atlas, and its count, and a
+/// frame around the selected slot. Slots shrink when the row would not fit
+/// the window.
+pub struct HotbarLayer {
+    sprites: SpriteBatcher,
+    skin: Skin,
+    /// Screen pixels per GUI pixel.
+    scale: f32,
+}
+
+impl HotbarLayer {
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        surface_format: wgpu::TextureFormat,
+        atlases: &AtlasRegistry,
+        skin: Skin,
+        scale: f32,
+    ) -> Self {
+        Self {
+            sprites: SpriteBatcher::new(device, queue, surface_format, atlases),
+            skin,
+            scale: scale.max(1.0),
+        }
+    }
+
+    /// Each slot's screen rect, left to right, and the GUI scale they were
+    /// shrunk to.
+    fn layout(&self, viewport: [u32; 2], count: usize) -> (Vec<Rect>, f32) {
+        let [width, height] = [viewport[0] as f32, viewport[1] as f32];
+        let margin = BAR_MARGIN * self.scale;
+        let fit = (width - margin * 2.0) / (count as f32 * SLOT_SIZE);
+        let scale = self.scale.min(fit).max(0.0);
+        let slot = (SLOT_SIZE * scale).floor();
+        let left = ((width - slot * count as f32) * 0.5).round();
+        let top = height - margin - slot;
+        let rects = (0..count)
+            .map(|index| Rect::new([left + index as f32 * slot, top], [slot, slot]))
+            .collect();
+        (rects, scale)
+    }
+}
+
+impl UiLayer for HotbarLayer {
+    fn label(&self) -> &'static str {
+        "Hotbar"
+    }
+
+    fn prepare(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, frame: &UiFrame) {
+        self.sprites.begin(frame.viewport);
+        let slots = frame.inventory.hotbar();
+        if frame.viewport[0] > 0 && frame.viewport[1] > 0 {
+            let (rects, scale) = self.layout(frame.viewport, slots.len());
+            // Backgrounds, then icons, then counts, so each kind of sprite
+            // draws in as few runs as it can.
+            for &rect in &rects {
+                self.skin
+                    .push(&mut self.sprites, Sprite::Slot, rect, scale, 1.0);
+            }
+            if let Some(&selected) = rects.get(frame.inventory.selected_index()) {
+                let frame_rect = selected.expand((SELECTION_OUTSET * scale).round());
+                self.skin
+                    .push(&mut self.sprites, Sprite::Focus, frame_rect, scale, 1.0);
+            }
+            push_stacks(&mut self.sprites, frame.atlases, slots, &rects, scale);
+        }
+        self.sprites.upload(device, queue);
+    }
+
+    fn draw<'a>(&'a self, pass: &mut wgpu::RenderPass<'a>) {
+        self.sprites.draw(pass);
+    }
+}
+
+/// Draws the frame's [`InventoryScreen`], if it shows one: a shade over the
+/// screen, a panel of slots with the hovered one lit, and the carried stack
+/// under the cursor.
+pub struct InventoryLayer {
+    sprites: SpriteBatcher,
+    skin: Skin,
+}
+
+impl InventoryLayer {
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        surface_format: wgpu::TextureFormat,
+        atlases: &AtlasRegistry,
+        skin: Skin,
+    ) -> Self {
+        Self {
+            sprites: SpriteBatcher::new(device, queue, surface_format, atlases),
+            skin,
+        }
+    }
+
+    fn push_screen(&mut self, screen: &InventoryScreen, frame: &UiFrame) {
+        let scale = screen.scale();
+        let viewport = [frame.viewport[0] as f32, frame.viewport[1] as f32];
+        self.sprites
+            .push_rect(Rect::new([0.0, 0.0], viewport), SCREEN_SHADE);
+        let panel = screen.panel();
+        self.skin
+            .push(&mut self.sprites, Sprite::Panel, panel, scale, 1.0);
+        let rects = screen.slot_rects();
+        for &rect in rects {
+            self.skin
+                .push(&mut self.sprites, Sprite::Slot, rect, scale, 1.0);
+        }
+        if let Some(hovered) = screen.hovered() {
+            self.sprites.push_rect(rects[hovered], HOVER_TINT);
+        }
+        let slots = frame.inventory.slots();
+        push_stacks(&mut self.sprites, frame.atlases, slots, rects, scale);
+
+        let title = screen.title_rect().center();
+        let title_origin = [
+            (title[0] - SpriteBatcher::text_width(TITLE, scale) * 0.5).round(),
+            (title[1] - SpriteBatcher::text_height(scale) * 0.5).round(),
+        ];
+        push_shadowed_text(&mut self.sprites, TITLE, title_origin, scale);
+
+        // The carried stack is centered on the cursor, over everything else.
+        if let (Some(stack), Some(cursor)) = (screen.held(), screen.cursor()) {
+            let size = SLOT_SIZE * scale;
+            let rect = Rect::new(
+                [cursor[0] - size * 0.5, cursor[1] - size * 0.5],
+                [size, size],
+            );
+            push_stacks(
+                &mut self.sprites,
+                frame.atlases,
+                &[Some(stack)],
+                &[rect],
+                scale,
+            );
+        }
+    }
+}
+
+impl UiLayer for InventoryLayer {
+    fn label(&self) -> &'static str {
+        "Inventory"
+    }
+
+    fn prepare(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, frame: &UiFrame) {
+        self.sprites.begin(frame.viewport);
+        if frame.viewport[0] > 0
+            && frame.viewport[1] > 0
+            && let Some(screen) = frame.inventory_screen
+        {
+            self.push_screen(screen, frame);
+        }
+        self.sprites.upload(device, queue);
+    }
+
+    fn draw<'a>(&'a self, pass: &mut wgpu::RenderPass<'a>) {
+        self.sprites.draw(pass);
+    }
+}
+
+/// Draws each stack's icon centered in the matching rect, then every count,
+/// so icons and text each draw in as few runs as they can.
+fn push_stacks(
+    sprites: &mut SpriteBatcher,
+    atlases: &AtlasRegistry,
+    slots: &[Option<ItemStack>],
+    rects: &[Rect],
+    scale: f32,
+) {
+    for (slot, &rect) in slots.iter().zip(rects) {
+        if let Some(stack) = slot {
+            push_icon(sprites, atlases, *stack, rect, scale);
+        }
+    }
+    for (slot, &rect) in slots.iter().zip(rects) {
+        if let Some(stack) = slot.filter(|stack| stack.count > 1) {
+            push_count(sprites, stack.count, rect, scale);
+        }
+    }
+}
+
+/// Draws `stack`'s icon centered in `slot`; skipped when its atlas or tile
+/// is missing.
+fn push_icon(
+    sprites: &mut SpriteBatcher,
+    atlases: &AtlasRegistry,
+    stack: ItemStack,
+    slot: Rect,
+    scale: f32,
+) {
+    let (sheet, tile, tint) = match stack.item.icon() {
+        ItemIcon::Block(kind) => {
+            let face = ItemIcon::block_face(kind);
+            let tint = biome::tint_color(kind.tint_for_face(face), ICON_CLIMATE);
+            let tile = AtlasTile {
+                atlas: AtlasKind::Blocks,
+                tile: kind.tile_for_face(face),
+            };
+            (Sheet::Blocks, tile, tint)
+        }
+        ItemIcon::Tile(name) => {
+            let Some(tile) = atlases
+                .get(AtlasKind::Items)
+                .and_then(|atlas| atlas.tile(name))
+            else {
+                return;
+            };
+            let tile = AtlasTile {
+                atlas: AtlasKind::Items,
+                tile,
+            };
+            (Sheet::Items, tile, [1.0; 3])
+        }
+    };
+    let Some([u0, v0, du, dv]) = atlases.tile_rect(tile) else {
+        return;
+    };
+    let size = (ICON_SIZE * scale).floor();
+    let center = slot.center();
+    let min = [
+        (center[0] - size * 0.5).round(),
+        (center[1] - size * 0.5).round(),
+    ];
+    sprites.push_sprite(
+        sheet,
+        Rect::new(min, [size, size]),
+        [u0, v0, u0 + du, v0 + dv],
+        [tint[0], tint[1], tint[2], 1.0],
+    );
+}
+
+/// Writes a stack's count into the bottom-right corner of `slot`.
+fn push_count(sprites: &mut SpriteBatcher, count: u8, slot: Rect, scale: f32) {
+    let line = count.to_string();
+    // Whole font pixels stay crisp; never smaller than one.
+    let text_scale = scale.floor().max(1.0);
+    let inset = scale.max(1.0);
+    let origin = [
+        slot.max[0] - inset - SpriteBatcher::text_width(&line, text_scale),
+        slot.max[1] - inset - SpriteBatcher::text_height(text_scale),
+    ];
+    push_shadowed_text(sprites, &line, origin, text_scale);
+}
+
+fn push_shadowed_text(sprites: &mut SpriteBatcher, line: &str, origin: [f32; 2], scale: f32) {
+    let shadow = [origin[0] + scale, origin[1] + scale];
+    sprites.push_text(shadow, line, scale, TEXT_SHADOW);
+    sprites.push_text(origin, line, scale, TEXT_COLOR);
+}
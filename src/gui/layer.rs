@@ -0,0 +1,167 @@
+use super::skin::{Skin, Sprite, fade};
+use super::sprites::SpriteBatcher;
+use super::{Gui, KNOB_WIDTH, Rect, TITLE_HEIGHT, Widget};
+use crate::texture::AtlasRegistry;
+use crate::toasts::PlacedToast;
+use crate::ui::{UiFrame, UiLayer};
+
+/// Tint over the whole screen behind a GUI.
+const SCREEN_SHADE: [f32; 4] = [0.0, 0.0, 0.0, 0.5];
+const TEXT_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+const TEXT_SHADOW: [f32; 4] = [0.0, 0.0, 0.0, 0.6];
+/// Second line of a toast.
+const DETAIL_COLOR: [f32; 4] = [0.7, 0.7, 0.75, 1.0];
+/// How far the focus frame sits outside the focused widget, in GUI pixels.
+const FOCUS_OUTSET: f32 = 2.0;
+
+/// Draws the frame's toasts, then its [`Gui`] over them, if it shows one: a
+/// shade over the screen, the panel and its widgets as nine-slice sprites
+/// from the `ui` atlas, or flat rectangles without one, and their text on
+/// top.
+pub struct GuiLayer {
+    sprites: SpriteBatcher,
+    skin: Skin,
+}
+
+impl GuiLayer {
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        surface_format: wgpu::TextureFormat,
+        atlases: &AtlasRegistry,
+        skin: Skin,
+    ) -> Self {
+        Self {
+            sprites: SpriteBatcher::new(device, queue, surface_format, atlases),
+            skin,
+        }
+    }
+
+    fn push_sprite(&mut self, sprite: Sprite, rect: Rect, scale: f32) {
+        self.push_faded_sprite(sprite, rect, scale, 1.0);
+    }
+
+    /// Draws `sprite` with its alpha scaled by `opacity`.
+    fn push_faded_sprite(&mut self, sprite: Sprite, rect: Rect, scale: f32, opacity: f32) {
+        self.skin
+            .push(&mut self.sprites, sprite, rect, scale, opacity);
+    }
+
+    /// Centers `line` on `center`, one font pixel per GUI pixel, over a
+    /// drop shadow.
+    fn push_label(&mut self, line: &str, center: [f32; 2], scale: f32) {
+        let origin = [
+            (center[0] - SpriteBatcher::text_width(line, scale) * 0.5).round(),
+            (center[1] - SpriteBatcher::text_height(scale) * 0.5).round(),
+        ];
+        self.push_shadowed_text(line, origin, scale, TEXT_COLOR);
+    }
+
+    /// Draws `line` from `origin` over a drop shadow as opaque as `color`.
+    fn push_shadowed_text(&mut self, line: &str, origin: [f32; 2], scale: f32, color: [f32; 4]) {
+        let shadow = [origin[0] + scale, origin[1] + scale];
+        self.sprites
+            .push_text(shadow, line, scale, fade(TEXT_SHADOW, color[3]));
+        self.sprites.push_text(origin, line, scale, color);
+    }
+
+    /// Panels first, then every line of text, as for a [`Gui`].
+    fn push_toasts(&mut self, toasts: &[PlacedToast]) {
+        for toast in toasts {
+            self.push_faded_sprite(Sprite::Panel, toast.rect, toast.scale, toast.opacity);
+        }
+        for toast in toasts {
+            let [title_origin, detail_origin] = toast.line_origins();
+            let color = fade(TEXT_COLOR, toast.opacity);
+            self.push_shadowed_text(&toast.title, title_origin, toast.scale, color);
+            if let Some(detail) = &toast.detail {
+                let color = fade(DETAIL_COLOR, toast.opacity);
+                self.push_shadowed_text(detail, detail_origin, toast.scale, color);
+            }
+        }
+    }
+
+    fn push_gui(&mut self, gui: &Gui, viewport: [u32; 2]) {
+        let scale = gui.scale;
+        let screen = Rect::new([0.0, 0.0], [viewport[0] as f32, viewport[1] as f32]);
+        self.sprites.push_rect(screen, SCREEN_SHADE);
+        self.push_sprite(Sprite::Panel, gui.panel, scale);
+
+        // Every sprite goes in before any text, so the skin and the labels
+        // each draw in one run.
+        if let Some(id) = gui.focused {
+            self.push_sprite(
+                Sprite::Focus,
+                gui.rects[id].expand(FOCUS_OUTSET * scale),
+                scale,
+            );
+        }
+        for (id, (widget, &rect)) in gui.widgets.iter().zip(&gui.rects).enumerate() {
+            let hovered = gui.hovered == Some(id);
+            let pressed = gui.pressed == Some(id);
+            match widget {
+                Widget::Label(_) => {}
+                Widget::Button(_) => {
+                    let sprite = if pressed && hovered {
+                        Sprite::ButtonPressed
+                    } else if hovered || pressed {
+                        Sprite::ButtonHover
+                    } else {
+                        Sprite::Button
+                    };
+                    self.push_sprite(sprite, rect, scale);
+                }
+                Widget::Slider(slider) => {
+                    self.push_sprite(Sprite::SliderTrack, rect, scale);
+                    let knob = KNOB_WIDTH * scale;
+                    let x = rect.min[0] + (rect.width() - knob) * slider.fraction();
+                    let knob_rect = Rect::new([x.round(), rect.min[1]], [knob, rect.height()]);
+                    let sprite = if hovered || pressed {
+                        Sprite::ButtonHover
+                    } else {
+                        Sprite::SliderKnob
+                    };
+                    self.push_sprite(sprite, knob_rect, scale);
+                }
+            }
+        }
+
+        let title_center = [
+            gui.panel.center()[0],
+            gui.rects
+                .first()
+                .map_or(gui.panel.center()[1], |rect| rect.min[1])
+                - TITLE_HEIGHT * scale * 0.5,
+        ];
+        self.push_label(&gui.title, title_center, scale);
+        for (widget, rect) in gui.widgets.iter().zip(&gui.rects) {
+            match widget {
+                Widget::Label(text) | Widget::Button(text) => {
+                    self.push_label(text, rect.center(), scale)
+                }
+                Widget::Slider(slider) => self.push_label(&slider.text(), rect.center(), scale),
+            }
+        }
+    }
+}
+
+impl UiLayer for GuiLayer {
+    fn label(&self) -> &'static str {
+        "GUI"
+    }
+
+    fn prepare(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, frame: &UiFrame) {
+        self.sprites.begin(frame.viewport);
+        if frame.viewport[0] > 0 && frame.viewport[1] > 0 {
+            self.push_toasts(frame.toasts);
+            if let Some(gui) = frame.gui {
+                self.push_gui(gui, frame.viewport);
+            }
+        }
+        self.sprites.upload(device, queue);
+    }
+
+    fn draw<'a>(&'a self, pass: &mut wgpu::RenderPass<'a>) {
+        self.sprites.draw(pass);
+    }
+}
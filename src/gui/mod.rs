@@ -0,0 +1,360 @@
+//! A small retained GUI toolkit for the game's own menus.
+//!
+//! A [`Gui`] is built once from labels, buttons and sliders, keeps its state
+//! between frames, lays its widgets out in a column on a panel at the screen
+//! center, and turns mouse and keyboard input into [`GuiEvent`]s for its
+//! owner. [`GuiLayer`] draws whichever one the frame shows, and
+//! [`HotbarLayer`] and [`InventoryLayer`] the hotbar and inventory screen,
+//! all skinned from the `ui` atlas by a shared [`Skin`].
+
+mod inventory;
+mod layer;
+mod skin;
+mod sprites;
+
+pub use inventory::{HotbarLayer, InventoryLayer};
+pub use layer::GuiLayer;
+pub use skin::Skin;
+
+use winit::event::{ElementState, MouseButton, VirtualKeyCode, WindowEvent};
+
+/// Sizes in GUI pixels, which are `Gui::scale` screen pixels each.
+const COLUMN_WIDTH: f32 = 200.0;
+const ROW_HEIGHT: f32 = 20.0;
+const ROW_GAP: f32 = 4.0;
+const TITLE_HEIGHT: f32 = 16.0;
+const PANEL_PADDING: f32 = 10.0;
+const KNOB_WIDTH: f32 = 8.0;
+
+/// Index of a widget in the [`Gui`] that added it.
+pub type WidgetId = usize;
+
+/// Screen rectangle in window pixels, origin top-left.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Rect {
+    pub min: [f32; 2],
+    pub max: [f32; 2],
+}
+
+impl Rect {
+    pub fn new(min: [f32; 2], size: [f32; 2]) -> Self {
+        Self {
+            min,
+            max: [min[0] + size[0], min[1] + size[1]],
+        }
+    }
+
+    pub fn width(&self) -> f32 {
+        self.max[0] - self.min[0]
+    }
+
+    pub fn height(&self) -> f32 {
+        self.max[1] - self.min[1]
+    }
+
+    pub fn center(&self) -> [f32; 2] {
+        [
+            (self.min[0] + self.max[0]) * 0.5,
+            (self.min[1] + self.max[1]) * 0.5,
+        ]
+    }
+
+    pub fn contains(&self, [x, y]: [f32; 2]) -> bool {
+        x >= self.min[0] && x < self.max[0] && y >= self.min[1] && y < self.max[1]
+    }
+
+    /// Grown by `amount` on every side; shrunk when negative.
+    pub fn expand(&self, amount: f32) -> Self {
+        Self {
+            min: [self.min[0] - amount, self.min[1] - amount],
+            max: [self.max[0] + amount, self.max[1] + amount],
+        }
+    }
+}
+
+/// A value picked by dragging a knob along a track.
+pub struct Slider {
+    pub label: String,
+    pub value: f32,
+    pub min: f32,
+    pub max: f32,
+    /// Values snap to multiples of this above `min`; also the arrow key step.
+    pub step: f32,
+    /// Decimal places the value is shown with.
+    pub decimals: usize,
+}
+
+impl Slider {
+    /// Where the knob sits along the track, 0 at `min` and 1 at `max`.
+    fn fraction(&self) -> f32 {
+        if self.max > self.min {
+            ((self.value - self.min) / (self.max - self.min)).clamp(0.0, 1.0)
+        } else {
+            0.0
+        }
+    }
+
+    /// Moves to the snapped value nearest `value`. Returns whether the value
+    /// changed.
+    fn set(&mut self, value: f32) -> bool {
+        let mut snapped = value.clamp(self.min, self.max);
+        if self.step > 0.0 {
+            snapped = self.min + ((snapped - self.min) / self.step).round() * self.step;
+            snapped = snapped.min(self.max);
+        }
+        let changed = snapped != self.value;
+        self.value = snapped;
+        changed
+    }
+
+    fn text(&self) -> String {
+        format!("{}: {:.*}", self.label, self.decimals, self.value)
+    }
+}
+
+pub enum Widget {
+    Label(String),
+    Button(String),
+    Slider(Slider),
+}
+
+impl Widget {
+    /// Labels are skipped by the keyboard and ignore the mouse.
+    fn is_interactive(&self) -> bool {
+        !matches!(self, Widget::Label(_))
+    }
+}
+
+/// What the player did to a widget.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GuiEvent {
+    /// A button was released over, or activated with Enter or Space.
+    Clicked(WidgetId),
+    /// A slider moved to a new value.
+    Changed(WidgetId, f32),
+}
+
+/// A titled panel of widgets, one per row.
+///
+/// The mouse hovers, presses and drags widgets; Tab and the up and down
+/// arrows move keyboard focus, Enter and Space press the focused button, and
+/// the left and right arrows step the focused slider. The owner lays the
+/// panel out for the current window size with [`Gui::layout`] before it is
+/// drawn.
+pub struct Gui {
+    title: String,
+    widgets: Vec<Widget>,
+    /// Each widget's screen rect as of the last layout.
+    rects: Vec<Rect>,
+    panel: Rect,
+    /// Screen pixels per GUI pixel.
+    scale: f32,
+    cursor: Option<[f32; 2]>,
+    hovered: Option<WidgetId>,
+    focused: Option<WidgetId>,
+    /// Widget the left button went down on, until it is released.
+    pressed: Option<WidgetId>,
+    shift: bool,
+}
+
+impl Gui {
+    pub fn new(title: impl Into<String>, scale: f32) -> Self {
+        Self {
+            title: title.into(),
+            widgets: Vec::new(),
+            rects: Vec::new(),
+            panel: Rect::default(),
+            scale: scale.max(1.0),
+            cursor: None,
+            hovered: None,
+            focused: None,
+            pressed: None,
+            shift: false,
+        }
+    }
+
+    pub fn add_label(&mut self, text: impl Into<String>) -> WidgetId {
+        self.push(Widget::Label(text.into()))
+    }
+
+    pub fn add_button(&mut self, label: impl Into<String>) -> WidgetId {
+        self.push(Widget::Button(label.into()))
+    }
+
+    pub fn add_slider(&mut self, slider: Slider) -> WidgetId {
+        self.push(Widget::Slider(slider))
+    }
+
+    fn push(&mut self, widget: Widget) -> WidgetId {
+        self.widgets.push(widget);
+        self.rects.push(Rect::default());
+        self.widgets.len() - 1
+    }
+
+    /// Replaces the text of a label or button.
+    pub fn set_text(&mut self, id: WidgetId, text: impl Into<String>) {
+        if let Some(Widget::Label(current) | Widget::Button(current)) = self.widgets.get_mut(id) {
+            *current = text.into();
+        }
+    }
+
+    /// Gives keyboard focus to `id`, as if tabbed to.
+    pub fn focus(&mut self, id: WidgetId) {
+        if self.widgets.get(id).is_some_and(Widget::is_interactive) {
+            self.focused = Some(id);
+        }
+    }
+
+    /// Centers the panel in a `viewport` sized window.
+    pub fn layout(&mut self, viewport: [u32; 2]) {
+        let scale = self.scale;
+        let padding = PANEL_PADDING * scale;
+        let rows = self.widgets.len() as f32;
+        let size = [
+            COLUMN_WIDTH * scale + padding * 2.0,
+            (TITLE_HEIGHT + rows * (ROW_HEIGHT + ROW_GAP)) * scale + padding * 2.0
+                - ROW_GAP * scale,
+        ];
+        let min = [
+            ((viewport[0] as f32 - size[0]) * 0.5).round(),
+            ((viewport[1] as f32 - size[1]) * 0.5).round(),
+        ];
+        self.panel = Rect::new(min, size);
+
+        let mut top = min[1] + padding + TITLE_HEIGHT * scale;
+        for rect in &mut self.rects {
+            *rect = Rect::new(
+                [min[0] + padding, top],
+                [COLUMN_WIDTH * scale, ROW_HEIGHT * scale],
+            );
+            top += (ROW_HEIGHT + ROW_GAP) * scale;
+        }
+        self.hovered = self.cursor.and_then(|cursor| self.hit(cursor));
+    }
+
+    /// Handles one window event, returning what it did to a widget.
+    pub fn input(&mut self, event: &WindowEvent) -> Option<GuiEvent> {
+        match event {
+            WindowEvent::ModifiersChanged(modifiers) => {
+                self.shift = modifiers.shift();
+                None
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                let cursor = [position.x as f32, position.y as f32];
+                self.cursor = Some(cursor);
+                self.hovered = self.hit(cursor);
+                self.drag(cursor)
+            }
+            WindowEvent::CursorLeft { .. } => {
+                self.cursor = None;
+                self.hovered = None;
+                None
+            }
+            WindowEvent::MouseInput {
+                state,
+                button: MouseButton::Left,
+                ..
+            } => match state {
+                ElementState::Pressed => {
+                    let id = self.hovered?;
+                    self.pressed = Some(id);
+                    self.focused = Some(id);
+                    self.drag(self.cursor?)
+                }
+                ElementState::Released => {
+                    let id = self.pressed.take()?;
+                    let released_over = self.hovered == Some(id);
+                    (released_over && matches!(self.widgets[id], Widget::Button(_)))
+                        .then_some(GuiEvent::Clicked(id))
+                }
+            },
+            WindowEvent::KeyboardInput { input, .. } if input.state == ElementState::Pressed => {
+                self.key(input.virtual_keycode?)
+            }
+            _ => None,
+        }
+    }
+
+    fn key(&mut self, key: VirtualKeyCode) -> Option<GuiEvent> {
+        match key {
+            VirtualKeyCode::Tab => {
+                self.move_focus(!self.shift);
+                None
+            }
+            VirtualKeyCode::Down => {
+                self.move_focus(true);
+                None
+            }
+            VirtualKeyCode::Up => {
+                self.move_focus(false);
+                None
+            }
+            VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter | VirtualKeyCode::Space => {
+                let id = self.focused?;
+                matches!(self.widgets[id], Widget::Button(_)).then_some(GuiEvent::Clicked(id))
+            }
+            VirtualKeyCode::Left | VirtualKeyCode::Right => {
+                let id = self.focused?;
+                let Widget::Slider(slider) = &mut self.widgets[id] else {
+                    return None;
+                };
+                let step = if key == VirtualKeyCode::Left {
+                    -slider.step
+                } else {
+                    slider.step
+                };
+                slider
+                    .set(slider.value + step)
+                    .then_some(GuiEvent::Changed(id, slider.value))
+            }
+            _ => None,
+        }
+    }
+
+    /// Focuses the next interactive widget after the focused one, or before
+    /// it when not `forward`, wrapping around. Starts from the first or last
+    /// when nothing has focus.
+    fn move_focus(&mut self, forward: bool) {
+        let count = self.widgets.len();
+        if count == 0 {
+            return;
+        }
+        let start = match (self.focused, forward) {
+            (Some(id), true) => id + 1,
+            (Some(id), false) => id + count - 1,
+            (None, true) => 0,
+            (None, false) => count - 1,
+        };
+        self.focused = (0..count)
+            .map(|offset| {
+                if forward {
+                    (start + offset) % count
+                } else {
+                    (start + count - offset) % count
+                }
+            })
+            .find(|&id| self.widgets[id].is_interactive());
+    }
+
+    /// Sets the pressed slider's value from the cursor's position on it.
+    fn drag(&mut self, [x, _]: [f32; 2]) -> Option<GuiEvent> {
+        let id = self.pressed?;
+        let rect = self.rects[id];
+        let Widget::Slider(slider) = &mut self.widgets[id] else {
+            return None;
+        };
+        let knob = KNOB_WIDTH * self.scale;
+        let travel = (rect.width() - knob).max(1.0);
+        let fraction = ((x - rect.min[0] - knob * 0.5) / travel).clamp(0.0, 1.0);
+        slider
+            .set(slider.min + fraction * (slider.max - slider.min))
+            .then_some(GuiEvent::Changed(id, slider.value))
+    }
+
+    fn hit(&self, cursor: [f32; 2]) -> Option<WidgetId> {
+        self.rects
+            .iter()
+            .zip(&self.widgets)
+            .position(|(rect, widget)| widget.is_interactive() && rect.contains(cursor))
+    }
+}
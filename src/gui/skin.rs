@@ -0,0 +1,120 @@
+use super::Rect;
+use super::sprites::SpriteBatcher;
+use crate::texture::{AtlasKind, AtlasRegistry};
+
+/// Width of the stretch-free border of every `ui` atlas tile, in texels.
+const SLICE_BORDER: f32 = 4.0;
+
+/// Parts of the GUI skin, each a tile of the `ui` atlas.
+#[derive(Clone, Copy)]
+pub enum Sprite {
+    Panel,
+    Button,
+    ButtonHover,
+    ButtonPressed,
+    SliderTrack,
+    SliderKnob,
+    Focus,
+    /// A hotbar slot's background.
+    Slot,
+}
+
+impl Sprite {
+    const ALL: [Sprite; 8] = [
+        Sprite::Panel,
+        Sprite::Button,
+        Sprite::ButtonHover,
+        Sprite::ButtonPressed,
+        Sprite::SliderTrack,
+        Sprite::SliderKnob,
+        Sprite::Focus,
+        Sprite::Slot,
+    ];
+
+    fn tile_name(self) -> &'static str {
+        match self {
+            Sprite::Panel => "panel",
+            Sprite::Button => "button",
+            Sprite::ButtonHover => "button_hover",
+            Sprite::ButtonPressed => "button_pressed",
+            Sprite::SliderTrack => "slider_track",
+            Sprite::SliderKnob => "slider_knob",
+            Sprite::Focus => "focus",
+            Sprite::Slot => "slot",
+        }
+    }
+
+    /// Flat color drawn instead when the atlas lacks the tile.
+    fn fallback_color(self) -> [f32; 4] {
+        match self {
+            Sprite::Panel => [0.03, 0.03, 0.04, 0.85],
+            Sprite::Button => [0.16, 0.16, 0.2, 1.0],
+            Sprite::ButtonHover => [0.15, 0.19, 0.42, 1.0],
+            Sprite::ButtonPressed => [0.07, 0.08, 0.18, 1.0],
+            Sprite::SliderTrack => [0.01, 0.01, 0.015, 1.0],
+            Sprite::SliderKnob => [0.5, 0.5, 0.55, 1.0],
+            Sprite::Focus => [1.0, 0.75, 0.1, 1.0],
+            Sprite::Slot => [0.02, 0.02, 0.03, 0.6],
+        }
+    }
+}
+
+/// Where each [`Sprite`] is in the `ui` atlas, shared by every layer drawn
+/// with it.
+#[derive(Clone)]
+pub struct Skin {
+    /// Each sprite's `[u0, v0, width, height]` tile rect, in `Sprite::ALL`
+    /// order; `None` where the atlas lacks it.
+    tiles: [Option<[f32; 4]>; Sprite::ALL.len()],
+    /// `SLICE_BORDER` as a fraction of a tile.
+    border: f32,
+}
+
+impl Skin {
+    /// Looks every sprite up, warning about the ones that will be drawn
+    /// flat.
+    pub fn new(atlases: &AtlasRegistry) -> Self {
+        let atlas = atlases.get(AtlasKind::Ui);
+        let tiles = Sprite::ALL.map(|sprite| {
+            let atlas = atlas?;
+            let tile = atlas.tile(sprite.tile_name());
+            if tile.is_none() {
+                log::warn!(
+                    "UI atlas has no '{}' tile; drawing it flat",
+                    sprite.tile_name()
+                );
+            }
+            tile.map(|tile| atlas.layout().tile_rect(tile))
+        });
+        let border = atlas.map_or(0.0, |atlas| {
+            (SLICE_BORDER / atlas.layout().tile_size as f32).min(0.5)
+        });
+        Self { tiles, border }
+    }
+
+    /// Draws `sprite` over `rect` at `scale` screen pixels per GUI pixel,
+    /// with its alpha scaled by `opacity`.
+    pub(super) fn push(
+        &self,
+        sprites: &mut SpriteBatcher,
+        sprite: Sprite,
+        rect: Rect,
+        scale: f32,
+        opacity: f32,
+    ) {
+        match self.tiles[sprite as usize] {
+            Some(tile) => sprites.push_nine_slice(
+                rect,
+                tile,
+                self.border,
+                SLICE_BORDER * scale,
+                fade([1.0; 4], opacity),
+            ),
+            None => sprites.push_rect(rect, fade(sprite.fallback_color(), opacity)),
+        }
+    }
+}
+
+pub(super) fn fade([r, g, b, a]: [f32; 4], opacity: f32) -> [f32; 4] {
+    [r, g, b, a * opacity]
+}
@@ -0,0 +1,47 @@
+// Replaced with `true` when the surface takes gamma-encoded color (neither
+// sRGB nor HDR), since colors and atlas texels are blended as linear color.
+const ENCODE_SRGB: bool = false;
+
+@group(0) @binding(0)
+var sheet_texture: texture_2d<f32>;
+
+@group(0) @binding(1)
+var sheet_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+}
+
+@vertex
+fn vs_main(
+    @location(0) position: vec2<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) color: vec4<f32>,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.position = vec4<f32>(position, 0.0, 1.0);
+    out.uv = uv;
+    out.color = color;
+    return out;
+}
+
+fn linear_to_srgb(color: vec3<f32>) -> vec3<f32> {
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = textureSample(sheet_texture, sheet_sampler, input.uv) * input.color;
+    if (texel.a <= 0.0) {
+        discard;
+    }
+    var color = clamp(texel.rgb, vec3<f32>(0.0), vec3<f32>(1.0));
+    if (ENCODE_SRGB) {
+        color = linear_to_srgb(color);
+    }
+    return vec4<f32>(color, texel.a);
+}
@@ -0,0 +1,352 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use bytemuck::{Pod, Zeroable};
+
+use super::Rect;
+use crate::render;
+use crate::text::{self, GLYPH_HEIGHT, GLYPH_SPACING_X, GLYPH_WIDTH, GlyphInfo, SOLID_GLYPH};
+use crate::texture::{AtlasKind, AtlasRegistry};
+use crate::ui::{self, UiBatch};
+
+/// Texture a sprite is cut from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sheet {
+    /// The debug font, which also has a solid glyph for flat rectangles.
+    Font,
+    /// The `ui` atlas, when one is installed.
+    Ui,
+    /// The block atlas, for block icons.
+    Blocks,
+    /// The `items` atlas, when one is installed.
+    Items,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct SpriteVertex {
+    position: [f32; 2],
+    uv: [f32; 2],
+    color: [f32; 4],
+}
+
+/// Consecutive sprites from one sheet, drawn with a single call.
+struct SpriteRun {
+    sheet: Sheet,
+    vertices: Range<u32>,
+}
+
+/// Collects a frame's textured quads in drawing order and draws them with
+/// one call per run of sprites sharing a sheet.
+pub struct SpriteBatcher {
+    pipeline: wgpu::RenderPipeline,
+    font_bind_group: wgpu::BindGroup,
+    _font_texture: wgpu::Texture,
+    /// `None` without a `ui` atlas; its sprites are then skipped.
+    ui_bind_group: Option<wgpu::BindGroup>,
+    block_bind_group: wgpu::BindGroup,
+    /// `None` without an `items` atlas, like `ui_bind_group`.
+    item_bind_group: Option<wgpu::BindGroup>,
+    glyphs: HashMap<char, GlyphInfo>,
+    batch: UiBatch<SpriteVertex>,
+    runs: Vec<SpriteRun>,
+    viewport: [f32; 2],
+}
+
+impl SpriteBatcher {
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        surface_format: wgpu::TextureFormat,
+        atlases: &AtlasRegistry,
+    ) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("GUI sprite bind group layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        let (glyphs, font_pixels, font_size) = text::build_font_atlas();
+        let font_extent = wgpu::Extent3d {
+            width: font_size[0],
+            height: font_size[1],
+            depth_or_array_layers: 1,
+        };
+        let font_texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("GUI font texture"),
+            size: font_extent,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &font_texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            &font_pixels,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(font_size[0] * 4),
+                rows_per_image: Some(font_size[1]),
+            },
+            font_extent,
+        );
+        let font_view = render::labeled_view(&font_texture, "GUI font view");
+        let font_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("GUI font sampler"),
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+        let font_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("GUI font bind group"),
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&font_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&font_sampler),
+                },
+            ],
+        });
+        let ui_bind_group = atlases
+            .get(AtlasKind::Ui)
+            .map(|atlas| atlas.create_bind_group(device, &bind_group_layout));
+        let block_bind_group = atlases
+            .blocks()
+            .create_bind_group(device, &bind_group_layout);
+        let item_bind_group = atlases
+            .get(AtlasKind::Items)
+            .map(|atlas| atlas.create_bind_group(device, &bind_group_layout));
+
+        let source = include_str!("sprite_shader.wgsl").replace(
+            "const ENCODE_SRGB: bool = false;",
+            &format!(
+                "const ENCODE_SRGB: bool = {};",
+                !render::surface_takes_linear(surface_format)
+            ),
+        );
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("GUI sprite shader"),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("GUI sprite pipeline layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("GUI sprite pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: std::mem::size_of::<SpriteVertex>() as wgpu::BufferAddress,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &wgpu::vertex_attr_array![
+                        0 => Float32x2,
+                        1 => Float32x2,
+                        2 => Float32x4
+                    ],
+                }],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: surface_format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        Self {
+            pipeline,
+            font_bind_group,
+            _font_texture: font_texture,
+            ui_bind_group,
+            block_bind_group,
+            item_bind_group,
+            glyphs,
+            batch: UiBatch::new(device, "GUI sprite vertex buffer"),
+            runs: Vec::new(),
+            viewport: [1.0, 1.0],
+        }
+    }
+
+    /// Drops the last frame's sprites and starts one for a `viewport` sized
+    /// window.
+    pub fn begin(&mut self, viewport: [u32; 2]) {
+        self.batch.clear();
+        self.runs.clear();
+        self.viewport = [viewport[0].max(1) as f32, viewport[1].max(1) as f32];
+    }
+
+    /// Draws the `uv` rect of `sheet`, given as `[u0, v0, u1, v1]`, over
+    /// `rect`, multiplied by `color`.
+    pub fn push_sprite(&mut self, sheet: Sheet, rect: Rect, uv: [f32; 4], color: [f32; 4]) {
+        let [u0, v0, u1, v1] = uv;
+        let viewport = self.viewport;
+        let corner = |x: f32, y: f32, uv: [f32; 2]| SpriteVertex {
+            position: ui::screen_to_ndc([x, y], viewport),
+            uv,
+            color,
+        };
+        let start = self.batch.vertex_count() as u32;
+        self.batch.push_quad([
+            corner(rect.min[0], rect.min[1], [u0, v0]),
+            corner(rect.max[0], rect.min[1], [u1, v0]),
+            corner(rect.min[0], rect.max[1], [u0, v1]),
+            corner(rect.max[0], rect.max[1], [u1, v1]),
+        ]);
+        let end = self.batch.vertex_count() as u32;
+        match self.runs.last_mut() {
+            Some(run) if run.sheet == sheet => run.vertices.end = end,
+            _ => self.runs.push(SpriteRun {
+                sheet,
+                vertices: start..end,
+            }),
+        }
+    }
+
+    /// Fills `rect` with a flat color.
+    pub fn push_rect(&mut self, rect: Rect, color: [f32; 4]) {
+        let Some(solid) = self.glyphs.get(&SOLID_GLYPH).copied() else {
+            return;
+        };
+        // Sample only the glyph's center so every texel is lit.
+        let u = (solid.u0 + solid.u1) * 0.5;
+        let v = (solid.v0 + solid.v1) * 0.5;
+        self.push_sprite(Sheet::Font, rect, [u, v, u, v], color);
+    }
+
+    /// Stretches a `ui` atlas tile over `rect` without stretching its border:
+    /// the corners keep their size, the edges stretch along their length and
+    /// the middle both ways.
+    ///
+    /// `tile` is the tile's `[u0, v0, width, height]` rect, `border` the
+    /// width of its border as a fraction of the tile, and `border_size` that
+    /// border's width on screen.
+    pub fn push_nine_slice(
+        &mut self,
+        rect: Rect,
+        tile: [f32; 4],
+        border: f32,
+        border_size: f32,
+        color: [f32; 4],
+    ) {
+        let [u0, v0, du, dv] = tile;
+        let size = border_size.min(rect.width() * 0.5).min(rect.height() * 0.5);
+        let xs = [
+            rect.min[0],
+            rect.min[0] + size,
+            rect.max[0] - size,
+            rect.max[0],
+        ];
+        let ys = [
+            rect.min[1],
+            rect.min[1] + size,
+            rect.max[1] - size,
+            rect.max[1],
+        ];
+        let us = [u0, u0 + du * border, u0 + du * (1.0 - border), u0 + du];
+        let vs = [v0, v0 + dv * border, v0 + dv * (1.0 - border), v0 + dv];
+        for row in 0..3 {
+            for column in 0..3 {
+                let slice = Rect {
+                    min: [xs[column], ys[row]],
+                    max: [xs[column + 1], ys[row + 1]],
+                };
+                if slice.width() <= 0.0 || slice.height() <= 0.0 {
+                    continue;
+                }
+                let uv = [us[column], vs[row], us[column + 1], vs[row + 1]];
+                self.push_sprite(Sheet::Ui, slice, uv, color);
+            }
+        }
+    }
+
+    /// Width of `line` in screen pixels at `scale` screen pixels per font
+    /// pixel.
+    pub fn text_width(line: &str, scale: f32) -> f32 {
+        let columns = line.chars().count() as f32;
+        ((GLYPH_WIDTH + GLYPH_SPACING_X) as f32 * columns - GLYPH_SPACING_X as f32).max(0.0) * scale
+    }
+
+    pub fn text_height(scale: f32) -> f32 {
+        GLYPH_HEIGHT as f32 * scale
+    }
+
+    /// Draws `line` with its top-left corner at `origin`.
+    pub fn push_text(&mut self, origin: [f32; 2], line: &str, scale: f32, color: [f32; 4]) {
+        let advance = (GLYPH_WIDTH + GLYPH_SPACING_X) as f32 * scale;
+        let size = [GLYPH_WIDTH as f32 * scale, GLYPH_HEIGHT as f32 * scale];
+        let mut x = origin[0];
+        for ch in line.chars() {
+            if let Some(glyph) = self.glyphs.get(&text::glyph_key(ch)).copied() {
+                self.push_sprite(
+                    Sheet::Font,
+                    Rect::new([x, origin[1]], size),
+                    [glyph.u0, glyph.v0, glyph.u1, glyph.v1],
+                    color,
+                );
+            }
+            x += advance;
+        }
+    }
+
+    pub fn upload(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        self.batch.upload(device, queue);
+    }
+
+    pub fn draw<'a>(&'a self, pass: &mut wgpu::RenderPass<'a>) {
+        if self.batch.is_empty() {
+            return;
+        }
+        pass.set_pipeline(&self.pipeline);
+        for run in &self.runs {
+            let bind_group = match run.sheet {
+                Sheet::Font => Some(&self.font_bind_group),
+                Sheet::Ui => self.ui_bind_group.as_ref(),
+                Sheet::Blocks => Some(&self.block_bind_group),
+                Sheet::Items => self.item_bind_group.as_ref(),
+            };
+            let Some(bind_group) = bind_group else {
+                continue;
+            };
+            pass.set_bind_group(0, bind_group, &[]);
+            self.batch.draw_range(pass, run.vertices.clone());
+        }
+    }
+}
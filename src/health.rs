@@ -0,0 +1,80 @@
+/// Hit points of a full-health player.
+pub const MAX_HEALTH: f32 = 20.0;
+/// Seconds after a landed hit during which further hits are ignored.
+const INVULNERABILITY_SECONDS: f32 = 0.5;
+
+/// What a hit did.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HitResult {
+    /// Landed during the invulnerability window after an earlier hit.
+    Ignored,
+    Hurt,
+    Killed,
+}
+
+/// Hit points of the player or an entity.
+pub struct Health {
+    current: f32,
+    max: f32,
+    invulnerable_for: f32,
+}
+
+impl Health {
+    pub fn new(max: f32) -> Self {
+        Self {
+            current: max,
+            max,
+            invulnerable_for: 0.0,
+        }
+    }
+
+    pub fn current(&self) -> f32 {
+        self.current
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.current <= 0.0
+    }
+
+    /// Whether a recent hit still shields against the next one.
+    pub fn is_invulnerable(&self) -> bool {
+        self.invulnerable_for > 0.0
+    }
+
+    /// Counts down the invulnerability window.
+    pub fn update(&mut self, dt: f32) {
+        self.invulnerable_for = (self.invulnerable_for - dt).max(0.0);
+    }
+
+    /// Removes `amount` hit points; returns whether this killed them.
+    pub fn damage(&mut self, amount: f32) -> bool {
+        let was_alive = !self.is_dead();
+        self.current = (self.current - amount).max(0.0);
+        was_alive && self.is_dead()
+    }
+
+    /// Damage from an attack, which starts an invulnerability window instead
+    /// of stacking with other hits inside one.
+    pub fn hit(&mut self, amount: f32) -> HitResult {
+        if self.is_invulnerable() {
+            return HitResult::Ignored;
+        }
+        self.invulnerable_for = INVULNERABILITY_SECONDS;
+        if self.damage(amount) {
+            HitResult::Killed
+        } else {
+            HitResult::Hurt
+        }
+    }
+
+    /// Sets the hit points outright, as when loading a save, clamped to
+    /// what the maximum allows. Starts no invulnerability window.
+    pub fn set(&mut self, current: f32) {
+        self.current = current.clamp(0.0, self.max);
+    }
+
+    pub fn restore(&mut self) {
+        self.current = self.max;
+        self.invulnerable_for = 0.0;
+    }
+}
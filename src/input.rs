@@ -5,22 +5,38 @@ use winit::event::{DeviceEvent, VirtualKeyCode};
 use crate::camera::Camera;
 use crate::config::KeyBindings;
 
+/// Which movement keys are held, whether by the player or by a script.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub struct MovementKeys {
+    pub forward: bool,
+    pub backward: bool,
+    pub left: bool,
+    pub right: bool,
+    pub up: bool,
+    pub down: bool,
+}
+
+/// Turns held keys and mouse motion into camera turns and movement intents.
+///
+/// Each frame calls [`Self::update_orientation`] and then
+/// [`Self::movement_intent`], in that order, whatever moves the camera
+/// afterwards: the player's physics reads the intent, and walking, flying and
+/// spectating differ only in what they do with it. Scripts such as the
+/// benchmark drive the controller through [`Self::set_movement_keys`] and
+/// [`Self::turn`] instead of faking key and mouse events.
 pub struct CameraController {
     key_bindings: KeyBindings,
     speed: f32,
     turn_speed: f32,
-    forward_pressed: bool,
-    backward_pressed: bool,
-    left_pressed: bool,
-    right_pressed: bool,
-    up_pressed: bool,
-    down_pressed: bool,
+    keys: MovementKeys,
     yaw_left_pressed: bool,
     yaw_right_pressed: bool,
     pitch_up_pressed: bool,
     pitch_down_pressed: bool,
+    /// Turn in degrees queued since the last `update_orientation`.
     yaw: f32,
     pitch: f32,
+    /// `up` was pressed since the last `movement_intent`.
     up_triggered: bool,
 }
 
@@ -30,12 +46,7 @@ impl CameraController {
             key_bindings,
             speed,
             turn_speed,
-            forward_pressed: false,
-            backward_pressed: false,
-            left_pressed: false,
-            right_pressed: false,
-            up_pressed: false,
-            down_pressed: false,
+            keys: MovementKeys::default(),
             yaw_left_pressed: false,
             yaw_right_pressed: false,
             pitch_up_pressed: false,
@@ -46,62 +57,83 @@ impl CameraController {
         }
     }
 
+    /// Applies a key event; returns whether the key is one the controller
+    /// uses.
     pub fn process_keyboard(&mut self, key: VirtualKeyCode, is_pressed: bool) -> bool {
-        if key == self.key_bindings.forward {
-            self.forward_pressed = is_pressed;
-            true
-        } else if key == self.key_bindings.backward {
-            self.backward_pressed = is_pressed;
-            true
-        } else if key == self.key_bindings.left {
-            self.left_pressed = is_pressed;
-            true
-        } else if key == self.key_bindings.right {
-            self.right_pressed = is_pressed;
-            true
-        } else if key == self.key_bindings.up {
+        let bindings = &self.key_bindings;
+        let mut keys = self.keys;
+        let held = if key == bindings.forward {
+            &mut keys.forward
+        } else if key == bindings.backward {
+            &mut keys.backward
+        } else if key == bindings.left {
+            &mut keys.left
+        } else if key == bindings.right {
+            &mut keys.right
+        } else if key == bindings.up {
             if is_pressed {
                 self.up_triggered = true;
             }
-            self.up_pressed = is_pressed;
-            true
-        } else if key == self.key_bindings.down {
-            self.down_pressed = is_pressed;
-            true
+            &mut keys.up
+        } else if key == bindings.down {
+            &mut keys.down
         } else {
-            match key {
-                VirtualKeyCode::Left => {
-                    self.yaw_left_pressed = is_pressed;
-                    true
-                }
-                VirtualKeyCode::Right => {
-                    self.yaw_right_pressed = is_pressed;
-                    true
-                }
-                VirtualKeyCode::Up => {
-                    self.pitch_up_pressed = is_pressed;
-                    true
-                }
-                VirtualKeyCode::Down => {
-                    self.pitch_down_pressed = is_pressed;
-                    true
-                }
-                _ => false,
-            }
+            let turn = match key {
+                VirtualKeyCode::Left => &mut self.yaw_left_pressed,
+                VirtualKeyCode::Right => &mut self.yaw_right_pressed,
+                VirtualKeyCode::Up => &mut self.pitch_up_pressed,
+                VirtualKeyCode::Down => &mut self.pitch_down_pressed,
+                _ => return false,
+            };
+            *turn = is_pressed;
+            return true;
+        };
+        *held = is_pressed;
+        self.keys = keys;
+        true
+    }
+
+    /// Holds exactly `keys`. Starting to hold `up` queues a jump, as
+    /// pressing its key does.
+    // Only the benchmark script holds keys this way so far.
+    #[allow(dead_code)]
+    pub fn set_movement_keys(&mut self, keys: MovementKeys) {
+        if keys.up && !self.keys.up {
+            self.up_triggered = true;
         }
+        self.keys = keys;
+    }
+
+    /// Queues a turn of `yaw` and `pitch` degrees for the next
+    /// `update_orientation`.
+    pub fn turn(&mut self, yaw: f32, pitch: f32) {
+        self.yaw += yaw;
+        self.pitch += pitch;
     }
 
     pub fn add_mouse_delta(&mut self, delta: (f32, f32), sensitivity: f32) {
-        self.yaw += delta.0 * sensitivity;
-        self.pitch -= delta.1 * sensitivity;
+        self.turn(delta.0 * sensitivity, -delta.1 * sensitivity);
+    }
+
+    /// Forgets every held key, e.g. when a text prompt takes the keyboard and
+    /// their release events will not reach the controller.
+    pub fn release_keys(&mut self) {
+        self.keys = MovementKeys::default();
+        self.yaw_left_pressed = false;
+        self.yaw_right_pressed = false;
+        self.pitch_up_pressed = false;
+        self.pitch_down_pressed = false;
     }
 
+    /// Turns `camera` by the queued turn plus `dt_seconds` of the held
+    /// turning keys.
     pub fn update_orientation(&mut self, camera: &mut Camera, dt_seconds: f32) {
         let yaw_delta = (self.yaw_right_pressed as i32 - self.yaw_left_pressed as i32) as f32;
         let pitch_delta = (self.pitch_up_pressed as i32 - self.pitch_down_pressed as i32) as f32;
-
-        self.yaw += yaw_delta * self.turn_speed * dt_seconds;
-        self.pitch += pitch_delta * self.turn_speed * dt_seconds;
+        self.turn(
+            yaw_delta * self.turn_speed * dt_seconds,
+            pitch_delta * self.turn_speed * dt_seconds,
+        );
 
         camera.yaw += self.yaw;
         camera.pitch = (camera.pitch + self.pitch).clamp(-89.0_f32, 89.0_f32);
@@ -110,30 +142,32 @@ impl CameraController {
         self.pitch = 0.0;
     }
 
-    pub fn movement_input(&mut self, camera: &Camera) -> MovementInput {
+    /// Where the held keys ask to move relative to `camera`, consuming any
+    /// queued jump.
+    pub fn movement_intent(&mut self, camera: &Camera) -> MovementIntent {
         let forward = camera.forward();
         let right = forward.cross(glam::Vec3::Y).normalize_or_zero();
         let mut wish_dir = glam::Vec3::ZERO;
-        if self.forward_pressed {
+        if self.keys.forward {
             wish_dir += forward;
         }
-        if self.backward_pressed {
+        if self.keys.backward {
             wish_dir -= forward;
         }
-        if self.left_pressed {
+        if self.keys.left {
             wish_dir -= right;
         }
-        if self.right_pressed {
+        if self.keys.right {
             wish_dir += right;
         }
 
         let jump = self.up_triggered;
         self.up_triggered = false;
 
-        MovementInput {
+        MovementIntent {
             wish_dir,
-            ascend: self.up_pressed,
-            descend: self.down_pressed,
+            ascend: self.keys.up,
+            descend: self.keys.down,
             jump,
             speed: self.speed,
         }
@@ -144,7 +178,8 @@ impl CameraController {
 pub struct MouseState {
     pub captured: bool,
     pub sensitivity: f32,
-    pub max_frame_time: Option<f32>,
+    /// Frame rate cap, if any.
+    pub max_fps: Option<f32>,
 }
 
 impl MouseState {
@@ -153,11 +188,10 @@ impl MouseState {
         if !clamped.is_finite() || clamped <= 0.0 {
             clamped = 0.001;
         }
-        let max_frame_time = max_fps.map(|fps| 1.0 / fps.max(1.0));
         Self {
             captured: false,
             sensitivity: clamped,
-            max_frame_time,
+            max_fps,
         }
     }
 
@@ -177,16 +211,20 @@ impl MouseState {
     }
 
     pub fn frame_sleep(&self, frame_elapsed: f32) {
-        if let Some(cap) = self.max_frame_time
-            && frame_elapsed < cap
-        {
+        let Some(fps) = self.max_fps else {
+            return;
+        };
+        let cap = 1.0 / fps.max(1.0);
+        if frame_elapsed < cap {
             std::thread::sleep(Duration::from_secs_f32(cap - frame_elapsed));
         }
     }
 }
 
-#[derive(Clone, Copy)]
-pub struct MovementInput {
+/// One frame's movement request, read by the player's physics. The default
+/// asks for nothing.
+#[derive(Clone, Copy, Default)]
+pub struct MovementIntent {
     pub wish_dir: glam::Vec3,
     pub ascend: bool,
     pub descend: bool,
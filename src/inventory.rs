@@ -0,0 +1,358 @@
+use std::f32::consts::{FRAC_PI_2, TAU};
+
+use glam::Vec2;
+
+use crate::block::{BlockKind, FaceDirection};
+use crate::item::{ItemKind, ItemStack, ToolTier, ToolType};
+use crate::text::RadialMenu;
+
+/// Pointer travel (in mouse counts or stick units) the radial picker's
+/// cursor is clamped to.
+const PICKER_RANGE: f32 = 60.0;
+/// The cursor must leave this radius before a slot is highlighted.
+const PICKER_DEAD_ZONE: f32 = 15.0;
+
+/// Slots in the hotbar row, the only ones the player can hold items from.
+pub const HOTBAR_SLOTS: usize = 9;
+/// Slots in the whole inventory: the hotbar row, then three rows of storage.
+pub const INVENTORY_SLOTS: usize = 36;
+
+/// The player's item slots. The first `HOTBAR_SLOTS` are the hotbar, one of
+/// which is selected; the rest are storage reached through the inventory
+/// screen. Stacks are not consumed yet: placing a block leaves its stack as
+/// it was.
+pub struct Inventory {
+    /// `INVENTORY_SLOTS` slots, `None` for an empty one.
+    slots: Vec<Option<ItemStack>>,
+    /// Always in the hotbar row.
+    selected: usize,
+}
+
+impl Inventory {
+    pub fn new() -> Self {
+        let blocks = [
+            BlockKind::Grass,
+            BlockKind::Dirt,
+            BlockKind::Stone,
+            BlockKind::Glass,
+            BlockKind::Metal,
+            BlockKind::Lamp,
+            BlockKind::SnowLayer(1),
+            BlockKind::Farmland,
+            BlockKind::Wheat(0),
+            BlockKind::Sand,
+            BlockKind::Gravel,
+            BlockKind::Flower(0),
+            BlockKind::Lever(false),
+            BlockKind::Wire(0),
+            BlockKind::SignalLamp(false),
+            BlockKind::Piston(FaceDirection::PosY, false),
+            BlockKind::Water,
+            BlockKind::Log,
+            BlockKind::Leaves,
+        ];
+        let items = [
+            ItemKind::Tool(ToolType::Pickaxe, ToolTier::Wooden),
+            ItemKind::Tool(ToolType::Pickaxe, ToolTier::Stone),
+            ItemKind::Tool(ToolType::Pickaxe, ToolTier::Iron),
+            ItemKind::Tool(ToolType::Shovel, ToolTier::Iron),
+            ItemKind::Tool(ToolType::Axe, ToolTier::Iron),
+            ItemKind::Stick,
+            ItemKind::Bucket,
+        ];
+        let mut slots: Vec<_> = blocks
+            .into_iter()
+            .map(ItemKind::Block)
+            .chain(items)
+            .map(|item| Some(ItemStack::full(item)))
+            .collect();
+        slots.resize(INVENTORY_SLOTS, None);
+        Self { slots, selected: 0 }
+    }
+
+    /// The selected stack, or `None` for an empty hand.
+    pub fn selected(&self) -> Option<ItemStack> {
+        self.slots[self.selected]
+    }
+
+    /// Selects hotbar slot `index`; other slots cannot be selected.
+    pub fn select_index(&mut self, index: usize) {
+        if index < HOTBAR_SLOTS {
+            self.selected = index;
+        }
+    }
+
+    /// Moves the selection along the hotbar, wrapping at either end.
+    pub fn cycle(&mut self, offset: isize) {
+        let len = HOTBAR_SLOTS as isize;
+        let index = (self.selected as isize + offset).rem_euclid(len);
+        self.selected = index as usize;
+    }
+
+    /// Selects the hotbar slot holding `block`. A stack of it in storage is
+    /// swapped into the selected slot instead. Returns whether one was
+    /// found.
+    pub fn select_block(&mut self, block: BlockKind) -> bool {
+        let Some(index) = self
+            .slots
+            .iter()
+            .position(|slot| slot.is_some_and(|stack| stack.item == ItemKind::Block(block)))
+        else {
+            return false;
+        };
+        if index < HOTBAR_SLOTS {
+            self.selected = index;
+        } else {
+            self.slots.swap(index, self.selected);
+        }
+        true
+    }
+
+    /// Adds `stack` to a matching stack with room for it, or else to the
+    /// first empty slot, returning whether it fit. The hotbar fills before
+    /// storage.
+    pub fn collect(&mut self, stack: ItemStack) -> bool {
+        if let Some(slot) = self.slots.iter_mut().flatten().find(|slot| {
+            slot.item == stack.item
+                && slot.count as u16 + stack.count as u16 <= slot.item.max_stack() as u16
+        }) {
+            slot.count += stack.count;
+            return true;
+        }
+        if let Some(empty) = self.slots.iter_mut().find(|slot| slot.is_none()) {
+            *empty = Some(stack);
+            return true;
+        }
+        false
+    }
+
+    /// Empties every slot, returning the stacks they held.
+    pub fn take_all(&mut self) -> Vec<ItemStack> {
+        self.slots.iter_mut().filter_map(Option::take).collect()
+    }
+
+    pub fn selected_index(&self) -> usize {
+        self.selected
+    }
+
+    /// Every slot in order, the hotbar row first; `None` for an empty one.
+    pub fn slots(&self) -> &[Option<ItemStack>] {
+        &self.slots
+    }
+
+    /// The hotbar row.
+    pub fn hotbar(&self) -> &[Option<ItemStack>] {
+        &self.slots[..HOTBAR_SLOTS]
+    }
+
+    pub fn hotbar_names(&self) -> Vec<String> {
+        self.hotbar()
+            .iter()
+            .map(|slot| {
+                slot.map_or("Empty", |stack| stack.item.display_name())
+                    .to_string()
+            })
+            .collect()
+    }
+
+    /// Left click on slot `index` with `held` on the cursor: picks the
+    /// stack up, puts the held one down, tops up a matching stack with as
+    /// much of it as fits, or swaps the two.
+    pub fn click(&mut self, index: usize, held: &mut Option<ItemStack>) {
+        let Some(slot) = self.slots.get_mut(index) else {
+            return;
+        };
+        match (slot.as_mut(), held.as_mut()) {
+            (Some(stack), Some(carried)) if stack.item == carried.item => {
+                let moved = carried
+                    .count
+                    .min(stack.item.max_stack().saturating_sub(stack.count));
+                stack.count += moved;
+                carried.count -= moved;
+                if carried.count == 0 {
+                    *held = None;
+                }
+            }
+            _ => std::mem::swap(slot, held),
+        }
+    }
+
+    /// Right click on slot `index` with `held` on the cursor: picks up half
+    /// the stack, rounded up, or puts one of the held items down on an empty
+    /// or matching slot. Swaps otherwise.
+    pub fn split_click(&mut self, index: usize, held: &mut Option<ItemStack>) {
+        let Some(slot) = self.slots.get_mut(index) else {
+            return;
+        };
+        match (slot.as_mut(), held.as_mut()) {
+            (Some(stack), None) => {
+                let taken = stack.count.div_ceil(2);
+                *held = Some(ItemStack {
+                    count: taken,
+                    ..*stack
+                });
+                stack.count -= taken;
+                if stack.count == 0 {
+                    *slot = None;
+                }
+            }
+            (None, Some(carried)) => {
+                *slot = Some(ItemStack {
+                    count: 1,
+                    ..*carried
+                });
+                carried.count -= 1;
+                if carried.count == 0 {
+                    *held = None;
+                }
+            }
+            (Some(stack), Some(carried)) if stack.item == carried.item => {
+                if stack.count < stack.item.max_stack() {
+                    stack.count += 1;
+                    carried.count -= 1;
+                    if carried.count == 0 {
+                        *held = None;
+                    }
+                }
+            }
+            _ => std::mem::swap(slot, held),
+        }
+    }
+}
+
+/// Hold-to-open ring of hotbar slots, steered by any 2D input.
+///
+/// Slots are laid out clockwise from the top; pointing the cursor past the
+/// dead zone highlights the slot in that direction and releasing the picker
+/// selects it.
+pub struct RadialPicker {
+    open: bool,
+    cursor: Vec2,
+    slot_count: usize,
+}
+
+impl RadialPicker {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            cursor: Vec2::ZERO,
+            slot_count: 0,
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn open(&mut self, slot_count: usize) {
+        self.open = true;
+        self.cursor = Vec2::ZERO;
+        self.slot_count = slot_count;
+    }
+
+    /// Moves the cursor by a screen-space delta (y down), as given by mouse
+    /// motion or a stick deflection scaled to `PICKER_RANGE`.
+    pub fn steer(&mut self, delta: Vec2) {
+        if self.open {
+            self.cursor = (self.cursor + delta).clamp_length_max(PICKER_RANGE);
+        }
+    }
+
+    /// Closes the picker, returning the highlighted slot if there is one.
+    pub fn close(&mut self) -> Option<usize> {
+        let choice = self.highlighted();
+        self.open = false;
+        choice
+    }
+
+    /// Closes the picker without changing the selection.
+    pub fn cancel(&mut self) {
+        self.open = false;
+    }
+
+    pub fn highlighted(&self) -> Option<usize> {
+        if !self.open || self.slot_count == 0 || self.cursor.length() < PICKER_DEAD_ZONE {
+            return None;
+        }
+        let sector = TAU / self.slot_count as f32;
+        // Angle measured clockwise from straight up.
+        let angle = (self.cursor.y.atan2(self.cursor.x) + FRAC_PI_2).rem_euclid(TAU);
+        Some(((angle / sector).round() as usize) % self.slot_count)
+    }
+
+    /// What the overlay should draw, or `None` while closed.
+    pub fn menu(&self, inventory: &Inventory) -> Option<RadialMenu> {
+        if !self.open {
+            return None;
+        }
+        Some(RadialMenu {
+            labels: inventory.hotbar_names(),
+            highlighted: self.highlighted(),
+            selected: inventory.selected_index(),
+            pointer: (self.cursor / PICKER_RANGE).to_array(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dirt(count: u8) -> ItemStack {
+        ItemStack::new(ItemKind::Block(BlockKind::Dirt), count)
+    }
+
+    fn empty() -> Inventory {
+        Inventory {
+            slots: vec![None; INVENTORY_SLOTS],
+            selected: 0,
+        }
+    }
+
+    #[test]
+    fn clicking_moves_merges_and_swaps_stacks() {
+        let mut inventory = empty();
+        inventory.slots[0] = Some(dirt(60));
+        inventory.slots[1] = Some(dirt(10));
+        inventory.slots[2] = Some(ItemStack::full(ItemKind::Stick));
+        let mut held = None;
+
+        inventory.click(1, &mut held);
+        assert_eq!((inventory.slots[1], held), (None, Some(dirt(10))));
+        // Only what fits tops the stack up; the rest stays carried.
+        inventory.click(0, &mut held);
+        assert_eq!((inventory.slots[0], held), (Some(dirt(64)), Some(dirt(6))));
+        inventory.click(2, &mut held);
+        assert_eq!(inventory.slots[2], Some(dirt(6)));
+        assert_eq!(held, Some(ItemStack::full(ItemKind::Stick)));
+    }
+
+    #[test]
+    fn right_clicking_splits_and_places_single_items() {
+        let mut inventory = empty();
+        inventory.slots[0] = Some(dirt(5));
+        let mut held = None;
+
+        inventory.split_click(0, &mut held);
+        assert_eq!((inventory.slots[0], held), (Some(dirt(2)), Some(dirt(3))));
+        inventory.split_click(1, &mut held);
+        inventory.split_click(0, &mut held);
+        assert_eq!(inventory.slots[0], Some(dirt(3)));
+        assert_eq!(inventory.slots[1], Some(dirt(1)));
+        assert_eq!(held, Some(dirt(1)));
+        inventory.split_click(1, &mut held);
+        assert_eq!((inventory.slots[1], held), (Some(dirt(2)), None));
+    }
+
+    #[test]
+    fn picking_a_stored_block_swaps_it_into_the_hand() {
+        let mut inventory = empty();
+        inventory.slots[HOTBAR_SLOTS + 4] = Some(dirt(1));
+        inventory.select_index(3);
+
+        assert!(inventory.select_block(BlockKind::Dirt));
+        assert_eq!(inventory.selected_index(), 3);
+        assert_eq!(inventory.selected(), Some(dirt(1)));
+        assert!(!inventory.select_block(BlockKind::Stone));
+    }
+}
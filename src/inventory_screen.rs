@@ -0,0 +1,151 @@
+use winit::event::{ElementState, MouseButton, VirtualKeyCode, WindowEvent};
+
+use crate::gui::Rect;
+use crate::inventory::{HOTBAR_SLOTS, INVENTORY_SLOTS, Inventory};
+use crate::item::ItemStack;
+
+/// Sizes in GUI pixels, which are `scale` screen pixels each.
+const SLOT_SIZE: f32 = 20.0;
+const COLUMNS: usize = HOTBAR_SLOTS;
+const TITLE_HEIGHT: f32 = 16.0;
+const PANEL_PADDING: f32 = 10.0;
+/// Space between storage and the hotbar row below it.
+const HOTBAR_GAP: f32 = 6.0;
+
+/// The inventory grid, opened with the inventory key (`E`): storage in
+/// three rows above the hotbar row, as in the HUD.
+///
+/// Left click picks a stack up or puts the carried one down, merging or
+/// swapping with what is there; right click picks up half a stack or puts
+/// down one item. The inventory key or Escape closes the screen, handing
+/// back whatever is still carried.
+pub struct InventoryScreen {
+    /// Screen pixels per GUI pixel.
+    scale: f32,
+    /// The key that opened the screen, which also closes it.
+    key: VirtualKeyCode,
+    panel: Rect,
+    /// Band along the top of the panel holding its title.
+    title: Rect,
+    /// Each inventory slot's screen rect as of the last layout, by index.
+    slots: Vec<Rect>,
+    cursor: Option<[f32; 2]>,
+    /// Stack picked up and following the cursor.
+    held: Option<ItemStack>,
+}
+
+impl InventoryScreen {
+    pub fn new(scale: f32, key: VirtualKeyCode) -> Self {
+        Self {
+            scale: scale.max(1.0),
+            key,
+            panel: Rect::default(),
+            title: Rect::default(),
+            slots: vec![Rect::default(); INVENTORY_SLOTS],
+            cursor: None,
+            held: None,
+        }
+    }
+
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    pub fn panel(&self) -> Rect {
+        self.panel
+    }
+
+    pub fn title_rect(&self) -> Rect {
+        self.title
+    }
+
+    /// Each slot's screen rect, by inventory index.
+    pub fn slot_rects(&self) -> &[Rect] {
+        &self.slots
+    }
+
+    pub fn cursor(&self) -> Option<[f32; 2]> {
+        self.cursor
+    }
+
+    pub fn held(&self) -> Option<ItemStack> {
+        self.held
+    }
+
+    /// The slot under the cursor.
+    pub fn hovered(&self) -> Option<usize> {
+        let cursor = self.cursor?;
+        self.slots.iter().position(|rect| rect.contains(cursor))
+    }
+
+    /// Centers the grid in a `viewport` sized window.
+    pub fn layout(&mut self, viewport: [u32; 2]) {
+        let scale = self.scale;
+        let padding = PANEL_PADDING * scale;
+        let slot = SLOT_SIZE * scale;
+        let rows = INVENTORY_SLOTS / COLUMNS;
+        let size = [
+            slot * COLUMNS as f32 + padding * 2.0,
+            TITLE_HEIGHT * scale + slot * rows as f32 + HOTBAR_GAP * scale + padding * 2.0,
+        ];
+        let min = [
+            ((viewport[0] as f32 - size[0]) * 0.5).round(),
+            ((viewport[1] as f32 - size[1]) * 0.5).round(),
+        ];
+        self.panel = Rect::new(min, size);
+        self.title = Rect::new(
+            [min[0] + padding, min[1] + padding],
+            [slot * COLUMNS as f32, TITLE_HEIGHT * scale],
+        );
+
+        let grid_top = self.title.max[1];
+        for (index, rect) in self.slots.iter_mut().enumerate() {
+            let column = index % COLUMNS;
+            let top = if index < HOTBAR_SLOTS {
+                grid_top + slot * (rows - 1) as f32 + HOTBAR_GAP * scale
+            } else {
+                grid_top + slot * (index / COLUMNS - 1) as f32
+            };
+            *rect = Rect::new([min[0] + padding + slot * column as f32, top], [slot, slot]);
+        }
+    }
+
+    /// Handles one window event, moving stacks in `inventory` as it is
+    /// clicked. Returns whether the player asked to close the screen.
+    pub fn input(&mut self, event: &WindowEvent, inventory: &mut Inventory) -> bool {
+        match event {
+            WindowEvent::CursorMoved { position, .. } => {
+                self.cursor = Some([position.x as f32, position.y as f32]);
+                false
+            }
+            WindowEvent::CursorLeft { .. } => {
+                self.cursor = None;
+                false
+            }
+            WindowEvent::MouseInput {
+                state: ElementState::Pressed,
+                button,
+                ..
+            } => {
+                if let Some(index) = self.hovered() {
+                    match button {
+                        MouseButton::Left => inventory.click(index, &mut self.held),
+                        MouseButton::Right => inventory.split_click(index, &mut self.held),
+                        _ => {}
+                    }
+                }
+                false
+            }
+            WindowEvent::KeyboardInput { input, .. } if input.state == ElementState::Pressed => {
+                matches!(input.virtual_keycode, Some(key) if key == self.key || key == VirtualKeyCode::Escape)
+            }
+            _ => false,
+        }
+    }
+
+    /// Closes the screen, returning the carried stack if it does not fit
+    /// back into `inventory`.
+    pub fn close(mut self, inventory: &mut Inventory) -> Option<ItemStack> {
+        self.held.take().filter(|&stack| !inventory.collect(stack))
+    }
+}
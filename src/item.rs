@@ -0,0 +1,226 @@
+use crate::block::{BlockId, BlockKind, BlockShape, FaceDirection};
+
+/// Item ids below `FIRST_ITEM` are block items and equal their block's id.
+pub type ItemId = u16;
+
+const FIRST_ITEM: ItemId = 256;
+pub const ITEM_STICK: ItemId = 256;
+pub const ITEM_BUCKET: ItemId = 257;
+/// Ids `ITEM_TOOL..ITEM_TOOL + 9` hold each tool type in each tier, ordered
+/// by type then tier.
+pub const ITEM_TOOL: ItemId = 258;
+const ITEM_TOOL_LAST: ItemId = ITEM_TOOL + 8;
+const ITEM_COUNT: usize = (ITEM_TOOL_LAST - FIRST_ITEM) as usize + 1;
+
+/// Stack size of block items.
+pub const BLOCK_MAX_STACK: u8 = 64;
+
+/// What a tool is good at breaking.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ToolType {
+    Pickaxe,
+    Shovel,
+    Axe,
+}
+
+/// Tool material; each tier breaks faster than the last and can harvest
+/// harder blocks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ToolTier {
+    Wooden,
+    Stone,
+    Iron,
+}
+
+impl ToolTier {
+    /// Break speed multiplier on blocks the tool type suits.
+    pub const fn speed(self) -> f32 {
+        match self {
+            ToolTier::Wooden => 2.0,
+            ToolTier::Stone => 4.0,
+            ToolTier::Iron => 6.0,
+        }
+    }
+}
+
+/// Where an item's inventory icon comes from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ItemIcon {
+    /// The block's top face (or its sprite, for cross-shaped plants) from the
+    /// block atlas.
+    Block(BlockKind),
+    /// A named tile in the items atlas.
+    Tile(&'static str),
+}
+
+/// Registry entry for an item that is not a block, indexed by
+/// `id - FIRST_ITEM`.
+#[derive(Clone, Copy)]
+pub struct ItemDefinition {
+    pub display_name: &'static str,
+    pub max_stack: u8,
+    /// Tile name in `items.json`.
+    pub icon: &'static str,
+}
+
+const ITEM_DEFINITIONS: [ItemDefinition; ITEM_COUNT] = [
+    // ITEM_STICK
+    ItemDefinition {
+        display_name: "Stick",
+        max_stack: 64,
+        icon: "stick",
+    },
+    // ITEM_BUCKET
+    ItemDefinition {
+        display_name: "Bucket",
+        max_stack: 16,
+        icon: "bucket",
+    },
+    tool("Wooden Pickaxe", "wooden_pickaxe"),
+    tool("Stone Pickaxe", "stone_pickaxe"),
+    tool("Iron Pickaxe", "iron_pickaxe"),
+    tool("Wooden Shovel", "wooden_shovel"),
+    tool("Stone Shovel", "stone_shovel"),
+    tool("Iron Shovel", "iron_shovel"),
+    tool("Wooden Axe", "wooden_axe"),
+    tool("Stone Axe", "stone_axe"),
+    tool("Iron Axe", "iron_axe"),
+];
+
+const fn tool(display_name: &'static str, icon: &'static str) -> ItemDefinition {
+    ItemDefinition {
+        display_name,
+        max_stack: 1,
+        icon,
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ItemKind {
+    /// Places the block; stateful blocks carry the state they are placed in.
+    Block(BlockKind),
+    /// Crafting material.
+    Stick,
+    /// Will carry fluids once they exist.
+    Bucket,
+    /// Speeds up breaking the blocks its type suits.
+    Tool(ToolType, ToolTier),
+}
+
+impl ItemKind {
+    pub const fn id(self) -> ItemId {
+        match self {
+            ItemKind::Block(kind) => kind.id() as ItemId,
+            ItemKind::Stick => ITEM_STICK,
+            ItemKind::Bucket => ITEM_BUCKET,
+            ItemKind::Tool(tool_type, tier) => ITEM_TOOL + tool_type as ItemId * 3 + tier as ItemId,
+        }
+    }
+
+    /// The item with `id`, if there is one, as stored in saves.
+    pub fn from_id(id: ItemId) -> Option<Self> {
+        match id {
+            0..FIRST_ITEM => {
+                let kind = BlockKind::from_id(id as BlockId);
+                (kind != BlockKind::Air && kind.id() as ItemId == id)
+                    .then_some(ItemKind::Block(kind))
+            }
+            ITEM_STICK => Some(ItemKind::Stick),
+            ITEM_BUCKET => Some(ItemKind::Bucket),
+            ITEM_TOOL..=ITEM_TOOL_LAST => {
+                let index = id - ITEM_TOOL;
+                let tool_type =
+                    [ToolType::Pickaxe, ToolType::Shovel, ToolType::Axe][index as usize / 3];
+                let tier = [ToolTier::Wooden, ToolTier::Stone, ToolTier::Iron][index as usize % 3];
+                Some(ItemKind::Tool(tool_type, tier))
+            }
+            _ => None,
+        }
+    }
+
+    /// The tool this item is used as, if any.
+    pub const fn as_tool(self) -> Option<(ToolType, ToolTier)> {
+        match self {
+            ItemKind::Tool(tool_type, tier) => Some((tool_type, tier)),
+            _ => None,
+        }
+    }
+
+    /// The block placed by this item, if it places one.
+    pub const fn as_block(self) -> Option<BlockKind> {
+        match self {
+            ItemKind::Block(kind) => Some(kind),
+            _ => None,
+        }
+    }
+
+    fn definition(self) -> Option<&'static ItemDefinition> {
+        let id = self.id();
+        (id >= FIRST_ITEM).then(|| &ITEM_DEFINITIONS[(id - FIRST_ITEM) as usize])
+    }
+
+    pub fn display_name(self) -> &'static str {
+        match (self, self.definition()) {
+            (ItemKind::Block(kind), _) => kind.display_name(),
+            (_, Some(definition)) => definition.display_name,
+            _ => "Unknown",
+        }
+    }
+
+    pub fn max_stack(self) -> u8 {
+        self.definition()
+            .map_or(BLOCK_MAX_STACK, |definition| definition.max_stack)
+    }
+
+    pub fn icon(self) -> ItemIcon {
+        match (self, self.definition()) {
+            (ItemKind::Block(kind), _) => ItemIcon::Block(kind),
+            (_, Some(definition)) => ItemIcon::Tile(definition.icon),
+            _ => unreachable!("non-block items are registered"),
+        }
+    }
+}
+
+impl ItemIcon {
+    /// Block face drawn for a block icon.
+    pub fn block_face(kind: BlockKind) -> FaceDirection {
+        match kind.definition().shape {
+            BlockShape::Cross => FaceDirection::NegX,
+            BlockShape::Cube | BlockShape::Liquid => FaceDirection::PosY,
+        }
+    }
+}
+
+/// Some number of one item, plus per-stack data.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ItemStack {
+    pub item: ItemKind,
+    /// `1..=item.max_stack()`.
+    pub count: u8,
+    /// Per-stack state such as tool wear; 0 for items without any.
+    pub data: u16,
+}
+
+impl ItemStack {
+    pub fn new(item: ItemKind, count: u8) -> Self {
+        Self {
+            item,
+            count: count.clamp(1, item.max_stack()),
+            data: 0,
+        }
+    }
+
+    /// A stack holding as many of `item` as fit.
+    pub fn full(item: ItemKind) -> Self {
+        Self::new(item, item.max_stack())
+    }
+
+    /// Display name, with the count when there is more than one.
+    pub fn label(&self) -> String {
+        if self.count > 1 {
+            format!("{} x{}", self.item.display_name(), self.count)
+        } else {
+            self.item.display_name().to_string()
+        }
+    }
+}
@@ -0,0 +1,334 @@
+use std::io::{self, Read};
+
+use flate2::read::ZlibDecoder;
+
+use crate::basis::{BasisLz, Etc1sTarget};
+
+const IDENTIFIER: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+const HEADER_LEN: usize = 80;
+const LEVEL_INDEX_ENTRY_LEN: usize = 24;
+
+const SUPERCOMPRESSION_NONE: u32 = 0;
+const SUPERCOMPRESSION_BASIS_LZ: u32 = 1;
+const SUPERCOMPRESSION_ZSTD: u32 = 2;
+const SUPERCOMPRESSION_ZLIB: u32 = 3;
+
+/// Data format descriptor color model of UASTC, the other Basis codec.
+const KHR_DF_MODEL_UASTC: u8 = 166;
+const KHR_DF_TRANSFER_SRGB: u8 = 2;
+
+const VK_FORMAT_UNDEFINED: u32 = 0;
+const VK_FORMAT_R8G8B8A8_UNORM: u32 = 37;
+const VK_FORMAT_R8G8B8A8_SRGB: u32 = 43;
+const VK_FORMAT_BC3_UNORM_BLOCK: u32 = 137;
+const VK_FORMAT_BC3_SRGB_BLOCK: u32 = 138;
+const VK_FORMAT_BC7_UNORM_BLOCK: u32 = 145;
+const VK_FORMAT_BC7_SRGB_BLOCK: u32 = 146;
+const VK_FORMAT_ETC2_R8G8B8A8_UNORM_BLOCK: u32 = 151;
+const VK_FORMAT_ETC2_R8G8B8A8_SRGB_BLOCK: u32 = 152;
+const VK_FORMAT_ASTC_4X4_UNORM_BLOCK: u32 = 157;
+const VK_FORMAT_ASTC_4X4_SRGB_BLOCK: u32 = 158;
+
+/// A parsed KTX2 texture whose payload can be uploaded to the GPU as-is.
+///
+/// Only single-layer 2D textures are accepted. Levels may be ZLIB
+/// supercompressed, and Basis Universal ETC1S (BasisLZ) payloads are
+/// transcoded while parsing; UASTC and Zstandard payloads must be transcoded
+/// offline (e.g. `basisu -unpack` or `toktx`) into one of the formats listed
+/// in [`Ktx2Texture::parse`].
+pub struct Ktx2Texture {
+    pub format: wgpu::TextureFormat,
+    pub width: u32,
+    pub height: u32,
+    /// Mip levels ordered from the base level down.
+    pub levels: Vec<Vec<u8>>,
+}
+
+impl Ktx2Texture {
+    /// Parses a KTX2 file holding RGBA8, BC3, BC7, ETC2 RGBA, or ASTC 4x4
+    /// data, keeping its declared UNORM or sRGB encoding, or Basis ETC1S data,
+    /// which becomes ETC2 RGB when `features` can sample it and the image is
+    /// opaque, and RGBA8 otherwise.
+    pub fn parse(bytes: &[u8], features: wgpu::Features) -> io::Result<Self> {
+        if bytes.len() < HEADER_LEN || bytes[..IDENTIFIER.len()] != IDENTIFIER {
+            return Err(invalid("not a KTX2 file"));
+        }
+
+        let vk_format = read_u32(bytes, 12);
+        let width = read_u32(bytes, 20);
+        let height = read_u32(bytes, 24);
+        let depth = read_u32(bytes, 28);
+        let layer_count = read_u32(bytes, 32);
+        let face_count = read_u32(bytes, 36);
+        let level_count = read_u32(bytes, 40).max(1) as usize;
+        let supercompression = read_u32(bytes, 44);
+
+        if width == 0 || height == 0 || depth > 1 || layer_count > 1 || face_count != 1 {
+            return Err(invalid("only single-layer 2D textures are supported"));
+        }
+        // A full chain ends at 1x1; more levels would shift the size away.
+        let max_levels = (u32::BITS - width.max(height).leading_zeros()) as usize;
+        if level_count > max_levels {
+            return Err(invalid(format!(
+                "{level_count} mip levels for a {width}x{height} texture; at most {max_levels}"
+            )));
+        }
+
+        let index_end = HEADER_LEN + level_count * LEVEL_INDEX_ENTRY_LEN;
+        if bytes.len() < index_end {
+            return Err(invalid("truncated level index"));
+        }
+        let level_data = |level: usize| {
+            let entry = HEADER_LEN + level * LEVEL_INDEX_ENTRY_LEN;
+            let offset = read_u64(bytes, entry) as usize;
+            let length = read_u64(bytes, entry + 8) as usize;
+            offset
+                .checked_add(length)
+                .and_then(|end| bytes.get(offset..end))
+                .ok_or_else(|| invalid(format!("level {level} lies outside the file")))
+        };
+        let level_size = |level: usize| ((width >> level).max(1), (height >> level).max(1));
+
+        if supercompression == SUPERCOMPRESSION_BASIS_LZ {
+            if vk_format != VK_FORMAT_UNDEFINED {
+                return Err(invalid(format!("BasisLZ data with vkFormat {vk_format}")));
+            }
+            let srgb = data_format(bytes)?.transfer == KHR_DF_TRANSFER_SRGB;
+            let basis = BasisLz::parse(
+                section(bytes, 64, "supercompression global data")?,
+                level_count,
+            )?;
+            let etc2 = if srgb {
+                wgpu::TextureFormat::Etc2Rgb8UnormSrgb
+            } else {
+                wgpu::TextureFormat::Etc2Rgb8Unorm
+            };
+            let (target, format) = if !basis.has_alpha()
+                && features.contains(etc2.required_features())
+                && width.is_multiple_of(4)
+                && height.is_multiple_of(4)
+            {
+                (Etc1sTarget::Etc1, etc2)
+            } else if srgb {
+                (Etc1sTarget::Rgba8, wgpu::TextureFormat::Rgba8UnormSrgb)
+            } else {
+                (Etc1sTarget::Rgba8, wgpu::TextureFormat::Rgba8Unorm)
+            };
+            let levels = (0..level_count)
+                .map(|level| {
+                    let (level_width, level_height) = level_size(level);
+                    basis.transcode(level, level_data(level)?, level_width, level_height, target)
+                })
+                .collect::<io::Result<_>>()?;
+            return Ok(Self {
+                format,
+                width,
+                height,
+                levels,
+            });
+        }
+
+        let format = match vk_format {
+            VK_FORMAT_R8G8B8A8_UNORM => wgpu::TextureFormat::Rgba8Unorm,
+            VK_FORMAT_R8G8B8A8_SRGB => wgpu::TextureFormat::Rgba8UnormSrgb,
+            VK_FORMAT_BC3_UNORM_BLOCK => wgpu::TextureFormat::Bc3RgbaUnorm,
+            VK_FORMAT_BC3_SRGB_BLOCK => wgpu::TextureFormat::Bc3RgbaUnormSrgb,
+            VK_FORMAT_BC7_UNORM_BLOCK => wgpu::TextureFormat::Bc7RgbaUnorm,
+            VK_FORMAT_BC7_SRGB_BLOCK => wgpu::TextureFormat::Bc7RgbaUnormSrgb,
+            VK_FORMAT_ETC2_R8G8B8A8_UNORM_BLOCK => wgpu::TextureFormat::Etc2Rgba8Unorm,
+            VK_FORMAT_ETC2_R8G8B8A8_SRGB_BLOCK => wgpu::TextureFormat::Etc2Rgba8UnormSrgb,
+            VK_FORMAT_ASTC_4X4_UNORM_BLOCK | VK_FORMAT_ASTC_4X4_SRGB_BLOCK => {
+                wgpu::TextureFormat::Astc {
+                    block: wgpu::AstcBlock::B4x4,
+                    channel: if vk_format == VK_FORMAT_ASTC_4X4_SRGB_BLOCK {
+                        wgpu::AstcChannel::UnormSrgb
+                    } else {
+                        wgpu::AstcChannel::Unorm
+                    },
+                }
+            }
+            VK_FORMAT_UNDEFINED if data_format(bytes)?.model == KHR_DF_MODEL_UASTC => {
+                return Err(invalid(
+                    "Basis UASTC data is not supported; transcode the texture offline",
+                ));
+            }
+            other => return Err(invalid(format!("unsupported vkFormat {other}"))),
+        };
+
+        let mut levels = Vec::with_capacity(level_count);
+        for level in 0..level_count {
+            let (level_width, level_height) = level_size(level);
+            let expected = level_byte_len(format, level_width, level_height);
+            let data = level_data(level)?;
+            let data = match supercompression {
+                SUPERCOMPRESSION_NONE => data.to_vec(),
+                SUPERCOMPRESSION_ZLIB => {
+                    // Reads one byte past the level, so trailing data is caught.
+                    let mut inflated = Vec::with_capacity(expected);
+                    ZlibDecoder::new(data)
+                        .take(expected as u64 + 1)
+                        .read_to_end(&mut inflated)
+                        .map_err(|err| invalid(format!("level {level} does not inflate: {err}")))?;
+                    inflated
+                }
+                SUPERCOMPRESSION_ZSTD => {
+                    return Err(invalid(
+                        "Zstandard supercompression is not supported; transcode the texture \
+                         offline",
+                    ));
+                }
+                other => return Err(invalid(format!("unknown supercompression scheme {other}"))),
+            };
+            if data.len() != expected {
+                return Err(invalid(format!(
+                    "level {level} holds {} bytes; expected {expected}",
+                    data.len()
+                )));
+            }
+            levels.push(data);
+        }
+
+        Ok(Self {
+            format,
+            width,
+            height,
+            levels,
+        })
+    }
+}
+
+/// Bytes in one row of blocks for a level of the given pixel width.
+pub fn bytes_per_row(format: wgpu::TextureFormat, width: u32) -> u32 {
+    let (block_w, _) = format.block_dimensions();
+    let block_bytes = format.block_size(None).unwrap_or(4);
+    width.max(1).div_ceil(block_w) * block_bytes
+}
+
+/// Rows of blocks for a level of the given pixel height.
+pub fn block_rows(format: wgpu::TextureFormat, height: u32) -> u32 {
+    let (_, block_h) = format.block_dimensions();
+    height.max(1).div_ceil(block_h)
+}
+
+/// The fields of the data format descriptor's first block that decide how
+/// formatless data is read.
+struct DataFormat {
+    model: u8,
+    transfer: u8,
+}
+
+fn data_format(bytes: &[u8]) -> io::Result<DataFormat> {
+    let offset = read_u32(bytes, 48) as usize;
+    let length = read_u32(bytes, 52) as usize;
+    // Total size, then the block's type and size words, then the fields.
+    let block = offset
+        .checked_add(length)
+        .and_then(|end| bytes.get(offset..end))
+        .filter(|block| block.len() >= 16)
+        .ok_or_else(|| invalid("data format descriptor lies outside the file"))?;
+    Ok(DataFormat {
+        model: block[12],
+        transfer: block[14],
+    })
+}
+
+/// The section a 64-bit offset and length at `at` in the header point to.
+fn section<'a>(bytes: &'a [u8], at: usize, name: &str) -> io::Result<&'a [u8]> {
+    let offset = read_u64(bytes, at) as usize;
+    let length = read_u64(bytes, at + 8) as usize;
+    offset
+        .checked_add(length)
+        .and_then(|end| bytes.get(offset..end))
+        .ok_or_else(|| invalid(format!("{name} lies outside the file")))
+}
+
+fn level_byte_len(format: wgpu::TextureFormat, width: u32, height: u32) -> usize {
+    bytes_per_row(format, width) as usize * block_rows(format, height) as usize
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap())
+}
+
+fn invalid(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::Compression;
+    use flate2::write::ZlibEncoder;
+
+    use super::*;
+
+    /// A KTX2 file of `levels`, stored in order after the level index.
+    fn ktx2_file(vk_format: u32, size: (u32, u32), scheme: u32, levels: &[Vec<u8>]) -> Vec<u8> {
+        let mut bytes = IDENTIFIER.to_vec();
+        for word in [vk_format, 1, size.0, size.1, 0, 0, 1] {
+            bytes.extend_from_slice(&word.to_le_bytes());
+        }
+        bytes.extend_from_slice(&(levels.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&scheme.to_le_bytes());
+        bytes.resize(HEADER_LEN, 0);
+
+        let mut offset = HEADER_LEN + levels.len() * LEVEL_INDEX_ENTRY_LEN;
+        for level in levels {
+            for word in [offset, level.len(), level.len()] {
+                bytes.extend_from_slice(&(word as u64).to_le_bytes());
+            }
+            offset += level.len();
+        }
+        for level in levels {
+            bytes.extend_from_slice(level);
+        }
+        bytes
+    }
+
+    fn parse(bytes: &[u8]) -> io::Result<Ktx2Texture> {
+        Ktx2Texture::parse(bytes, wgpu::Features::empty())
+    }
+
+    #[test]
+    fn rgba_files_keep_their_levels_and_declared_encoding() {
+        let levels = [vec![7; 4 * 2 * 2], vec![9; 4]];
+        let texture = parse(&ktx2_file(VK_FORMAT_R8G8B8A8_UNORM, (2, 2), 0, &levels)).unwrap();
+        assert_eq!(texture.format, wgpu::TextureFormat::Rgba8Unorm);
+        assert_eq!((texture.width, texture.height), (2, 2));
+        assert_eq!(texture.levels, levels);
+
+        let texture = parse(&ktx2_file(VK_FORMAT_R8G8B8A8_SRGB, (2, 2), 0, &levels)).unwrap();
+        assert_eq!(texture.format, wgpu::TextureFormat::Rgba8UnormSrgb);
+
+        let mut zlib = ZlibEncoder::new(Vec::new(), Compression::default());
+        zlib.write_all(&levels[0]).unwrap();
+        let inflated = [zlib.finish().unwrap()];
+        let texture = parse(&ktx2_file(VK_FORMAT_R8G8B8A8_SRGB, (2, 2), 3, &inflated)).unwrap();
+        assert_eq!(texture.levels, levels[..1]);
+    }
+
+    #[test]
+    fn truncated_files_are_rejected() {
+        let file = ktx2_file(VK_FORMAT_R8G8B8A8_UNORM, (2, 2), 0, &[vec![0; 16]]);
+        for length in [0, HEADER_LEN - 1, HEADER_LEN + 1, file.len() - 1] {
+            assert!(parse(&file[..length]).is_err(), "{length} bytes");
+        }
+    }
+
+    #[test]
+    fn more_levels_than_the_size_allows_are_rejected() {
+        let levels = [vec![0; 16], vec![0; 4], vec![0; 4]];
+        assert!(parse(&ktx2_file(VK_FORMAT_R8G8B8A8_UNORM, (2, 2), 0, &levels)).is_err());
+        // Enough levels to shift the width past its bits.
+        let mut file = ktx2_file(VK_FORMAT_BC7_SRGB_BLOCK, (4, 4), 0, &[vec![0; 16]]);
+        file[40..44].copy_from_slice(&40u32.to_le_bytes());
+        assert!(parse(&file).is_err());
+    }
+}
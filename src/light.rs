@@ -0,0 +1,38 @@
+use glam::IVec3;
+
+use crate::block::{BlockDefinition, BlockKind};
+use crate::world::World;
+
+/// Light level of open sky and of the brightest emitter.
+pub const MAX_LIGHT: u8 = 15;
+
+/// Brightness lost per light level below `MAX_LIGHT`.
+const LEVEL_FALLOFF: f32 = 0.8;
+
+/// Light level (0..=`MAX_LIGHT`) of the air cell at `cell`: the brighter of
+/// the sky light and the block light the world has propagated to it.
+pub fn light_level(world: &World, cell: IVec3) -> u8 {
+    world.sky_light(cell).max(world.block_light(cell))
+}
+
+/// Light level a block of `definition` gives off, from its material
+/// luminance.
+pub fn emission(definition: &BlockDefinition) -> u8 {
+    (definition.luminance * MAX_LIGHT as f32)
+        .round()
+        .clamp(0.0, MAX_LIGHT as f32) as u8
+}
+
+/// Whether a block stops light passing through its cell.
+pub fn blocks_light(kind: BlockKind) -> bool {
+    kind.is_full_cube() && kind.definition().transmission == 0.0
+}
+
+/// Brightness factor (0..=1) of a light level; each level is a fixed share
+/// dimmer than the one above, and level 0 is dark.
+pub fn brightness(level: u8) -> f32 {
+    if level == 0 {
+        return 0.0;
+    }
+    LEVEL_FALLOFF.powi(i32::from(MAX_LIGHT.saturating_sub(level)))
+}
@@ -0,0 +1,254 @@
+use std::fmt::Write;
+use std::io;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, mpsc};
+use std::thread;
+
+use crate::coords::ChunkPos;
+use crate::save::WorldSave;
+use crate::terrain::TerrainGenerator;
+use crate::texture::{AtlasRegistry, DecodedAtlas};
+use crate::world::{RegionStorage, World, chunk_coords_loaded_around};
+
+const PROGRESS_BAR_WIDTH: usize = 24;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LoadStage {
+    Assets,
+    World,
+    Pipelines,
+}
+
+impl LoadStage {
+    const ALL: [LoadStage; 3] = [LoadStage::Assets, LoadStage::World, LoadStage::Pipelines];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            LoadStage::Assets => "Decoding textures",
+            LoadStage::World => "Generating terrain",
+            LoadStage::Pipelines => "Building GPU pipelines",
+        }
+    }
+}
+
+/// Everything the worker thread produces; GPU uploads happen on the main thread.
+pub struct StartupData {
+    pub atlases: Vec<DecodedAtlas>,
+    pub world: World,
+}
+
+#[derive(Default)]
+struct Progress {
+    stage: AtomicUsize,
+    done: AtomicUsize,
+    total: AtomicUsize,
+}
+
+impl Progress {
+    fn begin(&self, stage: LoadStage, total: usize) {
+        self.done.store(0, Ordering::Relaxed);
+        self.total.store(total, Ordering::Relaxed);
+        self.stage.store(stage as usize, Ordering::Release);
+    }
+}
+
+/// Decodes atlases and generates the spawn area on a background thread while
+/// the main thread keeps presenting the loading screen.
+pub struct StartupLoader {
+    progress: Arc<Progress>,
+    receiver: mpsc::Receiver<io::Result<StartupData>>,
+}
+
+impl StartupLoader {
+    pub fn spawn(
+        features: wgpu::Features,
+        atlas_dir: PathBuf,
+        terrain: TerrainGenerator,
+        center: ChunkPos,
+        radius: i32,
+        vertical_radius: i32,
+    ) -> Self {
+        let progress = Arc::new(Progress::default());
+        let (sender, receiver) = mpsc::channel();
+        let worker_progress = Arc::clone(&progress);
+        thread::Builder::new()
+            .name("startup-loader".into())
+            .spawn(move || {
+                let result = load(
+                    &worker_progress,
+                    features,
+                    atlas_dir,
+                    terrain,
+                    center,
+                    radius,
+                    vertical_radius,
+                );
+                let _ = sender.send(result);
+            })
+            .expect("Failed to spawn startup loader thread");
+
+        Self { progress, receiver }
+    }
+
+    /// Returns the loaded data once the worker has finished, without blocking.
+    pub fn poll(&self) -> Option<io::Result<StartupData>> {
+        match self.receiver.try_recv() {
+            Ok(result) => Some(result),
+            Err(mpsc::TryRecvError::Empty) => None,
+            Err(mpsc::TryRecvError::Disconnected) => Some(Err(worker_died())),
+        }
+    }
+
+    pub fn wait(&self) -> io::Result<StartupData> {
+        self.receiver.recv().unwrap_or_else(|_| Err(worker_died()))
+    }
+
+    /// Marks the final, main-thread stage so the next frame reports it.
+    pub fn begin_pipelines(&self) {
+        self.progress.begin(LoadStage::Pipelines, 1);
+    }
+
+    pub fn formatted_status(&self) -> String {
+        let stage = LoadStage::ALL[self.progress.stage.load(Ordering::Acquire)];
+        let done = self.progress.done.load(Ordering::Relaxed);
+        let total = self.progress.total.load(Ordering::Relaxed).max(1);
+        let stage_fraction = (done as f32 / total as f32).min(1.0);
+        let overall = (stage as usize as f32 + stage_fraction) / LoadStage::ALL.len() as f32;
+        let filled = (overall * PROGRESS_BAR_WIDTH as f32).round() as usize;
+
+        let mut status = String::new();
+        let _ = writeln!(&mut status, "Loading Rustcraft");
+        let _ = writeln!(&mut status);
+        let _ = writeln!(
+            &mut status,
+            "[{}{}] {:>3.0}%",
+            "#".repeat(filled),
+            "-".repeat(PROGRESS_BAR_WIDTH - filled),
+            overall * 100.0
+        );
+        for (index, step) in LoadStage::ALL.iter().enumerate() {
+            let marker = match index.cmp(&(stage as usize)) {
+                std::cmp::Ordering::Less => "done",
+                std::cmp::Ordering::Equal => "....",
+                std::cmp::Ordering::Greater => "    ",
+            };
+            let _ = write!(&mut status, "[{}] {}", marker, step.label());
+            if *step == stage && total > 1 {
+                let _ = write!(&mut status, " ({}/{})", done, total);
+            }
+            status.push('\n');
+        }
+        status
+    }
+}
+
+/// Generates a region of chunks on a background thread, e.g. around a
+/// teleport destination, so the main thread never stalls on terrain.
+pub struct RegionLoader {
+    progress: Arc<Progress>,
+    receiver: mpsc::Receiver<World>,
+}
+
+impl RegionLoader {
+    /// Chunks saved in `storage` load from it; pass the world's own so they
+    /// share its cache.
+    pub fn spawn(
+        terrain: TerrainGenerator,
+        storage: RegionStorage,
+        center: ChunkPos,
+        radius: i32,
+        vertical_radius: i32,
+    ) -> Self {
+        let progress = Arc::new(Progress::default());
+        let (sender, receiver) = mpsc::channel();
+        let worker_progress = Arc::clone(&progress);
+        thread::Builder::new()
+            .name("region-loader".into())
+            .spawn(move || {
+                let region = generate_region(
+                    &worker_progress,
+                    terrain,
+                    storage,
+                    center,
+                    radius,
+                    vertical_radius,
+                );
+                // The receiver is gone if the teleport was superseded.
+                let _ = sender.send(region);
+            })
+            .expect("Failed to spawn region loader thread");
+
+        Self { progress, receiver }
+    }
+
+    /// Returns the generated region once the worker has finished, without
+    /// blocking.
+    pub fn poll(&self) -> Option<io::Result<World>> {
+        match self.receiver.try_recv() {
+            Ok(region) => Some(Ok(region)),
+            Err(mpsc::TryRecvError::Empty) => None,
+            Err(mpsc::TryRecvError::Disconnected) => Some(Err(io::Error::other(
+                "region loader thread exited without a result",
+            ))),
+        }
+    }
+
+    /// Fraction of the region generated so far, in `0.0..=1.0`.
+    pub fn fraction(&self) -> f32 {
+        let done = self.progress.done.load(Ordering::Relaxed);
+        let total = self.progress.total.load(Ordering::Relaxed).max(1);
+        (done as f32 / total as f32).min(1.0)
+    }
+
+    pub fn formatted_status(&self) -> String {
+        let filled = (self.fraction() * PROGRESS_BAR_WIDTH as f32).round() as usize;
+        format!(
+            "Loading destination [{}{}]",
+            "#".repeat(filled),
+            "-".repeat(PROGRESS_BAR_WIDTH - filled)
+        )
+    }
+}
+
+fn load(
+    progress: &Progress,
+    features: wgpu::Features,
+    atlas_dir: PathBuf,
+    terrain: TerrainGenerator,
+    center: ChunkPos,
+    radius: i32,
+    vertical_radius: i32,
+) -> io::Result<StartupData> {
+    progress.begin(LoadStage::Assets, 1);
+    let atlases = AtlasRegistry::decode(features, atlas_dir)?;
+    progress.done.store(1, Ordering::Relaxed);
+
+    let storage = WorldSave::new(&terrain.world_name()).region_storage();
+    let world = generate_region(progress, terrain, storage, center, radius, vertical_radius);
+
+    Ok(StartupData { atlases, world })
+}
+
+fn generate_region(
+    progress: &Progress,
+    terrain: TerrainGenerator,
+    storage: RegionStorage,
+    center: ChunkPos,
+    radius: i32,
+    vertical_radius: i32,
+) -> World {
+    let coords: Vec<ChunkPos> =
+        chunk_coords_loaded_around(terrain, center, radius, vertical_radius).collect();
+    progress.begin(LoadStage::World, coords.len());
+    let mut world = World::new(terrain, storage);
+    for coord in coords {
+        world.ensure_chunk(coord);
+        progress.done.fetch_add(1, Ordering::Relaxed);
+    }
+    world
+}
+
+fn worker_died() -> io::Error {
+    io::Error::other("startup loader thread exited without a result")
+}
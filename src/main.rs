@@ -1,18 +1,66 @@
+#[cfg(all(feature = "alsa", target_os = "linux"))]
+mod alsa;
 mod app;
+mod audio;
+mod basis;
+mod biome;
 mod block;
+mod block_files;
 mod camera;
+mod combat;
+mod commands;
 mod config;
+mod console;
+mod controls_card;
+mod coords;
+mod damage_numbers;
+mod death;
+mod debug_window;
+mod decoration;
+mod determinism;
+#[cfg(feature = "dev-ui")]
+mod dev_ui;
+mod dimension;
+mod entity;
+mod events;
 mod fps;
-mod hotbar;
+mod free_camera;
+mod gpu_trace;
+mod gui;
+mod health;
 mod input;
+mod inventory;
+mod inventory_screen;
+mod item;
+mod ktx2;
+mod light;
+mod loading;
+mod material_editor;
+mod mining;
+mod mob;
+mod pathfinding;
+mod pause_menu;
 mod physics;
+mod piston;
+mod portal;
+mod rand;
 mod raycast;
 mod render;
+mod save;
+mod screenshot;
+mod sky;
+mod subtitles;
+#[cfg(test)]
+mod temp_dir;
+mod terrain;
 mod text;
 mod texture;
+mod ticking;
+mod toasts;
+mod ui;
 mod world;
 
 fn main() {
-    env_logger::init();
+    debug_window::init_logging();
     pollster::block_on(app::run());
 }
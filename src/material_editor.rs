@@ -0,0 +1,347 @@
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+use winit::event::VirtualKeyCode;
+
+use crate::block::{BlockDefinition, BlockKind};
+use crate::block_files::BlockFiles;
+use crate::texture::TextureAtlas;
+
+const SLIDER_WIDTH: usize = 12;
+
+/// Developer overlay for live-tweaking block materials.
+///
+/// Holds a runtime copy of every block definition; edits are flagged dirty so
+/// the active renderer can re-upload its block metadata on the next frame.
+/// Block files in `assets/blocks/` are layered on top as they change on disk,
+/// and saving writes the edited blocks back into them.
+pub struct MaterialEditor {
+    visible: bool,
+    definitions: Vec<BlockDefinition>,
+    block_files: BlockFiles,
+    /// Indices of blocks with edits not yet saved to their block files.
+    edited: BTreeSet<usize>,
+    selected_block: usize,
+    selected_field: usize,
+    dirty: bool,
+    /// Set when block files were written, until taken.
+    saved: bool,
+}
+
+impl MaterialEditor {
+    pub fn load() -> Self {
+        Self {
+            visible: false,
+            definitions: BlockKind::ALL
+                .iter()
+                .map(|kind| *kind.definition())
+                .collect(),
+            block_files: BlockFiles::new(),
+            edited: BTreeSet::new(),
+            selected_block: 1,
+            selected_field: 0,
+            dirty: false,
+            saved: false,
+        }
+    }
+
+    /// Applies the block files changed on disk since the last call, each
+    /// over its block's built-in values, and flags the definitions dirty
+    /// when any did. Tile names are looked up in `atlas`. Unsaved edits to
+    /// those blocks are replaced.
+    pub fn poll_block_files(&mut self, atlas: &TextureAtlas) {
+        for change in self.block_files.poll() {
+            let index = change.kind.id() as usize;
+            let mut definition = *change.kind.definition();
+            match &change.file {
+                Some(file) => {
+                    file.apply(&mut definition, atlas);
+                    log::info!("Loaded block file {}", change.path.display());
+                }
+                None => log::info!(
+                    "Block file {} removed; {} is back to its built-in values",
+                    change.path.display(),
+                    change.kind.display_name()
+                ),
+            }
+            self.definitions[index] = definition;
+            self.edited.remove(&index);
+            self.dirty = true;
+        }
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn definitions(&self) -> &[BlockDefinition] {
+        &self.definitions
+    }
+
+    /// Returns true once after any material value changed.
+    pub fn take_dirty(&mut self) -> bool {
+        std::mem::take(&mut self.dirty)
+    }
+
+    /// Returns true once after edits were written to block files.
+    pub fn take_saved(&mut self) -> bool {
+        std::mem::take(&mut self.saved)
+    }
+
+    /// Handles a key press. Returns true when the key was consumed by the editor.
+    pub fn handle_key(&mut self, key: VirtualKeyCode) -> bool {
+        if key == VirtualKeyCode::F6 {
+            self.visible = !self.visible;
+            return true;
+        }
+        if !self.visible {
+            return false;
+        }
+
+        match key {
+            VirtualKeyCode::PageUp => self.cycle_block(-1),
+            VirtualKeyCode::PageDown => self.cycle_block(1),
+            VirtualKeyCode::Up => self.cycle_field(-1),
+            VirtualKeyCode::Down => self.cycle_field(1),
+            VirtualKeyCode::Left => self.adjust(-1.0),
+            VirtualKeyCode::Right => self.adjust(1.0),
+            VirtualKeyCode::F7 => self.save(),
+            _ => return false,
+        }
+        true
+    }
+
+    /// The block being edited; never air.
+    pub fn selected_block(&self) -> BlockKind {
+        BlockKind::ALL[self.selected_block]
+    }
+
+    #[cfg(feature = "dev-ui")]
+    pub fn select_block(&mut self, kind: BlockKind) {
+        if let Some(index) = BlockKind::ALL.iter().position(|&other| other == kind)
+            && kind != BlockKind::Air
+        {
+            self.selected_block = index;
+        }
+    }
+
+    pub fn value(&self, field: MaterialField) -> f32 {
+        field.get(&self.definitions[self.selected_block])
+    }
+
+    /// Sets `field` of the selected block, clamped to the field's range.
+    pub fn set_value(&mut self, field: MaterialField, value: f32) {
+        let (min, max) = field.range();
+        let value = value.clamp(min, max);
+        let definition = &mut self.definitions[self.selected_block];
+        if field.get(definition) != value {
+            field.set(definition, value);
+            self.edited.insert(self.selected_block);
+            self.dirty = true;
+        }
+    }
+
+    /// Writes each edited block's material values into its block file,
+    /// logging the outcome. Blocks that fail to save stay edited.
+    pub fn save(&mut self) {
+        if self.edited.is_empty() {
+            log::info!("No material edits to save");
+            return;
+        }
+        for index in std::mem::take(&mut self.edited) {
+            let kind = BlockKind::ALL[index];
+            let path = self.block_files.path(kind);
+            let materials = MaterialValues::from_definition(&self.definitions[index]);
+            match self.block_files.store_materials(kind, &materials) {
+                Ok(()) => {
+                    log::info!("Saved materials to {}", path.display());
+                    self.saved = true;
+                }
+                Err(err) => {
+                    warn!("Failed to save block file {}: {}", path.display(), err);
+                    self.edited.insert(index);
+                }
+            }
+        }
+    }
+
+    pub fn formatted_panel(&self) -> String {
+        let kind = self.selected_block();
+        let definition = &self.definitions[self.selected_block];
+        let mut panel = String::new();
+        let _ = writeln!(&mut panel, "Material Editor (F6 close, F7 save)");
+        let _ = writeln!(&mut panel, "Block: < {} > (PgUp/PgDn)", kind.display_name());
+        for (index, field) in MaterialField::ALL.iter().enumerate() {
+            let value = field.get(definition);
+            let (min, max) = field.range();
+            let filled = (((value - min) / (max - min)).clamp(0.0, 1.0) * SLIDER_WIDTH as f32)
+                .round() as usize;
+            let marker = if index == self.selected_field {
+                '>'
+            } else {
+                ' '
+            };
+            let _ = writeln!(
+                &mut panel,
+                "{}{:<13}[{}{}] {:>6.3}",
+                marker,
+                field.label(),
+                "#".repeat(filled),
+                "-".repeat(SLIDER_WIDTH - filled),
+                value
+            );
+        }
+        panel
+    }
+
+    fn cycle_block(&mut self, offset: isize) {
+        // Skip air: it has no visible surface to tweak.
+        let editable = self.definitions.len() as isize - 1;
+        let index = self.selected_block as isize - 1 + offset;
+        self.selected_block = (index.rem_euclid(editable) + 1) as usize;
+    }
+
+    fn cycle_field(&mut self, offset: isize) {
+        let len = MaterialField::ALL.len() as isize;
+        let index = self.selected_field as isize + offset;
+        self.selected_field = index.rem_euclid(len) as usize;
+    }
+
+    fn adjust(&mut self, direction: f32) {
+        let field = MaterialField::ALL[self.selected_field];
+        self.set_value(field, self.value(field) + direction * field.step());
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum MaterialField {
+    Luminance,
+    Specular,
+    Diffuse,
+    Roughness,
+    Metallic,
+    Transmission,
+    Ior,
+    TransmissionTint,
+}
+
+impl MaterialField {
+    pub const ALL: [MaterialField; 8] = [
+        MaterialField::Luminance,
+        MaterialField::Specular,
+        MaterialField::Diffuse,
+        MaterialField::Roughness,
+        MaterialField::Metallic,
+        MaterialField::Transmission,
+        MaterialField::Ior,
+        MaterialField::TransmissionTint,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            MaterialField::Luminance => "Luminance",
+            MaterialField::Specular => "Specular",
+            MaterialField::Diffuse => "Diffuse",
+            MaterialField::Roughness => "Roughness",
+            MaterialField::Metallic => "Metallic",
+            MaterialField::Transmission => "Transmission",
+            MaterialField::Ior => "IOR",
+            MaterialField::TransmissionTint => "Tint",
+        }
+    }
+
+    pub fn range(self) -> (f32, f32) {
+        match self {
+            MaterialField::Luminance => (0.0, 16.0),
+            MaterialField::Ior => (1.0, 3.0),
+            _ => (0.0, 1.0),
+        }
+    }
+
+    fn step(self) -> f32 {
+        match self {
+            MaterialField::Luminance => 0.5,
+            _ => 0.05,
+        }
+    }
+
+    fn get(self, definition: &BlockDefinition) -> f32 {
+        match self {
+            MaterialField::Luminance => definition.luminance,
+            MaterialField::Specular => definition.specular,
+            MaterialField::Diffuse => definition.diffuse,
+            MaterialField::Roughness => definition.roughness,
+            MaterialField::Metallic => definition.metallic,
+            MaterialField::Transmission => definition.transmission,
+            MaterialField::Ior => definition.ior,
+            MaterialField::TransmissionTint => definition.transmission_tint,
+        }
+    }
+
+    fn set(self, definition: &mut BlockDefinition, value: f32) {
+        match self {
+            MaterialField::Luminance => definition.luminance = value,
+            MaterialField::Specular => definition.specular = value,
+            MaterialField::Diffuse => definition.diffuse = value,
+            MaterialField::Roughness => definition.roughness = value,
+            MaterialField::Metallic => definition.metallic = value,
+            MaterialField::Transmission => definition.transmission = value,
+            MaterialField::Ior => definition.ior = value,
+            MaterialField::TransmissionTint => definition.transmission_tint = value,
+        }
+    }
+}
+
+#[derive(Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MaterialValues {
+    luminance: Option<f32>,
+    specular: Option<f32>,
+    diffuse: Option<f32>,
+    roughness: Option<f32>,
+    metallic: Option<f32>,
+    transmission: Option<f32>,
+    ior: Option<f32>,
+    transmission_tint: Option<f32>,
+}
+
+impl MaterialValues {
+    pub fn from_definition(definition: &BlockDefinition) -> Self {
+        Self {
+            luminance: Some(definition.luminance),
+            specular: Some(definition.specular),
+            diffuse: Some(definition.diffuse),
+            roughness: Some(definition.roughness),
+            metallic: Some(definition.metallic),
+            transmission: Some(definition.transmission),
+            ior: Some(definition.ior),
+            transmission_tint: Some(definition.transmission_tint),
+        }
+    }
+
+    pub fn apply(&self, definition: &mut BlockDefinition) {
+        let values = [
+            (MaterialField::Luminance, self.luminance),
+            (MaterialField::Specular, self.specular),
+            (MaterialField::Diffuse, self.diffuse),
+            (MaterialField::Roughness, self.roughness),
+            (MaterialField::Metallic, self.metallic),
+            (MaterialField::Transmission, self.transmission),
+            (MaterialField::Ior, self.ior),
+            (MaterialField::TransmissionTint, self.transmission_tint),
+        ];
+        for (field, value) in values {
+            let Some(value) = value else {
+                continue;
+            };
+            if !value.is_finite() {
+                warn!("Invalid {} {}; ignoring", field.label(), value);
+                continue;
+            }
+            let (min, max) = field.range();
+            field.set(definition, value.clamp(min, max));
+        }
+    }
+}
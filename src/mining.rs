@@ -0,0 +1,111 @@
+use glam::IVec3;
+
+use crate::block::BlockKind;
+use crate::item::{ToolTier, ToolType};
+
+/// Break time multiplier while the held tool cannot harvest the block.
+const UNHARVESTABLE_PENALTY: f32 = 3.0;
+/// Seconds after a block breaks before the next one starts, so holding the
+/// button does not mow through instantly broken blocks every frame.
+const BREAK_COOLDOWN: f32 = 0.2;
+
+/// The tool in hand, or `None` for an empty hand or a non-tool item.
+pub type HeldTool = Option<(ToolType, ToolTier)>;
+
+/// Whether breaking `kind` with `tool` drops it.
+pub fn can_harvest(kind: BlockKind, tool: HeldTool) -> bool {
+    let definition = kind.definition();
+    match (definition.harvest_tier, tool) {
+        (None, _) => true,
+        (Some(required), Some((tool_type, tier))) => {
+            definition.tool == Some(tool_type) && tier >= required
+        }
+        (Some(_), None) => false,
+    }
+}
+
+/// Seconds it takes to break `kind` with `tool`, or `None` if it cannot be
+/// broken at all.
+pub fn break_time(kind: BlockKind, tool: HeldTool) -> Option<f32> {
+    let definition = kind.definition();
+    if !definition.breakable {
+        return None;
+    }
+    let speed = match tool {
+        Some((tool_type, tier)) if definition.tool == Some(tool_type) => tier.speed(),
+        _ => 1.0,
+    };
+    let penalty = if can_harvest(kind, tool) {
+        1.0
+    } else {
+        UNHARVESTABLE_PENALTY
+    };
+    Some(definition.hardness * penalty / speed)
+}
+
+/// Hold-to-break progress on the block under the crosshair. Looking away or
+/// letting go of the button starts over.
+pub struct BlockBreaker {
+    held: bool,
+    target: Option<IVec3>,
+    progress: f32,
+    cooldown: f32,
+}
+
+impl BlockBreaker {
+    pub fn new() -> Self {
+        Self {
+            held: false,
+            target: None,
+            progress: 0.0,
+            cooldown: 0.0,
+        }
+    }
+
+    pub fn press(&mut self) {
+        self.held = true;
+    }
+
+    pub fn release(&mut self) {
+        self.held = false;
+        self.target = None;
+        self.progress = 0.0;
+    }
+
+    pub fn is_held(&self) -> bool {
+        self.held
+    }
+
+    /// Advances by `dt` seconds against `target`, the looked-at block and the
+    /// time it takes to break. Returns the block once it breaks.
+    pub fn update(&mut self, target: Option<(IVec3, f32)>, dt: f32) -> Option<IVec3> {
+        self.cooldown = (self.cooldown - dt).max(0.0);
+        let Some((position, time)) = target.filter(|_| self.held) else {
+            self.target = None;
+            self.progress = 0.0;
+            return None;
+        };
+        if self.target != Some(position) {
+            self.target = Some(position);
+            self.progress = 0.0;
+        }
+        if self.cooldown > 0.0 {
+            return None;
+        }
+        self.progress += if time > 0.0 { dt / time } else { 1.0 };
+        if self.progress < 1.0 {
+            return None;
+        }
+        self.target = None;
+        self.progress = 0.0;
+        self.cooldown = BREAK_COOLDOWN;
+        Some(position)
+    }
+
+    /// Fraction of the current block broken so far, while one is underway.
+    pub fn progress(&self) -> Option<f32> {
+        self.target
+            .map(|_| self.progress)
+            .filter(|&progress| progress > 0.0)
+    }
+}
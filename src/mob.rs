@@ -0,0 +1,213 @@
+use std::f32::consts::TAU;
+
+use glam::{IVec3, Vec3};
+
+use crate::entity::{Entities, Entity, EntityKind};
+use crate::light::light_level;
+use crate::pathfinding::{find_path, is_walkable};
+use crate::physics::Body;
+use crate::rand::Rng;
+use crate::world::World;
+
+/// Seconds between spawn attempts.
+const SPAWN_INTERVAL: f32 = 1.0;
+/// Random columns tried per spawn attempt.
+const SPAWN_COLUMNS: usize = 8;
+/// Horizontal spawn distance from the player: out of sight of the immediate
+/// surroundings, but close enough to find them.
+const SPAWN_MIN_DISTANCE: f32 = 12.0;
+const SPAWN_MAX_DISTANCE: f32 = 32.0;
+/// Blocks above and below the player's feet searched for a spawn cell.
+const SPAWN_HEIGHT_RANGE: i32 = 12;
+/// Brightest light level lurkers spawn in.
+const MAX_SPAWN_LIGHT: u8 = 4;
+const MAX_LURKERS: usize = 6;
+/// Mobs this far from the player despawn at once.
+const DESPAWN_DISTANCE: f32 = 64.0;
+/// Mobs past this distance despawn at random, after this many seconds on
+/// average, so the dark fills with fresh ones near the player.
+const IDLE_DISTANCE: f32 = 32.0;
+const IDLE_DESPAWN_SECONDS: f32 = 30.0;
+
+/// Lurkers notice the player from this far away.
+const PURSUIT_RANGE: f32 = 32.0;
+/// Seconds between route searches while chasing.
+const REPATH_INTERVAL: f32 = 0.5;
+/// Cells a route search may expand before settling for a partial route.
+const MAX_PATH_NODES: usize = 512;
+/// How close a lurker heads straight for the player instead of its route.
+const CLOSE_RANGE: f32 = 1.5;
+const LURKER_SPEED: f32 = 3.2;
+/// Horizontal distance from a waypoint's center that counts as reaching it.
+const WAYPOINT_RADIUS: f32 = 0.3;
+const CONTACT_DAMAGE: f32 = 3.0;
+/// Seconds a lurker waits between hits.
+const ATTACK_INTERVAL: f32 = 1.0;
+
+/// Chase state of a hostile mob.
+#[derive(Default)]
+pub struct Pursuit {
+    /// Remaining waypoints, next one last.
+    route: Vec<IVec3>,
+    repath_in: f32,
+    attack_cooldown: f32,
+}
+
+/// Spawns lurkers in the dark around the player, drives their chase, and
+/// despawns the ones left far behind.
+pub struct MobDirector {
+    rng: Rng,
+    spawn_in: f32,
+}
+
+impl MobDirector {
+    /// `rng` drives spawning and despawning; see `Stream::Mobs`.
+    pub fn new(rng: Rng) -> Self {
+        Self {
+            rng,
+            spawn_in: SPAWN_INTERVAL,
+        }
+    }
+
+    /// Spawns lurkers and steers every mob for the next `dt` seconds;
+    /// returns the contact damage dealt to `player`. Mobs leave a player
+    /// that is not `hunted`, such as a spectator, alone.
+    pub fn update(
+        &mut self,
+        entities: &mut Entities,
+        world: &World,
+        player: &Body,
+        hunted: bool,
+        dt: f32,
+    ) -> f32 {
+        let rng = &mut self.rng;
+        entities.retain(|entity| {
+            if entity.pursuit.is_none() {
+                return true;
+            }
+            let distance = entity.body.position.distance(player.position);
+            distance < IDLE_DISTANCE
+                || (distance < DESPAWN_DISTANCE && rng.unit() >= dt / IDLE_DESPAWN_SECONDS)
+        });
+
+        self.spawn_in -= dt;
+        if self.spawn_in <= 0.0 {
+            self.spawn_in = SPAWN_INTERVAL;
+            if entities.count(EntityKind::Lurker) < MAX_LURKERS
+                && let Some(cell) = self.find_spawn_cell(world, player.position)
+            {
+                entities.spawn(Entity::new(
+                    EntityKind::Lurker,
+                    cell.as_vec3() + Vec3::new(0.5, 0.0, 0.5),
+                ));
+            }
+        }
+
+        let mut damage = 0.0;
+        for entity in entities.iter_mut() {
+            // A mob knocked back by a hit flies until it lands.
+            let staggered = entity.health.is_invulnerable() && !entity.body.on_ground;
+            if let Some(pursuit) = &mut entity.pursuit
+                && !staggered
+            {
+                damage += pursuit.update(&mut entity.body, world, player, hunted, dt);
+            }
+        }
+        damage
+    }
+
+    /// A walkable cell dark enough for a lurker somewhere around `feet`;
+    /// the highest dark cell in each column tried wins.
+    fn find_spawn_cell(&mut self, world: &World, feet: Vec3) -> Option<IVec3> {
+        for _ in 0..SPAWN_COLUMNS {
+            let angle = self.rng.unit() * TAU;
+            let distance =
+                SPAWN_MIN_DISTANCE + self.rng.unit() * (SPAWN_MAX_DISTANCE - SPAWN_MIN_DISTANCE);
+            let column = (feet + Vec3::new(angle.cos(), 0.0, angle.sin()) * distance)
+                .floor()
+                .as_ivec3();
+            let top = column.y + SPAWN_HEIGHT_RANGE;
+            let bottom = column.y - SPAWN_HEIGHT_RANGE;
+            let found = (bottom..=top)
+                .rev()
+                .map(|y| column.with_y(y))
+                .find(|&cell| {
+                    is_walkable(world, cell) && light_level(world, cell) <= MAX_SPAWN_LIGHT
+                });
+            if found.is_some() {
+                return found;
+            }
+        }
+        None
+    }
+}
+
+impl Pursuit {
+    /// Steers `body` along a route to the player, and returns the damage
+    /// dealt if it is touching them.
+    fn update(
+        &mut self,
+        body: &mut Body,
+        world: &World,
+        player: &Body,
+        hunted: bool,
+        dt: f32,
+    ) -> f32 {
+        self.attack_cooldown = (self.attack_cooldown - dt).max(0.0);
+        let to_player = player.position - body.position;
+        let in_range = hunted && to_player.length() <= PURSUIT_RANGE;
+
+        self.repath_in -= dt;
+        if self.repath_in <= 0.0 {
+            self.repath_in = REPATH_INTERVAL;
+            self.route.clear();
+            if in_range {
+                let start = body.position.floor().as_ivec3();
+                let goal = player.position.floor().as_ivec3();
+                if let Some(mut route) = find_path(world, start, goal, MAX_PATH_NODES) {
+                    route.reverse();
+                    self.route = route;
+                }
+            }
+        }
+        while let Some(&waypoint) = self.route.last()
+            && reached(body.position, waypoint)
+        {
+            self.route.pop();
+        }
+
+        let target = if in_range && to_player.length() <= CLOSE_RANGE {
+            Some(player.position)
+        } else {
+            self.route
+                .last()
+                .map(|waypoint| waypoint.as_vec3() + Vec3::new(0.5, 0.0, 0.5))
+        };
+        let heading = target.map_or(Vec3::ZERO, |target| {
+            (target - body.position).with_y(0.0).normalize_or_zero()
+        });
+        body.velocity.x = heading.x * LURKER_SPEED;
+        body.velocity.z = heading.z * LURKER_SPEED;
+        let climbing = self
+            .route
+            .last()
+            .is_some_and(|waypoint| waypoint.y as f32 > body.position.y + 0.5);
+        if climbing && body.on_ground {
+            body.jump();
+        }
+
+        if hunted && body.touches(player) && self.attack_cooldown <= 0.0 {
+            self.attack_cooldown = ATTACK_INTERVAL;
+            CONTACT_DAMAGE
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Whether feet at `position` stand in `waypoint`'s cell, near its center.
+fn reached(position: Vec3, waypoint: IVec3) -> bool {
+    let center = waypoint.as_vec3() + Vec3::new(0.5, 0.0, 0.5);
+    (center - position).with_y(0.0).length() <= WAYPOINT_RADIUS
+        && position.y >= waypoint.y as f32 - 0.1
+}
@@ -0,0 +1,123 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use glam::IVec3;
+
+use crate::coords::HORIZONTAL_NEIGHBORS;
+use crate::ticking::block_kind_at;
+use crate::world::World;
+
+/// Cost of one level step; climbs and drops cost extra so flat routes win ties.
+const STEP_COST: u32 = 10;
+const CLIMB_COST: u32 = 15;
+const DROP_COST_PER_BLOCK: u32 = 4;
+/// Deepest ledge a walker drops off.
+const MAX_DROP: i32 = 3;
+/// Multiplier on the distance estimate; above 1 the search heads straight
+/// for the goal and settles for a slightly longer route than the shortest.
+const HEURISTIC_WEIGHT: u32 = 2;
+
+/// Whether a two-block-tall walker can stand with its feet in `cell`.
+pub fn is_walkable(world: &World, cell: IVec3) -> bool {
+    is_open(world, cell) && is_open(world, cell + IVec3::Y) && !is_open(world, cell - IVec3::Y)
+}
+
+/// Route over walkable cells from `start` towards `goal`, excluding `start`.
+///
+/// Weighted A*: at most `max_nodes` cells are expanded, and when the goal is
+/// unreachable or too far the route leads to the expanded cell closest to
+/// it instead, so a pursuer still closes in. Returns `None` when that is
+/// `start` itself.
+pub fn find_path(world: &World, start: IVec3, goal: IVec3, max_nodes: usize) -> Option<Vec<IVec3>> {
+    let mut open = BinaryHeap::new();
+    let mut came_from: HashMap<IVec3, IVec3> = HashMap::new();
+    let mut cost_so_far: HashMap<IVec3, u32> = HashMap::new();
+    let mut closest = (estimate(start, goal), start);
+    let mut expanded = 0;
+
+    open.push(Reverse((HEURISTIC_WEIGHT * closest.0, start.to_array())));
+    cost_so_far.insert(start, 0);
+
+    while let Some(Reverse((_, cell))) = open.pop() {
+        let cell = IVec3::from_array(cell);
+        if cell == goal {
+            closest = (0, cell);
+            break;
+        }
+        expanded += 1;
+        if expanded > max_nodes {
+            break;
+        }
+
+        let cost = cost_so_far[&cell];
+        for (next, step_cost) in neighbours(world, cell) {
+            let next_cost = cost + step_cost;
+            if cost_so_far
+                .get(&next)
+                .is_some_and(|&known| known <= next_cost)
+            {
+                continue;
+            }
+            cost_so_far.insert(next, next_cost);
+            came_from.insert(next, cell);
+            let remaining = estimate(next, goal);
+            if remaining < closest.0 {
+                closest = (remaining, next);
+            }
+            open.push(Reverse((
+                next_cost + HEURISTIC_WEIGHT * remaining,
+                next.to_array(),
+            )));
+        }
+    }
+
+    let mut cell = closest.1;
+    if cell == start {
+        return None;
+    }
+    let mut path = vec![cell];
+    while let Some(&previous) = came_from.get(&cell) {
+        if previous == start {
+            break;
+        }
+        path.push(previous);
+        cell = previous;
+    }
+    path.reverse();
+    Some(path)
+}
+
+/// Walkable cells one move away from `cell`, with the cost of moving there:
+/// level steps, one-block climbs, and drops of up to `MAX_DROP` blocks.
+fn neighbours(world: &World, cell: IVec3) -> impl Iterator<Item = (IVec3, u32)> + '_ {
+    let headroom = is_open(world, cell + IVec3::Y * 2);
+    HORIZONTAL_NEIGHBORS
+        .into_iter()
+        .filter_map(move |direction| {
+            let level = cell + direction;
+            if is_walkable(world, level) {
+                return Some((level, STEP_COST));
+            }
+            let raised = level + IVec3::Y;
+            if headroom && is_walkable(world, raised) {
+                return Some((raised, CLIMB_COST));
+            }
+            if !is_open(world, level) || !is_open(world, raised) {
+                return None;
+            }
+            // The first walkable cell going down is the ledge's landing.
+            (1..=MAX_DROP)
+                .map(|depth| (level - IVec3::Y * depth, depth))
+                .find(|&(lowered, _)| is_walkable(world, lowered))
+                .map(|(lowered, depth)| (lowered, STEP_COST + DROP_COST_PER_BLOCK * depth as u32))
+        })
+}
+
+fn estimate(from: IVec3, to: IVec3) -> u32 {
+    let delta = (to - from).abs();
+    (delta.x + delta.y + delta.z) as u32 * STEP_COST
+}
+
+fn is_open(world: &World, cell: IVec3) -> bool {
+    !block_kind_at(world, cell).has_collision()
+}
@@ -0,0 +1,304 @@
+use winit::event::{ElementState, VirtualKeyCode, WindowEvent};
+
+use crate::config::{FOV_RANGE, PresentModeSetting, RENDER_DISTANCE_RANGE, WaterQuality};
+use crate::gui::{Gui, GuiEvent, Slider, WidgetId};
+use crate::render;
+
+/// Something picked in the pause menu that the game acts on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PauseChoice {
+    /// Close the menu.
+    Resume,
+    /// Leave the game; the world is saved on the way out.
+    Quit,
+    /// The settings screen was left; its values are written to
+    /// `config.json`. The menu stays open.
+    SaveSettings,
+}
+
+/// Runtime settings the settings screen may change, borrowed from the game
+/// while it handles input.
+pub struct MenuSettings<'a> {
+    pub gamma: &'a mut f32,
+    pub mouse_sensitivity: &'a mut f32,
+    pub light_shafts: &'a mut bool,
+    pub shadows: &'a mut bool,
+    pub water_quality: &'a mut WaterQuality,
+    pub present_mode: &'a mut PresentModeSetting,
+    pub max_fps: &'a mut Option<f32>,
+    /// Registry name of the renderer.
+    pub renderer: &'a mut &'static str,
+    pub fov: &'a mut f32,
+    pub render_distance: &'a mut i32,
+}
+
+/// Frame caps the settings screen steps through, after none.
+const FPS_CAPS: [f32; 5] = [30.0, 60.0, 120.0, 144.0, 240.0];
+
+/// The screen the menu shows and its widgets.
+enum Screen {
+    Main {
+        resume: WidgetId,
+        settings: WidgetId,
+        quit: WidgetId,
+    },
+    Settings {
+        renderer: WidgetId,
+        fov: WidgetId,
+        render_distance: WidgetId,
+        gamma: WidgetId,
+        sensitivity: WidgetId,
+        light_shafts: WidgetId,
+        shadows: WidgetId,
+        water: WidgetId,
+        present_mode: WidgetId,
+        max_fps: WidgetId,
+        done: WidgetId,
+    },
+}
+
+/// Menu opened with Escape: back to the game, the settings screen, or quit.
+/// Escape steps back a screen, and out of the menu from the first one.
+pub struct PauseMenu {
+    screen: Screen,
+    gui: Gui,
+    /// Screen pixels per GUI pixel.
+    scale: f32,
+    /// Shown under the title of the first screen.
+    world_name: String,
+}
+
+impl PauseMenu {
+    pub fn new(scale: f32, world_name: String) -> Self {
+        let (screen, gui) = main_screen(scale, &world_name);
+        Self {
+            screen,
+            gui,
+            scale,
+            world_name,
+        }
+    }
+
+    pub fn gui(&self) -> &Gui {
+        &self.gui
+    }
+
+    pub fn layout(&mut self, viewport: [u32; 2]) {
+        self.gui.layout(viewport);
+    }
+
+    /// Handles one window event, applying setting changes as they are made.
+    /// Returns the choice that closes the menu, if one was made.
+    pub fn input(&mut self, event: &WindowEvent, settings: MenuSettings) -> Option<PauseChoice> {
+        if let WindowEvent::KeyboardInput { input, .. } = event
+            && input.state == ElementState::Pressed
+            && input.virtual_keycode == Some(VirtualKeyCode::Escape)
+        {
+            return match self.screen {
+                Screen::Main { .. } => Some(PauseChoice::Resume),
+                Screen::Settings { .. } => {
+                    self.show_main();
+                    Some(PauseChoice::SaveSettings)
+                }
+            };
+        }
+
+        let event = self.gui.input(event)?;
+        match self.screen {
+            Screen::Main {
+                resume,
+                settings: open_settings,
+                quit,
+            } => match event {
+                GuiEvent::Clicked(id) if id == resume => Some(PauseChoice::Resume),
+                GuiEvent::Clicked(id) if id == open_settings => {
+                    let (screen, gui) = settings_screen(self.scale, &settings);
+                    self.screen = screen;
+                    self.gui = gui;
+                    None
+                }
+                GuiEvent::Clicked(id) if id == quit => Some(PauseChoice::Quit),
+                _ => None,
+            },
+            Screen::Settings {
+                renderer,
+                fov,
+                render_distance,
+                gamma,
+                sensitivity,
+                light_shafts,
+                shadows,
+                water,
+                present_mode,
+                max_fps,
+                done,
+            } => {
+                match event {
+                    GuiEvent::Clicked(id) if id == renderer => {
+                        *settings.renderer = render::next_renderer(settings.renderer).name;
+                        self.gui.set_text(id, renderer_text(settings.renderer));
+                    }
+                    GuiEvent::Changed(id, value) if id == fov => *settings.fov = value,
+                    GuiEvent::Changed(id, value) if id == render_distance => {
+                        *settings.render_distance = value.round() as i32
+                    }
+                    GuiEvent::Changed(id, value) if id == gamma => *settings.gamma = value,
+                    GuiEvent::Changed(id, value) if id == sensitivity => {
+                        *settings.mouse_sensitivity = value
+                    }
+                    GuiEvent::Clicked(id) if id == light_shafts => {
+                        *settings.light_shafts = !*settings.light_shafts;
+                        self.gui
+                            .set_text(id, toggle_text("Light shafts", *settings.light_shafts));
+                    }
+                    GuiEvent::Clicked(id) if id == shadows => {
+                        *settings.shadows = !*settings.shadows;
+                        self.gui
+                            .set_text(id, toggle_text("Shadows", *settings.shadows));
+                    }
+                    GuiEvent::Clicked(id) if id == water => {
+                        *settings.water_quality = match *settings.water_quality {
+                            WaterQuality::ScreenSpace => WaterQuality::Planar,
+                            WaterQuality::Planar => WaterQuality::ScreenSpace,
+                        };
+                        self.gui.set_text(id, water_text(*settings.water_quality));
+                    }
+                    GuiEvent::Clicked(id) if id == present_mode => {
+                        *settings.present_mode = settings.present_mode.next();
+                        self.gui.set_text(id, present_text(*settings.present_mode));
+                    }
+                    GuiEvent::Clicked(id) if id == max_fps => {
+                        *settings.max_fps = next_fps_cap(*settings.max_fps);
+                        self.gui.set_text(id, max_fps_text(*settings.max_fps));
+                    }
+                    GuiEvent::Clicked(id) if id == done => {
+                        self.show_main();
+                        return Some(PauseChoice::SaveSettings);
+                    }
+                    _ => {}
+                }
+                None
+            }
+        }
+    }
+
+    fn show_main(&mut self) {
+        let (screen, gui) = main_screen(self.scale, &self.world_name);
+        self.screen = screen;
+        self.gui = gui;
+    }
+}
+
+fn main_screen(scale: f32, world_name: &str) -> (Screen, Gui) {
+    let mut gui = Gui::new("Game paused", scale);
+    gui.add_label(world_name);
+    let resume = gui.add_button("Back to game");
+    let settings = gui.add_button("Settings...");
+    let quit = gui.add_button("Save and quit");
+    gui.focus(resume);
+    let screen = Screen::Main {
+        resume,
+        settings,
+        quit,
+    };
+    (screen, gui)
+}
+
+fn settings_screen(scale: f32, settings: &MenuSettings) -> (Screen, Gui) {
+    let mut gui = Gui::new("Settings", scale);
+    let renderer = gui.add_button(renderer_text(settings.renderer));
+    let fov = gui.add_slider(Slider {
+        label: "FOV".to_string(),
+        value: *settings.fov,
+        min: *FOV_RANGE.start(),
+        max: *FOV_RANGE.end(),
+        step: 1.0,
+        decimals: 0,
+    });
+    let render_distance = gui.add_slider(Slider {
+        label: "Render distance".to_string(),
+        value: *settings.render_distance as f32,
+        min: *RENDER_DISTANCE_RANGE.start() as f32,
+        max: *RENDER_DISTANCE_RANGE.end() as f32,
+        step: 1.0,
+        decimals: 0,
+    });
+    let gamma = gui.add_slider(Slider {
+        label: "Gamma".to_string(),
+        value: *settings.gamma,
+        min: 1.0,
+        max: 3.0,
+        step: 0.05,
+        decimals: 2,
+    });
+    let sensitivity = gui.add_slider(Slider {
+        label: "Mouse sensitivity".to_string(),
+        value: *settings.mouse_sensitivity,
+        min: 0.01,
+        max: 0.2,
+        step: 0.005,
+        decimals: 3,
+    });
+    let light_shafts = gui.add_button(toggle_text("Light shafts", *settings.light_shafts));
+    let shadows = gui.add_button(toggle_text("Shadows", *settings.shadows));
+    let water = gui.add_button(water_text(*settings.water_quality));
+    let present_mode = gui.add_button(present_text(*settings.present_mode));
+    let max_fps = gui.add_button(max_fps_text(*settings.max_fps));
+    let done = gui.add_button("Done");
+    gui.focus(done);
+    let screen = Screen::Settings {
+        renderer,
+        fov,
+        render_distance,
+        gamma,
+        sensitivity,
+        light_shafts,
+        shadows,
+        water,
+        present_mode,
+        max_fps,
+        done,
+    };
+    (screen, gui)
+}
+
+fn toggle_text(label: &str, on: bool) -> String {
+    format!("{label}: {}", if on { "On" } else { "Off" })
+}
+
+fn water_text(quality: WaterQuality) -> String {
+    let level = match quality {
+        WaterQuality::ScreenSpace => "Low",
+        WaterQuality::Planar => "High",
+    };
+    format!("Water: {level}")
+}
+
+fn present_text(mode: PresentModeSetting) -> String {
+    let name = match mode {
+        PresentModeSetting::VSync => "VSync",
+        PresentModeSetting::Mailbox => "Mailbox",
+        PresentModeSetting::Immediate => "Immediate",
+    };
+    format!("Present mode: {name}")
+}
+
+fn max_fps_text(max_fps: Option<f32>) -> String {
+    match max_fps {
+        Some(fps) => format!("Max FPS: {fps:.0}"),
+        None => "Max FPS: Unlimited".to_string(),
+    }
+}
+
+/// The next cap in [`FPS_CAPS`] above `max_fps`, wrapping to no cap after
+/// the last, so a cap set from the config joins the cycle where it fits.
+fn next_fps_cap(max_fps: Option<f32>) -> Option<f32> {
+    match max_fps {
+        None => Some(FPS_CAPS[0]),
+        Some(fps) => FPS_CAPS.into_iter().find(|&cap| cap > fps),
+    }
+}
+
+fn renderer_text(name: &str) -> String {
+    format!("Renderer: {name}")
+}
@@ -1,12 +1,13 @@
+use std::f32::consts::PI;
+
 use glam::{IVec3, Vec3};
 
-use crate::block::BlockKind;
-use crate::input::MovementInput;
-use crate::world::World;
+use crate::input::MovementIntent;
+use crate::world::{BlockSample, World};
 
 const PLAYER_WIDTH: f32 = 0.6;
 const PLAYER_HALF_WIDTH: f32 = PLAYER_WIDTH * 0.5;
-const PLAYER_HEIGHT: f32 = 1.8;
+pub const PLAYER_HEIGHT: f32 = 1.8;
 pub const PLAYER_EYE_HEIGHT: f32 = 1.62;
 
 const FLY_SPEED_MULTIPLIER: f32 = 1.0;
@@ -16,11 +17,33 @@ const GRAVITY: f32 = -20.0;
 const MAX_FALL_SPEED: f32 = -54.0;
 const COLLISION_STEP: f32 = 0.25;
 const COLLISION_EPS: f32 = 1e-4;
+/// Tallest ledge (e.g. a stack of snow layers) walked onto without jumping.
+const STEP_HEIGHT: f32 = 0.5;
+/// Ground distance covered between footsteps.
+const STRIDE_LENGTH: f32 = 1.7;
+/// Peak camera lift over one stride while walking at full speed.
+const BOB_HEIGHT: f32 = 0.06;
+/// How quickly view bobbing fades in and out, per second.
+const BOB_EASE_RATE: f32 = 8.0;
+/// Walking speed in liquid, as a share of `WALK_SPEED`.
+const SWIM_SPEED_MULTIPLIER: f32 = 0.5;
+/// Share of `GRAVITY` that still pulls on a body in liquid.
+const LIQUID_GRAVITY_SCALE: f32 = 0.25;
+/// Upward acceleration of a fully submerged body; just short of the reduced
+/// gravity, so a swimmer who stops sinks slowly.
+const BUOYANCY: f32 = 4.0;
+/// Share of vertical speed liquid takes away per second.
+const LIQUID_DRAG: f32 = 3.0;
+/// Steady climbing speed while holding jump in liquid; enough to climb out
+/// onto a bank one block above the water.
+const SWIM_UP_SPEED: f32 = 3.0;
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum MovementMode {
     Fly,
     Walk,
+    /// Flies through blocks; the only mode left once a hardcore world ends.
+    Spectator,
 }
 
 impl MovementMode {
@@ -28,24 +51,37 @@ impl MovementMode {
         match self {
             MovementMode::Fly => MovementMode::Walk,
             MovementMode::Walk => MovementMode::Fly,
+            MovementMode::Spectator => MovementMode::Spectator,
         }
     }
 }
 
+/// An upright box that moves through the world and collides with its blocks.
+pub struct Body {
+    /// Bottom center of the box.
+    pub position: Vec3,
+    pub velocity: Vec3,
+    pub on_ground: bool,
+    half_width: f32,
+    height: f32,
+    /// Cells around the box, refilled by each collision test.
+    nearby: BlockSample,
+}
+
 pub struct PlayerPhysics {
-    position: Vec3,
-    velocity: Vec3,
+    body: Body,
     mode: MovementMode,
-    on_ground: bool,
+    stride: f32,
+    bob_weight: f32,
 }
 
 impl PlayerPhysics {
     pub fn new(feet_position: Vec3, mode: MovementMode) -> Self {
         Self {
-            position: feet_position,
-            velocity: Vec3::ZERO,
+            body: Body::new(feet_position, PLAYER_HALF_WIDTH, PLAYER_HEIGHT),
             mode,
-            on_ground: false,
+            stride: 0.0,
+            bob_weight: 0.0,
         }
     }
 
@@ -55,22 +91,55 @@ impl PlayerPhysics {
     }
 
     pub fn camera_position(&self) -> Vec3 {
-        self.position + Vec3::new(0.0, PLAYER_EYE_HEIGHT, 0.0)
+        self.body.position + Vec3::new(0.0, PLAYER_EYE_HEIGHT, 0.0)
+    }
+
+    /// Camera offset that rises and falls once per stride while walking.
+    pub fn view_bob(&self) -> Vec3 {
+        let phase = (self.stride / STRIDE_LENGTH).fract();
+        Vec3::Y * (BOB_HEIGHT * self.bob_weight * (phase * PI).sin())
+    }
+
+    pub fn feet_position(&self) -> Vec3 {
+        self.body.position
+    }
+
+    pub fn body(&self) -> &Body {
+        &self.body
+    }
+
+    /// Whether the walker has covered a full stride on the ground since the
+    /// last step; consumes it if so.
+    pub fn take_step(&mut self) -> bool {
+        if self.stride < STRIDE_LENGTH {
+            return false;
+        }
+        self.stride -= STRIDE_LENGTH;
+        true
     }
 
     pub fn mode(&self) -> MovementMode {
         self.mode
     }
 
+    /// Moves the camera to `camera_position` at rest, as after a respawn.
+    pub fn teleport(&mut self, camera_position: Vec3) {
+        self.body.position = camera_position - Vec3::new(0.0, PLAYER_EYE_HEIGHT, 0.0);
+        self.body.velocity = Vec3::ZERO;
+        self.body.on_ground = false;
+        self.stride = 0.0;
+        self.bob_weight = 0.0;
+    }
+
     pub fn set_mode(&mut self, mode: MovementMode) {
         if self.mode == mode {
             return;
         }
         self.mode = mode;
-        if matches!(self.mode, MovementMode::Fly) {
-            self.on_ground = false;
+        if matches!(self.mode, MovementMode::Fly | MovementMode::Spectator) {
+            self.body.on_ground = false;
         } else {
-            self.velocity.y = 0.0;
+            self.body.velocity.y = 0.0;
         }
     }
 
@@ -80,35 +149,18 @@ impl PlayerPhysics {
     }
 
     pub fn overlaps_block(&self, block: IVec3) -> bool {
-        let block_min = block.as_vec3();
-        let block_max = block_min + Vec3::ONE;
-        let player_min = Vec3::new(
-            self.position.x - PLAYER_HALF_WIDTH,
-            self.position.y,
-            self.position.z - PLAYER_HALF_WIDTH,
-        );
-        let player_max = Vec3::new(
-            self.position.x + PLAYER_HALF_WIDTH,
-            self.position.y + PLAYER_HEIGHT,
-            self.position.z + PLAYER_HALF_WIDTH,
-        );
-
-        !(player_max.x <= block_min.x
-            || player_min.x >= block_max.x
-            || player_max.y <= block_min.y
-            || player_min.y >= block_max.y
-            || player_max.z <= block_min.z
-            || player_min.z >= block_max.z)
+        self.mode != MovementMode::Spectator && self.body.overlaps_block(block)
     }
 
-    pub fn update(&mut self, world: &World, dt: f32, movement: &MovementInput) {
+    pub fn update(&mut self, world: &World, dt: f32, movement: &MovementIntent) {
         match self.mode {
-            MovementMode::Fly => self.update_fly(world, dt, movement),
+            MovementMode::Fly => self.update_fly(world, dt, movement, true),
             MovementMode::Walk => self.update_walk(world, dt, movement),
+            MovementMode::Spectator => self.update_fly(world, dt, movement, false),
         }
     }
 
-    fn update_fly(&mut self, world: &World, dt: f32, movement: &MovementInput) {
+    fn update_fly(&mut self, world: &World, dt: f32, movement: &MovementIntent, collide: bool) {
         let mut desired = movement.wish_dir;
         if movement.ascend {
             desired += Vec3::Y;
@@ -118,45 +170,147 @@ impl PlayerPhysics {
         }
 
         if desired.length_squared() > 0.0 {
-            self.velocity = desired.normalize() * (movement.speed * FLY_SPEED_MULTIPLIER);
+            self.body.velocity = desired.normalize() * (movement.speed * FLY_SPEED_MULTIPLIER);
         } else {
-            self.velocity = Vec3::ZERO;
+            self.body.velocity = Vec3::ZERO;
         }
 
-        self.apply_movement(world, dt);
+        if collide {
+            self.body.apply_movement(world, dt, false);
+        } else {
+            self.body.position += self.body.velocity * dt;
+        }
     }
 
-    fn update_walk(&mut self, world: &World, dt: f32, movement: &MovementInput) {
+    fn update_walk(&mut self, world: &World, dt: f32, movement: &MovementIntent) {
+        let submerged = self.body.submerged_fraction(world);
+        let speed = if submerged > 0.0 {
+            WALK_SPEED * SWIM_SPEED_MULTIPLIER
+        } else {
+            WALK_SPEED
+        };
         let mut desired = movement.wish_dir;
         desired.y = 0.0;
         if desired.length_squared() > 0.0 {
-            desired = desired.normalize() * WALK_SPEED;
+            desired = desired.normalize() * speed;
         }
 
-        self.velocity.x = desired.x;
-        self.velocity.z = desired.z;
+        self.body.velocity.x = desired.x;
+        self.body.velocity.z = desired.z;
 
-        if movement.jump && self.on_ground {
-            self.velocity.y = JUMP_SPEED;
-            self.on_ground = false;
+        if movement.jump && self.body.on_ground {
+            self.body.jump();
+        } else if submerged > 0.0 {
+            self.body.swim(dt, submerged, movement.jump);
         } else {
-            self.velocity.y += GRAVITY * dt;
-            if self.velocity.y < MAX_FALL_SPEED {
-                self.velocity.y = MAX_FALL_SPEED;
-            }
+            self.body.fall(dt);
         }
 
-        self.apply_movement(world, dt);
+        let start = self.body.position;
+        self.body.apply_movement(world, dt, true);
+        let travelled = (self.body.position - start).with_y(0.0).length();
+        if self.body.on_ground {
+            self.stride += travelled;
+        }
+
+        let target_bob = if self.body.on_ground && dt > 0.0 {
+            (travelled / (speed * dt)).min(1.0)
+        } else {
+            0.0
+        };
+        let ease = (BOB_EASE_RATE * dt).min(1.0);
+        self.bob_weight += (target_bob - self.bob_weight) * ease;
     }
+}
 
-    fn apply_movement(&mut self, world: &World, dt: f32) {
+impl Body {
+    pub fn new(position: Vec3, half_width: f32, height: f32) -> Self {
+        Self {
+            position,
+            velocity: Vec3::ZERO,
+            on_ground: false,
+            half_width,
+            height,
+            nearby: BlockSample::new(),
+        }
+    }
+
+    /// Lower and upper corners of the box.
+    pub fn bounds(&self) -> (Vec3, Vec3) {
+        let extent = Vec3::new(self.half_width, 0.0, self.half_width);
+        (
+            self.position - extent,
+            self.position + extent + Vec3::Y * self.height,
+        )
+    }
+
+    /// Whether the two boxes overlap.
+    pub fn touches(&self, other: &Body) -> bool {
+        let (min, max) = self.bounds();
+        let (other_min, other_max) = other.bounds();
+        min.cmplt(other_max).all() && max.cmpgt(other_min).all()
+    }
+
+    pub fn overlaps_block(&self, block: IVec3) -> bool {
+        let block_min = block.as_vec3();
+        let block_max = block_min + Vec3::ONE;
+        let (min, max) = self.bounds();
+        min.cmplt(block_max).all() && max.cmpgt(block_min).all()
+    }
+
+    /// Launches the body with `velocity`, as when knocked back by a hit.
+    pub fn knock_back(&mut self, velocity: Vec3) {
+        self.velocity = velocity;
+        self.on_ground = false;
+    }
+
+    pub fn jump(&mut self) {
+        self.velocity.y = JUMP_SPEED;
+        self.on_ground = false;
+    }
+
+    /// Accelerates downwards, up to terminal speed.
+    pub fn fall(&mut self, dt: f32) {
+        self.velocity.y = (self.velocity.y + GRAVITY * dt).max(MAX_FALL_SPEED);
+    }
+
+    /// Sinks or rises through liquid covering `submerged` (0..=1) of the box:
+    /// weakened gravity, partly offset by buoyancy, and drag on vertical
+    /// motion; `swim_up` climbs at a steady speed instead.
+    pub fn swim(&mut self, dt: f32, submerged: f32, swim_up: bool) {
+        let acceleration = GRAVITY * LIQUID_GRAVITY_SCALE + BUOYANCY * submerged;
+        self.velocity.y += acceleration * dt;
+        self.velocity.y *= (1.0 - LIQUID_DRAG * dt).max(0.0);
+        if swim_up {
+            self.velocity.y = self.velocity.y.max(SWIM_UP_SPEED);
+        }
+    }
+
+    /// Share (0..=1) of the box's height inside liquid cells, measured down
+    /// its middle.
+    pub fn submerged_fraction(&self, world: &World) -> f32 {
+        let (min, max) = self.bounds();
+        let x = self.position.x.floor() as i32;
+        let z = self.position.z.floor() as i32;
+        let mut covered = 0.0;
+        for y in min.y.floor() as i32..=(max.y - COLLISION_EPS).floor() as i32 {
+            if world.block_info(IVec3::new(x, y, z)).kind.is_liquid() {
+                covered += max.y.min(y as f32 + 1.0) - min.y.max(y as f32);
+            }
+        }
+        covered / self.height
+    }
+
+    /// Moves by `velocity * dt` one axis at a time, stopping at blocks; a
+    /// grounded body with `step_up` walks onto low ledges.
+    pub fn apply_movement(&mut self, world: &World, dt: f32, step_up: bool) {
         let dx = self.velocity.x * dt;
         let dy = self.velocity.y * dt;
         let dz = self.velocity.z * dt;
 
-        self.move_along_axis(world, Axis::X, dx);
-        let vertical_hit = self.move_along_axis(world, Axis::Y, dy);
-        self.move_along_axis(world, Axis::Z, dz);
+        self.move_along_axis(world, Axis::X, dx, step_up);
+        let vertical_hit = self.move_along_axis(world, Axis::Y, dy, step_up);
+        self.move_along_axis(world, Axis::Z, dz, step_up);
 
         if let Some(hit) = vertical_hit {
             if hit == VerticalHit::Floor {
@@ -173,7 +327,13 @@ impl PlayerPhysics {
         }
     }
 
-    fn move_along_axis(&mut self, world: &World, axis: Axis, delta: f32) -> Option<VerticalHit> {
+    fn move_along_axis(
+        &mut self,
+        world: &World,
+        axis: Axis,
+        delta: f32,
+        step_up: bool,
+    ) -> Option<VerticalHit> {
         if delta.abs() < f32::EPSILON {
             return None;
         }
@@ -186,6 +346,11 @@ impl PlayerPhysics {
             let candidate = self.position_with_axis_offset(axis, step);
 
             if self.collides(world, candidate) {
+                if axis != Axis::Y && step_up && self.try_step_up(world, candidate) {
+                    remaining -= step;
+                    continue;
+                }
+
                 // Increase precision near the collision.
                 let mut reduced = step;
                 while reduced.abs() > COLLISION_EPS {
@@ -218,6 +383,30 @@ impl PlayerPhysics {
         last_vertical_hit
     }
 
+    /// Lifts a grounded walker onto a low ledge in front of them, settling
+    /// onto its top surface.
+    fn try_step_up(&mut self, world: &World, candidate: Vec3) -> bool {
+        if !self.on_ground {
+            return false;
+        }
+        let raised = candidate + Vec3::Y * STEP_HEIGHT;
+        if self.collides(world, raised) {
+            return false;
+        }
+
+        let mut settled = raised;
+        let mut drop = STEP_HEIGHT;
+        while drop > COLLISION_EPS {
+            drop *= 0.5;
+            let lowered = settled - Vec3::Y * drop;
+            if !self.collides(world, lowered) {
+                settled = lowered;
+            }
+        }
+        self.position = settled;
+        true
+    }
+
     fn position_with_axis_offset(&self, axis: Axis, delta: f32) -> Vec3 {
         match axis {
             Axis::X => Vec3::new(self.position.x + delta, self.position.y, self.position.z),
@@ -226,36 +415,22 @@ impl PlayerPhysics {
         }
     }
 
-    fn collides(&self, world: &World, feet_position: Vec3) -> bool {
-        let min_x = feet_position.x - PLAYER_HALF_WIDTH;
-        let max_x = feet_position.x + PLAYER_HALF_WIDTH;
-        let min_y = feet_position.y;
-        let max_y = feet_position.y + PLAYER_HEIGHT;
-        let min_z = feet_position.z - PLAYER_HALF_WIDTH;
-        let max_z = feet_position.z + PLAYER_HALF_WIDTH;
-
-        let min_block_x = min_x.floor() as i32;
-        let max_block_x = (max_x - COLLISION_EPS).floor() as i32;
-        let min_block_y = min_y.floor() as i32;
-        let max_block_y = (max_y - COLLISION_EPS).floor() as i32;
-        let min_block_z = min_z.floor() as i32;
-        let max_block_z = (max_z - COLLISION_EPS).floor() as i32;
-
-        for y in min_block_y..=max_block_y {
-            for z in min_block_z..=max_block_z {
-                for x in min_block_x..=max_block_x {
-                    if BlockKind::from_id(world.block_at(x, y, z)).is_solid() {
-                        return true;
-                    }
-                }
-            }
-        }
-
-        false
+    fn collides(&mut self, world: &World, feet_position: Vec3) -> bool {
+        let extent = Vec3::new(self.half_width, 0.0, self.half_width);
+        let min = feet_position - extent;
+        let max = feet_position + extent + Vec3::Y * self.height;
+
+        let min_block = min.floor().as_ivec3();
+        let max_block = (max - COLLISION_EPS).floor().as_ivec3();
+        world.sample_box(min_block, max_block, &mut self.nearby);
+        self.nearby.iter().any(|(cell, block)| {
+            // Partial blocks only fill the bottom of their cell.
+            block.definition.has_collision() && min.y < cell.y as f32 + block.definition.height
+        })
     }
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq, Eq)]
 enum Axis {
     X,
     Y,
@@ -0,0 +1,108 @@
+use glam::IVec3;
+
+use crate::block::{BlockChange, BlockChanges, BlockKind, FaceDirection};
+use crate::coords::BlockPos;
+use crate::ticking::block_kind_at;
+use crate::world::World;
+
+/// Most blocks one piston moves; longer rows keep it from extending.
+pub const PUSH_LIMIT: usize = 12;
+/// Ticks the head and pushed blocks take to slide one cell.
+const MOVE_TICKS: u64 = 3;
+
+/// Scheduled hook for pistons: extends while any neighbour other than the
+/// one in front carries a signal, and retracts once none does.
+///
+/// Extending clears the pushed row at once and slides every block, plus the
+/// head, one cell forward; they land in the world when the motion finishes.
+pub fn update_piston(world: &World, position: IVec3, kind: BlockKind) -> BlockChanges {
+    let BlockKind::Piston(facing, extended) = kind else {
+        return Vec::new();
+    };
+    let powered = FaceDirection::ALL
+        .iter()
+        .filter(|direction| **direction != facing)
+        .any(|direction| block_kind_at(world, position + direction.normal()).signal_output() > 0);
+    match (powered, extended) {
+        (true, false) => extend(world, position, facing),
+        (false, true) => retract(world, position, facing),
+        _ => Vec::new(),
+    }
+}
+
+/// Scheduled hook for piston heads: a head without its extended piston
+/// behind it (broken, or retracted while the head was still moving) goes away.
+pub fn update_piston_head(world: &World, position: IVec3, kind: BlockKind) -> BlockChanges {
+    let BlockKind::PistonHead(facing) = kind else {
+        return Vec::new();
+    };
+    if block_kind_at(world, position - facing.normal()) == BlockKind::Piston(facing, true) {
+        return Vec::new();
+    }
+    vec![BlockChange::Set(position, BlockKind::Air)]
+}
+
+fn extend(world: &World, position: IVec3, facing: FaceDirection) -> BlockChanges {
+    let direction = facing.normal();
+    let Some((row, end)) = push_row(world, position + direction, direction) else {
+        return Vec::new();
+    };
+
+    let mut changes = vec![BlockChange::Set(position, BlockKind::Piston(facing, true))];
+    // Whatever sits past the row (plants, snow, wire) is crushed.
+    if block_kind_at(world, end) != BlockKind::Air {
+        changes.push(BlockChange::Set(end, BlockKind::Air));
+    }
+    for cell in &row {
+        changes.push(BlockChange::Set(*cell, BlockKind::Air));
+    }
+    for cell in row.iter().rev() {
+        changes.push(BlockChange::Move {
+            to: *cell + direction,
+            direction,
+            kind: block_kind_at(world, *cell),
+            ticks: MOVE_TICKS,
+        });
+    }
+    changes.push(BlockChange::Move {
+        to: position + direction,
+        direction,
+        kind: BlockKind::PistonHead(facing),
+        ticks: MOVE_TICKS,
+    });
+    changes
+}
+
+fn retract(world: &World, position: IVec3, facing: FaceDirection) -> BlockChanges {
+    let head = position + facing.normal();
+    let mut changes = vec![BlockChange::Set(position, BlockKind::Piston(facing, false))];
+    if block_kind_at(world, head) == BlockKind::PistonHead(facing) {
+        changes.push(BlockChange::Set(head, BlockKind::Air));
+    }
+    changes
+}
+
+/// Collects the full blocks in a row starting at `start`, nearest first, and
+/// the cell past them they move into. Fails if the row is longer than
+/// [`PUSH_LIMIT`], holds an immovable block, or runs into an unloaded chunk.
+fn push_row(world: &World, start: IVec3, direction: IVec3) -> Option<(Vec<IVec3>, IVec3)> {
+    let mut row = Vec::new();
+    let mut cell = start;
+    loop {
+        world.chunk(BlockPos(cell).chunk())?;
+        let kind = block_kind_at(world, cell);
+        if !kind.is_full_cube() {
+            return Some((row, cell));
+        }
+        if !is_movable(kind) || row.len() == PUSH_LIMIT {
+            return None;
+        }
+        row.push(cell);
+        cell += direction;
+    }
+}
+
+/// Extended pistons, their heads, and unbreakable blocks stay put.
+fn is_movable(kind: BlockKind) -> bool {
+    kind.is_breakable() && !matches!(kind, BlockKind::Piston(_, true) | BlockKind::PistonHead(_))
+}
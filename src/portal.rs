@@ -0,0 +1,199 @@
+//! Portals built with `/portal`, linked in pairs: the first and second built
+//! lead to each other, then the third and fourth, and so on. A portal built
+//! after an odd count waits for its partner and leads nowhere until then.
+//!
+//! Each portal is a frame of `PortalFrame` blocks around a sheet of `Portal`
+//! two wide and three tall, standing upright and facing one of the four
+//! horizontal directions. Walking into the sheet takes the player out in
+//! front of the linked portal, which may stand in another dimension.
+
+use std::io;
+
+use glam::{IVec3, Vec3};
+
+use crate::block::{BlockKind, FaceDirection};
+use crate::dimension::Dimension;
+use crate::save::{PortalRecord, WorldSave};
+
+/// Cells of the sheet across the portal and up it.
+const SHEET_WIDTH: i32 = 2;
+const SHEET_HEIGHT: i32 = 3;
+/// How far in front of the player `/portal` builds, in blocks.
+const BUILD_DISTANCE: i32 = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Portal {
+    /// Bottom cell of the sheet's first column.
+    pub base: IVec3,
+    /// Direction the portal faces, and travellers step out along; always
+    /// horizontal.
+    pub facing: FaceDirection,
+    pub dimension: Dimension,
+}
+
+impl Portal {
+    /// A portal `BUILD_DISTANCE` blocks ahead of the player standing at
+    /// `feet` in `dimension` and looking along `forward`, facing them.
+    pub fn in_front_of(feet: Vec3, forward: Vec3, dimension: Dimension) -> Self {
+        let toward = if forward.x.abs() > forward.z.abs() {
+            if forward.x > 0.0 {
+                FaceDirection::PosX
+            } else {
+                FaceDirection::NegX
+            }
+        } else if forward.z > 0.0 {
+            FaceDirection::PosZ
+        } else {
+            FaceDirection::NegZ
+        };
+        Self {
+            base: feet.floor().as_ivec3() + toward.normal() * BUILD_DISTANCE,
+            facing: toward.opposite(),
+            dimension,
+        }
+    }
+
+    /// Step across the sheet from its first column to its second.
+    fn across(&self) -> IVec3 {
+        let normal = self.facing.normal();
+        IVec3::new(-normal.z, 0, normal.x)
+    }
+
+    /// Every block of the portal: the sheet and the frame around it.
+    pub fn blocks(&self) -> impl Iterator<Item = (IVec3, BlockKind)> {
+        let (base, across) = (self.base, self.across());
+        (-1..=SHEET_HEIGHT).flat_map(move |up| {
+            (-1..=SHEET_WIDTH).map(move |side| {
+                let in_sheet = (0..SHEET_WIDTH).contains(&side) && (0..SHEET_HEIGHT).contains(&up);
+                let kind = if in_sheet {
+                    BlockKind::Portal
+                } else {
+                    BlockKind::PortalFrame
+                };
+                (base + across * side + IVec3::Y * up, kind)
+            })
+        })
+    }
+
+    /// Whether `cell` is part of the sheet.
+    pub fn sheet_contains(&self, cell: IVec3) -> bool {
+        let offset = cell - self.base;
+        let across = self.across();
+        let side = offset.dot(across);
+        offset == across * side + IVec3::Y * offset.y
+            && (0..SHEET_WIDTH).contains(&side)
+            && (0..SHEET_HEIGHT).contains(&offset.y)
+    }
+
+    /// Where a traveller arriving through the portal stands: on the sheet's
+    /// bottom row, one block out in front of it.
+    pub fn exit_feet(&self) -> Vec3 {
+        let across = self.across().as_vec3() * (SHEET_WIDTH as f32 - 1.0) * 0.5;
+        self.base.as_vec3() + Vec3::new(0.5, 0.0, 0.5) + across + self.facing.normal().as_vec3()
+    }
+
+    fn to_record(self) -> PortalRecord {
+        PortalRecord {
+            base: self.base.to_array(),
+            facing: self.facing as u8,
+            dimension: self.dimension,
+        }
+    }
+
+    /// The saved portal, or `None` if it faces up or down or nowhere.
+    fn from_record(record: &PortalRecord) -> Option<Self> {
+        let facing = *FaceDirection::ALL.get(usize::from(record.facing))?;
+        (facing.normal().y == 0).then_some(Self {
+            base: IVec3::from_array(record.base),
+            facing,
+            dimension: record.dimension,
+        })
+    }
+}
+
+/// Every portal of a world, in the order they were built.
+pub struct Portals {
+    portals: Vec<Portal>,
+}
+
+impl Portals {
+    pub fn new(portals: Vec<Portal>) -> Self {
+        Self { portals }
+    }
+
+    /// The portals saved for the world, skipping any that cannot be read.
+    pub fn load(save: &WorldSave) -> Self {
+        Self::new(
+            save.load_portals()
+                .iter()
+                .filter_map(Portal::from_record)
+                .collect(),
+        )
+    }
+
+    pub fn store(&self, save: &WorldSave) -> io::Result<()> {
+        let records: Vec<_> = self
+            .portals
+            .iter()
+            .map(|portal| portal.to_record())
+            .collect();
+        save.store_portals(&records)
+    }
+
+    /// Adds a newly built portal; `true` when it completes a pair.
+    pub fn add(&mut self, portal: Portal) -> bool {
+        self.portals.push(portal);
+        self.portals.len().is_multiple_of(2)
+    }
+
+    /// The portal linked to the one in `dimension` whose sheet holds
+    /// `cell`, if any.
+    pub fn destination(&self, dimension: Dimension, cell: IVec3) -> Option<Portal> {
+        let index = self
+            .portals
+            .iter()
+            .position(|portal| portal.dimension == dimension && portal.sheet_contains(cell))?;
+        self.portals.get(index ^ 1).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn portals_link_in_pairs_and_exit_in_front() {
+        let first =
+            Portal::in_front_of(Vec3::new(0.5, 10.0, 0.5), Vec3::NEG_Z, Dimension::Overworld);
+        assert_eq!(first.base, IVec3::new(0, 10, -2));
+        assert_eq!(first.facing, FaceDirection::PosZ);
+        assert_eq!(first.blocks().count(), 20);
+        let sheet: Vec<_> = first
+            .blocks()
+            .filter(|&(_, kind)| kind == BlockKind::Portal)
+            .map(|(cell, _)| cell)
+            .collect();
+        assert_eq!(sheet.len(), 6);
+        assert!(sheet.iter().all(|&cell| first.sheet_contains(cell)));
+        assert!(!first.sheet_contains(IVec3::new(0, 10, -1)));
+
+        let second = Portal::in_front_of(Vec3::new(100.5, 20.0, 0.5), Vec3::X, Dimension::Void);
+        let mut portals = Portals::new(Vec::new());
+        assert!(!portals.add(first));
+        assert_eq!(portals.destination(Dimension::Overworld, sheet[0]), None);
+        assert!(portals.add(second));
+        assert_eq!(
+            portals.destination(Dimension::Overworld, sheet[0]),
+            Some(second)
+        );
+        assert_eq!(
+            portals.destination(Dimension::Void, second.base),
+            Some(first)
+        );
+        assert_eq!(portals.destination(Dimension::Overworld, second.base), None);
+
+        let exit = second.exit_feet().floor().as_ivec3();
+        assert_eq!(exit, second.base + IVec3::NEG_X);
+        assert!(!second.sheet_contains(exit));
+    }
+}
@@ -1,11 +1,13 @@
 use glam::{IVec3, Vec3};
 
-use crate::block::{BlockKind, FaceDirection};
+use crate::block::FaceDirection;
 use crate::world::World;
 
 pub struct RaycastHit {
     pub block: IVec3,
     pub face: FaceDirection,
+    /// Distance along the ray to where it enters the block's cell.
+    pub distance: f32,
 }
 
 impl RaycastHit {
@@ -45,10 +47,23 @@ pub fn pick_block(
 
     while traveled <= max_distance && steps < max_steps {
         if let Some(face) = last_face {
-            if BlockKind::from_id(world.block_at(current.x, current.y, current.z)).is_solid() {
+            let kind = world.block_info(current).kind;
+            if kind.is_full_cube() {
                 return Some(RaycastHit {
                     block: current,
                     face,
+                    distance: traveled,
+                });
+            }
+            // Liquids are aimed through, so blocks can be placed in them.
+            if kind.is_solid()
+                && !kind.is_liquid()
+                && let Some(face) = partial_block_face(origin, dir, current, kind.height())
+            {
+                return Some(RaycastHit {
+                    block: current,
+                    face,
+                    distance: traveled,
                 });
             }
         }
@@ -112,6 +127,65 @@ pub fn pick_block(
     None
 }
 
+/// Distance along the unit-length `dir` at which a ray from `origin` enters
+/// the box `min..max`; 0.0 if it starts inside.
+pub fn ray_box_distance(origin: Vec3, dir: Vec3, min: Vec3, max: Vec3) -> Option<f32> {
+    let mut t_enter = 0.0_f32;
+    let mut t_exit = f32::INFINITY;
+    for axis in 0..3 {
+        if dir[axis].abs() < f32::EPSILON {
+            if origin[axis] < min[axis] || origin[axis] > max[axis] {
+                return None;
+            }
+            continue;
+        }
+        let t0 = (min[axis] - origin[axis]) / dir[axis];
+        let t1 = (max[axis] - origin[axis]) / dir[axis];
+        t_enter = t_enter.max(t0.min(t1));
+        t_exit = t_exit.min(t0.max(t1));
+    }
+    (t_enter <= t_exit).then_some(t_enter)
+}
+
+/// Face through which the ray enters the bottom `height` of the cell, if at all.
+fn partial_block_face(origin: Vec3, dir: Vec3, block: IVec3, height: f32) -> Option<FaceDirection> {
+    let min = block.as_vec3();
+    let max = min + Vec3::new(1.0, height, 1.0);
+    let mut t_enter = f32::NEG_INFINITY;
+    let mut t_exit = f32::INFINITY;
+    let mut face = None;
+    let axes = [
+        (FaceDirection::NegX, FaceDirection::PosX),
+        (FaceDirection::NegY, FaceDirection::PosY),
+        (FaceDirection::NegZ, FaceDirection::PosZ),
+    ];
+    for (axis, (neg_face, pos_face)) in axes.into_iter().enumerate() {
+        if dir[axis].abs() < f32::EPSILON {
+            if origin[axis] < min[axis] || origin[axis] > max[axis] {
+                return None;
+            }
+            continue;
+        }
+        let t0 = (min[axis] - origin[axis]) / dir[axis];
+        let t1 = (max[axis] - origin[axis]) / dir[axis];
+        let (near, far, entered) = if t0 < t1 {
+            (t0, t1, neg_face)
+        } else {
+            (t1, t0, pos_face)
+        };
+        if near > t_enter {
+            t_enter = near;
+            face = Some(entered);
+        }
+        t_exit = t_exit.min(far);
+    }
+
+    if t_enter > t_exit || t_exit < 0.0 {
+        return None;
+    }
+    face
+}
+
 fn axis_params(
     origin_component: f32,
     direction_component: f32,
@@ -135,3 +209,151 @@ fn axis_params(
     let t_delta = 1.0 / direction_component.abs();
     (step, t_max, t_delta)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::block::{BLOCK_SNOW_LAYER, BLOCK_STONE, BLOCK_WATER, BlockId, SNOW_MAX_LAYERS};
+
+    /// Step of the brute-force marcher along the ray.
+    const MARCH_STEP: f32 = 1e-3;
+    /// How far inside a box a sample must be, and how far before a hit, to
+    /// count; rays grazing an edge or corner may go either way.
+    const MARGIN: f32 = 2e-3;
+
+    /// Blocks around the origin, across chunk borders on every axis.
+    fn placed_blocks() -> impl Strategy<Value = HashMap<IVec3, BlockId>> {
+        let cell = (-3..3, -3..3, -3..3).prop_map(|(x, y, z)| IVec3::new(x, y, z));
+        let block = prop_oneof![
+            3 => Just(BLOCK_STONE),
+            1 => Just(BLOCK_WATER),
+            2 => (0..SNOW_MAX_LAYERS).prop_map(|layer| BLOCK_SNOW_LAYER + layer),
+        ];
+        prop::collection::hash_map(cell, block, 0..40)
+    }
+
+    /// Origins in and around the blocks, often exactly on a cell boundary.
+    fn origin() -> impl Strategy<Value = Vec3> {
+        let component = prop_oneof![-4.0f32..4.0, (-4..=4).prop_map(|c| c as f32)];
+        [component.clone(), component.clone(), component].prop_map(Vec3::from)
+    }
+
+    /// Directions of any length, often parallel to an axis or a cell face.
+    fn direction() -> impl Strategy<Value = Vec3> {
+        let component = prop_oneof![Just(0.0f32), 1e-3f32..1.0, -1.0f32..-1e-3];
+        [component.clone(), component.clone(), component]
+            .prop_map(Vec3::from)
+            .prop_filter("a ray needs a direction", |dir| dir.length() > 0.1)
+    }
+
+    /// The part of `cell` that stops a pick: all of a full cube, the bottom
+    /// of other solid blocks, and nothing of liquids or air.
+    fn pick_box(world: &World, cell: IVec3) -> Option<(Vec3, Vec3)> {
+        let kind = world.block_info(cell).kind;
+        let height = if kind.is_full_cube() {
+            1.0
+        } else if kind.is_solid() && !kind.is_liquid() {
+            kind.height()
+        } else {
+            return None;
+        };
+        let min = cell.as_vec3();
+        Some((min, min + Vec3::new(1.0, height, 1.0)))
+    }
+
+    /// Distances up to `until` at which the ray is well inside a pick box
+    /// outside its starting cell, sampled every `MARCH_STEP`.
+    fn march(world: &World, origin: Vec3, dir: Vec3, until: f32) -> impl Iterator<Item = f32> {
+        let start = origin.floor().as_ivec3();
+        let samples = (until / MARCH_STEP) as u32;
+        (0..=samples)
+            .map(|i| i as f32 * MARCH_STEP)
+            .filter(move |&t| {
+                let point = origin + dir * t;
+                let cell = point.floor().as_ivec3();
+                cell != start
+                    && pick_box(world, cell).is_some_and(|(min, max)| {
+                        (point - min).min_element() > MARGIN && (max - point).min_element() > MARGIN
+                    })
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn hits_are_entered_faces_with_nothing_solid_before_them(
+            blocks in placed_blocks(),
+            origin in origin(),
+            direction in direction(),
+            max_distance in 0.5f32..12.0,
+        ) {
+            let world = World::from_blocks(blocks);
+            let dir = direction.normalize();
+            let Some(hit) = pick_block(&world, origin, direction, max_distance) else {
+                return Ok(());
+            };
+            prop_assert_ne!(hit.block, origin.floor().as_ivec3());
+            prop_assert!(pick_box(&world, hit.block).is_some());
+            prop_assert!(hit.distance <= max_distance);
+
+            let cell = hit.block.as_vec3();
+            let entry = ray_box_distance(origin, dir, cell, cell + Vec3::ONE);
+            prop_assert!(
+                entry.is_some_and(|entry| (entry - hit.distance).abs() < 1e-3),
+                "hit {:?} at {} but the ray enters its cell at {:?}",
+                hit.block,
+                hit.distance,
+                entry
+            );
+            let normal = hit.face.normal().as_vec3();
+            prop_assert!(normal.dot(dir) < 0.0, "{:?} faces away from the ray", hit.face);
+            if world.block_info(hit.block).kind
+                .is_full_cube()
+            {
+                let plane = cell + (normal.max(Vec3::ZERO));
+                let point = origin + dir * hit.distance;
+                prop_assert!(
+                    ((point - plane) * normal).abs().max_element() < 1e-3,
+                    "{:?} hit at {} is off its {:?} face",
+                    hit.block,
+                    point,
+                    hit.face
+                );
+            }
+
+            let earlier = march(&world, origin, dir, hit.distance - MARGIN).next();
+            prop_assert!(
+                earlier.is_none(),
+                "hit {:?} at {} behind a block at {:?}",
+                hit.block,
+                hit.distance,
+                earlier
+            );
+        }
+
+        #[test]
+        fn marched_hits_are_picked(
+            blocks in placed_blocks(),
+            origin in origin(),
+            direction in direction(),
+            max_distance in 0.5f32..12.0,
+        ) {
+            let world = World::from_blocks(blocks);
+            let dir = direction.normalize();
+            let Some(marched) = march(&world, origin, dir, max_distance - MARGIN).next() else {
+                return Ok(());
+            };
+            let hit = pick_block(&world, origin, direction, max_distance);
+            prop_assert!(
+                hit.as_ref().is_some_and(|hit| hit.distance <= marched),
+                "marching hits {:?} at {} but pick_block found {:?}",
+                (origin + dir * marched).floor().as_ivec3(),
+                marched,
+                hit.map(|hit| (hit.block, hit.distance))
+            );
+        }
+    }
+}
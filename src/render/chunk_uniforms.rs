@@ -0,0 +1,108 @@
+use std::num::NonZeroU64;
+
+use bytemuck::{Pod, Zeroable};
+use glam::IVec3;
+use wgpu::util::DeviceExt;
+
+use crate::coords::ChunkPos;
+
+/// Placement and shading parameters of one mesh drawn by the world shaders.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct ChunkUniform {
+    /// xyz: where the mesh's vertex positions start, w: level of detail; the
+    /// mesh is scaled by `2^lod` about its origin.
+    origin: [f32; 4],
+    /// rgb: multiplied into every vertex colour.
+    tint: [f32; 4],
+}
+
+impl ChunkUniform {
+    /// Meshes whose vertices are already placed relative to the render origin.
+    pub const WORLD: Self = Self {
+        origin: [0.0; 4],
+        tint: [1.0; 4],
+    };
+
+    /// The uniform of `coord`'s chunk mesh, drawn relative to `render_origin`.
+    pub fn chunk(coord: ChunkPos, render_origin: IVec3) -> Self {
+        let [x, y, z] = coord.origin(render_origin);
+        Self {
+            origin: [x, y, z, 0.0],
+            ..Self::WORLD
+        }
+    }
+}
+
+/// Every mesh's `ChunkUniform` in one buffer, each at its own aligned slot,
+/// and the bind group selecting one of them through a dynamic offset.
+pub struct ChunkUniforms {
+    _buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    stride: u32,
+}
+
+impl ChunkUniforms {
+    /// Uploads `uniforms`; slot `i` is bound with `offset(i)`.
+    pub fn new(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        uniforms: &[ChunkUniform],
+    ) -> Self {
+        let size = std::mem::size_of::<ChunkUniform>() as u32;
+        let stride = size.next_multiple_of(device.limits().min_uniform_buffer_offset_alignment);
+        let mut contents = vec![0u8; stride as usize * uniforms.len().max(1)];
+        for (slot, uniform) in contents.chunks_exact_mut(stride as usize).zip(uniforms) {
+            slot[..size as usize].copy_from_slice(bytemuck::bytes_of(uniform));
+        }
+
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Chunk uniform buffer"),
+            contents: &contents,
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Chunk uniform bind group"),
+            layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer: &buffer,
+                    offset: 0,
+                    size: NonZeroU64::new(size as u64),
+                }),
+            }],
+        });
+
+        Self {
+            _buffer: buffer,
+            bind_group,
+            stride,
+        }
+    }
+
+    fn offset(&self, slot: usize) -> u32 {
+        slot as u32 * self.stride
+    }
+
+    /// Binds slot `slot` as `index` for the draws that follow.
+    pub fn bind<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, index: u32, slot: usize) {
+        render_pass.set_bind_group(index, &self.bind_group, &[self.offset(slot)]);
+    }
+}
+
+pub fn bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Chunk uniform bind group layout"),
+        entries: &[wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::VERTEX,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: true,
+                min_binding_size: NonZeroU64::new(std::mem::size_of::<ChunkUniform>() as u64),
+            },
+            count: None,
+        }],
+    })
+}
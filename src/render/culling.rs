@@ -0,0 +1,231 @@
+use bytemuck::{Pod, Zeroable};
+use glam::{IVec3, Vec3};
+use wgpu::util::DeviceExt;
+
+use crate::camera::Frustum;
+use crate::coords::ChunkPos;
+use crate::world::CHUNK_SIZE;
+
+/// Chunks each culling workgroup tests; matches `culling.wgsl`.
+const WORKGROUP_SIZE: u32 = 64;
+/// Size of one `wgpu::util::DrawIndexedIndirect`.
+const DRAW_SIZE: u64 = 20;
+
+/// A chunk's world-space bounds and how many indices each of its draws has.
+/// Mirrors `Chunk` in `culling.wgsl`.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct ChunkBounds {
+    min: [f32; 3],
+    terrain_indices: u32,
+    max: [f32; 3],
+    water_indices: u32,
+}
+
+impl ChunkBounds {
+    pub fn new(
+        coord: ChunkPos,
+        render_origin: IVec3,
+        terrain_indices: u32,
+        water_indices: u32,
+    ) -> Self {
+        let min = coord.origin(render_origin);
+        Self {
+            min,
+            terrain_indices,
+            max: min.map(|axis| axis + CHUNK_SIZE as f32),
+            water_indices,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct FrustumUniform {
+    planes: [[f32; 4]; 6],
+    chunk_count: u32,
+    _padding: [u32; 3],
+}
+
+/// GPU frustum culling of the chunk meshes.
+///
+/// Every chunk's bounds are uploaded once per mesh rebuild. Each frame a
+/// compute pass tests them against the camera frustum and writes the
+/// chunk's indirect draws, with no instances when it is off screen, so
+/// drawing never waits on the CPU. There is no depth pyramid yet, so chunks
+/// hidden behind others are still drawn.
+///
+/// The CPU repeats the same test only to count the chunks drawn, which the
+/// renderer reports in its timings; reading back the draws would stall.
+pub struct ChunkCulling {
+    frustum_buffer: wgpu::Buffer,
+    _chunk_buffer: wgpu::Buffer,
+    draw_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    bounds: Vec<ChunkBounds>,
+}
+
+/// How many chunks this frame's culling kept and rejected.
+#[derive(Clone, Copy, Default)]
+pub struct CullStats {
+    pub drawn: u32,
+    pub culled: u32,
+}
+
+impl ChunkCulling {
+    pub fn new(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        chunks: &[ChunkBounds],
+    ) -> Self {
+        let frustum_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Culling frustum buffer"),
+            size: std::mem::size_of::<FrustumUniform>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        // Storage bindings cannot be empty, so a world with no meshes still
+        // gets one (unused) chunk.
+        let placeholder = [ChunkBounds::zeroed()];
+        let bounds = if chunks.is_empty() {
+            &placeholder[..]
+        } else {
+            chunks
+        };
+        let chunk_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Culling chunk buffer"),
+            contents: bytemuck::cast_slice(bounds),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        let draw_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Chunk indirect draw buffer"),
+            size: bounds.len() as u64 * 2 * DRAW_SIZE,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::INDIRECT,
+            mapped_at_creation: false,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Culling bind group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: frustum_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: chunk_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: draw_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        Self {
+            frustum_buffer,
+            _chunk_buffer: chunk_buffer,
+            draw_buffer,
+            bind_group,
+            bounds: chunks.to_vec(),
+        }
+    }
+
+    /// Records the pass writing this frame's draws as seen through `frustum`.
+    pub fn cull(
+        &self,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        pipeline: &wgpu::ComputePipeline,
+        frustum: &Frustum,
+    ) -> CullStats {
+        let chunk_count = self.bounds.len() as u32;
+        if chunk_count == 0 {
+            return CullStats::default();
+        }
+        let drawn = self
+            .bounds
+            .iter()
+            .filter(|chunk| frustum.intersects_aabb(Vec3::from(chunk.min), Vec3::from(chunk.max)))
+            .count() as u32;
+        let frustum = FrustumUniform {
+            planes: frustum.planes(),
+            chunk_count,
+            _padding: [0; 3],
+        };
+        queue.write_buffer(&self.frustum_buffer, 0, bytemuck::bytes_of(&frustum));
+
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Chunk culling pass"),
+        });
+        pass.set_pipeline(pipeline);
+        pass.set_bind_group(0, &self.bind_group, &[]);
+        pass.dispatch_workgroups(chunk_count.div_ceil(WORKGROUP_SIZE), 1, 1);
+        CullStats {
+            drawn,
+            culled: chunk_count - drawn,
+        }
+    }
+
+    /// Draws the terrain of the chunk at `index` in the bounds, if it survived culling.
+    pub fn draw_terrain<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, index: usize) {
+        render_pass.draw_indexed_indirect(&self.draw_buffer, index as u64 * 2 * DRAW_SIZE);
+    }
+
+    /// Draws the liquids of the chunk at `index` in the bounds, if it survived culling.
+    pub fn draw_water<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, index: usize) {
+        render_pass.draw_indexed_indirect(&self.draw_buffer, (index as u64 * 2 + 1) * DRAW_SIZE);
+    }
+}
+
+pub fn bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    let storage = |binding, read_only| wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage { read_only },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    };
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Culling bind group layout"),
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            storage(1, true),
+            storage(2, false),
+        ],
+    })
+}
+
+pub fn create_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+) -> wgpu::ComputePipeline {
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Chunk culling pipeline layout"),
+        bind_group_layouts: &[layout],
+        push_constant_ranges: &[],
+    });
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Chunk culling shader"),
+        source: wgpu::ShaderSource::Wgsl(include_str!("culling.wgsl").into()),
+    });
+    device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+        label: Some("Chunk culling pipeline"),
+        layout: Some(&pipeline_layout),
+        module: &shader,
+        entry_point: "cs_main",
+    })
+}
@@ -0,0 +1,67 @@
+struct Frustum {
+    // Inward-facing planes (xyz normal, w offset); a point is inside all of
+    // them when `dot(plane.xyz, p) + plane.w >= 0`.
+    planes: array<vec4<f32>, 6>,
+    chunk_count: u32,
+};
+
+struct Chunk {
+    min: vec3<f32>,
+    terrain_indices: u32,
+    max: vec3<f32>,
+    water_indices: u32,
+};
+
+// Mirrors `wgpu::util::DrawIndexedIndirect`.
+struct DrawIndexed {
+    index_count: u32,
+    instance_count: u32,
+    first_index: u32,
+    base_vertex: i32,
+    first_instance: u32,
+};
+
+@group(0) @binding(0)
+var<uniform> u_frustum: Frustum;
+
+@group(0) @binding(1)
+var<storage, read> chunks: array<Chunk>;
+
+// Two draws per chunk: its terrain, then its liquids.
+@group(0) @binding(2)
+var<storage, read_write> draws: array<DrawIndexed>;
+
+// Whether any of the box lies in front of every plane, testing the corner
+// farthest along each plane's normal.
+fn in_frustum(lower: vec3<f32>, upper: vec3<f32>) -> bool {
+    for (var i = 0u; i < 6u; i = i + 1u) {
+        let plane = u_frustum.planes[i];
+        let corner = select(lower, upper, plane.xyz >= vec3<f32>(0.0));
+        if dot(plane.xyz, corner) + plane.w < 0.0 {
+            return false;
+        }
+    }
+    return true;
+}
+
+fn draw(index_count: u32, visible: bool) -> DrawIndexed {
+    var out: DrawIndexed;
+    out.index_count = index_count;
+    out.instance_count = select(0u, 1u, visible);
+    out.first_index = 0u;
+    out.base_vertex = 0;
+    out.first_instance = 0u;
+    return out;
+}
+
+@compute @workgroup_size(64)
+fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let index = id.x;
+    if index >= u_frustum.chunk_count {
+        return;
+    }
+    let chunk = chunks[index];
+    let visible = in_frustum(chunk.min, chunk.max);
+    draws[index * 2u] = draw(chunk.terrain_indices, visible);
+    draws[index * 2u + 1u] = draw(chunk.water_indices, visible);
+}
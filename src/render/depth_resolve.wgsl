@@ -0,0 +1,23 @@
+// Resolves the multisampled scene depth into the single-sample depth buffer
+// the water and light shaft passes read, keeping the nearest sample of each
+// pixel: with reverse-Z, the largest.
+
+@group(0) @binding(0)
+var msaa_depth: texture_depth_multisampled_2d;
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    // Single oversized triangle covering the screen.
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    return vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+}
+
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @builtin(frag_depth) f32 {
+    let texel = vec2<i32>(position.xy);
+    var depth = 0.0;
+    for (var i = 0; i < i32(textureNumSamples(msaa_depth)); i += 1) {
+        depth = max(depth, textureLoad(msaa_depth, texel, i));
+    }
+    return depth;
+}
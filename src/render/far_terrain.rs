@@ -0,0 +1,102 @@
+use glam::{IVec3, Vec3};
+use wgpu::util::DeviceExt;
+
+use crate::coords::BlockPos;
+use crate::render::mesh::{self, BlockTiles, MeshVertex};
+use crate::world::World;
+
+/// Heightmap-derived low-poly ring drawn beyond the loaded chunks, meshed by
+/// [`mesh::build_far_terrain_mesh`].
+///
+/// Rebuilt lazily when the camera moves into another chunk column or the set
+/// of loaded chunks changes; cells over loaded columns are skipped.
+pub struct FarTerrain {
+    vertex_buffer: Option<wgpu::Buffer>,
+    index_buffer: Option<wgpu::Buffer>,
+    index_count: u32,
+    center_column: Option<(i32, i32)>,
+    chunk_count: usize,
+    radius: f32,
+    render_origin: IVec3,
+}
+
+impl FarTerrain {
+    pub fn new() -> Self {
+        Self {
+            vertex_buffer: None,
+            index_buffer: None,
+            index_count: 0,
+            center_column: None,
+            chunk_count: 0,
+            radius: 0.0,
+            render_origin: IVec3::ZERO,
+        }
+    }
+
+    /// Rebuilds the mesh if the camera column, loaded chunk set, view
+    /// distance or render origin changed since the last build.
+    /// `camera_position` is in world space; the mesh is placed relative to
+    /// `render_origin`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn sync<V: bytemuck::Pod>(
+        &mut self,
+        device: &wgpu::Device,
+        world: &World,
+        camera_position: Vec3,
+        radius: f32,
+        render_origin: IVec3,
+        tiles: &BlockTiles,
+        to_vertex: impl Fn(MeshVertex) -> V,
+    ) {
+        let center = BlockPos(camera_position.floor().as_ivec3()).chunk();
+        let center_column = (center.x, center.z);
+        let chunk_count = world.chunk_count();
+        if self.center_column == Some(center_column)
+            && self.chunk_count == chunk_count
+            && self.radius == radius
+            && self.render_origin == render_origin
+        {
+            return;
+        }
+        self.center_column = Some(center_column);
+        self.chunk_count = chunk_count;
+        self.radius = radius;
+        self.render_origin = render_origin;
+
+        let mesh =
+            mesh::build_far_terrain_mesh(world, camera_position, radius, render_origin, tiles);
+        self.index_count = mesh.indices.len() as u32;
+        if mesh.indices.is_empty() {
+            self.vertex_buffer = None;
+            self.index_buffer = None;
+            return;
+        }
+
+        let vertices: Vec<V> = mesh.vertices.into_iter().map(to_vertex).collect();
+        self.vertex_buffer = Some(
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Far terrain vertex buffer"),
+                contents: bytemuck::cast_slice(&vertices),
+                usage: wgpu::BufferUsages::VERTEX,
+            }),
+        );
+        self.index_buffer = Some(
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Far terrain index buffer"),
+                contents: bytemuck::cast_slice(&mesh.indices),
+                usage: wgpu::BufferUsages::INDEX,
+            }),
+        );
+    }
+
+    /// Draws with whatever world pipeline and bind groups are already set.
+    pub fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        let (Some(vertex_buffer), Some(index_buffer)) = (&self.vertex_buffer, &self.index_buffer)
+        else {
+            return;
+        };
+        render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+        render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+        render_pass.draw_indexed(0..self.index_count, 0, 0..1);
+    }
+}
@@ -0,0 +1,228 @@
+use std::ops::Range;
+
+use bytemuck::{Pod, Zeroable};
+use glam::{IVec3, Vec3};
+use wgpu::util::DeviceExt;
+
+use crate::biome::{self, BiomeTint};
+use crate::block::{BlockKind, FaceDirection};
+use crate::render::mesh::{self, BlockTiles, MeshVertex};
+use crate::world::CHUNK_SIZE;
+
+/// Vertex of an instanced block mesh: a world vertex plus whether its colour
+/// takes the instance's biome tint.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct InstanceVertex {
+    position: [f32; 3],
+    color: [f32; 3],
+    uv: [f32; 2],
+    tile: [f32; 4],
+    alpha_cutoff: f32,
+    tinted: f32,
+}
+
+impl InstanceVertex {
+    fn new(vertex: MeshVertex, tinted: bool) -> Self {
+        Self {
+            position: vertex.position,
+            color: vertex.color,
+            uv: vertex.uv,
+            tile: vertex.tile,
+            alpha_cutoff: vertex.alpha_cutoff,
+            tinted: if tinted { 1.0 } else { 0.0 },
+        }
+    }
+
+    pub fn buffer_layout() -> wgpu::VertexBufferLayout<'static> {
+        const ATTRIBUTES: [wgpu::VertexAttribute; 6] = wgpu::vertex_attr_array![
+            0 => Float32x3,
+            1 => Float32x3,
+            2 => Float32x2,
+            3 => Float32x4,
+            4 => Float32,
+            5 => Float32,
+        ];
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<InstanceVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &ATTRIBUTES,
+        }
+    }
+}
+
+/// Placement of one copy of a block mesh.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct Instance {
+    /// Minimum corner in render space.
+    position: [f32; 3],
+    /// Edge length; below 1.0 for shrunken models such as dropped items.
+    scale: f32,
+    /// Biome colormap tint for the mesh's tinted vertices.
+    tint: [f32; 3],
+    /// Light reaching the copy, which the shader treats like sky light.
+    light: f32,
+}
+
+impl Instance {
+    /// An instance of `kind` with its minimum corner at the world-space
+    /// `position`, placed relative to `render_origin`.
+    pub fn new(
+        kind: BlockKind,
+        position: Vec3,
+        render_origin: IVec3,
+        scale: f32,
+        light: f32,
+    ) -> Self {
+        // Match the rasterizer's chunk placement (see `ChunkPos::origin`).
+        let render_offset = Vec3::new(-(CHUNK_SIZE as f32) / 2.0, 0.0, -(CHUNK_SIZE as f32) / 2.0);
+        let column = position.floor().as_ivec3();
+        Self {
+            position: (position - render_origin.as_vec3() + render_offset).to_array(),
+            scale,
+            tint: biome::tint_at(biome_tint(kind), column.x, column.z),
+            light,
+        }
+    }
+
+    pub fn buffer_layout() -> wgpu::VertexBufferLayout<'static> {
+        const ATTRIBUTES: [wgpu::VertexAttribute; 2] = wgpu::vertex_attr_array![
+            6 => Float32x4,
+            7 => Float32x4,
+        ];
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Instance>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &ATTRIBUTES,
+        }
+    }
+}
+
+/// The colormap a block's tinted faces follow; blocks only ever use one.
+fn biome_tint(kind: BlockKind) -> BiomeTint {
+    FaceDirection::ALL
+        .into_iter()
+        .map(|face| kind.tint_for_face(face))
+        .find(|&tint| tint != BiomeTint::None)
+        .unwrap_or(BiomeTint::None)
+}
+
+/// A unit mesh of every solid block, packed into one vertex and index
+/// buffer, which every instance batch draws from.
+pub struct InstanceMeshes {
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+    /// Per block id: the mesh's index range and base vertex.
+    meshes: Vec<(Range<u32>, i32)>,
+}
+
+impl InstanceMeshes {
+    pub fn new(device: &wgpu::Device, tiles: &BlockTiles) -> Self {
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        let mut meshes = Vec::new();
+        for kind in BlockKind::ALL {
+            let first_index = indices.len() as u32;
+            let base_vertex = vertices.len() as i32;
+            if kind.is_solid() {
+                let instance_mesh = mesh::build_instance_mesh(kind, tiles);
+                vertices.extend(
+                    instance_mesh
+                        .mesh
+                        .vertices
+                        .into_iter()
+                        .zip(instance_mesh.tinted)
+                        .map(|(vertex, tinted)| InstanceVertex::new(vertex, tinted)),
+                );
+                indices.extend(instance_mesh.mesh.indices);
+            }
+            meshes.push((first_index..indices.len() as u32, base_vertex));
+        }
+
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Instance mesh vertex buffer"),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Instance mesh index buffer"),
+            contents: bytemuck::cast_slice(&indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+        Self {
+            vertex_buffer,
+            index_buffer,
+            meshes,
+        }
+    }
+}
+
+/// Instances grouped by block, uploaded in one buffer so every block's
+/// instances are a single draw wherever they are in the world.
+pub struct InstanceBatches {
+    instance_buffer: Option<wgpu::Buffer>,
+    /// Block id and range of its instances in the buffer.
+    batches: Vec<(usize, Range<u32>)>,
+}
+
+impl InstanceBatches {
+    pub fn new() -> Self {
+        Self {
+            instance_buffer: None,
+            batches: Vec::new(),
+        }
+    }
+
+    pub fn upload(
+        &mut self,
+        device: &wgpu::Device,
+        label: &str,
+        mut instances: Vec<(BlockKind, Instance)>,
+    ) {
+        self.batches.clear();
+        if instances.is_empty() {
+            self.instance_buffer = None;
+            return;
+        }
+
+        instances.sort_by_key(|(kind, _)| kind.id());
+        let mut start = 0;
+        for (index, (kind, _)) in instances.iter().enumerate() {
+            let last = instances
+                .get(index + 1)
+                .is_none_or(|(next, _)| next.id() != kind.id());
+            if last {
+                self.batches
+                    .push((kind.id() as usize, start..index as u32 + 1));
+                start = index as u32 + 1;
+            }
+        }
+
+        let data: Vec<Instance> = instances
+            .into_iter()
+            .map(|(_, instance)| instance)
+            .collect();
+        self.instance_buffer = Some(
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(label),
+                contents: bytemuck::cast_slice(&data),
+                usage: wgpu::BufferUsages::VERTEX,
+            }),
+        );
+    }
+
+    /// Draws with whatever instanced pipeline and bind groups are already set.
+    pub fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, meshes: &'a InstanceMeshes) {
+        let Some(instance_buffer) = &self.instance_buffer else {
+            return;
+        };
+        render_pass.set_vertex_buffer(0, meshes.vertex_buffer.slice(..));
+        render_pass.set_vertex_buffer(1, instance_buffer.slice(..));
+        render_pass.set_index_buffer(meshes.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+        for (block, instances) in &self.batches {
+            let (indices, base_vertex) = &meshes.meshes[*block];
+            render_pass.draw_indexed(indices.clone(), *base_vertex, instances.clone());
+        }
+    }
+}
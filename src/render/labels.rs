@@ -0,0 +1,51 @@
+//! Names for GPU captures (RenderDoc, Xcode, PIX) and validation messages:
+//! debug groups around each stage of a frame, and labels for the resources
+//! wgpu would otherwise leave anonymous or ambiguous.
+
+use crate::coords::ChunkPos;
+
+/// Records `record` inside an encoder debug group, so captures show the
+/// passes and copies it makes nested under `label`.
+pub fn debug_group<R>(
+    encoder: &mut wgpu::CommandEncoder,
+    label: &str,
+    record: impl FnOnce(&mut wgpu::CommandEncoder) -> R,
+) -> R {
+    encoder.push_debug_group(label);
+    let result = record(encoder);
+    encoder.pop_debug_group();
+    result
+}
+
+/// Like [`debug_group`], for a run of draws within one render pass.
+pub fn pass_debug_group<'a, R>(
+    pass: &mut wgpu::RenderPass<'a>,
+    label: &str,
+    record: impl FnOnce(&mut wgpu::RenderPass<'a>) -> R,
+) -> R {
+    pass.push_debug_group(label);
+    let result = record(pass);
+    pass.pop_debug_group();
+    result
+}
+
+/// A view of all of `texture`. Views made from the default descriptor are
+/// unnamed, and captures list them by id alone.
+pub fn labeled_view(texture: &wgpu::Texture, label: &str) -> wgpu::TextureView {
+    texture.create_view(&wgpu::TextureViewDescriptor {
+        label: Some(label),
+        ..Default::default()
+    })
+}
+
+/// Label of one of the resources there is one of per chunk, such as
+/// "Terrain vertex buffer (chunk 3, 0, -2)".
+pub fn chunk_label(resource: &str, coord: ChunkPos) -> String {
+    format!("{resource} (chunk {}, {}, {})", coord.x, coord.y, coord.z)
+}
+
+/// Label of one of the targets there is one of per view size, such as
+/// "Scene color texture (1280x720)".
+pub fn sized_label(resource: &str, (width, height): (u32, u32)) -> String {
+    format!("{resource} ({width}x{height})")
+}
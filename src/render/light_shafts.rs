@@ -0,0 +1,41 @@
+use bytemuck::{Pod, Zeroable};
+use glam::Vec3;
+
+use crate::camera::{Camera, Projection};
+
+const STRENGTH: f32 = 0.6;
+
+/// Screen-space sun position for the radial-blur light shaft pass.
+///
+/// Both renderers mark sky pixels (depth 0 in the rasterizer, alpha 0 in the
+/// ray tracer) and march from each pixel towards `sun_uv`, so geometry in
+/// front of the sun casts visible shafts through the haze.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct LightShaftUniform {
+    sun_uv: [f32; 2],
+    intensity: f32,
+    aspect: f32,
+}
+
+impl LightShaftUniform {
+    pub fn new(camera: &Camera, projection: &Projection, sun: Vec3, enabled: bool) -> Self {
+        let view_proj = projection.matrix() * camera.view_matrix();
+        let clip = view_proj * sun.extend(0.0);
+        let facing = camera.forward().dot(sun).clamp(0.0, 1.0);
+        if !enabled || clip.w <= f32::EPSILON || facing <= 0.0 {
+            return Self {
+                sun_uv: [0.5, 0.5],
+                intensity: 0.0,
+                aspect: projection.aspect,
+            };
+        }
+
+        let ndc = [clip.x / clip.w, clip.y / clip.w];
+        Self {
+            sun_uv: [ndc[0] * 0.5 + 0.5, 0.5 - ndc[1] * 0.5],
+            intensity: STRENGTH * facing,
+            aspect: projection.aspect,
+        }
+    }
+}
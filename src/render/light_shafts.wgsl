@@ -0,0 +1,87 @@
+struct VertexOut {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOut {
+    // Single oversized triangle covering the screen.
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    var out: VertexOut;
+    out.position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+@group(0) @binding(0) var scene_texture: texture_2d<f32>;
+@group(0) @binding(1) var depth_texture: texture_depth_2d;
+@group(0) @binding(2) var scene_sampler: sampler;
+
+struct Output {
+    gamma: f32,
+    encode_srgb: u32,
+    hdr: u32,
+};
+
+@group(0) @binding(3) var<uniform> u_output: Output;
+
+struct LightShafts {
+    sun_uv: vec2<f32>,
+    intensity: f32,
+    aspect: f32,
+};
+
+@group(0) @binding(4) var<uniform> u_shafts: LightShafts;
+
+const SHAFT_SAMPLES: i32 = 48;
+const SHAFT_DENSITY: f32 = 0.9;
+const SHAFT_DECAY: f32 = 0.955;
+const SHAFT_WEIGHT: f32 = 0.06;
+const SUN_COLOR: vec3<f32> = vec3<f32>(1.0, 0.9, 0.7);
+
+fn linear_to_srgb(c: vec3<f32>) -> vec3<f32> {
+    let low = c * 12.92;
+    let high = 1.055 * pow(c, vec3<f32>(1.0 / 2.4)) - vec3<f32>(0.055);
+    return select(high, low, c <= vec3<f32>(0.0031308));
+}
+
+fn encode_output(linear: vec3<f32>) -> vec3<f32> {
+    if u_output.hdr != 0u {
+        // The display takes linear light and shows values above 1.0 brighter
+        // than SDR white, so nothing is clamped or encoded.
+        return pow(max(linear, vec3<f32>(0.0)), vec3<f32>(2.2 / u_output.gamma));
+    }
+    let adjusted = pow(clamp(linear, vec3<f32>(0.0), vec3<f32>(1.0)), vec3<f32>(2.2 / u_output.gamma));
+    if u_output.encode_srgb != 0u {
+        return linear_to_srgb(adjusted);
+    }
+    return adjusted;
+}
+
+// Sky pixels near the sun act as the light source; everything else occludes.
+fn sun_source(uv: vec2<f32>) -> f32 {
+    let dims = vec2<f32>(textureDimensions(depth_texture));
+    let texel = vec2<i32>(clamp(uv, vec2<f32>(0.0), vec2<f32>(0.999)) * dims);
+    // Reverse-Z: the cleared far plane is 0.
+    let sky = select(0.0, 1.0, textureLoad(depth_texture, texel, 0) <= 0.0);
+    let offset = (uv - u_shafts.sun_uv) * vec2<f32>(u_shafts.aspect, 1.0);
+    return sky * exp(-length(offset) * 3.0);
+}
+
+@fragment
+fn fs_main(in: VertexOut) -> @location(0) vec4<f32> {
+    var color = textureSample(scene_texture, scene_sampler, in.uv).rgb;
+    if u_shafts.intensity > 0.0 {
+        let step = (in.uv - u_shafts.sun_uv) * (SHAFT_DENSITY / f32(SHAFT_SAMPLES));
+        var coord = in.uv;
+        var decay = 1.0;
+        var shafts = 0.0;
+        for (var i = 0; i < SHAFT_SAMPLES; i = i + 1) {
+            coord = coord - step;
+            shafts = shafts + sun_source(coord) * decay * SHAFT_WEIGHT;
+            decay = decay * SHAFT_DECAY;
+        }
+        color = color + SUN_COLOR * shafts * u_shafts.intensity;
+    }
+    return vec4<f32>(encode_output(color), 1.0);
+}
@@ -0,0 +1,103 @@
+use std::collections::HashSet;
+
+use glam::{IVec3, Vec3};
+
+use crate::biome;
+use crate::block::{BlockKind, FaceDirection};
+use crate::coords::BlockPos;
+use crate::render::mesh::{BlockTiles, Mesh, MeshVertex};
+use crate::terrain::SEA_LEVEL;
+use crate::world::{CHUNK_SIZE, World};
+
+/// Edge length of one far-field cell in blocks; divides `CHUNK_SIZE` so cells
+/// line up with chunk columns and never overlap loaded terrain.
+const CELL_SIZE: i32 = 8;
+/// Slightly dimmer than loaded grass so the far field reads as distance haze.
+const FAR_SHADE: f32 = 0.9;
+
+/// Meshes the terrain within `radius` of `camera_position` that lies outside
+/// the loaded chunk columns: one grass-textured quad per `CELL_SIZE` cell,
+/// its corners at the generated surface height. Positions are relative to
+/// `render_origin`; worlds without a heightfield get an empty mesh.
+pub fn build_far_terrain_mesh(
+    world: &World,
+    camera_position: Vec3,
+    radius: f32,
+    render_origin: IVec3,
+    tiles: &BlockTiles,
+) -> Mesh {
+    let terrain = world.terrain();
+    if !terrain.preset.has_heightfield() {
+        return Mesh {
+            vertices: Vec::new(),
+            indices: Vec::new(),
+        };
+    }
+    let loaded_columns: HashSet<(i32, i32)> = world
+        .iter_chunks()
+        .map(|(coord, _)| (coord.x, coord.z))
+        .collect();
+
+    // Match the rasterizer's chunk placement (see `ChunkPos::origin`).
+    let render_offset = -(CHUNK_SIZE as f32) / 2.0;
+    let tile = tiles.rect(BlockKind::Grass, FaceDirection::PosY);
+    let tint = BlockKind::Grass.tint_for_face(FaceDirection::PosY);
+
+    let min_cell_x = ((camera_position.x - radius) / CELL_SIZE as f32).floor() as i32;
+    let max_cell_x = ((camera_position.x + radius) / CELL_SIZE as f32).ceil() as i32;
+    let min_cell_z = ((camera_position.z - radius) / CELL_SIZE as f32).floor() as i32;
+    let max_cell_z = ((camera_position.z + radius) / CELL_SIZE as f32).ceil() as i32;
+    let radius_sq = radius * radius;
+
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    for cell_z in min_cell_z..max_cell_z {
+        for cell_x in min_cell_x..max_cell_x {
+            let x0 = cell_x * CELL_SIZE;
+            let z0 = cell_z * CELL_SIZE;
+            let chunk = BlockPos::new(x0, 0, z0).chunk();
+            let column = (chunk.x, chunk.z);
+            if loaded_columns.contains(&column) {
+                continue;
+            }
+            let center_x = (x0 + CELL_SIZE / 2) as f32 - camera_position.x;
+            let center_z = (z0 + CELL_SIZE / 2) as f32 - camera_position.z;
+            if center_x * center_x + center_z * center_z > radius_sq {
+                continue;
+            }
+
+            let base_index = vertices.len() as u32;
+            for (dx, dz) in [(0, 0), (0, 1), (1, 0), (1, 1)] {
+                let x = x0 + dx * CELL_SIZE;
+                let z = z0 + dz * CELL_SIZE;
+                // Top surface of the column's highest block; seas are drawn flat.
+                let y = terrain
+                    .surface_height(x, z)
+                    .unwrap_or_default()
+                    .max(SEA_LEVEL) as f32
+                    + 1.0;
+                let color = biome::tint_at(tint, x, z).map(|channel| channel * FAR_SHADE);
+                let [x, z] = [x - render_origin.x, z - render_origin.z];
+                vertices.push(MeshVertex {
+                    position: [x as f32 + render_offset, y, z as f32 + render_offset],
+                    color,
+                    sky_light: 1.0,
+                    block_light: 0.0,
+                    uv: [(dx * CELL_SIZE) as f32, (dz * CELL_SIZE) as f32],
+                    tile,
+                    alpha_cutoff: 0.0,
+                });
+            }
+            indices.extend_from_slice(&[
+                base_index,
+                base_index + 1,
+                base_index + 2,
+                base_index + 2,
+                base_index + 1,
+                base_index + 3,
+            ]);
+        }
+    }
+
+    Mesh { vertices, indices }
+}
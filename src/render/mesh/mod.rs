@@ -0,0 +1,625 @@
+//! Every mesher the renderers draw from, turning world data into
+//! [`MeshVertex`] geometry on the CPU; the renderers only upload and draw the
+//! results.
+//!
+//! - [`build_chunk_mesh`]: a loaded chunk, one quad per visible block face so
+//!   each corner keeps its own smooth light and occlusion.
+//! - [`build_instance_mesh`]: one block at the origin, drawn as instances.
+//! - [`build_moving_block_mesh`]: blocks sliding between cells.
+//! - [`build_far_terrain_mesh`]: the low-detail heightmap ring past the
+//!   loaded chunks.
+//!
+//! There is no greedy mesher: merging faces would merge their corners'
+//! light and occlusion too.
+
+use glam::{IVec3, Vec3};
+
+use crate::biome::{self, BiomeTint};
+use crate::block::{BlockDefinition, BlockId, BlockKind, BlockShape, FaceDirection};
+use crate::coords::{ChunkPos, LocalPos};
+use crate::light;
+use crate::render::sky_occlusion::Heightmap;
+use crate::texture::{AtlasLayout, TileId};
+use crate::ticking::MovingBlock;
+use crate::world::{BlockSample, CHUNK_SIZE, World};
+
+mod far;
+
+pub use far::build_far_terrain_mesh;
+
+/// Where every block's faces are in the block atlas, following the runtime
+/// block definitions rather than the built-in ones, so tiles changed by block
+/// files show once the meshes are rebuilt.
+#[derive(Clone)]
+pub struct BlockTiles {
+    layout: AtlasLayout,
+    /// Face tiles per block id.
+    faces: Vec<[TileId; 6]>,
+}
+
+impl BlockTiles {
+    pub fn new(layout: AtlasLayout) -> Self {
+        Self {
+            layout,
+            faces: BlockKind::ALL
+                .iter()
+                .map(|kind| kind.definition().face_tiles)
+                .collect(),
+        }
+    }
+
+    /// Takes the face tiles of `definitions`, indexed by block id.
+    pub fn update(&mut self, definitions: &[BlockDefinition]) {
+        if !definitions.is_empty() {
+            self.faces = definitions
+                .iter()
+                .map(|definition| definition.face_tiles)
+                .collect();
+        }
+    }
+
+    /// Atlas rectangle of `kind`'s `face`; see [`AtlasLayout::tile_rect`].
+    pub fn rect(&self, kind: BlockKind, face: FaceDirection) -> [f32; 4] {
+        let tile = self
+            .faces
+            .get(kind.id() as usize)
+            .map_or(kind.tile_for_face(face), |faces| faces[face.index()]);
+        self.layout.tile_rect(tile)
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct MeshVertex {
+    /// Relative to the chunk's origin for chunk meshes, in world space otherwise.
+    pub position: [f32; 3],
+    /// Biome tint, face shading and ambient occlusion; the light reaching
+    /// the face is separate.
+    pub color: [f32; 3],
+    /// Brightness (0..=1) of the sky light reaching the vertex, darkened by
+    /// how much of the sky the columns around it hide; sun shadows darken it
+    /// further.
+    pub sky_light: f32,
+    /// Brightness (0..=1) of the light luminous blocks spread to the vertex.
+    pub block_light: f32,
+    pub uv: [f32; 2],
+    pub tile: [f32; 4],
+    /// Texels with alpha below this are discarded; 0.0 for opaque faces.
+    pub alpha_cutoff: f32,
+}
+
+pub struct Mesh {
+    pub vertices: Vec<MeshVertex>,
+    pub indices: Vec<u32>,
+}
+
+/// A chunk's geometry, split by the pass that draws it.
+pub struct ChunkMesh {
+    pub opaque: Mesh,
+    /// Liquid surfaces, drawn after everything behind them.
+    pub liquid: Mesh,
+    /// Non-cube blocks, left out of the meshes to be drawn as instances.
+    pub decorations: Vec<Decoration>,
+}
+
+/// A non-cube block of a chunk and the light reaching it: the sky's, or a
+/// luminous block's where that is brighter.
+#[derive(Clone, Copy)]
+pub struct Decoration {
+    pub kind: BlockKind,
+    pub position: [i32; 3],
+    pub light: f32,
+}
+
+/// Geometry of one block at the origin, for drawing as instances.
+pub struct InstanceMesh {
+    pub mesh: Mesh,
+    /// Per vertex: whether it follows the biome colormap. Those vertices are
+    /// left untinted, since each instance brings the tint of its own column.
+    pub tinted: Vec<bool>,
+}
+
+#[derive(Clone, Copy)]
+struct BlockPosition {
+    world: IVec3,
+    /// Where the block's mesh starts, in the mesh's own space.
+    origin: [f32; 3],
+    /// Whether faces following the biome colormap are tinted for `world`'s column.
+    biome_tint: bool,
+}
+
+/// The world a chunk's faces are culled against and lit by.
+#[derive(Clone, Copy)]
+struct Surroundings<'a> {
+    /// The chunk and the cells bordering it.
+    blocks: &'a BlockSample,
+    heightmap: &'a Heightmap,
+    /// 0 leaves corners unoccluded, 1 darkens the most enclosed ones to
+    /// a quarter.
+    ao_strength: f32,
+}
+
+/// Positions are relative to `coord.origin(..)`, which the renderer
+/// supplies per chunk. Faces are lit smoothly by the world's sky
+/// and block light, darkened by how much of the sky `heightmap` says the
+/// columns around them hide, and darkened at corners closed in by
+/// neighbouring blocks by up to `ao_strength` (see `corner_occlusion`).
+pub fn build_chunk_mesh(
+    world: &World,
+    heightmap: &Heightmap,
+    coord: ChunkPos,
+    tiles: &BlockTiles,
+    ao_strength: f32,
+) -> ChunkMesh {
+    let chunk = world
+        .chunk(coord)
+        .expect("chunk must be generated before meshing");
+
+    let mut opaque = Mesh {
+        vertices: Vec::new(),
+        indices: Vec::new(),
+    };
+    let mut liquid = Mesh {
+        vertices: Vec::new(),
+        indices: Vec::new(),
+    };
+    let mut decorations = Vec::new();
+    let mut blocks = BlockSample::new();
+    let min_corner = coord.min_block();
+    world.sample_box(
+        min_corner - IVec3::ONE,
+        min_corner + IVec3::splat(CHUNK_SIZE as i32),
+        &mut blocks,
+    );
+    let surroundings = Surroundings {
+        blocks: &blocks,
+        heightmap,
+        ao_strength,
+    };
+
+    for local in LocalPos::all() {
+        let Some(kind) = solid_kind(chunk.get(local)) else {
+            continue;
+        };
+        let world_position = coord.block(local);
+        let block = BlockPosition {
+            world: world_position,
+            origin: local.as_ivec3().as_vec3().to_array(),
+            biome_tint: true,
+        };
+        let mesh = if kind.is_liquid() {
+            &mut liquid
+        } else {
+            &mut opaque
+        };
+        match kind.definition().shape {
+            BlockShape::Cube | BlockShape::Liquid => add_block_faces(
+                Some(surroundings),
+                tiles,
+                kind,
+                block,
+                &mut mesh.vertices,
+                &mut mesh.indices,
+            ),
+            BlockShape::Cross => {
+                let cell = blocks.get(world_position);
+                let sky = light::brightness(cell.sky_light)
+                    * heightmap.sky_light(world_position.to_array());
+                decorations.push(Decoration {
+                    kind,
+                    position: world_position.to_array(),
+                    light: sky.max(light::brightness(cell.block_light)),
+                })
+            }
+        }
+    }
+
+    ChunkMesh {
+        opaque,
+        liquid,
+        decorations,
+    }
+}
+
+/// Meshes one block of `kind` with its minimum corner at the origin and
+/// every face drawn.
+pub fn build_instance_mesh(kind: BlockKind, tiles: &BlockTiles) -> InstanceMesh {
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    let block = BlockPosition {
+        world: IVec3::ZERO,
+        origin: [0.0; 3],
+        biome_tint: false,
+    };
+    // Both emitters write four vertices per face, in `FACES` order for cubes.
+    let tinted = match kind.definition().shape {
+        BlockShape::Cube | BlockShape::Liquid => {
+            add_block_faces(None, tiles, kind, block, &mut vertices, &mut indices);
+            FACES
+                .iter()
+                .flat_map(|face| [kind.tint_for_face(face.direction) != BiomeTint::None; 4])
+                .collect()
+        }
+        BlockShape::Cross => {
+            add_cross_quads(tiles, kind, block, &mut vertices, &mut indices);
+            let tinted = kind.tint_for_face(FaceDirection::NegX) != BiomeTint::None;
+            vec![tinted; vertices.len()]
+        }
+    };
+    InstanceMesh {
+        mesh: Mesh { vertices, indices },
+        tinted,
+    }
+}
+
+/// Meshes blocks that are between cells with every face drawn, since the
+/// world's neighbours do not line up with them. Positions are relative to
+/// `render_origin`.
+pub fn build_moving_block_mesh(
+    blocks: &[MovingBlock],
+    tiles: &BlockTiles,
+    render_origin: IVec3,
+) -> Mesh {
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    // Match the rasterizer's chunk placement (see `ChunkPos::origin`).
+    let render_offset = Vec3::new(-(CHUNK_SIZE as f32) / 2.0, 0.0, -(CHUNK_SIZE as f32) / 2.0)
+        - render_origin.as_vec3();
+    for moving in blocks {
+        let origin = moving.position + render_offset;
+        let block = BlockPosition {
+            world: moving.position.round().as_ivec3(),
+            origin: origin.to_array(),
+            biome_tint: true,
+        };
+        let first_vertex = vertices.len();
+        match moving.kind.definition().shape {
+            BlockShape::Cube | BlockShape::Liquid => {
+                add_block_faces(None, tiles, moving.kind, block, &mut vertices, &mut indices)
+            }
+            BlockShape::Cross => {
+                add_cross_quads(tiles, moving.kind, block, &mut vertices, &mut indices)
+            }
+        }
+        if moving.scale != 1.0 {
+            for vertex in &mut vertices[first_vertex..] {
+                let position = Vec3::from_array(vertex.position);
+                vertex.position = (origin + (position - origin) * moving.scale).to_array();
+            }
+        }
+    }
+    Mesh { vertices, indices }
+}
+
+impl BlockPosition {
+    fn tint(self, tint: BiomeTint) -> [f32; 3] {
+        if self.biome_tint {
+            biome::tint_at(tint, self.world.x, self.world.z)
+        } else {
+            [1.0; 3]
+        }
+    }
+}
+
+fn solid_kind(id: BlockId) -> Option<BlockKind> {
+    let kind = BlockKind::from_id(id);
+    if kind.is_solid() { Some(kind) } else { None }
+}
+
+/// Faces pressed against a full block of the world, or liquid against liquid
+/// at least as deep, are culled, and the rest are lit per corner by the sky and
+/// block light around it (see `corner_light`) and occluded by the blocks
+/// beside it (see `corner_occlusion`); with no world every face is emitted
+/// unoccluded in full sky light.
+fn add_block_faces(
+    surroundings: Option<Surroundings>,
+    tiles: &BlockTiles,
+    kind: BlockKind,
+    block: BlockPosition,
+    vertices: &mut Vec<MeshVertex>,
+    indices: &mut Vec<u32>,
+) {
+    let height = match surroundings {
+        Some(Surroundings { blocks, .. }) if kind.is_liquid() => {
+            liquid_height(blocks, block.world, kind)
+        }
+        _ => kind.height(),
+    };
+    for face in FACES.iter() {
+        let normal = face.direction.normal();
+        let neighbor_world = block.world + normal;
+
+        let neighbor = surroundings.map_or(BlockKind::Air, |Surroundings { blocks, .. }| {
+            blocks.get(neighbor_world).kind
+        });
+
+        let alpha_cutoff = if kind.definition().cutout {
+            CUTOUT_ALPHA
+        } else {
+            0.0
+        };
+        // A partial block's top face sits below the cell boundary, so it stays
+        // visible even under a full block.
+        let culled = (neighbor.is_full_cube()
+            && !(face.direction == FaceDirection::PosY && height < 1.0))
+            || (kind.is_liquid()
+                && neighbor.is_liquid()
+                && (normal.y != 0
+                    || surroundings.is_some_and(|Surroundings { blocks, .. }| {
+                        liquid_height(blocks, neighbor_world, neighbor) >= height
+                    })));
+        if !culled {
+            let tile = tiles.rect(kind, face.direction);
+            let occlusion = surroundings.map_or(1.0, |Surroundings { heightmap, .. }| {
+                heightmap.sky_light(neighbor_world.to_array())
+            });
+            let tint = block.tint(kind.tint_for_face(face.direction));
+            let shade = face.vertices.map(|corner| {
+                surroundings.map_or(
+                    1.0,
+                    |Surroundings {
+                         blocks,
+                         ao_strength,
+                         ..
+                     }| {
+                        let cells = corner_cells(neighbor_world, face, corner);
+                        1.0 - ao_strength * corner_occlusion(blocks, cells)
+                    },
+                )
+            });
+
+            let base_index = vertices.len() as u32;
+            for ((corner, uv), shade) in face.vertices.iter().zip(face.uvs.iter()).zip(shade) {
+                let position = [
+                    block.origin[0] + corner[0],
+                    block.origin[1] + corner[1] * height,
+                    block.origin[2] + corner[2],
+                ];
+                let (sky_light, block_light) =
+                    surroundings.map_or((1.0, 0.0), |Surroundings { blocks, .. }| {
+                        let cells = corner_cells(neighbor_world, face, *corner);
+                        let (sky, block) = corner_light(blocks, cells);
+                        (sky * occlusion, block)
+                    });
+                // Side faces crop the tile rather than squashing it.
+                let uv = if normal.y == 0 {
+                    [uv[0], uv[1] * height]
+                } else {
+                    *uv
+                };
+                vertices.push(MeshVertex {
+                    position,
+                    color: tint.map(|channel| channel * face.light * shade),
+                    sky_light,
+                    block_light,
+                    uv,
+                    tile,
+                    alpha_cutoff,
+                });
+            }
+
+            // Corners 0 and 3 are opposite. Splitting the quad along the
+            // diagonal through the darker pair would smear their shade
+            // across both triangles, so it is split along the lighter one.
+            let quad = if shade[0] + shade[3] > shade[1] + shade[2] {
+                [0, 1, 3, 0, 3, 2]
+            } else {
+                [0, 1, 2, 2, 1, 3]
+            };
+            indices.extend(quad.map(|corner| base_index + corner));
+        }
+    }
+}
+
+/// Height of the liquid surface in `cell`: the whole cell under more liquid,
+/// and otherwise below its top by `LIQUID_SURFACE`, so shores and pools do
+/// not look filled to the brim.
+fn liquid_height(blocks: &BlockSample, cell: IVec3, kind: BlockKind) -> f32 {
+    if blocks.get(cell + IVec3::Y).kind.is_liquid() {
+        1.0
+    } else {
+        kind.height() * LIQUID_SURFACE
+    }
+}
+
+/// The four cells sharing a face's `corner` on its outer side: `outer`, the
+/// cell the face looks into, its two neighbours towards the corner, and the
+/// one diagonally across from it.
+fn corner_cells(outer: IVec3, face: &Face, corner: [f32; 3]) -> [IVec3; 4] {
+    // Towards the corner along each axis the face spans.
+    let mut steps = [IVec3::ZERO; 2];
+    let normal = face.direction.normal();
+    let mut axes = (0..3).filter(|&axis| normal[axis] == 0);
+    for step in &mut steps {
+        let axis = axes.next().expect("faces span two axes");
+        step[axis] = if corner[axis] > 0.5 { 1 } else { -1 };
+    }
+    [
+        outer,
+        outer + steps[0],
+        outer + steps[1],
+        outer + steps[0] + steps[1],
+    ]
+}
+
+/// Sky and block light brightness at one corner of a face: the mean over the
+/// `corner_cells`. Cells that stop light are left out, except the one the
+/// face looks into, and so is the diagonal one when walls on both sides cut
+/// it off; light then fades smoothly across faces instead of stepping at
+/// their edges.
+fn corner_light(blocks: &BlockSample, cells: [IVec3; 4]) -> (f32, f32) {
+    let cells = cells.map(|cell| blocks.get(cell));
+    let blocked = cells.map(|cell| light::blocks_light(cell.kind));
+    let mut sky = 0.0;
+    let mut block = 0.0;
+    let mut count = 0.0;
+    for (index, cell) in cells.into_iter().enumerate() {
+        let cut_off = match index {
+            0 => false,
+            3 => blocked[3] || (blocked[1] && blocked[2]),
+            _ => blocked[index],
+        };
+        if cut_off {
+            continue;
+        }
+        sky += light::brightness(cell.sky_light);
+        block += light::brightness(cell.block_light);
+        count += 1.0;
+    }
+    (sky / count, block / count)
+}
+
+/// How much (0..=0.75) of the light the full cubes among the `corner_cells`
+/// take from a face's corner: a quarter for each of the side and diagonal
+/// cells, and the most when both sides are walls, however open the diagonal.
+fn corner_occlusion(blocks: &BlockSample, cells: [IVec3; 4]) -> f32 {
+    let [_, side_a, side_b, diagonal] = cells.map(|cell| blocks.get(cell).kind.is_full_cube());
+    let occluders = if side_a && side_b {
+        3
+    } else {
+        side_a as u8 + side_b as u8 + diagonal as u8
+    };
+    occluders as f32 * 0.25
+}
+
+/// Share of a liquid's height its open surface is drawn at.
+pub const LIQUID_SURFACE: f32 = 0.875;
+/// Alpha cutoff for `cutout` blocks, whose background texels are fully transparent.
+const CUTOUT_ALPHA: f32 = 0.5;
+/// Plants are not shaded by face direction, only slightly darkened overall.
+const CROSS_SHADE: f32 = 0.9;
+
+/// Emits the two diagonal quads of a cross-shaped block. The raster pipeline
+/// does not cull back faces, so one quad per diagonal is visible from both sides.
+fn add_cross_quads(
+    tiles: &BlockTiles,
+    kind: BlockKind,
+    block: BlockPosition,
+    vertices: &mut Vec<MeshVertex>,
+    indices: &mut Vec<u32>,
+) {
+    let tile = tiles.rect(kind, FaceDirection::NegX);
+    let tint = block.tint(kind.tint_for_face(FaceDirection::NegX));
+    let color = tint.map(|channel| channel * CROSS_SHADE);
+    let diagonals = [[[0.0, 0.0], [1.0, 1.0]], [[0.0, 1.0], [1.0, 0.0]]];
+    for [start, end] in diagonals {
+        let base_index = vertices.len() as u32;
+        for (x, z, u) in [(start[0], start[1], 0.0), (end[0], end[1], 1.0)] {
+            for y in [0.0, 1.0] {
+                vertices.push(MeshVertex {
+                    position: [
+                        block.origin[0] + x,
+                        block.origin[1] + y,
+                        block.origin[2] + z,
+                    ],
+                    color,
+                    sky_light: 1.0,
+                    block_light: 0.0,
+                    // Image rows run top-down, so v grows towards the ground.
+                    uv: [u, 1.0 - y],
+                    tile,
+                    alpha_cutoff: CUTOUT_ALPHA,
+                });
+            }
+        }
+        indices.extend_from_slice(&[
+            base_index,
+            base_index + 1,
+            base_index + 2,
+            base_index + 2,
+            base_index + 1,
+            base_index + 3,
+        ]);
+    }
+}
+
+struct Face {
+    vertices: [[f32; 3]; 4],
+    uvs: [[f32; 2]; 4],
+    direction: FaceDirection,
+    light: f32,
+}
+
+impl Face {
+    const fn new(
+        vertices: [[f32; 3]; 4],
+        uvs: [[f32; 2]; 4],
+        direction: FaceDirection,
+        light: f32,
+    ) -> Self {
+        Self {
+            vertices,
+            uvs,
+            direction,
+            light,
+        }
+    }
+}
+
+const FACES: [Face; 6] = [
+    Face::new(
+        [
+            [0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [1.0, 1.0, 0.0],
+        ],
+        [[0.0, 0.0], [0.0, 1.0], [1.0, 0.0], [1.0, 1.0]],
+        FaceDirection::NegZ,
+        0.85,
+    ),
+    Face::new(
+        [
+            [0.0, 0.0, 1.0],
+            [1.0, 0.0, 1.0],
+            [0.0, 1.0, 1.0],
+            [1.0, 1.0, 1.0],
+        ],
+        [[0.0, 0.0], [1.0, 0.0], [0.0, 1.0], [1.0, 1.0]],
+        FaceDirection::PosZ,
+        0.85,
+    ),
+    Face::new(
+        [
+            [0.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0],
+            [0.0, 1.0, 0.0],
+            [0.0, 1.0, 1.0],
+        ],
+        [[1.0, 0.0], [0.0, 0.0], [1.0, 1.0], [0.0, 1.0]],
+        FaceDirection::NegX,
+        0.75,
+    ),
+    Face::new(
+        [
+            [1.0, 0.0, 0.0],
+            [1.0, 1.0, 0.0],
+            [1.0, 0.0, 1.0],
+            [1.0, 1.0, 1.0],
+        ],
+        [[0.0, 0.0], [0.0, 1.0], [1.0, 0.0], [1.0, 1.0]],
+        FaceDirection::PosX,
+        0.75,
+    ),
+    Face::new(
+        [
+            [0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0],
+            [1.0, 0.0, 1.0],
+        ],
+        [[0.0, 1.0], [1.0, 1.0], [0.0, 0.0], [1.0, 0.0]],
+        FaceDirection::NegY,
+        0.6,
+    ),
+    Face::new(
+        [
+            [0.0, 1.0, 0.0],
+            [0.0, 1.0, 1.0],
+            [1.0, 1.0, 0.0],
+            [1.0, 1.0, 1.0],
+        ],
+        [[0.0, 0.0], [0.0, 1.0], [1.0, 0.0], [1.0, 1.0]],
+        FaceDirection::PosY,
+        1.0,
+    ),
+];
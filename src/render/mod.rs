@@ -1,11 +1,51 @@
+mod chunk_uniforms;
+mod culling;
+mod depth_resolve;
+mod far_terrain;
+mod instancing;
+mod labels;
+mod light_shafts;
 mod mesh;
+mod moving_blocks;
+mod origin;
+mod output;
+mod picture_in_picture;
+mod pipelines;
 mod raster;
 mod raytrace;
+mod readback;
+mod registry;
+mod shadows;
+mod sky_dome;
+mod sky_occlusion;
+mod target;
+mod timestamps;
+mod traversal;
+mod water;
 
-pub use raster::RasterRenderer;
-pub use raytrace::RayTraceRenderer;
+pub use labels::{debug_group, labeled_view, pass_debug_group};
+pub use origin::{rebase_origin, relative_camera};
+pub use output::{choose_surface_format, surface_format_choices, surface_takes_linear};
+pub use picture_in_picture::PictureInPicture;
+pub use pipelines::PipelineCache;
+use raster::RasterRenderer;
+pub use raster::msaa_sample_count;
+use raytrace::RayTraceRenderer;
+pub use readback::{ReadbackPoll, TexelImage, TextureReadback};
+pub use registry::{
+    DEFAULT_RENDERER, RENDERERS, RendererContext, RendererEntry, find_renderer, next_renderer,
+    renderer_names,
+};
+pub use target::{CameraBinding, RenderTarget, RenderTexture};
+pub use traversal::TraversalCounts;
 
+use glam::IVec3;
+
+use crate::block::BlockDefinition;
 use crate::camera::{Camera, Projection};
+use crate::config::{LightingSettings, WaterQuality};
+use crate::sky::SkyLight;
+use crate::ticking::MovingBlock;
 use crate::world::World;
 
 #[derive(Clone, Copy, Debug, Default)]
@@ -17,10 +57,26 @@ pub struct RenderTimings {
     pub present_ms: f32,
     pub gpu_compute_ms: f32,
     pub gpu_present_ms: f32,
+    /// Voxels uploaded to the ray tracer, counting whole chunk bricks.
     pub voxels: u32,
     pub solid_blocks: u32,
+    /// Chunks out from the camera the ray tracer traces, when the loaded
+    /// world is too big for its buffers; `None` when it traces all of it.
+    pub traced_radius: Option<i32>,
+    /// Chunk meshes inside the camera frustum, for the rasterizer.
+    pub drawn_chunks: u32,
+    /// Chunk meshes skipped as outside the camera frustum, for the rasterizer.
+    pub culled_chunks: u32,
+    /// Path-traced samples accumulated into the current image, 0 when path
+    /// tracing is off.
+    pub samples: u32,
+    /// How far the ray tracer's rays walked the voxel grid in a recent
+    /// frame, once one has been read back.
+    pub traversal: Option<TraversalCounts>,
 }
 
+/// The family a renderer belongs to, which decides what its timings hold
+/// and which settings apply to it.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum RendererKind {
     Rasterized,
@@ -36,14 +92,65 @@ impl RendererKind {
     }
 }
 
+/// Compiles every renderer's pipelines for `surface_format` and
+/// `msaa_samples` so later renderer construction or switching never blocks
+/// on shader compilation.
+pub fn warm_up_pipelines(
+    device: &wgpu::Device,
+    cache: &PipelineCache,
+    surface_format: wgpu::TextureFormat,
+    msaa_samples: u32,
+    camera_bind_group_layout: &wgpu::BindGroupLayout,
+) {
+    for entry in RENDERERS {
+        (entry.warm_up)(
+            device,
+            cache,
+            surface_format,
+            msaa_samples,
+            camera_bind_group_layout,
+        );
+    }
+}
+
 pub struct FrameContext<'a> {
     pub device: &'a wgpu::Device,
     pub queue: &'a wgpu::Queue,
-    pub surface_config: &'a wgpu::SurfaceConfiguration,
+    /// Whether this is the window's own view. Only it syncs per-frame state
+    /// such as moving blocks and records timings; other views, drawn before
+    /// it, reuse the previous frame's.
+    pub primary: bool,
     pub world: &'a World,
+    /// World block position the frame is drawn relative to; see
+    /// [`rebase_origin`]. Everything the renderer places, it places relative
+    /// to this.
+    pub origin: IVec3,
+    /// Positioned relative to `origin`.
     pub camera: &'a Camera,
     pub projection: &'a Projection,
-    pub camera_bind_group: &'a wgpu::BindGroup,
+    /// Must hold `camera` and `projection`.
+    pub camera_binding: &'a CameraBinding,
+    pub gamma: f32,
+    pub light_shafts: bool,
+    /// Whether the ray tracer accumulates path-traced samples.
+    pub path_tracing: bool,
+    /// Most reflection or refraction bounces the ray tracer follows a ray
+    /// through.
+    pub ray_bounces: u32,
+    /// How the rasterizer draws reflections in water.
+    pub water_quality: WaterQuality,
+    /// The direction direct light comes from and whether the rasterizer
+    /// shadows it.
+    pub lighting: LightingSettings,
+    /// The sky at this moment of the day: its colours and how it lights the
+    /// world.
+    pub sky: SkyLight,
+    /// Blocks sliding between cells and mob models, drawn on top of the voxel world.
+    pub moving_blocks: &'a [MovingBlock],
+    /// Dropped item models; many share a block, so the rasterizer batches them.
+    pub items: &'a [MovingBlock],
+    /// Seconds since the world was opened, for animated surfaces like water.
+    pub time: f32,
 }
 
 pub trait Renderer {
@@ -56,13 +163,22 @@ pub trait Renderer {
         config: &wgpu::SurfaceConfiguration,
     );
 
+    /// Format of the textures [`render`](Self::render) draws into; the
+    /// surface format it was created or last resized with.
+    fn output_format(&self) -> wgpu::TextureFormat;
+
+    /// Draws `ctx.camera`'s view into `target`, which may be any size.
     fn render(
         &mut self,
         encoder: &mut wgpu::CommandEncoder,
-        output_view: &wgpu::TextureView,
+        target: RenderTarget,
         ctx: &FrameContext,
     );
 
+    /// Replaces the material values and face tiles used for shading,
+    /// indexed by block id.
+    fn update_materials(&mut self, _queue: &wgpu::Queue, _definitions: &[BlockDefinition]) {}
+
     #[allow(dead_code)]
     fn timings(&self) -> Option<RenderTimings> {
         None
@@ -0,0 +1,69 @@
+use glam::IVec3;
+use wgpu::util::DeviceExt;
+
+use crate::render::mesh::{self, BlockTiles, MeshVertex};
+use crate::ticking::MovingBlock;
+
+/// Per-frame mesh for blocks sliding between cells.
+///
+/// Motions only last a few ticks and change every frame, so the buffers are
+/// simply rebuilt each frame while anything is moving.
+pub struct MovingBlockMesh {
+    vertex_buffer: Option<wgpu::Buffer>,
+    index_buffer: Option<wgpu::Buffer>,
+    index_count: u32,
+}
+
+impl MovingBlockMesh {
+    pub fn new() -> Self {
+        Self {
+            vertex_buffer: None,
+            index_buffer: None,
+            index_count: 0,
+        }
+    }
+
+    pub fn sync<V: bytemuck::Pod>(
+        &mut self,
+        device: &wgpu::Device,
+        blocks: &[MovingBlock],
+        tiles: &BlockTiles,
+        render_origin: IVec3,
+        to_vertex: impl Fn(MeshVertex) -> V,
+    ) {
+        let mesh = mesh::build_moving_block_mesh(blocks, tiles, render_origin);
+        self.index_count = mesh.indices.len() as u32;
+        if mesh.indices.is_empty() {
+            self.vertex_buffer = None;
+            self.index_buffer = None;
+            return;
+        }
+
+        let vertices: Vec<V> = mesh.vertices.into_iter().map(to_vertex).collect();
+        self.vertex_buffer = Some(
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Moving block vertex buffer"),
+                contents: bytemuck::cast_slice(&vertices),
+                usage: wgpu::BufferUsages::VERTEX,
+            }),
+        );
+        self.index_buffer = Some(
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Moving block index buffer"),
+                contents: bytemuck::cast_slice(&mesh.indices),
+                usage: wgpu::BufferUsages::INDEX,
+            }),
+        );
+    }
+
+    /// Draws with whatever world pipeline and bind groups are already set.
+    pub fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        let (Some(vertex_buffer), Some(index_buffer)) = (&self.vertex_buffer, &self.index_buffer)
+        else {
+            return;
+        };
+        render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+        render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+        render_pass.draw_indexed(0..self.index_count, 0, 0..1);
+    }
+}
@@ -0,0 +1,59 @@
+//! The point the renderers draw the world relative to.
+//!
+//! An `f32` only resolves 1/16 of a block a million blocks from zero, so
+//! vertices placed in world space jitter and tear far from spawn. Instead
+//! every position handed to the GPU is relative to a render origin near the
+//! camera, and the camera itself is placed relative to it. The origin stays
+//! put until the camera has travelled [`REBASE_DISTANCE`] away from it, so
+//! the renderers only re-place their chunks once in a while. It only moves
+//! horizontally: the world is a few chunks tall, and water planes and sun
+//! shadows stay at their world heights. Effects keyed on position, such as
+//! water ripples, read relative positions and jump when the origin moves.
+
+use glam::{IVec3, Vec3};
+
+use crate::camera::Camera;
+
+/// How far the camera may get from the render origin, along x or z, before
+/// the origin moves; also the grid the origin snaps to.
+pub const REBASE_DISTANCE: i32 = 1024;
+
+/// The render origin for a camera at `position`: `origin` while the camera
+/// is within [`REBASE_DISTANCE`] of it, and otherwise the nearest grid point
+/// to the camera.
+pub fn rebase_origin(origin: IVec3, position: Vec3) -> IVec3 {
+    let offset = position - origin.as_vec3();
+    if offset.x.abs() <= REBASE_DISTANCE as f32 && offset.z.abs() <= REBASE_DISTANCE as f32 {
+        return origin;
+    }
+    let snap = |axis: f32| (axis / REBASE_DISTANCE as f32).round() as i32 * REBASE_DISTANCE;
+    IVec3::new(snap(position.x), 0, snap(position.z))
+}
+
+/// `camera` positioned relative to `origin`, for the renderers.
+pub fn relative_camera(camera: &Camera, origin: IVec3) -> Camera {
+    Camera {
+        position: camera.position - origin.as_vec3(),
+        ..camera.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn origin_only_moves_once_the_camera_is_far_from_it() {
+        let origin = IVec3::ZERO;
+        let near = Vec3::new(1000.0, 80.0, -1000.0);
+        assert_eq!(rebase_origin(origin, near), origin);
+
+        let far = Vec3::new(3_000_000.5, 80.0, -1030.0);
+        let moved = rebase_origin(origin, far);
+        assert_eq!(moved, IVec3::new(3_000_320, 0, -1024));
+        assert_eq!(rebase_origin(moved, far), moved);
+        let relative = far - moved.as_vec3();
+        assert!(relative.x.abs() <= REBASE_DISTANCE as f32);
+        assert!(relative.z.abs() <= REBASE_DISTANCE as f32);
+    }
+}
@@ -0,0 +1,102 @@
+use bytemuck::{Pod, Zeroable};
+
+/// Parameters for the final linear-to-display encode, shared by every pass
+/// that writes shaded color to the surface.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct OutputUniform {
+    gamma: f32,
+    encode_srgb: u32,
+    hdr: u32,
+    _padding: u32,
+}
+
+impl OutputUniform {
+    /// When the surface takes linear color the shader only applies the
+    /// gamma adjustment; an HDR surface also keeps values above 1.0.
+    pub fn new(gamma: f32, surface_format: wgpu::TextureFormat) -> Self {
+        Self {
+            gamma,
+            encode_srgb: u32::from(!surface_takes_linear(surface_format)),
+            hdr: u32::from(is_hdr_format(surface_format)),
+            _padding: 0,
+        }
+    }
+}
+
+/// Whether `format` is the HDR surface format, which displays linear,
+/// extended-range color (scRGB): 1.0 is SDR white and larger values are
+/// brighter.
+pub fn is_hdr_format(format: wgpu::TextureFormat) -> bool {
+    format == wgpu::TextureFormat::Rgba16Float
+}
+
+/// The surface format to present in, out of the surface's `formats` (most
+/// preferred first): the HDR one when `hdr_output` asks for it and there is
+/// one, and otherwise the first SDR format.
+///
+/// Shading happens in linear light and the final pass encodes explicitly, so
+/// a non-sRGB SDR format is preferred; an sRGB one still works via hardware
+/// encode.
+pub fn choose_surface_format(
+    formats: &[wgpu::TextureFormat],
+    hdr_output: bool,
+) -> wgpu::TextureFormat {
+    let (sdr_format, hdr_format) = surface_format_choices(formats);
+    if !hdr_output {
+        return sdr_format;
+    }
+    match hdr_format {
+        Some(format) => format,
+        None => {
+            log::warn!("hdr_output is on, but the surface offers no HDR format; using SDR");
+            sdr_format
+        }
+    }
+}
+
+/// The SDR format and, when the surface offers one, the HDR format that
+/// [`choose_surface_format`] picks between.
+pub fn surface_format_choices(
+    formats: &[wgpu::TextureFormat],
+) -> (wgpu::TextureFormat, Option<wgpu::TextureFormat>) {
+    let sdr = formats
+        .iter()
+        .copied()
+        .filter(|&format| !is_hdr_format(format));
+    let sdr_format = sdr
+        .clone()
+        .find(|format| !format.is_srgb())
+        .or_else(|| sdr.clone().next())
+        .unwrap_or(formats[0]);
+    let hdr_format = formats
+        .iter()
+        .copied()
+        .find(|&format| is_hdr_format(format));
+    (sdr_format, hdr_format)
+}
+
+/// Whether color written to a surface of `format` stays linear, because the
+/// hardware encodes it (sRGB formats) or the display reads it as linear (HDR).
+/// Otherwise shaders encode it to sRGB themselves.
+pub fn surface_takes_linear(format: wgpu::TextureFormat) -> bool {
+    format.is_srgb() || is_hdr_format(format)
+}
+
+#[cfg(test)]
+mod tests {
+    use wgpu::TextureFormat::{Bgra8Unorm, Bgra8UnormSrgb, Rgba16Float};
+
+    use super::*;
+
+    #[test]
+    fn hdr_formats_are_only_picked_when_asked_for() {
+        let formats = [Rgba16Float, Bgra8UnormSrgb, Bgra8Unorm];
+        assert_eq!(choose_surface_format(&formats, false), Bgra8Unorm);
+        assert_eq!(choose_surface_format(&formats, true), Rgba16Float);
+        // Without a linear SDR format, the sRGB one beats HDR.
+        assert_eq!(choose_surface_format(&formats[..2], false), Bgra8UnormSrgb);
+        assert_eq!(choose_surface_format(&formats[1..], true), Bgra8Unorm);
+        assert_eq!(choose_surface_format(&formats[..1], false), Rgba16Float);
+    }
+}
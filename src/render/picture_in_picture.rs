@@ -0,0 +1,201 @@
+use crate::camera::{Camera, Projection};
+use crate::render::{CameraBinding, RenderTarget, RenderTexture};
+
+/// Resolution of the inset, drawn one to one in the window corner. Must
+/// match `INSET_SIZE` in `picture_in_picture.wgsl`.
+const INSET_SIZE: (u32, u32) = (320, 180);
+/// Frame width in pixels; must match `BORDER` in `picture_in_picture.wgsl`.
+const BORDER: u32 = 2;
+/// Gap between the frame and the window's top-right corner.
+const MARGIN: u32 = 16;
+const FOVY: f32 = 70.0;
+
+/// A second camera, rendered by the active renderer into a small texture
+/// before the main view and then drawn into the window's top-right corner.
+pub struct PictureInPicture {
+    texture: RenderTexture,
+    camera: CameraBinding,
+    projection: Projection,
+    bind_group: wgpu::BindGroup,
+    pipeline: wgpu::RenderPipeline,
+}
+
+impl PictureInPicture {
+    pub fn new(
+        device: &wgpu::Device,
+        surface_format: wgpu::TextureFormat,
+        camera_bind_group_layout: &wgpu::BindGroupLayout,
+        znear: f32,
+        zfar: f32,
+    ) -> Self {
+        let texture = RenderTexture::new(
+            device,
+            "Picture-in-picture texture",
+            INSET_SIZE,
+            surface_format,
+        );
+        let camera = CameraBinding::new(
+            device,
+            camera_bind_group_layout,
+            "Picture-in-picture camera",
+        );
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Picture-in-picture sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Nearest,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Picture-in-picture bind group layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Picture-in-picture bind group"),
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(texture.view()),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Picture-in-picture shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("picture_in_picture.wgsl").into()),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Picture-in-picture pipeline layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Picture-in-picture pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: surface_format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        Self {
+            texture,
+            camera,
+            projection: Projection::new(INSET_SIZE.0, INSET_SIZE.1, FOVY, znear, zfar),
+            bind_group,
+            pipeline,
+        }
+    }
+
+    /// Points the inset at `camera`. The write lands with the next submit,
+    /// so the inset must be submitted before the main view updates its own
+    /// uniforms.
+    pub fn set_camera(&self, queue: &wgpu::Queue, camera: &Camera) {
+        self.camera.update(queue, camera, &self.projection);
+    }
+
+    pub fn projection(&self) -> &Projection {
+        &self.projection
+    }
+
+    pub fn camera_binding(&self) -> &CameraBinding {
+        &self.camera
+    }
+
+    /// Where the inset view renders.
+    pub fn target(&self) -> RenderTarget<'_> {
+        self.texture.target()
+    }
+
+    /// Height of the top-right strip of a `viewport` sized window the
+    /// composited inset takes, its top margin included; 0 when it does not
+    /// fit.
+    pub fn covered_height(viewport: [u32; 2]) -> u32 {
+        let width = INSET_SIZE.0 + 2 * BORDER;
+        let height = INSET_SIZE.1 + 2 * BORDER;
+        if viewport[0] < width + 2 * MARGIN || viewport[1] < height + 2 * MARGIN {
+            return 0;
+        }
+        MARGIN + height
+    }
+
+    /// Draws the inset, framed, into the top-right corner of `view`. Skipped
+    /// when the window is too small to hold it.
+    pub fn composite(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        view: &wgpu::TextureView,
+        viewport: [u32; 2],
+    ) {
+        if Self::covered_height(viewport) == 0 {
+            return;
+        }
+        let width = INSET_SIZE.0 + 2 * BORDER;
+        let height = INSET_SIZE.1 + 2 * BORDER;
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Picture-in-picture pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: None,
+        });
+        render_pass.set_viewport(
+            (viewport[0] - MARGIN - width) as f32,
+            MARGIN as f32,
+            width as f32,
+            height as f32,
+            0.0,
+            1.0,
+        );
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
@@ -0,0 +1,39 @@
+// Draws the picture-in-picture inset, framed by a thin border. The viewport
+// covers the inset plus the border on every side.
+
+// Must match `INSET_SIZE` and `BORDER` in `picture_in_picture.rs`.
+const INSET_SIZE: vec2<f32> = vec2<f32>(320.0, 180.0);
+const BORDER: f32 = 2.0;
+const BORDER_COLOR: vec4<f32> = vec4<f32>(0.05, 0.05, 0.05, 1.0);
+
+@group(0) @binding(0)
+var inset_texture: texture_2d<f32>;
+
+@group(0) @binding(1)
+var inset_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    // Inset texture coordinates; outside 0..1 over the border.
+    @location(0) uv: vec2<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    // One triangle covering the viewport.
+    let corner = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    var out: VertexOutput;
+    out.position = vec4<f32>(corner * 2.0 - 1.0, 0.0, 1.0);
+    let framed = INSET_SIZE + 2.0 * BORDER;
+    let pixel = vec2<f32>(corner.x, 1.0 - corner.y) * framed;
+    out.uv = (pixel - BORDER) / INSET_SIZE;
+    return out;
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    // The inset is already encoded for the surface, so it is copied as is.
+    let color = textureSample(inset_texture, inset_sampler, input.uv);
+    let inside = all(input.uv >= vec2<f32>(0.0)) && all(input.uv <= vec2<f32>(1.0));
+    return select(BORDER_COLOR, color, inside);
+}
@@ -0,0 +1,44 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+type PipelineKey = (&'static str, Option<wgpu::TextureFormat>);
+
+/// Compiled pipelines and their layouts, shared by every renderer instance.
+///
+/// This is only an in-memory map from name and format to compiled objects,
+/// not a driver pipeline cache: wgpu 0.17 exposes none, so nothing here is
+/// written to disk and every launch compiles its shaders again. Within one
+/// run, recreating a renderer reuses the entries rather than recompiling.
+/// Cloning is cheap and every clone sees the same entries.
+#[derive(Clone, Default)]
+pub struct PipelineCache {
+    entries: Arc<Mutex<HashMap<PipelineKey, Arc<dyn Any + Send + Sync>>>>,
+}
+
+impl PipelineCache {
+    /// Returns the entry stored under `name`/`format`, building it on first use.
+    pub fn get_or_create<T, F>(
+        &self,
+        name: &'static str,
+        format: Option<wgpu::TextureFormat>,
+        create: F,
+    ) -> Arc<T>
+    where
+        T: Any + Send + Sync,
+        F: FnOnce() -> T,
+    {
+        let mut entries = self.entries.lock().expect("pipeline cache poisoned");
+        let entry = entries.entry((name, format)).or_insert_with(|| {
+            log::debug!("Compiling pipeline {name} ({format:?})");
+            Arc::new(create())
+        });
+        Arc::clone(entry)
+            .downcast::<T>()
+            .unwrap_or_else(|_| panic!("pipeline cache entry {name} has a different type"))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().expect("pipeline cache poisoned").len()
+    }
+}
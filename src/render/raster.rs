@@ -1,157 +1,336 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Instant;
+
+use glam::{IVec3, Vec3};
 use wgpu::util::DeviceExt;
 
-use crate::render::mesh;
-use crate::render::{FrameContext, Renderer, RendererKind};
-use crate::texture::{AtlasLayout, TextureAtlas};
+use crate::block::BlockDefinition;
+use crate::camera::{CameraUniform, Frustum};
+use crate::config::WaterQuality;
+use crate::coords::{BlockPos, ChunkPos};
+use crate::render::chunk_uniforms::{self, ChunkUniform, ChunkUniforms};
+use crate::render::culling::{self, ChunkBounds, ChunkCulling};
+use crate::render::depth_resolve;
+use crate::render::far_terrain::FarTerrain;
+use crate::render::instancing::{Instance, InstanceBatches, InstanceMeshes, InstanceVertex};
+use crate::render::labels::{self, debug_group, pass_debug_group};
+use crate::render::light_shafts::LightShaftUniform;
+use crate::render::mesh::{self, BlockTiles, Decoration, Mesh, MeshVertex};
+use crate::render::moving_blocks::MovingBlockMesh;
+use crate::render::output::OutputUniform;
+use crate::render::pipelines::PipelineCache;
+use crate::render::shadows::{self, ShadowMap};
+use crate::render::sky_dome::{self, SkyDome};
+use crate::render::sky_occlusion::Heightmap;
+use crate::render::timestamps::{GpuSpan, GpuTimer};
+use crate::render::water::{self, WaterTargets, WaterUniform};
+use crate::render::{FrameContext, RenderTarget, RenderTimings, Renderer, RendererKind};
+use crate::texture::TextureAtlas;
 use crate::world::World;
 
+/// Bind group index of the per-mesh `ChunkUniform` in the world and water shaders.
+const CHUNK_GROUP: u32 = 2;
+/// Bind group index of the water pass's own resources in `water.wgsl`.
+const WATER_GROUP: u32 = 3;
+/// Bind group index of the sun's shadow map in the world shader.
+const SHADOW_GROUP: u32 = 3;
+/// Linear HDR target the world is drawn into before the light shaft pass.
+const SCENE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+/// Sample counts `msaa_sample_count` tries, most to fewest.
+const MSAA_FALLBACKS: [u32; 4] = [8, 4, 2, 1];
+
+/// The most samples per pixel, up to `requested`, that both the scene's
+/// color and depth formats support on this device; the world pass draws
+/// with that many.
+pub fn msaa_sample_count(adapter: &wgpu::Adapter, device: &wgpu::Device, requested: u32) -> u32 {
+    // The device validates against the adapter's own format features only
+    // with this feature on or on a downlevel backend; otherwise against
+    // what WebGPU guarantees.
+    let adapter_specific = device
+        .features()
+        .contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES)
+        || !adapter.get_downlevel_capabilities().is_webgpu_compliant();
+    let flags = |format: wgpu::TextureFormat| {
+        if adapter_specific {
+            adapter.get_texture_format_features(format).flags
+        } else {
+            format.guaranteed_format_features(device.features()).flags
+        }
+    };
+    let (color, depth) = (flags(SCENE_FORMAT), flags(SceneTargets::DEPTH_FORMAT));
+    // Color is resolved by the render pass itself, so it must allow that too.
+    let supports = |count: u32| {
+        color.sample_count_supported(count)
+            && depth.sample_count_supported(count)
+            && (count == 1 || color.contains(wgpu::TextureFormatFeatureFlags::MULTISAMPLE_RESOLVE))
+    };
+    let count = MSAA_FALLBACKS
+        .into_iter()
+        .filter(|&count| count <= requested)
+        .find(|&count| supports(count))
+        .unwrap_or(1);
+    if count != requested {
+        log::warn!(
+            "msaa {} is not supported by this adapter; falling back to {}",
+            requested,
+            count
+        );
+    }
+    count
+}
+
 pub struct RasterRenderer {
-    pipeline: wgpu::RenderPipeline,
-    vertex_buffer: wgpu::Buffer,
-    index_buffer: wgpu::Buffer,
-    index_count: u32,
+    pipelines: Arc<RasterPipelines>,
+    geometry: WorldGeometry,
+    far_terrain: FarTerrain,
+    moving_blocks: MovingBlockMesh,
+    instance_meshes: InstanceMeshes,
+    /// Dropped items, rebuilt every frame like `moving_blocks`.
+    items: InstanceBatches,
     atlas_bind_group: wgpu::BindGroup,
-    depth_texture: DepthTexture,
+    output_buffer: wgpu::Buffer,
+    shaft_buffer: wgpu::Buffer,
+    post_sampler: wgpu::Sampler,
+    /// Size-dependent targets per output size, created on first use; the
+    /// window and a picture-in-picture inset each get their own.
+    targets: HashMap<(u32, u32), ViewTargets>,
+    water_buffer: wgpu::Buffer,
+    reflection_camera_buffer: wgpu::Buffer,
+    reflection_camera_bind_group: wgpu::BindGroup,
+    water_sampler: wgpu::Sampler,
+    shadow_map: ShadowMap,
+    sky_dome: SkyDome,
+    /// The light shaft pass for `surface_format`.
+    post_pipeline: Arc<wgpu::RenderPipeline>,
+    pipeline_cache: PipelineCache,
     surface_format: wgpu::TextureFormat,
-    atlas_layout: AtlasLayout,
-    chunk_count: usize,
+    /// Samples per pixel of the world pass; 1 draws straight into the
+    /// scene targets.
+    msaa_samples: u32,
+    block_tiles: BlockTiles,
+    /// Set when the block definitions changed, until every mesh is rebuilt
+    /// with them.
+    remesh: bool,
     world_version: u64,
+    last_timings: RenderTimings,
+    gpu_timer: Option<GpuTimer>,
 }
 
 impl RasterRenderer {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         device: &wgpu::Device,
-        _queue: &wgpu::Queue,
+        queue: &wgpu::Queue,
         config: &wgpu::SurfaceConfiguration,
         world: &World,
         atlas: &TextureAtlas,
         camera_bind_group_layout: &wgpu::BindGroupLayout,
+        cache: &PipelineCache,
+        ao_strength: f32,
+        msaa_samples: u32,
     ) -> Self {
         let surface_format = config.format;
+        let pipelines = Self::pipelines(device, cache, msaa_samples, camera_bind_group_layout);
+        let post_pipeline =
+            Self::post_pipeline(device, cache, &pipelines, msaa_samples, surface_format);
 
-        let atlas_layout = atlas.layout();
-        let (vertex_data, index_data) = build_world_geometry(world, &atlas_layout);
+        let block_tiles = BlockTiles::new(atlas.layout());
+        let geometry = WorldGeometry::new(device, &pipelines, world, &block_tiles, ao_strength);
 
-        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Terrain vertex buffer"),
-            contents: bytemuck::cast_slice(&vertex_data),
-            usage: wgpu::BufferUsages::VERTEX,
-        });
+        let instance_meshes = InstanceMeshes::new(device, &block_tiles);
+        let atlas_bind_group =
+            atlas.create_bind_group(device, &pipelines.texture_bind_group_layout);
 
-        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Terrain index buffer"),
-            contents: bytemuck::cast_slice(&index_data),
-            usage: wgpu::BufferUsages::INDEX,
+        let output_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("World output buffer"),
+            size: std::mem::size_of::<OutputUniform>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
         });
 
-        let texture_bind_group_layout =
-            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                label: Some("Texture bind group layout"),
-                entries: &[
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 0,
-                        visibility: wgpu::ShaderStages::FRAGMENT,
-                        ty: wgpu::BindingType::Texture {
-                            multisampled: false,
-                            view_dimension: wgpu::TextureViewDimension::D2,
-                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                        },
-                        count: None,
-                    },
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 1,
-                        visibility: wgpu::ShaderStages::FRAGMENT,
-                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-                        count: None,
-                    },
-                ],
-            });
+        let shaft_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Light shaft buffer"),
+            size: std::mem::size_of::<LightShaftUniform>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
 
-        let atlas_bind_group = atlas.create_bind_group(device, &texture_bind_group_layout);
+        let post_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Light shaft scene sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Nearest,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
 
-        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("World shader"),
-            source: wgpu::ShaderSource::Wgsl(include_str!("../shader.wgsl").into()),
+        let water_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Water uniform buffer"),
+            size: std::mem::size_of::<WaterUniform>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
         });
 
-        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: Some("World pipeline layout"),
-            bind_group_layouts: &[camera_bind_group_layout, &texture_bind_group_layout],
-            push_constant_ranges: &[],
+        let reflection_camera_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Water reflection camera buffer"),
+            size: std::mem::size_of::<CameraUniform>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let reflection_camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Water reflection camera bind group"),
+            layout: camera_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: reflection_camera_buffer.as_entire_binding(),
+            }],
         });
 
-        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("World pipeline"),
-            layout: Some(&pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: "vs_main",
-                buffers: &[Vertex::buffer_layout()],
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                entry_point: "fs_main",
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: surface_format,
-                    blend: Some(wgpu::BlendState::REPLACE),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-            }),
-            primitive: wgpu::PrimitiveState::default(),
-            depth_stencil: Some(wgpu::DepthStencilState {
-                format: DepthTexture::FORMAT,
-                depth_write_enabled: true,
-                depth_compare: wgpu::CompareFunction::Less,
-                stencil: wgpu::StencilState::default(),
-                bias: wgpu::DepthBiasState::default(),
-            }),
-            multisample: wgpu::MultisampleState::default(),
-            multiview: None,
+        let water_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Water scene sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
         });
 
-        let depth_texture = DepthTexture::create(device, config);
+        let shadow_map = ShadowMap::new(
+            device,
+            camera_bind_group_layout,
+            &pipelines.shadow_bind_group_layout,
+        );
 
-        let index_count = index_data.len() as u32;
+        let sky_dome = SkyDome::new(device, &pipelines.sky_bind_group_layout);
 
         Self {
-            pipeline,
-            vertex_buffer,
-            index_buffer,
-            index_count,
+            pipelines,
+            geometry,
+            far_terrain: FarTerrain::new(),
+            moving_blocks: MovingBlockMesh::new(),
+            instance_meshes,
+            items: InstanceBatches::new(),
             atlas_bind_group,
-            depth_texture,
+            output_buffer,
+            shaft_buffer,
+            post_sampler,
+            targets: HashMap::new(),
+            water_buffer,
+            reflection_camera_buffer,
+            reflection_camera_bind_group,
+            water_sampler,
+            shadow_map,
+            sky_dome,
+            gpu_timer: GpuTimer::new(device, queue, "Raster"),
+            post_pipeline,
+            pipeline_cache: cache.clone(),
             surface_format,
-            atlas_layout,
-            chunk_count: world.chunk_count(),
+            msaa_samples,
+            block_tiles,
+            remesh: false,
             world_version: world.version(),
+            last_timings: RenderTimings::default(),
         }
     }
 }
 
 impl RasterRenderer {
-    fn sync_world(&mut self, device: &wgpu::Device, world: &World) {
-        let current_count = world.chunk_count();
-        let version = world.version();
-        if current_count == self.chunk_count && version == self.world_version {
-            return;
-        }
+    /// Compiles the raster pipelines for `surface_format` and `msaa_samples`
+    /// ahead of first use.
+    pub fn warm_up(
+        device: &wgpu::Device,
+        cache: &PipelineCache,
+        surface_format: wgpu::TextureFormat,
+        msaa_samples: u32,
+        camera_bind_group_layout: &wgpu::BindGroupLayout,
+    ) {
+        let pipelines = Self::pipelines(device, cache, msaa_samples, camera_bind_group_layout);
+        Self::post_pipeline(device, cache, &pipelines, msaa_samples, surface_format);
+    }
 
-        let (vertex_data, index_data) = build_world_geometry(world, &self.atlas_layout);
+    fn pipelines(
+        device: &wgpu::Device,
+        cache: &PipelineCache,
+        msaa_samples: u32,
+        camera_bind_group_layout: &wgpu::BindGroupLayout,
+    ) -> Arc<RasterPipelines> {
+        cache.get_or_create(pipeline_names(msaa_samples).0, None, || {
+            RasterPipelines::create(device, msaa_samples, camera_bind_group_layout)
+        })
+    }
 
-        self.vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Terrain vertex buffer"),
-            contents: bytemuck::cast_slice(&vertex_data),
-            usage: wgpu::BufferUsages::VERTEX,
-        });
+    fn post_pipeline(
+        device: &wgpu::Device,
+        cache: &PipelineCache,
+        pipelines: &RasterPipelines,
+        msaa_samples: u32,
+        surface_format: wgpu::TextureFormat,
+    ) -> Arc<wgpu::RenderPipeline> {
+        cache.get_or_create(pipeline_names(msaa_samples).1, Some(surface_format), || {
+            RasterPipelines::create_post(device, &pipelines.post_bind_group_layout, surface_format)
+        })
+    }
 
-        self.index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Terrain index buffer"),
-            contents: bytemuck::cast_slice(&index_data),
-            usage: wgpu::BufferUsages::INDEX,
-        });
+    fn ensure_targets(&mut self, device: &wgpu::Device, size: (u32, u32)) {
+        if self.targets.contains_key(&size) {
+            return;
+        }
+        let scene = SceneTargets::create(
+            device,
+            size,
+            self.msaa_samples,
+            &self.pipelines.depth_resolve_bind_group_layout,
+            &self.pipelines.post_bind_group_layout,
+            &self.post_sampler,
+            &self.output_buffer,
+            &self.shaft_buffer,
+        );
+        let water = WaterTargets::create(
+            device,
+            size,
+            SCENE_FORMAT,
+            SceneTargets::DEPTH_FORMAT,
+            &self.pipelines.water_bind_group_layout,
+            &self.water_sampler,
+            &self.water_buffer,
+        );
+        self.targets.insert(size, ViewTargets { scene, water });
+    }
 
-        self.index_count = index_data.len() as u32;
-        self.chunk_count = current_count;
+    fn sync_world(
+        &mut self,
+        device: &wgpu::Device,
+        world: &World,
+        ao_strength: f32,
+        origin: IVec3,
+    ) {
+        let version = world.version();
+        if std::mem::take(&mut self.remesh) {
+            // Every mesh was built from the old definitions.
+            self.geometry.chunks.clear();
+            self.instance_meshes = InstanceMeshes::new(device, &self.block_tiles);
+            self.far_terrain = FarTerrain::new();
+        } else if version == self.world_version && ao_strength == self.geometry.ao_strength {
+            // A rebased origin only moves the chunks, never their meshes.
+            if origin != self.geometry.origin {
+                self.geometry.place(device, &self.pipelines, origin);
+            }
+            return;
+        }
+
+        self.geometry.sync(
+            device,
+            &self.pipelines,
+            world,
+            &self.block_tiles,
+            ao_strength,
+            origin,
+        );
         self.world_version = version;
     }
 }
@@ -167,68 +346,1072 @@ impl Renderer for RasterRenderer {
         _queue: &wgpu::Queue,
         config: &wgpu::SurfaceConfiguration,
     ) {
+        // Only the light shaft pass writes the surface, so it is the one
+        // pipeline a new format needs.
         self.surface_format = config.format;
-        self.depth_texture = DepthTexture::create(device, config);
+        self.post_pipeline = Self::post_pipeline(
+            device,
+            &self.pipeline_cache,
+            &self.pipelines,
+            self.msaa_samples,
+            self.surface_format,
+        );
+        // Targets of the old window size are dead weight now; every size
+        // still drawn is recreated on its next frame.
+        self.targets.clear();
+        self.ensure_targets(device, (config.width, config.height));
+    }
+
+    fn output_format(&self) -> wgpu::TextureFormat {
+        self.surface_format
     }
 
     fn render(
         &mut self,
         encoder: &mut wgpu::CommandEncoder,
-        output_view: &wgpu::TextureView,
+        target: RenderTarget,
         ctx: &FrameContext,
     ) {
-        self.sync_world(ctx.device, ctx.world);
+        let frame_start = Instant::now();
+        // Other views reuse what the window's view last synced, so a second
+        // camera elsewhere never drags the far terrain ring away.
+        if ctx.primary {
+            self.sync_world(ctx.device, ctx.world, ctx.lighting.ao_strength, ctx.origin);
+            self.far_terrain.sync(
+                ctx.device,
+                ctx.world,
+                ctx.camera.position + ctx.origin.as_vec3(),
+                ctx.projection.zfar,
+                ctx.origin,
+                &self.block_tiles,
+                Vertex::from,
+            );
+            self.moving_blocks.sync(
+                ctx.device,
+                ctx.moving_blocks,
+                &self.block_tiles,
+                ctx.origin,
+                Vertex::from,
+            );
+            let items = ctx
+                .items
+                .iter()
+                .map(|item| {
+                    let instance =
+                        Instance::new(item.kind, item.position, ctx.origin, item.scale, 1.0);
+                    (item.kind, instance)
+                })
+                .collect();
+            self.items.upload(ctx.device, "Item instance buffer", items);
+        }
+        self.ensure_targets(ctx.device, target.size);
+        let targets = &self.targets[&target.size];
+
+        let output = OutputUniform::new(ctx.gamma, self.surface_format);
+        ctx.queue
+            .write_buffer(&self.output_buffer, 0, bytemuck::bytes_of(&output));
+        let shafts = LightShaftUniform::new(
+            ctx.camera,
+            ctx.projection,
+            ctx.sky.sun,
+            ctx.light_shafts && ctx.sky.sun.y > 0.0,
+        );
+        ctx.queue
+            .write_buffer(&self.shaft_buffer, 0, bytemuck::bytes_of(&shafts));
 
+        // Only the window's view is timed, so the queries describe one frame.
+        if ctx.primary
+            && let Some(timer) = self.gpu_timer.as_mut()
+        {
+            timer.begin_frame(ctx.device);
+        }
+        let timer = self.gpu_timer.as_ref().filter(|_| ctx.primary);
+        if let Some(timer) = timer {
+            timer.start(encoder, GpuSpan::Compute);
+        }
+
+        let view_proj = ctx.projection.matrix() * ctx.camera.view_matrix();
+        let cull_stats = debug_group(encoder, "Chunk culling", |encoder| {
+            self.geometry.culling.cull(
+                ctx.queue,
+                encoder,
+                &self.pipelines.culling_pipeline,
+                &Frustum::from_view_proj(view_proj),
+            )
+        });
+
+        self.sky_dome
+            .update(ctx.queue, ctx.camera, ctx.projection, &ctx.sky);
+        if self
+            .shadow_map
+            .update(ctx.queue, &ctx.lighting, &ctx.sky, ctx.camera.position)
+        {
+            debug_group(encoder, "Shadow map", |encoder| {
+                self.draw_shadow_map(encoder)
+            });
+        }
+
+        let has_water = self.geometry.has_water();
+        if has_water {
+            let uniform = WaterUniform::new(
+                ctx.camera,
+                ctx.projection,
+                ctx.water_quality,
+                &ctx.sky,
+                ctx.time,
+                target.size,
+            );
+            ctx.queue
+                .write_buffer(&self.water_buffer, 0, bytemuck::bytes_of(&uniform));
+        }
+        if has_water && ctx.water_quality == WaterQuality::Planar {
+            let camera = water::reflection_camera(ctx.camera, ctx.projection);
+            ctx.queue.write_buffer(
+                &self.reflection_camera_buffer,
+                0,
+                bytemuck::bytes_of(&camera),
+            );
+            debug_group(encoder, "Water reflection", |encoder| {
+                self.draw_reflection(encoder, &targets.water, ctx.sky.horizon)
+            });
+        }
+
+        let (color_view, resolve_target) = targets.scene.color_attachment();
         let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: Some("World render pass"),
             color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view: output_view,
+                view: color_view,
+                resolve_target,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: targets.scene.drawn_depth_view(),
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(0.0),
+                    store: true,
+                }),
+                stencil_ops: None,
+            }),
+        });
+
+        render_pass.set_pipeline(&self.pipelines.sky_pipeline);
+        render_pass.set_bind_group(0, &self.sky_dome.bind_group, &[]);
+        pass_debug_group(&mut render_pass, "Sky", |pass| pass.draw(0..3, 0..1));
+        render_pass.set_pipeline(&self.pipelines.pipeline);
+        render_pass.set_bind_group(0, ctx.camera_binding.bind_group(), &[]);
+        render_pass.set_bind_group(1, &self.atlas_bind_group, &[]);
+        render_pass.set_bind_group(SHADOW_GROUP, &self.shadow_map.bind_group, &[]);
+        self.draw_world(&mut render_pass, true);
+        render_pass.set_pipeline(&self.pipelines.instanced_pipeline);
+        self.draw_instances(&mut render_pass);
+        drop(render_pass);
+        self.resolve_depth(encoder, &targets.scene);
+
+        if has_water {
+            debug_group(encoder, "Water", |encoder| {
+                self.draw_water(encoder, ctx, targets, target.size)
+            });
+            self.resolve_depth(encoder, &targets.scene);
+        }
+        if let Some(timer) = timer {
+            timer.end(encoder, GpuSpan::Compute);
+            timer.start(encoder, GpuSpan::Present);
+        }
+
+        let mut post_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Light shaft pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target.view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: None,
+        });
+        post_pass.set_pipeline(&self.post_pipeline);
+        post_pass.set_bind_group(0, &targets.scene.post_bind_group, &[]);
+        post_pass.draw(0..3, 0..1);
+        drop(post_pass);
+
+        if !ctx.primary {
+            return;
+        }
+        let gpu_times = self.gpu_timer.as_mut().and_then(|timer| {
+            timer.end(encoder, GpuSpan::Present);
+            timer.resolve(encoder);
+            timer.times()
+        });
+        self.last_timings = RenderTimings {
+            total_ms: frame_start.elapsed().as_secs_f32() * 1000.0,
+            gpu_compute_ms: gpu_times.map_or(0.0, |times| times.compute_ms),
+            gpu_present_ms: gpu_times.map_or(0.0, |times| times.present_ms),
+            drawn_chunks: cull_stats.drawn,
+            culled_chunks: cull_stats.culled,
+            ..RenderTimings::default()
+        };
+    }
+
+    fn update_materials(&mut self, _queue: &wgpu::Queue, definitions: &[BlockDefinition]) {
+        self.block_tiles.update(definitions);
+        self.remesh = true;
+    }
+
+    fn timings(&self) -> Option<RenderTimings> {
+        Some(self.last_timings)
+    }
+}
+
+impl RasterRenderer {
+    /// Renders the depth of the terrain and moving blocks as seen from the
+    /// sun. Decorations and items are too small to cast useful shadows.
+    fn draw_shadow_map(&self, encoder: &mut wgpu::CommandEncoder) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Shadow pass"),
+            color_attachments: &[],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &self.shadow_map.depth_view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(1.0),
+                    store: true,
+                }),
+                stencil_ops: None,
+            }),
+        });
+        render_pass.set_pipeline(&self.pipelines.shadow_pipeline);
+        render_pass.set_bind_group(0, &self.shadow_map.caster_bind_group, &[]);
+        render_pass.set_bind_group(1, &self.atlas_bind_group, &[]);
+        pass_debug_group(&mut render_pass, "Chunk terrain", |pass| {
+            self.geometry.draw_all_terrain(pass)
+        });
+        self.geometry.bind_world_space(&mut render_pass);
+        pass_debug_group(&mut render_pass, "Moving blocks", |pass| {
+            self.moving_blocks.draw(pass)
+        });
+    }
+
+    /// Renders the world above the water plane, mirrored, into the
+    /// half-resolution reflection target, over the `sky` horizon colour.
+    fn draw_reflection(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        targets: &WaterTargets,
+        sky: Vec3,
+    ) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Water reflection pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &targets.reflection_color_view,
                 resolve_target: None,
                 ops: wgpu::Operations {
                     load: wgpu::LoadOp::Clear(wgpu::Color {
-                        r: 0.1,
-                        g: 0.2,
-                        b: 0.3,
+                        r: f64::from(sky.x),
+                        g: f64::from(sky.y),
+                        b: f64::from(sky.z),
                         a: 1.0,
                     }),
                     store: true,
                 },
             })],
             depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                view: &self.depth_texture.view,
+                view: &targets.reflection_depth_view,
                 depth_ops: Some(wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(1.0),
-                    store: true,
+                    load: wgpu::LoadOp::Clear(0.0),
+                    store: false,
                 }),
                 stencil_ops: None,
             }),
         });
+        render_pass.set_pipeline(&self.pipelines.reflection_pipeline);
+        render_pass.set_bind_group(0, &self.reflection_camera_bind_group, &[]);
+        render_pass.set_bind_group(1, &self.atlas_bind_group, &[]);
+        render_pass.set_bind_group(SHADOW_GROUP, &self.shadow_map.bind_group, &[]);
+        self.draw_world(&mut render_pass, false);
+        render_pass.set_pipeline(&self.pipelines.instanced_reflection_pipeline);
+        self.draw_instances(&mut render_pass);
+    }
 
-        render_pass.set_pipeline(&self.pipeline);
-        render_pass.set_bind_group(0, ctx.camera_bind_group, &[]);
+    /// Draws the chunk terrain, the far terrain and the moving blocks with
+    /// whichever world pipeline is set; `culled` skips the chunks outside
+    /// the camera's frustum.
+    fn draw_world<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, culled: bool) {
+        pass_debug_group(render_pass, "Chunk terrain", |pass| {
+            if culled {
+                self.geometry.draw_terrain(pass);
+            } else {
+                self.geometry.draw_all_terrain(pass);
+            }
+        });
+        self.geometry.bind_world_space(render_pass);
+        pass_debug_group(render_pass, "Far terrain", |pass| {
+            self.far_terrain.draw(pass)
+        });
+        pass_debug_group(render_pass, "Moving blocks", |pass| {
+            self.moving_blocks.draw(pass)
+        });
+    }
+
+    /// Draws the chunks' decorations and the dropped items, one draw per
+    /// block kind, with whichever instanced pipeline is set.
+    fn draw_instances<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        pass_debug_group(render_pass, "Decorations", |pass| {
+            self.geometry.decorations.draw(pass, &self.instance_meshes)
+        });
+        pass_debug_group(render_pass, "Dropped items", |pass| {
+            self.items.draw(pass, &self.instance_meshes)
+        });
+    }
+
+    /// Copies the frame so far for the water shader to read, then draws the
+    /// liquid surfaces on top of it.
+    fn draw_water(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        ctx: &FrameContext,
+        targets: &ViewTargets,
+        (width, height): (u32, u32),
+    ) {
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+        encoder.copy_texture_to_texture(
+            targets.scene.color.as_image_copy(),
+            targets.water.scene_color.as_image_copy(),
+            size,
+        );
+        encoder.copy_texture_to_texture(
+            targets.scene.depth.as_image_copy(),
+            targets.water.scene_depth.as_image_copy(),
+            size,
+        );
+
+        let (color_view, resolve_target) = targets.scene.color_attachment();
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Water pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: color_view,
+                resolve_target,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: targets.scene.drawn_depth_view(),
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: true,
+                }),
+                stencil_ops: None,
+            }),
+        });
+        render_pass.set_pipeline(&self.pipelines.water_pipeline);
+        render_pass.set_bind_group(0, ctx.camera_binding.bind_group(), &[]);
         render_pass.set_bind_group(1, &self.atlas_bind_group, &[]);
+        render_pass.set_bind_group(WATER_GROUP, &targets.water.bind_group, &[]);
+        self.geometry.draw_water(&mut render_pass);
+    }
+
+    /// Copies the nearest of each pixel's depth samples into the scene's
+    /// single-sample depth buffer, for the passes that read it; nothing to
+    /// do without MSAA.
+    fn resolve_depth(&self, encoder: &mut wgpu::CommandEncoder, scene: &SceneTargets) {
+        let (Some(msaa), Some(pipeline)) = (&scene.msaa, &self.pipelines.depth_resolve_pipeline)
+        else {
+            return;
+        };
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Depth resolve pass"),
+            color_attachments: &[],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &scene.depth_view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(0.0),
+                    store: true,
+                }),
+                stencil_ops: None,
+            }),
+        });
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_bind_group(0, &msaa.depth_resolve_bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+/// Every loaded chunk's geometry, each chunk drawn with its own
+/// `ChunkUniform` placing its chunk-local vertices in the world.
+///
+/// Meshes are cached per chunk and only rebuilt when the chunk's blocks or
+/// surroundings change; the small per-frame inputs (uniforms, culling bounds
+/// and decoration instances) are rebuilt from the cache on every change,
+/// and when the render origin moves.
+struct WorldGeometry {
+    chunks: HashMap<ChunkPos, ChunkGeometry>,
+    /// Chunks with anything to draw. `order[i]` is drawn with uniform slot
+    /// `i + 1` and culling index `i`.
+    order: Vec<ChunkPos>,
+    /// The heightmap the cached meshes were shaded with.
+    heightmap: Heightmap,
+    /// The `LightingSettings::ao_strength` the cached meshes were built with.
+    ao_strength: f32,
+    /// The render origin the uniforms, bounds and decorations place chunks
+    /// relative to.
+    origin: IVec3,
+    uniforms: ChunkUniforms,
+    /// Indirect draws of `order`, in the same order.
+    culling: ChunkCulling,
+    /// Every chunk's non-cube blocks, batched across chunks. They are not
+    /// culled; each block kind is a single draw wherever its copies are.
+    decorations: InstanceBatches,
+}
+
+struct ChunkGeometry {
+    /// `Chunk::revision` the meshes were built from.
+    revision: u64,
+    terrain: Option<GeometryBuffers>,
+    /// Liquid surfaces, drawn by the water pass after the rest of the world.
+    water: Option<GeometryBuffers>,
+    /// In world space, so they can be placed again when the origin moves.
+    decorations: Vec<Decoration>,
+}
+
+impl ChunkGeometry {
+    fn build(
+        device: &wgpu::Device,
+        world: &World,
+        heightmap: &Heightmap,
+        coord: ChunkPos,
+        revision: u64,
+        tiles: &BlockTiles,
+        ao_strength: f32,
+    ) -> Self {
+        let mesh = mesh::build_chunk_mesh(world, heightmap, coord, tiles, ao_strength);
+        Self {
+            revision,
+            terrain: GeometryBuffers::upload(device, "Terrain", coord, mesh.opaque),
+            water: GeometryBuffers::upload(device, "Water", coord, mesh.liquid),
+            decorations: mesh.decorations,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.terrain.is_none() && self.water.is_none()
+    }
+
+    fn bounds(&self, coord: ChunkPos, origin: IVec3) -> ChunkBounds {
+        let index_count =
+            |part: &Option<GeometryBuffers>| part.as_ref().map_or(0, |part| part.index_count);
+        ChunkBounds::new(
+            coord,
+            origin,
+            index_count(&self.terrain),
+            index_count(&self.water),
+        )
+    }
+}
+
+impl WorldGeometry {
+    /// Uniform slot of `ChunkUniform::WORLD`, for meshes built in world space.
+    const WORLD_SLOT: usize = 0;
+
+    fn new(
+        device: &wgpu::Device,
+        pipelines: &RasterPipelines,
+        world: &World,
+        tiles: &BlockTiles,
+        ao_strength: f32,
+    ) -> Self {
+        let mut geometry = Self {
+            chunks: HashMap::new(),
+            order: Vec::new(),
+            heightmap: Heightmap::empty(),
+            ao_strength,
+            origin: IVec3::ZERO,
+            uniforms: ChunkUniforms::new(
+                device,
+                &pipelines.chunk_bind_group_layout,
+                &[ChunkUniform::WORLD],
+            ),
+            culling: ChunkCulling::new(device, &pipelines.culling_bind_group_layout, &[]),
+            decorations: InstanceBatches::new(),
+        };
+        geometry.sync(device, pipelines, world, tiles, ao_strength, IVec3::ZERO);
+        geometry
+    }
+
+    /// Drops the meshes of unloaded chunks and rebuilds the stale ones, or
+    /// every one when `ao_strength` changed, then places them relative to
+    /// `origin`.
+    fn sync(
+        &mut self,
+        device: &wgpu::Device,
+        pipelines: &RasterPipelines,
+        world: &World,
+        tiles: &BlockTiles,
+        ao_strength: f32,
+        origin: IVec3,
+    ) {
+        if ao_strength != self.ao_strength {
+            self.chunks.clear();
+            self.ao_strength = ao_strength;
+        }
+        let heightmap = Heightmap::build(world);
+        // Sky occlusion looks at columns up to a chunk away, so a column
+        // whose top moved changes the shading of the chunk columns around it.
+        let sky_changed: HashSet<(i32, i32)> = heightmap
+            .changed_columns(&self.heightmap)
+            .flat_map(|[x, z]| {
+                let coord = BlockPos::new(x, 0, z).chunk();
+                (-1..=1).flat_map(move |dz| (-1..=1).map(move |dx| (coord.x + dx, coord.z + dz)))
+            })
+            .collect();
+
+        self.chunks.retain(|coord, _| world.chunk(*coord).is_some());
+        let mut remeshed = 0;
+        for (coord, chunk) in world.iter_chunks() {
+            let current = self
+                .chunks
+                .get(coord)
+                .is_some_and(|cached| cached.revision == chunk.revision())
+                && !sky_changed.contains(&(coord.x, coord.z));
+            if current {
+                continue;
+            }
+            let geometry = ChunkGeometry::build(
+                device,
+                world,
+                &heightmap,
+                *coord,
+                chunk.revision(),
+                tiles,
+                ao_strength,
+            );
+            self.chunks.insert(*coord, geometry);
+            remeshed += 1;
+        }
+        self.heightmap = heightmap;
+        log::debug!(
+            "Remeshed {} of {} raster chunks",
+            remeshed,
+            self.chunks.len()
+        );
+
+        self.order = self
+            .chunks
+            .iter()
+            .filter(|(_, chunk)| !chunk.is_empty())
+            .map(|(coord, _)| *coord)
+            .collect();
+        self.place(device, pipelines, origin);
+    }
+
+    /// Rebuilds the uniforms, culling bounds and decoration instances of the
+    /// cached chunks relative to `origin`.
+    fn place(&mut self, device: &wgpu::Device, pipelines: &RasterPipelines, origin: IVec3) {
+        self.origin = origin;
+        let mut uniforms = vec![ChunkUniform::WORLD];
+        uniforms.extend(
+            self.order
+                .iter()
+                .map(|&coord| ChunkUniform::chunk(coord, origin)),
+        );
+        let bounds: Vec<ChunkBounds> = self
+            .order
+            .iter()
+            .map(|coord| self.chunks[coord].bounds(*coord, origin))
+            .collect();
+        let decorations = self
+            .chunks
+            .values()
+            .flat_map(|chunk| &chunk.decorations)
+            .map(|decoration| {
+                let position = IVec3::from_array(decoration.position).as_vec3();
+                let instance =
+                    Instance::new(decoration.kind, position, origin, 1.0, decoration.light);
+                (decoration.kind, instance)
+            })
+            .collect();
+
+        self.uniforms = ChunkUniforms::new(device, &pipelines.chunk_bind_group_layout, &uniforms);
+        self.culling = ChunkCulling::new(device, &pipelines.culling_bind_group_layout, &bounds);
+        self.decorations
+            .upload(device, "Decoration instance buffer", decorations);
+    }
+
+    /// Every drawn chunk with its uniform slot, in culling order.
+    fn drawn_chunks(&self) -> impl Iterator<Item = (usize, &ChunkGeometry)> {
+        self.order
+            .iter()
+            .enumerate()
+            .map(|(index, coord)| (index, &self.chunks[coord]))
+    }
+
+    fn slot(index: usize) -> usize {
+        index + 1
+    }
+
+    fn has_water(&self) -> bool {
+        self.chunks.values().any(|chunk| chunk.water.is_some())
+    }
+
+    /// Binds the world-space placement for the meshes drawn after this.
+    fn bind_world_space<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        self.uniforms
+            .bind(render_pass, CHUNK_GROUP, Self::WORLD_SLOT);
+    }
+
+    /// Draws the terrain of the chunks that survived this frame's culling.
+    fn draw_terrain<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        for (index, chunk) in self.drawn_chunks() {
+            if let Some(terrain) = &chunk.terrain {
+                self.uniforms
+                    .bind(render_pass, CHUNK_GROUP, Self::slot(index));
+                terrain.bind(render_pass);
+                self.culling.draw_terrain(render_pass, index);
+            }
+        }
+    }
+
+    /// Draws every chunk's terrain, for the views the camera's culling does
+    /// not hold for: the planar reflection's mirrored one and the sun's.
+    fn draw_all_terrain<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        for (index, chunk) in self.drawn_chunks() {
+            if let Some(terrain) = &chunk.terrain {
+                self.uniforms
+                    .bind(render_pass, CHUNK_GROUP, Self::slot(index));
+                terrain.draw(render_pass);
+            }
+        }
+    }
+
+    /// Draws the liquids of the chunks that survived this frame's culling.
+    fn draw_water<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        for (index, chunk) in self.drawn_chunks() {
+            if let Some(water) = &chunk.water {
+                self.uniforms
+                    .bind(render_pass, CHUNK_GROUP, Self::slot(index));
+                water.bind(render_pass);
+                self.culling.draw_water(render_pass, index);
+            }
+        }
+    }
+}
+
+/// Vertex and index buffers of one part of a chunk's geometry.
+struct GeometryBuffers {
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+    index_count: u32,
+}
+
+impl GeometryBuffers {
+    /// Returns `None` for an empty mesh, which has nothing to draw.
+    fn upload(device: &wgpu::Device, name: &str, coord: ChunkPos, mesh: Mesh) -> Option<Self> {
+        if mesh.indices.is_empty() {
+            return None;
+        }
+        let vertices: Vec<Vertex> = mesh.vertices.into_iter().map(Vertex::from).collect();
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(&labels::chunk_label(
+                &format!("{name} vertex buffer"),
+                coord,
+            )),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(&labels::chunk_label(&format!("{name} index buffer"), coord)),
+            contents: bytemuck::cast_slice(&mesh.indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+        Some(Self {
+            vertex_buffer,
+            index_buffer,
+            index_count: mesh.indices.len() as u32,
+        })
+    }
+
+    fn bind<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
         render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
         render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+    }
+
+    fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        self.bind(render_pass);
         render_pass.draw_indexed(0..self.index_count, 0, 0..1);
     }
 }
 
-fn build_world_geometry(world: &World, atlas_layout: &AtlasLayout) -> (Vec<Vertex>, Vec<u32>) {
-    let mut vertices: Vec<Vertex> = Vec::new();
-    let mut indices: Vec<u32> = Vec::new();
+/// Cache names of the world pipelines and the light shaft pass for a sample
+/// count. The cache keys on name and format, so each sample count gets names
+/// of its own.
+fn pipeline_names(msaa_samples: u32) -> (&'static str, &'static str) {
+    match msaa_samples {
+        1 => ("raster.world", "raster.post"),
+        2 => ("raster.world.msaa2", "raster.post.msaa2"),
+        4 => ("raster.world.msaa4", "raster.post.msaa4"),
+        _ => ("raster.world.msaa8", "raster.post.msaa8"),
+    }
+}
+
+/// Layouts and pipelines shared by every raster renderer with the same sample
+/// count; none of them depends on the surface format.
+struct RasterPipelines {
+    texture_bind_group_layout: wgpu::BindGroupLayout,
+    chunk_bind_group_layout: wgpu::BindGroupLayout,
+    culling_bind_group_layout: wgpu::BindGroupLayout,
+    post_bind_group_layout: wgpu::BindGroupLayout,
+    water_bind_group_layout: wgpu::BindGroupLayout,
+    shadow_bind_group_layout: wgpu::BindGroupLayout,
+    sky_bind_group_layout: wgpu::BindGroupLayout,
+    /// The sky behind everything, drawn first in the world pass.
+    sky_pipeline: wgpu::RenderPipeline,
+    pipeline: wgpu::RenderPipeline,
+    /// The world pipeline clipped to above the water plane, for planar reflections.
+    reflection_pipeline: wgpu::RenderPipeline,
+    /// The world pipelines drawing block meshes as per-instance copies.
+    instanced_pipeline: wgpu::RenderPipeline,
+    instanced_reflection_pipeline: wgpu::RenderPipeline,
+    water_pipeline: wgpu::RenderPipeline,
+    /// Depth-only pass drawing shadow casters from the sun.
+    shadow_pipeline: wgpu::RenderPipeline,
+    depth_resolve_bind_group_layout: wgpu::BindGroupLayout,
+    /// Present when the world pass is multisampled.
+    depth_resolve_pipeline: Option<wgpu::RenderPipeline>,
+    /// Writes the indirect chunk draws each frame.
+    culling_pipeline: wgpu::ComputePipeline,
+}
 
-    for (coord, _) in world.iter_chunks() {
-        let mesh = mesh::build_chunk_mesh(world, *coord, atlas_layout);
-        let base_index = vertices.len() as u32;
-        vertices.extend(mesh.vertices.into_iter().map(|v| Vertex {
-            position: v.position,
-            color: v.color,
-            uv: v.uv,
-        }));
-        indices.extend(mesh.indices.into_iter().map(|i| i + base_index));
+impl RasterPipelines {
+    fn create(
+        device: &wgpu::Device,
+        msaa_samples: u32,
+        camera_bind_group_layout: &wgpu::BindGroupLayout,
+    ) -> Self {
+        let texture_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Texture bind group layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            multisampled: false,
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
+            });
+
+        let chunk_bind_group_layout = chunk_uniforms::bind_group_layout(device);
+        let culling_bind_group_layout = culling::bind_group_layout(device);
+        let culling_pipeline = culling::create_pipeline(device, &culling_bind_group_layout);
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("World shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../shader.wgsl").into()),
+        });
+
+        let shadow_bind_group_layout = shadows::bind_group_layout(device);
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("World pipeline layout"),
+            bind_group_layouts: &[
+                camera_bind_group_layout,
+                &texture_bind_group_layout,
+                &chunk_bind_group_layout,
+                &shadow_bind_group_layout,
+            ],
+            push_constant_ranges: &[],
+        });
+
+        // The world, sky and water draw into the multisampled scene targets;
+        // the planar reflection keeps its single-sample target.
+        let world_pipeline = |label,
+                              layout,
+                              module,
+                              (vertex_entry, buffers): (&str, &[wgpu::VertexBufferLayout]),
+                              fragment_entry,
+                              sample_count| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(layout),
+                vertex: wgpu::VertexState {
+                    module,
+                    entry_point: vertex_entry,
+                    buffers,
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module,
+                    entry_point: fragment_entry,
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: SCENE_FORMAT,
+                        blend: Some(wgpu::BlendState::REPLACE),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: SceneTargets::DEPTH_FORMAT,
+                    depth_write_enabled: true,
+                    depth_compare: wgpu::CompareFunction::Greater,
+                    stencil: wgpu::StencilState::default(),
+                    bias: wgpu::DepthBiasState::default(),
+                }),
+                multisample: wgpu::MultisampleState {
+                    count: sample_count,
+                    ..Default::default()
+                },
+                multiview: None,
+            })
+        };
+        let meshes = [Vertex::buffer_layout()];
+        let instances = [InstanceVertex::buffer_layout(), Instance::buffer_layout()];
+        let pipeline = world_pipeline(
+            "World pipeline",
+            &pipeline_layout,
+            &shader,
+            ("vs_main", &meshes),
+            "fs_main",
+            msaa_samples,
+        );
+        let reflection_pipeline = world_pipeline(
+            "Water reflection pipeline",
+            &pipeline_layout,
+            &shader,
+            ("vs_main", &meshes),
+            "fs_reflected",
+            1,
+        );
+        let instanced_pipeline = world_pipeline(
+            "Instanced world pipeline",
+            &pipeline_layout,
+            &shader,
+            ("vs_instanced", &instances),
+            "fs_main",
+            msaa_samples,
+        );
+        let instanced_reflection_pipeline = world_pipeline(
+            "Instanced water reflection pipeline",
+            &pipeline_layout,
+            &shader,
+            ("vs_instanced", &instances),
+            "fs_reflected",
+            1,
+        );
+
+        let shadow_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Shadow pipeline layout"),
+                bind_group_layouts: &[
+                    camera_bind_group_layout,
+                    &texture_bind_group_layout,
+                    &chunk_bind_group_layout,
+                ],
+                push_constant_ranges: &[],
+            });
+        let shadow_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Shadow pipeline"),
+            layout: Some(&shadow_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &meshes,
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_shadow",
+                targets: &[],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: shadows::SHADOW_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                // Pushes casters back so surfaces at a grazing angle to the
+                // sun do not shadow themselves.
+                bias: wgpu::DepthBiasState {
+                    constant: 2,
+                    slope_scale: 2.0,
+                    clamp: 0.0,
+                },
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+
+        let water_bind_group_layout = water::bind_group_layout(device);
+        let water_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Water shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("water.wgsl").into()),
+        });
+        let water_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Water pipeline layout"),
+                bind_group_layouts: &[
+                    camera_bind_group_layout,
+                    &texture_bind_group_layout,
+                    &chunk_bind_group_layout,
+                    &water_bind_group_layout,
+                ],
+                push_constant_ranges: &[],
+            });
+        let water_pipeline = world_pipeline(
+            "Water pipeline",
+            &water_pipeline_layout,
+            &water_shader,
+            ("vs_main", &meshes),
+            "fs_main",
+            msaa_samples,
+        );
+
+        let sky_bind_group_layout = sky_dome::bind_group_layout(device);
+        let sky_pipeline = sky_dome::create_pipeline(
+            device,
+            &sky_bind_group_layout,
+            SCENE_FORMAT,
+            SceneTargets::DEPTH_FORMAT,
+            msaa_samples,
+        );
+
+        let depth_resolve_bind_group_layout = depth_resolve::bind_group_layout(device);
+        let depth_resolve_pipeline = (msaa_samples > 1).then(|| {
+            depth_resolve::create_pipeline(
+                device,
+                &depth_resolve_bind_group_layout,
+                SceneTargets::DEPTH_FORMAT,
+            )
+        });
+
+        let post_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Light shaft bind group layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            multisampled: false,
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            multisampled: false,
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            sample_type: wgpu::TextureSampleType::Depth,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 3,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 4,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                ],
+            });
+
+        Self {
+            texture_bind_group_layout,
+            chunk_bind_group_layout,
+            culling_bind_group_layout,
+            culling_pipeline,
+            post_bind_group_layout,
+            water_bind_group_layout,
+            shadow_bind_group_layout,
+            sky_bind_group_layout,
+            sky_pipeline,
+            pipeline,
+            reflection_pipeline,
+            instanced_pipeline,
+            instanced_reflection_pipeline,
+            water_pipeline,
+            shadow_pipeline,
+            depth_resolve_bind_group_layout,
+            depth_resolve_pipeline,
+        }
     }
 
-    (vertices, indices)
+    /// The light shaft pass, the one pipeline that writes the surface and so
+    /// depends on its format.
+    fn create_post(
+        device: &wgpu::Device,
+        post_bind_group_layout: &wgpu::BindGroupLayout,
+        surface_format: wgpu::TextureFormat,
+    ) -> wgpu::RenderPipeline {
+        let post_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Light shaft shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("light_shafts.wgsl").into()),
+        });
+
+        let post_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Light shaft pipeline layout"),
+            bind_group_layouts: &[post_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Light shaft pipeline"),
+            layout: Some(&post_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &post_shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &post_shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: surface_format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        })
+    }
 }
 
 #[repr(C)]
@@ -237,6 +1420,23 @@ struct Vertex {
     position: [f32; 3],
     color: [f32; 3],
     uv: [f32; 2],
+    tile: [f32; 4],
+    alpha_cutoff: f32,
+    /// x: sky light, y: block light.
+    light: [f32; 2],
+}
+
+impl From<MeshVertex> for Vertex {
+    fn from(v: MeshVertex) -> Self {
+        Self {
+            position: v.position,
+            color: v.color,
+            uv: v.uv,
+            tile: v.tile,
+            alpha_cutoff: v.alpha_cutoff,
+            light: [v.sky_light, v.block_light],
+        }
+    }
 }
 
 impl Vertex {
@@ -260,38 +1460,178 @@ impl Vertex {
                     shader_location: 2,
                     format: wgpu::VertexFormat::Float32x2,
                 },
+                wgpu::VertexAttribute {
+                    offset: 32,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: 48,
+                    shader_location: 4,
+                    format: wgpu::VertexFormat::Float32,
+                },
+                wgpu::VertexAttribute {
+                    offset: 52,
+                    shader_location: 5,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
             ],
         }
     }
 }
 
-struct DepthTexture {
-    _texture: wgpu::Texture,
-    view: wgpu::TextureView,
+/// Everything one output size renders into.
+struct ViewTargets {
+    scene: SceneTargets,
+    water: WaterTargets,
 }
 
-impl DepthTexture {
-    const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth24Plus;
+/// Size-dependent render targets: the HDR scene color, the depth buffer, and
+/// the bind group the light shaft pass reads them through.
+///
+/// With MSAA the world and water passes draw into `msaa`'s targets instead,
+/// resolving color into `color` as they end; depth is resolved into `depth`
+/// by a pass of its own.
+struct SceneTargets {
+    color: wgpu::Texture,
+    color_view: wgpu::TextureView,
+    depth: wgpu::Texture,
+    depth_view: wgpu::TextureView,
+    post_bind_group: wgpu::BindGroup,
+    msaa: Option<MultisampledTargets>,
+}
 
-    fn create(device: &wgpu::Device, config: &wgpu::SurfaceConfiguration) -> Self {
-        let texture = device.create_texture(&wgpu::TextureDescriptor {
-            label: Some("Depth texture"),
-            size: wgpu::Extent3d {
-                width: config.width,
-                height: config.height,
-                depth_or_array_layers: 1,
-            },
+struct MultisampledTargets {
+    color_view: wgpu::TextureView,
+    depth_view: wgpu::TextureView,
+    depth_resolve_bind_group: wgpu::BindGroup,
+}
+
+impl SceneTargets {
+    /// Reverse-Z needs a float depth buffer; cleared to 0 (far) and compared with `Greater`.
+    const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+    #[allow(clippy::too_many_arguments)]
+    fn create(
+        device: &wgpu::Device,
+        (width, height): (u32, u32),
+        sample_count: u32,
+        depth_resolve_layout: &wgpu::BindGroupLayout,
+        layout: &wgpu::BindGroupLayout,
+        sampler: &wgpu::Sampler,
+        output_buffer: &wgpu::Buffer,
+        shaft_buffer: &wgpu::Buffer,
+    ) -> Self {
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+        let color = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(&labels::sized_label("Scene color texture", (width, height))),
+            size,
             mip_level_count: 1,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
-            format: Self::FORMAT,
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: SCENE_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
             view_formats: &[],
         });
-        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let color_view = labels::labeled_view(&color, "Scene color view");
+        let depth = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(&labels::sized_label("Scene depth texture", (width, height))),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: Self::DEPTH_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let depth_view = labels::labeled_view(&depth, "Scene depth view");
+
+        let post_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Light shaft bind group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&color_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&depth_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: output_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: shaft_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let msaa = (sample_count > 1).then(|| {
+            let texture = |label, format| {
+                device.create_texture(&wgpu::TextureDescriptor {
+                    label: Some(&labels::sized_label(label, (width, height))),
+                    size,
+                    mip_level_count: 1,
+                    sample_count,
+                    dimension: wgpu::TextureDimension::D2,
+                    format,
+                    usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                        | wgpu::TextureUsages::TEXTURE_BINDING,
+                    view_formats: &[],
+                })
+            };
+            let color = texture("Multisampled scene color texture", SCENE_FORMAT);
+            let depth = texture("Multisampled scene depth texture", Self::DEPTH_FORMAT);
+            let depth_view = labels::labeled_view(&depth, "Multisampled scene depth view");
+            MultisampledTargets {
+                color_view: labels::labeled_view(&color, "Multisampled scene color view"),
+                depth_resolve_bind_group: depth_resolve::create_bind_group(
+                    device,
+                    depth_resolve_layout,
+                    &depth_view,
+                ),
+                depth_view,
+            }
+        });
+
         Self {
-            _texture: texture,
-            view,
+            color,
+            color_view,
+            depth,
+            depth_view,
+            post_bind_group,
+            msaa,
         }
     }
+
+    /// The view the world and water passes draw color into, and the one
+    /// it resolves into, if any.
+    fn color_attachment(&self) -> (&wgpu::TextureView, Option<&wgpu::TextureView>) {
+        match &self.msaa {
+            Some(msaa) => (&msaa.color_view, Some(&self.color_view)),
+            None => (&self.color_view, None),
+        }
+    }
+
+    /// The depth buffer the world and water passes test and write.
+    fn drawn_depth_view(&self) -> &wgpu::TextureView {
+        self.msaa
+            .as_ref()
+            .map_or(&self.depth_view, |msaa| &msaa.depth_view)
+    }
 }
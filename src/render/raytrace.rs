@@ -1,38 +1,68 @@
-use std::{sync::mpsc, time::Instant};
+use std::{collections::HashMap, sync::Arc, time::Instant};
 
 use bytemuck::{Pod, Zeroable};
 use glam::{IVec3, Mat4, Vec2, Vec3, Vec4};
 use wgpu::util::DeviceExt;
 
-use crate::block::{self, BLOCK_AIR, BlockId, BlockKind};
-use crate::render::{FrameContext, RenderTimings, Renderer, RendererKind};
+use crate::block::{self, BLOCK_AIR, BlockDefinition, BlockId, BlockKind};
+use crate::coords::{BlockPos, ChunkPos, LocalPos};
+use crate::light::MAX_LIGHT;
+use crate::render::labels::{self, debug_group};
+use crate::render::light_shafts::LightShaftUniform;
+use crate::render::output::OutputUniform;
+use crate::render::pipelines::PipelineCache;
+use crate::render::timestamps::{GpuSpan, GpuTimer};
+use crate::render::traversal::TraversalCounter;
+use crate::render::{FrameContext, RenderTarget, RenderTimings, Renderer, RendererKind};
 use crate::texture::{AtlasLayout, TextureAtlas, TileId};
-use crate::world::{CHUNK_SIZE, World, chunk_min_corner};
+use crate::world::{CHUNK_SIZE, Chunk, World};
+
+/// Linear HDR target written by the compute pass; the blit encodes it for display.
+const SCREEN_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+/// Running mean of path-traced samples, kept at full precision.
+const HISTORY_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba32Float;
+/// Past this many samples the history becomes an exponential moving average,
+/// so float precision never stalls it.
+const MAX_ACCUMULATED_SAMPLES: u32 = 4096;
+/// Steps per unit of the light direction's axes that the accumulation key
+/// rounds it to: path tracing starts over each time the sun moves this far,
+/// rather than every frame.
+const LIGHT_KEY_STEPS: f32 = 128.0;
+/// Moving blocks traced per frame; any beyond this are skipped until they land.
+const MAX_MOVING_BLOCKS: usize = 64;
+/// Emissive blocks the shader casts shadow rays towards, nearest the camera
+/// first; any further away give no light in the ray tracer.
+const MAX_LAMPS: usize = 1024;
 
 pub struct RayTraceRenderer {
-    blit_pipeline: wgpu::RenderPipeline,
-    blit_bind_group_layout: wgpu::BindGroupLayout,
+    pipeline_cache: PipelineCache,
+    blit: Arc<BlitPipeline>,
     blit_sampler: wgpu::Sampler,
+    output_buffer: wgpu::Buffer,
+    shaft_buffer: wgpu::Buffer,
     fullscreen_vertex: wgpu::Buffer,
     fullscreen_index: wgpu::Buffer,
     index_count: u32,
-    compute_pipeline: wgpu::ComputePipeline,
-    compute_bind_group_layout: wgpu::BindGroupLayout,
-    compute_bind_group: Option<wgpu::BindGroup>,
+    compute: Arc<ComputePipeline>,
     uniform_buffer: wgpu::Buffer,
-    voxel_buffer: Option<wgpu::Buffer>,
+    voxel_buffers: Option<VoxelBuffers>,
     block_info_buffer: wgpu::Buffer,
+    moving_block_buffer: wgpu::Buffer,
     atlas_view: wgpu::TextureView,
     atlas_sampler: wgpu::Sampler,
     atlas_layout: AtlasLayout,
-    screen: Option<ScreenTexture>,
+    /// Which block ids give off light, as their current materials say.
+    emissive: [bool; BLOCK_IDS],
+    /// Storage textures per output size, created on first use; the window
+    /// and a picture-in-picture inset each get their own.
+    screens: HashMap<(u32, u32), ScreenTexture>,
     scene: Option<VoxelScene>,
     surface_format: wgpu::TextureFormat,
     last_log: Instant,
     last_timings: RenderTimings,
     timings_valid: bool,
-    timestamp_query: Option<TimestampQuery>,
-    gpu_sample: Option<TimestampSample>,
+    gpu_timer: Option<GpuTimer>,
+    traversal: TraversalCounter,
 }
 
 impl RayTraceRenderer {
@@ -41,29 +71,24 @@ impl RayTraceRenderer {
         queue: &wgpu::Queue,
         surface_format: wgpu::TextureFormat,
         atlas: &TextureAtlas,
+        cache: &PipelineCache,
     ) -> Self {
-        let blit_bind_group_layout =
-            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                label: Some("Ray traced blit bind group layout"),
-                entries: &[
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 0,
-                        visibility: wgpu::ShaderStages::FRAGMENT,
-                        ty: wgpu::BindingType::Texture {
-                            multisampled: false,
-                            view_dimension: wgpu::TextureViewDimension::D2,
-                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                        },
-                        count: None,
-                    },
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 1,
-                        visibility: wgpu::ShaderStages::FRAGMENT,
-                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-                        count: None,
-                    },
-                ],
-            });
+        let blit = blit_pipeline(device, cache, surface_format);
+        let compute = compute_pipeline(device, cache);
+
+        let output_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Ray traced output buffer"),
+            size: std::mem::size_of::<OutputUniform>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let shaft_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Ray traced light shaft buffer"),
+            size: std::mem::size_of::<LightShaftUniform>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
 
         let blit_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
             label: Some("Ray traced blit sampler"),
@@ -77,76 +102,6 @@ impl RayTraceRenderer {
         });
 
         let (fullscreen_vertex, fullscreen_index, index_count) = create_fullscreen_quad(device);
-        let blit_pipeline = create_blit_pipeline(device, &blit_bind_group_layout, surface_format);
-
-        let compute_bind_group_layout =
-            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                label: Some("Ray tracing compute bind group layout"),
-                entries: &[
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 0,
-                        visibility: wgpu::ShaderStages::COMPUTE,
-                        ty: wgpu::BindingType::StorageTexture {
-                            access: wgpu::StorageTextureAccess::WriteOnly,
-                            format: wgpu::TextureFormat::Rgba8Unorm,
-                            view_dimension: wgpu::TextureViewDimension::D2,
-                        },
-                        count: None,
-                    },
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 1,
-                        visibility: wgpu::ShaderStages::COMPUTE,
-                        ty: wgpu::BindingType::Buffer {
-                            ty: wgpu::BufferBindingType::Uniform,
-                            has_dynamic_offset: false,
-                            min_binding_size: std::num::NonZeroU64::new(std::mem::size_of::<
-                                RayUniforms,
-                            >(
-                            )
-                                as u64),
-                        },
-                        count: None,
-                    },
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 2,
-                        visibility: wgpu::ShaderStages::COMPUTE,
-                        ty: wgpu::BindingType::Buffer {
-                            ty: wgpu::BufferBindingType::Storage { read_only: true },
-                            has_dynamic_offset: false,
-                            min_binding_size: None,
-                        },
-                        count: None,
-                    },
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 3,
-                        visibility: wgpu::ShaderStages::COMPUTE,
-                        ty: wgpu::BindingType::Buffer {
-                            ty: wgpu::BufferBindingType::Storage { read_only: true },
-                            has_dynamic_offset: false,
-                            min_binding_size: None,
-                        },
-                        count: None,
-                    },
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 4,
-                        visibility: wgpu::ShaderStages::COMPUTE,
-                        ty: wgpu::BindingType::Texture {
-                            multisampled: false,
-                            view_dimension: wgpu::TextureViewDimension::D2,
-                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                        },
-                        count: None,
-                    },
-                    wgpu::BindGroupLayoutEntry {
-                        binding: 5,
-                        visibility: wgpu::ShaderStages::COMPUTE,
-                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-                        count: None,
-                    },
-                ],
-            });
-
-        let compute_pipeline = create_compute_pipeline(device, &compute_bind_group_layout);
 
         let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("Ray tracing uniforms"),
@@ -155,11 +110,19 @@ impl RayTraceRenderer {
             mapped_at_creation: false,
         });
 
-        let block_info_data = build_block_metadata();
+        let block_info_data = build_block_metadata(&atlas.layout(), &[]);
         let block_info_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Block metadata buffer"),
             contents: bytemuck::cast_slice(&block_info_data),
-            usage: wgpu::BufferUsages::STORAGE,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let moving_block_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Moving block buffer"),
+            size: (MAX_MOVING_BLOCKS * std::mem::size_of::<GpuMovingBlock>())
+                as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
         });
 
         let atlas_view = atlas.create_view();
@@ -174,52 +137,47 @@ impl RayTraceRenderer {
             ..Default::default()
         });
         let atlas_layout = atlas.layout();
+        let emissive = emissive_blocks(&[]);
 
         Self {
-            blit_pipeline,
-            blit_bind_group_layout,
+            pipeline_cache: cache.clone(),
+            blit,
             blit_sampler,
+            output_buffer,
+            shaft_buffer,
             fullscreen_vertex,
             fullscreen_index,
             index_count,
-            compute_pipeline,
-            compute_bind_group_layout,
-            compute_bind_group: None,
+            compute,
             uniform_buffer,
-            voxel_buffer: None,
+            voxel_buffers: None,
             block_info_buffer,
+            moving_block_buffer,
             atlas_view,
             atlas_sampler,
             atlas_layout,
-            screen: None,
+            emissive,
+            screens: HashMap::new(),
             scene: None,
             surface_format,
             last_log: Instant::now(),
             last_timings: RenderTimings::default(),
             timings_valid: false,
-            timestamp_query: TimestampQuery::new(device, queue),
-            gpu_sample: None,
+            gpu_timer: GpuTimer::new(device, queue, "Ray trace"),
+            traversal: TraversalCounter::new(device),
         }
     }
 
     fn ensure_screen_texture(&mut self, device: &wgpu::Device, width: u32, height: u32) {
-        if width == 0 || height == 0 {
-            self.screen = None;
-            self.compute_bind_group = None;
-            return;
-        }
-
-        let recreate = match self.screen.as_ref() {
-            Some(screen) => screen.size != (width, height),
-            None => true,
-        };
-
-        if !recreate {
+        if width == 0 || height == 0 || self.screens.contains_key(&(width, height)) {
             return;
         }
 
         let texture = device.create_texture(&wgpu::TextureDescriptor {
-            label: Some("Ray traced storage texture"),
+            label: Some(&labels::sized_label(
+                "Ray traced storage texture",
+                (width, height),
+            )),
             size: wgpu::Extent3d {
                 width,
                 height,
@@ -228,17 +186,17 @@ impl RayTraceRenderer {
             mip_level_count: 1,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba8Unorm,
+            format: SCREEN_FORMAT,
             usage: wgpu::TextureUsages::STORAGE_BINDING
                 | wgpu::TextureUsages::TEXTURE_BINDING
                 | wgpu::TextureUsages::RENDER_ATTACHMENT,
             view_formats: &[],
         });
 
-        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let view = labels::labeled_view(&texture, "Ray traced storage view");
         let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("Ray traced blit bind group"),
-            layout: &self.blit_bind_group_layout,
+            layout: &self.blit.bind_group_layout,
             entries: &[
                 wgpu::BindGroupEntry {
                     binding: 0,
@@ -248,24 +206,67 @@ impl RayTraceRenderer {
                     binding: 1,
                     resource: wgpu::BindingResource::Sampler(&self.blit_sampler),
                 },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: self.output_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: self.shaft_buffer.as_entire_binding(),
+                },
             ],
         });
 
-        self.screen = Some(ScreenTexture {
+        let history = [0, 1].map(|index| {
+            device.create_texture(&wgpu::TextureDescriptor {
+                label: Some(&labels::sized_label(
+                    &format!("Path tracing history texture {index}"),
+                    (width, height),
+                )),
+                size: wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: HISTORY_FORMAT,
+                usage: wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            })
+        });
+        let history_views = [0, 1].map(|index| {
+            labels::labeled_view(
+                &history[index],
+                &format!("Path tracing history view {index}"),
+            )
+        });
+
+        let mut screen = ScreenTexture {
             _texture: texture,
             view,
             bind_group,
-            size: (width, height),
-        });
-
-        self.recreate_compute_bind_group(device);
+            _history: history,
+            history_views,
+            compute_bind_groups: None,
+            accumulation: Accumulation::default(),
+        };
+        screen.compute_bind_groups = self.compute_bind_groups(device, &screen);
+        self.screens.insert((width, height), screen);
     }
 
-    fn ensure_scene(&mut self, device: &wgpu::Device, world: &World) {
+    /// Rebuilds the voxel scene when the world changed, keeping only the
+    /// chunks around `center` if the whole of it overflows a buffer.
+    fn ensure_scene(&mut self, device: &wgpu::Device, world: &World, center: ChunkPos) {
         let chunk_count = world.chunk_count();
         let world_version = world.version();
         let needs_rebuild = match &self.scene {
-            Some(scene) => scene.chunk_count != chunk_count || scene.world_version != world_version,
+            Some(scene) => {
+                scene.chunk_count != chunk_count
+                    || scene.world_version != world_version
+                    || scene.emissive != self.emissive
+            }
             None => true,
         };
 
@@ -273,42 +274,107 @@ impl RayTraceRenderer {
             return;
         }
 
-        let Some(grid) = VoxelGrid::from_world(world) else {
+        let limits = device.limits();
+        let max_buffer_size =
+            u64::from(limits.max_storage_buffer_binding_size).min(limits.max_buffer_size);
+        let previous_radius = self
+            .scene
+            .as_ref()
+            .and_then(|scene| scene.grid.traced_radius);
+        let Some(grid) = VoxelGrid::from_world(world, center, max_buffer_size, &self.emissive)
+        else {
             self.scene = None;
-            self.voxel_buffer = None;
-            self.compute_bind_group = None;
+            self.voxel_buffers = None;
+            for screen in self.screens.values_mut() {
+                screen.compute_bind_groups = None;
+            }
             return;
         };
 
-        let voxel_data = grid.pack_voxels();
+        if grid.traced_radius != previous_radius
+            && let Some(radius) = grid.traced_radius
+        {
+            log::warn!(
+                "Loaded world overflows the ray tracer's {} MiB buffers; tracing {} chunks around the camera",
+                max_buffer_size >> 20,
+                radius
+            );
+        }
+
+        let chunk_table = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Ray traced chunk table buffer"),
+            contents: bytemuck::cast_slice(&grid.chunk_table),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        let bricks = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Ray traced brick buffer"),
+            contents: bytemuck::cast_slice(&grid.pack_bricks()),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
 
-        let voxel_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Ray traced voxel buffer"),
-            contents: bytemuck::cast_slice(&voxel_data),
+        let light_table = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Ray traced light table buffer"),
+            contents: bytemuck::cast_slice(&grid.light_table),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        let light_bricks = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Ray traced light brick buffer"),
+            contents: bytemuck::cast_slice(&grid.pack_light_bricks()),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        let lamps = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Ray traced lamp buffer"),
+            contents: bytemuck::cast_slice(&grid.lamp_entries()),
             usage: wgpu::BufferUsages::STORAGE,
         });
 
-        self.voxel_buffer = Some(voxel_buffer);
+        self.voxel_buffers = Some(VoxelBuffers {
+            chunk_table,
+            bricks,
+            light_table,
+            light_bricks,
+            lamps,
+        });
         self.scene = Some(VoxelScene {
             grid,
             chunk_count,
             world_version,
+            emissive: self.emissive,
         });
-        self.recreate_compute_bind_group(device);
+        let bind_groups: Vec<_> = self
+            .screens
+            .iter()
+            .map(|(&size, screen)| (size, self.compute_bind_groups(device, screen)))
+            .collect();
+        for (size, bind_groups) in bind_groups {
+            if let Some(screen) = self.screens.get_mut(&size) {
+                screen.compute_bind_groups = bind_groups;
+            }
+        }
     }
 
-    fn recreate_compute_bind_group(&mut self, device: &wgpu::Device) {
-        let (screen, voxel) = match (&self.screen, &self.voxel_buffer) {
-            (Some(screen), Some(voxel)) => (screen, voxel),
-            _ => {
-                self.compute_bind_group = None;
-                return;
-            }
-        };
+    /// Binds the voxel scene and everything else the tracer reads to a
+    /// screen's textures; `None` until the scene exists. Bind group `i`
+    /// writes path-traced history `i` and reads the other one.
+    fn compute_bind_groups(
+        &self,
+        device: &wgpu::Device,
+        screen: &ScreenTexture,
+    ) -> Option<[wgpu::BindGroup; 2]> {
+        let voxels = self.voxel_buffers.as_ref()?;
+        Some([0, 1].map(|write| self.compute_bind_group(device, screen, voxels, write)))
+    }
 
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+    fn compute_bind_group(
+        &self,
+        device: &wgpu::Device,
+        screen: &ScreenTexture,
+        voxels: &VoxelBuffers,
+        history_write: usize,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("Ray tracing compute bind group"),
-            layout: &self.compute_bind_group_layout,
+            layout: &self.compute.bind_group_layout,
             entries: &[
                 wgpu::BindGroupEntry {
                     binding: 0,
@@ -320,7 +386,7 @@ impl RayTraceRenderer {
                 },
                 wgpu::BindGroupEntry {
                     binding: 2,
-                    resource: voxel.as_entire_binding(),
+                    resource: voxels.chunk_table.as_entire_binding(),
                 },
                 wgpu::BindGroupEntry {
                     binding: 3,
@@ -334,13 +400,74 @@ impl RayTraceRenderer {
                     binding: 5,
                     resource: wgpu::BindingResource::Sampler(&self.atlas_sampler),
                 },
+                wgpu::BindGroupEntry {
+                    binding: 6,
+                    resource: self.moving_block_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 7,
+                    resource: voxels.bricks.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 8,
+                    resource: wgpu::BindingResource::TextureView(
+                        &screen.history_views[1 - history_write],
+                    ),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 9,
+                    resource: wgpu::BindingResource::TextureView(
+                        &screen.history_views[history_write],
+                    ),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 10,
+                    resource: voxels.light_table.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 11,
+                    resource: voxels.light_bricks.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 12,
+                    resource: self.traversal.buffer().as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 13,
+                    resource: voxels.lamps.as_entire_binding(),
+                },
             ],
-        });
+        })
+    }
 
-        self.compute_bind_group = Some(bind_group);
+    /// Uploads the moving blocks and returns how many were written.
+    fn update_moving_blocks(&self, queue: &wgpu::Queue, ctx: &FrameContext) -> u32 {
+        let blocks: Vec<GpuMovingBlock> = ctx
+            .moving_blocks
+            .iter()
+            .chain(ctx.items)
+            .take(MAX_MOVING_BLOCKS)
+            .map(|moving| GpuMovingBlock {
+                position: (moving.position - ctx.origin.as_vec3()).to_array(),
+                block: moving.kind.id() as u32,
+                scale: moving.scale,
+                _padding: [0.0; 3],
+            })
+            .collect();
+        if !blocks.is_empty() {
+            queue.write_buffer(&self.moving_block_buffer, 0, bytemuck::cast_slice(&blocks));
+        }
+        blocks.len() as u32
     }
 
-    fn update_uniforms(&self, queue: &wgpu::Queue, ctx: &FrameContext, grid: &VoxelGrid) {
+    fn update_uniforms(
+        &self,
+        queue: &wgpu::Queue,
+        ctx: &FrameContext,
+        grid: &VoxelGrid,
+        (width, height): (u32, u32),
+        accumulation: [u32; 4],
+    ) {
         let view = ctx.camera.view_matrix();
         let proj = ctx.projection.matrix();
         let inv_projection = proj.inverse();
@@ -353,7 +480,9 @@ impl RayTraceRenderer {
         let uniforms = RayUniforms {
             frustum,
             eye: [eye.x, eye.y, eye.z, 1.0],
-            grid_origin: [grid.origin.x, grid.origin.y, grid.origin.z, 0],
+            // The grid is built in world space; the eye is relative to the
+            // render origin.
+            grid_origin: (grid.origin - ctx.origin).extend(0).to_array(),
             grid_size: [
                 grid.size.x as u32,
                 grid.size.y as u32,
@@ -361,17 +490,29 @@ impl RayTraceRenderer {
                 0,
             ],
             stride: [
-                grid.stride_y as u32,
-                grid.stride_z as u32,
-                ctx.surface_config.width,
-                ctx.surface_config.height,
+                grid.table_stride_y as u32,
+                grid.table_stride_z as u32,
+                width,
+                height,
             ],
             atlas: [
                 self.atlas_layout.tile_size,
                 self.atlas_layout.width,
                 self.atlas_layout.height,
-                0,
+                self.atlas_layout.padding,
             ],
+            moving: [self.update_moving_blocks(queue, ctx), 0, 0, 0],
+            time: [ctx.time, 0.0, 0.0, 0.0],
+            accumulation,
+            sun: ctx.lighting.sun_direction.extend(0.0).to_array(),
+            sun_color: ctx.sky.light_color.extend(0.0).to_array(),
+            ambient: ctx.sky.ambient.extend(0.0).to_array(),
+            sky_sun: ctx.sky.sun.extend(0.0).to_array(),
+            sky_zenith: ctx.sky.zenith.extend(0.0).to_array(),
+            sky_horizon: ctx.sky.horizon.extend(0.0).to_array(),
+            world_origin: ctx.origin.extend(0).to_array(),
+            bounces: [ctx.ray_bounces, 0, 0, 0],
+            lamps: [grid.lamps.len() as u32, 0, 0, 0],
         };
 
         queue.write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(&uniforms));
@@ -390,75 +531,132 @@ impl Renderer for RayTraceRenderer {
         config: &wgpu::SurfaceConfiguration,
     ) {
         self.surface_format = config.format;
-        self.blit_pipeline =
-            create_blit_pipeline(device, &self.blit_bind_group_layout, self.surface_format);
-        self.screen = None;
-        self.compute_bind_group = None;
+        self.blit = blit_pipeline(device, &self.pipeline_cache, self.surface_format);
+        self.screens.clear();
+    }
+
+    fn output_format(&self) -> wgpu::TextureFormat {
+        self.surface_format
     }
 
     fn render(
         &mut self,
         encoder: &mut wgpu::CommandEncoder,
-        output_view: &wgpu::TextureView,
+        target: RenderTarget,
         ctx: &FrameContext,
     ) {
-        let width = ctx.surface_config.width;
-        let height = ctx.surface_config.height;
+        let (width, height) = target.size;
 
         let frame_start = Instant::now();
         let mut timings = RenderTimings::default();
 
-        self.gpu_sample = None;
-        if let Some(ts) = self.timestamp_query.as_mut()
-            && let Some(sample) = ts.begin_frame(ctx.device)
+        // Only the window's view is timed, so the queries describe one frame.
+        if ctx.primary
+            && let Some(timer) = self.gpu_timer.as_mut()
         {
-            self.gpu_sample = Some(sample);
+            timer.begin_frame(ctx.device);
         }
 
         let prep_start = Instant::now();
         self.ensure_screen_texture(ctx.device, width, height);
-        self.ensure_scene(ctx.device, ctx.world);
+        if ctx.primary {
+            let camera = ctx.camera.position + ctx.origin.as_vec3();
+            let center = BlockPos(camera.floor().as_ivec3()).chunk();
+            self.ensure_scene(ctx.device, ctx.world, center);
+        }
         timings.scene_ms = prep_start.elapsed().as_secs_f32() * 1000.0;
 
-        let (scene, compute_bind_group) = match (&self.scene, &self.compute_bind_group) {
-            (Some(scene), Some(bind_group)) => (scene, bind_group),
+        let key = match &self.scene {
+            Some(scene) if ctx.path_tracing => Some(AccumulationKey {
+                view_proj: ctx.projection.matrix() * ctx.camera.view_matrix(),
+                world_version: scene.world_version,
+                light: (ctx.lighting.sun_direction * LIGHT_KEY_STEPS)
+                    .round()
+                    .as_ivec3(),
+            }),
+            _ => None,
+        };
+        let Some((samples, history_write)) = self
+            .screens
+            .get_mut(&target.size)
+            .filter(|screen| screen.compute_bind_groups.is_some())
+            .map(|screen| screen.accumulation.next_sample(key))
+        else {
+            self.timings_valid &= !ctx.primary;
+            return;
+        };
+
+        let screen = self.screens.get(&target.size);
+        let (scene, screen, compute_bind_group) = match (&self.scene, screen) {
+            (Some(scene), Some(screen)) => match &screen.compute_bind_groups {
+                Some(bind_groups) => (scene, screen, &bind_groups[history_write]),
+                None => {
+                    self.timings_valid &= !ctx.primary;
+                    return;
+                }
+            },
             _ => {
-                self.timings_valid = false;
+                self.timings_valid &= !ctx.primary;
                 return;
             }
         };
 
-        timings.voxels = scene.grid.voxels.len() as u32;
+        let timer = self.gpu_timer.as_ref().filter(|_| ctx.primary);
+        timings.voxels = scene.grid.bricks.len() as u32;
         timings.solid_blocks = scene.grid.solid_count;
+        timings.traced_radius = scene.grid.traced_radius;
+        timings.samples = screen.accumulation.samples;
 
         let uniform_start = Instant::now();
-        self.update_uniforms(ctx.queue, ctx, &scene.grid);
+        let accumulation = [samples, key.is_some() as u32, 0, 0];
+        self.update_uniforms(ctx.queue, ctx, &scene.grid, target.size, accumulation);
+        let output = OutputUniform::new(ctx.gamma, self.surface_format);
+        ctx.queue
+            .write_buffer(&self.output_buffer, 0, bytemuck::bytes_of(&output));
+        let shafts = LightShaftUniform::new(
+            ctx.camera,
+            ctx.projection,
+            ctx.lighting.sun_direction,
+            ctx.light_shafts,
+        );
+        ctx.queue
+            .write_buffer(&self.shaft_buffer, 0, bytemuck::bytes_of(&shafts));
         timings.uniforms_ms = uniform_start.elapsed().as_secs_f32() * 1000.0;
 
+        // Only the window's view is counted; the others' counts are cleared
+        // before it runs.
+        if ctx.primary {
+            self.traversal.begin_frame(ctx.device, encoder);
+        }
         {
             let compute_start = Instant::now();
-            if let Some(ts) = self.timestamp_query.as_ref() {
-                ts.write_compute_start(encoder);
-            }
-            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
-                label: Some("Ray tracing compute pass"),
+            debug_group(encoder, "Ray tracing", |encoder| {
+                if let Some(timer) = timer {
+                    timer.start(encoder, GpuSpan::Compute);
+                }
+                let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                    label: Some("Ray tracing compute pass"),
+                });
+                compute_pass.set_pipeline(&self.compute.pipeline);
+                compute_pass.set_bind_group(0, compute_bind_group, &[]);
+
+                let workgroup_size = 8u32;
+                let dispatch_x = width.div_ceil(workgroup_size);
+                let dispatch_y = height.div_ceil(workgroup_size);
+
+                compute_pass.dispatch_workgroups(dispatch_x, dispatch_y, 1);
+                drop(compute_pass);
+                if let Some(timer) = timer {
+                    timer.end(encoder, GpuSpan::Compute);
+                }
             });
-            compute_pass.set_pipeline(&self.compute_pipeline);
-            compute_pass.set_bind_group(0, compute_bind_group, &[]);
-
-            let workgroup_size = 8u32;
-            let dispatch_x = width.div_ceil(workgroup_size);
-            let dispatch_y = height.div_ceil(workgroup_size);
-
-            compute_pass.dispatch_workgroups(dispatch_x, dispatch_y, 1);
-            drop(compute_pass);
-            if let Some(ts) = self.timestamp_query.as_ref() {
-                ts.write_compute_end(encoder);
-            }
             timings.compute_ms = compute_start.elapsed().as_secs_f32() * 1000.0;
         }
+        if ctx.primary {
+            self.traversal.resolve(encoder);
+        }
 
-        if self.last_log.elapsed().as_secs_f32() > 1.0 {
+        if ctx.primary && self.last_log.elapsed().as_secs_f32() > 1.0 {
             log::info!(
                 "Ray tracer: {}x{}, voxels {}x{}x{}",
                 width,
@@ -470,16 +668,14 @@ impl Renderer for RayTraceRenderer {
             self.last_log = Instant::now();
         }
 
-        let screen = self.screen.as_ref().expect("screen texture must exist");
-
         let present_start = Instant::now();
-        if let Some(ts) = self.timestamp_query.as_ref() {
-            ts.write_present_start(encoder);
+        if let Some(timer) = timer {
+            timer.start(encoder, GpuSpan::Present);
         }
         let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: Some("Ray traced present"),
             color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view: output_view,
+                view: target.view,
                 resolve_target: None,
                 ops: wgpu::Operations {
                     load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
@@ -489,27 +685,46 @@ impl Renderer for RayTraceRenderer {
             depth_stencil_attachment: None,
         });
 
-        render_pass.set_pipeline(&self.blit_pipeline);
+        render_pass.set_pipeline(&self.blit.pipeline);
         render_pass.set_bind_group(0, &screen.bind_group, &[]);
         render_pass.set_vertex_buffer(0, self.fullscreen_vertex.slice(..));
         render_pass.set_index_buffer(self.fullscreen_index.slice(..), wgpu::IndexFormat::Uint16);
         render_pass.draw_indexed(0..self.index_count, 0, 0..1);
         drop(render_pass);
-        if let Some(ts) = self.timestamp_query.as_mut() {
-            ts.write_present_end(encoder);
-            ts.resolve(encoder);
+        if !ctx.primary {
+            return;
+        }
+        if let Some(timer) = self.gpu_timer.as_mut() {
+            timer.end(encoder, GpuSpan::Present);
+            timer.resolve(encoder);
         }
         timings.present_ms = present_start.elapsed().as_secs_f32() * 1000.0;
 
         timings.total_ms = frame_start.elapsed().as_secs_f32() * 1000.0;
-        if let Some(sample) = self.gpu_sample {
-            timings.gpu_compute_ms = sample.compute_ms;
-            timings.gpu_present_ms = sample.present_ms;
+        if let Some(times) = self.gpu_timer.as_ref().and_then(GpuTimer::times) {
+            timings.gpu_compute_ms = times.compute_ms;
+            timings.gpu_present_ms = times.present_ms;
         }
+        timings.traversal = self.traversal.counts();
         self.last_timings = timings;
         self.timings_valid = true;
     }
 
+    fn update_materials(&mut self, queue: &wgpu::Queue, definitions: &[BlockDefinition]) {
+        let block_info_data = build_block_metadata(&self.atlas_layout, definitions);
+        queue.write_buffer(
+            &self.block_info_buffer,
+            0,
+            bytemuck::cast_slice(&block_info_data),
+        );
+        for screen in self.screens.values_mut() {
+            screen.accumulation.key = None;
+        }
+        // A block that starts or stops glowing changes the lamp list, which
+        // the next frame's scene rebuild picks up.
+        self.emissive = emissive_blocks(definitions);
+    }
+
     fn timings(&self) -> Option<RenderTimings> {
         if self.timings_valid {
             Some(self.last_timings)
@@ -523,143 +738,381 @@ struct ScreenTexture {
     _texture: wgpu::Texture,
     view: wgpu::TextureView,
     bind_group: wgpu::BindGroup,
-    size: (u32, u32),
+    /// Path-traced running means; each frame reads one and writes the other.
+    _history: [wgpu::Texture; 2],
+    history_views: [wgpu::TextureView; 2],
+    /// Indexed by the history texture written. `None` until the voxel scene
+    /// exists.
+    compute_bind_groups: Option<[wgpu::BindGroup; 2]>,
+    accumulation: Accumulation,
+}
+
+/// How far path tracing has converged for one screen texture.
+#[derive(Default)]
+struct Accumulation {
+    samples: u32,
+    /// The history texture holding the current mean.
+    latest: usize,
+    /// What the samples were taken of; any change starts over.
+    key: Option<AccumulationKey>,
+}
+
+impl Accumulation {
+    /// Starts a frame of `key`, or of plain ray tracing when `None`. Returns
+    /// how many samples the history already holds, 0 when starting over,
+    /// and which history texture this frame writes.
+    fn next_sample(&mut self, key: Option<AccumulationKey>) -> (u32, usize) {
+        if key.is_none() || key != self.key {
+            self.samples = 0;
+        }
+        self.key = key;
+        let samples = self.samples;
+        self.latest = 1 - self.latest;
+        if key.is_some() {
+            self.samples = (samples + 1).min(MAX_ACCUMULATED_SAMPLES);
+        }
+        (samples, self.latest)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+struct AccumulationKey {
+    view_proj: Mat4,
+    world_version: u64,
+    /// The direct light's direction, rounded by `LIGHT_KEY_STEPS`.
+    light: IVec3,
+}
+
+/// The storage buffers behind a [`VoxelGrid`].
+struct VoxelBuffers {
+    chunk_table: wgpu::Buffer,
+    bricks: wgpu::Buffer,
+    light_table: wgpu::Buffer,
+    light_bricks: wgpu::Buffer,
+    lamps: wgpu::Buffer,
 }
 
 struct VoxelScene {
     grid: VoxelGrid,
     chunk_count: usize,
     world_version: u64,
+    /// The block ids the lamp list was gathered for.
+    emissive: [bool; BLOCK_IDS],
+}
+
+/// Block ids in one chunk-sized brick; must match `BRICK_WORDS` in
+/// `raytrace_compute.wgsl`, which packs four ids per word.
+const BRICK_VOLUME: usize = CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE;
+
+/// Edge of the cells a chunk's occupancy bits cover, in blocks; must match
+/// `OCCUPANCY_CELL` in `raytrace_compute.wgsl`.
+const OCCUPANCY_CELL: usize = 4;
+const OCCUPANCY_CELLS: usize = CHUNK_SIZE / OCCUPANCY_CELL;
+
+/// One chunk's entry in the grid's table; must match `ChunkEntry` in
+/// `raytrace_compute.wgsl`.
+#[repr(C)]
+#[derive(Clone, Copy, Default, Pod, Zeroable)]
+struct GpuChunkEntry {
+    /// Brick index plus one, or 0 when the chunk has no brick.
+    brick: u32,
+    /// [`occupancy`] of the brick, low word first.
+    occupancy: [u32; 2],
 }
 
-#[derive(Clone, Copy, Default)]
-struct TimestampSample {
-    compute_ms: f32,
-    present_ms: f32,
+/// An emissive block in the grid; must match `Lamp` in
+/// `raytrace_compute.wgsl`.
+#[repr(C)]
+#[derive(Clone, Copy, Default, Pod, Zeroable)]
+struct GpuLamp {
+    /// Cell of the block, from the grid origin.
+    position: [u32; 3],
+    block: u32,
 }
 
+/// The loaded world as the ray tracer sees it, stored sparsely: a table over
+/// the chunk-aligned bounding box that points each chunk at its brick, and a
+/// brick of `BRICK_VOLUME` block ids for every chunk with a visible solid
+/// block. Memory grows with the chunks that hold something rather than with
+/// the bounding volume; an empty chunk costs one table entry.
+///
+/// The table also says which parts of each brick hold blocks, so rays cross
+/// empty chunks and empty quarters of chunks in one step instead of cell by
+/// cell.
+///
+/// The world's sky and block light is stored the same way, in a table and
+/// bricks of its own, with a brick for every chunk not entirely in open sky.
+/// Alongside it goes a list of the visible emissive blocks, which the shader
+/// casts shadow rays towards.
 struct VoxelGrid {
+    /// Minimum block corner of the bounding box, on a chunk boundary.
     origin: IVec3,
+    /// Size of the bounding box in blocks.
     size: IVec3,
-    stride_y: usize,
-    stride_z: usize,
-    voxels: Vec<BlockId>,
+    /// Table entries per row of chunks along x, and per xy layer.
+    table_stride_y: usize,
+    table_stride_z: usize,
+    chunk_table: Vec<GpuChunkEntry>,
+    /// Brick contents back to back, each in the chunk's own block order.
+    bricks: Vec<BlockId>,
+    /// Light brick index plus one per chunk, or 0 when the chunk is in full
+    /// sky light without block light.
+    light_table: Vec<u32>,
+    /// Light brick contents back to back, one cell per byte: sky light in
+    /// the high four bits and block light in the low four.
+    light_bricks: Vec<u8>,
+    /// Visible emissive blocks, nearest the camera first, at most
+    /// `MAX_LAMPS`.
+    lamps: Vec<GpuLamp>,
     solid_count: u32,
+    /// Chunks out from the camera the grid was cut down to because the
+    /// whole loaded world would overflow a storage buffer.
+    traced_radius: Option<i32>,
 }
 
-impl VoxelGrid {
-    fn from_world(world: &World) -> Option<Self> {
-        let mut min = IVec3::new(i32::MAX, i32::MAX, i32::MAX);
-        let mut max = IVec3::new(i32::MIN, i32::MIN, i32::MIN);
-        let mut has_chunks = false;
-
-        for (coord, _) in world.iter_chunks() {
-            has_chunks = true;
-            let base = chunk_min_corner(*coord);
-            let chunk_max = base + IVec3::splat(CHUNK_SIZE as i32) - IVec3::new(1, 1, 1);
-            min = min.min(base);
-            max = max.max(chunk_max);
+/// One loaded chunk's bricks, before they are placed in a [`VoxelGrid`].
+struct ChunkVoxels {
+    coord: ChunkPos,
+    /// Visible solid block ids, or `None` when there are none.
+    brick: Option<Vec<BlockId>>,
+    /// Light levels, or `None` when the chunk is in open sky.
+    light: Option<Vec<u8>>,
+    solid: u32,
+}
+
+impl ChunkVoxels {
+    fn new(coord: ChunkPos, chunk: &Chunk) -> Self {
+        let open_sky = chunk.sky_light().iter().all(|&level| level == MAX_LIGHT)
+            && chunk.block_light().iter().all(|&level| level == 0);
+        let light = (!open_sky).then(|| {
+            chunk
+                .sky_light()
+                .iter()
+                .zip(chunk.block_light())
+                .map(|(&sky, &block)| (sky << 4) | block)
+                .collect()
+        });
+
+        let mask = chunk.visible_mask();
+        let mask_has_visibility = mask.iter().any(|visible| *visible);
+        let mut brick = vec![BLOCK_AIR; BRICK_VOLUME];
+        let mut solid = 0u32;
+        for (index, block) in chunk.blocks().iter().enumerate() {
+            let kind = BlockKind::from_id(*block);
+            if !kind.is_solid() {
+                continue;
+            }
+
+            let is_visible = if mask_has_visibility {
+                mask.get(index).copied().unwrap_or(false)
+            } else {
+                // Fallback: mask not populated yet, keep solid blocks to avoid holes.
+                true
+            };
+
+            if is_visible && let Some(slot) = brick.get_mut(index) {
+                *slot = *block;
+                solid += 1;
+            }
         }
 
-        if !has_chunks {
-            return None;
+        Self {
+            coord,
+            brick: (solid > 0).then_some(brick),
+            light,
+            solid,
         }
+    }
 
-        let size = max - min + IVec3::new(1, 1, 1);
-        let size_x = size.x as usize;
-        let size_y = size.y as usize;
-        let size_z = size.z as usize;
-        let stride_y = size_x;
-        let stride_z = stride_y * size_y;
-        let mut voxels = vec![BLOCK_AIR; stride_z * size_z];
-        let mut solid_count = 0u32;
-        let mut has_visible_blocks = false;
-
-        for (coord, chunk) in world.iter_chunks() {
-            let mask = chunk.visible_mask();
-            let mask_has_visibility = mask.iter().any(|visible| *visible);
-            let base = chunk_min_corner(*coord);
-            for (index, block) in chunk.blocks().iter().enumerate() {
-                let kind = BlockKind::from_id(*block);
-                if !kind.is_solid() {
-                    continue;
-                }
+    /// Bricks this chunk adds to the block and light brick buffers.
+    fn brick_counts(&self) -> (u64, u64) {
+        (self.brick.is_some() as u64, self.light.is_some() as u64)
+    }
+}
 
-                let is_visible = if mask_has_visibility {
-                    mask.get(index).copied().unwrap_or(false)
-                } else {
-                    // Fallback: mask not populated yet, keep solid blocks to avoid holes.
-                    true
-                };
+/// How far apart two chunks are, in chunks along the furthest axis.
+fn chunk_distance(a: ChunkPos, b: ChunkPos) -> i32 {
+    (a.x - b.x)
+        .abs()
+        .max((a.y - b.y).abs())
+        .max((a.z - b.z).abs())
+}
 
-                if !is_visible {
-                    continue;
-                }
+/// The largest radius around `center` whose chunks' bricks fit in
+/// `max_buffer_size` bytes per buffer, or `None` when every chunk fits.
+fn fitting_radius(chunks: &[ChunkVoxels], center: ChunkPos, max_buffer_size: u64) -> Option<i32> {
+    let fits = |radius: i32| {
+        let (bricks, lights) = chunks
+            .iter()
+            .filter(|voxels| chunk_distance(voxels.coord, center) <= radius)
+            .map(ChunkVoxels::brick_counts)
+            .fold((0, 0), |(bricks, lights), (brick, light)| {
+                (bricks + brick, lights + light)
+            });
+        bricks.max(lights) * BRICK_VOLUME as u64 <= max_buffer_size
+    };
+    let farthest = chunks
+        .iter()
+        .map(|voxels| chunk_distance(voxels.coord, center))
+        .max()?;
+    if fits(farthest) {
+        return None;
+    }
+    Some(
+        (0..farthest)
+            .rev()
+            .find(|&radius| fits(radius))
+            .unwrap_or(0),
+    )
+}
 
-                let lx = (index % CHUNK_SIZE) as i32;
-                let temp = index / CHUNK_SIZE;
-                let lz = (temp % CHUNK_SIZE) as i32;
-                let ly = (temp / CHUNK_SIZE) as i32;
-
-                let world_pos = base + IVec3::new(lx, ly, lz);
-                let local = world_pos - min;
-
-                if local.x < 0
-                    || local.y < 0
-                    || local.z < 0
-                    || local.x as usize >= size_x
-                    || local.y as usize >= size_y
-                    || local.z as usize >= size_z
-                {
-                    continue;
-                }
+impl VoxelGrid {
+    /// The loaded chunks around `center` as a grid whose brick buffers fit
+    /// in `max_buffer_size` bytes each. When every chunk does not fit, only
+    /// those within the largest radius that does are kept. Block ids marked
+    /// in `emissive` are listed as lamps.
+    fn from_world(
+        world: &World,
+        center: ChunkPos,
+        max_buffer_size: u64,
+        emissive: &[bool; BLOCK_IDS],
+    ) -> Option<Self> {
+        let mut chunks: Vec<ChunkVoxels> = world
+            .iter_chunks()
+            .map(|(coord, chunk)| ChunkVoxels::new(*coord, chunk))
+            .collect();
+        let traced_radius = fitting_radius(&chunks, center, max_buffer_size);
+        if let Some(radius) = traced_radius {
+            chunks.retain(|voxels| chunk_distance(voxels.coord, center) <= radius);
+        }
+        if chunks.iter().all(|voxels| voxels.brick.is_none()) {
+            return None;
+        }
 
-                let idx =
-                    local.x as usize + local.y as usize * stride_y + local.z as usize * stride_z;
-                if voxels[idx] == BLOCK_AIR {
-                    voxels[idx] = *block;
-                    solid_count += 1;
-                    has_visible_blocks = true;
-                }
+        let min = chunks
+            .iter()
+            .map(|voxels| voxels.coord.min_block())
+            .reduce(IVec3::min)?;
+        let max = chunks
+            .iter()
+            .map(|voxels| voxels.coord.min_block())
+            .reduce(IVec3::max)?;
+
+        let extent = (max - min) / CHUNK_SIZE as i32 + IVec3::ONE;
+        let table_stride_y = extent.x as usize;
+        let table_stride_z = table_stride_y * extent.y as usize;
+        let mut chunk_table = vec![GpuChunkEntry::default(); table_stride_z * extent.z as usize];
+        let mut bricks = Vec::new();
+        let mut light_table = vec![0u32; chunk_table.len()];
+        let mut light_bricks = Vec::new();
+        let mut lamps = Vec::new();
+        let mut solid_count = 0u32;
+
+        for voxels in chunks {
+            let cell = (voxels.coord.min_block() - min) / CHUNK_SIZE as i32;
+            let entry = cell.x as usize
+                + cell.y as usize * table_stride_y
+                + cell.z as usize * table_stride_z;
+            if let Some(light) = voxels.light {
+                light_table[entry] = (light_bricks.len() / BRICK_VOLUME) as u32 + 1;
+                light_bricks.extend(light);
+            }
+            if let Some(brick) = voxels.brick {
+                let corner = voxels.coord.min_block() - min;
+                lamps.extend(
+                    brick
+                        .iter()
+                        .enumerate()
+                        .filter(|&(_, &block)| emissive[block as usize])
+                        .map(|(index, &block)| GpuLamp {
+                            position: (corner + LocalPos::from_index(index).as_ivec3())
+                                .as_uvec3()
+                                .to_array(),
+                            block: block as u32,
+                        }),
+                );
+                let occupancy = occupancy(&brick);
+                chunk_table[entry] = GpuChunkEntry {
+                    brick: (bricks.len() / BRICK_VOLUME) as u32 + 1,
+                    occupancy: [occupancy as u32, (occupancy >> 32) as u32],
+                };
+                bricks.extend(brick);
+                solid_count += voxels.solid;
             }
         }
 
-        if !has_visible_blocks {
-            return None;
-        }
+        let camera = center.min_block() - min + IVec3::splat(CHUNK_SIZE as i32 / 2);
+        lamps.sort_by_key(|lamp| {
+            (IVec3::from_array(lamp.position.map(|axis| axis as i32)) - camera).length_squared()
+        });
+        lamps.truncate(MAX_LAMPS);
 
         Some(Self {
             origin: min,
-            size,
-            stride_y,
-            stride_z,
-            voxels,
+            size: extent * CHUNK_SIZE as i32,
+            table_stride_y,
+            table_stride_z,
+            chunk_table,
+            bricks,
+            light_table,
+            light_bricks,
+            lamps,
             solid_count,
+            traced_radius,
         })
     }
 
-    fn pack_voxels(&self) -> Vec<u32> {
-        let total = self.voxels.len();
-        let words = total.div_ceil(4);
-        let mut packed = Vec::with_capacity(words);
-
-        for chunk in 0..words {
-            let mut word = 0u32;
-            for lane in 0..4 {
-                let index = chunk * 4 + lane;
-                if index >= total {
-                    break;
-                }
-                let value = self.voxels[index] as u32;
-                word |= value << (lane * 8);
-            }
-            packed.push(word);
+    /// Brick contents, four block ids per word.
+    fn pack_bricks(&self) -> Vec<u32> {
+        pack_bytes(&self.bricks)
+    }
+
+    /// Light brick contents, four cells per word. Holds one empty word when
+    /// every chunk is in open sky, since a storage buffer cannot be empty.
+    fn pack_light_bricks(&self) -> Vec<u32> {
+        if self.light_bricks.is_empty() {
+            return vec![0];
         }
+        pack_bytes(&self.light_bricks)
+    }
 
-        packed
+    /// The lamp list, or one unused entry when there are no lamps, since a
+    /// storage buffer cannot be empty.
+    fn lamp_entries(&self) -> Vec<GpuLamp> {
+        if self.lamps.is_empty() {
+            return vec![GpuLamp::default()];
+        }
+        self.lamps.clone()
     }
 }
 
+/// One bit per `OCCUPANCY_CELL`-sized cell of `brick`, set when the cell
+/// holds a block. Cells are numbered in the brick's own block order: x, then
+/// z, then y.
+fn occupancy(brick: &[BlockId]) -> u64 {
+    brick
+        .iter()
+        .enumerate()
+        .filter(|&(_, &block)| block != BLOCK_AIR)
+        .fold(0, |bits, (index, _)| {
+            let cell = LocalPos::from_index(index).as_ivec3() / OCCUPANCY_CELL as i32;
+            let bit = cell.x as usize
+                + (cell.z as usize + cell.y as usize * OCCUPANCY_CELLS) * OCCUPANCY_CELLS;
+            bits | 1 << bit
+        })
+}
+
+fn pack_bytes(bytes: &[u8]) -> Vec<u32> {
+    bytes
+        .chunks(4)
+        .map(|lanes| {
+            lanes.iter().enumerate().fold(0u32, |word, (lane, &byte)| {
+                word | (byte as u32) << (lane * 8)
+            })
+        })
+        .collect()
+}
+
 #[repr(C)]
 #[derive(Clone, Copy, Pod, Zeroable)]
 struct GpuBlockInfo {
@@ -672,15 +1125,36 @@ struct GpuBlockInfo {
     transmission: f32,
     ior: f32,
     transmission_tint: f32,
+    height: f32,
+    shape: u32,
+    face_tints: [u32; 6],
+    cutout: u32,
 }
 
-fn build_block_metadata() -> Vec<GpuBlockInfo> {
-    let mut entries = Vec::with_capacity(u8::MAX as usize + 1);
+/// Every block id, as indexed in the block metadata.
+const BLOCK_IDS: usize = u8::MAX as usize + 1;
+
+/// Which block ids give off at least one level of light, as
+/// `light::emission` rounds it, with `overrides` in place of the built-in
+/// definitions they cover.
+fn emissive_blocks(overrides: &[BlockDefinition]) -> [bool; BLOCK_IDS] {
+    std::array::from_fn(|id| {
+        let definition = overrides
+            .get(id)
+            .unwrap_or_else(|| block::block_definition(id as BlockId));
+        (definition.luminance * f32::from(MAX_LIGHT)).round() >= 1.0
+    })
+}
+
+fn build_block_metadata(atlas: &AtlasLayout, overrides: &[BlockDefinition]) -> Vec<GpuBlockInfo> {
+    let mut entries = Vec::with_capacity(BLOCK_IDS);
     for id in 0..=u8::MAX {
-        let definition = block::block_definition(id);
+        let definition = overrides
+            .get(id as usize)
+            .unwrap_or_else(|| block::block_definition(id));
         let mut face_tiles = [0u32; 6];
         for (idx, tile) in definition.face_tiles.iter().enumerate() {
-            face_tiles[idx] = encode_tile_id(*tile);
+            face_tiles[idx] = encode_tile_id(atlas.resolve(*tile));
         }
         entries.push(GpuBlockInfo {
             face_tiles,
@@ -692,6 +1166,10 @@ fn build_block_metadata() -> Vec<GpuBlockInfo> {
             transmission: definition.transmission,
             ior: definition.ior,
             transmission_tint: definition.transmission_tint,
+            height: definition.height,
+            shape: definition.shape.gpu_id(),
+            face_tints: definition.face_tints.map(|tint| tint.gpu_id()),
+            cutout: u32::from(definition.cutout),
         });
     }
     entries
@@ -712,6 +1190,36 @@ struct RayUniforms {
     grid_size: [u32; 4],
     stride: [u32; 4],
     atlas: [u32; 4],
+    moving: [u32; 4],
+    time: [f32; 4],
+    /// x: path-traced samples already in the history, y: 1 when path tracing.
+    accumulation: [u32; 4],
+    /// xyz: direction towards the sun, or the moon at night.
+    sun: [f32; 4],
+    /// rgb: colour and strength of the light along `sun`.
+    sun_color: [f32; 4],
+    /// rgb: the light open sky gives at this time of day.
+    ambient: [f32; 4],
+    /// xyz: direction towards the sun itself, drawn in the sky.
+    sky_sun: [f32; 4],
+    /// rgb: colour of the sky straight up and at the horizon.
+    sky_zenith: [f32; 4],
+    sky_horizon: [f32; 4],
+    /// xyz: the render origin, which voxels are relative to.
+    world_origin: [i32; 4],
+    /// x: most reflection or refraction bounces a secondary ray makes.
+    bounces: [u32; 4],
+    /// x: number of entries in the lamp list.
+    lamps: [u32; 4],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct GpuMovingBlock {
+    position: [f32; 3],
+    block: u32,
+    scale: f32,
+    _padding: [f32; 3],
 }
 
 fn compute_frustum_rays(inv_projection: Mat4, view_to_world: Mat4) -> [[f32; 4]; 4] {
@@ -736,113 +1244,6 @@ fn compute_frustum_rays(inv_projection: Mat4, view_to_world: Mat4) -> [[f32; 4];
     rays
 }
 
-struct TimestampQuery {
-    query_set: wgpu::QuerySet,
-    resolve_buffer: wgpu::Buffer,
-    readback_buffer: wgpu::Buffer,
-    pending: bool,
-    period: f32,
-}
-
-impl TimestampQuery {
-    fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Option<Self> {
-        if !device.features().contains(wgpu::Features::TIMESTAMP_QUERY) {
-            return None;
-        }
-
-        let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
-            label: Some("Ray trace timestamps"),
-            ty: wgpu::QueryType::Timestamp,
-            count: 4,
-        });
-
-        let size = (std::mem::size_of::<u64>() * 4) as u64;
-        let resolve_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Ray trace timestamp resolve buffer"),
-            size,
-            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
-            mapped_at_creation: false,
-        });
-
-        let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Ray trace timestamp readback buffer"),
-            size,
-            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
-
-        Some(Self {
-            query_set,
-            resolve_buffer,
-            readback_buffer,
-            pending: false,
-            period: queue.get_timestamp_period(),
-        })
-    }
-
-    fn begin_frame(&mut self, device: &wgpu::Device) -> Option<TimestampSample> {
-        if !self.pending {
-            return None;
-        }
-
-        let slice = self.readback_buffer.slice(..);
-        let (sender, receiver) = mpsc::channel();
-        slice.map_async(wgpu::MapMode::Read, move |res| {
-            let _ = sender.send(res);
-        });
-        device.poll(wgpu::Maintain::Wait);
-        receiver.recv().ok()?.ok()?;
-        let data = slice.get_mapped_range();
-        let values: &[u64] = bytemuck::cast_slice(&data);
-        if values.len() < 4 {
-            drop(data);
-            self.readback_buffer.unmap();
-            self.pending = false;
-            return None;
-        }
-
-        let compute_ticks = values[1].saturating_sub(values[0]);
-        let present_ticks = values[3].saturating_sub(values[2]);
-        drop(data);
-        self.readback_buffer.unmap();
-        self.pending = false;
-
-        let factor = self.period / 1_000_000.0;
-        Some(TimestampSample {
-            compute_ms: compute_ticks as f32 * factor,
-            present_ms: present_ticks as f32 * factor,
-        })
-    }
-
-    fn write_compute_start(&self, encoder: &mut wgpu::CommandEncoder) {
-        encoder.write_timestamp(&self.query_set, 0);
-    }
-
-    fn write_compute_end(&self, encoder: &mut wgpu::CommandEncoder) {
-        encoder.write_timestamp(&self.query_set, 1);
-    }
-
-    fn write_present_start(&self, encoder: &mut wgpu::CommandEncoder) {
-        encoder.write_timestamp(&self.query_set, 2);
-    }
-
-    fn write_present_end(&self, encoder: &mut wgpu::CommandEncoder) {
-        encoder.write_timestamp(&self.query_set, 3);
-    }
-
-    fn resolve(&mut self, encoder: &mut wgpu::CommandEncoder) {
-        encoder.resolve_query_set(&self.query_set, 0..4, &self.resolve_buffer, 0);
-        encoder.copy_buffer_to_buffer(
-            &self.resolve_buffer,
-            0,
-            &self.readback_buffer,
-            0,
-            (std::mem::size_of::<u64>() * 4) as u64,
-        );
-        self.pending = true;
-    }
-}
-
 fn create_fullscreen_quad(device: &wgpu::Device) -> (wgpu::Buffer, wgpu::Buffer, u32) {
     #[repr(C)]
     #[derive(Clone, Copy, Pod, Zeroable)]
@@ -887,6 +1288,239 @@ fn create_fullscreen_quad(device: &wgpu::Device) -> (wgpu::Buffer, wgpu::Buffer,
     (vertex_buffer, index_buffer, INDICES.len() as u32)
 }
 
+/// Layouts and pipelines shared by every ray tracer, built through the cache.
+struct BlitPipeline {
+    bind_group_layout: wgpu::BindGroupLayout,
+    pipeline: wgpu::RenderPipeline,
+}
+
+struct ComputePipeline {
+    bind_group_layout: wgpu::BindGroupLayout,
+    pipeline: wgpu::ComputePipeline,
+}
+
+impl RayTraceRenderer {
+    /// Compiles the compute and blit pipelines ahead of first use.
+    pub fn warm_up(
+        device: &wgpu::Device,
+        cache: &PipelineCache,
+        surface_format: wgpu::TextureFormat,
+    ) {
+        blit_pipeline(device, cache, surface_format);
+        compute_pipeline(device, cache);
+    }
+}
+
+fn blit_pipeline(
+    device: &wgpu::Device,
+    cache: &PipelineCache,
+    surface_format: wgpu::TextureFormat,
+) -> Arc<BlitPipeline> {
+    cache.get_or_create("raytrace.blit", Some(surface_format), || {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Ray traced blit bind group layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline = create_blit_pipeline(device, &bind_group_layout, surface_format);
+        BlitPipeline {
+            bind_group_layout,
+            pipeline,
+        }
+    })
+}
+
+fn compute_pipeline(device: &wgpu::Device, cache: &PipelineCache) -> Arc<ComputePipeline> {
+    cache.get_or_create("raytrace.compute", None, || {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Ray tracing compute bind group layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::StorageTexture {
+                        access: wgpu::StorageTextureAccess::WriteOnly,
+                        format: SCREEN_FORMAT,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: std::num::NonZeroU64::new(
+                            std::mem::size_of::<RayUniforms>() as u64,
+                        ),
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 4,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 5,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 6,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 7,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 8,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 9,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::StorageTexture {
+                        access: wgpu::StorageTextureAccess::WriteOnly,
+                        format: HISTORY_FORMAT,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 10,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 11,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 12,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 13,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline = create_compute_pipeline(device, &bind_group_layout);
+        ComputePipeline {
+            bind_group_layout,
+            pipeline,
+        }
+    })
+}
+
 fn create_blit_pipeline(
     device: &wgpu::Device,
     layout: &wgpu::BindGroupLayout,
@@ -964,3 +1598,62 @@ fn create_compute_pipeline(
         entry_point: "cs_main",
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid_chunk(x: i32, z: i32) -> ChunkVoxels {
+        ChunkVoxels {
+            coord: ChunkPos::new(x, 0, z),
+            brick: Some(vec![BlockKind::Stone.id(); BRICK_VOLUME]),
+            light: None,
+            solid: BRICK_VOLUME as u32,
+        }
+    }
+
+    #[test]
+    fn overflowing_worlds_are_traced_within_the_radius_that_fits() {
+        let chunks: Vec<ChunkVoxels> = (-2..=2)
+            .flat_map(|z| (-2..=2).map(move |x| solid_chunk(x, z)))
+            .collect();
+        let center = ChunkPos::new(0, 0, 0);
+        let brick_bytes = BRICK_VOLUME as u64;
+
+        assert_eq!(fitting_radius(&chunks, center, 25 * brick_bytes), None);
+        // The 3x3 square around the center fits, the 5x5 one does not.
+        assert_eq!(fitting_radius(&chunks, center, 24 * brick_bytes), Some(1));
+        assert_eq!(fitting_radius(&chunks, center, 9 * brick_bytes), Some(1));
+        assert_eq!(fitting_radius(&chunks, center, 8 * brick_bytes), Some(0));
+    }
+
+    #[test]
+    fn occupancy_marks_the_cells_holding_blocks() {
+        let mut brick = vec![BLOCK_AIR; BRICK_VOLUME];
+        assert_eq!(occupancy(&brick), 0);
+        brick[LocalPos::new(0, 0, 0).index()] = BlockKind::Stone.id();
+        brick[LocalPos::new(5, 0, 0).index()] = BlockKind::Stone.id();
+        brick[LocalPos::new(0, 0, 4).index()] = BlockKind::Stone.id();
+        brick[LocalPos::new(15, 15, 15).index()] = BlockKind::Glass.id();
+        assert_eq!(occupancy(&brick), 1 | 1 << 1 | 1 << 4 | 1 << 63);
+    }
+
+    #[test]
+    fn lamps_follow_the_current_luminance() {
+        let lamp = |kind: BlockKind, emissive: &[bool; BLOCK_IDS]| emissive[kind.id() as usize];
+        let built_in = emissive_blocks(&[]);
+        assert!(lamp(BlockKind::Lamp, &built_in));
+        assert!(lamp(BlockKind::SignalLamp(true), &built_in));
+        assert!(!lamp(BlockKind::SignalLamp(false), &built_in));
+        assert!(!lamp(BlockKind::Stone, &built_in));
+
+        let mut definitions: Vec<BlockDefinition> = (0..=u8::MAX)
+            .map(|id| *block::block_definition(id))
+            .collect();
+        definitions[BlockKind::Stone.id() as usize].luminance = 0.5;
+        definitions[BlockKind::Lamp.id() as usize].luminance = 0.0;
+        let edited = emissive_blocks(&definitions);
+        assert!(lamp(BlockKind::Stone, &edited));
+        assert!(!lamp(BlockKind::Lamp, &edited));
+    }
+}
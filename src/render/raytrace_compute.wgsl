@@ -5,16 +5,102 @@ struct RayUniforms {
     grid_size: vec4<u32>,
     stride: vec4<u32>,
     atlas: vec4<u32>,
+    // x: number of entries in `moving_blocks`.
+    moving: vec4<u32>,
+    // x: seconds since the world was opened.
+    time: vec4<f32>,
+    // x: path-traced samples already in `history_in`, y: 1 when path tracing.
+    accumulation: vec4<u32>,
+    // xyz: direction towards the sun, or the moon at night.
+    sun: vec4<f32>,
+    // rgb: colour and strength of the light along `sun`.
+    sun_color: vec4<f32>,
+    // rgb: the light open sky gives at this time of day.
+    ambient: vec4<f32>,
+    // xyz: direction towards the sun itself, drawn in the sky.
+    sky_sun: vec4<f32>,
+    sky_zenith: vec4<f32>,
+    sky_horizon: vec4<f32>,
+    // xyz: the render origin, which voxels are relative to.
+    world_origin: vec4<i32>,
+    // x: most reflection or refraction bounces a secondary ray makes.
+    bounces: vec4<u32>,
+    // x: number of entries in `lamps`.
+    lamps: vec4<u32>,
 };
 
 @group(0) @binding(0)
-var target_image: texture_storage_2d<rgba8unorm, write>;
+var target_image: texture_storage_2d<rgba16float, write>;
 
 @group(0) @binding(1)
 var<uniform> uniforms: RayUniforms;
 
+// The world is stored sparsely: `chunk_table` covers the chunk-aligned grid
+// bounds with one entry per chunk, holding its brick index plus one, or 0 for
+// a chunk without blocks. Each brick holds a chunk's block ids, four per word.
+// Must match `GpuChunkEntry` in `raytrace.rs`.
+struct ChunkEntry {
+    brick: u32,
+    // One bit per `OCCUPANCY_CELL`-sized cell of the brick, set when the cell
+    // holds a block; cells in block order.
+    occupancy_low: u32,
+    occupancy_high: u32,
+};
+
 @group(0) @binding(2)
-var<storage, read> voxels: array<u32>;
+var<storage, read> chunk_table: array<ChunkEntry>;
+
+@group(0) @binding(7)
+var<storage, read> bricks: array<u32>;
+
+// The world's light is stored the same way: `light_table` holds each chunk's
+// light brick index plus one, or 0 for a chunk in full sky light without
+// block light, and each light brick holds a byte per cell, four per word,
+// with sky light in the high four bits and block light in the low four.
+@group(0) @binding(10)
+var<storage, read> light_table: array<u32>;
+
+@group(0) @binding(11)
+var<storage, read> light_bricks: array<u32>;
+
+// The visible emissive blocks, nearest the camera first, which surfaces lit
+// by block light cast shadow rays towards. Must match `GpuLamp` in
+// `raytrace.rs`.
+struct Lamp {
+    // Cell of the block, from the grid origin.
+    position: vec3<u32>,
+    block: u32,
+};
+
+@group(0) @binding(13)
+var<storage, read> lamps: array<Lamp>;
+
+// Must match `CHUNK_SIZE` in `world/mod.rs`.
+const CHUNK_SIZE: u32 = 16u;
+const BRICK_WORDS: u32 = 1024u;
+// Must match `OCCUPANCY_CELL` in `raytrace.rs`.
+const OCCUPANCY_CELL: u32 = 4u;
+
+// Traversal work, read back for the timings. Must match `GpuCounters` in
+// `traversal.rs`.
+struct TraversalCounters {
+    steps_low: atomic<u32>,
+    steps_high: atomic<u32>,
+    rays: atomic<u32>,
+    skips: atomic<u32>,
+};
+
+@group(0) @binding(12)
+var<storage, read_write> traversal: TraversalCounters;
+
+// This invocation's counts, gathered per workgroup before they reach
+// `traversal` so the global atomics see one add per group.
+var<private> ray_steps: u32;
+var<private> ray_count: u32;
+var<private> ray_skips: u32;
+var<workgroup> group_steps: atomic<u32>;
+var<workgroup> group_rays: atomic<u32>;
+var<workgroup> group_skips: atomic<u32>;
 
 struct BlockInfo {
     face_tiles: array<u32, 6>,
@@ -26,6 +112,10 @@ struct BlockInfo {
     transmission: f32,
     ior: f32,
     transmission_tint: f32,
+    height: f32,
+    shape: u32,
+    face_tints: array<u32, 6>,
+    cutout: u32,
 };
 
 @group(0) @binding(3)
@@ -37,12 +127,57 @@ var block_atlas: texture_2d<f32>;
 @group(0) @binding(5)
 var atlas_sampler: sampler;
 
-const SUN_DIRECTION: vec3<f32> = vec3<f32>(0.2795085, 0.8385254, 0.4658469);
+// A block drawn off the grid: sliding between cells (pushed by a piston) or
+// part of an entity's model, shrunken to `scale` for dropped items.
+struct MovingBlock {
+    position: vec3<f32>,
+    block: u32,
+    scale: f32,
+};
+
+@group(0) @binding(6)
+var<storage, read> moving_blocks: array<MovingBlock>;
+
+// Running mean of the path-traced samples so far, and where this frame's
+// updated mean goes; the two swap every frame.
+@group(0) @binding(8)
+var history_in: texture_2d<f32>;
+
+@group(0) @binding(9)
+var history_out: texture_storage_2d<rgba32float, write>;
+
 const PI: f32 = 3.14159265359;
-const MAX_SPECULAR_BOUNCES: u32 = 2u;
 const ROUGH_SPECULAR_LIMIT: f32 = 0.4;
 const DIFFUSE_SAMPLE_WEIGHT: f32 = 0.6;
-const MAX_TRANSMISSION_BOUNCES: u32 = 2u;
+const PATH_MAX_BOUNCES: u32 = 5u;
+// Bounces before Russian roulette may end a path.
+const PATH_MIN_BOUNCES: u32 = 2u;
+// Colour light fades to while travelling through water, and how fast.
+const WATER_FOG_COLOR: vec3<f32> = vec3<f32>(0.04, 0.16, 0.26);
+const WATER_FOG_DENSITY: f32 = 0.12;
+// Distance used for rays through water that hit nothing.
+const WATER_FOG_FAR: f32 = 1000.0;
+// How far ripples tilt a water surface's normal.
+const RIPPLE_STRENGTH: f32 = 0.2;
+// Cosine of the angular radius of the sun's and moon's discs, their colours
+// and the haze around the sun, as in `sky_dome.wgsl`.
+const SUN_DISC: f32 = 0.9995;
+const MOON_DISC: f32 = 0.9997;
+const SUN_COLOR: vec3<f32> = vec3<f32>(4.0, 3.6, 3.0);
+const MOON_COLOR: vec3<f32> = vec3<f32>(0.6, 0.65, 0.75);
+const SUN_GLOW: f32 = 0.35;
+// Brightness of the sun's reflection on water.
+const SUN_GLINT: f32 = 4.0;
+// Must match `MAX_LIGHT` and `LEVEL_FALLOFF` in `light.rs`.
+const MAX_LIGHT: u32 = 15u;
+const LIGHT_FALLOFF: f32 = 0.8;
+// Mirrors `BLOCK_LIGHT_COLOR` in `shader.wgsl`.
+const BLOCK_LIGHT_COLOR: vec3<f32> = vec3<f32>(1.0, 0.85, 0.65);
+// Shadow rays per shaded point, one to each of its brightest lamps.
+const LAMP_RAYS: u32 = 4u;
+// Width of the part of a lamp's cell shadow rays aim across; wider lamps
+// cast wider penumbrae.
+const LAMP_SIZE: f32 = 0.9;
 
 fn lerp_vec3(a: vec3<f32>, b: vec3<f32>, t: f32) -> vec3<f32> {
     return a + t * (b - a);
@@ -61,13 +196,16 @@ fn decode_tile(tile: u32) -> vec2<u32> {
 fn atlas_coords(tile: u32, uv: vec2<f32>) -> vec2<f32> {
     let coords = decode_tile(tile);
     let tile_size = f32(uniforms.atlas.x);
-    let atlas_width = f32(uniforms.atlas.y);
-    let atlas_height = f32(uniforms.atlas.z);
-    let pixel = vec2<f32>(
-        f32(coords.x) * tile_size + uv.x * (tile_size - 1.0) + 0.5,
-        f32(coords.y) * tile_size + uv.y * (tile_size - 1.0) + 0.5,
-    );
-    return vec2<f32>(pixel.x / atlas_width, pixel.y / atlas_height);
+    let atlas_size = vec2<f32>(f32(uniforms.atlas.y), f32(uniforms.atlas.z));
+    let padding = f32(uniforms.atlas.w);
+    let stride = tile_size + 2.0 * padding;
+    let pixel = vec2<f32>(f32(coords.x), f32(coords.y)) * stride + padding + fract(uv) * tile_size;
+    return pixel / atlas_size;
+}
+
+fn sample_tile_alpha(tile: u32, uv: vec2<f32>) -> f32 {
+    let coords = atlas_coords(tile, uv);
+    return textureSampleLevel(block_atlas, atlas_sampler, coords, 0.0).a;
 }
 
 fn sample_tile(tile: u32, uv: vec2<f32>) -> vec3<f32> {
@@ -75,12 +213,61 @@ fn sample_tile(tile: u32, uv: vec2<f32>) -> vec3<f32> {
     return textureSampleLevel(block_atlas, atlas_sampler, coords, 0.0).rgb;
 }
 
+// Mirrors `biome::climate_at`; returns (temperature, humidity).
+fn climate_at(x: i32, z: i32) -> vec2<f32> {
+    let fx = f32(x) / 96.0;
+    let fz = f32(z) / 96.0;
+    let temperature = 0.5 + 0.3 * sin(fx * PI * 0.5) + 0.2 * cos(fz * PI * 0.35 + 1.3);
+    let humidity = 0.5 + 0.3 * sin(fz * PI * 0.45) + 0.2 * cos((fx + fz) * PI * 0.3);
+    return clamp(vec2<f32>(temperature, humidity), vec2<f32>(0.0), vec2<f32>(1.0));
+}
+
+// Mirrors `biome::tint_color`; tint ids match `BiomeTint::gpu_id`.
+fn biome_tint(tint: u32, voxel: vec3<i32>) -> vec3<f32> {
+    if tint == 0u {
+        return vec3<f32>(1.0);
+    }
+    var cold = vec3<f32>(0.78, 0.92, 0.86);
+    var dry = vec3<f32>(1.08, 0.96, 0.62);
+    var lush = vec3<f32>(0.82, 1.05, 0.72);
+    if tint == 2u {
+        cold = vec3<f32>(0.72, 0.88, 0.84);
+        dry = vec3<f32>(1.0, 0.9, 0.55);
+        lush = vec3<f32>(0.7, 1.0, 0.62);
+    }
+    let climate = climate_at(
+        voxel.x + uniforms.world_origin.x,
+        voxel.z + uniforms.world_origin.z,
+    );
+    let warm = climate.x;
+    let wet = climate.y * warm;
+    return cold * (1.0 - warm) + dry * (warm - wet) + lush * wet;
+}
+
 fn schlick(f0: f32, cos_theta: f32) -> f32 {
     let base = saturate(1.0 - cos_theta);
     let factor = base * base * base * base * base;
     return f0 + (1.0 - f0) * factor;
 }
 
+fn lattice_value(cell: vec2<i32>) -> f32 {
+    let hashed = hash_u32((u32(cell.x) * 0x8da6b343u) ^ (u32(cell.y) * 0xd8163841u));
+    return f32(hashed & 0xFFFFu) / 65535.0;
+}
+
+// Smoothly interpolated lattice noise in [0, 1].
+fn value_noise(p: vec2<f32>) -> f32 {
+    let cell = floor(p);
+    let f = p - cell;
+    let u = f * f * (3.0 - 2.0 * f);
+    let c = vec2<i32>(cell);
+    let a = lattice_value(c);
+    let b = lattice_value(c + vec2<i32>(1, 0));
+    let d = lattice_value(c + vec2<i32>(0, 1));
+    let e = lattice_value(c + vec2<i32>(1, 1));
+    return mix(mix(a, b, u.x), mix(d, e, u.x), u.y);
+}
+
 fn hash_u32(value: u32) -> u32 {
     var x = value;
     x = (x ^ (x >> 17u)) * 0xed5ad4bbu;
@@ -151,7 +338,7 @@ fn face_index(normal: vec3<f32>) -> u32 {
 }
 
 fn face_uv(normal: vec3<f32>, local: vec3<f32>) -> vec2<f32> {
-    let clamped = clamp(local, vec3<f32>(0.0), vec3<f32>(0.999));
+    let clamped = clamp(local, vec3<f32>(0.001), vec3<f32>(0.999));
     if normal.x > 0.5 {
         return vec2<f32>(clamped.z, 1.0 - clamped.y);
     }
@@ -189,37 +376,230 @@ fn tile_for_face(info: BlockInfo, face: u32) -> u32 {
     return info.face_tiles[5u];
 }
 
-fn voxel_count() -> u32 {
-    return uniforms.stride.y * uniforms.grid_size.z;
-}
-
-fn voxel_index(coord: vec3<i32>) -> u32 {
-    let origin = uniforms.grid_origin.xyz;
-    let local = coord - origin;
-    if any(local < vec3<i32>(0)) {
-        return voxel_count();
+fn tint_for_face(info: BlockInfo, face: u32) -> u32 {
+    if face == 0u {
+        return info.face_tints[0u];
+    }
+    if face == 1u {
+        return info.face_tints[1u];
+    }
+    if face == 2u {
+        return info.face_tints[2u];
     }
-    let size = uniforms.grid_size.xyz;
-    let lx = u32(local.x);
-    let ly = u32(local.y);
-    let lz = u32(local.z);
-    if lx >= size.x || ly >= size.y || lz >= size.z {
-        return voxel_count();
+    if face == 3u {
+        return info.face_tints[3u];
     }
-    let stride_y = uniforms.stride.x;
-    let stride_z = uniforms.stride.y;
-    return lx + ly * stride_y + lz * stride_z;
+    if face == 4u {
+        return info.face_tints[4u];
+    }
+    return info.face_tints[5u];
 }
 
 fn sample_block(coord: vec3<i32>) -> u32 {
-    let idx = voxel_index(coord);
-    if idx >= voxel_count() {
+    let offset = coord - uniforms.grid_origin.xyz;
+    if any(offset < vec3<i32>(0)) {
+        return 0u;
+    }
+    let local = vec3<u32>(offset);
+    if any(local >= uniforms.grid_size.xyz) {
         return 0u;
     }
-    let word_index = idx >> 2u;
-    let lane = (idx & 3u) * 8u;
-    let packed = voxels[word_index];
-    return (packed >> lane) & 0xFFu;
+    let chunk = local / CHUNK_SIZE;
+    let entry = chunk_table[chunk.x + chunk.y * uniforms.stride.x + chunk.z * uniforms.stride.y].brick;
+    if entry == 0u {
+        return 0u;
+    }
+    // Same block order as `Chunk::blocks`: x, then z, then y.
+    let within = local % CHUNK_SIZE;
+    let idx = within.x + (within.z + within.y * CHUNK_SIZE) * CHUNK_SIZE;
+    let packed = bricks[(entry - 1u) * BRICK_WORDS + (idx >> 2u)];
+    return (packed >> ((idx & 3u) * 8u)) & 0xFFu;
+}
+
+// Edge of the empty box, aligned to the grid, that `coord` lies in: a whole
+// chunk without a brick, or an occupancy cell without blocks. 1 when the
+// cell may hold a block, which the ray has to look at.
+fn empty_span(coord: vec3<i32>) -> i32 {
+    let offset = coord - uniforms.grid_origin.xyz;
+    if any(offset < vec3<i32>(0)) {
+        return 1;
+    }
+    let local = vec3<u32>(offset);
+    if any(local >= uniforms.grid_size.xyz) {
+        return 1;
+    }
+    let chunk = local / CHUNK_SIZE;
+    let entry = chunk_table[chunk.x + chunk.y * uniforms.stride.x + chunk.z * uniforms.stride.y];
+    if entry.brick == 0u {
+        return i32(CHUNK_SIZE);
+    }
+    let cells = CHUNK_SIZE / OCCUPANCY_CELL;
+    let cell = (local % CHUNK_SIZE) / OCCUPANCY_CELL;
+    let bit = cell.x + (cell.z + cell.y * cells) * cells;
+    let word = select(entry.occupancy_low, entry.occupancy_high, bit >= 32u);
+    if ((word >> (bit & 31u)) & 1u) == 0u {
+        return i32(OCCUPANCY_CELL);
+    }
+    return 1;
+}
+
+// Sky (x) and block (y) light levels of the cell at `coord`; full sky light
+// and no block light outside the stored chunks.
+fn sample_light(coord: vec3<i32>) -> vec2<u32> {
+    let open_sky = vec2<u32>(MAX_LIGHT, 0u);
+    let offset = coord - uniforms.grid_origin.xyz;
+    if any(offset < vec3<i32>(0)) {
+        return open_sky;
+    }
+    let local = vec3<u32>(offset);
+    if any(local >= uniforms.grid_size.xyz) {
+        return open_sky;
+    }
+    let chunk = local / CHUNK_SIZE;
+    let entry = light_table[chunk.x + chunk.y * uniforms.stride.x + chunk.z * uniforms.stride.y];
+    if entry == 0u {
+        return open_sky;
+    }
+    let within = local % CHUNK_SIZE;
+    let idx = within.x + (within.z + within.y * CHUNK_SIZE) * CHUNK_SIZE;
+    let packed = light_bricks[(entry - 1u) * BRICK_WORDS + (idx >> 2u)];
+    let cell = (packed >> ((idx & 3u) * 8u)) & 0xFFu;
+    return vec2<u32>(cell >> 4u, cell & 0xFu);
+}
+
+// Brightness factor of a light level, as `light::brightness`.
+fn light_brightness(level: u32) -> f32 {
+    if level == 0u {
+        return 0.0;
+    }
+    return pow(LIGHT_FALLOFF, f32(MAX_LIGHT - min(level, MAX_LIGHT)));
+}
+
+fn cell_light(coord: vec3<i32>) -> vec2<f32> {
+    let levels = sample_light(coord);
+    return vec2<f32>(light_brightness(levels.x), light_brightness(levels.y));
+}
+
+// Light level a block gives off, as `light::emission`.
+fn emission_level(block: u32) -> f32 {
+    return clamp(round(block_data[block].luminance * f32(MAX_LIGHT)), 0.0, f32(MAX_LIGHT));
+}
+
+// Light from the lamps reaching `position` on a surface facing `normal`,
+// where the world's block light is `block_light`. Each lamp fades with
+// distance the way block light fades per cell, and the brightest
+// `LAMP_RAYS` of those in reach cast a shadow ray towards a random point of
+// their cell, so shadows soften into a penumbra as samples accumulate.
+// Block light marks everywhere a lamp can reach, so unlit points cost
+// nothing.
+fn lamp_light(position: vec3<f32>, normal: vec3<f32>, block_light: f32, seed: vec3<u32>) -> vec3<f32> {
+    if block_light <= 0.0 || uniforms.lamps.x == 0u {
+        return vec3<f32>(0.0);
+    }
+
+    var chosen = array<u32, LAMP_RAYS>(0u, 0u, 0u, 0u);
+    var weights = array<f32, LAMP_RAYS>(0.0, 0.0, 0.0, 0.0);
+    for (var index = 0u; index < uniforms.lamps.x; index = index + 1u) {
+        let lamp = lamps[index];
+        let center = vec3<f32>(vec3<i32>(lamp.position) + uniforms.grid_origin.xyz) + vec3<f32>(0.5);
+        let to_lamp = center - position;
+        let distance = length(to_lamp);
+        let level = emission_level(lamp.block) - distance;
+        let facing = dot(normal, to_lamp) / max(distance, 1e-4);
+        if level <= 0.0 || facing <= 0.0 {
+            continue;
+        }
+        let weight = pow(LIGHT_FALLOFF, f32(MAX_LIGHT) - level) * facing;
+        // Replace the dimmest of the lamps kept so far.
+        var dimmest = 0u;
+        for (var slot = 1u; slot < LAMP_RAYS; slot = slot + 1u) {
+            if weights[slot] < weights[dimmest] {
+                dimmest = slot;
+            }
+        }
+        if weight > weights[dimmest] {
+            chosen[dimmest] = index;
+            weights[dimmest] = weight;
+        }
+    }
+
+    var light = vec3<f32>(0.0);
+    let origin = position + normal * 1e-3;
+    for (var slot = 0u; slot < LAMP_RAYS; slot = slot + 1u) {
+        if weights[slot] <= 0.0 {
+            continue;
+        }
+        let lamp = lamps[chosen[slot]];
+        let voxel = vec3<i32>(lamp.position) + uniforms.grid_origin.xyz;
+        let xi = random_vec2(seed, 41u + slot * 13u);
+        let zeta = random_scalar(seed, 47u + slot * 13u);
+        let offset = (vec3<f32>(xi, zeta) - vec3<f32>(0.5)) * LAMP_SIZE;
+        let target_point = vec3<f32>(voxel) + vec3<f32>(0.5) + offset;
+        let hit = trace_ray(origin, normalize(target_point - origin));
+        if hit.block == 0u || all(hit.voxel == voxel) {
+            light += weights[slot] * BLOCK_LIGHT_COLOR;
+        }
+    }
+    return light;
+}
+
+// `material` with the lamps' light, cast by shadow rays, in place of the
+// block light it was shaded with.
+fn with_lamp_light(material: MaterialInfo, seed: vec3<u32>) -> MaterialInfo {
+    if material.diffuse <= 0.0 || material.light.y <= 0.0 || uniforms.lamps.x == 0u {
+        return material;
+    }
+    let sun = max(dot(material.normal, uniforms.sun.xyz), 0.0) * material.light.x * uniforms.sun_color.rgb;
+    let flood = max(sun, material.light.y * BLOCK_LIGHT_COLOR);
+    let traced = max(sun, lamp_light(material.position, material.normal, material.light.y, seed));
+    var lit = material;
+    lit.direct += material.albedo * material.diffuse * (traced - flood);
+    return lit;
+}
+
+// As `light::blocks_light`.
+fn blocks_light(block: u32) -> bool {
+    let info = block_data[block];
+    return block != 0u && info.shape == SHAPE_CUBE && info.height >= 1.0 && info.transmission == 0.0;
+}
+
+// Sky (x) and block (y) light brightness at `point` on the face of `voxel`
+// facing `normal`: the light of the cells in front of the face, blended
+// between their centres. As in the raster mesher, cells that stop light are
+// left out, and so is the diagonal one when both cells beside it are.
+fn surface_light(voxel: vec3<i32>, normal: vec3<f32>, point: vec3<f32>) -> vec2<f32> {
+    let axis = vec3<i32>(round(normal));
+    let outer = voxel + axis;
+    var u = vec3<i32>(1, 0, 0);
+    var v = vec3<i32>(0, 0, 1);
+    if axis.x != 0 {
+        u = vec3<i32>(0, 1, 0);
+    } else if axis.z != 0 {
+        v = vec3<i32>(0, 1, 0);
+    }
+    // Position relative to the centre of `outer`, across the face.
+    let rel = point - (vec3<f32>(outer) + vec3<f32>(0.5));
+    let du = dot(rel, vec3<f32>(u));
+    let dv = dot(rel, vec3<f32>(v));
+    let side_u = outer + u * select(-1, 1, du >= 0.0);
+    let side_v = outer + v * select(-1, 1, dv >= 0.0);
+    let diagonal = side_u + (side_v - outer);
+    let tu = clamp(abs(du), 0.0, 0.5);
+    let tv = clamp(abs(dv), 0.0, 0.5);
+
+    let blocked_u = blocks_light(sample_block(side_u));
+    let blocked_v = blocks_light(sample_block(side_v));
+    let blocked_diagonal = blocks_light(sample_block(diagonal)) || (blocked_u && blocked_v);
+    let w_outer = (1.0 - tu) * (1.0 - tv);
+    let w_u = select(tu * (1.0 - tv), 0.0, blocked_u);
+    let w_v = select((1.0 - tu) * tv, 0.0, blocked_v);
+    let w_diagonal = select(tu * tv, 0.0, blocked_diagonal);
+
+    var light = cell_light(outer) * w_outer;
+    light += cell_light(side_u) * w_u;
+    light += cell_light(side_v) * w_v;
+    light += cell_light(diagonal) * w_diagonal;
+    return light / (w_outer + w_u + w_v + w_diagonal);
 }
 
 fn intersect_aabb(origin: vec3<f32>, dir: vec3<f32>, min: vec3<f32>, max: vec3<f32>) -> vec2<f32> {
@@ -354,11 +734,18 @@ fn refract_snell(incident: vec3<f32>, normal: vec3<f32>, eta_i: f32, eta_t: f32)
     return eta * incident - (eta * cos_i + sqrt(k)) * n;
 }
 
+// As `sky_color` in `sky_dome.wgsl`, so both renderers draw the same sky.
 fn sky(dir: vec3<f32>) -> vec3<f32> {
-    let t = clamp(dir.y * 0.5 + 0.5, 0.0, 1.0);
-    let horizon = vec3<f32>(0.4, 0.5, 0.7);
-    let zenith = vec3<f32>(0.05, 0.09, 0.15);
-    return lerp_vec3(horizon, zenith, t);
+    let up = saturate(dir.y);
+    let below = saturate(-dir.y * 4.0);
+    let horizon = uniforms.sky_horizon.rgb;
+    var color = lerp_vec3(horizon, uniforms.sky_zenith.rgb, sqrt(up)) * (1.0 - below * 0.5);
+    let toward_sun = dot(dir, uniforms.sky_sun.xyz);
+    let above = smoothstep(-0.02, 0.02, dir.y);
+    color += horizon * pow(saturate(toward_sun), 8.0) * SUN_GLOW;
+    color += SUN_COLOR * smoothstep(SUN_DISC - 0.0002, SUN_DISC, toward_sun) * above;
+    color += MOON_COLOR * smoothstep(MOON_DISC - 0.0002, MOON_DISC, -toward_sun) * above;
+    return color;
 }
 
 struct HitResult {
@@ -366,6 +753,10 @@ struct HitResult {
     voxel: vec3<i32>,
     normal: vec3<f32>,
     travel: f32,
+    // Offset of the block's box from `voxel`; only moving blocks are off-grid.
+    offset: vec3<f32>,
+    // Edge length of the block's box; only moving blocks are scaled.
+    scale: f32,
 }
 
 struct SurfaceSample {
@@ -389,14 +780,179 @@ struct MaterialInfo {
     transmission: f32,
     ior: f32,
     transmission_tint: f32,
-    voxel: vec3<i32>,
+    block_min: vec3<f32>,
+    block_scale: f32,
+    // Sky (x) and block (y) light brightness reaching the surface.
+    light: vec2<f32>,
 }
 
 fn miss_hit() -> HitResult {
-    return HitResult(0u, vec3<i32>(0, 0, 0), vec3<f32>(0.0, 0.0, 0.0), 0.0);
+    return HitResult(0u, vec3<i32>(0, 0, 0), vec3<f32>(0.0, 0.0, 0.0), 0.0, vec3<f32>(0.0), 1.0);
+}
+
+const SHAPE_CUBE: u32 = 0u;
+const SHAPE_CROSS: u32 = 1u;
+const SHAPE_LIQUID: u32 = 2u;
+const CUTOUT_ALPHA: f32 = 0.5;
+
+// Texture coordinates on a cross quad: u runs along x, v down from the top.
+fn cross_uv(local: vec3<f32>) -> vec2<f32> {
+    let clamped = clamp(local, vec3<f32>(0.001), vec3<f32>(0.999));
+    return vec2<f32>(clamped.x, 1.0 - clamped.y);
+}
+
+// Intersects the two alpha-tested diagonal quads of a cross-shaped block.
+fn resolve_cross_hit(block: u32, voxel: vec3<i32>, origin: vec3<f32>, dir: vec3<f32>) -> HitResult {
+    let info = block_data[block];
+    let box_min = vec3<f32>(f32(voxel.x), f32(voxel.y), f32(voxel.z));
+    let local_origin = origin - box_min;
+    // Planes x - z = 0 and x + z = 1 in voxel-local space.
+    var normals = array<vec3<f32>, 2>(
+        vec3<f32>(0.70710677, 0.0, -0.70710677),
+        vec3<f32>(0.70710677, 0.0, 0.70710677),
+    );
+    var offsets = array<f32, 2>(0.0, 0.70710677);
+
+    var best = miss_hit();
+    var best_t = 1e30;
+    for (var i = 0u; i < 2u; i = i + 1u) {
+        let n = normals[i];
+        let denom = dot(n, dir);
+        if abs(denom) < 1e-5 {
+            continue;
+        }
+        let t = (offsets[i] - dot(n, local_origin)) / denom;
+        if t < 0.0 || t >= best_t {
+            continue;
+        }
+        let local = local_origin + dir * t;
+        if any(local < vec3<f32>(0.0)) || any(local > vec3<f32>(1.0)) {
+            continue;
+        }
+        if sample_tile_alpha(info.face_tiles[0u], cross_uv(local)) < CUTOUT_ALPHA {
+            continue;
+        }
+        best_t = t;
+        best = HitResult(block, voxel, n * -sign(denom), t, vec3<f32>(0.0), 1.0);
+    }
+    return best;
+}
+
+// Cutout cubes (wire) are see-through wherever the face they are entered
+// through is transparent.
+fn passes_cutout(hit: HitResult, origin: vec3<f32>, dir: vec3<f32>) -> bool {
+    let info = block_data[hit.block];
+    if info.cutout == 0u {
+        return true;
+    }
+    let block_origin = vec3<f32>(f32(hit.voxel.x), f32(hit.voxel.y), f32(hit.voxel.z)) + hit.offset;
+    let local = (origin + dir * (hit.travel + 1e-4) - block_origin) / hit.scale;
+    let tile = tile_for_face(info, face_index(hit.normal));
+    return sample_tile_alpha(tile, face_uv(hit.normal, local)) >= CUTOUT_ALPHA;
+}
+
+// Partial-height blocks (snow layers) only fill the bottom of their voxel,
+// cross-shaped plants are mostly empty and cutout faces have holes, so the
+// ray may pass through any of them; returns a miss in that case.
+fn resolve_hit(
+    block: u32,
+    voxel: vec3<i32>,
+    normal: vec3<f32>,
+    travel: f32,
+    origin: vec3<f32>,
+    dir: vec3<f32>,
+) -> HitResult {
+    if block_data[block].shape == SHAPE_CROSS {
+        return resolve_cross_hit(block, voxel, origin, dir);
+    }
+    let height = block_data[block].height;
+    var hit = HitResult(block, voxel, normal, travel, vec3<f32>(0.0), 1.0);
+    if height < 1.0 {
+        let box_min = vec3<f32>(f32(voxel.x), f32(voxel.y), f32(voxel.z));
+        let box_max = box_min + vec3<f32>(1.0, height, 1.0);
+        let span = intersect_aabb(origin, dir, box_min, box_max);
+        if span.x > span.y || span.y < 0.0 {
+            return miss_hit();
+        }
+        let t = max(span.x, 0.0);
+        let hit_normal = determine_entry_normal(origin + dir * t, box_min, box_max, dir);
+        hit = HitResult(block, voxel, hit_normal, t, vec3<f32>(0.0), 1.0);
+    }
+    if !passes_cutout(hit, origin, dir) {
+        return miss_hit();
+    }
+    return hit;
+}
+
+// The liquid filling the cell at `position`, or 0 if there is none.
+fn liquid_at(position: vec3<f32>) -> u32 {
+    let cell = floor(position);
+    let block = sample_block(vec3<i32>(i32(cell.x), i32(cell.y), i32(cell.z)));
+    if block != 0u && block_data[block].shape == SHAPE_LIQUID {
+        return block;
+    }
+    return 0u;
+}
+
+// Opaque boxes filling their whole cell, which hide a liquid surface behind them.
+fn fills_cell(block: u32) -> bool {
+    let info = block_data[block];
+    return info.shape == SHAPE_CUBE && info.height >= 1.0 && info.cutout == 0u;
+}
+
+// Hit for a ray entering `voxel` through the face with `normal`. Inside the
+// liquid `medium` the ray passes through liquid cells, still or flowing, and
+// stops at the surface where it leaves them, unless a block fills the next cell.
+fn resolve_voxel(
+    medium: u32,
+    voxel: vec3<i32>,
+    normal: vec3<f32>,
+    travel: f32,
+    origin: vec3<f32>,
+    dir: vec3<f32>,
+) -> HitResult {
+    let block = sample_block(voxel);
+    if medium == 0u {
+        if block == 0u {
+            return miss_hit();
+        }
+        return resolve_hit(block, voxel, normal, travel, origin, dir);
+    }
+    if block_data[block].shape == SHAPE_LIQUID {
+        return miss_hit();
+    }
+    if block != 0u && fills_cell(block) {
+        return resolve_hit(block, voxel, normal, travel, origin, dir);
+    }
+    // The surface belongs to the liquid cell being left, which `normal` points back into.
+    let inside = voxel + vec3<i32>(normal);
+    return HitResult(medium, inside, normal, travel, vec3<f32>(0.0), 1.0);
 }
 
+// Nearest hit against the voxel grid and every moving block.
 fn trace_ray(origin: vec3<f32>, dir: vec3<f32>) -> HitResult {
+    var hit = trace_voxels(origin, dir);
+    for (var i = 0u; i < uniforms.moving.x; i = i + 1u) {
+        let moving = moving_blocks[i];
+        let box_min = moving.position;
+        let box_max = box_min + vec3<f32>(moving.scale);
+        let span = intersect_aabb(origin, dir, box_min, box_max);
+        if span.x > span.y || span.y < 0.0 {
+            continue;
+        }
+        let t = max(span.x, 0.0);
+        if hit.block != 0u && t >= hit.travel {
+            continue;
+        }
+        let cell = floor(box_min);
+        let voxel = vec3<i32>(i32(cell.x), i32(cell.y), i32(cell.z));
+        let normal = determine_entry_normal(origin + dir * t, box_min, box_max, dir);
+        hit = HitResult(moving.block, voxel, normal, t, box_min - cell, moving.scale);
+    }
+    return hit;
+}
+
+fn trace_voxels(origin: vec3<f32>, dir: vec3<f32>) -> HitResult {
     let grid_origin_i = uniforms.grid_origin.xyz;
     let grid_min = vec3<f32>(
         f32(grid_origin_i.x),
@@ -447,10 +1003,11 @@ fn trace_ray(origin: vec3<f32>, dir: vec3<f32>) -> HitResult {
         step_vec.z = -1;
     }
 
+    // Distances along the ray from `origin`, the same as `travel`.
     var t_max = vec3<f32>(
-        compute_t_max(start.x, dir.x, voxel.x, step_vec.x),
-        compute_t_max(start.y, dir.y, voxel.y, step_vec.y),
-        compute_t_max(start.z, dir.z, voxel.z, step_vec.z),
+        compute_t_max(origin.x, dir.x, voxel.x, step_vec.x),
+        compute_t_max(origin.y, dir.y, voxel.y, step_vec.y),
+        compute_t_max(origin.z, dir.z, voxel.z, step_vec.z),
     );
     let delta = vec3<f32>(
         compute_step_delta(dir.x, step_vec.x),
@@ -458,10 +1015,12 @@ fn trace_ray(origin: vec3<f32>, dir: vec3<f32>) -> HitResult {
         compute_step_delta(dir.z, step_vec.z),
     );
 
+    ray_count = ray_count + 1u;
+    let medium = liquid_at(origin);
     var normal = determine_entry_normal(start, grid_min, grid_max, dir);
-    var block = sample_block(voxel);
-    if block != 0u {
-        return HitResult(block, voxel, normal, entry);
+    let first = resolve_voxel(medium, voxel, normal, entry, origin, dir);
+    if first.block != 0u {
+        return first;
     }
 
     var travel = entry;
@@ -473,6 +1032,49 @@ fn trace_ray(origin: vec3<f32>, dir: vec3<f32>) -> HitResult {
             break;
         }
 
+        // Outside liquid, empty chunks and cells are crossed in one step to
+        // the face the ray leaves them through. Inside it, every cell is
+        // looked at for the surface.
+        let span = select(1, empty_span(voxel), medium == 0u);
+        if span > 1 {
+            let box_min = grid_origin_i + (voxel - grid_origin_i) / span * span;
+            let box_max = box_min + vec3<i32>(span);
+            let far = vec3<f32>(select(box_min, box_max, step_vec > vec3<i32>(0)));
+            let t_far = vec3<f32>(
+                select(1e30, (far.x - origin.x) / dir.x, step_vec.x != 0),
+                select(1e30, (far.y - origin.y) / dir.y, step_vec.y != 0),
+                select(1e30, (far.z - origin.z) / dir.z, step_vec.z != 0),
+            );
+            travel = min(t_far.x, min(t_far.y, t_far.z));
+            let leaving = floor(origin + dir * travel);
+            voxel = clamp(vec3<i32>(leaving), box_min, box_max - vec3<i32>(1));
+            if travel == t_far.x {
+                voxel.x = select(box_min.x - 1, box_max.x, step_vec.x > 0);
+                normal = vec3<f32>(-f32(step_vec.x), 0.0, 0.0);
+            } else if travel == t_far.y {
+                voxel.y = select(box_min.y - 1, box_max.y, step_vec.y > 0);
+                normal = vec3<f32>(0.0, -f32(step_vec.y), 0.0);
+            } else {
+                voxel.z = select(box_min.z - 1, box_max.z, step_vec.z > 0);
+                normal = vec3<f32>(0.0, 0.0, -f32(step_vec.z));
+            }
+            t_max = vec3<f32>(
+                compute_t_max(origin.x, dir.x, voxel.x, step_vec.x),
+                compute_t_max(origin.y, dir.y, voxel.y, step_vec.y),
+                compute_t_max(origin.z, dir.z, voxel.z, step_vec.z),
+            );
+            ray_skips = ray_skips + 1u;
+            if travel > exit {
+                break;
+            }
+            let hit = resolve_voxel(medium, voxel, normal, travel, origin, dir);
+            if hit.block != 0u {
+                return hit;
+            }
+            steps = steps + 1u;
+            continue;
+        }
+
         var axis: u32 = 0u;
         if t_max.x < t_max.y {
             if t_max.x < t_max.z {
@@ -509,9 +1111,10 @@ fn trace_ray(origin: vec3<f32>, dir: vec3<f32>) -> HitResult {
             break;
         }
 
-        block = sample_block(voxel);
-        if block != 0u {
-            return HitResult(block, voxel, normal, travel);
+        ray_steps = ray_steps + 1u;
+        let hit = resolve_voxel(medium, voxel, normal, travel, origin, dir);
+        if hit.block != 0u {
+            return hit;
         }
 
         steps = steps + 1u;
@@ -527,20 +1130,30 @@ fn gather_material(hit: HitResult, origin: vec3<f32>, dir: vec3<f32>) -> Materia
         f32(hit.voxel.x),
         f32(hit.voxel.y),
         f32(hit.voxel.z),
-    );
-    let local = hit_point - block_origin;
+    ) + hit.offset;
+    let local = (hit_point - block_origin) / hit.scale;
     let face = face_index(hit.normal);
     let tile = tile_for_face(info, face);
-    let uv = face_uv(hit.normal, local);
-    let albedo = sample_tile(tile, uv);
+    var uv = face_uv(hit.normal, local);
+    if info.shape == SHAPE_CROSS {
+        uv = cross_uv(local);
+    }
+    let albedo = sample_tile(tile, uv) * biome_tint(tint_for_face(info, face), hit.voxel);
 
     let metallic = saturate(info.metallic);
     let transmission = saturate(info.transmission);
     let tint_mix = saturate(info.transmission_tint);
     let ior = max(info.ior, 1.0);
 
-    let light = max(dot(hit.normal, SUN_DIRECTION), 0.0);
-    let diffuse_base = albedo * light * saturate(info.diffuse);
+    // Plants are lit by their own cell, faces by the cells in front of them.
+    var light = cell_light(hit.voxel);
+    if info.shape != SHAPE_CROSS {
+        light = surface_light(hit.voxel, hit.normal, hit_point);
+    }
+    // As in the raster shader, block light fills in where the sun is dimmer.
+    let sun = max(dot(hit.normal, uniforms.sun.xyz), 0.0) * light.x * uniforms.sun_color.rgb;
+    let lit = max(sun, light.y * BLOCK_LIGHT_COLOR);
+    let diffuse_base = albedo * lit * saturate(info.diffuse);
     let diffuse_component = diffuse_base * (1.0 - metallic) * (1.0 - transmission);
     let emission = albedo * info.luminance * 0.12;
     let direct = diffuse_component + emission;
@@ -561,27 +1174,45 @@ fn gather_material(hit: HitResult, origin: vec3<f32>, dir: vec3<f32>) -> Materia
         transmission,
         ior,
         tint_mix,
-        hit.voxel,
+        block_origin,
+        hit.scale,
+        light,
     );
 }
 
-fn trace_specular_chain(material: MaterialInfo, incoming: vec3<f32>, seed: vec3<u32>) -> vec3<f32> {
+// Reflectance at normal incidence: the specular value for dielectrics, near
+// white for metals, and the one the index of refraction gives for the
+// transmissive share, so glass reflects like glass.
+fn reflectance_f0(material: MaterialInfo) -> f32 {
+    let r0 = (material.ior - 1.0) / (material.ior + 1.0);
+    let opaque = material.specular * (1.0 - material.metallic) + 0.96 * material.metallic;
+    return mix(opaque, r0 * r0, material.transmission);
+}
+
+// Light reflected back along `incoming` off `material`, following the mirror
+// direction (widened by roughness) for up to `depth` surfaces; rough
+// surfaces stop after the first.
+fn trace_specular_chain(
+    material: MaterialInfo,
+    incoming: vec3<f32>,
+    seed: vec3<u32>,
+    depth: u32,
+) -> vec3<f32> {
     let cos_theta = saturate(dot(material.normal, -incoming));
-    let base_f0 = material.specular * (1.0 - material.metallic) + 0.96 * material.metallic;
-    let base_reflect = schlick(base_f0, cos_theta);
-    if base_reflect < 0.005 {
+    let base_reflect = schlick(reflectance_f0(material), cos_theta);
+    if base_reflect < 0.005 || depth == 0u {
         return vec3<f32>(0.0);
     }
 
     var color = vec3<f32>(0.0);
     let base_tint = lerp_vec3(vec3<f32>(1.0), material.albedo, material.metallic);
-    var throughput = base_tint * base_reflect * (1.0 - material.transmission);
+    var throughput = base_tint * base_reflect;
     var ray_origin = material.position + material.normal * 1e-3;
     let jitter_seed = random_vec2(seed, 1u);
     let jitter = sample_cosine_hemisphere(material.normal, jitter_seed);
     var ray_dir = normalize(mix(reflect(incoming, material.normal), jitter, material.roughness));
     let allow_second = material.roughness < ROUGH_SPECULAR_LIMIT;
-    let bounce_limit = select(1u, MAX_SPECULAR_BOUNCES, allow_second);
+    let bounce_limit = select(1u, depth, allow_second);
 
     for (var bounce = 0u; bounce < bounce_limit; bounce = bounce + 1u) {
         let hit = trace_ray(ray_origin, ray_dir);
@@ -594,11 +1225,9 @@ fn trace_specular_chain(material: MaterialInfo, incoming: vec3<f32>, seed: vec3<
         color += throughput * sample_material.direct;
 
         let next_cos = saturate(dot(sample_material.normal, -ray_dir));
-        let sample_f0 =
-            sample_material.specular * (1.0 - sample_material.metallic) + 0.96 * sample_material.metallic;
-        let fresnel = schlick(sample_f0, next_cos);
+        let fresnel = schlick(reflectance_f0(sample_material), next_cos);
         let tint = lerp_vec3(vec3<f32>(1.0), sample_material.albedo, sample_material.metallic);
-        let attenuation = tint * fresnel * (1.0 - sample_material.roughness * 0.35) * (1.0 - sample_material.transmission * 0.5);
+        let attenuation = tint * fresnel * (1.0 - sample_material.roughness * 0.35);
 
         throughput *= attenuation;
         if max(max(throughput.x, throughput.y), throughput.z) < 0.01 {
@@ -625,7 +1254,7 @@ fn trace_diffuse_component(material: MaterialInfo, seed: vec3<u32>) -> vec3<f32>
     let bounce_origin = material.position + material.normal * 1e-3;
     let hit = trace_ray(bounce_origin, bounce_dir);
 
-    var indirect = material.albedo * material.diffuse * 0.1;
+    var indirect = material.albedo * material.diffuse * 0.1 * material.light.x * uniforms.ambient.rgb;
     if hit.block == 0u {
         indirect += material.albedo * material.diffuse * 0.25 * sky(bounce_dir);
     } else {
@@ -636,64 +1265,198 @@ fn trace_diffuse_component(material: MaterialInfo, seed: vec3<u32>) -> vec3<f32>
     return indirect;
 }
 
-fn trace_transmission(material: MaterialInfo, dir: vec3<f32>, seed: vec3<u32>) -> vec3<f32> {
-    if material.transmission < 0.01 {
-        return vec3<f32>(0.0);
-    }
+struct Ray {
+    origin: vec3<f32>,
+    dir: vec3<f32>,
+}
 
+// The ray leaving the far side of the block after refracting in through the
+// hit face and out through the face it reaches; a zero `dir` when either
+// refraction is totally internal.
+fn refract_through_block(material: MaterialInfo, dir: vec3<f32>) -> Ray {
+    let blocked = Ray(material.position, vec3<f32>(0.0));
     let inside_dir = refract_snell(dir, material.normal, 1.0, material.ior);
     if length(inside_dir) < 1e-4 {
-        return vec3<f32>(0.0);
+        return blocked;
     }
 
-    let block_min = vec3<f32>(
-        f32(material.voxel.x),
-        f32(material.voxel.y),
-        f32(material.voxel.z),
-    );
-    let block_max = block_min + vec3<f32>(1.0);
+    let block_min = material.block_min;
+    let block_max = block_min + vec3<f32>(material.block_scale);
     let entry = material.position + inside_dir * 1e-4;
     let bounds = intersect_aabb(entry, inside_dir, block_min, block_max);
     if bounds.x > bounds.y {
-        return vec3<f32>(0.0);
+        return blocked;
     }
     let exit_t = bounds.y;
     if exit_t <= 1e-4 {
-        return vec3<f32>(0.0);
+        return blocked;
     }
     let exit_point = entry + inside_dir * (exit_t + 1e-4);
     let exit_normal = determine_entry_normal(exit_point, block_min, block_max, inside_dir);
     let exit_dir = refract_snell(inside_dir, exit_normal, material.ior, 1.0);
     if length(exit_dir) < 1e-4 {
-        return vec3<f32>(0.0);
+        return blocked;
     }
+    return Ray(exit_point + exit_dir * 1e-3, exit_dir);
+}
 
-    let tint = lerp_vec3(vec3<f32>(1.0), material.albedo, material.transmission_tint);
-    let throughput = tint * material.transmission;
-    let next_origin = exit_point + exit_dir * 1e-3;
-    let next_hit = trace_ray(next_origin, exit_dir);
-    if next_hit.block == 0u {
-        return throughput * sky(exit_dir);
+// Light refracted through `material` towards the eye: the share Fresnel does
+// not reflect passes through the block, and on through every transmissive
+// block behind it, for up to `uniforms.bounces.x` blocks. Each surface
+// reached adds its own light and reflection; the last one reached, or the
+// first opaque one, its diffuse bounce too.
+fn trace_transmission(material: MaterialInfo, dir: vec3<f32>, seed: vec3<u32>) -> vec3<f32> {
+    if material.transmission < 0.01 {
+        return vec3<f32>(0.0);
     }
 
-    let bounced = gather_material(next_hit, next_origin, exit_dir);
-    var color = throughput * bounced.direct;
+    var color = vec3<f32>(0.0);
+    var current = material;
+    var ray_dir = dir;
+    var throughput = vec3<f32>(1.0);
+    let depth = uniforms.bounces.x;
+    for (var bounce = 0u; bounce < depth; bounce = bounce + 1u) {
+        let exit = refract_through_block(current, ray_dir);
+        if length(exit.dir) < 1e-4 {
+            break;
+        }
+
+        let cos_theta = saturate(dot(current.normal, -ray_dir));
+        let fresnel = schlick(reflectance_f0(current), cos_theta);
+        let tint = lerp_vec3(vec3<f32>(1.0), current.albedo, current.transmission_tint);
+        throughput *= tint * current.transmission * (1.0 - fresnel);
+        if max(max(throughput.x, throughput.y), throughput.z) < 0.01 {
+            break;
+        }
+
+        ray_dir = exit.dir;
+        let next_hit = trace_ray(exit.origin, ray_dir);
+        if next_hit.block == 0u {
+            color += throughput * sky(ray_dir);
+            break;
+        }
 
-    if bounced.diffuse > 0.02 && bounced.roughness > 0.12 {
-        let diffuse_seed =
-            vec3<u32>(seed.x ^ 0x6c8e9cf5u, seed.y + 0x52dce729u, seed.z + 0x7f4a7c15u);
-        color += throughput * trace_diffuse_component(bounced, diffuse_seed);
+        let bounced = gather_material(next_hit, exit.origin, ray_dir);
+        color += throughput * bounced.direct;
+
+        let bounce_seed = seed + vec3<u32>(bounce * 0x9e3779b9u);
+        let spec_seed =
+            vec3<u32>(bounce_seed.x + 0x12345u, bounce_seed.y ^ 0x9e3779b9u, bounce_seed.z + 0x51ed1099u);
+        color += throughput * trace_specular_chain(bounced, ray_dir, spec_seed, depth - bounce);
+
+        let passes_on = bounced.transmission >= 0.01 && bounce + 1u < depth;
+        if !passes_on {
+            if bounced.diffuse > 0.02 && bounced.roughness > 0.12 {
+                let diffuse_seed = vec3<u32>(
+                    bounce_seed.x ^ 0x6c8e9cf5u,
+                    bounce_seed.y + 0x52dce729u,
+                    bounce_seed.z + 0x7f4a7c15u,
+                );
+                color += throughput * trace_diffuse_component(bounced, diffuse_seed);
+            }
+            break;
+        }
+        current = bounced;
     }
 
-    let spec_seed = vec3<u32>(seed.x + 0x12345u, seed.y ^ 0x9e3779b9u, seed.z + 0x51ed1099u);
-    color += throughput * trace_specular_chain(bounced, exit_dir, spec_seed);
+    return color;
+}
+
+// Light reaching `color` from `distance` away through water.
+fn water_fog(color: vec3<f32>, distance: f32) -> vec3<f32> {
+    return lerp_vec3(WATER_FOG_COLOR, color, exp(-distance * WATER_FOG_DENSITY));
+}
 
+fn ripple_height(p: vec2<f32>) -> f32 {
+    let t = uniforms.time.x;
+    let slow = value_noise(p * 0.8 + vec2<f32>(t * 0.6, t * 0.35));
+    let fast = value_noise(p * 2.1 + vec2<f32>(-t * 0.9, t * 0.7));
+    return slow * 0.6 + fast * 0.25;
+}
+
+// Tilts the normal of a liquid's horizontal faces along the gradient of
+// drifting noise ripples; side faces stay flat.
+fn ripple_normal(position: vec3<f32>, normal: vec3<f32>) -> vec3<f32> {
+    if abs(normal.y) < 0.5 {
+        return normal;
+    }
+    let probe = 0.05;
+    let p = position.xz;
+    let height = ripple_height(p);
+    let dx = (ripple_height(p + vec2<f32>(probe, 0.0)) - height) / probe;
+    let dz = (ripple_height(p + vec2<f32>(0.0, probe)) - height) / probe;
+    let up = normalize(vec3<f32>(-dx * RIPPLE_STRENGTH, 1.0, -dz * RIPPLE_STRENGTH));
+    return up * normal.y;
+}
+
+// Radiance along one reflected or refracted ray off a liquid surface, fogged
+// when the ray travels through the liquid.
+fn trace_liquid_ray(origin: vec3<f32>, dir: vec3<f32>, through_liquid: bool) -> vec3<f32> {
+    let hit = trace_ray(origin, dir);
+    var color = sky(dir);
+    var distance = WATER_FOG_FAR;
+    if hit.block != 0u {
+        color = gather_material(hit, origin, dir).direct;
+        distance = hit.travel;
+    }
+    if through_liquid {
+        color = water_fog(color, distance);
+    }
     return color;
 }
 
-fn evaluate_surface(hit: HitResult, origin: vec3<f32>, dir: vec3<f32>, seed: vec3<u32>) -> SurfaceSample {
+// Liquid surfaces, seen from either side: one reflection and one refraction
+// ray off the rippled normal, blended by Fresnel, plus the sun's glint.
+fn evaluate_liquid(hit: HitResult, origin: vec3<f32>, dir: vec3<f32>) -> SurfaceSample {
     let material = gather_material(hit, origin, dir);
-    let specular = trace_specular_chain(material, dir, seed);
+    let inside = liquid_at(origin) != 0u;
+    let normal = ripple_normal(material.position, hit.normal);
+
+    let ior = material.ior;
+    let r0 = (ior - 1.0) / (ior + 1.0);
+    var fresnel = schlick(r0 * r0, saturate(dot(normal, -dir)));
+    var refracted = vec3<f32>(0.0);
+    if inside {
+        refracted = refract_snell(dir, normal, ior, 1.0);
+    } else {
+        refracted = refract_snell(dir, normal, 1.0, ior);
+    }
+    // Total internal reflection, or ripples bending the ray back out.
+    if length(refracted) < 1e-4 || dot(refracted, hit.normal) > 0.0 {
+        fresnel = 1.0;
+    }
+
+    var reflected = reflect(dir, normal);
+    if dot(reflected, hit.normal) < 0.0 {
+        reflected = reflect(dir, hit.normal);
+    }
+    let reflection = trace_liquid_ray(material.position + hit.normal * 1e-3, reflected, inside);
+
+    var refraction = vec3<f32>(0.0);
+    if fresnel < 1.0 {
+        let tint = lerp_vec3(vec3<f32>(1.0), material.albedo, material.transmission_tint);
+        refraction =
+            tint * trace_liquid_ray(material.position - hit.normal * 1e-3, refracted, !inside);
+    }
+
+    var color = lerp_vec3(refraction, reflection, fresnel) + material.direct;
+    if !inside {
+        color += vec3<f32>(pow(saturate(dot(reflected, uniforms.sun.xyz)), 256.0) * SUN_GLINT * uniforms.sun_color.rgb);
+    }
+    let fog_color = uniforms.sky_horizon.rgb;
+    let fog = clamp(hit.travel / 400.0, 0.0, 1.0) * 0.6;
+    return SurfaceSample(color, vec3<f32>(0.0), vec3<f32>(0.0), vec3<f32>(0.0), fog_color, fog);
+}
+
+fn evaluate_surface(hit: HitResult, origin: vec3<f32>, dir: vec3<f32>, seed: vec3<u32>) -> SurfaceSample {
+    if block_data[hit.block].shape == SHAPE_LIQUID {
+        return evaluate_liquid(hit, origin, dir);
+    }
+    let material = with_lamp_light(
+        gather_material(hit, origin, dir),
+        vec3<u32>(seed.x ^ 0x3c6ef372u, seed.y + 0xa54ff53au, seed.z ^ 0x510e527fu),
+    );
+    let specular = trace_specular_chain(material, dir, seed, uniforms.bounces.x);
     var diffuse = vec3<f32>(0.0);
     if material.diffuse > 0.02 && material.roughness > 0.12 {
         diffuse = trace_diffuse_component(
@@ -706,21 +1469,123 @@ fn evaluate_surface(hit: HitResult, origin: vec3<f32>, dir: vec3<f32>, seed: vec
         dir,
         vec3<u32>(seed.x + 0xb5297a4du, seed.y ^ 0x68e31da4u, seed.z + 0x1b56c4f5u),
     );
-    let fog_color = vec3<f32>(0.6, 0.75, 0.95);
+    let fog_color = uniforms.sky_horizon.rgb;
     let fog = clamp(hit.travel / 400.0, 0.0, 1.0) * 0.6;
 
     return SurfaceSample(material.direct, specular, diffuse, transmission, fog_color, fog);
 }
 
+// One path-traced sample of the light arriving back along `dir` from `first`.
+// Every vertex adds its emission, and the sun and its nearby lamps through
+// shadow rays, then picks one lobe by its weight: transmission, a
+// Fresnel-weighted specular lobe widened by roughness and tinted by
+// metallic, or cosine-weighted diffuse.
+// Liquids end the path with their usual reflection and refraction.
+fn trace_path(first: HitResult, origin: vec3<f32>, dir: vec3<f32>, seed: vec3<u32>) -> vec3<f32> {
+    var hit = first;
+    var ray_origin = origin;
+    var ray_dir = dir;
+    var throughput = vec3<f32>(1.0);
+    var color = vec3<f32>(0.0);
+
+    for (var bounce = 0u; bounce < PATH_MAX_BOUNCES; bounce = bounce + 1u) {
+        if hit.block == 0u {
+            color += throughput * sky(ray_dir);
+            break;
+        }
+        let info = block_data[hit.block];
+        if info.shape == SHAPE_LIQUID {
+            color += throughput * evaluate_liquid(hit, ray_origin, ray_dir).direct;
+            break;
+        }
+
+        let material = gather_material(hit, ray_origin, ray_dir);
+        color += throughput * material.albedo * info.luminance * 0.12;
+        let lifted = material.position + material.normal * 1e-3;
+        let sun_cos = max(dot(material.normal, uniforms.sun.xyz), 0.0);
+        if material.diffuse > 0.0 && sun_cos > 0.0 && trace_ray(lifted, uniforms.sun.xyz).block == 0u {
+            color += throughput * material.albedo * material.diffuse * sun_cos * uniforms.sun_color.rgb;
+        }
+        let offset = 97u * (bounce + 1u);
+        let lamp_seed = vec3<u32>(seed.x, seed.y ^ offset, seed.z + 0x9b05688cu);
+        color += throughput * material.albedo * material.diffuse
+            * lamp_light(material.position, material.normal, material.light.y, lamp_seed);
+
+        let choice = random_scalar(seed, offset);
+        let xi = random_vec2(seed, offset + 31u);
+        // Specular share of what transmission leaves over.
+        let fresnel = schlick(material.specular, saturate(dot(material.normal, -ray_dir)));
+        if choice < material.transmission {
+            let exit = refract_through_block(material, ray_dir);
+            if length(exit.dir) < 1e-4 {
+                break;
+            }
+            throughput *= lerp_vec3(vec3<f32>(1.0), material.albedo, material.transmission_tint);
+            ray_origin = exit.origin;
+            ray_dir = exit.dir;
+        } else {
+            let jitter = sample_cosine_hemisphere(material.normal, xi);
+            if (choice - material.transmission) < fresnel * (1.0 - material.transmission) {
+                ray_dir = normalize(mix(reflect(ray_dir, material.normal), jitter, material.roughness));
+                throughput *= lerp_vec3(vec3<f32>(1.0), material.albedo, material.metallic);
+            } else {
+                ray_dir = jitter;
+                throughput *= material.albedo * saturate(info.diffuse) * (1.0 - material.metallic);
+            }
+            if dot(ray_dir, material.normal) <= 0.0 {
+                break;
+            }
+            ray_origin = lifted;
+        }
+
+        if bounce + 1u >= PATH_MIN_BOUNCES {
+            let survive = clamp(max(max(throughput.x, throughput.y), throughput.z), 0.05, 1.0);
+            if random_scalar(seed, offset + 57u) > survive {
+                break;
+            }
+            throughput /= survive;
+        }
+        hit = trace_ray(ray_origin, ray_dir);
+    }
+
+    return color;
+}
+
 @compute @workgroup_size(8, 8, 1)
-fn cs_main(@builtin(global_invocation_id) gid: vec3<u32>) {
-    let resolution = uniforms.stride.zw;
-    if gid.x >= resolution.x || gid.y >= resolution.y {
-        return;
+fn cs_main(
+    @builtin(global_invocation_id) gid: vec3<u32>,
+    @builtin(local_invocation_index) local_index: u32,
+) {
+    if all(gid.xy < uniforms.stride.zw) {
+        shade_pixel(gid);
     }
 
+    atomicAdd(&group_steps, ray_steps);
+    atomicAdd(&group_rays, ray_count);
+    atomicAdd(&group_skips, ray_skips);
+    workgroupBarrier();
+    if local_index == 0u {
+        let steps = atomicLoad(&group_steps);
+        let low = atomicAdd(&traversal.steps_low, steps);
+        // Carry into the high word when the low one wraps.
+        if low > 0xFFFFFFFFu - steps {
+            atomicAdd(&traversal.steps_high, 1u);
+        }
+        atomicAdd(&traversal.rays, atomicLoad(&group_rays));
+        atomicAdd(&traversal.skips, atomicLoad(&group_skips));
+    }
+}
+
+fn shade_pixel(gid: vec3<u32>) {
+    let resolution = uniforms.stride.zw;
+    let path_tracing = uniforms.accumulation.y != 0u;
+    let samples = uniforms.accumulation.x;
+    let rng_seed = vec3<u32>(gid.x, gid.y, samples);
+
     let res = vec2<f32>(f32(resolution.x), f32(resolution.y));
-    let pixel = vec2<f32>(f32(gid.x) + 0.5, f32(gid.y) + 0.5);
+    // Accumulated samples spread over the pixel, which antialiases edges.
+    let jitter = select(vec2<f32>(0.5), random_vec2(rng_seed, 7u), path_tracing);
+    let pixel = vec2<f32>(f32(gid.x), f32(gid.y)) + jitter;
     let uv = pixel / res;
 
     let f0 = uniforms.frustum[0].xyz;
@@ -732,15 +1597,37 @@ fn cs_main(@builtin(global_invocation_id) gid: vec3<u32>) {
     let bottom = normalize(mix(f2, f3, uv.x));
     let dir = normalize(mix(bottom, top, 1.0 - uv.y));
     let origin = uniforms.eye.xyz;
-    let rng_seed = vec3<u32>(gid.x, gid.y, 0u);
 
     let hit = trace_ray(origin, dir);
     var color = sky(dir);
-    if hit.block != 0u {
+    // Alpha 0 marks sky pixels for the light shaft pass in the display shader.
+    var coverage = 0.0;
+    if hit.block != 0u && path_tracing {
+        coverage = 1.0;
+        let fog_color = uniforms.sky_horizon.rgb;
+        let fog = clamp(hit.travel / 400.0, 0.0, 1.0) * 0.6;
+        color = lerp_vec3(trace_path(hit, origin, dir, rng_seed), fog_color, fog);
+    } else if hit.block != 0u {
+        coverage = 1.0;
         let sample = evaluate_surface(hit, origin, dir, rng_seed);
         let shaded = sample.direct + sample.specular + sample.diffuse + sample.transmission;
         color = lerp_vec3(shaded, sample.fog_color, sample.fog);
     }
+    // Underwater, everything up to the first surface is seen through water
+    // and the sky is never visible directly.
+    if liquid_at(origin) != 0u {
+        coverage = 1.0;
+        color = water_fog(color, select(WATER_FOG_FAR, hit.travel, hit.block != 0u));
+    }
+
+    var result = vec4<f32>(color, coverage);
+    if path_tracing {
+        if samples > 0u {
+            let previous = textureLoad(history_in, vec2<i32>(gid.xy), 0);
+            result = mix(previous, result, 1.0 / f32(samples + 1u));
+        }
+        textureStore(history_out, vec2<i32>(gid.xy), result);
+    }
 
-    textureStore(target_image, vec2<i32>(gid.xy), vec4<f32>(color, 1.0));
+    textureStore(target_image, vec2<i32>(gid.xy), result);
 }
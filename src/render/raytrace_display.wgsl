@@ -14,7 +14,70 @@ fn vs_main(@location(0) position: vec2<f32>, @location(1) uv: vec2<f32>) -> Vert
 @group(0) @binding(0) var render_texture: texture_2d<f32>;
 @group(0) @binding(1) var render_sampler: sampler;
 
+struct Output {
+    gamma: f32,
+    encode_srgb: u32,
+    hdr: u32,
+};
+
+@group(0) @binding(2) var<uniform> u_output: Output;
+
+struct LightShafts {
+    sun_uv: vec2<f32>,
+    intensity: f32,
+    aspect: f32,
+};
+
+@group(0) @binding(3) var<uniform> u_shafts: LightShafts;
+
+const SHAFT_SAMPLES: i32 = 48;
+const SHAFT_DENSITY: f32 = 0.9;
+const SHAFT_DECAY: f32 = 0.955;
+const SHAFT_WEIGHT: f32 = 0.06;
+const SUN_COLOR: vec3<f32> = vec3<f32>(1.0, 0.9, 0.7);
+
+fn linear_to_srgb(c: vec3<f32>) -> vec3<f32> {
+    let low = c * 12.92;
+    let high = 1.055 * pow(c, vec3<f32>(1.0 / 2.4)) - vec3<f32>(0.055);
+    return select(high, low, c <= vec3<f32>(0.0031308));
+}
+
+fn encode_output(linear: vec3<f32>) -> vec3<f32> {
+    if u_output.hdr != 0u {
+        // The display takes linear light and shows values above 1.0 brighter
+        // than SDR white, so nothing is clamped or encoded.
+        return pow(max(linear, vec3<f32>(0.0)), vec3<f32>(2.2 / u_output.gamma));
+    }
+    let adjusted = pow(clamp(linear, vec3<f32>(0.0), vec3<f32>(1.0)), vec3<f32>(2.2 / u_output.gamma));
+    if u_output.encode_srgb != 0u {
+        return linear_to_srgb(adjusted);
+    }
+    return adjusted;
+}
+
+// Sky pixels (alpha 0) near the sun act as the light source.
+fn sun_source(uv: vec2<f32>) -> f32 {
+    let dims = vec2<f32>(textureDimensions(render_texture));
+    let texel = vec2<i32>(clamp(uv, vec2<f32>(0.0), vec2<f32>(0.999)) * dims);
+    let sky = 1.0 - textureLoad(render_texture, texel, 0).a;
+    let offset = (uv - u_shafts.sun_uv) * vec2<f32>(u_shafts.aspect, 1.0);
+    return sky * exp(-length(offset) * 3.0);
+}
+
 @fragment
 fn fs_main(in: VertexOut) -> @location(0) vec4<f32> {
-    return textureSample(render_texture, render_sampler, in.uv);
+    var color = textureSample(render_texture, render_sampler, in.uv).rgb;
+    if u_shafts.intensity > 0.0 {
+        let step = (in.uv - u_shafts.sun_uv) * (SHAFT_DENSITY / f32(SHAFT_SAMPLES));
+        var coord = in.uv;
+        var decay = 1.0;
+        var shafts = 0.0;
+        for (var i = 0; i < SHAFT_SAMPLES; i = i + 1) {
+            coord = coord - step;
+            shafts = shafts + sun_source(coord) * decay * SHAFT_WEIGHT;
+            decay = decay * SHAFT_DECAY;
+        }
+        color = color + SUN_COLOR * shafts * u_shafts.intensity;
+    }
+    return vec4<f32>(encode_output(color), 1.0);
 }
@@ -0,0 +1,123 @@
+//! Copies of whole textures read back to the CPU, such as the window surface
+//! for a screenshot.
+//!
+//! Like the timestamp reader, a readback never waits on the GPU: the copy is
+//! recorded into a frame's encoder, mapped once that frame is submitted, and
+//! polled on later frames until the texels arrive.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+const MAPPING: u8 = 0;
+const MAPPED: u8 = 1;
+const FAILED: u8 = 2;
+
+/// Texels read back from a texture, rows tightly packed top to bottom.
+pub struct TexelImage {
+    pub width: u32,
+    pub height: u32,
+    pub format: wgpu::TextureFormat,
+    pub data: Vec<u8>,
+}
+
+/// What [`TextureReadback::poll`] found.
+pub enum ReadbackPoll {
+    Pending,
+    Ready(TexelImage),
+    Failed,
+}
+
+/// A texture copy on its way back from the GPU.
+pub struct TextureReadback {
+    buffer: wgpu::Buffer,
+    width: u32,
+    height: u32,
+    format: wgpu::TextureFormat,
+    /// Bytes per texel row in `buffer`, padded to wgpu's copy alignment.
+    padded_row: u32,
+    state: Arc<AtomicU8>,
+    started: bool,
+}
+
+impl TextureReadback {
+    /// Records a copy of all of `texture` into `encoder`, which must have
+    /// `COPY_SRC` usage. Returns `None` for formats without a fixed texel
+    /// size, such as depth.
+    pub fn record(
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        texture: &wgpu::Texture,
+    ) -> Option<Self> {
+        let format = texture.format();
+        let texel = format.block_size(None)?;
+        let (width, height) = (texture.width(), texture.height());
+        let padded_row = (width * texel).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Texture readback buffer"),
+            size: u64::from(padded_row) * u64::from(height),
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            texture.size(),
+        );
+        Some(Self {
+            buffer,
+            width,
+            height,
+            format,
+            padded_row,
+            state: Arc::new(AtomicU8::new(MAPPING)),
+            started: false,
+        })
+    }
+
+    /// Checks on the copy without blocking. The first call starts mapping
+    /// the buffer, so call it only once the encoder holding the copy has
+    /// been submitted.
+    pub fn poll(&mut self, device: &wgpu::Device) -> ReadbackPoll {
+        if !self.started {
+            self.started = true;
+            let state = Arc::clone(&self.state);
+            self.buffer
+                .slice(..)
+                .map_async(wgpu::MapMode::Read, move |result| {
+                    let next = if result.is_ok() { MAPPED } else { FAILED };
+                    state.store(next, Ordering::Release);
+                });
+        }
+        device.poll(wgpu::Maintain::Poll);
+        match self.state.load(Ordering::Acquire) {
+            MAPPED => ReadbackPoll::Ready(self.read()),
+            FAILED => ReadbackPoll::Failed,
+            _ => ReadbackPoll::Pending,
+        }
+    }
+
+    fn read(&self) -> TexelImage {
+        let texel = self.format.block_size(None).unwrap_or(0);
+        let row = (self.width * texel) as usize;
+        let mapped = self.buffer.slice(..).get_mapped_range();
+        let mut data = Vec::with_capacity(row * self.height as usize);
+        for padded in mapped.chunks_exact(self.padded_row as usize) {
+            data.extend_from_slice(&padded[..row]);
+        }
+        drop(mapped);
+        self.buffer.unmap();
+        TexelImage {
+            width: self.width,
+            height: self.height,
+            format: self.format,
+            data,
+        }
+    }
+}
@@ -0,0 +1,108 @@
+//! Every renderer the game can draw with, by name.
+//!
+//! A renderer joins by adding a [`RendererEntry`] to [`RENDERERS`]. The
+//! `render_method` config value and the `/renderer` console command both
+//! look renderers up here, so nothing outside this module names a renderer
+//! type.
+
+use super::{PipelineCache, RasterRenderer, RayTraceRenderer, Renderer};
+use crate::texture::TextureAtlas;
+use crate::world::World;
+
+/// Everything a renderer may be built from.
+pub struct RendererContext<'a> {
+    pub device: &'a wgpu::Device,
+    pub queue: &'a wgpu::Queue,
+    pub surface_config: &'a wgpu::SurfaceConfiguration,
+    pub world: &'a World,
+    pub block_atlas: &'a TextureAtlas,
+    pub camera_bind_group_layout: &'a wgpu::BindGroupLayout,
+    pub pipeline_cache: &'a PipelineCache,
+    pub ao_strength: f32,
+    /// Samples per pixel the rasterizer draws the world with, one of those
+    /// [`msaa_sample_count`](super::msaa_sample_count) allows.
+    pub msaa_samples: u32,
+}
+
+/// A registered renderer.
+pub struct RendererEntry {
+    /// What `render_method` and `/renderer` call it.
+    pub name: &'static str,
+    /// Other names accepted for it.
+    pub aliases: &'static [&'static str],
+    /// Compiles its pipelines for a surface format and MSAA sample count
+    /// into the cache, so building it later never waits on shader
+    /// compilation.
+    pub warm_up:
+        fn(&wgpu::Device, &PipelineCache, wgpu::TextureFormat, u32, &wgpu::BindGroupLayout),
+    pub create: fn(&RendererContext) -> Box<dyn Renderer>,
+}
+
+/// The renderer used when the config names none, or one that is not
+/// registered.
+pub const DEFAULT_RENDERER: &str = "rasterized";
+
+pub const RENDERERS: &[RendererEntry] = &[
+    RendererEntry {
+        name: "rasterized",
+        aliases: &["raster", "mesh"],
+        warm_up: RasterRenderer::warm_up,
+        create: |ctx| {
+            Box::new(RasterRenderer::new(
+                ctx.device,
+                ctx.queue,
+                ctx.surface_config,
+                ctx.world,
+                ctx.block_atlas,
+                ctx.camera_bind_group_layout,
+                ctx.pipeline_cache,
+                ctx.ao_strength,
+                ctx.msaa_samples,
+            ))
+        },
+    },
+    RendererEntry {
+        name: "raytraced",
+        aliases: &["ray-traced", "raytrace"],
+        warm_up: |device, cache, surface_format, _, _| {
+            RayTraceRenderer::warm_up(device, cache, surface_format)
+        },
+        create: |ctx| {
+            Box::new(RayTraceRenderer::new(
+                ctx.device,
+                ctx.queue,
+                ctx.surface_config.format,
+                ctx.block_atlas,
+                ctx.pipeline_cache,
+            ))
+        },
+    },
+];
+
+/// The renderer called `name` or one of its aliases, ignoring case and
+/// surrounding whitespace.
+pub fn find_renderer(name: &str) -> Option<&'static RendererEntry> {
+    let name = name.trim();
+    RENDERERS.iter().find(|entry| {
+        entry.name.eq_ignore_ascii_case(name)
+            || entry
+                .aliases
+                .iter()
+                .any(|alias| alias.eq_ignore_ascii_case(name))
+    })
+}
+
+/// The renderer registered after the one called `name`, wrapping around.
+pub fn next_renderer(name: &str) -> &'static RendererEntry {
+    let index = RENDERERS.iter().position(|entry| entry.name == name);
+    &RENDERERS[index.map_or(0, |index| (index + 1) % RENDERERS.len())]
+}
+
+/// The registered names, comma separated, for messages.
+pub fn renderer_names() -> String {
+    RENDERERS
+        .iter()
+        .map(|entry| entry.name)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
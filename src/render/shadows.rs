@@ -0,0 +1,223 @@
+use bytemuck::{Pod, Zeroable};
+use glam::{Mat4, Vec3};
+
+use crate::camera::CameraUniform;
+use crate::config::LightingSettings;
+use crate::render::labels;
+use crate::sky::SkyLight;
+
+/// Width and height of the shadow map in texels.
+const SHADOW_MAP_SIZE: u32 = 2048;
+pub const SHADOW_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+/// How far towards the sun, past the shadowed area, blocks still cast
+/// shadows into it.
+const CASTER_MARGIN: f32 = 128.0;
+/// Share of the light a full shadow takes away in full sunlight; the rest is
+/// sky light. Shadows fade with the direct light towards dusk.
+const SHADOW_STRENGTH: f32 = 0.45;
+/// How far receivers are pushed off their face before the lookup, in shadow
+/// map texels, so flat ground does not shadow itself.
+const NORMAL_OFFSET_TEXELS: f32 = 1.5;
+
+/// Per-view parameters of the shadow lookup in `shader.wgsl`.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct ShadowUniform {
+    view_proj: [[f32; 4]; 4],
+    sun: [f32; 4],
+    /// x: 1 when shadows are on, y: texel size in UV, z: strength, w: normal
+    /// offset in blocks.
+    params: [f32; 4],
+    /// rgb: the light open sky gives at this time of day.
+    ambient: [f32; 4],
+}
+
+/// The depth of the world as seen from the sun, fitted to a square around
+/// the camera, and the bind group the world shader reads it through.
+///
+/// The map is redrawn for every view; its uniforms are written with
+/// `queue.write_buffer`, so each view must be submitted before the next one
+/// updates them.
+pub struct ShadowMap {
+    _texture: wgpu::Texture,
+    pub depth_view: wgpu::TextureView,
+    caster_buffer: wgpu::Buffer,
+    /// The sun as a camera, for drawing casters with the world pipeline layout.
+    pub caster_bind_group: wgpu::BindGroup,
+    uniform_buffer: wgpu::Buffer,
+    pub bind_group: wgpu::BindGroup,
+}
+
+impl ShadowMap {
+    pub fn new(
+        device: &wgpu::Device,
+        camera_layout: &wgpu::BindGroupLayout,
+        layout: &wgpu::BindGroupLayout,
+    ) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Shadow map"),
+            size: wgpu::Extent3d {
+                width: SHADOW_MAP_SIZE,
+                height: SHADOW_MAP_SIZE,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: SHADOW_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let depth_view = labels::labeled_view(&texture, "Shadow map view");
+
+        let caster_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Shadow caster camera buffer"),
+            size: std::mem::size_of::<CameraUniform>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let caster_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Shadow caster camera bind group"),
+            layout: camera_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: caster_buffer.as_entire_binding(),
+            }],
+        });
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Shadow uniform buffer"),
+            size: std::mem::size_of::<ShadowUniform>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        // Standard depth, unlike the reverse-Z scene: the map is cleared to 1
+        // and a receiver is lit when it is no deeper than the nearest caster.
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Shadow sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            compare: Some(wgpu::CompareFunction::LessEqual),
+            ..Default::default()
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Shadow bind group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&depth_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+        });
+
+        Self {
+            _texture: texture,
+            depth_view,
+            caster_buffer,
+            caster_bind_group,
+            uniform_buffer,
+            bind_group,
+        }
+    }
+
+    /// Fits the map around `eye` and writes this view's uniforms. Returns
+    /// whether the map should be redrawn; with shadows off, the lookup is
+    /// skipped and the map left as it is.
+    pub fn update(
+        &self,
+        queue: &wgpu::Queue,
+        lighting: &LightingSettings,
+        sky: &SkyLight,
+        eye: Vec3,
+    ) -> bool {
+        let view_proj = sun_view_proj(lighting.sun_direction, lighting.shadow_distance, eye);
+        let texel = 2.0 * lighting.shadow_distance / SHADOW_MAP_SIZE as f32;
+        let uniform = ShadowUniform {
+            view_proj: view_proj.to_cols_array_2d(),
+            sun: lighting.sun_direction.extend(0.0).to_array(),
+            params: [
+                if lighting.shadows { 1.0 } else { 0.0 },
+                1.0 / SHADOW_MAP_SIZE as f32,
+                SHADOW_STRENGTH * sky.light_color.max_element().min(1.0),
+                texel * NORMAL_OFFSET_TEXELS,
+            ],
+            ambient: sky.ambient.extend(1.0).to_array(),
+        };
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(&uniform));
+        if lighting.shadows {
+            let caster = CameraUniform {
+                view_proj: view_proj.to_cols_array_2d(),
+            };
+            queue.write_buffer(&self.caster_buffer, 0, bytemuck::bytes_of(&caster));
+        }
+        lighting.shadows
+    }
+}
+
+/// Orthographic projection looking down `-sun` over a square of half-width
+/// `distance` centred on `eye`. The centre is snapped to whole texels so the
+/// shadows' edges stay put while the camera moves.
+fn sun_view_proj(sun: Vec3, distance: f32, eye: Vec3) -> Mat4 {
+    let up = if sun.y.abs() > 0.99 { Vec3::Z } else { Vec3::Y };
+    let view = Mat4::look_at_rh(Vec3::ZERO, -sun, up);
+    let texel = 2.0 * distance / SHADOW_MAP_SIZE as f32;
+    let center = view.transform_point3(eye);
+    let x = (center.x / texel).floor() * texel;
+    let y = (center.y / texel).floor() * texel;
+    let projection = Mat4::orthographic_rh(
+        x - distance,
+        x + distance,
+        y - distance,
+        y + distance,
+        -center.z - distance - CASTER_MARGIN,
+        -center.z + distance,
+    );
+    projection * view
+}
+
+pub fn bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Shadow bind group layout"),
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    multisampled: false,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    sample_type: wgpu::TextureSampleType::Depth,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 2,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Comparison),
+                count: None,
+            },
+        ],
+    })
+}
@@ -0,0 +1,59 @@
+struct VertexOut {
+    @builtin(position) position: vec4<f32>,
+    @location(0) ndc: vec2<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOut {
+    // Single oversized triangle covering the screen, on the far plane
+    // (reverse-Z: depth 0).
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    let ndc = vec2<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0);
+    var out: VertexOut;
+    out.position = vec4<f32>(ndc, 0.0, 1.0);
+    out.ndc = ndc;
+    return out;
+}
+
+// See `sky_dome::SkyUniform`.
+struct Sky {
+    inv_view_proj: mat4x4<f32>,
+    // xyz: direction towards the sun.
+    sun: vec4<f32>,
+    zenith: vec4<f32>,
+    horizon: vec4<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> u_sky: Sky;
+
+// Cosine of the angular radius of the sun's and moon's discs.
+const SUN_DISC: f32 = 0.9995;
+const MOON_DISC: f32 = 0.9997;
+const SUN_COLOR: vec3<f32> = vec3<f32>(4.0, 3.6, 3.0);
+const MOON_COLOR: vec3<f32> = vec3<f32>(0.6, 0.65, 0.75);
+// Brightness of the haze around the sun.
+const SUN_GLOW: f32 = 0.35;
+
+// Linear sky color along `dir`; `raytrace_compute.wgsl` draws the same sky
+// for rays that leave the world.
+fn sky_color(dir: vec3<f32>) -> vec3<f32> {
+    let up = saturate(dir.y);
+    // Below the horizon the sky fades towards a darker haze.
+    let below = saturate(-dir.y * 4.0);
+    var color = mix(u_sky.horizon.rgb, u_sky.zenith.rgb, sqrt(up)) * (1.0 - below * 0.5);
+    let toward_sun = dot(dir, u_sky.sun.xyz);
+    let above = smoothstep(-0.02, 0.02, dir.y);
+    color += u_sky.horizon.rgb * pow(saturate(toward_sun), 8.0) * SUN_GLOW;
+    color += SUN_COLOR * smoothstep(SUN_DISC - 0.0002, SUN_DISC, toward_sun) * above;
+    color += MOON_COLOR * smoothstep(MOON_DISC - 0.0002, MOON_DISC, -toward_sun) * above;
+    return color;
+}
+
+@fragment
+fn fs_main(in: VertexOut) -> @location(0) vec4<f32> {
+    // Reverse-Z: depth 1 is the near plane.
+    let near = u_sky.inv_view_proj * vec4<f32>(in.ndc, 1.0, 1.0);
+    let dir = normalize(near.xyz / near.w);
+    return vec4<f32>(sky_color(dir), 1.0);
+}
@@ -0,0 +1,161 @@
+use crate::block::BlockKind;
+use crate::coords::LocalPos;
+use crate::light::blocks_light;
+use crate::world::{CHUNK_SIZE, World};
+
+/// Horizontal directions the horizon is searched along.
+const DIRECTIONS: [[f32; 2]; 8] = [
+    [1.0, 0.0],
+    [0.70710677, 0.70710677],
+    [0.0, 1.0],
+    [-0.70710677, 0.70710677],
+    [-1.0, 0.0],
+    [-0.70710677, -0.70710677],
+    [0.0, -1.0],
+    [0.70710677, -0.70710677],
+];
+/// Distances (in blocks) each direction is sampled at; sparser farther out,
+/// where a column has to be much taller to raise the horizon.
+const SAMPLE_DISTANCES: [f32; 7] = [1.0, 2.0, 3.0, 5.0, 8.0, 12.0, 16.0];
+/// Brightness of a fully occluded face relative to one under open sky.
+const MIN_SKY_LIGHT: f32 = 0.35;
+
+/// Height of the highest sky-blocking block of every loaded column.
+///
+/// Rebuilt from the world whenever it changes, so it follows block edits and
+/// chunk streaming; the raster renderer remeshes the chunks near columns
+/// whose height changed.
+pub struct Heightmap {
+    min_x: i32,
+    min_z: i32,
+    width: usize,
+    depth: usize,
+    /// Top block y per column, or `i32::MIN` for columns with nothing blocking.
+    heights: Vec<i32>,
+}
+
+impl Heightmap {
+    /// A heightmap with no columns, as for a world with nothing loaded.
+    pub fn empty() -> Self {
+        Self {
+            min_x: 0,
+            min_z: 0,
+            width: 0,
+            depth: 0,
+            heights: Vec::new(),
+        }
+    }
+
+    pub fn build(world: &World) -> Self {
+        let size = CHUNK_SIZE as i32;
+        let mut bounds: Option<(i32, i32, i32, i32)> = None;
+        for (coord, _) in world.iter_chunks() {
+            let (min_x, min_z, max_x, max_z) =
+                bounds.unwrap_or((coord.x, coord.z, coord.x, coord.z));
+            bounds = Some((
+                min_x.min(coord.x),
+                min_z.min(coord.z),
+                max_x.max(coord.x),
+                max_z.max(coord.z),
+            ));
+        }
+        let Some((min_cx, min_cz, max_cx, max_cz)) = bounds else {
+            return Self::empty();
+        };
+
+        let width = ((max_cx - min_cx + 1) * size) as usize;
+        let depth = ((max_cz - min_cz + 1) * size) as usize;
+        let mut heightmap = Self {
+            min_x: min_cx * size,
+            min_z: min_cz * size,
+            width,
+            depth,
+            heights: vec![i32::MIN; width * depth],
+        };
+
+        for (coord, chunk) in world.iter_chunks() {
+            for z in 0..CHUNK_SIZE {
+                for x in 0..CHUNK_SIZE {
+                    let Some(top) = (0..CHUNK_SIZE)
+                        .rev()
+                        .map(|y| LocalPos::new(x, y, z))
+                        .find(|&local| blocks_light(BlockKind::from_id(chunk.get(local))))
+                        .map(|local| coord.block(local))
+                    else {
+                        continue;
+                    };
+                    let column = heightmap
+                        .index(top.x, top.z)
+                        .expect("loaded chunks lie inside the heightmap");
+                    heightmap.heights[column] = heightmap.heights[column].max(top.y);
+                }
+            }
+        }
+        heightmap
+    }
+
+    /// Columns whose top differs between this and `previous`, including those
+    /// only one of them covers. Columns may be listed twice.
+    pub fn changed_columns<'a>(
+        &'a self,
+        previous: &'a Heightmap,
+    ) -> impl Iterator<Item = [i32; 2]> + 'a {
+        let changed_in = |map: &'a Heightmap, other: &'a Heightmap| {
+            map.columns()
+                .filter(move |&([x, z], top)| other.height(x, z) != top)
+                .map(|(column, _)| column)
+        };
+        changed_in(self, previous).chain(changed_in(previous, self))
+    }
+
+    fn columns(&self) -> impl Iterator<Item = ([i32; 2], i32)> + '_ {
+        self.heights.iter().enumerate().map(|(index, &top)| {
+            let x = self.min_x + (index % self.width) as i32;
+            let z = self.min_z + (index / self.width) as i32;
+            ([x, z], top)
+        })
+    }
+
+    fn height(&self, x: i32, z: i32) -> i32 {
+        self.index(x, z)
+            .map_or(i32::MIN, |index| self.heights[index])
+    }
+
+    fn index(&self, x: i32, z: i32) -> Option<usize> {
+        let local_x = usize::try_from(x - self.min_x).ok()?;
+        let local_z = usize::try_from(z - self.min_z).ok()?;
+        (local_x < self.width && local_z < self.depth).then(|| local_x + local_z * self.width)
+    }
+
+    /// Brightness factor (`MIN_SKY_LIGHT..=1`) for faces lit from the cell at
+    /// `cell`, from how much of the sky the columns around it hide.
+    ///
+    /// Each direction's horizon is the steepest angle up to a column top seen
+    /// from the middle of the cell, and leaves `cos²` of that slice of the sky
+    /// visible, as for an upward-facing surface under uniform sky light.
+    pub fn sky_light(&self, cell: [i32; 3]) -> f32 {
+        let eye = [
+            cell[0] as f32 + 0.5,
+            cell[1] as f32 + 0.5,
+            cell[2] as f32 + 0.5,
+        ];
+        let mut visible = 0.0;
+        for [dx, dz] in DIRECTIONS {
+            let mut horizon: f32 = 0.0;
+            for distance in SAMPLE_DISTANCES {
+                let x = (eye[0] + dx * distance).floor() as i32;
+                let z = (eye[2] + dz * distance).floor() as i32;
+                let top = self.height(x, z);
+                if top == i32::MIN {
+                    continue;
+                }
+                // The column occludes up to the top of its highest block.
+                horizon = horizon.max((top as f32 + 1.0 - eye[1]) / distance);
+            }
+            // cos² of the horizon angle, from its tangent.
+            visible += 1.0 / (1.0 + horizon * horizon);
+        }
+        let visible = visible / DIRECTIONS.len() as f32;
+        MIN_SKY_LIGHT + (1.0 - MIN_SKY_LIGHT) * visible
+    }
+}
@@ -0,0 +1,102 @@
+use crate::camera::{Camera, CameraUniform, Projection};
+use crate::render::labels;
+
+/// Where a renderer draws a frame: the window's surface texture or a
+/// [`RenderTexture`]. The view's format must be the renderer's
+/// [`output_format`](super::Renderer::output_format).
+#[derive(Clone, Copy)]
+pub struct RenderTarget<'a> {
+    pub view: &'a wgpu::TextureView,
+    pub size: (u32, u32),
+}
+
+/// An offscreen color texture for a renderer to draw into, which can then be
+/// sampled or copied out.
+pub struct RenderTexture {
+    _texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    size: (u32, u32),
+}
+
+impl RenderTexture {
+    pub fn new(
+        device: &wgpu::Device,
+        label: &str,
+        (width, height): (u32, u32),
+        format: wgpu::TextureFormat,
+    ) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = labels::labeled_view(&texture, &format!("{label} view"));
+        Self {
+            _texture: texture,
+            view,
+            size: (width, height),
+        }
+    }
+
+    pub fn target(&self) -> RenderTarget<'_> {
+        RenderTarget {
+            view: &self.view,
+            size: self.size,
+        }
+    }
+
+    pub fn view(&self) -> &wgpu::TextureView {
+        &self.view
+    }
+}
+
+/// A camera's uniform buffer and the bind group the renderers read it
+/// through, one per view being drawn.
+pub struct CameraBinding {
+    buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+}
+
+impl CameraBinding {
+    pub fn new(device: &wgpu::Device, layout: &wgpu::BindGroupLayout, label: &str) -> Self {
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(&format!("{label} buffer")),
+            size: std::mem::size_of::<CameraUniform>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some(&format!("{label} bind group")),
+            layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+        });
+        Self { buffer, bind_group }
+    }
+
+    /// Points the binding at `camera`. Like every buffer write this lands at
+    /// the next submit, so a binding shared between views in one submission
+    /// shows the last camera written to all of them.
+    pub fn update(&self, queue: &wgpu::Queue, camera: &Camera, projection: &Projection) {
+        let mut uniform = CameraUniform::new();
+        uniform.update(camera, projection);
+        queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&[uniform]));
+    }
+
+    pub fn bind_group(&self) -> &wgpu::BindGroup {
+        &self.bind_group
+    }
+}
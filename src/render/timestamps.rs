@@ -0,0 +1,178 @@
+//! GPU time spent on each renderer's two stretches of a frame, read from
+//! timestamp queries.
+//!
+//! Reading the queries back never waits on the GPU: the results of a frame
+//! are mapped once it has been submitted and picked up on a later frame
+//! when they are ready, and no new frame is timed while a readback is in
+//! flight. The times reported are therefore those of a recent frame rather
+//! than the current one.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// The two stretches of a frame each renderer times, as `RenderTimings`
+/// reports them.
+#[derive(Clone, Copy)]
+pub enum GpuSpan {
+    /// Drawing the scene: the ray tracer's compute pass, or the
+    /// rasterizer's culling, shadow, world and water passes.
+    Compute,
+    /// Putting it on the target: the ray tracer's blit, or the rasterizer's
+    /// light shaft and output pass.
+    Present,
+}
+
+impl GpuSpan {
+    /// Index of the span's first query; its end is the next one.
+    fn query(self) -> u32 {
+        match self {
+            GpuSpan::Compute => 0,
+            GpuSpan::Present => 2,
+        }
+    }
+}
+
+const QUERY_COUNT: u32 = 4;
+const BUFFER_SIZE: u64 = QUERY_COUNT as u64 * std::mem::size_of::<u64>() as u64;
+
+/// Readback states, shared with the `map_async` callback.
+const IDLE: u8 = 0;
+const RESOLVED: u8 = 1;
+const MAPPING: u8 = 2;
+const MAPPED: u8 = 3;
+
+/// Times of the last frame read back, in milliseconds.
+#[derive(Clone, Copy, Default)]
+pub struct GpuTimes {
+    pub compute_ms: f32,
+    pub present_ms: f32,
+}
+
+pub struct GpuTimer {
+    query_set: wgpu::QuerySet,
+    resolve_buffer: wgpu::Buffer,
+    readback_buffer: wgpu::Buffer,
+    /// Nanoseconds per timestamp tick.
+    period: f32,
+    state: Arc<AtomicU8>,
+    /// Whether the frame being recorded writes timestamps.
+    recording: bool,
+    times: Option<GpuTimes>,
+}
+
+impl GpuTimer {
+    /// A timer labelled after `renderer`, or `None` if the device cannot
+    /// write timestamps.
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, renderer: &str) -> Option<Self> {
+        if !device.features().contains(wgpu::Features::TIMESTAMP_QUERY) {
+            return None;
+        }
+        let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some(&format!("{renderer} timestamps")),
+            ty: wgpu::QueryType::Timestamp,
+            count: QUERY_COUNT,
+        });
+        let resolve_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(&format!("{renderer} timestamp resolve buffer")),
+            size: BUFFER_SIZE,
+            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(&format!("{renderer} timestamp readback buffer")),
+            size: BUFFER_SIZE,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        Some(Self {
+            query_set,
+            resolve_buffer,
+            readback_buffer,
+            period: queue.get_timestamp_period(),
+            state: Arc::new(AtomicU8::new(IDLE)),
+            recording: false,
+            times: None,
+        })
+    }
+
+    /// Moves the readback of an earlier frame along, and decides whether
+    /// the frame about to be recorded is timed. Call once per frame before
+    /// any span.
+    pub fn begin_frame(&mut self, device: &wgpu::Device) {
+        match self.state.load(Ordering::Acquire) {
+            RESOLVED => {
+                // The resolving frame has been submitted by now.
+                self.state.store(MAPPING, Ordering::Release);
+                let state = Arc::clone(&self.state);
+                self.readback_buffer
+                    .slice(..)
+                    .map_async(wgpu::MapMode::Read, move |result| {
+                        let next = if result.is_ok() { MAPPED } else { IDLE };
+                        state.store(next, Ordering::Release);
+                    });
+                device.poll(wgpu::Maintain::Poll);
+            }
+            MAPPING => {
+                device.poll(wgpu::Maintain::Poll);
+            }
+            _ => {}
+        }
+        if self.state.load(Ordering::Acquire) == MAPPED {
+            self.read_back();
+        }
+        self.recording = self.state.load(Ordering::Acquire) == IDLE;
+    }
+
+    /// Marks the start of `span` in `encoder`, if this frame is timed.
+    pub fn start(&self, encoder: &mut wgpu::CommandEncoder, span: GpuSpan) {
+        if self.recording {
+            encoder.write_timestamp(&self.query_set, span.query());
+        }
+    }
+
+    /// Marks the end of `span` in `encoder`, if this frame is timed.
+    pub fn end(&self, encoder: &mut wgpu::CommandEncoder, span: GpuSpan) {
+        if self.recording {
+            encoder.write_timestamp(&self.query_set, span.query() + 1);
+        }
+    }
+
+    /// Copies this frame's timestamps out for reading, once both spans are
+    /// written.
+    pub fn resolve(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        if !self.recording {
+            return;
+        }
+        encoder.resolve_query_set(&self.query_set, 0..QUERY_COUNT, &self.resolve_buffer, 0);
+        encoder.copy_buffer_to_buffer(
+            &self.resolve_buffer,
+            0,
+            &self.readback_buffer,
+            0,
+            BUFFER_SIZE,
+        );
+        self.recording = false;
+        self.state.store(RESOLVED, Ordering::Release);
+    }
+
+    /// The most recent frame's times, once one has been read back.
+    pub fn times(&self) -> Option<GpuTimes> {
+        self.times
+    }
+
+    fn read_back(&mut self) {
+        let slice = self.readback_buffer.slice(..);
+        let data = slice.get_mapped_range();
+        let values: &[u64] = bytemuck::cast_slice(&data);
+        let ms = |start: usize| {
+            values[start + 1].saturating_sub(values[start]) as f32 * self.period / 1_000_000.0
+        };
+        self.times = Some(GpuTimes {
+            compute_ms: ms(GpuSpan::Compute.query() as usize),
+            present_ms: ms(GpuSpan::Present.query() as usize),
+        });
+        drop(data);
+        self.readback_buffer.unmap();
+        self.state.store(IDLE, Ordering::Release);
+    }
+}
@@ -0,0 +1,142 @@
+//! How much work the ray tracer's grid traversal did, counted by the compute
+//! shader and read back like the timestamps: never waiting on the GPU, and
+//! reporting a recent frame rather than the current one.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// Counters the shader adds to; must match `TraversalCounters` in
+/// `raytrace_compute.wgsl`.
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct GpuCounters {
+    /// Voxel steps, as the low and high halves of a 64-bit count.
+    steps_low: u32,
+    steps_high: u32,
+    rays: u32,
+    skips: u32,
+}
+
+const BUFFER_SIZE: u64 = std::mem::size_of::<GpuCounters>() as u64;
+
+/// Readback states, shared with the `map_async` callback.
+const IDLE: u8 = 0;
+const COPIED: u8 = 1;
+const MAPPING: u8 = 2;
+const MAPPED: u8 = 3;
+
+/// Traversal work of one frame's view.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TraversalCounts {
+    /// Cells rays stepped through one at a time.
+    pub steps: u64,
+    /// Rays traced through the grid: camera rays and their bounces.
+    pub rays: u32,
+    /// Empty chunks and occupancy cells crossed in a single step.
+    pub skips: u32,
+}
+
+impl TraversalCounts {
+    pub fn steps_per_ray(&self) -> f32 {
+        self.steps as f32 / self.rays.max(1) as f32
+    }
+}
+
+pub struct TraversalCounter {
+    buffer: wgpu::Buffer,
+    readback_buffer: wgpu::Buffer,
+    state: Arc<AtomicU8>,
+    /// Whether the frame being recorded copies its counts out.
+    recording: bool,
+    counts: Option<TraversalCounts>,
+}
+
+impl TraversalCounter {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Ray traversal counter buffer"),
+            size: BUFFER_SIZE,
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_SRC
+                | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Ray traversal readback buffer"),
+            size: BUFFER_SIZE,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        Self {
+            buffer,
+            readback_buffer,
+            state: Arc::new(AtomicU8::new(IDLE)),
+            recording: false,
+            counts: None,
+        }
+    }
+
+    /// The storage buffer the shader counts into.
+    pub fn buffer(&self) -> &wgpu::Buffer {
+        &self.buffer
+    }
+
+    /// Moves the readback of an earlier frame along, and zeroes the counters
+    /// for the frame being recorded. Call once per frame, before its
+    /// dispatch.
+    pub fn begin_frame(&mut self, device: &wgpu::Device, encoder: &mut wgpu::CommandEncoder) {
+        match self.state.load(Ordering::Acquire) {
+            COPIED => {
+                // The copying frame has been submitted by now.
+                self.state.store(MAPPING, Ordering::Release);
+                let state = Arc::clone(&self.state);
+                self.readback_buffer
+                    .slice(..)
+                    .map_async(wgpu::MapMode::Read, move |result| {
+                        let next = if result.is_ok() { MAPPED } else { IDLE };
+                        state.store(next, Ordering::Release);
+                    });
+                device.poll(wgpu::Maintain::Poll);
+            }
+            MAPPING => {
+                device.poll(wgpu::Maintain::Poll);
+            }
+            _ => {}
+        }
+        if self.state.load(Ordering::Acquire) == MAPPED {
+            self.read_back();
+        }
+        self.recording = self.state.load(Ordering::Acquire) == IDLE;
+        encoder.clear_buffer(&self.buffer, 0, None);
+    }
+
+    /// Copies this frame's counts out for reading, once its dispatch is
+    /// recorded.
+    pub fn resolve(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        if !self.recording {
+            return;
+        }
+        encoder.copy_buffer_to_buffer(&self.buffer, 0, &self.readback_buffer, 0, BUFFER_SIZE);
+        self.recording = false;
+        self.state.store(COPIED, Ordering::Release);
+    }
+
+    /// The most recent frame's counts, once one has been read back.
+    pub fn counts(&self) -> Option<TraversalCounts> {
+        self.counts
+    }
+
+    fn read_back(&mut self) {
+        let slice = self.readback_buffer.slice(..);
+        let data = slice.get_mapped_range();
+        let counters: GpuCounters = bytemuck::pod_read_unaligned(&data);
+        self.counts = Some(TraversalCounts {
+            steps: u64::from(counters.steps_high) << 32 | u64::from(counters.steps_low),
+            rays: counters.rays,
+            skips: counters.skips,
+        });
+        drop(data);
+        self.readback_buffer.unmap();
+        self.state.store(IDLE, Ordering::Release);
+    }
+}
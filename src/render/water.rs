@@ -0,0 +1,212 @@
+use bytemuck::{Pod, Zeroable};
+use glam::{Mat4, Vec3};
+
+use crate::camera::{Camera, CameraUniform, Projection};
+use crate::config::WaterQuality;
+use crate::render::labels;
+use crate::render::mesh::LIQUID_SURFACE;
+use crate::sky::SkyLight;
+use crate::terrain::SEA_LEVEL;
+
+/// Height planar reflections mirror the world about: the surface of
+/// sea-level water. Must match `WATER_PLANE` in `shader.wgsl`.
+const WATER_PLANE: f32 = SEA_LEVEL as f32 + LIQUID_SURFACE;
+
+/// Per-frame parameters of `water.wgsl`.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct WaterUniform {
+    view_proj: [[f32; 4]; 4],
+    inv_view_proj: [[f32; 4]; 4],
+    eye: [f32; 4],
+    params: [f32; 4],
+    sun: [f32; 4],
+    sky_zenith: [f32; 4],
+    sky_horizon: [f32; 4],
+}
+
+impl WaterUniform {
+    pub fn new(
+        camera: &Camera,
+        projection: &Projection,
+        quality: WaterQuality,
+        sky: &SkyLight,
+        time: f32,
+        (width, height): (u32, u32),
+    ) -> Self {
+        let view_proj = projection.matrix() * camera.view_matrix();
+        let eye = camera.position;
+        let mode = match quality {
+            WaterQuality::ScreenSpace => 0.0,
+            WaterQuality::Planar => 1.0,
+        };
+        Self {
+            view_proj: view_proj.to_cols_array_2d(),
+            inv_view_proj: view_proj.inverse().to_cols_array_2d(),
+            eye: [eye.x, eye.y, eye.z, time],
+            params: [mode, WATER_PLANE, width as f32, height as f32],
+            // The moon glints too, faintly, while the sun is down.
+            sun: sky
+                .light_direction
+                .extend(sky.light_color.max_element())
+                .to_array(),
+            sky_zenith: sky.zenith.extend(1.0).to_array(),
+            sky_horizon: sky.horizon.extend(1.0).to_array(),
+        }
+    }
+}
+
+/// Camera for the planar reflection pass: the real camera looking at the
+/// world mirrored about the water plane.
+pub fn reflection_camera(camera: &Camera, projection: &Projection) -> CameraUniform {
+    let mirror = Mat4::from_translation(Vec3::Y * WATER_PLANE)
+        * Mat4::from_scale(Vec3::new(1.0, -1.0, 1.0))
+        * Mat4::from_translation(Vec3::NEG_Y * WATER_PLANE);
+    let view_proj = projection.matrix() * camera.view_matrix() * mirror;
+    CameraUniform {
+        view_proj: view_proj.to_cols_array_2d(),
+    }
+}
+
+/// Size-dependent textures of the water pass: copies of the frame it reads
+/// while drawing into it, the half-resolution planar reflection, and the bind
+/// group exposing them to `water.wgsl`.
+pub struct WaterTargets {
+    pub scene_color: wgpu::Texture,
+    pub scene_depth: wgpu::Texture,
+    _reflection_color: wgpu::Texture,
+    pub reflection_color_view: wgpu::TextureView,
+    _reflection_depth: wgpu::Texture,
+    pub reflection_depth_view: wgpu::TextureView,
+    pub bind_group: wgpu::BindGroup,
+}
+
+impl WaterTargets {
+    pub fn create(
+        device: &wgpu::Device,
+        (width, height): (u32, u32),
+        color_format: wgpu::TextureFormat,
+        depth_format: wgpu::TextureFormat,
+        layout: &wgpu::BindGroupLayout,
+        sampler: &wgpu::Sampler,
+        uniform_buffer: &wgpu::Buffer,
+    ) -> Self {
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+        let half = wgpu::Extent3d {
+            width: (width / 2).max(1),
+            height: (height / 2).max(1),
+            depth_or_array_layers: 1,
+        };
+        let texture = |label, size, format, usage| {
+            device.create_texture(&wgpu::TextureDescriptor {
+                label: Some(&labels::sized_label(label, (width, height))),
+                size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage,
+                view_formats: &[],
+            })
+        };
+        let copy_usage = wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::TEXTURE_BINDING;
+        let target_usage =
+            wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING;
+
+        let scene_color = texture("Water scene color copy", size, color_format, copy_usage);
+        let scene_depth = texture("Water scene depth copy", size, depth_format, copy_usage);
+        let reflection_color = texture("Water reflection color", half, color_format, target_usage);
+        let reflection_depth = texture(
+            "Water reflection depth",
+            half,
+            depth_format,
+            wgpu::TextureUsages::RENDER_ATTACHMENT,
+        );
+
+        let scene_color_view = labels::labeled_view(&scene_color, "Water scene color copy view");
+        let scene_depth_view = labels::labeled_view(&scene_depth, "Water scene depth copy view");
+        let reflection_color_view =
+            labels::labeled_view(&reflection_color, "Water reflection color view");
+        let reflection_depth_view =
+            labels::labeled_view(&reflection_depth, "Water reflection depth view");
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Water bind group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&scene_color_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(&scene_depth_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::TextureView(&reflection_color_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+        });
+
+        Self {
+            scene_color,
+            scene_depth,
+            _reflection_color: reflection_color,
+            reflection_color_view,
+            _reflection_depth: reflection_depth,
+            reflection_depth_view,
+            bind_group,
+        }
+    }
+}
+
+pub fn bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    let texture = |binding, sample_type| wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Texture {
+            multisampled: false,
+            view_dimension: wgpu::TextureViewDimension::D2,
+            sample_type,
+        },
+        count: None,
+    };
+    let color = wgpu::TextureSampleType::Float { filterable: true };
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Water bind group layout"),
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            texture(1, color),
+            texture(2, wgpu::TextureSampleType::Depth),
+            texture(3, color),
+            wgpu::BindGroupLayoutEntry {
+                binding: 4,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+        ],
+    })
+}
@@ -0,0 +1,257 @@
+struct Camera {
+    view_proj: mat4x4<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> u_camera: Camera;
+
+@group(1) @binding(0)
+var u_atlas: texture_2d<f32>;
+
+@group(1) @binding(1)
+var u_sampler: sampler;
+
+// Placement of the mesh being drawn; see `ChunkUniform`.
+struct Chunk {
+    // xyz: origin of the mesh's vertex positions, w: level of detail.
+    origin: vec4<f32>,
+    tint: vec4<f32>,
+};
+
+@group(2) @binding(0)
+var<uniform> u_chunk: Chunk;
+
+struct Water {
+    view_proj: mat4x4<f32>,
+    inv_view_proj: mat4x4<f32>,
+    // xyz: camera position, w: seconds since the world was opened.
+    eye: vec4<f32>,
+    // x: reflection mode (0 screen space, 1 planar), y: height of the planar
+    // reflection's mirror, zw: viewport size in pixels.
+    params: vec4<f32>,
+    // xyz: direction towards the sun or moon, w: strength of its light.
+    sun: vec4<f32>,
+    sky_zenith: vec4<f32>,
+    sky_horizon: vec4<f32>,
+};
+
+@group(3) @binding(0)
+var<uniform> u_water: Water;
+
+// Copies of the frame drawn so far, since the water pass draws into it.
+@group(3) @binding(1)
+var scene_color: texture_2d<f32>;
+
+@group(3) @binding(2)
+var scene_depth: texture_depth_2d;
+
+// The world mirrored about the water plane; only rendered in planar mode.
+@group(3) @binding(3)
+var reflection_color: texture_2d<f32>;
+
+@group(3) @binding(4)
+var linear_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) color: vec3<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) @interpolate(flat) tile: vec4<f32>,
+    @location(3) world: vec3<f32>,
+};
+
+const MODE_PLANAR: f32 = 1.0;
+// Reflectance of water seen head-on, from its index of refraction (1.33).
+const WATER_F0: f32 = 0.02;
+// Share of the water texture's colour mixed into what is seen through it.
+const WATER_TINT: f32 = 0.4;
+const WATER_FOG_COLOR: vec3<f32> = vec3<f32>(0.04, 0.16, 0.26);
+const WATER_FOG_DENSITY: f32 = 0.12;
+const RIPPLE_STRENGTH: f32 = 0.2;
+// How far ripples shift the refracted and planar-reflected images, in UV units.
+const RIPPLE_SHIFT: f32 = 0.03;
+const SUN_GLINT: f32 = 4.0;
+// Mirrors `BLOCK_LIGHT_COLOR` in `shader.wgsl`.
+const BLOCK_LIGHT_COLOR: vec3<f32> = vec3<f32>(1.0, 0.85, 0.65);
+const SSR_STEPS: u32 = 48u;
+const SSR_FIRST_STEP: f32 = 0.2;
+const SSR_STEP_GROWTH: f32 = 1.08;
+
+@vertex
+fn vs_main(
+    @location(0) position: vec3<f32>,
+    @location(1) color: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+    @location(3) tile: vec4<f32>,
+    @location(4) alpha_cutoff: f32,
+    // x: sky light, y: block light.
+    @location(5) light: vec2<f32>,
+) -> VertexOutput {
+    let world = u_chunk.origin.xyz + position * exp2(u_chunk.origin.w);
+    var out: VertexOutput;
+    out.position = u_camera.view_proj * vec4<f32>(world, 1.0);
+    let lit = max(vec3<f32>(light.x), light.y * BLOCK_LIGHT_COLOR);
+    out.color = color * u_chunk.tint.rgb * lit;
+    out.uv = uv;
+    out.tile = tile;
+    out.world = world;
+    return out;
+}
+
+fn saturate(x: f32) -> f32 {
+    return clamp(x, 0.0, 1.0);
+}
+
+fn schlick(f0: f32, cos_theta: f32) -> f32 {
+    let base = saturate(1.0 - cos_theta);
+    let factor = base * base * base * base * base;
+    return f0 + (1.0 - f0) * factor;
+}
+
+fn hash_u32(value: u32) -> u32 {
+    var x = value;
+    x = (x ^ (x >> 17u)) * 0xed5ad4bbu;
+    x = (x ^ (x >> 11u)) * 0xac4c1b51u;
+    x = (x ^ (x >> 15u)) * 0x31848babu;
+    return x ^ (x >> 14u);
+}
+
+fn lattice_value(cell: vec2<i32>) -> f32 {
+    let hashed = hash_u32((u32(cell.x) * 0x8da6b343u) ^ (u32(cell.y) * 0xd8163841u));
+    return f32(hashed & 0xFFFFu) / 65535.0;
+}
+
+// Smoothly interpolated lattice noise in [0, 1].
+fn value_noise(p: vec2<f32>) -> f32 {
+    let cell = floor(p);
+    let f = p - cell;
+    let u = f * f * (3.0 - 2.0 * f);
+    let c = vec2<i32>(cell);
+    let a = lattice_value(c);
+    let b = lattice_value(c + vec2<i32>(1, 0));
+    let d = lattice_value(c + vec2<i32>(0, 1));
+    let e = lattice_value(c + vec2<i32>(1, 1));
+    return mix(mix(a, b, u.x), mix(d, e, u.x), u.y);
+}
+
+// Same ripples as the ray tracer's water.
+fn ripple_height(p: vec2<f32>) -> f32 {
+    let t = u_water.eye.w;
+    let slow = value_noise(p * 0.8 + vec2<f32>(t * 0.6, t * 0.35));
+    let fast = value_noise(p * 2.1 + vec2<f32>(-t * 0.9, t * 0.7));
+    return slow * 0.6 + fast * 0.25;
+}
+
+fn ripple_normal(position: vec3<f32>, normal: vec3<f32>) -> vec3<f32> {
+    if abs(normal.y) < 0.5 {
+        return normal;
+    }
+    let probe = 0.05;
+    // Relative to the render origin, so the pattern jumps when it rebases;
+    // the ripples are too busy for that to show.
+    let p = position.xz;
+    let height = ripple_height(p);
+    let dx = (ripple_height(p + vec2<f32>(probe, 0.0)) - height) / probe;
+    let dz = (ripple_height(p + vec2<f32>(0.0, probe)) - height) / probe;
+    let up = normalize(vec3<f32>(-dx * RIPPLE_STRENGTH, 1.0, -dz * RIPPLE_STRENGTH));
+    return up * normal.y;
+}
+
+fn water_fog(color: vec3<f32>, distance: f32) -> vec3<f32> {
+    return mix(WATER_FOG_COLOR, color, exp(-distance * WATER_FOG_DENSITY));
+}
+
+fn depth_at(uv: vec2<f32>) -> f32 {
+    let size = vec2<i32>(textureDimensions(scene_depth));
+    let texel = clamp(vec2<i32>(uv * vec2<f32>(size)), vec2<i32>(0), size - 1);
+    return textureLoad(scene_depth, texel, 0);
+}
+
+fn world_from_depth(uv: vec2<f32>, depth: f32) -> vec3<f32> {
+    let ndc = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, depth, 1.0);
+    let world = u_water.inv_view_proj * ndc;
+    return world.xyz / world.w;
+}
+
+// Marches `dir` from `origin` through the frame's depth buffer and returns
+// the colour where it first passes behind the scene, or `fallback` if it
+// leaves the screen first.
+fn screen_space_reflection(origin: vec3<f32>, dir: vec3<f32>, fallback: vec3<f32>) -> vec3<f32> {
+    var position = origin;
+    var stride = SSR_FIRST_STEP;
+    for (var i = 0u; i < SSR_STEPS; i = i + 1u) {
+        position += dir * stride;
+        stride *= SSR_STEP_GROWTH;
+        let clip = u_water.view_proj * vec4<f32>(position, 1.0);
+        if clip.w <= 0.0 {
+            break;
+        }
+        let ndc = clip.xyz / clip.w;
+        let uv = vec2<f32>(ndc.x * 0.5 + 0.5, 0.5 - ndc.y * 0.5);
+        if any(uv < vec2<f32>(0.0)) || any(uv > vec2<f32>(1.0)) {
+            break;
+        }
+        // Reverse-Z: the scene is in front of the ray where its depth is greater.
+        let depth = depth_at(uv);
+        if depth > ndc.z {
+            // Only a surface close to the ray counts; anything else is an
+            // object the ray passed behind.
+            if distance(world_from_depth(uv, depth), position) < stride * 2.0 {
+                return textureSampleLevel(scene_color, linear_sampler, uv, 0.0).rgb;
+            }
+            break;
+        }
+    }
+    return fallback;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let atlas_uv = in.tile.xy + fract(in.uv) * in.tile.zw;
+    let scaled = in.uv * in.tile.zw;
+    let albedo =
+        textureSampleGrad(u_atlas, u_sampler, atlas_uv, dpdx(scaled), dpdy(scaled)).rgb * in.color;
+    // Flat face normal from the position's screen-space derivatives.
+    var face = normalize(cross(dpdx(in.world), dpdy(in.world)));
+
+    let to_eye = u_water.eye.xyz - in.world;
+    let view = normalize(to_eye);
+    if dot(face, view) < 0.0 {
+        face = -face;
+    }
+    // Looking up at the surface from below.
+    let underwater = face.y < -0.5;
+    let normal = ripple_normal(in.world, face);
+    let fresnel = schlick(WATER_F0, saturate(dot(normal, view)));
+    let screen_uv = in.position.xy / u_water.params.zw;
+
+    // Refraction: the frame behind the surface, shifted by the ripples unless
+    // that would pick up something in front of the water.
+    var refract_uv = screen_uv + normal.xz * RIPPLE_SHIFT;
+    if depth_at(refract_uv) > in.position.z {
+        refract_uv = screen_uv;
+    }
+    let tint = mix(vec3<f32>(1.0), albedo, WATER_TINT);
+    var refraction = textureSampleLevel(scene_color, linear_sampler, refract_uv, 0.0).rgb * tint;
+    if !underwater {
+        let bottom = world_from_depth(refract_uv, depth_at(refract_uv));
+        refraction = water_fog(refraction, distance(bottom, in.world));
+    }
+
+    let reflected = reflect(-view, normal);
+    let sky = mix(u_water.sky_horizon.rgb, u_water.sky_zenith.rgb, sqrt(saturate(reflected.y)));
+    var reflection = sky;
+    let on_plane = face.y > 0.5 && abs(in.world.y - u_water.params.y) < 0.01;
+    if u_water.params.x == MODE_PLANAR && on_plane {
+        let uv = screen_uv + normal.xz * RIPPLE_SHIFT;
+        reflection = textureSampleLevel(reflection_color, linear_sampler, uv, 0.0).rgb;
+    } else {
+        reflection = screen_space_reflection(in.world + face * 0.01, reflected, sky);
+    }
+
+    var color = mix(refraction, reflection, fresnel);
+    if !underwater {
+        color += vec3<f32>(pow(saturate(dot(reflected, u_water.sun.xyz)), 256.0) * SUN_GLINT * u_water.sun.w);
+    }
+    return vec4<f32>(color, 1.0);
+}
@@ -0,0 +1,279 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use glam::Vec3;
+use log::warn;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::coords::ChunkPos;
+use crate::dimension::Dimension;
+use crate::world::RegionStorage;
+
+/// Per-world files under `saves/<world>/`.
+///
+/// Worlds are named after their terrain preset and seed, so switching either
+/// in the config switches saves too. The world's flags and portals live at
+/// the top; chunks, entities and the home are per dimension, the
+/// overworld's at the top too and every other's under
+/// `dimensions/<name>/`.
+pub struct WorldSave {
+    root: PathBuf,
+    /// Directory of the dimension this save reads and writes.
+    dir: PathBuf,
+}
+
+/// Flags fixed when a world is created, plus whether it has ended.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct WorldInfo {
+    /// Death ends the world instead of respawning the player.
+    pub hardcore: bool,
+    /// Set when a hardcore player dies; the world can only be spectated.
+    #[serde(default)]
+    pub spectator_only: bool,
+}
+
+/// An entity as stored in its chunk's file.
+#[derive(Serialize, Deserialize)]
+pub struct EntityRecord {
+    /// `EntityKind::name`.
+    pub kind: String,
+    pub position: [f32; 3],
+    pub velocity: [f32; 3],
+    pub health: f32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub item: Option<ItemRecord>,
+}
+
+/// The stack carried by an item entity, and how long it has lain around.
+#[derive(Serialize, Deserialize)]
+pub struct ItemRecord {
+    /// `ItemKind::id`.
+    pub id: u16,
+    pub count: u8,
+    #[serde(default)]
+    pub data: u16,
+    pub age: f32,
+}
+
+/// A scheduled block update as stored in its chunk's file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduledUpdateRecord {
+    pub position: [i32; 3],
+    /// Ticks it had left to wait when it was saved; the tick counter starts
+    /// over every session.
+    pub delay: u64,
+}
+
+/// A portal as stored in the world's portal file.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct PortalRecord {
+    pub base: [i32; 3],
+    /// `FaceDirection` index.
+    pub facing: u8,
+    /// Portals saved before dimensions existed are all in the overworld.
+    #[serde(default)]
+    pub dimension: Dimension,
+}
+
+#[derive(Serialize, Deserialize)]
+struct HomeFile {
+    x: f32,
+    y: f32,
+    z: f32,
+}
+
+impl WorldSave {
+    pub fn new(world_name: &str) -> Self {
+        let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("saves")
+            .join(world_name);
+        Self {
+            dir: root.clone(),
+            root,
+        }
+    }
+
+    /// A save kept in `root` rather than under `saves/`, for tests.
+    #[cfg(test)]
+    pub fn at(root: PathBuf) -> Self {
+        Self {
+            dir: root.clone(),
+            root,
+        }
+    }
+
+    /// The same world's save, reading and writing `dimension`'s files.
+    pub fn in_dimension(&self, dimension: Dimension) -> Self {
+        let dir = match dimension {
+            Dimension::Overworld => self.root.clone(),
+            _ => self.root.join("dimensions").join(dimension.name()),
+        };
+        Self {
+            root: self.root.clone(),
+            dir,
+        }
+    }
+
+    /// Where the dimension's edited chunks are kept.
+    pub fn region_storage(&self) -> RegionStorage {
+        RegionStorage::new(self.dir.join("regions"))
+    }
+
+    /// Feet position set with `/sethome`, if any.
+    pub fn load_home(&self) -> Option<Vec3> {
+        let path = self.dir.join("home.json");
+        match fs::read(&path) {
+            Ok(bytes) => match serde_json::from_slice::<HomeFile>(&bytes) {
+                Ok(home) => Some(Vec3::new(home.x, home.y, home.z)),
+                Err(err) => {
+                    warn!("Failed to parse home file {}: {}", path.display(), err);
+                    None
+                }
+            },
+            Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+            Err(err) => {
+                warn!("Failed to read home file {}: {}", path.display(), err);
+                None
+            }
+        }
+    }
+
+    /// Portals built with `/portal`, in the order they were built.
+    pub fn load_portals(&self) -> Vec<PortalRecord> {
+        let path = self.root.join("portals.json");
+        match fs::read(&path) {
+            Ok(bytes) => match serde_json::from_slice(&bytes) {
+                Ok(records) => records,
+                Err(err) => {
+                    warn!("Failed to parse portal file {}: {}", path.display(), err);
+                    Vec::new()
+                }
+            },
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => {
+                warn!("Failed to read portal file {}: {}", path.display(), err);
+                Vec::new()
+            }
+        }
+    }
+
+    pub fn store_portals(&self, records: &[PortalRecord]) -> io::Result<()> {
+        fs::create_dir_all(&self.root)?;
+        let json = serde_json::to_string_pretty(records).map_err(io::Error::other)?;
+        fs::write(self.root.join("portals.json"), json + "\n")
+    }
+
+    /// The world's flags, or `None` for a world that has never been saved.
+    pub fn load_info(&self) -> Option<WorldInfo> {
+        let path = self.root.join("world.json");
+        match fs::read(&path) {
+            Ok(bytes) => match serde_json::from_slice(&bytes) {
+                Ok(info) => Some(info),
+                Err(err) => {
+                    warn!("Failed to parse world file {}: {}", path.display(), err);
+                    None
+                }
+            },
+            Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+            Err(err) => {
+                warn!("Failed to read world file {}: {}", path.display(), err);
+                None
+            }
+        }
+    }
+
+    pub fn store_info(&self, info: &WorldInfo) -> io::Result<()> {
+        fs::create_dir_all(&self.root)?;
+        let json = serde_json::to_string_pretty(info).map_err(io::Error::other)?;
+        fs::write(self.root.join("world.json"), json + "\n")
+    }
+
+    /// Entities saved when `coord` was last unloaded; empty when it had none.
+    pub fn load_entities(&self, coord: ChunkPos) -> Vec<EntityRecord> {
+        self.load_chunk_records("entities", "entity", coord)
+    }
+
+    /// Replaces the entities saved for `coord`; a chunk without any has no
+    /// file.
+    pub fn store_entities(&self, coord: ChunkPos, records: &[EntityRecord]) -> io::Result<()> {
+        self.store_chunk_records("entities", coord, records)
+    }
+
+    /// Block updates that were still pending when `coord` was last
+    /// unloaded; empty when it had none.
+    pub fn load_updates(&self, coord: ChunkPos) -> Vec<ScheduledUpdateRecord> {
+        self.load_chunk_records("updates", "block update", coord)
+    }
+
+    /// Replaces the block updates saved for `coord`; a chunk without any
+    /// has no file.
+    pub fn store_updates(
+        &self,
+        coord: ChunkPos,
+        records: &[ScheduledUpdateRecord],
+    ) -> io::Result<()> {
+        self.store_chunk_records("updates", coord, records)
+    }
+
+    /// The records of `coord` in the per-chunk `folder`, logging and
+    /// skipping a file that cannot be read as `what` records.
+    fn load_chunk_records<T: DeserializeOwned>(
+        &self,
+        folder: &str,
+        what: &str,
+        coord: ChunkPos,
+    ) -> Vec<T> {
+        let path = self.chunk_path(folder, coord);
+        match fs::read(&path) {
+            Ok(bytes) => match serde_json::from_slice(&bytes) {
+                Ok(records) => records,
+                Err(err) => {
+                    warn!("Failed to parse {} file {}: {}", what, path.display(), err);
+                    Vec::new()
+                }
+            },
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => {
+                warn!("Failed to read {} file {}: {}", what, path.display(), err);
+                Vec::new()
+            }
+        }
+    }
+
+    fn store_chunk_records<T: Serialize>(
+        &self,
+        folder: &str,
+        coord: ChunkPos,
+        records: &[T],
+    ) -> io::Result<()> {
+        let path = self.chunk_path(folder, coord);
+        if records.is_empty() {
+            return match fs::remove_file(&path) {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+                _ => Ok(()),
+            };
+        }
+        fs::create_dir_all(self.dir.join(folder))?;
+        let json = serde_json::to_string(records).map_err(io::Error::other)?;
+        fs::write(path, json + "\n")
+    }
+
+    fn chunk_path(&self, folder: &str, coord: ChunkPos) -> PathBuf {
+        self.dir
+            .join(folder)
+            .join(format!("{}_{}_{}.json", coord.x, coord.y, coord.z))
+    }
+
+    pub fn store_home(&self, feet: Vec3) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let home = HomeFile {
+            x: feet.x,
+            y: feet.y,
+            z: feet.z,
+        };
+        let json = serde_json::to_string_pretty(&home).map_err(io::Error::other)?;
+        fs::write(self.dir.join("home.json"), json + "\n")
+    }
+}
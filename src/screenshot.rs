@@ -0,0 +1,203 @@
+//! Screenshots of the window, taken with the screenshot key (`F2`) and
+//! written as PNGs to `screenshots/`.
+//!
+//! The surface is copied at the end of a frame, HUD included, so either
+//! renderer is captured exactly as shown. The copy is read back over the
+//! following frames without stalling them, and encoded on a worker thread.
+
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use image::RgbaImage;
+use log::{info, warn};
+
+use crate::events::Notice;
+use crate::render::{ReadbackPoll, TexelImage, TextureReadback};
+
+pub struct Screenshots {
+    directory: PathBuf,
+    /// Copies still on their way back from the GPU.
+    pending: Vec<TextureReadback>,
+    sender: mpsc::Sender<Notice>,
+    /// Outcomes of the PNGs the workers finished writing.
+    receiver: mpsc::Receiver<Notice>,
+}
+
+impl Screenshots {
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self {
+            directory: PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("screenshots"),
+            pending: Vec::new(),
+            sender,
+            receiver,
+        }
+    }
+
+    /// Records a copy of the frame's `surface` texture into `encoder`, which
+    /// must draw everything else first.
+    pub fn capture(
+        &mut self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        surface: &wgpu::Texture,
+    ) {
+        match TextureReadback::record(device, encoder, surface) {
+            Some(readback) => self.pending.push(readback),
+            None => warn!("Cannot read back a {:?} surface", surface.format()),
+        }
+    }
+
+    /// Moves captures along, handing each one read back to a worker that
+    /// writes its PNG. Returns what finished since the last call, for the
+    /// toasts.
+    pub fn poll(&mut self, device: &wgpu::Device) -> Vec<Notice> {
+        let mut notices = Vec::new();
+        let mut index = 0;
+        while index < self.pending.len() {
+            match self.pending[index].poll(device) {
+                ReadbackPoll::Pending => index += 1,
+                ReadbackPoll::Ready(image) => {
+                    self.pending.swap_remove(index);
+                    self.save(image);
+                }
+                ReadbackPoll::Failed => {
+                    self.pending.swap_remove(index);
+                    warn!("Failed to read the screenshot back from the GPU");
+                    notices.push(Notice::ScreenshotFailed);
+                }
+            }
+        }
+        notices.extend(self.receiver.try_iter());
+        notices
+    }
+
+    fn save(&self, image: TexelImage) {
+        let path = self.directory.join(file_name());
+        let sender = self.sender.clone();
+        let spawned = thread::Builder::new()
+            .name("screenshot-writer".into())
+            .spawn(move || {
+                let notice = match write_png(&path, &image) {
+                    Ok(()) => {
+                        info!("Saved screenshot {}", path.display());
+                        Notice::ScreenshotSaved
+                    }
+                    Err(err) => {
+                        warn!("Failed to save screenshot {}: {}", path.display(), err);
+                        Notice::ScreenshotFailed
+                    }
+                };
+                let _ = sender.send(notice);
+            });
+        if let Err(err) = spawned {
+            warn!("Failed to spawn screenshot writer thread: {}", err);
+            let _ = self.sender.send(Notice::ScreenshotFailed);
+        }
+    }
+}
+
+/// `screenshot-<unix seconds>-<milliseconds>.png`, so shots taken within the
+/// same second keep their order and do not overwrite each other.
+fn file_name() -> String {
+    let elapsed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    format!(
+        "screenshot-{}-{:03}.png",
+        elapsed.as_secs(),
+        elapsed.subsec_millis()
+    )
+}
+
+fn write_png(path: &Path, image: &TexelImage) -> Result<(), String> {
+    let rgba = to_rgba8(image).ok_or_else(|| format!("unsupported format {:?}", image.format))?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+    }
+    rgba.save(path).map_err(|err| err.to_string())
+}
+
+/// The image as 8-bit sRGB, the way the display showed it. HDR surfaces hold
+/// linear scRGB, which is clamped to SDR white and encoded.
+fn to_rgba8(image: &TexelImage) -> Option<RgbaImage> {
+    use wgpu::TextureFormat as F;
+
+    let data = match image.format {
+        F::Rgba8Unorm | F::Rgba8UnormSrgb => image.data.clone(),
+        F::Bgra8Unorm | F::Bgra8UnormSrgb => image
+            .data
+            .chunks_exact(4)
+            .flat_map(|texel| [texel[2], texel[1], texel[0], texel[3]])
+            .collect(),
+        F::Rgba16Float => image
+            .data
+            .chunks_exact(8)
+            .flat_map(|texel| {
+                let channel = |index: usize| {
+                    f16_to_f32(u16::from_le_bytes([texel[index * 2], texel[index * 2 + 1]]))
+                };
+                let encode = |linear: f32| (linear_to_srgb(linear) * 255.0).round() as u8;
+                [
+                    encode(channel(0)),
+                    encode(channel(1)),
+                    encode(channel(2)),
+                    (channel(3).clamp(0.0, 1.0) * 255.0).round() as u8,
+                ]
+            })
+            .collect(),
+        _ => return None,
+    };
+    RgbaImage::from_raw(image.width, image.height, data)
+}
+
+fn linear_to_srgb(linear: f32) -> f32 {
+    let linear = linear.clamp(0.0, 1.0);
+    if linear <= 0.003_130_8 {
+        linear * 12.92
+    } else {
+        1.055 * linear.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Widens an IEEE 754 half-precision float.
+fn f16_to_f32(bits: u16) -> f32 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = i32::from((bits >> 10) & 0x1f);
+    let mantissa = f32::from(bits & 0x3ff);
+    match exponent {
+        0 => sign * mantissa * 2f32.powi(-24),
+        0x1f if mantissa == 0.0 => sign * f32::INFINITY,
+        0x1f => f32::NAN,
+        _ => sign * (1.0 + mantissa / 1024.0) * 2f32.powi(exponent - 15),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hdr_and_bgra_surfaces_convert_to_srgb_rgba() {
+        let half = |value: u16| value.to_le_bytes();
+        // 1.0, 0.0, 2.0 (past SDR white) and 0.5 alpha as halves.
+        let hdr = [half(0x3c00), half(0x0000), half(0x4000), half(0x3800)].concat();
+        let image = TexelImage {
+            width: 1,
+            height: 1,
+            format: wgpu::TextureFormat::Rgba16Float,
+            data: hdr,
+        };
+        assert_eq!(to_rgba8(&image).unwrap().into_raw(), [255, 0, 255, 128]);
+
+        let image = TexelImage {
+            width: 1,
+            height: 1,
+            format: wgpu::TextureFormat::Bgra8UnormSrgb,
+            data: vec![10, 20, 30, 40],
+        };
+        assert_eq!(to_rgba8(&image).unwrap().into_raw(), [30, 20, 10, 40]);
+    }
+}
@@ -11,28 +11,169 @@ var u_atlas: texture_2d<f32>;
 @group(1) @binding(1)
 var u_sampler: sampler;
 
+// Placement of the mesh being drawn; see `ChunkUniform`.
+struct Chunk {
+    // xyz: origin of the mesh's vertex positions, w: level of detail.
+    origin: vec4<f32>,
+    tint: vec4<f32>,
+};
+
+@group(2) @binding(0)
+var<uniform> u_chunk: Chunk;
+
+// The world's depth seen from the sun; see `shadows::ShadowMap`.
+struct Shadow {
+    view_proj: mat4x4<f32>,
+    // xyz: direction towards the sun.
+    sun: vec4<f32>,
+    // x: 1 when shadows are on, y: shadow map texel size in UV, z: share of
+    // the light a full shadow takes away, w: receiver offset in blocks.
+    params: vec4<f32>,
+    // rgb: the light open sky gives at this time of day.
+    ambient: vec4<f32>,
+};
+
+@group(3) @binding(0)
+var<uniform> u_shadow: Shadow;
+
+@group(3) @binding(1)
+var u_shadow_map: texture_depth_2d;
+
+@group(3) @binding(2)
+var u_shadow_sampler: sampler_comparison;
+
 struct VertexOutput {
     @builtin(position) position: vec4<f32>,
     @location(0) color: vec3<f32>,
     @location(1) uv: vec2<f32>,
+    @location(2) @interpolate(flat) tile: vec4<f32>,
+    @location(3) @interpolate(flat) alpha_cutoff: f32,
+    @location(4) world: vec3<f32>,
+    // x: sky light, y: block light.
+    @location(5) light: vec2<f32>,
 };
 
+// Surface of sea-level water, which planar reflections mirror the world about.
+// Mirrors `terrain::SEA_LEVEL + mesh::LIQUID_SURFACE`.
+const WATER_PLANE: f32 = 4.875;
+// Share of the shadow map's edge over which shadows fade out.
+const SHADOW_FADE: f32 = 0.1;
+// Colour of the light luminous blocks spread; warmer than daylight.
+const BLOCK_LIGHT_COLOR: vec3<f32> = vec3<f32>(1.0, 0.85, 0.65);
+
 @vertex
 fn vs_main(
     @location(0) position: vec3<f32>,
     @location(1) color: vec3<f32>,
     @location(2) uv: vec2<f32>,
+    @location(3) tile: vec4<f32>,
+    @location(4) alpha_cutoff: f32,
+    @location(5) light: vec2<f32>,
 ) -> VertexOutput {
+    let world = u_chunk.origin.xyz + position * exp2(u_chunk.origin.w);
     var out: VertexOutput;
-    out.position = u_camera.view_proj * vec4<f32>(position, 1.0);
-    out.color = color;
+    out.position = u_camera.view_proj * vec4<f32>(world, 1.0);
+    out.color = color * u_chunk.tint.rgb;
     out.uv = uv;
+    out.tile = tile;
+    out.alpha_cutoff = alpha_cutoff;
+    out.world = world;
+    out.light = light;
+    return out;
+}
+
+// Copies of a block mesh; see `instancing::Instance`.
+@vertex
+fn vs_instanced(
+    @location(0) position: vec3<f32>,
+    @location(1) color: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+    @location(3) tile: vec4<f32>,
+    @location(4) alpha_cutoff: f32,
+    @location(5) tinted: f32,
+    // xyz: minimum corner, w: scale.
+    @location(6) placement: vec4<f32>,
+    // rgb: biome tint, a: light.
+    @location(7) shading: vec4<f32>,
+) -> VertexOutput {
+    let world = placement.xyz + position * placement.w;
+    let tint = mix(vec3<f32>(1.0), shading.rgb, tinted);
+    var out: VertexOutput;
+    out.position = u_camera.view_proj * vec4<f32>(world, 1.0);
+    out.color = color * tint;
+    out.uv = uv;
+    out.tile = tile;
+    out.alpha_cutoff = alpha_cutoff;
+    out.world = world;
+    out.light = vec2<f32>(shading.a, 0.0);
     return out;
 }
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    let tex = textureSample(u_atlas, u_sampler, in.uv);
-    let rgb = tex.rgb * in.color;
-    return vec4<f32>(rgb, tex.a);
+    return shade(in);
+}
+
+// The world as seen in a planar water reflection: only what is above the water.
+@fragment
+fn fs_reflected(in: VertexOutput) -> @location(0) vec4<f32> {
+    if in.world.y < WATER_PLANE {
+        discard;
+    }
+    return shade(in);
+}
+
+// Depth from the sun for the shadow map; cutout texels cast no shadow.
+@fragment
+fn fs_shadow(in: VertexOutput) {
+    let atlas_uv = in.tile.xy + fract(in.uv) * in.tile.zw;
+    if textureSampleLevel(u_atlas, u_sampler, atlas_uv, 0.0).a < in.alpha_cutoff {
+        discard;
+    }
+}
+
+fn shade(in: VertexOutput) -> vec4<f32> {
+    // Wrap the face-local UV inside the tile so merged faces repeat cleanly.
+    // Gradients come from the unwrapped UV to avoid mip seams at the wrap.
+    let atlas_uv = in.tile.xy + fract(in.uv) * in.tile.zw;
+    let scaled = in.uv * in.tile.zw;
+    let tex = textureSampleGrad(u_atlas, u_sampler, atlas_uv, dpdx(scaled), dpdy(scaled));
+    let normal = normalize(cross(dpdx(in.world), dpdy(in.world)));
+    if tex.a < in.alpha_cutoff {
+        discard;
+    }
+    let shadow = u_shadow.params.z * (1.0 - sun_visibility(in.world, normal));
+    // Shadows only take away sunlight; block light fills them back in.
+    let sky = in.light.x * (1.0 - shadow) * u_shadow.ambient.rgb;
+    let light = max(sky, in.light.y * BLOCK_LIGHT_COLOR);
+    // Linear output; the light shaft pass applies gamma and the sRGB encode.
+    return vec4<f32>(tex.rgb * in.color * light, tex.a);
+}
+
+// How much of the sun reaches `world`, from a 3x3 PCF lookup in the shadow
+// map. `normal` is the face's, pointing either way.
+fn sun_visibility(world: vec3<f32>, normal: vec3<f32>) -> f32 {
+    if u_shadow.params.x == 0.0 {
+        return 1.0;
+    }
+    // Lift the point off its face, towards the sun's side, so the face does
+    // not shadow itself.
+    let facing = select(-1.0, 1.0, dot(normal, u_shadow.sun.xyz) >= 0.0);
+    let lifted = world + normal * facing * u_shadow.params.w;
+    let clip = u_shadow.view_proj * vec4<f32>(lifted, 1.0);
+    let uv = clip.xy * vec2<f32>(0.5, -0.5) + 0.5;
+    let edge = max(abs(uv.x - 0.5), abs(uv.y - 0.5)) * 2.0;
+    if edge >= 1.0 || clip.z >= 1.0 {
+        return 1.0;
+    }
+
+    let texel = u_shadow.params.y;
+    var lit = 0.0;
+    for (var y = -1; y <= 1; y++) {
+        for (var x = -1; x <= 1; x++) {
+            let offset = vec2<f32>(f32(x), f32(y)) * texel;
+            lit += textureSampleCompareLevel(u_shadow_map, u_shadow_sampler, uv + offset, clip.z);
+        }
+    }
+    return mix(lit / 9.0, 1.0, smoothstep(1.0 - SHADOW_FADE, 1.0, edge));
 }
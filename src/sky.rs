@@ -0,0 +1,178 @@
+//! The time of day and what it does to the sky: where the sun is, the
+//! colours the sky is drawn in, and how strongly it lights the world.
+//!
+//! The sun turns once per `lighting.day_length` seconds on a circle through
+//! the configured noon direction, rising and setting on the horizon a
+//! quarter turn either side of it. While it is down the moon, opposite it,
+//! casts a faint blue light and the shadows.
+
+use glam::Vec3;
+
+/// Share of the day passed at the first frame: just after sunrise.
+const START_TIME: f64 = 0.3;
+/// Seconds a whole day takes while time is fast-forwarded.
+const FAST_FORWARD_DAY: f32 = 12.0;
+/// Lowest elevation, as the y of a unit vector, the light that casts shadows
+/// is kept above, so the shadow map never looks along the ground.
+const MIN_LIGHT_ELEVATION: f32 = 0.2;
+/// Sun elevations over which the sky fades between night and day.
+const DUSK: f32 = -0.15;
+const DAWN: f32 = 0.25;
+/// Sun elevation below which the horizon shows sunset colours, fading out
+/// as the sun climbs or sinks past it.
+const TWILIGHT: f32 = 0.3;
+
+const DAY_ZENITH: Vec3 = Vec3::new(0.08, 0.16, 0.32);
+const DAY_HORIZON: Vec3 = Vec3::new(0.4, 0.5, 0.7);
+const SUNSET_HORIZON: Vec3 = Vec3::new(0.75, 0.4, 0.2);
+const NIGHT_ZENITH: Vec3 = Vec3::new(0.004, 0.006, 0.016);
+const NIGHT_HORIZON: Vec3 = Vec3::new(0.02, 0.03, 0.06);
+/// Open-sky light at noon leaves surfaces as they were before the sky had a
+/// cycle; at night a moonlit remnant stays.
+const DAY_AMBIENT: Vec3 = Vec3::ONE;
+const NIGHT_AMBIENT: Vec3 = Vec3::new(0.1, 0.12, 0.2);
+const SUNSET_TINT: Vec3 = Vec3::new(1.0, 0.8, 0.65);
+const SUN_LIGHT: Vec3 = Vec3::ONE;
+const MOON_LIGHT: Vec3 = Vec3::new(0.08, 0.1, 0.16);
+
+/// The sky at one moment, as the renderers draw it and light the world with
+/// it.
+#[derive(Clone, Copy, Debug)]
+pub struct SkyLight {
+    /// Towards the sun; below the horizon at night.
+    pub sun: Vec3,
+    /// Towards whichever of the sun and the moon is up, kept above
+    /// `MIN_LIGHT_ELEVATION`; direct light and shadows come from here.
+    pub light_direction: Vec3,
+    /// Colour and strength of the direct light along `light_direction`.
+    pub light_color: Vec3,
+    /// Colour and strength of the light open sky gives; white at noon.
+    pub ambient: Vec3,
+    /// Colour of the sky straight up and at the horizon.
+    pub zenith: Vec3,
+    pub horizon: Vec3,
+}
+
+pub struct Sky {
+    /// Days passed since the world was opened, starting at `START_TIME`.
+    days: f64,
+    /// Seconds a whole day takes.
+    day_length: f32,
+    /// Towards the sun at noon.
+    noon: Vec3,
+    /// Horizontal direction the sun rises from.
+    east: Vec3,
+}
+
+impl Sky {
+    pub fn new(noon: Vec3, day_length: f32) -> Self {
+        let east = Vec3::new(noon.z, 0.0, -noon.x)
+            .try_normalize()
+            .unwrap_or(Vec3::X);
+        Self {
+            days: START_TIME,
+            day_length,
+            noon,
+            east,
+        }
+    }
+
+    /// Moves time on by `dt` seconds, or by a larger share of the day while
+    /// `fast_forward` is held.
+    pub fn advance(&mut self, dt: f32, fast_forward: bool) {
+        let day = if fast_forward {
+            FAST_FORWARD_DAY
+        } else {
+            self.day_length
+        };
+        self.days += f64::from(dt / day);
+    }
+
+    /// Share of the current day passed since midnight, in `0.0..1.0`.
+    pub fn time_of_day(&self) -> f32 {
+        self.days.fract() as f32
+    }
+
+    /// The day's number, counting the first as 1.
+    pub fn day(&self) -> u64 {
+        self.days as u64 + 1
+    }
+
+    /// Hours and minutes on a 24-hour clock.
+    pub fn clock(&self) -> (u32, u32) {
+        let minutes = (self.time_of_day() * 24.0 * 60.0) as u32;
+        (minutes / 60, minutes % 60)
+    }
+
+    /// Towards the sun: on the eastern horizon at 6:00, at `noon` at 12:00.
+    pub fn sun_direction(&self) -> Vec3 {
+        let angle = (self.time_of_day() - 0.25) * std::f32::consts::TAU;
+        (self.east * angle.cos() + self.noon * angle.sin()).normalize()
+    }
+
+    pub fn light(&self) -> SkyLight {
+        let sun = self.sun_direction();
+        let day = smoothstep(DUSK, DAWN, sun.y);
+        let twilight = (1.0 - sun.y.abs() / TWILIGHT).clamp(0.0, 1.0);
+        let sun_up = sun.y >= 0.0;
+        let light_direction = if sun_up { sun } else { -sun };
+        let light_direction = light_direction
+            .with_y(light_direction.y.max(MIN_LIGHT_ELEVATION))
+            .normalize();
+        let light_color = if sun_up {
+            SUN_LIGHT.lerp(SUN_LIGHT * SUNSET_TINT, twilight) * smoothstep(0.0, 0.1, sun.y)
+        } else {
+            MOON_LIGHT * smoothstep(0.0, 0.1, -sun.y)
+        };
+        let ambient = NIGHT_AMBIENT.lerp(DAY_AMBIENT, day) * Vec3::ONE.lerp(SUNSET_TINT, twilight);
+        SkyLight {
+            sun,
+            light_direction,
+            light_color,
+            ambient,
+            zenith: NIGHT_ZENITH.lerp(DAY_ZENITH, day),
+            horizon: NIGHT_HORIZON
+                .lerp(DAY_HORIZON, day)
+                .lerp(SUNSET_HORIZON * day.max(0.2), twilight * 0.7),
+        }
+    }
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_sun_rises_peaks_at_noon_and_sets() {
+        let noon = Vec3::new(0.3, 0.8, 0.5).normalize();
+        let mut sky = Sky::new(noon, 1200.0);
+        let mut at = |time: f32| {
+            sky.days = f64::from(time);
+            (sky.sun_direction(), sky.light(), sky.clock())
+        };
+
+        let (sunrise, _, clock) = at(0.25);
+        assert!(sunrise.y.abs() < 1e-4);
+        assert_eq!(clock, (6, 0));
+        let (midday, day, _) = at(0.5);
+        assert!(midday.distance(noon) < 1e-4);
+        assert!(day.ambient.distance(Vec3::ONE) < 1e-4);
+        assert!(day.light_direction.distance(noon) < 1e-4);
+        let (midnight, night, _) = at(1.0);
+        assert!(midnight.distance(-noon) < 1e-4);
+        assert!(night.light_direction.y >= MIN_LIGHT_ELEVATION);
+        assert!(night.ambient.max_element() < 0.25);
+
+        sky.days = 0.0;
+        sky.advance(600.0, false);
+        assert_eq!(sky.clock(), (12, 0));
+        assert_eq!(sky.day(), 1);
+        sky.advance(FAST_FORWARD_DAY, true);
+        assert_eq!(sky.day(), 2);
+    }
+}
@@ -0,0 +1,129 @@
+use std::collections::VecDeque;
+
+use glam::Vec3;
+
+use crate::audio::{PlayedSound, SoundAction};
+use crate::camera::Camera;
+
+/// Seconds a subtitle stays on screen after its sound last played.
+const SUBTITLE_LIFETIME: f32 = 3.0;
+/// Oldest subtitles are dropped beyond this many lines.
+const MAX_SUBTITLES: usize = 5;
+/// Sounds closer than this (horizontally) get no direction arrow.
+const NEARBY_DISTANCE: f32 = 1.5;
+
+struct Subtitle {
+    caption: String,
+    position: Vec3,
+    age: f32,
+}
+
+/// Recent sounds as short captions ("Stone broken"), each with an arrow
+/// pointing towards where it came from.
+///
+/// A sound that repeats while its caption is still shown refreshes that line
+/// instead of adding another, so footsteps do not flood the feed.
+pub struct SubtitleFeed {
+    entries: VecDeque<Subtitle>,
+}
+
+impl SubtitleFeed {
+    pub fn new() -> Self {
+        Self {
+            entries: VecDeque::new(),
+        }
+    }
+
+    pub fn push(&mut self, sound: &PlayedSound) {
+        let caption = caption(sound);
+        if let Some(index) = self
+            .entries
+            .iter()
+            .position(|entry| entry.caption == caption)
+        {
+            // Move it to the bottom so the newest sound is always last.
+            let mut entry = self.entries.remove(index).unwrap();
+            entry.position = sound.position;
+            entry.age = 0.0;
+            self.entries.push_back(entry);
+            return;
+        }
+        if self.entries.len() == MAX_SUBTITLES {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(Subtitle {
+            caption,
+            position: sound.position,
+            age: 0.0,
+        });
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        for entry in &mut self.entries {
+            entry.age += dt;
+        }
+        self.entries.retain(|entry| entry.age < SUBTITLE_LIFETIME);
+    }
+
+    /// Display lines, oldest first, with arrows relative to `camera`.
+    pub fn lines(&self, camera: &Camera) -> Vec<String> {
+        self.entries
+            .iter()
+            .map(|entry| match direction_to(camera, entry.position) {
+                Some(Direction::Left) => format!("\u{2190} {}", entry.caption),
+                Some(Direction::Right) => format!("{} \u{2192}", entry.caption),
+                Some(Direction::Ahead) => format!("\u{2191} {}", entry.caption),
+                Some(Direction::Behind) => format!("\u{2193} {}", entry.caption),
+                None => entry.caption.clone(),
+            })
+            .collect()
+    }
+}
+
+fn caption(sound: &PlayedSound) -> String {
+    let material = sound.material.name();
+    match sound.action {
+        SoundAction::Break => format!("{} broken", capitalize(material)),
+        SoundAction::Place => format!("{} placed", capitalize(material)),
+        SoundAction::Step => "Footsteps".to_string(),
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+enum Direction {
+    Ahead,
+    Behind,
+    Left,
+    Right,
+}
+
+/// Which quarter of the horizontal plane around the camera `position` lies in.
+fn direction_to(camera: &Camera, position: Vec3) -> Option<Direction> {
+    let offset = (position - camera.position).with_y(0.0);
+    if offset.length() < NEARBY_DISTANCE {
+        return None;
+    }
+    let yaw = camera.yaw.to_radians();
+    let forward = Vec3::new(yaw.cos(), 0.0, yaw.sin());
+    let right = Vec3::new(-yaw.sin(), 0.0, yaw.cos());
+    let ahead = offset.dot(forward);
+    let side = offset.dot(right);
+    Some(if ahead.abs() >= side.abs() {
+        if ahead >= 0.0 {
+            Direction::Ahead
+        } else {
+            Direction::Behind
+        }
+    } else if side >= 0.0 {
+        Direction::Right
+    } else {
+        Direction::Left
+    })
+}
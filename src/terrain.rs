@@ -0,0 +1,720 @@
+use std::ops::RangeInclusive;
+
+use glam::{IVec3, Vec3};
+
+use crate::biome;
+use crate::block::{BLOCK_AIR, BlockId, BlockKind, FaceDirection};
+use crate::coords::{BlockPos, HORIZONTAL_NEIGHBORS};
+use crate::decoration::{TREE_HEIGHT, chunk_decorations, column_trees};
+use crate::rand::noise_layer;
+
+/// Lowest generated layer. Heightfield presets put bedrock here and leave
+/// everything below empty.
+pub const WORLD_MIN_Y: i32 = 0;
+
+/// Heightfield presets flood every column whose ground is lower than this,
+/// up to and including this layer.
+pub const SEA_LEVEL: i32 = 4;
+
+/// Deepest natural snow cover; deeper drifts only come from placing layers.
+const MAX_GENERATED_SNOW_LAYERS: u8 = 3;
+/// Ground below this many blocks under the surface is stone.
+const DIRT_DEPTH: i32 = 3;
+
+/// Mean ground height of the rolling hills.
+const HILLS_BASE: f32 = 6.0;
+/// Height the hills' noise is scaled to; the sum rarely reaches its full range.
+const HILLS_AMPLITUDE: f32 = 7.0;
+/// Width (in blocks) of the hills' broadest octave.
+const HILLS_SCALE: f32 = 48.0;
+const HILLS_OCTAVES: u32 = 4;
+
+const AMPLIFIED_BASE: f32 = 10.0;
+const AMPLIFIED_HILL_SCALE: f32 = 2.5;
+/// Height of the ridged mountain term on top of the stretched hills.
+const AMPLIFIED_RIDGE_HEIGHT: f32 = 14.0;
+
+/// Width (in blocks) of the noise cave tunnels follow. Vertical distances
+/// count `CAVE_FLATTENING` times over, so tunnels mostly run level.
+const CAVE_SCALE: f32 = 24.0;
+const CAVE_FLATTENING: f32 = 2.0;
+/// Tunnels run where two noise fields are both this close to zero, so it
+/// sets how wide they are.
+const CAVE_RADIUS: f32 = 0.12;
+/// Noise layers of the tunnels' two fields, and the first of the ore pockets'.
+const CAVE_LAYERS: [u32; 2] = [20, 21];
+const ORE_POCKET_LAYER: u32 = 32;
+
+/// Farthest column from the origin searched for a spawn point.
+const SPAWN_SEARCH_RADIUS: i32 = 256;
+/// Spacing of the rings searched for a spawn point.
+const SPAWN_SEARCH_STEP: i32 = 4;
+
+/// Islands only exist between these heights; density fades out towards both.
+const ISLAND_FLOOR: i32 = 16;
+const ISLAND_CEILING: i32 = 44;
+/// Subtracted from the island density; higher values mean smaller islands.
+const ISLAND_THRESHOLD: f32 = 1.05;
+
+/// Rock only floats between these heights in the void.
+const VOID_FLOOR: i32 = 8;
+const VOID_CEILING: i32 = 56;
+/// Subtracted from the void rock's density; higher values mean fewer,
+/// smaller shards than the floating islands.
+const VOID_THRESHOLD: f32 = 0.55;
+/// Feet height of the platform at the void's origin, where travellers
+/// arrive, and how far it reaches from the origin column along x and z.
+const VOID_PLATFORM_Y: i32 = 32;
+const VOID_PLATFORM_RADIUS: i32 = 3;
+const VOID_LAYER: u32 = 50;
+/// Lamps per thousand cells on top of the void's rock; the void has no sun,
+/// so they are all the light it has.
+const VOID_LAMP_PERMILLE: u32 = 40;
+
+/// The benchmark field fills every layer from the bedrock up to this one:
+/// three chunk layers.
+const BENCHMARK_FIELD_TOP: i32 = 47;
+/// Feet height of the cleared pocket the benchmark scene spawns in.
+const BENCHMARK_SPAWN_Y: i32 = 24;
+/// How far the spawn pocket reaches from the origin column along x and z.
+const BENCHMARK_POCKET_RADIUS: i32 = 2;
+const BENCHMARK_LAYER: u32 = 40;
+/// What the benchmark field's solid cells are drawn from: opaque cubes, and
+/// glass so the ray tracer has something to see through.
+const BENCHMARK_PALETTE: [BlockKind; 4] = [
+    BlockKind::Stone,
+    BlockKind::Dirt,
+    BlockKind::Metal,
+    BlockKind::Glass,
+];
+
+/// A kind of ore found in pockets in the stone of cave walls.
+// Nothing is constructed until the game has ore blocks to put in `ORE_POCKETS`.
+#[allow(dead_code)]
+struct OrePocket {
+    kind: BlockKind,
+    /// Layers the pockets appear in.
+    heights: RangeInclusive<i32>,
+    /// Width (in blocks) of the noise the pockets are cut from.
+    scale: f32,
+    /// Noise value above which wall stone turns to ore; higher values mean
+    /// smaller, rarer pockets.
+    threshold: f32,
+}
+
+/// Ores that replace stone facing into a cave, tried in order. Each pocket
+/// draws its own noise layer, so adding an entry generates that ore without
+/// moving any other.
+const ORE_POCKETS: &[OrePocket] = &[];
+
+/// Shape of the generated world, chosen once per world.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TerrainPreset {
+    /// Gentle rolling hills.
+    Default,
+    /// The default hills stretched vertically, with ridged mountains on top.
+    Amplified,
+    /// Islands of 3D noise floating over the void.
+    FloatingIslands,
+    /// A field of scattered blocks filling every loaded layer, for pushing
+    /// the renderers towards their buffer and vertex limits. The field
+    /// grows with the render distance.
+    Benchmark(BenchmarkScene),
+    /// Bare shards of rock in the dark, dotted with lamps, around a platform
+    /// at the origin. Only the void dimension generates it; no config
+    /// picks it.
+    Void,
+}
+
+/// How dense the `Benchmark` preset's field is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BenchmarkScene {
+    /// Solid cells per thousand cells of the field.
+    pub solid_permille: u16,
+    /// Lamps per thousand solid cells.
+    pub lamp_permille: u16,
+}
+
+impl Default for BenchmarkScene {
+    fn default() -> Self {
+        Self {
+            solid_permille: 350,
+            lamp_permille: 10,
+        }
+    }
+}
+
+impl TerrainPreset {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "default" | "normal" => Some(TerrainPreset::Default),
+            "amplified" => Some(TerrainPreset::Amplified),
+            "floating_islands" | "floating-islands" | "islands" => {
+                Some(TerrainPreset::FloatingIslands)
+            }
+            "benchmark" | "stress" => Some(TerrainPreset::Benchmark(BenchmarkScene::default())),
+            _ => None,
+        }
+    }
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            TerrainPreset::Default => "default",
+            TerrainPreset::Amplified => "amplified",
+            TerrainPreset::FloatingIslands => "floating_islands",
+            TerrainPreset::Benchmark(_) => "benchmark",
+            TerrainPreset::Void => "void",
+        }
+    }
+
+    /// Whether every column has ground, so the surface can be drawn as a
+    /// heightfield (e.g. the far terrain ring).
+    pub const fn has_heightfield(self) -> bool {
+        matches!(self, TerrainPreset::Default | TerrainPreset::Amplified)
+    }
+}
+
+/// A world's terrain: its preset and the seed all of its noise is drawn from.
+///
+/// Generation is a pure function of these two, so the same seed and preset
+/// always produce the same world.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TerrainGenerator {
+    pub preset: TerrainPreset,
+    pub seed: u64,
+}
+
+impl TerrainGenerator {
+    pub fn new(preset: TerrainPreset, seed: u64) -> Self {
+        Self { preset, seed }
+    }
+
+    /// Name of the world's save directory; each seed of a preset is its own
+    /// world, and so is each density of the benchmark field.
+    pub fn world_name(self) -> String {
+        match self.preset {
+            TerrainPreset::Benchmark(scene) => format!(
+                "{}-{}-{}-{}",
+                self.preset.name(),
+                scene.solid_permille,
+                scene.lamp_permille,
+                self.seed
+            ),
+            preset => format!("{}-{}", preset.name(), self.seed),
+        }
+    }
+
+    /// Height of the topmost generated ground in the column at `x`, `z`, or
+    /// `None` if the column is empty. Water above it is not counted, and
+    /// the benchmark field has no ground to decorate.
+    pub fn surface_height(self, x: i32, z: i32) -> Option<i32> {
+        match self.preset {
+            TerrainPreset::Default => Some(rolling_height(x, z, self.seed)),
+            TerrainPreset::Amplified => Some(amplified_height(x, z, self.seed)),
+            TerrainPreset::FloatingIslands => (ISLAND_FLOOR..=ISLAND_CEILING)
+                .rev()
+                .find(|&y| island_density(x, y, z, self.seed) > 0.0),
+            TerrainPreset::Benchmark(_) => None,
+            TerrainPreset::Void => {
+                if on_void_platform(x, z) {
+                    return Some(VOID_PLATFORM_Y - 1);
+                }
+                (VOID_FLOOR..=VOID_CEILING)
+                    .rev()
+                    .find(|&y| void_density(x, y, z, self.seed) > 0.0)
+            }
+        }
+    }
+
+    /// Every layer the preset can generate a block in, decorations and trees
+    /// included. The world keeps all of it loaded, so terrain taller than the
+    /// camera's vertical render distance is never cut off.
+    pub fn height_range(self) -> RangeInclusive<i32> {
+        match self.preset {
+            TerrainPreset::Default => {
+                WORLD_MIN_Y..=(HILLS_BASE + max_rolling_hills()).ceil() as i32 + TREE_HEIGHT
+            }
+            TerrainPreset::Amplified => {
+                let mountains = AMPLIFIED_BASE
+                    + max_rolling_hills() * AMPLIFIED_HILL_SCALE
+                    + AMPLIFIED_RIDGE_HEIGHT;
+                WORLD_MIN_Y..=mountains.ceil() as i32 + TREE_HEIGHT
+            }
+            TerrainPreset::FloatingIslands => ISLAND_FLOOR..=ISLAND_CEILING + TREE_HEIGHT,
+            TerrainPreset::Benchmark(_) => WORLD_MIN_Y..=BENCHMARK_FIELD_TOP,
+            TerrainPreset::Void => VOID_FLOOR..=VOID_CEILING,
+        }
+    }
+
+    /// The generated block at a position, before decorations. Heightfield
+    /// presets carve caves out of their ground and line them with ore; the
+    /// islands' noise already leaves them hollow enough.
+    pub fn block_at(self, world_x: i32, world_y: i32, world_z: i32) -> BlockId {
+        let column = self.column_height(world_x, world_z);
+        let ground = self.ground_block_at(world_x, world_y, world_z, column);
+        self.carve_caves(IVec3::new(world_x, world_y, world_z), column, ground)
+    }
+
+    /// Surface height of the column at `x`, `z` for heightfield presets,
+    /// which the ground and cave stages of generation share; `None` for
+    /// the others.
+    pub fn column_height(self, x: i32, z: i32) -> Option<i32> {
+        if self.preset.has_heightfield() {
+            self.surface_height(x, z)
+        } else {
+            None
+        }
+    }
+
+    /// The first stage of [`block_at`](Self::block_at): the block before
+    /// caves, in a column of [`column_height`](Self::column_height) `column`.
+    pub fn ground_block_at(
+        self,
+        world_x: i32,
+        world_y: i32,
+        world_z: i32,
+        column: Option<i32>,
+    ) -> BlockId {
+        match self.preset {
+            TerrainPreset::Default | TerrainPreset::Amplified => {
+                let height = column.expect("heightfield presets cover every column");
+                heightfield_block(height, world_x, world_y, world_z)
+            }
+            TerrainPreset::FloatingIslands => island_block(world_x, world_y, world_z, self.seed),
+            TerrainPreset::Benchmark(scene) => {
+                benchmark_block(scene, IVec3::new(world_x, world_y, world_z), self.seed)
+            }
+            TerrainPreset::Void => void_block(IVec3::new(world_x, world_y, world_z), self.seed),
+        }
+    }
+
+    /// The second stage of [`block_at`](Self::block_at): `ground` at
+    /// `position` with caves carved out and their walls turned to ore.
+    pub fn carve_caves(self, position: IVec3, column: Option<i32>, ground: BlockId) -> BlockId {
+        let Some(height) = column else {
+            return ground;
+        };
+        if self.carved(position, height) {
+            return BLOCK_AIR;
+        }
+        if ground == BlockKind::Stone.id()
+            && let Some(ore) = self.cave_wall_ore(position)
+        {
+            return ore.id();
+        }
+        ground
+    }
+
+    /// Whether a cave tunnel removes the ground at `position`, in a
+    /// heightfield column whose surface is at `height`.
+    ///
+    /// The surface block and the bedrock are never carved, so decorations
+    /// and snow keep their footing; tunnels open up where they run into a
+    /// hillside instead, leaving overhangs above. Below sea level a tunnel
+    /// stops short of cells beside the sea, whose water would otherwise
+    /// stand as a wall in the opening.
+    fn carved(self, position: IVec3, height: i32) -> bool {
+        if position.y <= WORLD_MIN_Y || position.y >= height || !cave_tunnel(position, self.seed) {
+            return false;
+        }
+        position.y > SEA_LEVEL
+            || HORIZONTAL_NEIGHBORS
+                .into_iter()
+                .map(|offset| position + offset)
+                .all(|side| {
+                    self.surface_height(side.x, side.z)
+                        .is_some_and(|side_height| side_height >= position.y)
+                })
+    }
+
+    /// The ore, if any, that the stone at `position` turns into because it
+    /// faces into a cave.
+    fn cave_wall_ore(self, position: IVec3) -> Option<BlockKind> {
+        let (_, pocket) = ORE_POCKETS.iter().enumerate().find(|(index, pocket)| {
+            let p = position.as_vec3() / pocket.scale;
+            let layer = noise_layer(self.seed, ORE_POCKET_LAYER + *index as u32);
+            pocket.heights.contains(&position.y)
+                && value_noise_3d(p.x, p.y, p.z, layer) > pocket.threshold
+        })?;
+        let faces_cave = FaceDirection::ALL.iter().any(|face| {
+            let side = position + face.normal();
+            self.surface_height(side.x, side.z)
+                .is_some_and(|side_height| self.carved(side, side_height))
+        });
+        faces_cave.then_some(pocket.kind)
+    }
+}
+
+fn rolling_height(x: i32, z: i32, seed: u64) -> i32 {
+    (HILLS_BASE + rolling_hills(x, z, seed)).round() as i32
+}
+
+/// Offset of the rolling hills from `HILLS_BASE`: fBm over simplex noise,
+/// raised and stretched by the column's biome.
+fn rolling_hills(x: i32, z: i32, seed: u64) -> f32 {
+    let (fx, fz) = (x as f32 / HILLS_SCALE, z as f32 / HILLS_SCALE);
+    let shape = biome::terrain_shape_at(x, z);
+    shape.base
+        + fbm_2d(fx, fz, HILLS_OCTAVES, noise_layer(seed, 0)) * HILLS_AMPLITUDE * shape.amplitude
+}
+
+/// Highest `rolling_hills` reaches, where the noise peaks in the tallest
+/// biome.
+fn max_rolling_hills() -> f32 {
+    let shape = biome::tallest_shape();
+    shape.base + HILLS_AMPLITUDE * shape.amplitude
+}
+
+fn amplified_height(x: i32, z: i32, seed: u64) -> i32 {
+    let hills = rolling_hills(x, z, seed);
+    let ridge = 1.0 - value_noise_2d(x as f32 / 64.0, z as f32 / 64.0, noise_layer(seed, 11)).abs();
+    let mountains = AMPLIFIED_RIDGE_HEIGHT * ridge * ridge;
+    let height = (AMPLIFIED_BASE + hills * AMPLIFIED_HILL_SCALE + mountains).round() as i32;
+    // Deep valleys stop just above the bedrock.
+    height.max(WORLD_MIN_Y + 1)
+}
+
+/// Whether `position` lies on a cave tunnel: where two noise fields are both
+/// near zero, along the curves their zero surfaces cross in.
+fn cave_tunnel(position: IVec3, seed: u64) -> bool {
+    let p = position.as_vec3() * Vec3::new(1.0, CAVE_FLATTENING, 1.0) / CAVE_SCALE;
+    let [a, b] = CAVE_LAYERS.map(|layer| {
+        // Offset the fields' lattices so their cells do not line up.
+        let shift = layer as f32 * 0.37;
+        value_noise_3d(
+            p.x + shift,
+            p.y + shift,
+            p.z + shift,
+            noise_layer(seed, layer),
+        )
+    });
+    a * a + b * b < CAVE_RADIUS * CAVE_RADIUS
+}
+
+fn heightfield_block(height: i32, world_x: i32, world_y: i32, world_z: i32) -> BlockId {
+    if world_y > height && world_y <= SEA_LEVEL {
+        return BlockKind::Water.id();
+    }
+    if world_y == height + 1 {
+        return match snow_layers(world_x, world_z) {
+            Some(layers) => BlockKind::SnowLayer(layers).id(),
+            None => BLOCK_AIR,
+        };
+    }
+    if world_y > height || world_y < WORLD_MIN_Y {
+        return BLOCK_AIR;
+    }
+
+    let kind = if world_y == WORLD_MIN_Y {
+        BlockKind::Bedrock
+    } else if world_y == height && height <= SEA_LEVEL {
+        // Sea floor and beaches.
+        BlockKind::Sand
+    } else if world_y == height {
+        biome::biome_at(world_x, world_z).surface(height)
+    } else if world_y >= height - DIRT_DEPTH {
+        biome::biome_at(world_x, world_z).subsurface()
+    } else {
+        BlockKind::Stone
+    };
+
+    kind.id()
+}
+
+/// Positive inside an island. Two octaves of 3D noise, a slow 2D mask that
+/// clusters islands into archipelagos, and a band that peaks halfway between
+/// `ISLAND_FLOOR` and `ISLAND_CEILING`.
+fn island_density(x: i32, y: i32, z: i32, seed: u64) -> f32 {
+    if !(ISLAND_FLOOR..=ISLAND_CEILING).contains(&y) {
+        return -1.0;
+    }
+    let middle = (ISLAND_FLOOR + ISLAND_CEILING) as f32 * 0.5;
+    let half_span = (ISLAND_CEILING - ISLAND_FLOOR) as f32 * 0.5;
+    let t = (y as f32 - middle) / half_span;
+    let band = 1.0 - t * t;
+
+    let (fx, fy, fz) = (x as f32, y as f32, z as f32);
+    let detail = value_noise_3d(fx / 20.0, fy / 12.0, fz / 20.0, noise_layer(seed, 1)) * 0.65
+        + value_noise_3d(fx / 9.0, fy / 6.0, fz / 9.0, noise_layer(seed, 2)) * 0.35;
+    let mask = value_noise_2d(fx / 32.0, fz / 32.0, noise_layer(seed, 3));
+    detail + mask * 0.8 + band * 0.8 - ISLAND_THRESHOLD
+}
+
+fn island_block(world_x: i32, world_y: i32, world_z: i32, seed: u64) -> BlockId {
+    if island_density(world_x, world_y, world_z, seed) <= 0.0 {
+        let below = world_y - 1;
+        if island_density(world_x, below, world_z, seed) > 0.0
+            && let Some(layers) = snow_layers(world_x, world_z)
+        {
+            return BlockKind::SnowLayer(layers).id();
+        }
+        return BLOCK_AIR;
+    }
+
+    let open_above = (1..=DIRT_DEPTH + 1)
+        .find(|&offset| island_density(world_x, world_y + offset, world_z, seed) <= 0.0);
+    let kind = match open_above {
+        Some(1) => biome::biome_at(world_x, world_z).surface(world_y),
+        Some(_) => biome::biome_at(world_x, world_z).subsurface(),
+        None => BlockKind::Stone,
+    };
+    kind.id()
+}
+
+/// Positive inside a shard of void rock: two octaves of 3D noise in a band
+/// peaking halfway between `VOID_FLOOR` and `VOID_CEILING`, stretched
+/// vertically so shards hang like stalactites.
+fn void_density(x: i32, y: i32, z: i32, seed: u64) -> f32 {
+    if !(VOID_FLOOR..=VOID_CEILING).contains(&y) {
+        return -1.0;
+    }
+    let middle = (VOID_FLOOR + VOID_CEILING) as f32 * 0.5;
+    let half_span = (VOID_CEILING - VOID_FLOOR) as f32 * 0.5;
+    let t = (y as f32 - middle) / half_span;
+    let band = 1.0 - t * t;
+
+    let (fx, fy, fz) = (x as f32, y as f32, z as f32);
+    let detail = value_noise_3d(
+        fx / 14.0,
+        fy / 24.0,
+        fz / 14.0,
+        noise_layer(seed, VOID_LAYER),
+    ) * 0.7
+        + value_noise_3d(
+            fx / 6.0,
+            fy / 8.0,
+            fz / 6.0,
+            noise_layer(seed, VOID_LAYER + 1),
+        ) * 0.3;
+    detail + band * 0.3 - VOID_THRESHOLD
+}
+
+fn on_void_platform(x: i32, z: i32) -> bool {
+    x.abs() <= VOID_PLATFORM_RADIUS && z.abs() <= VOID_PLATFORM_RADIUS
+}
+
+/// A cell of the void: the arrival platform with open air above it, or
+/// rock where the density is positive, turned to a lamp on some of the
+/// cells open to the air above.
+fn void_block(position: IVec3, seed: u64) -> BlockId {
+    if on_void_platform(position.x, position.z) {
+        if position.y == VOID_PLATFORM_Y - 1 {
+            return BlockKind::Stone.id();
+        }
+        if (VOID_PLATFORM_Y..VOID_PLATFORM_Y + 3).contains(&position.y) {
+            return BLOCK_AIR;
+        }
+    }
+    if void_density(position.x, position.y, position.z, seed) <= 0.0 {
+        return BLOCK_AIR;
+    }
+    let hash = lattice_hash(
+        position.x,
+        position.y,
+        position.z,
+        noise_layer(seed, VOID_LAYER + 2),
+    );
+    let open_above = void_density(position.x, position.y + 1, position.z, seed) <= 0.0;
+    if open_above && hash % 1000 < VOID_LAMP_PERMILLE {
+        return BlockKind::Lamp.id();
+    }
+    BlockKind::Stone.id()
+}
+
+/// A cell of the benchmark field: each is solid or not by its own roll, so
+/// the field has no structure for either renderer to skip, except for a
+/// pocket at the origin to spawn in.
+fn benchmark_block(scene: BenchmarkScene, position: IVec3, seed: u64) -> BlockId {
+    if position.y == WORLD_MIN_Y {
+        return BlockKind::Bedrock.id();
+    }
+    if !(WORLD_MIN_Y..=BENCHMARK_FIELD_TOP).contains(&position.y) {
+        return BLOCK_AIR;
+    }
+    let in_pocket =
+        position.x.abs() <= BENCHMARK_POCKET_RADIUS && position.z.abs() <= BENCHMARK_POCKET_RADIUS;
+    if in_pocket && position.y == BENCHMARK_SPAWN_Y - 1 {
+        return BlockKind::Stone.id();
+    }
+    if in_pocket && (BENCHMARK_SPAWN_Y..BENCHMARK_SPAWN_Y + 3).contains(&position.y) {
+        return BLOCK_AIR;
+    }
+
+    let hash = lattice_hash(
+        position.x,
+        position.y,
+        position.z,
+        noise_layer(seed, BENCHMARK_LAYER),
+    );
+    if hash % 1000 >= u32::from(scene.solid_permille) {
+        return BLOCK_AIR;
+    }
+    if hash / 1000 % 1000 < u32::from(scene.lamp_permille) {
+        return BlockKind::Lamp.id();
+    }
+    let pick = hash / 1_000_000 % BENCHMARK_PALETTE.len() as u32;
+    BENCHMARK_PALETTE[pick as usize].id()
+}
+
+/// Feet position of a safe spawn nearest the origin: standing on a grass
+/// surface with open air above, clear of decorations. Falls back to the origin above the island
+/// band when nothing is found. The benchmark field always spawns in its
+/// pocket, and the void on its platform.
+pub fn find_spawn(terrain: TerrainGenerator) -> Vec3 {
+    match terrain.preset {
+        TerrainPreset::Benchmark(_) => return Vec3::new(0.5, BENCHMARK_SPAWN_Y as f32, 0.5),
+        TerrainPreset::Void => return Vec3::new(0.5, VOID_PLATFORM_Y as f32, 0.5),
+        _ => {}
+    }
+    let is_safe = |x: i32, z: i32| {
+        let height = terrain.surface_height(x, z)?;
+        let grass = terrain.block_at(x, height, z) == BlockKind::Grass.id();
+        let open = (1..=2).all(|dy| terrain.block_at(x, height + dy, z) == BLOCK_AIR);
+        (grass && open && !decorated(terrain, IVec3::new(x, height + 1, z))).then_some(height)
+    };
+
+    for radius in (0..=SPAWN_SEARCH_RADIUS).step_by(SPAWN_SEARCH_STEP as usize) {
+        for (x, z) in ring(radius) {
+            if let Some(height) = is_safe(x, z) {
+                return Vec3::new(x as f32 + 0.5, (height + 1) as f32, z as f32 + 0.5);
+            }
+        }
+    }
+    Vec3::new(0.5, (ISLAND_CEILING + 1) as f32, 0.5)
+}
+
+/// Whether a decoration or tree puts anything in the two cells from `feet` up.
+fn decorated(terrain: TerrainGenerator, feet: IVec3) -> bool {
+    let head = feet + IVec3::Y;
+    let blocked = |position: IVec3| position == feet || position == head;
+    let featured = [feet, head].iter().any(|&cell| {
+        chunk_decorations(terrain, BlockPos(cell).chunk())
+            .iter()
+            .any(|&(position, _)| blocked(position))
+    });
+    // A canopy reaches at most one column over.
+    let column = BlockPos(feet).chunk();
+    let mut treed = false;
+    for dz in -1..=1 {
+        for dx in -1..=1 {
+            column_trees(terrain, column.x + dx, column.z + dz, &mut |position, _| {
+                treed |= blocked(position);
+            });
+        }
+    }
+    featured || treed
+}
+
+/// Columns on the square ring `radius` blocks from the origin.
+fn ring(radius: i32) -> impl Iterator<Item = (i32, i32)> {
+    let rows = (-radius..=radius).flat_map(move |x| [(x, -radius), (x, radius)]);
+    let columns = (-radius + 1..radius).flat_map(move |z| [(-radius, z), (radius, z)]);
+    rows.chain(columns)
+}
+
+/// Snow cover generated on the column's surface; colder columns get deeper snow.
+pub fn snow_layers(world_x: i32, world_z: i32) -> Option<u8> {
+    let climate = biome::climate_at(world_x, world_z);
+    if climate.temperature >= biome::SNOW_TEMPERATURE {
+        return None;
+    }
+    let coldness = 1.0 - climate.temperature / biome::SNOW_TEMPERATURE;
+    Some((1.0 + coldness * (MAX_GENERATED_SNOW_LAYERS - 1) as f32).round() as u8)
+}
+
+/// Fractal Brownian motion: `octaves` layers of simplex noise, each at twice
+/// the frequency and half the amplitude of the last, in roughly `-1.0..=1.0`.
+fn fbm_2d(x: f32, z: f32, octaves: u32, salt: u32) -> f32 {
+    let mut total = 0.0;
+    let mut amplitude = 1.0;
+    let mut frequency = 1.0;
+    let mut range = 0.0;
+    for octave in 0..octaves {
+        let layer = salt.wrapping_add(octave.wrapping_mul(0x27D4_EB2F));
+        total += simplex_2d(x * frequency, z * frequency, layer) * amplitude;
+        range += amplitude;
+        amplitude *= 0.5;
+        frequency *= 2.0;
+    }
+    total / range
+}
+
+/// 2D simplex noise in roughly `-1.0..=1.0`. Each corner of the skewed
+/// triangle lattice picks one of eight gradients by hash.
+fn simplex_2d(x: f32, z: f32, salt: u32) -> f32 {
+    // Skew and unskew factors between the square and triangle lattices.
+    const F2: f32 = 0.366_025_4;
+    const G2: f32 = 0.211_324_87;
+    const GRADIENTS: [(f32, f32); 8] = [
+        (1.0, 1.0),
+        (-1.0, 1.0),
+        (1.0, -1.0),
+        (-1.0, -1.0),
+        (1.0, 0.0),
+        (-1.0, 0.0),
+        (0.0, 1.0),
+        (0.0, -1.0),
+    ];
+
+    let skew = (x + z) * F2;
+    let (i, j) = ((x + skew).floor(), (z + skew).floor());
+    let unskew = (i + j) * G2;
+    let (x0, z0) = (x - (i - unskew), z - (j - unskew));
+    // The middle corner depends on which triangle of the cell the point is in.
+    let (i1, j1) = if x0 > z0 { (1, 0) } else { (0, 1) };
+    let corners = [
+        (0, 0, x0, z0),
+        (i1, j1, x0 - i1 as f32 + G2, z0 - j1 as f32 + G2),
+        (1, 1, x0 - 1.0 + 2.0 * G2, z0 - 1.0 + 2.0 * G2),
+    ];
+
+    let mut total = 0.0;
+    for (di, dj, dx, dz) in corners {
+        let falloff = 0.5 - dx * dx - dz * dz;
+        if falloff <= 0.0 {
+            continue;
+        }
+        let hash = lattice_hash(i as i32 + di, 0, j as i32 + dj, salt);
+        let (gx, gz) = GRADIENTS[(hash & 7) as usize];
+        total += falloff.powi(4) * (gx * dx + gz * dz);
+    }
+    total * 70.0
+}
+
+/// Smoothly interpolated lattice noise in `-1.0..=1.0`.
+fn value_noise_3d(x: f32, y: f32, z: f32, salt: u32) -> f32 {
+    let (x0, y0, z0) = (x.floor(), y.floor(), z.floor());
+    let (tx, ty, tz) = (smooth(x - x0), smooth(y - y0), smooth(z - z0));
+    let (ix, iy, iz) = (x0 as i32, y0 as i32, z0 as i32);
+    let corner = |dx: i32, dy: i32, dz: i32| lattice(ix + dx, iy + dy, iz + dz, salt);
+    let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+
+    let x00 = lerp(corner(0, 0, 0), corner(1, 0, 0), tx);
+    let x10 = lerp(corner(0, 1, 0), corner(1, 1, 0), tx);
+    let x01 = lerp(corner(0, 0, 1), corner(1, 0, 1), tx);
+    let x11 = lerp(corner(0, 1, 1), corner(1, 1, 1), tx);
+    lerp(lerp(x00, x10, ty), lerp(x01, x11, ty), tz)
+}
+
+fn value_noise_2d(x: f32, z: f32, salt: u32) -> f32 {
+    value_noise_3d(x, 0.0, z, salt)
+}
+
+fn smooth(t: f32) -> f32 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+fn lattice(x: i32, y: i32, z: i32, salt: u32) -> f32 {
+    lattice_hash(x, y, z, salt) as f32 / u32::MAX as f32 * 2.0 - 1.0
+}
+
+fn lattice_hash(x: i32, y: i32, z: i32, salt: u32) -> u32 {
+    let mut h = (x as u32).wrapping_mul(73_856_093)
+        ^ (y as u32).wrapping_mul(19_349_663)
+        ^ (z as u32).wrapping_mul(83_492_791)
+        ^ salt.wrapping_mul(2_654_435_761);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0x5bd1_e995);
+    h ^= h >> 15;
+    h
+}
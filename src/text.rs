@@ -2,12 +2,149 @@ use std::collections::HashMap;
 
 use bytemuck::{Pod, Zeroable};
 
-const GLYPH_WIDTH: u32 = 5;
-const GLYPH_HEIGHT: u32 = 7;
-const GLYPH_SPACING_X: u32 = 1;
+use crate::render;
+use crate::ui::{self, UiBatch, UiFrame, UiLayer};
+
+pub const GLYPH_WIDTH: u32 = 5;
+pub const GLYPH_HEIGHT: u32 = 7;
+pub const GLYPH_SPACING_X: u32 = 1;
 const GLYPH_SPACING_Y: u32 = 3;
 const PADDING_X: f32 = 12.0;
 const PADDING_Y: f32 = 14.0;
+/// Backdrop margin around each line of text, in font pixels.
+const BACKDROP_MARGIN: f32 = 2.0;
+/// Half-length and thickness of the crosshair arms in screen pixels.
+const CROSSHAIR_ARM: f32 = 6.0;
+const CROSSHAIR_THICKNESS: f32 = 2.0;
+const HIGH_CONTRAST_CROSSHAIR_ARM: f32 = 10.0;
+const HIGH_CONTRAST_CROSSHAIR_THICKNESS: f32 = 4.0;
+const CROSSHAIR_OUTLINE: f32 = 1.0;
+/// Fully lit glyph used to draw solid rectangles (backdrops, crosshair).
+pub const SOLID_GLYPH: char = '\u{2588}';
+/// Radial picker ring radius per slot and at minimum, in font pixels.
+const RADIAL_LABEL_SPACING: f32 = 12.0;
+const RADIAL_MIN_RADIUS: f32 = 90.0;
+const RADIAL_POINTER_SIZE: f32 = 4.0;
+/// Size of the progress bars below the crosshair, in screen pixels.
+const BREAK_BAR_WIDTH: f32 = 32.0;
+const BREAK_BAR_HEIGHT: f32 = 3.0;
+const BREAK_BAR_OFFSET: f32 = 16.0;
+/// The attack cooldown bar sits under the break bar.
+const ATTACK_BAR_OFFSET: f32 = 23.0;
+/// Panel behind subtitles when the palette has no backdrop of its own.
+const SUBTITLE_BACKDROP: [f32; 4] = [0.0, 0.0, 0.0, 0.5];
+/// Tint over the whole screen behind a dialog.
+const DIALOG_SHADE: [f32; 4] = [0.3, 0.0, 0.0, 0.45];
+
+/// Labels of the radial block picker, drawn instead of the crosshair.
+pub struct RadialMenu {
+    pub labels: Vec<String>,
+    pub highlighted: Option<usize>,
+    /// Currently selected slot, marked with `*`.
+    pub selected: usize,
+    /// Picker cursor in `-1.0..=1.0` screen-space units (y down).
+    pub pointer: [f32; 2],
+}
+
+/// Text centered on a point of the screen, such as a damage number.
+pub struct FloatingLabel {
+    pub text: String,
+    /// Center in screen pixels.
+    pub position: [f32; 2],
+    pub color: [f32; 4],
+}
+
+/// A bar chart of recent samples, such as frame times.
+pub struct BarGraph<'a> {
+    /// Drawn above the graph.
+    pub label: &'a str,
+    /// Top-left corner in screen pixels.
+    pub origin: [f32; 2],
+    pub size: [f32; 2],
+    /// Bar heights as fractions of the graph height, oldest first; one bar
+    /// per sample, squeezed to fit the width.
+    pub values: &'a [f32],
+    /// Marker lines across the graph, as fractions of its height.
+    pub guides: &'a [f32],
+}
+
+/// Everything the overlay draws in one frame.
+#[derive(Default)]
+pub struct OverlayContent<'a> {
+    /// Top-left text block.
+    pub text: &'a str,
+    /// Bottom-right captions, oldest first.
+    pub subtitles: &'a [String],
+    /// Bottom-left command prompt and its feedback, oldest first.
+    pub console: &'a [String],
+    /// Fraction of the looked-at block broken so far, while breaking it.
+    pub break_progress: Option<f32>,
+    /// Fraction of the attack recharged, while recharging.
+    pub attack_cooldown: Option<f32>,
+    /// Labels anchored to points in the world, drawn under everything else.
+    pub floating_labels: &'a [FloatingLabel],
+    pub radial_menu: Option<RadialMenu>,
+    /// Lines centered on a shaded screen, such as the death screen; hides
+    /// the crosshair while shown.
+    pub dialog: &'a [String],
+    pub graphs: &'a [BarGraph<'a>],
+}
+
+/// Overlay colors as straight RGBA.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct UiPalette {
+    pub text: [f32; 4],
+    /// Panel drawn behind each line of text; fully transparent to skip it.
+    pub backdrop: [f32; 4],
+    pub crosshair: [f32; 4],
+    pub crosshair_outline: [f32; 4],
+}
+
+impl UiPalette {
+    pub const DEFAULT: UiPalette = UiPalette {
+        text: [1.0, 1.0, 1.0, 1.0],
+        backdrop: [0.0, 0.0, 0.0, 0.0],
+        crosshair: [1.0, 1.0, 1.0, 0.85],
+        crosshair_outline: [0.0, 0.0, 0.0, 0.0],
+    };
+
+    /// Okabe-Ito orange and sky blue, which stay distinct under the common
+    /// forms of color vision deficiency.
+    pub const COLORBLIND: UiPalette = UiPalette {
+        text: [0.34, 0.71, 0.91, 1.0],
+        backdrop: [0.0, 0.0, 0.0, 0.45],
+        crosshair: [0.9, 0.62, 0.0, 1.0],
+        crosshair_outline: [0.0, 0.0, 0.0, 0.6],
+    };
+
+    /// Pure white text on near-opaque black panels and a yellow crosshair.
+    pub const HIGH_CONTRAST: UiPalette = UiPalette {
+        text: [1.0, 1.0, 1.0, 1.0],
+        backdrop: [0.0, 0.0, 0.0, 0.85],
+        crosshair: [1.0, 1.0, 0.0, 1.0],
+        crosshair_outline: [0.0, 0.0, 0.0, 1.0],
+    };
+}
+
+/// How the overlay draws text and the crosshair.
+#[derive(Clone, Copy, Debug)]
+pub struct OverlayStyle {
+    pub palette: UiPalette,
+    /// Screen pixels per font pixel.
+    pub text_scale: f32,
+    /// Longer, thicker crosshair arms.
+    pub high_contrast_crosshair: bool,
+}
+
+impl Default for OverlayStyle {
+    fn default() -> Self {
+        Self {
+            palette: UiPalette::DEFAULT,
+            text_scale: 1.0,
+            high_contrast_crosshair: false,
+        }
+    }
+}
 
 pub struct DebugOverlay {
     pipeline: wgpu::RenderPipeline,
@@ -16,18 +153,18 @@ pub struct DebugOverlay {
     _texture_view: wgpu::TextureView,
     _sampler: wgpu::Sampler,
     glyphs: HashMap<char, GlyphInfo>,
-    vertex_buffer: wgpu::Buffer,
-    vertex_capacity: usize,
-    vertex_count: usize,
-    vertices: Vec<TextVertex>,
+    batch: UiBatch<TextVertex>,
+    style: OverlayStyle,
+    show_crosshair: bool,
 }
 
+/// A glyph's texels in the font atlas, inset by half a texel.
 #[derive(Clone, Copy)]
-struct GlyphInfo {
-    u0: f32,
-    v0: f32,
-    u1: f32,
-    v1: f32,
+pub struct GlyphInfo {
+    pub u0: f32,
+    pub v0: f32,
+    pub u1: f32,
+    pub v1: f32,
 }
 
 #[repr(C)]
@@ -81,7 +218,7 @@ impl DebugOverlay {
             },
         );
 
-        let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let texture_view = render::labeled_view(&texture, "Debug text atlas view");
         let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
             label: Some("Debug text sampler"),
             address_mode_u: wgpu::AddressMode::ClampToEdge,
@@ -184,14 +321,6 @@ impl DebugOverlay {
             multiview: None,
         });
 
-        let initial_capacity = 256;
-        let vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Debug text vertex buffer"),
-            size: (initial_capacity * std::mem::size_of::<TextVertex>()) as wgpu::BufferAddress,
-            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
-
         Self {
             pipeline,
             bind_group,
@@ -199,124 +328,352 @@ impl DebugOverlay {
             _texture_view: texture_view,
             _sampler: sampler,
             glyphs,
-            vertex_buffer,
-            vertex_capacity: initial_capacity,
-            vertex_count: 0,
-            vertices: Vec::new(),
+            batch: UiBatch::new(device, "Debug text vertex buffer"),
+            style: OverlayStyle::default(),
+            show_crosshair: false,
         }
     }
 
+    pub fn set_style(&mut self, style: OverlayStyle) {
+        self.style = style;
+    }
+
+    pub fn set_crosshair_visible(&mut self, visible: bool) {
+        self.show_crosshair = visible;
+    }
+
     pub fn prepare(
         &mut self,
         device: &wgpu::Device,
         queue: &wgpu::Queue,
         viewport: [u32; 2],
-        text: &str,
+        content: &OverlayContent,
     ) {
+        self.batch.clear();
         if viewport[0] == 0 || viewport[1] == 0 {
-            self.vertex_count = 0;
             return;
         }
 
-        self.vertices.clear();
         let width = viewport[0] as f32;
         let height = viewport[1] as f32;
 
-        let mut cursor_x = PADDING_X;
-        let mut cursor_y = PADDING_Y;
-        let line_height = (GLYPH_HEIGHT + GLYPH_SPACING_Y) as f32;
-        let advance = (GLYPH_WIDTH + GLYPH_SPACING_X) as f32;
+        let viewport = [width, height];
+        let scale = self.style.text_scale;
+        let line_height = (GLYPH_HEIGHT + GLYPH_SPACING_Y) as f32 * scale;
+        let advance = (GLYPH_WIDTH + GLYPH_SPACING_X) as f32 * scale;
 
-        for ch in text.chars() {
-            if ch == '\n' {
-                cursor_x = PADDING_X;
-                cursor_y += line_height;
-                continue;
+        let palette = self.style.palette;
+        let backdrop = palette.backdrop;
+        for label in content.floating_labels {
+            let columns = label.text.chars().count() as f32;
+            let origin = [
+                (label.position[0] - columns * advance * 0.5).round(),
+                (label.position[1] - GLYPH_HEIGHT as f32 * scale * 0.5).round(),
+            ];
+            self.push_line(origin, &label.text, label.color, [0.0; 4], viewport);
+        }
+
+        for (row, line) in content.text.lines().enumerate() {
+            let origin = [PADDING_X, PADDING_Y + row as f32 * line_height];
+            self.push_line(origin, line, palette.text, backdrop, viewport);
+        }
+
+        // Subtitles stack upwards from the bottom-right corner, newest last,
+        // and always get a panel so they stay legible over bright terrain.
+        let subtitle_backdrop = if backdrop[3] > 0.0 {
+            backdrop
+        } else {
+            SUBTITLE_BACKDROP
+        };
+        for graph in content.graphs {
+            self.push_graph(graph, subtitle_backdrop, viewport);
+        }
+
+        let bottom = height - PADDING_Y - GLYPH_HEIGHT as f32 * scale;
+        for (row, line) in content.subtitles.iter().rev().enumerate() {
+            let columns = line.chars().count() as f32;
+            let origin = [
+                width - PADDING_X - columns * advance + GLYPH_SPACING_X as f32 * scale,
+                bottom - row as f32 * line_height,
+            ];
+            self.push_line(origin, line, palette.text, subtitle_backdrop, viewport);
+        }
+
+        for (row, line) in content.console.iter().rev().enumerate() {
+            let origin = [PADDING_X, bottom - row as f32 * line_height];
+            self.push_line(origin, line, palette.text, subtitle_backdrop, viewport);
+        }
+
+        if !content.dialog.is_empty() {
+            self.push_dialog(content.dialog, subtitle_backdrop, viewport);
+        } else if let Some(menu) = &content.radial_menu {
+            self.push_radial_menu(menu, subtitle_backdrop, viewport);
+        } else if self.show_crosshair {
+            self.push_crosshair(viewport);
+            if let Some(progress) = content.break_progress {
+                self.push_progress_bar(progress, BREAK_BAR_OFFSET, viewport);
             }
+            if let Some(progress) = content.attack_cooldown {
+                self.push_progress_bar(progress, ATTACK_BAR_OFFSET, viewport);
+            }
+        }
 
-            let key = if ch.is_ascii_alphabetic() {
-                ch.to_ascii_uppercase()
+        self.batch.upload(device, queue);
+    }
+
+    /// Draws one line of text with its top-left corner at `origin`, over a
+    /// backdrop panel unless `backdrop` is fully transparent.
+    fn push_line(
+        &mut self,
+        origin: [f32; 2],
+        line: &str,
+        color: [f32; 4],
+        backdrop: [f32; 4],
+        viewport: [f32; 2],
+    ) {
+        let scale = self.style.text_scale;
+        let advance = (GLYPH_WIDTH + GLYPH_SPACING_X) as f32 * scale;
+        let glyph_width = GLYPH_WIDTH as f32 * scale;
+        let glyph_height = GLYPH_HEIGHT as f32 * scale;
+
+        let columns = line.chars().count();
+        if columns == 0 {
+            return;
+        }
+        if backdrop[3] > 0.0 {
+            let margin = BACKDROP_MARGIN * scale;
+            self.push_rect(
+                [origin[0] - margin, origin[1] - margin],
+                [
+                    origin[0] + columns as f32 * advance + margin,
+                    origin[1] + glyph_height + margin,
+                ],
+                backdrop,
+                viewport,
+            );
+        }
+
+        let mut cursor_x = origin[0];
+        for ch in line.chars() {
+            if let Some(glyph) = self.glyphs.get(&glyph_key(ch)).copied() {
+                self.push_quad(
+                    [cursor_x, origin[1]],
+                    [cursor_x + glyph_width, origin[1] + glyph_height],
+                    glyph,
+                    color,
+                    viewport,
+                );
+            }
+            cursor_x += advance;
+        }
+    }
+
+    /// Slot labels on a ring around the screen center, the highlighted one in
+    /// the crosshair color, plus a dot following the picker's cursor.
+    fn push_radial_menu(&mut self, menu: &RadialMenu, backdrop: [f32; 4], viewport: [f32; 2]) {
+        let scale = self.style.text_scale;
+        let advance = (GLYPH_WIDTH + GLYPH_SPACING_X) as f32 * scale;
+        let center = [(viewport[0] * 0.5).round(), (viewport[1] * 0.5).round()];
+        let count = menu.labels.len();
+        let radius = (RADIAL_LABEL_SPACING * count as f32).max(RADIAL_MIN_RADIUS) * scale;
+        let palette = self.style.palette;
+
+        for (index, label) in menu.labels.iter().enumerate() {
+            let marked = if index == menu.selected {
+                format!("*{label}")
             } else {
-                ch
+                label.clone()
             };
-
-            let glyph = match self.glyphs.get(&key) {
-                Some(info) => info,
-                None => {
-                    cursor_x += advance;
-                    continue;
-                }
+            // Clockwise from straight up, matching `RadialPicker`.
+            let angle = index as f32 / count as f32 * std::f32::consts::TAU;
+            let label_width = marked.chars().count() as f32 * advance;
+            let origin = [
+                (center[0] + radius * angle.sin() - label_width * 0.5).round(),
+                (center[1] - radius * angle.cos() - GLYPH_HEIGHT as f32 * scale * 0.5).round(),
+            ];
+            let color = if menu.highlighted == Some(index) {
+                palette.crosshair
+            } else {
+                palette.text
             };
+            self.push_line(origin, &marked, color, backdrop, viewport);
+        }
 
-            let x0 = cursor_x;
-            let y0 = cursor_y;
-            let x1 = x0 + GLYPH_WIDTH as f32;
-            let y1 = y0 + GLYPH_HEIGHT as f32;
-
-            let p0 = screen_to_ndc(x0, y0, width, height);
-            let p1 = screen_to_ndc(x1, y0, width, height);
-            let p2 = screen_to_ndc(x0, y1, width, height);
-            let p3 = screen_to_ndc(x1, y1, width, height);
+        let pointer = [
+            center[0] + menu.pointer[0] * radius * 0.5,
+            center[1] + menu.pointer[1] * radius * 0.5,
+        ];
+        let half = RADIAL_POINTER_SIZE * 0.5;
+        self.push_rect(
+            [pointer[0] - half, pointer[1] - half],
+            [pointer[0] + half, pointer[1] + half],
+            palette.crosshair,
+            viewport,
+        );
+    }
 
-            let color = [1.0, 1.0, 1.0, 1.0];
-            let (u0, v0, u1, v1) = (glyph.u0, glyph.v0, glyph.u1, glyph.v1);
+    /// Shades the screen and stacks `lines` around its center.
+    fn push_dialog(&mut self, lines: &[String], backdrop: [f32; 4], viewport: [f32; 2]) {
+        self.push_rect([0.0, 0.0], viewport, DIALOG_SHADE, viewport);
+        let scale = self.style.text_scale;
+        let advance = (GLYPH_WIDTH + GLYPH_SPACING_X) as f32 * scale;
+        let line_height = (GLYPH_HEIGHT + GLYPH_SPACING_Y) as f32 * scale;
+        let top = viewport[1] * 0.5 - lines.len() as f32 * line_height * 0.5;
+        let color = self.style.palette.text;
+        for (row, line) in lines.iter().enumerate() {
+            let columns = line.chars().count() as f32;
+            let origin = [
+                (viewport[0] * 0.5 - columns * advance * 0.5).round(),
+                (top + row as f32 * line_height).round(),
+            ];
+            self.push_line(origin, line, color, backdrop, viewport);
+        }
+    }
 
-            self.vertices.push(TextVertex {
-                position: p0,
-                uv: [u0, v0],
-                color,
-            });
-            self.vertices.push(TextVertex {
-                position: p1,
-                uv: [u1, v0],
-                color,
-            });
-            self.vertices.push(TextVertex {
-                position: p2,
-                uv: [u0, v1],
-                color,
-            });
-            self.vertices.push(TextVertex {
-                position: p2,
-                uv: [u0, v1],
-                color,
-            });
-            self.vertices.push(TextVertex {
-                position: p1,
-                uv: [u1, v0],
+    /// Two crossed bars at the screen center, over a slightly larger outline
+    /// when the palette has one.
+    fn push_crosshair(&mut self, viewport: [f32; 2]) {
+        let (arm, thickness) = if self.style.high_contrast_crosshair {
+            (
+                HIGH_CONTRAST_CROSSHAIR_ARM,
+                HIGH_CONTRAST_CROSSHAIR_THICKNESS,
+            )
+        } else {
+            (CROSSHAIR_ARM, CROSSHAIR_THICKNESS)
+        };
+        let center = [(viewport[0] * 0.5).round(), (viewport[1] * 0.5).round()];
+        let palette = self.style.palette;
+        let layers = [
+            (
+                arm + CROSSHAIR_OUTLINE,
+                thickness * 0.5 + CROSSHAIR_OUTLINE,
+                palette.crosshair_outline,
+            ),
+            (arm, thickness * 0.5, palette.crosshair),
+        ];
+        for (half_length, half_width, color) in layers {
+            if color[3] <= 0.0 {
+                continue;
+            }
+            self.push_rect(
+                [center[0] - half_length, center[1] - half_width],
+                [center[0] + half_length, center[1] + half_width],
                 color,
-            });
-            self.vertices.push(TextVertex {
-                position: p3,
-                uv: [u1, v1],
+                viewport,
+            );
+            self.push_rect(
+                [center[0] - half_width, center[1] - half_length],
+                [center[0] + half_width, center[1] + half_length],
                 color,
-            });
-
-            cursor_x += advance;
+                viewport,
+            );
         }
+    }
 
-        self.vertex_count = self.vertices.len();
+    /// Bar `offset` pixels under the crosshair, filled to `progress`.
+    fn push_progress_bar(&mut self, progress: f32, offset: f32, viewport: [f32; 2]) {
+        let palette = self.style.palette;
+        let min = [
+            (viewport[0] * 0.5 - BREAK_BAR_WIDTH * 0.5).round(),
+            (viewport[1] * 0.5).round() + offset,
+        ];
+        let max = [min[0] + BREAK_BAR_WIDTH, min[1] + BREAK_BAR_HEIGHT];
+        let outline = CROSSHAIR_OUTLINE;
+        self.push_rect(
+            [min[0] - outline, min[1] - outline],
+            [max[0] + outline, max[1] + outline],
+            palette.crosshair_outline,
+            viewport,
+        );
+        let filled = min[0] + BREAK_BAR_WIDTH * progress.clamp(0.0, 1.0);
+        self.push_rect(min, [filled, max[1]], palette.crosshair, viewport);
+    }
 
-        if self.vertex_count == 0 {
-            return;
-        }
+    /// A panel with one bar per value, guide lines across it, and the
+    /// label above.
+    fn push_graph(&mut self, graph: &BarGraph, backdrop: [f32; 4], viewport: [f32; 2]) {
+        let scale = self.style.text_scale;
+        let palette = self.style.palette;
+        let [x, y] = graph.origin;
+        let [width, height] = graph.size;
+        let label_origin = [x, y - (GLYPH_HEIGHT + GLYPH_SPACING_Y) as f32 * scale];
+        self.push_line(label_origin, graph.label, palette.text, [0.0; 4], viewport);
+        self.push_rect([x, y], [x + width, y + height], backdrop, viewport);
 
-        if self.vertex_count > self.vertex_capacity {
-            self.vertex_capacity = self.vertex_count.next_power_of_two();
-            self.vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-                label: Some("Debug text vertex buffer"),
-                size: (self.vertex_capacity * std::mem::size_of::<TextVertex>())
-                    as wgpu::BufferAddress,
-                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-                mapped_at_creation: false,
-            });
+        let bar_width = width / graph.values.len().max(1) as f32;
+        for (index, value) in graph.values.iter().enumerate() {
+            let left = x + index as f32 * bar_width;
+            let top = y + height * (1.0 - value.clamp(0.0, 1.0));
+            self.push_rect(
+                [left, top],
+                [left + bar_width, y + height],
+                palette.text,
+                viewport,
+            );
+        }
+        for guide in graph.guides {
+            let line_y = (y + height * (1.0 - guide.clamp(0.0, 1.0))).round();
+            self.push_rect(
+                [x, line_y],
+                [x + width, line_y + 1.0],
+                palette.crosshair,
+                viewport,
+            );
         }
+    }
+
+    fn push_rect(&mut self, min: [f32; 2], max: [f32; 2], color: [f32; 4], viewport: [f32; 2]) {
+        let Some(solid) = self.glyphs.get(&SOLID_GLYPH).copied() else {
+            return;
+        };
+        // Sample only the glyph's center so every texel is lit.
+        let u = (solid.u0 + solid.u1) * 0.5;
+        let v = (solid.v0 + solid.v1) * 0.5;
+        let center = GlyphInfo {
+            u0: u,
+            v0: v,
+            u1: u,
+            v1: v,
+        };
+        self.push_quad(min, max, center, color, viewport);
+    }
+
+    fn push_quad(
+        &mut self,
+        min: [f32; 2],
+        max: [f32; 2],
+        glyph: GlyphInfo,
+        color: [f32; 4],
+        viewport: [f32; 2],
+    ) {
+        let (u0, v0, u1, v1) = (glyph.u0, glyph.v0, glyph.u1, glyph.v1);
+        let corner = |x: f32, y: f32, uv: [f32; 2]| TextVertex {
+            position: ui::screen_to_ndc([x, y], viewport),
+            uv,
+            color,
+        };
+        self.batch.push_quad([
+            corner(min[0], min[1], [u0, v0]),
+            corner(max[0], min[1], [u1, v0]),
+            corner(min[0], max[1], [u0, v1]),
+            corner(max[0], max[1], [u1, v1]),
+        ]);
+    }
 
-        queue.write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(&self.vertices));
+    /// Distance between text rows in screen pixels.
+    pub fn line_height(&self) -> f32 {
+        (GLYPH_HEIGHT + GLYPH_SPACING_Y) as f32 * self.style.text_scale
     }
 
+    /// Top-left corner of the first row of `OverlayContent::text`.
+    pub fn text_origin(&self) -> [f32; 2] {
+        [PADDING_X, PADDING_Y]
+    }
+
+    /// Draws the overlay in a pass of its own, for screens without a
+    /// [`UiStack`](crate::ui::UiStack).
     pub fn render(&self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
-        if self.vertex_count == 0 {
+        if self.batch.is_empty() {
             return;
         }
 
@@ -333,18 +690,42 @@ impl DebugOverlay {
             depth_stencil_attachment: None,
         });
 
+        self.draw(&mut pass);
+    }
+}
+
+/// The debug text, crosshair, menus and dialogs.
+impl UiLayer for DebugOverlay {
+    fn label(&self) -> &'static str {
+        "Debug text overlay"
+    }
+
+    fn prepare(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, frame: &UiFrame) {
+        DebugOverlay::prepare(self, device, queue, frame.viewport, frame.content);
+    }
+
+    fn draw<'a>(&'a self, pass: &mut wgpu::RenderPass<'a>) {
+        if self.batch.is_empty() {
+            return;
+        }
         pass.set_pipeline(&self.pipeline);
         pass.set_bind_group(0, &self.bind_group, &[]);
-        pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-        pass.draw(0..self.vertex_count as u32, 0..1);
+        self.batch.draw(pass);
     }
 }
 
-fn screen_to_ndc(x: f32, y: f32, width: f32, height: f32) -> [f32; 2] {
-    [(x / width) * 2.0 - 1.0, 1.0 - (y / height) * 2.0]
+/// The font has capitals only; lowercase letters are drawn with them.
+pub fn glyph_key(ch: char) -> char {
+    if ch.is_ascii_alphabetic() {
+        ch.to_ascii_uppercase()
+    } else {
+        ch
+    }
 }
 
-fn build_font_atlas() -> (HashMap<char, GlyphInfo>, Vec<u8>, [u32; 2]) {
+/// Packs every glyph, white on transparent, into an RGBA8 texture. Returns
+/// the glyphs, the texels and the texture size.
+pub fn build_font_atlas() -> (HashMap<char, GlyphInfo>, Vec<u8>, [u32; 2]) {
     let patterns = glyph_patterns();
     let glyph_count = patterns.len() as u32;
     let cols = 8u32;
@@ -804,5 +1185,30 @@ fn glyph_patterns() -> Vec<GlyphPattern> {
                 0b00000, 0b00000, 0b01001, 0b10110, 0b00000, 0b00000, 0b00000,
             ],
         ),
+        glyph(
+            '\u{2190}',
+            [
+                0b00000, 0b00100, 0b01000, 0b11111, 0b01000, 0b00100, 0b00000,
+            ],
+        ),
+        glyph(
+            '\u{2191}',
+            [
+                0b00100, 0b01110, 0b10101, 0b00100, 0b00100, 0b00100, 0b00000,
+            ],
+        ),
+        glyph(
+            '\u{2192}',
+            [
+                0b00000, 0b00100, 0b00010, 0b11111, 0b00010, 0b00100, 0b00000,
+            ],
+        ),
+        glyph(
+            '\u{2193}',
+            [
+                0b00000, 0b00100, 0b00100, 0b00100, 0b10101, 0b01110, 0b00100,
+            ],
+        ),
+        glyph(SOLID_GLYPH, [0b11111; GLYPH_HEIGHT as usize]),
     ]
 }
@@ -1,54 +1,183 @@
+use std::collections::HashMap;
 use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
 
 use serde::Deserialize;
 
-#[derive(Clone, Copy)]
+use crate::block::{BlockKind, FaceDirection};
+use crate::ktx2::{self, Ktx2Texture};
+use crate::render;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct TileId {
     pub x: u32,
     pub y: u32,
 }
 
+/// Named atlases; tile coordinates are only meaningful within their own atlas.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AtlasKind {
+    Blocks,
+    Items,
+    Particles,
+    Ui,
+}
+
+impl AtlasKind {
+    pub const ALL: [AtlasKind; 4] = [
+        AtlasKind::Blocks,
+        AtlasKind::Items,
+        AtlasKind::Particles,
+        AtlasKind::Ui,
+    ];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            AtlasKind::Blocks => "blocks",
+            AtlasKind::Items => "items",
+            AtlasKind::Particles => "particles",
+            AtlasKind::Ui => "ui",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            AtlasKind::Blocks => "Block atlas",
+            AtlasKind::Items => "Item atlas",
+            AtlasKind::Particles => "Particle atlas",
+            AtlasKind::Ui => "UI atlas",
+        }
+    }
+}
+
+/// A tile reference qualified by the atlas it lives in.
+#[derive(Clone, Copy)]
+pub struct AtlasTile {
+    pub atlas: AtlasKind,
+    pub tile: TileId,
+}
+
 #[derive(Clone, Copy)]
 pub struct AtlasLayout {
     pub width: u32,
     pub height: u32,
     pub tile_size: u32,
-    pub _tiles_x: u32,
-    pub _tiles_y: u32,
+    /// Extruded gutter width on each side of a tile, in pixels.
+    pub padding: u32,
+    /// Tiles the sheet holds across and down, not counting the
+    /// missing-texture tile.
+    pub tiles_x: u32,
+    pub tiles_y: u32,
+    /// Magenta and black checker drawn for references outside the grid,
+    /// appended below it; `None` for compressed atlases, which are uploaded
+    /// as they are.
+    pub missing: Option<TileId>,
 }
 
 impl AtlasLayout {
-    pub fn map_uv(&self, tile: TileId, uv: [f32; 2]) -> [f32; 2] {
+    /// Returns the tile's normalized atlas rectangle as `[u0, v0, width, height]`.
+    ///
+    /// Shaders wrap the per-face local UV with `fract` and map it into this rect,
+    /// so a face may span several tiles worth of UV without bleeding.
+    pub fn tile_rect(&self, tile: TileId) -> [f32; 4] {
         let tile_size = self.tile_size as f32;
-        let tile_origin_x = tile.x as f32 * tile_size;
-        let tile_origin_y = tile.y as f32 * tile_size;
-        let pixel_x = tile_origin_x + uv[0].clamp(0.0, 1.0) * (tile_size - 1.0) + 0.5;
-        let pixel_y = tile_origin_y + uv[1].clamp(0.0, 1.0) * (tile_size - 1.0) + 0.5;
-        [pixel_x / self.width as f32, pixel_y / self.height as f32]
+        let width = self.width as f32;
+        let height = self.height as f32;
+        let [origin_x, origin_y] = self.tile_origin(self.resolve(tile));
+        [
+            origin_x as f32 / width,
+            origin_y as f32 / height,
+            tile_size / width,
+            tile_size / height,
+        ]
+    }
+
+    pub fn contains(&self, tile: TileId) -> bool {
+        tile.x < self.tiles_x && tile.y < self.tiles_y
+    }
+
+    /// The tile to draw for `tile`: itself when the sheet has it, otherwise
+    /// the missing-texture tile where there is one.
+    pub fn resolve(&self, tile: TileId) -> TileId {
+        match self.missing {
+            Some(missing) if !self.contains(tile) => missing,
+            _ => tile,
+        }
+    }
+
+    /// Distance in pixels between neighbouring tiles, gutters included.
+    pub fn stride(&self) -> u32 {
+        self.tile_size + self.padding * 2
+    }
+
+    /// Pixel position of the tile's first content texel, past its gutter.
+    pub fn tile_origin(&self, tile: TileId) -> [u32; 2] {
+        let stride = self.stride();
+        [
+            tile.x * stride + self.padding,
+            tile.y * stride + self.padding,
+        ]
     }
 }
 
 pub struct TextureAtlas {
+    kind: AtlasKind,
     _texture: wgpu::Texture,
     view: wgpu::TextureView,
     sampler: wgpu::Sampler,
     layout: AtlasLayout,
+    named_tiles: HashMap<String, TileId>,
 }
 
 #[derive(Deserialize)]
 struct AtlasMetadata {
     texture: String,
     tile_size: u32,
+    #[serde(default)]
+    padding: u32,
+    #[serde(default)]
+    tiles: HashMap<String, [u32; 2]>,
+    /// Pre-compressed KTX2 variants of `texture`, in order of preference.
+    #[serde(default)]
+    compressed: Vec<String>,
+}
+
+/// Decoded atlas pixels ready for upload, either RGBA8 or a GPU block format.
+struct AtlasImage {
+    format: wgpu::TextureFormat,
+    width: u32,
+    height: u32,
+    levels: Vec<Vec<u8>>,
+}
+
+impl From<Ktx2Texture> for AtlasImage {
+    fn from(texture: Ktx2Texture) -> Self {
+        Self {
+            format: texture.format,
+            width: texture.width,
+            height: texture.height,
+            levels: texture.levels,
+        }
+    }
+}
+
+/// CPU-side atlas data, decoded off the render thread and uploaded later.
+pub struct DecodedAtlas {
+    kind: AtlasKind,
+    image: AtlasImage,
+    layout: AtlasLayout,
+    named_tiles: HashMap<String, TileId>,
 }
 
 impl TextureAtlas {
-    pub fn load(
-        device: &wgpu::Device,
-        queue: &wgpu::Queue,
+    /// Reads and validates an atlas without touching the GPU. `features` decides
+    /// which compressed variants are eligible.
+    pub fn decode(
+        features: wgpu::Features,
+        kind: AtlasKind,
         metadata_path: impl AsRef<Path>,
-    ) -> io::Result<Self> {
+    ) -> io::Result<DecodedAtlas> {
         let metadata_path = metadata_path.as_ref();
         let metadata: AtlasMetadata =
             serde_json::from_slice(&fs::read(metadata_path)?).map_err(|err| {
@@ -58,18 +187,11 @@ impl TextureAtlas {
                 )
             })?;
 
-        let texture_path = resolve_texture_path(metadata_path, &metadata.texture);
-        let image = image::open(&texture_path).map_err(|err| {
-            io::Error::new(
-                io::ErrorKind::InvalidInput,
-                format!(
-                    "failed to open atlas image {}: {err}",
-                    texture_path.display()
-                ),
-            )
-        })?;
-        let rgba = image.to_rgba8();
-        let (width, height) = rgba.dimensions();
+        let mut image = match load_compressed(features, metadata_path, &metadata.compressed) {
+            Some(image) => image,
+            None => load_png(&resolve_texture_path(metadata_path, &metadata.texture))?,
+        };
+        let (width, height) = (image.width, image.height);
 
         if metadata.tile_size == 0 {
             return Err(io::Error::new(
@@ -77,7 +199,12 @@ impl TextureAtlas {
                 "tile_size must be > 0",
             ));
         }
-        if width % metadata.tile_size != 0 || height % metadata.tile_size != 0 {
+        // Padded atlases are rounded up to a power of two, so only unpadded
+        // sheets must tile the image exactly.
+        let stride = metadata.tile_size + metadata.padding * 2;
+        if metadata.padding == 0
+            && (width % metadata.tile_size != 0 || height % metadata.tile_size != 0)
+        {
             return Err(io::Error::new(
                 io::ErrorKind::InvalidData,
                 format!(
@@ -87,48 +214,108 @@ impl TextureAtlas {
             ));
         }
 
-        let tiles_x = width / metadata.tile_size;
-        let tiles_y = height / metadata.tile_size;
-        let pixel_data = rgba.into_raw();
+        let tiles_x = width / stride;
+        let tiles_y = height / stride;
+        if tiles_x == 0 || tiles_y == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "image dimensions {}x{} cannot hold a {} px tile with {} px padding",
+                    width, height, metadata.tile_size, metadata.padding
+                ),
+            ));
+        }
+
+        let mut named_tiles = HashMap::with_capacity(metadata.tiles.len());
+        for (name, [x, y]) in metadata.tiles {
+            if x >= tiles_x || y >= tiles_y {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "tile '{}' at ({}, {}) lies outside the {}x{} grid",
+                        name, x, y, tiles_x, tiles_y
+                    ),
+                ));
+            }
+            named_tiles.insert(name, TileId { x, y });
+        }
+
+        let mut layout = AtlasLayout {
+            width,
+            height,
+            tile_size: metadata.tile_size,
+            padding: metadata.padding,
+            tiles_x,
+            tiles_y,
+            missing: None,
+        };
+        if image.format == wgpu::TextureFormat::Rgba8UnormSrgb {
+            append_missing_tile(&mut image, &mut layout);
+        }
+        if kind == AtlasKind::Blocks {
+            check_block_tiles(&layout);
+        }
+
+        Ok(DecodedAtlas {
+            kind,
+            image,
+            layout,
+            named_tiles,
+        })
+    }
+
+    pub fn upload(device: &wgpu::Device, queue: &wgpu::Queue, decoded: DecodedAtlas) -> Self {
+        let DecodedAtlas {
+            kind,
+            image,
+            layout,
+            named_tiles,
+        } = decoded;
+        let (width, height) = (layout.width, layout.height);
 
         let texture = device.create_texture(&wgpu::TextureDescriptor {
-            label: Some("Block atlas texture"),
+            label: Some(&format!("{} texture", kind.label())),
             size: wgpu::Extent3d {
                 width,
                 height,
                 depth_or_array_layers: 1,
             },
-            mip_level_count: 1,
+            mip_level_count: image.levels.len() as u32,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            format: image.format,
             usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
             view_formats: &[],
         });
 
-        queue.write_texture(
-            wgpu::ImageCopyTexture {
-                texture: &texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: wgpu::TextureAspect::All,
-            },
-            &pixel_data,
-            wgpu::ImageDataLayout {
-                offset: 0,
-                bytes_per_row: Some(4 * width),
-                rows_per_image: Some(height),
-            },
-            wgpu::Extent3d {
-                width,
-                height,
-                depth_or_array_layers: 1,
-            },
-        );
+        for (level, data) in image.levels.iter().enumerate() {
+            let level_width = (width >> level).max(1);
+            let level_height = (height >> level).max(1);
+            queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    texture: &texture,
+                    mip_level: level as u32,
+                    origin: wgpu::Origin3d::ZERO,
+                    aspect: wgpu::TextureAspect::All,
+                },
+                data,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(ktx2::bytes_per_row(image.format, level_width)),
+                    rows_per_image: Some(ktx2::block_rows(image.format, level_height)),
+                },
+                wgpu::Extent3d {
+                    width: level_width,
+                    height: level_height,
+                    depth_or_array_layers: 1,
+                }
+                .physical_size(image.format),
+            );
+        }
 
-        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let view = render::labeled_view(&texture, &format!("{} view", kind.label()));
         let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
-            label: Some("Block atlas sampler"),
+            label: Some(&format!("{} sampler", kind.label())),
             address_mode_u: wgpu::AddressMode::ClampToEdge,
             address_mode_v: wgpu::AddressMode::ClampToEdge,
             address_mode_w: wgpu::AddressMode::ClampToEdge,
@@ -138,18 +325,19 @@ impl TextureAtlas {
             ..Default::default()
         });
 
-        Ok(Self {
+        Self {
+            kind,
             _texture: texture,
             view,
             sampler,
-            layout: AtlasLayout {
-                width,
-                height,
-                tile_size: metadata.tile_size,
-                _tiles_x: tiles_x,
-                _tiles_y: tiles_y,
-            },
-        })
+            layout,
+            named_tiles,
+        }
+    }
+
+    /// Looks up a tile by the name recorded in the atlas manifest.
+    pub fn tile(&self, name: &str) -> Option<TileId> {
+        self.named_tiles.get(name).copied()
     }
 
     pub fn layout(&self) -> AtlasLayout {
@@ -157,8 +345,7 @@ impl TextureAtlas {
     }
 
     pub fn create_view(&self) -> wgpu::TextureView {
-        self._texture
-            .create_view(&wgpu::TextureViewDescriptor::default())
+        render::labeled_view(&self._texture, &format!("{} view", self.kind.label()))
     }
 
     pub fn create_bind_group(
@@ -167,7 +354,7 @@ impl TextureAtlas {
         layout: &wgpu::BindGroupLayout,
     ) -> wgpu::BindGroup {
         device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Block atlas bind group"),
+            label: Some(&format!("{} bind group", self.kind.label())),
             layout,
             entries: &[
                 wgpu::BindGroupEntry {
@@ -183,6 +370,201 @@ impl TextureAtlas {
     }
 }
 
+/// Owns every loaded atlas, keyed by [`AtlasKind`].
+///
+/// Each atlas is described by `<name>.json` inside the texture directory. The
+/// block atlas is required; the others are optional and skipped when absent.
+pub struct AtlasRegistry {
+    atlases: HashMap<AtlasKind, TextureAtlas>,
+}
+
+impl AtlasRegistry {
+    /// Decodes every atlas in `directory`; safe to call from a worker thread.
+    pub fn decode(
+        features: wgpu::Features,
+        directory: impl AsRef<Path>,
+    ) -> io::Result<Vec<DecodedAtlas>> {
+        let directory = directory.as_ref();
+        let mut decoded = Vec::new();
+        for kind in AtlasKind::ALL {
+            let metadata_path = directory.join(format!("{}.json", kind.name()));
+            match TextureAtlas::decode(features, kind, &metadata_path) {
+                Ok(atlas) => decoded.push(atlas),
+                Err(err) if kind != AtlasKind::Blocks && err.kind() == io::ErrorKind::NotFound => {
+                    log::debug!("No {} atlas at {}", kind.name(), metadata_path.display());
+                }
+                Err(err) => {
+                    return Err(io::Error::new(
+                        err.kind(),
+                        format!("{} atlas: {err}", kind.name()),
+                    ));
+                }
+            }
+        }
+        Ok(decoded)
+    }
+
+    pub fn upload(device: &wgpu::Device, queue: &wgpu::Queue, decoded: Vec<DecodedAtlas>) -> Self {
+        let atlases = decoded
+            .into_iter()
+            .map(|atlas| (atlas.kind, TextureAtlas::upload(device, queue, atlas)))
+            .collect();
+        Self { atlases }
+    }
+
+    pub fn get(&self, kind: AtlasKind) -> Option<&TextureAtlas> {
+        self.atlases.get(&kind)
+    }
+
+    pub fn blocks(&self) -> &TextureAtlas {
+        self.atlases
+            .get(&AtlasKind::Blocks)
+            .expect("block atlas is loaded by AtlasRegistry::load")
+    }
+
+    /// Resolves a namespaced tile to its normalized rect, if its atlas is loaded.
+    pub fn tile_rect(&self, tile: AtlasTile) -> Option<[f32; 4]> {
+        self.get(tile.atlas)
+            .map(|atlas| atlas.layout().tile_rect(tile.tile))
+    }
+}
+
+/// Grows an RGBA8 sheet by one row of tiles and draws the missing-texture
+/// checker in its first tile, gutters included.
+fn append_missing_tile(image: &mut AtlasImage, layout: &mut AtlasLayout) {
+    let stride = layout.stride();
+    let row_bytes = image.width as usize * 4;
+    let pixels = &mut image.levels[0];
+    pixels.resize(pixels.len() + row_bytes * stride as usize, 0);
+    let half = (layout.tile_size / 2).max(1);
+    for y in 0..stride {
+        for x in 0..stride.min(image.width) {
+            let content_x = x.saturating_sub(layout.padding).min(layout.tile_size - 1);
+            let content_y = y.saturating_sub(layout.padding).min(layout.tile_size - 1);
+            let texel = if (content_x / half + content_y / half).is_multiple_of(2) {
+                [255, 0, 255, 255]
+            } else {
+                [0, 0, 0, 255]
+            };
+            let offset = (image.height + y) as usize * row_bytes + x as usize * 4;
+            pixels[offset..offset + 4].copy_from_slice(&texel);
+        }
+    }
+    image.height += stride;
+    layout.height = image.height;
+    layout.missing = Some(TileId {
+        x: 0,
+        y: layout.tiles_y,
+    });
+}
+
+/// Logs every block face whose tile lies outside the block atlas's grid.
+/// Returns how many there are.
+fn check_block_tiles(layout: &AtlasLayout) -> usize {
+    let mut problems = 0;
+    for kind in BlockKind::ALL {
+        let mut faces: Vec<(TileId, Vec<FaceDirection>)> = Vec::new();
+        for face in FaceDirection::ALL {
+            let tile = kind.tile_for_face(face);
+            if layout.contains(tile) {
+                continue;
+            }
+            match faces.iter_mut().find(|(other, _)| *other == tile) {
+                Some((_, directions)) => directions.push(face),
+                None => faces.push((tile, vec![face])),
+            }
+        }
+        for (tile, directions) in faces {
+            log::error!(
+                "Block atlas: {} uses tile ({}, {}) on its {:?} faces, outside the {}x{} tile grid",
+                kind.display_name(),
+                tile.x,
+                tile.y,
+                directions,
+                layout.tiles_x,
+                layout.tiles_y
+            );
+            problems += 1;
+        }
+    }
+    if problems > 0 {
+        match layout.missing {
+            Some(_) => log::error!(
+                "Block atlas: {problems} tile references are drawn with the missing-texture tile"
+            ),
+            None => log::error!(
+                "Block atlas: {problems} tile references sample past the compressed sheet, \
+                 which has no missing-texture tile"
+            ),
+        }
+    }
+    problems
+}
+
+/// Picks the first KTX2 variant whose format the enabled features can sample.
+fn load_compressed(
+    features: wgpu::Features,
+    metadata_path: &Path,
+    candidates: &[String],
+) -> Option<AtlasImage> {
+    for candidate in candidates {
+        let path = resolve_texture_path(metadata_path, candidate);
+        let texture = match fs::read(&path).and_then(|bytes| Ktx2Texture::parse(&bytes, features)) {
+            Ok(texture) => texture,
+            Err(err) => {
+                log::warn!("Skipping compressed atlas {}: {err}", path.display());
+                continue;
+            }
+        };
+        let (block_w, block_h) = texture.format.block_dimensions();
+        if texture.width % block_w != 0 || texture.height % block_h != 0 {
+            log::warn!(
+                "Skipping compressed atlas {}: {}x{} is not a multiple of the {}x{} block size",
+                path.display(),
+                texture.width,
+                texture.height,
+                block_w,
+                block_h
+            );
+            continue;
+        }
+        if features.contains(texture.format.required_features()) {
+            log::info!(
+                "Using compressed atlas {} ({:?})",
+                path.display(),
+                texture.format
+            );
+            return Some(texture.into());
+        }
+        log::debug!(
+            "Adapter cannot sample {:?}; skipping {}",
+            texture.format,
+            path.display()
+        );
+    }
+    None
+}
+
+fn load_png(texture_path: &Path) -> io::Result<AtlasImage> {
+    let image = image::open(texture_path).map_err(|err| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "failed to open atlas image {}: {err}",
+                texture_path.display()
+            ),
+        )
+    })?;
+    let rgba = image.to_rgba8();
+    let (width, height) = rgba.dimensions();
+    Ok(AtlasImage {
+        format: wgpu::TextureFormat::Rgba8UnormSrgb,
+        width,
+        height,
+        levels: vec![rgba.into_raw()],
+    })
+}
+
 fn resolve_texture_path(metadata_path: &Path, texture: &str) -> PathBuf {
     let base = metadata_path
         .parent()
@@ -190,3 +572,35 @@ fn resolve_texture_path(metadata_path: &Path, texture: &str) -> PathBuf {
         .unwrap_or_else(|| PathBuf::from("."));
     base.join(texture)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shipped_block_atlas_covers_the_registry() {
+        let decoded = TextureAtlas::decode(
+            wgpu::Features::empty(),
+            AtlasKind::Blocks,
+            concat!(env!("CARGO_MANIFEST_DIR"), "/assets/textures/blocks.json"),
+        )
+        .unwrap();
+        let layout = decoded.layout;
+        assert_eq!(check_block_tiles(&layout), 0);
+
+        let missing = layout.missing.unwrap();
+        let outside = TileId {
+            x: layout.tiles_x,
+            y: 0,
+        };
+        assert_eq!(layout.resolve(outside), missing);
+        assert_eq!(layout.tile_rect(outside), layout.tile_rect(missing));
+        let [x, y] = layout.tile_origin(missing);
+        let texel = |x: u32, y: u32| {
+            let offset = (y * decoded.image.width + x) as usize * 4;
+            decoded.image.levels[0][offset..offset + 4].to_vec()
+        };
+        assert_eq!(texel(x, y), [255, 0, 255, 255]);
+        assert_eq!(texel(x + layout.tile_size / 2, y), [0, 0, 0, 255]);
+    }
+}
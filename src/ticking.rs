@@ -0,0 +1,641 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use glam::{IVec3, Vec3};
+
+use crate::block::{BlockChange, BlockChanges, BlockKind, WATER_MAX_FLOW, WHEAT_MAX_STAGE};
+use crate::coords::{BlockPos, ChunkPos, FACE_NEIGHBORS, HORIZONTAL_NEIGHBORS};
+use crate::events::{ChangeCause, EventBus, GameEvent};
+use crate::rand::Rng;
+use crate::save::{ScheduledUpdateRecord, WorldSave};
+use crate::world::{CHUNK_SIZE, World};
+
+/// Fixed simulation rate for block updates, independent of frame rate.
+pub const TICKS_PER_SECOND: f32 = 20.0;
+/// Blocks picked per loaded chunk each tick to receive a random update.
+pub const RANDOM_TICKS_PER_CHUNK: usize = 3;
+
+const TICK_SECONDS: f32 = 1.0 / TICKS_PER_SECOND;
+/// Caps catch-up after a stall so a long hitch does not freeze the game further.
+const MAX_TICKS_PER_UPDATE: u32 = 10;
+/// Bounds the work done per tick; the rest stays queued for the next one.
+const MAX_SCHEDULED_UPDATES_PER_TICK: usize = 1024;
+
+/// A pending `on_scheduled_update` call; ordered by due tick, then by the
+/// order it was scheduled in.
+type QueuedUpdate = Reverse<(u64, u64, [i32; 3])>;
+
+/// A block sliding between cells, drawn by the renderers until it lands.
+#[derive(Clone, Copy, Debug)]
+pub struct MovingBlock {
+    pub kind: BlockKind,
+    /// Minimum corner in world space.
+    pub position: Vec3,
+    /// Edge length of the drawn cube; below 1.0 for shrunken models such as
+    /// dropped items.
+    pub scale: f32,
+}
+
+/// A `BlockChange::Move` in progress.
+struct BlockMotion {
+    to: IVec3,
+    direction: IVec3,
+    kind: BlockKind,
+    start: u64,
+    end: u64,
+}
+
+/// Drives block updates at a fixed rate.
+///
+/// Every tick a few random blocks in each loaded chunk get their
+/// [`random_tick`] hook (grass spreading), and every scheduled update that has
+/// come due runs its block's `on_scheduled_update` hook from the registry
+/// (crop growth, falling sand). Updates are scheduled whenever a block or one
+/// of its neighbours changes, and updates whose chunk is unloaded when they
+/// come due wait until it is loaded again. Blocks moved by an update (pushed
+/// by pistons) slide over a few ticks and are written to the world on arrival.
+/// Updates still pending when their chunk leaves the loaded area are saved
+/// with it and requeued when it comes back.
+pub struct BlockTicker {
+    accumulator: f32,
+    rng: Rng,
+    tick: u64,
+    sequence: u64,
+    queue: BinaryHeap<QueuedUpdate>,
+    pending: HashSet<[i32; 3]>,
+    parked: HashMap<ChunkPos, Vec<[i32; 3]>>,
+    motions: Vec<BlockMotion>,
+    /// Chunks whose saved updates have been requeued.
+    restored: HashSet<ChunkPos>,
+}
+
+impl BlockTicker {
+    /// `rng` drives the random ticks; see `Stream::RandomTicks`.
+    pub fn new(rng: Rng) -> Self {
+        Self {
+            accumulator: 0.0,
+            rng,
+            tick: 0,
+            sequence: 0,
+            queue: BinaryHeap::new(),
+            pending: HashSet::new(),
+            parked: HashMap::new(),
+            motions: Vec::new(),
+            restored: HashSet::new(),
+        }
+    }
+
+    /// Number of scheduled updates not yet run, including parked ones.
+    pub fn pending_updates(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Blocks currently sliding between cells, interpolated to the current
+    /// frame so the motion stays smooth between ticks.
+    pub fn moving_blocks(&self) -> Vec<MovingBlock> {
+        let partial_tick = self.accumulator / TICK_SECONDS;
+        self.motions
+            .iter()
+            .map(|motion| {
+                let elapsed = (self.tick - motion.start) as f32 + partial_tick;
+                let progress = (elapsed / (motion.end - motion.start) as f32).min(1.0);
+                let from = (motion.to - motion.direction).as_vec3();
+                MovingBlock {
+                    kind: motion.kind,
+                    position: from + motion.direction.as_vec3() * progress,
+                    scale: 1.0,
+                }
+            })
+            .collect()
+    }
+
+    /// Sets a block on the player's behalf and schedules updates for it and
+    /// its neighbours.
+    pub fn set_block(
+        &mut self,
+        world: &mut World,
+        events: &mut EventBus,
+        position: IVec3,
+        kind: BlockKind,
+    ) -> bool {
+        self.change_block(world, events, position, kind, ChangeCause::Player)
+    }
+
+    fn change_block(
+        &mut self,
+        world: &mut World,
+        events: &mut EventBus,
+        position: IVec3,
+        kind: BlockKind,
+        cause: ChangeCause,
+    ) -> bool {
+        let old = block_kind_at(world, position);
+        let changed = world.set_block(position, kind.id());
+        if changed {
+            self.block_changed(world, position);
+            events.publish(GameEvent::BlockChanged {
+                position,
+                old,
+                new: kind,
+                cause,
+            });
+        }
+        changed
+    }
+
+    /// Schedules the registry update of the block at `position` and of its
+    /// neighbours; call after any block edit made outside the ticker.
+    pub fn block_changed(&mut self, world: &World, position: IVec3) {
+        self.schedule(world, position);
+        for offset in FACE_NEIGHBORS {
+            self.schedule(world, position + offset);
+        }
+    }
+
+    fn schedule(&mut self, world: &World, position: IVec3) {
+        let kind = block_kind_at(world, position);
+        let Some(update) = kind.definition().on_scheduled_update else {
+            return;
+        };
+        let key = position.to_array();
+        if !self.pending.insert(key) {
+            return;
+        }
+        self.push(self.tick + update.delay, key);
+    }
+
+    fn push(&mut self, due: u64, key: [i32; 3]) {
+        self.sequence += 1;
+        self.queue.push(Reverse((due, self.sequence, key)));
+    }
+
+    /// Saves the pending updates of chunks that have been unloaded and
+    /// requeues the saved updates of chunks that have been loaded.
+    pub fn sync(&mut self, world: &World, save: &WorldSave) {
+        let unloaded: HashSet<ChunkPos> = self
+            .restored
+            .iter()
+            .copied()
+            .filter(|&coord| world.chunk(coord).is_none())
+            .collect();
+        if !unloaded.is_empty() {
+            let mut leaving = self.records_by_chunk(|coord| unloaded.contains(&coord));
+            self.queue.retain(|Reverse((_, _, key))| {
+                !unloaded.contains(&BlockPos(IVec3::from_array(*key)).chunk())
+            });
+            for coord in unloaded {
+                self.restored.remove(&coord);
+                self.parked.remove(&coord);
+                let records = leaving.remove(&coord).unwrap_or_default();
+                for record in &records {
+                    self.pending.remove(&record.position);
+                }
+                if let Err(err) = save.store_updates(coord, &records) {
+                    log::warn!("Failed to save block updates of chunk {:?}: {}", coord, err);
+                }
+            }
+        }
+
+        let mut loaded: Vec<ChunkPos> = world
+            .iter_chunks()
+            .map(|(&coord, _)| coord)
+            .filter(|coord| !self.restored.contains(coord))
+            .collect();
+        // Requeue in a fixed order, since updates due on the same tick run
+        // in the order they were queued.
+        loaded.sort_by_key(|coord| (coord.x, coord.y, coord.z));
+        for coord in loaded {
+            self.restored.insert(coord);
+            let records = save.load_updates(coord);
+            if records.is_empty() {
+                continue;
+            }
+            for record in records {
+                if self.pending.insert(record.position) {
+                    self.push(self.tick + record.delay, record.position);
+                }
+            }
+            // The updates live in the queue now and are saved again if the
+            // chunk unloads before they run.
+            if let Err(err) = save.store_updates(coord, &[]) {
+                log::warn!(
+                    "Failed to clear block updates of chunk {:?}: {}",
+                    coord,
+                    err
+                );
+            }
+        }
+    }
+
+    /// Saves the pending updates of every loaded chunk, for quitting or
+    /// leaving the dimension.
+    pub fn save_all(&self, save: &WorldSave) {
+        let mut by_chunk = self.records_by_chunk(|coord| self.restored.contains(&coord));
+        for &coord in &self.restored {
+            let records = by_chunk.remove(&coord).unwrap_or_default();
+            if let Err(err) = save.store_updates(coord, &records) {
+                log::warn!("Failed to save block updates of chunk {:?}: {}", coord, err);
+            }
+        }
+    }
+
+    /// Queued and parked updates in chunks matching `filter`, in the order
+    /// they would run, with their due tick made relative to now.
+    fn records_by_chunk(
+        &self,
+        filter: impl Fn(ChunkPos) -> bool,
+    ) -> HashMap<ChunkPos, Vec<ScheduledUpdateRecord>> {
+        let mut queued: Vec<(u64, u64, [i32; 3])> = self
+            .queue
+            .iter()
+            .map(|Reverse(entry)| *entry)
+            .filter(|&(_, _, key)| filter(BlockPos(IVec3::from_array(key)).chunk()))
+            .collect();
+        queued.sort_unstable();
+        let mut by_chunk: HashMap<ChunkPos, Vec<ScheduledUpdateRecord>> = HashMap::new();
+        // Parked updates are overdue and run as soon as their chunk is back.
+        for (&coord, keys) in self.parked.iter().filter(|(coord, _)| filter(**coord)) {
+            by_chunk.entry(coord).or_default().extend(
+                keys.iter()
+                    .map(|&position| ScheduledUpdateRecord { position, delay: 0 }),
+            );
+        }
+        for (due, _, position) in queued {
+            by_chunk
+                .entry(BlockPos(IVec3::from_array(position)).chunk())
+                .or_default()
+                .push(ScheduledUpdateRecord {
+                    position,
+                    delay: due.saturating_sub(self.tick),
+                });
+        }
+        by_chunk
+    }
+
+    /// Runs however many fixed ticks fit in the elapsed time.
+    pub fn update(&mut self, world: &mut World, events: &mut EventBus, dt: f32) {
+        self.accumulator += dt;
+        let mut ticks = 0;
+        while self.accumulator >= TICK_SECONDS {
+            self.accumulator -= TICK_SECONDS;
+            if ticks < MAX_TICKS_PER_UPDATE {
+                self.tick_once(world, events);
+                ticks += 1;
+            }
+        }
+    }
+
+    fn tick_once(&mut self, world: &mut World, events: &mut EventBus) {
+        self.tick += 1;
+        self.random_ticks(world, events);
+        self.unpark_loaded(world);
+        self.land_motions(world, events);
+        self.run_scheduled(world, events);
+    }
+
+    fn random_ticks(&mut self, world: &mut World, events: &mut EventBus) {
+        let mut coords: Vec<ChunkPos> = world.iter_chunks().map(|(coord, _)| *coord).collect();
+        // The chunk map iterates in a different order every run; the random
+        // picks must not.
+        coords.sort_by_key(|coord| (coord.x, coord.y, coord.z));
+        for coord in coords {
+            let base = coord.min_block();
+            for _ in 0..RANDOM_TICKS_PER_CHUNK {
+                let local = IVec3::new(
+                    self.rng.below(CHUNK_SIZE as u32) as i32,
+                    self.rng.below(CHUNK_SIZE as u32) as i32,
+                    self.rng.below(CHUNK_SIZE as u32) as i32,
+                );
+                let position = base + local;
+                let kind = world.block_info(position).kind;
+                if let Some((target, replacement)) =
+                    random_tick(world, position, kind, &mut self.rng)
+                {
+                    self.change_block(world, events, target, replacement, ChangeCause::Simulation);
+                }
+            }
+        }
+    }
+
+    fn run_scheduled(&mut self, world: &mut World, events: &mut EventBus) {
+        let mut processed = 0;
+        while processed < MAX_SCHEDULED_UPDATES_PER_TICK
+            && let Some(Reverse((due, _, key))) = self.queue.peek().copied()
+            && due <= self.tick
+        {
+            self.queue.pop();
+            processed += 1;
+
+            let position = IVec3::from_array(key);
+            let chunk = BlockPos(position).chunk();
+            if world.chunk(chunk).is_none() {
+                self.parked.entry(chunk).or_default().push(key);
+                continue;
+            }
+            self.pending.remove(&key);
+
+            let kind = block_kind_at(world, position);
+            let Some(update) = kind.definition().on_scheduled_update else {
+                continue;
+            };
+            for change in (update.hook)(world, position, kind) {
+                self.apply(world, events, change);
+            }
+        }
+    }
+
+    fn apply(&mut self, world: &mut World, events: &mut EventBus, change: BlockChange) {
+        match change {
+            BlockChange::Set(position, kind) => {
+                self.change_block(world, events, position, kind, ChangeCause::Simulation);
+            }
+            BlockChange::Move {
+                to,
+                direction,
+                kind,
+                ticks,
+            } => self.motions.push(BlockMotion {
+                to,
+                direction,
+                kind,
+                start: self.tick,
+                end: self.tick + ticks.max(1),
+            }),
+        }
+    }
+
+    /// Writes blocks whose motion has finished into their destination cell.
+    fn land_motions(&mut self, world: &mut World, events: &mut EventBus) {
+        let tick = self.tick;
+        let (landed, moving) = std::mem::take(&mut self.motions)
+            .into_iter()
+            .partition(|motion| motion.end <= tick);
+        self.motions = moving;
+        for motion in landed {
+            self.change_block(
+                world,
+                events,
+                motion.to,
+                motion.kind,
+                ChangeCause::Simulation,
+            );
+        }
+    }
+
+    /// Requeues parked updates whose chunk has been loaded again.
+    fn unpark_loaded(&mut self, world: &World) {
+        let loaded: Vec<ChunkPos> = self
+            .parked
+            .keys()
+            .copied()
+            .filter(|coord| world.chunk(*coord).is_some())
+            .collect();
+        for coord in loaded {
+            for key in self.parked.remove(&coord).unwrap_or_default() {
+                self.push(self.tick, key);
+            }
+        }
+    }
+}
+
+/// Per-block random update hook; returns the block change to apply, which
+/// need not be at `position` (grass converts its neighbours).
+fn random_tick(
+    world: &World,
+    position: IVec3,
+    kind: BlockKind,
+    rng: &mut Rng,
+) -> Option<(IVec3, BlockKind)> {
+    let above = block_kind_at(world, position + IVec3::Y);
+    match kind {
+        BlockKind::Grass if above.is_full_cube() => Some((position, BlockKind::Dirt)),
+        BlockKind::Grass => spread_grass(world, position, rng).map(|target| (target, kind)),
+        BlockKind::Farmland if above.is_full_cube() => Some((position, BlockKind::Dirt)),
+        _ => None,
+    }
+}
+
+/// Scheduled hook for crops: advances one growth stage on farmland and pops
+/// the crop once its farmland is gone.
+pub fn grow_crop(world: &World, position: IVec3, kind: BlockKind) -> BlockChanges {
+    let BlockKind::Wheat(stage) = kind else {
+        return Vec::new();
+    };
+    if block_kind_at(world, position - IVec3::Y) != BlockKind::Farmland {
+        vec![BlockChange::Set(position, BlockKind::Air)]
+    } else if stage < WHEAT_MAX_STAGE {
+        vec![BlockChange::Set(position, BlockKind::Wheat(stage + 1))]
+    } else {
+        Vec::new()
+    }
+}
+
+/// Scheduled hook for decorative plants: pops them once the grass or dirt
+/// beneath is gone.
+pub fn uproot(world: &World, position: IVec3, _kind: BlockKind) -> BlockChanges {
+    match block_kind_at(world, position - IVec3::Y) {
+        BlockKind::Grass | BlockKind::Dirt => Vec::new(),
+        _ => vec![BlockChange::Set(position, BlockKind::Air)],
+    }
+}
+
+/// Scheduled hook for wire: takes the strongest neighbouring signal, one
+/// weaker when it arrives through another wire. Changes ripple outwards one
+/// block per update, so signals fade with distance and decay once cut off.
+pub fn propagate_signal(world: &World, position: IVec3, kind: BlockKind) -> BlockChanges {
+    let BlockKind::Wire(current) = kind else {
+        return Vec::new();
+    };
+    let incoming = FACE_NEIGHBORS
+        .iter()
+        .map(|offset| match block_kind_at(world, position + *offset) {
+            BlockKind::Wire(signal) => signal.saturating_sub(1),
+            neighbor => neighbor.signal_output(),
+        })
+        .max()
+        .unwrap_or(0);
+    if incoming == current {
+        return Vec::new();
+    }
+    vec![BlockChange::Set(position, BlockKind::Wire(incoming))]
+}
+
+/// Scheduled hook for signal lamps: lit while any neighbour carries a signal.
+pub fn update_signal_lamp(world: &World, position: IVec3, kind: BlockKind) -> BlockChanges {
+    let BlockKind::SignalLamp(lit) = kind else {
+        return Vec::new();
+    };
+    let powered = FACE_NEIGHBORS
+        .iter()
+        .any(|offset| block_kind_at(world, position + *offset).signal_output() > 0);
+    if powered == lit {
+        return Vec::new();
+    }
+    vec![BlockChange::Set(position, BlockKind::SignalLamp(powered))]
+}
+
+/// Scheduled hook for falling blocks: drops one cell into air below.
+pub fn fall(world: &World, position: IVec3, kind: BlockKind) -> BlockChanges {
+    let below = position - IVec3::Y;
+    if block_kind_at(world, below) != BlockKind::Air
+        || world.chunk(BlockPos(below).chunk()).is_none()
+    {
+        return Vec::new();
+    }
+    vec![
+        BlockChange::Set(position, BlockKind::Air),
+        BlockChange::Set(below, kind),
+    ]
+}
+
+/// Scheduled hook for water: flowing water first settles one block further
+/// than the nearest water feeding it, or dries up with none left. Water then
+/// falls into air below, or spreads sideways along the ground while it has
+/// flowed less than `WATER_MAX_FLOW` blocks. Each change schedules its
+/// neighbours, so the flow advances a block per update.
+pub fn flow_water(world: &World, position: IVec3, kind: BlockKind) -> BlockChanges {
+    let distance = match kind {
+        BlockKind::Water => 0,
+        BlockKind::FlowingWater(distance) => {
+            let Some(fed) = fed_distance(world, position) else {
+                return vec![BlockChange::Set(position, BlockKind::Air)];
+            };
+            if fed != distance {
+                return vec![BlockChange::Set(position, BlockKind::FlowingWater(fed))];
+            }
+            distance
+        }
+        _ => return Vec::new(),
+    };
+
+    let below = position - IVec3::Y;
+    let below_kind = block_kind_at(world, below);
+    if below_kind == BlockKind::Air {
+        return if can_flow_into(world, below, 1) {
+            vec![BlockChange::Set(below, BlockKind::FlowingWater(1))]
+        } else {
+            Vec::new()
+        };
+    }
+    if below_kind.is_liquid() || distance >= WATER_MAX_FLOW {
+        return Vec::new();
+    }
+    HORIZONTAL_NEIGHBORS
+        .iter()
+        .map(|offset| position + *offset)
+        .filter(|target| can_flow_into(world, *target, distance + 1))
+        .map(|target| BlockChange::Set(target, BlockKind::FlowingWater(distance + 1)))
+        .collect()
+}
+
+/// How far flowing water at `position` is from its source: one block when
+/// water falls into it from above, otherwise one more than its nearest
+/// sideways neighbour. `None` once that is past `WATER_MAX_FLOW`.
+fn fed_distance(world: &World, position: IVec3) -> Option<u8> {
+    if block_kind_at(world, position + IVec3::Y).is_liquid() {
+        return Some(1);
+    }
+    HORIZONTAL_NEIGHBORS
+        .iter()
+        .filter_map(|offset| match block_kind_at(world, position + *offset) {
+            BlockKind::Water => Some(1),
+            BlockKind::FlowingWater(distance) => Some(distance + 1),
+            _ => None,
+        })
+        .min()
+        .filter(|distance| *distance <= WATER_MAX_FLOW)
+}
+
+/// Whether water that has flowed `distance` blocks may fill `target`: air,
+/// or water that is further from its source, in a loaded chunk.
+fn can_flow_into(world: &World, target: IVec3, distance: u8) -> bool {
+    let open = match block_kind_at(world, target) {
+        BlockKind::Air => true,
+        BlockKind::FlowingWater(existing) => existing > distance,
+        _ => false,
+    };
+    open && world.chunk(BlockPos(target).chunk()).is_some()
+}
+
+/// Picks a random nearby dirt block that is not buried for grass to spread onto.
+fn spread_grass(world: &World, position: IVec3, rng: &mut Rng) -> Option<IVec3> {
+    let offset = IVec3::new(
+        rng.below(3) as i32 - 1,
+        rng.below(5) as i32 - 3,
+        rng.below(3) as i32 - 1,
+    );
+    let target = position + offset;
+    if block_kind_at(world, target) != BlockKind::Dirt
+        || block_kind_at(world, target + IVec3::Y).is_full_cube()
+    {
+        return None;
+    }
+    Some(target)
+}
+
+pub fn block_kind_at(world: &World, position: IVec3) -> BlockKind {
+    world.block_info(position).kind
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp_dir::TempDir;
+
+    #[test]
+    fn pending_updates_are_saved_with_their_chunk_and_resume_when_it_loads() {
+        let dir = TempDir::new("ticking");
+        let save = WorldSave::at(dir.path().to_path_buf());
+        let sand = IVec3::new(3, 5, 3);
+        let mut world = World::from_blocks([(sand, BlockKind::Sand.id())]);
+        let unloaded = World::from_blocks([]);
+        let mut events = EventBus::new();
+
+        let mut ticker = BlockTicker::new(Rng::new(1));
+        ticker.sync(&world, &save);
+        ticker.block_changed(&world, sand);
+        ticker.update(&mut world, &mut events, TICK_SECONDS);
+        assert_eq!(ticker.pending_updates(), 1);
+
+        ticker.sync(&unloaded, &save);
+        assert_eq!(ticker.pending_updates(), 0);
+        let chunk = BlockPos(sand).chunk();
+        let delay = BlockKind::Sand
+            .definition()
+            .on_scheduled_update
+            .unwrap()
+            .delay;
+        let left = delay - 1;
+        assert_eq!(
+            save.load_updates(chunk),
+            [ScheduledUpdateRecord {
+                position: sand.to_array(),
+                delay: left,
+            }]
+        );
+
+        // A new session counts ticks from zero; the update still waits out
+        // only the ticks it had left.
+        let mut ticker = BlockTicker::new(Rng::new(1));
+        ticker.sync(&world, &save);
+        assert_eq!(ticker.pending_updates(), 1);
+        assert!(save.load_updates(chunk).is_empty());
+        for _ in 1..left {
+            ticker.update(&mut world, &mut events, TICK_SECONDS);
+        }
+        assert_eq!(block_kind_at(&world, sand), BlockKind::Sand);
+        ticker.update(&mut world, &mut events, TICK_SECONDS);
+        assert_eq!(block_kind_at(&world, sand), BlockKind::Air);
+        assert_eq!(block_kind_at(&world, sand - IVec3::Y), BlockKind::Sand);
+
+        // Quitting saves the fall the landing scheduled, from loaded chunks.
+        ticker.save_all(&save);
+        assert_eq!(
+            save.load_updates(chunk),
+            [ScheduledUpdateRecord {
+                position: (sand - IVec3::Y).to_array(),
+                delay,
+            }]
+        );
+    }
+}
@@ -0,0 +1,150 @@
+use std::collections::VecDeque;
+
+use crate::events::Notice;
+use crate::gui::Rect;
+use crate::text::GLYPH_HEIGHT;
+
+/// Seconds a toast stays up, fades included.
+const TOAST_LIFETIME: f32 = 4.0;
+const FADE_IN: f32 = 0.25;
+const FADE_OUT: f32 = 0.6;
+/// Toasts beyond this many wait in line until one leaves.
+const MAX_VISIBLE: usize = 3;
+/// Sizes in GUI pixels, which are `ToastFeed::scale` screen pixels each.
+const TOAST_WIDTH: f32 = 150.0;
+const TOAST_PADDING: f32 = 6.0;
+const LINE_GAP: f32 = 4.0;
+const TOAST_GAP: f32 = 4.0;
+const SCREEN_MARGIN: f32 = 8.0;
+/// How far a toast slides in from the right while it fades in.
+const SLIDE_DISTANCE: f32 = 24.0;
+
+struct Toast {
+    title: String,
+    detail: Option<String>,
+    /// Seconds since it was first shown; waiting toasts do not age.
+    age: f32,
+}
+
+/// A toast laid out for this frame.
+pub struct PlacedToast {
+    pub rect: Rect,
+    pub title: String,
+    pub detail: Option<String>,
+    /// 0 while invisible, 1 once fully faded in.
+    pub opacity: f32,
+    /// Screen pixels per GUI pixel.
+    pub scale: f32,
+}
+
+impl PlacedToast {
+    /// Top-left corners of the title and detail lines, in screen pixels.
+    pub fn line_origins(&self) -> [[f32; 2]; 2] {
+        let x = self.rect.min[0] + TOAST_PADDING * self.scale;
+        let y = self.rect.min[1] + TOAST_PADDING * self.scale;
+        let advance = (GLYPH_HEIGHT as f32 + LINE_GAP) * self.scale;
+        [[x, y], [x, y + advance]]
+    }
+}
+
+/// Short notifications stacked in the top-right corner ("World saved"),
+/// newest at the bottom.
+///
+/// Each fades and slides in, stays a few seconds and fades out. Only a few
+/// are shown at once; the rest queue up and appear as earlier ones leave.
+pub struct ToastFeed {
+    shown: VecDeque<Toast>,
+    waiting: VecDeque<Toast>,
+    /// Screen pixels per GUI pixel.
+    scale: f32,
+}
+
+impl ToastFeed {
+    pub fn new(scale: f32) -> Self {
+        Self {
+            shown: VecDeque::new(),
+            waiting: VecDeque::new(),
+            scale: scale.max(1.0),
+        }
+    }
+
+    pub fn push(&mut self, title: impl Into<String>, detail: Option<String>) {
+        self.waiting.push_back(Toast {
+            title: title.into(),
+            detail,
+            age: 0.0,
+        });
+        self.promote();
+    }
+
+    pub fn notify(&mut self, notice: Notice) {
+        let (title, detail) = message(notice);
+        self.push(title, detail.map(str::to_string));
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        for toast in &mut self.shown {
+            toast.age += dt;
+        }
+        self.shown.retain(|toast| toast.age < TOAST_LIFETIME);
+        self.promote();
+    }
+
+    fn promote(&mut self) {
+        while self.shown.len() < MAX_VISIBLE
+            && let Some(toast) = self.waiting.pop_front()
+        {
+            self.shown.push_back(toast);
+        }
+    }
+
+    /// The shown toasts, oldest first, stacked down from `top` screen pixels
+    /// at the right edge of a `viewport` sized window.
+    pub fn layout(&self, viewport: [u32; 2], top: f32) -> Vec<PlacedToast> {
+        let scale = self.scale;
+        let line = GLYPH_HEIGHT as f32;
+        let mut y = top + SCREEN_MARGIN * scale;
+        self.shown
+            .iter()
+            .map(|toast| {
+                let lines = if toast.detail.is_some() { 2.0 } else { 1.0 };
+                let height = TOAST_PADDING * 2.0 + lines * line + (lines - 1.0) * LINE_GAP;
+                let fade_in = (toast.age / FADE_IN).min(1.0);
+                let fade_out = ((TOAST_LIFETIME - toast.age) / FADE_OUT).min(1.0);
+                // Eased, so the toast settles into place.
+                let arrival = 1.0 - (1.0 - fade_in).powi(2);
+                let x = viewport[0] as f32
+                    - (SCREEN_MARGIN + TOAST_WIDTH - SLIDE_DISTANCE * (1.0 - arrival)) * scale;
+                let rect = Rect::new(
+                    [x.round(), y.round()],
+                    [TOAST_WIDTH * scale, height * scale],
+                );
+                y += (height + TOAST_GAP) * scale;
+                PlacedToast {
+                    rect,
+                    title: toast.title.clone(),
+                    detail: toast.detail.clone(),
+                    opacity: fade_in.min(fade_out).clamp(0.0, 1.0),
+                    scale,
+                }
+            })
+            .collect()
+    }
+}
+
+fn message(notice: Notice) -> (&'static str, Option<&'static str>) {
+    match notice {
+        Notice::WorldSaved => ("World saved", None),
+        Notice::MaterialsSaved => ("Materials saved", Some("assets/blocks/")),
+        Notice::SettingsSaved => ("Settings saved", Some("config.json")),
+        Notice::RendererSwitched(name) => ("Renderer switched", Some(name)),
+        Notice::GpuTraceRecording => ("Recording GPU trace", Some("F9 to stop and close")),
+        Notice::GpuTraceOff => ("GPU tracing is off", Some("Set gpu_trace.frames")),
+        Notice::ScreenshotSaved => ("Screenshot saved", Some("screenshots/")),
+        Notice::ScreenshotFailed => ("Screenshot failed", Some("See the log")),
+        Notice::ScreenshotUnavailable => (
+            "Screenshots unavailable",
+            Some("The surface cannot be copied"),
+        ),
+    }
+}
@@ -0,0 +1,187 @@
+use std::ops::Range;
+
+use bytemuck::Pod;
+use winit::event::WindowEvent;
+
+use crate::gui::Gui;
+use crate::inventory::Inventory;
+use crate::inventory_screen::InventoryScreen;
+use crate::render;
+use crate::text::OverlayContent;
+use crate::texture::AtlasRegistry;
+use crate::toasts::PlacedToast;
+
+/// Everything the HUD shows this frame. Each layer picks out what it draws.
+pub struct UiFrame<'a> {
+    pub viewport: [u32; 2],
+    pub content: &'a OverlayContent<'a>,
+    pub inventory: &'a Inventory,
+    /// The inventory screen, when open.
+    pub inventory_screen: Option<&'a InventoryScreen>,
+    pub atlases: &'a AtlasRegistry,
+    /// Menu shown over everything else, such as the pause menu.
+    pub gui: Option<&'a Gui>,
+    /// Notifications in the top-right corner, oldest first.
+    pub toasts: &'a [PlacedToast],
+}
+
+/// One 2D element drawn over the world, such as the debug text or the
+/// hotbar.
+pub trait UiLayer {
+    /// Names the layer's draws in GPU captures.
+    fn label(&self) -> &'static str;
+
+    /// Rebuilds the layer's vertices for this frame.
+    fn prepare(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, frame: &UiFrame);
+
+    /// Draws into the pass shared by the whole stack, over the layers below.
+    fn draw<'a>(&'a self, pass: &mut wgpu::RenderPass<'a>);
+
+    /// Offered window events before the game sees them, top layer first.
+    /// Returning `true` keeps the event from the layers below and the game.
+    fn input(&mut self, _event: &WindowEvent) -> bool {
+        false
+    }
+}
+
+/// The HUD's layers, drawn bottom to top in a single render pass.
+#[derive(Default)]
+pub struct UiStack {
+    layers: Vec<Box<dyn UiLayer>>,
+}
+
+impl UiStack {
+    /// Adds `layer` on top of the existing ones.
+    pub fn push(&mut self, layer: impl UiLayer + 'static) {
+        self.layers.push(Box::new(layer));
+    }
+
+    pub fn prepare(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, frame: &UiFrame) {
+        for layer in &mut self.layers {
+            layer.prepare(device, queue, frame);
+        }
+    }
+
+    /// Routes `event` from the top layer down; `true` when a layer took it.
+    pub fn input(&mut self, event: &WindowEvent) -> bool {
+        self.layers.iter_mut().rev().any(|layer| layer.input(event))
+    }
+
+    pub fn render(&self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("UI pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: None,
+        });
+        for layer in &self.layers {
+            render::pass_debug_group(&mut pass, layer.label(), |pass| layer.draw(pass));
+        }
+    }
+}
+
+/// Vertices for a layer's triangle list, rebuilt every frame and uploaded to
+/// a vertex buffer that grows as needed.
+pub struct UiBatch<V> {
+    label: &'static str,
+    vertices: Vec<V>,
+    buffer: wgpu::Buffer,
+    capacity: usize,
+    /// Vertices in `buffer` as of the last upload.
+    uploaded: usize,
+}
+
+impl<V: Pod> UiBatch<V> {
+    const INITIAL_CAPACITY: usize = 256;
+
+    pub fn new(device: &wgpu::Device, label: &'static str) -> Self {
+        Self {
+            label,
+            vertices: Vec::new(),
+            buffer: Self::create_buffer(device, label, Self::INITIAL_CAPACITY),
+            capacity: Self::INITIAL_CAPACITY,
+            uploaded: 0,
+        }
+    }
+
+    fn create_buffer(device: &wgpu::Device, label: &str, capacity: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            size: (capacity * std::mem::size_of::<V>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    /// Drops this frame's vertices and what was uploaded, so nothing draws
+    /// until the next upload.
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+        self.uploaded = 0;
+    }
+
+    /// Adds a quad as two triangles, corners given top-left, top-right,
+    /// bottom-left, bottom-right.
+    pub fn push_quad(&mut self, [top_left, top_right, bottom_left, bottom_right]: [V; 4]) {
+        self.extend([
+            top_left,
+            top_right,
+            bottom_left,
+            bottom_left,
+            top_right,
+            bottom_right,
+        ]);
+    }
+
+    /// Adds vertices already laid out as a triangle list.
+    pub fn extend(&mut self, vertices: impl IntoIterator<Item = V>) {
+        self.vertices.extend(vertices);
+    }
+
+    /// Vertices added so far this frame, where the next ones will start.
+    pub fn vertex_count(&self) -> usize {
+        self.vertices.len()
+    }
+
+    pub fn upload(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        self.uploaded = self.vertices.len();
+        if self.uploaded == 0 {
+            return;
+        }
+        if self.uploaded > self.capacity {
+            self.capacity = self.uploaded.next_power_of_two();
+            self.buffer = Self::create_buffer(device, self.label, self.capacity);
+        }
+        queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&self.vertices));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.uploaded == 0
+    }
+
+    /// Draws the uploaded vertices with whatever pipeline is bound.
+    pub fn draw<'a>(&'a self, pass: &mut wgpu::RenderPass<'a>) {
+        self.draw_range(pass, 0..self.uploaded as u32);
+    }
+
+    /// Draws part of the uploaded vertices, for batches whose runs need
+    /// different bind groups or scissor rects.
+    pub fn draw_range<'a>(&'a self, pass: &mut wgpu::RenderPass<'a>, range: Range<u32>) {
+        if self.is_empty() || range.is_empty() {
+            return;
+        }
+        pass.set_vertex_buffer(0, self.buffer.slice(..));
+        pass.draw(range, 0..1);
+    }
+}
+
+/// Converts a position in window pixels, origin top-left, to clip space.
+pub fn screen_to_ndc([x, y]: [f32; 2], [width, height]: [f32; 2]) -> [f32; 2] {
+    [(x / width) * 2.0 - 1.0, 1.0 - (y / height) * 2.0]
+}
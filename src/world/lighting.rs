@@ -0,0 +1,382 @@
+//! Block light and sky light, stored per cell in each chunk and kept up to
+//! date by flood fills as blocks change and chunks load and unload.
+//!
+//! Block light is what luminous blocks give off: a cell's level is the
+//! brightest of its own block's emission and one less than any neighbour's.
+//! Sky light enters from above: every open cell at the top of the loaded
+//! column is fully lit, as though nothing stood above the loaded chunks, and
+//! full sky light falls straight down without fading while spreading
+//! sideways and upwards one level dimmer per step. Neither kind enters a
+//! cell that `light::blocks_light`.
+//!
+//! Updates follow the usual two-queue scheme: light that may have lost its
+//! source is cleared outwards first, then every cell still lit at the edge
+//! of the cleared area, plus any new source, spreads its light back in.
+
+use std::collections::{HashSet, VecDeque};
+
+use glam::IVec3;
+
+use super::{CHUNK_SIZE, Chunk, World};
+use crate::block::{BlockDefinition, BlockKind};
+use crate::coords::{BlockPos, ChunkPos, FACE_NEIGHBORS, LocalPos};
+use crate::light::{self, MAX_LIGHT};
+
+/// The two kinds of light a chunk stores.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Channel {
+    Block,
+    Sky,
+}
+
+impl Channel {
+    const ALL: [Channel; 2] = [Channel::Block, Channel::Sky];
+
+    /// Level light of `level` reaches the neighbour at `offset` with.
+    fn spread(self, level: u8, offset: IVec3) -> u8 {
+        if self == Channel::Sky && offset == IVec3::NEG_Y && level == MAX_LIGHT {
+            MAX_LIGHT
+        } else {
+            level.saturating_sub(1)
+        }
+    }
+}
+
+/// Pending work of one light update.
+struct LightUpdate {
+    channel: Channel,
+    /// Cleared cells and the level each had, whose dimmer neighbours may
+    /// have been lit through them.
+    removals: VecDeque<(IVec3, u8)>,
+    /// Lit cells to spread light from.
+    additions: VecDeque<IVec3>,
+    /// Chunks whose meshes saw the light change.
+    changed: HashSet<ChunkPos>,
+}
+
+impl LightUpdate {
+    fn new(channel: Channel) -> Self {
+        Self {
+            channel,
+            removals: VecDeque::new(),
+            additions: VecDeque::new(),
+            changed: HashSet::new(),
+        }
+    }
+}
+
+impl Chunk {
+    fn light(&self, channel: Channel) -> &[u8] {
+        match channel {
+            Channel::Block => &self.block_light,
+            Channel::Sky => &self.sky_light,
+        }
+    }
+
+    fn light_mut(&mut self, channel: Channel) -> &mut [u8] {
+        match channel {
+            Channel::Block => &mut self.block_light,
+            Channel::Sky => &mut self.sky_light,
+        }
+    }
+}
+
+impl World {
+    /// Light level (0..=`light::MAX_LIGHT`) luminous blocks give the cell at
+    /// `position`; 0 where no chunk is loaded.
+    pub fn block_light(&self, position: IVec3) -> u8 {
+        self.light_at(Channel::Block, position).unwrap_or(0)
+    }
+
+    /// Light level (0..=`light::MAX_LIGHT`) the sky gives the cell at
+    /// `position`; full where no chunk is loaded, like the open sky above
+    /// the loaded chunks.
+    pub fn sky_light(&self, position: IVec3) -> u8 {
+        self.light_at(Channel::Sky, position).unwrap_or(MAX_LIGHT)
+    }
+
+    /// Lights freshly inserted chunks: their own sources, and the light of
+    /// loaded neighbours reaching in across their faces. Light they carried
+    /// over from elsewhere is dropped first, and loaded chunks below them
+    /// lose the open sky they had above.
+    pub(super) fn light_new_chunks(&mut self, coords: &[ChunkPos]) {
+        for channel in Channel::ALL {
+            self.light_new_chunks_in(channel, coords);
+        }
+    }
+
+    fn light_new_chunks_in(&mut self, channel: Channel, coords: &[ChunkPos]) {
+        let mut update = LightUpdate::new(channel);
+        for &coord in coords {
+            if let Some(chunk) = self.chunks.get_mut(&coord) {
+                chunk.light_mut(channel).fill(0);
+                update.changed.insert(coord);
+            }
+        }
+        // Sky sources depend on what is loaded above, so every chunk goes in
+        // before any is seeded.
+        for &coord in coords {
+            if !self.chunks.contains_key(&coord) {
+                continue;
+            }
+            for local in LocalPos::all() {
+                let position = coord.block(local);
+                let source = self.source(channel, position);
+                if source > 0 {
+                    self.set_light(position, source, &mut update);
+                    update.additions.push_back(position);
+                }
+            }
+        }
+        for &coord in coords {
+            let base = coord.min_block();
+            let last = CHUNK_SIZE as i32 - 1;
+            for a in 0..=last {
+                for b in 0..=last {
+                    if channel == Channel::Sky {
+                        // The cell under this one was open sky until now.
+                        let below = base + IVec3::new(a, -1, b);
+                        if let Some(level) = self.light_at(channel, below)
+                            && level == MAX_LIGHT
+                            && self.source(channel, below) < MAX_LIGHT
+                        {
+                            self.set_light(below, 0, &mut update);
+                            update.removals.push_back((below, level));
+                        }
+                    }
+                    let outside = [
+                        IVec3::new(-1, a, b),
+                        IVec3::new(last + 1, a, b),
+                        IVec3::new(a, -1, b),
+                        IVec3::new(a, last + 1, b),
+                        IVec3::new(a, b, -1),
+                        IVec3::new(a, b, last + 1),
+                    ];
+                    for offset in outside {
+                        let position = base + offset;
+                        if self
+                            .light_at(channel, position)
+                            .is_some_and(|level| level > 1)
+                        {
+                            update.additions.push_back(position);
+                        }
+                    }
+                }
+            }
+        }
+        self.apply_light_update(update);
+    }
+
+    /// Brings the light around `position` up to date after its block changed.
+    pub(super) fn relight_block(&mut self, position: IVec3) {
+        for channel in Channel::ALL {
+            let Some(level) = self.light_at(channel, position) else {
+                return;
+            };
+            let mut update = LightUpdate::new(channel);
+            let source = self.source(channel, position);
+            self.set_light(position, source, &mut update);
+            update.removals.push_back((position, level));
+            if source > 0 {
+                update.additions.push_back(position);
+            }
+            // A cell opened up takes light from its neighbours.
+            for offset in FACE_NEIGHBORS {
+                update.additions.push_back(position + offset);
+            }
+            self.apply_light_update(update);
+        }
+    }
+
+    /// Takes the luminance of `definitions`, indexed by block id, in place of
+    /// what blocks gave off so far, and lights every loaded chunk afresh
+    /// with it when any block's emission changed.
+    pub fn update_emission(&mut self, definitions: &[BlockDefinition]) {
+        let emission: Vec<u8> = definitions.iter().map(light::emission).collect();
+        if emission.is_empty() || emission == self.emission {
+            return;
+        }
+        self.emission = emission;
+        let coords: Vec<ChunkPos> = self.chunks.keys().copied().collect();
+        self.light_new_chunks_in(Channel::Block, &coords);
+        self.bump_version();
+    }
+
+    /// Clears the light `coords` spread into the chunks that stay loaded,
+    /// removes the chunks with `remove`, then lets the remaining light
+    /// refill what was cleared, with open sky again above the chunks that
+    /// were under them.
+    pub(super) fn unlight_chunks(&mut self, coords: &[ChunkPos], remove: impl FnOnce(&mut Self)) {
+        let mut updates = Channel::ALL.map(LightUpdate::new);
+        for update in &mut updates {
+            for &coord in coords {
+                let Some(chunk) = self.chunks.get_mut(&coord) else {
+                    continue;
+                };
+                for (index, level) in chunk.light_mut(update.channel).iter_mut().enumerate() {
+                    if *level > 0 {
+                        let position = coord.block(LocalPos::from_index(index));
+                        update.removals.push_back((position, *level));
+                        *level = 0;
+                    }
+                }
+            }
+            self.propagate_removals(update);
+        }
+        remove(self);
+        for update in &mut updates {
+            if update.channel == Channel::Sky {
+                for &coord in coords {
+                    let base = coord.min_block();
+                    for a in 0..CHUNK_SIZE as i32 {
+                        for b in 0..CHUNK_SIZE as i32 {
+                            let below = base + IVec3::new(a, -1, b);
+                            let source = self.source(Channel::Sky, below);
+                            if self
+                                .light_at(Channel::Sky, below)
+                                .is_some_and(|level| level < source)
+                            {
+                                self.set_light(below, source, update);
+                                update.additions.push_back(below);
+                            }
+                        }
+                    }
+                }
+            }
+            self.propagate_additions(update);
+            self.bump_light_revisions(update);
+        }
+    }
+
+    fn apply_light_update(&mut self, mut update: LightUpdate) {
+        self.propagate_removals(&mut update);
+        self.propagate_additions(&mut update);
+        self.bump_light_revisions(&update);
+    }
+
+    /// Clears every neighbour a removed cell's light could have reached, and
+    /// queues the brighter ones to spread light back. Cleared cells keep the
+    /// light of their own source.
+    fn propagate_removals(&mut self, update: &mut LightUpdate) {
+        let channel = update.channel;
+        while let Some((position, level)) = update.removals.pop_front() {
+            for offset in FACE_NEIGHBORS {
+                let neighbor = position + offset;
+                let Some(neighbor_level) = self.light_at(channel, neighbor) else {
+                    continue;
+                };
+                let reached = channel.spread(level, offset);
+                if neighbor_level > 0 && neighbor_level <= reached {
+                    let source = self.source(channel, neighbor);
+                    self.set_light(neighbor, source, update);
+                    update.removals.push_back((neighbor, neighbor_level));
+                    if source > 0 {
+                        update.additions.push_back(neighbor);
+                    }
+                } else if neighbor_level > reached {
+                    update.additions.push_back(neighbor);
+                }
+            }
+        }
+    }
+
+    /// Spreads light outwards from the queued cells into every loaded cell
+    /// that lets it in and is darker than it would make it.
+    fn propagate_additions(&mut self, update: &mut LightUpdate) {
+        let channel = update.channel;
+        while let Some(position) = update.additions.pop_front() {
+            let level = self.light_at(channel, position).unwrap_or(0);
+            if level <= 1 {
+                continue;
+            }
+            for offset in FACE_NEIGHBORS {
+                let neighbor = position + offset;
+                let Some(neighbor_level) = self.light_at(channel, neighbor) else {
+                    continue;
+                };
+                let reached = channel.spread(level, offset);
+                if neighbor_level < reached && !light::blocks_light(self.kind_at(neighbor)) {
+                    self.set_light(neighbor, reached, update);
+                    update.additions.push_back(neighbor);
+                }
+            }
+        }
+    }
+
+    fn bump_light_revisions(&mut self, update: &LightUpdate) {
+        for coord in &update.changed {
+            if let Some(chunk) = self.chunks.get_mut(coord) {
+                chunk.revision += 1;
+            }
+        }
+    }
+
+    /// Light a cell gives itself, whatever its neighbours do: a block's
+    /// emission, or full sky light for an open cell with nothing loaded
+    /// above it.
+    fn source(&self, channel: Channel, position: IVec3) -> u8 {
+        let kind = self.kind_at(position);
+        match channel {
+            Channel::Block => self.emission[kind.id() as usize],
+            Channel::Sky => {
+                let open = !light::blocks_light(kind)
+                    && self.light_at(channel, position + IVec3::Y).is_none();
+                if open { MAX_LIGHT } else { 0 }
+            }
+        }
+    }
+
+    fn light_at(&self, channel: Channel, position: IVec3) -> Option<u8> {
+        let (coord, local) = BlockPos(position).split();
+        self.chunks
+            .get(&coord)
+            .map(|chunk| chunk.light(channel)[local.index()])
+    }
+
+    /// Sets a loaded cell's light, noting its chunk as changed, and the chunk
+    /// across any face it touches, whose faces may look into it.
+    fn set_light(&mut self, position: IVec3, level: u8, update: &mut LightUpdate) {
+        let (coord, local) = BlockPos(position).split();
+        let Some(chunk) = self.chunks.get_mut(&coord) else {
+            return;
+        };
+        chunk.light_mut(update.channel)[local.index()] = level;
+        update.changed.insert(coord);
+        for offset in FACE_NEIGHBORS {
+            let across = BlockPos(position + offset).chunk();
+            if across != coord {
+                update.changed.insert(across);
+            }
+        }
+    }
+
+    fn kind_at(&self, position: IVec3) -> BlockKind {
+        BlockKind::from_id(self.block_at(position.x, position.y, position.z))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changed_luminance_relights_the_loaded_chunks() {
+        let lamp = IVec3::new(3, 5, 3);
+        let mut world = World::from_blocks([(lamp, BlockKind::Lamp.id())]);
+        let mut definitions: Vec<BlockDefinition> = BlockKind::ALL
+            .iter()
+            .map(|kind| *kind.definition())
+            .collect();
+        let version = world.version();
+
+        definitions[BlockKind::Lamp.id() as usize].luminance = 0.4;
+        world.update_emission(&definitions);
+        assert_eq!(world.block_light(lamp), 6);
+        assert_eq!(world.block_light(lamp + IVec3::X), 5);
+        assert_ne!(world.version(), version);
+
+        definitions[BlockKind::Lamp.id() as usize].luminance = 0.0;
+        world.update_emission(&definitions);
+        assert_eq!(world.block_light(lamp + IVec3::X), 0);
+    }
+}
@@ -0,0 +1,752 @@
+use std::{
+    collections::{HashMap, HashSet, hash_map::Entry},
+    time::{Duration, Instant},
+};
+
+use glam::IVec3;
+
+use crate::block::{BLOCK_AIR, BlockDefinition, BlockId, BlockKind, FaceDirection};
+use crate::coords::{BlockPos, ChunkPos, LocalPos, chunk_layers};
+use crate::decoration;
+use crate::light::{self, MAX_LIGHT};
+use crate::terrain::TerrainGenerator;
+
+mod lighting;
+mod storage;
+mod structures;
+
+pub use storage::RegionStorage;
+use structures::StructureQueue;
+
+pub const CHUNK_SIZE: usize = 16;
+const CHUNK_VOLUME: usize = CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE;
+
+pub struct Chunk {
+    blocks: Vec<BlockId>,
+    /// Light from luminous blocks and from the sky per cell; see `lighting`.
+    block_light: Vec<u8>,
+    sky_light: Vec<u8>,
+    visible_mask: Vec<bool>,
+    revision: u64,
+}
+
+impl Chunk {
+    pub fn new() -> Self {
+        Self::from_blocks(vec![BLOCK_AIR; CHUNK_VOLUME])
+    }
+
+    fn from_blocks(blocks: Vec<BlockId>) -> Self {
+        debug_assert_eq!(blocks.len(), CHUNK_VOLUME);
+        Self {
+            blocks,
+            block_light: vec![0; CHUNK_VOLUME],
+            sky_light: vec![0; CHUNK_VOLUME],
+            visible_mask: vec![false; CHUNK_VOLUME],
+            revision: 0,
+        }
+    }
+
+    pub fn set(&mut self, local: LocalPos, block: BlockId) {
+        self.blocks[local.index()] = block;
+    }
+
+    pub fn get(&self, local: LocalPos) -> BlockId {
+        self.blocks[local.index()]
+    }
+
+    pub fn blocks(&self) -> &[BlockId] {
+        &self.blocks
+    }
+
+    /// Block light level per cell, in the same order as `blocks`.
+    pub fn block_light(&self) -> &[u8] {
+        &self.block_light
+    }
+
+    /// Sky light level per cell, in the same order as `blocks`.
+    pub fn sky_light(&self) -> &[u8] {
+        &self.sky_light
+    }
+
+    pub fn visible_mask(&self) -> &[bool] {
+        &self.visible_mask
+    }
+
+    /// Changes whenever the chunk's blocks or a face neighbour's do, so
+    /// anything derived from its blocks and borders, like its mesh, is stale.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    pub fn set_visible_mask(&mut self, mask: Vec<bool>) {
+        debug_assert_eq!(mask.len(), CHUNK_VOLUME);
+        self.visible_mask = mask;
+    }
+
+    /// The cell at `local` as a `BlockRef`.
+    fn block_ref(&self, local: LocalPos) -> BlockRef {
+        let index = local.index();
+        BlockRef::new(
+            self.blocks[index],
+            self.sky_light[index],
+            self.block_light[index],
+        )
+    }
+}
+
+/// One cell of the world with what callers of `World::block_at` usually look
+/// up next.
+#[derive(Clone, Copy)]
+pub struct BlockRef {
+    pub id: BlockId,
+    /// The block and its state (snow depth, crop stage, facing, ...).
+    pub kind: BlockKind,
+    pub definition: &'static BlockDefinition,
+    /// Light levels of the cell, as `World::sky_light` and
+    /// `World::block_light` give them.
+    pub sky_light: u8,
+    pub block_light: u8,
+}
+
+impl BlockRef {
+    /// A cell of no loaded chunk: air, open to the sky.
+    fn unloaded() -> Self {
+        Self::new(BLOCK_AIR, MAX_LIGHT, 0)
+    }
+
+    fn new(id: BlockId, sky_light: u8, block_light: u8) -> Self {
+        let kind = BlockKind::from_id(id);
+        Self {
+            id,
+            kind,
+            definition: kind.definition(),
+            sky_light,
+            block_light,
+        }
+    }
+}
+
+/// A box of cells copied out of the world by `World::sample_box`, for code
+/// that looks at many neighbouring cells and would otherwise look up their
+/// chunk for each. Reusing one keeps its buffer.
+pub struct BlockSample {
+    min: IVec3,
+    size: IVec3,
+    cells: Vec<BlockRef>,
+}
+
+impl BlockSample {
+    pub fn new() -> Self {
+        Self {
+            min: IVec3::ZERO,
+            size: IVec3::ZERO,
+            cells: Vec::new(),
+        }
+    }
+
+    /// The sampled cell at world `position`, which must lie in the box.
+    pub fn get(&self, position: IVec3) -> BlockRef {
+        let offset = position - self.min;
+        assert!(
+            offset.cmpge(IVec3::ZERO).all() && offset.cmplt(self.size).all(),
+            "{position} is outside the sampled box"
+        );
+        self.cells[Self::index(self.size, offset)]
+    }
+
+    /// Every sampled cell with its world position.
+    pub fn iter(&self) -> impl Iterator<Item = (IVec3, BlockRef)> + '_ {
+        let size = self.size;
+        self.cells.iter().enumerate().map(move |(index, cell)| {
+            let index = index as i32;
+            let offset = IVec3::new(
+                index % size.x,
+                index / (size.x * size.z),
+                index / size.x % size.z,
+            );
+            (self.min + offset, *cell)
+        })
+    }
+
+    /// Cells are stored x fastest, then z, then y, like a chunk's own.
+    fn index(size: IVec3, offset: IVec3) -> usize {
+        (offset.x + size.x * (offset.z + size.z * offset.y)) as usize
+    }
+}
+
+pub struct World {
+    chunks: HashMap<ChunkPos, Chunk>,
+    version: u64,
+    terrain: TerrainGenerator,
+    storage: RegionStorage,
+    /// Loaded chunks edited since they were generated or last stored.
+    dirty: HashSet<ChunkPos>,
+    /// Lowest and highest chunk layer (chunk y) of the loaded chunks, where
+    /// column queries start and stop; `None` while nothing is loaded.
+    layers: Option<(i32, i32)>,
+    structures: StructureQueue,
+    /// Light level each block id gives off; see [`World::update_emission`].
+    emission: Vec<u8>,
+}
+
+impl World {
+    /// A world whose chunks come from `storage` when saved there, and from
+    /// `terrain` otherwise.
+    pub fn new(terrain: TerrainGenerator, storage: RegionStorage) -> Self {
+        Self {
+            chunks: HashMap::new(),
+            version: 0,
+            terrain,
+            storage,
+            dirty: HashSet::new(),
+            layers: None,
+            structures: StructureQueue::default(),
+            emission: BlockKind::ALL
+                .iter()
+                .map(|kind| light::emission(kind.definition()))
+                .collect(),
+        }
+    }
+
+    pub fn terrain(&self) -> TerrainGenerator {
+        self.terrain
+    }
+
+    /// A handle to the world's saved chunks, sharing its cache.
+    pub fn storage(&self) -> RegionStorage {
+        self.storage.clone()
+    }
+
+    pub fn ensure_chunk(&mut self, coord: ChunkPos) {
+        let mut inserted_metrics: Option<(f32, usize)> = None;
+        match self.chunks.entry(coord) {
+            Entry::Occupied(_) => {}
+            Entry::Vacant(vacant) => {
+                let start = Instant::now();
+                let chunk = self.storage.load_chunk(coord).unwrap_or_else(|| {
+                    generate_chunk(
+                        coord,
+                        self.terrain,
+                        &mut self.structures,
+                        &mut GenerationTimings::default(),
+                    )
+                });
+                let generation_ms = start.elapsed().as_secs_f32() * 1000.0;
+                let solid_blocks = chunk
+                    .blocks()
+                    .iter()
+                    .filter(|&&block| block != BLOCK_AIR)
+                    .count();
+                vacant.insert(chunk);
+                self.layers = Some(widen_layers(self.layers, coord.y));
+                inserted_metrics = Some((generation_ms, solid_blocks));
+            }
+        }
+
+        if let Some((generation_ms, solid_blocks)) = inserted_metrics {
+            let visibility_start = Instant::now();
+            self.recompute_visibility_around(coord);
+            let visibility_ms = visibility_start.elapsed().as_secs_f32() * 1000.0;
+            self.light_new_chunks(&[coord]);
+
+            let visible_blocks = self
+                .chunks
+                .get(&coord)
+                .map(|chunk| chunk.visible_mask().iter().filter(|&&v| v).count())
+                .unwrap_or(0);
+
+            log::info!(
+                "Loaded chunk {:?}: gen {:.2} ms, vis {:.2} ms, solid {}, visible {}",
+                coord,
+                generation_ms,
+                visibility_ms,
+                solid_blocks,
+                visible_blocks
+            );
+            self.bump_version();
+        }
+    }
+
+    pub fn chunk(&self, coord: ChunkPos) -> Option<&Chunk> {
+        self.chunks.get(&coord)
+    }
+
+    pub fn block_at(&self, world_x: i32, world_y: i32, world_z: i32) -> BlockId {
+        let (coord, local) = BlockPos::new(world_x, world_y, world_z).split();
+        self.chunk(coord)
+            .map(|chunk| chunk.get(local))
+            .unwrap_or(BLOCK_AIR)
+    }
+
+    /// The block at `position` with its kind, definition and light.
+    pub fn block_info(&self, position: IVec3) -> BlockRef {
+        let (coord, local) = BlockPos(position).split();
+        self.chunk(coord)
+            .map_or(BlockRef::unloaded(), |chunk| chunk.block_ref(local))
+    }
+
+    /// Loaded chunks overlapping the box of cells from `min` to `max`
+    /// inclusive, with their coordinates.
+    pub fn iter_chunks_in_box(
+        &self,
+        min: IVec3,
+        max: IVec3,
+    ) -> impl Iterator<Item = (ChunkPos, &Chunk)> + '_ {
+        let first = BlockPos(min).chunk();
+        let last = BlockPos(max).chunk();
+        (first.y..=last.y).flat_map(move |y| {
+            (first.z..=last.z).flat_map(move |z| {
+                (first.x..=last.x).filter_map(move |x| {
+                    let coord = ChunkPos::new(x, y, z);
+                    self.chunk(coord).map(|chunk| (coord, chunk))
+                })
+            })
+        })
+    }
+
+    /// Calls `visit` with every loaded cell from `min` to `max` inclusive and
+    /// its world position, chunk by chunk; cells of unloaded chunks are
+    /// skipped.
+    pub fn for_each_block_in_region(
+        &self,
+        min: IVec3,
+        max: IVec3,
+        mut visit: impl FnMut(IVec3, BlockRef),
+    ) {
+        for (coord, chunk) in self.iter_chunks_in_box(min, max) {
+            let chunk_min = coord.min_block();
+            let low = min.max(chunk_min);
+            let high = max.min(chunk_min + IVec3::splat(CHUNK_SIZE as i32 - 1));
+            for y in low.y..=high.y {
+                for z in low.z..=high.z {
+                    for x in low.x..=high.x {
+                        let position = IVec3::new(x, y, z);
+                        visit(position, chunk.block_ref(BlockPos(position).local()));
+                    }
+                }
+            }
+        }
+    }
+
+    /// The highest loaded block of column (`x`, `z`) that is not air, with
+    /// its position.
+    // Nothing calls this until the minimap and structure placement exist.
+    #[allow(dead_code)]
+    pub fn highest_block_at(&self, x: i32, z: i32) -> Option<(IVec3, BlockRef)> {
+        let (lowest, highest) = self.layers?;
+        let size = CHUNK_SIZE as i32;
+        (lowest..=highest).rev().find_map(|layer| {
+            let (coord, top) = BlockPos::new(x, layer * size + size - 1, z).split();
+            let chunk = self.chunk(coord)?;
+            (0..CHUNK_SIZE).rev().find_map(|y| {
+                let local = LocalPos::new(top.x(), y, top.z());
+                (chunk.get(local) != BLOCK_AIR)
+                    .then(|| (coord.block(local), chunk.block_ref(local)))
+            })
+        })
+    }
+
+    /// Copies the cells from `min` to `max` inclusive into `sample`, one
+    /// chunk lookup per chunk the box touches; cells of unloaded chunks read
+    /// as `block_info` gives them.
+    pub fn sample_box(&self, min: IVec3, max: IVec3, sample: &mut BlockSample) {
+        sample.min = min;
+        sample.size = (max - min + IVec3::ONE).max(IVec3::ZERO);
+        sample.cells.clear();
+        sample
+            .cells
+            .resize(sample.size.element_product() as usize, BlockRef::unloaded());
+        if sample.cells.is_empty() {
+            return;
+        }
+
+        let size = sample.size;
+        let cells = &mut sample.cells;
+        self.for_each_block_in_region(min, max, |position, block| {
+            cells[BlockSample::index(size, position - min)] = block;
+        });
+    }
+
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn iter_chunks(&self) -> impl Iterator<Item = (&ChunkPos, &Chunk)> {
+        self.chunks.iter()
+    }
+
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Writes every edited chunk to the region files, as on exit. Returns
+    /// whether they were all written.
+    pub fn save_dirty(&mut self) -> bool {
+        let chunks = self
+            .dirty
+            .iter()
+            .filter_map(|&coord| self.chunks.get(&coord).map(|chunk| (coord, chunk)));
+        match self.storage.store_chunks(chunks) {
+            Ok(()) => {
+                self.dirty.clear();
+                true
+            }
+            Err(err) => {
+                log::warn!("Failed to save edited chunks: {}", err);
+                false
+            }
+        }
+    }
+
+    /// Moves every chunk of `region` that is not loaded here into this world.
+    ///
+    /// The region's visibility masks assumed plain generated terrain past its
+    /// edges, so only chunks touching one that was already loaded here are
+    /// recomputed.
+    pub fn absorb(&mut self, region: World) {
+        let mut inserted = Vec::new();
+        for (coord, chunk) in region.chunks {
+            if let Entry::Vacant(vacant) = self.chunks.entry(coord) {
+                vacant.insert(chunk);
+                self.layers = Some(widen_layers(self.layers, coord.y));
+                inserted.push(coord);
+            }
+        }
+        if inserted.is_empty() {
+            return;
+        }
+        self.light_new_chunks(&inserted);
+        let fresh: HashSet<ChunkPos> = inserted.iter().copied().collect();
+        for coord in inserted {
+            let seam = chunk_coords_in_radius(coord, 1, 1).any(|neighbor| {
+                neighbor != coord
+                    && !fresh.contains(&neighbor)
+                    && self.chunks.contains_key(&neighbor)
+            });
+            if seam {
+                self.recompute_visibility_around(coord);
+            }
+        }
+        self.bump_version();
+    }
+
+    /// A world of air but for `blocks`, for tests that need exact contents.
+    /// Its chunks are left unlit and with nothing marked visible.
+    #[cfg(test)]
+    pub fn from_blocks(blocks: impl IntoIterator<Item = (IVec3, BlockId)>) -> Self {
+        use crate::terrain::TerrainPreset;
+
+        let terrain = TerrainGenerator::new(TerrainPreset::Default, 0);
+        let mut world = Self::new(terrain, RegionStorage::new(Default::default()));
+        for (position, block) in blocks {
+            let (coord, local) = BlockPos(position).split();
+            world
+                .chunks
+                .entry(coord)
+                .or_insert_with(Chunk::new)
+                .set(local, block);
+            world.layers = Some(widen_layers(world.layers, coord.y));
+        }
+        world
+    }
+
+    /// Unloads every chunk outside `radius` columns of `center` or outside
+    /// the [`loaded_layers`] for `vertical_radius`.
+    ///
+    /// Edited chunks are saved first; if that fails they stay loaded and
+    /// dirty so the next unload or save tries again.
+    pub fn unload_chunks_outside(&mut self, center: ChunkPos, radius: i32, vertical_radius: i32) {
+        let layers = loaded_layers(self.terrain, center.y, vertical_radius);
+        let mut leaving: Vec<ChunkPos> = self
+            .chunks
+            .keys()
+            .copied()
+            .filter(|coord| {
+                let dx = (coord.x - center.x).abs();
+                let dz = (coord.z - center.z).abs();
+                dx > radius || dz > radius || !layers.contains(&coord.y)
+            })
+            .collect();
+        let edited = leaving
+            .iter()
+            .filter(|coord| self.dirty.contains(coord))
+            .filter_map(|&coord| self.chunks.get(&coord).map(|chunk| (coord, chunk)));
+        if let Err(err) = self.storage.store_chunks(edited) {
+            log::warn!(
+                "Failed to save unloaded chunks, keeping them loaded: {}",
+                err
+            );
+            leaving.retain(|coord| !self.dirty.contains(coord));
+        }
+        let changed = !leaving.is_empty();
+        self.unlight_chunks(&leaving, |world| {
+            for &coord in &leaving {
+                if world.chunks.remove(&coord).is_some() {
+                    world.dirty.remove(&coord);
+                    world.recompute_visibility_around(coord);
+                }
+                world.structures.forget(coord);
+            }
+        });
+        self.structures.forget_outside(center, radius);
+        self.layers = self
+            .chunks
+            .keys()
+            .fold(None, |layers, coord| Some(widen_layers(layers, coord.y)));
+        if changed {
+            self.bump_version();
+        }
+    }
+
+    pub fn set_block(&mut self, world_pos: IVec3, block: BlockId) -> bool {
+        let (chunk_coord, local) = BlockPos(world_pos).split();
+        {
+            let Some(chunk) = self.chunks.get_mut(&chunk_coord) else {
+                return false;
+            };
+            let current = chunk.get(local);
+            if current == block {
+                return false;
+            }
+            chunk.set(local, block);
+        }
+        self.dirty.insert(chunk_coord);
+        self.recompute_visibility_around(chunk_coord);
+        self.relight_block(world_pos);
+        self.bump_version();
+        true
+    }
+
+    fn recompute_visibility_around(&mut self, center: ChunkPos) {
+        let neighbors = FaceDirection::ALL.map(|face| center.neighbor(face));
+        for neighbor_coord in [center].into_iter().chain(neighbors) {
+            if self.chunks.contains_key(&neighbor_coord)
+                && let Some(mask) = self.compute_visibility_mask(neighbor_coord)
+                && let Some(chunk) = self.chunks.get_mut(&neighbor_coord)
+            {
+                // Every change that can affect a chunk's faces passes here.
+                chunk.set_visible_mask(mask);
+                chunk.revision += 1;
+            }
+        }
+    }
+
+    /// Which solid cells of the chunk at `coord` have a face that is not
+    /// against a full cube. Across unloaded neighbours the generated terrain
+    /// stands in for them.
+    fn compute_visibility_mask(&self, coord: ChunkPos) -> Option<Vec<bool>> {
+        let chunk = self.chunk(coord)?;
+        let neighbor_blocks =
+            FaceDirection::ALL.map(|face| self.chunk(coord.neighbor(face)).map(Chunk::blocks));
+
+        let mut mask = vec![false; CHUNK_VOLUME];
+        for local in LocalPos::all() {
+            if !BlockKind::from_id(chunk.get(local)).is_solid() {
+                continue;
+            }
+            let position = BlockPos(coord.block(local));
+            mask[local.index()] = FaceDirection::ALL.into_iter().any(|face| {
+                let neighbor = position.neighbor(face);
+                let (neighbor_coord, neighbor_local) = neighbor.split();
+                let blocks = if neighbor_coord == coord {
+                    Some(chunk.blocks())
+                } else {
+                    neighbor_blocks[face.index()]
+                };
+                !self.is_occluding_neighbor(blocks, neighbor_local, neighbor.0)
+            });
+        }
+
+        Some(mask)
+    }
+
+    fn is_occluding_neighbor(
+        &self,
+        neighbor: Option<&[BlockId]>,
+        local: LocalPos,
+        fallback_world: IVec3,
+    ) -> bool {
+        if let Some(blocks) = neighbor {
+            BlockKind::from_id(blocks[local.index()]).is_full_cube()
+        } else {
+            BlockKind::from_id(self.terrain.block_at(
+                fallback_world.x,
+                fallback_world.y,
+                fallback_world.z,
+            ))
+            .is_full_cube()
+        }
+    }
+}
+
+/// Every chunk coordinate in the box around `center`, top layer first, so
+/// chunks loaded in this order take sky light from the layer above them
+/// rather than darkening the one below.
+pub fn chunk_coords_in_radius(
+    center: ChunkPos,
+    radius: i32,
+    vertical_radius: i32,
+) -> impl Iterator<Item = ChunkPos> {
+    let layers = (center.y - vertical_radius..=center.y + vertical_radius).rev();
+    chunk_coords_in_layers(center, radius, layers.collect())
+}
+
+/// Every chunk coordinate within `radius` columns of `center` in each of
+/// `layers`, in the order given.
+pub fn chunk_coords_in_layers(
+    center: ChunkPos,
+    radius: i32,
+    layers: Vec<i32>,
+) -> impl Iterator<Item = ChunkPos> {
+    layers.into_iter().flat_map(move |y| {
+        (-radius..=radius).flat_map(move |dz| {
+            (-radius..=radius).map(move |dx| ChunkPos::new(center.x + dx, y, center.z + dz))
+        })
+    })
+}
+
+/// Chunk layers kept loaded around a camera in layer `center_y`, top first:
+/// those within `vertical_radius` of it, and every layer `terrain` can
+/// generate blocks in, so ground taller than the camera's band is never
+/// sliced off at its edge.
+pub fn loaded_layers(terrain: TerrainGenerator, center_y: i32, vertical_radius: i32) -> Vec<i32> {
+    let ground = chunk_layers(terrain.height_range());
+    let mut layers: Vec<i32> = (center_y - vertical_radius..=center_y + vertical_radius)
+        .chain(ground)
+        .collect();
+    layers.sort_unstable_by(|a, b| b.cmp(a));
+    layers.dedup();
+    layers
+}
+
+/// Every chunk coordinate within `radius` columns of `center` in its
+/// [`loaded_layers`], top layer first.
+pub fn chunk_coords_loaded_around(
+    terrain: TerrainGenerator,
+    center: ChunkPos,
+    radius: i32,
+    vertical_radius: i32,
+) -> impl Iterator<Item = ChunkPos> {
+    chunk_coords_in_layers(
+        center,
+        radius,
+        loaded_layers(terrain, center.y, vertical_radius),
+    )
+}
+
+impl World {
+    /// Loads or generates every chunk in [`chunk_coords_loaded_around`]
+    /// `center` that is missing.
+    pub fn ensure_chunks_in_radius(&mut self, center: ChunkPos, radius: i32, vertical_radius: i32) {
+        for coord in chunk_coords_loaded_around(self.terrain, center, radius, vertical_radius) {
+            self.ensure_chunk(coord);
+        }
+    }
+
+    fn bump_version(&mut self) {
+        self.version = self.version.wrapping_add(1);
+    }
+}
+
+/// Time spent in each stage of generating chunks, summed over the chunks.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GenerationTimings {
+    /// Ground blocks, before caves.
+    pub terrain: Duration,
+    /// Caves carved out of the ground, and the ore lining them.
+    pub caves: Duration,
+    /// Features, trees and the lamp at the origin.
+    pub decoration: Duration,
+}
+
+/// Generates chunks outside a [`World`], timing each stage; for profiling
+/// the generators.
+#[allow(dead_code)]
+pub struct ChunkGenerator {
+    terrain: TerrainGenerator,
+    structures: StructureQueue,
+    pub timings: GenerationTimings,
+}
+
+#[allow(dead_code)]
+impl ChunkGenerator {
+    pub fn new(terrain: TerrainGenerator) -> Self {
+        Self {
+            terrain,
+            structures: StructureQueue::default(),
+            timings: GenerationTimings::default(),
+        }
+    }
+
+    pub fn generate(&mut self, coord: ChunkPos) -> Chunk {
+        generate_chunk(coord, self.terrain, &mut self.structures, &mut self.timings)
+    }
+}
+
+fn generate_chunk(
+    coord: ChunkPos,
+    terrain: TerrainGenerator,
+    structures: &mut StructureQueue,
+    timings: &mut GenerationTimings,
+) -> Chunk {
+    let started = Instant::now();
+    // Both stages need each column's surface, so it is sampled once.
+    let origin = coord.min_block();
+    let columns: [Option<i32>; CHUNK_SIZE * CHUNK_SIZE] = std::array::from_fn(|index| {
+        let (x, z) = (index % CHUNK_SIZE, index / CHUNK_SIZE);
+        terrain.column_height(origin.x + x as i32, origin.z + z as i32)
+    });
+    let column = |local: LocalPos| columns[local.z() * CHUNK_SIZE + local.x()];
+
+    let mut chunk = Chunk::new();
+    for local in LocalPos::all() {
+        let position = coord.block(local);
+        let block = terrain.ground_block_at(position.x, position.y, position.z, column(local));
+        if block != BLOCK_AIR {
+            chunk.set(local, block);
+        }
+    }
+    let ground_done = Instant::now();
+    timings.terrain += ground_done - started;
+
+    for local in LocalPos::all() {
+        let ground = chunk.get(local);
+        if ground == BLOCK_AIR {
+            continue;
+        }
+        let block = terrain.carve_caves(coord.block(local), column(local), ground);
+        if block != ground {
+            chunk.set(local, block);
+        }
+    }
+    let caves_done = Instant::now();
+    timings.caves += caves_done - ground_done;
+
+    for (position, kind) in decoration::chunk_decorations(terrain, coord) {
+        chunk.set(BlockPos(position).local(), kind.id());
+    }
+    structures.apply(terrain, coord, &mut chunk);
+
+    if coord == ChunkPos::new(0, 0, 0) {
+        let middle = coord.min_block() + IVec3::splat(CHUNK_SIZE as i32 / 2);
+        if let Some(height) = terrain.surface_height(middle.x, middle.z) {
+            // A lamp on the ground with glass and metal stacked on it, as far
+            // as they fit in the chunk.
+            let kinds = [BlockKind::Lamp, BlockKind::Glass, BlockKind::Metal];
+            for (offset, kind) in (1..).zip(kinds) {
+                let (block_coord, local) = BlockPos(middle.with_y(height + offset)).split();
+                if block_coord == coord {
+                    chunk.set(local, kind.id());
+                }
+            }
+        }
+    }
+    timings.decoration += caves_done.elapsed();
+
+    chunk
+}
+
+/// `layers` grown to include chunk layer `y`.
+fn widen_layers(layers: Option<(i32, i32)>, y: i32) -> (i32, i32) {
+    layers.map_or((y, y), |(lowest, highest)| (lowest.min(y), highest.max(y)))
+}
@@ -0,0 +1,532 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::hash::Hash;
+use std::io;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use log::warn;
+use memmap2::Mmap;
+
+use crate::block::BlockId;
+use crate::coords::ChunkPos;
+use crate::world::{CHUNK_VOLUME, Chunk};
+
+/// Chunks along x and z in one region file; each file holds a single chunk layer.
+const REGION_SIZE: i32 = 32;
+const REGION_CHUNKS: usize = (REGION_SIZE * REGION_SIZE) as usize;
+const MAGIC: &[u8; 4] = b"RCRG";
+const VERSION: u32 = 1;
+/// Magic, version, then an `(offset, length)` pair per chunk slot.
+const HEADER_SIZE: usize = 8 + REGION_CHUNKS * 8;
+
+/// Region files kept mapped at once; older mappings are dropped first.
+const MAPPED_REGIONS: usize = 64;
+/// Decoded chunks kept in memory, about 4 KiB each.
+const DECODED_CHUNKS: usize = 1024;
+
+/// Edited chunks on disk, grouped into region files under `saves/<world>/regions/`.
+///
+/// A region file starts with a table locating each chunk's payload, and
+/// payloads are run-length encoded block ids, which keeps mostly-air or
+/// mostly-stone chunks to a few bytes. Storing rewrites the whole region;
+/// regions are small and only change when edited chunks are unloaded or the
+/// game exits.
+///
+/// Region files are memory-mapped rather than read, so looking up a chunk
+/// only touches the pages holding its table entry and payload, and payloads
+/// are decoded when their chunk is loaded. Recently decoded chunks stay in
+/// an LRU cache, so walking back into an area skips the decoding entirely.
+/// Clones share the mappings and the cache.
+#[derive(Clone)]
+pub struct RegionStorage {
+    dir: PathBuf,
+    cache: Arc<Mutex<RegionCache>>,
+}
+
+struct RegionCache {
+    regions: Lru<RegionCoord, Arc<Mmap>>,
+    chunks: Lru<ChunkPos, Arc<[BlockId]>>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct RegionCoord {
+    x: i32,
+    y: i32,
+    z: i32,
+}
+
+impl RegionCoord {
+    fn of(coord: ChunkPos) -> (Self, usize) {
+        let region = Self {
+            x: coord.x.div_euclid(REGION_SIZE),
+            y: coord.y,
+            z: coord.z.div_euclid(REGION_SIZE),
+        };
+        let slot = coord.x.rem_euclid(REGION_SIZE) + coord.z.rem_euclid(REGION_SIZE) * REGION_SIZE;
+        (region, slot as usize)
+    }
+}
+
+impl RegionStorage {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            cache: Arc::new(Mutex::new(RegionCache {
+                regions: Lru::new(MAPPED_REGIONS),
+                chunks: Lru::new(DECODED_CHUNKS),
+            })),
+        }
+    }
+
+    /// The saved blocks of `coord`, or `None` when it was never edited or its
+    /// region cannot be read.
+    pub fn load_chunk(&self, coord: ChunkPos) -> Option<Chunk> {
+        let mut cache = self.cache.lock().expect("region cache poisoned");
+        if let Some(blocks) = cache.chunks.get(&coord) {
+            return Some(Chunk::from_blocks(blocks.to_vec()));
+        }
+
+        let (region, slot) = RegionCoord::of(coord);
+        let path = self.region_path(region);
+        let blocks = self
+            .map_region(&mut cache, region)
+            .and_then(|map| read_payload(&map, slot)?.map(decode_blocks).transpose());
+        match blocks {
+            Ok(Some(blocks)) => {
+                let blocks: Arc<[BlockId]> = blocks.into();
+                cache.chunks.insert(coord, Arc::clone(&blocks));
+                Some(Chunk::from_blocks(blocks.to_vec()))
+            }
+            Ok(None) => None,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+            Err(err) => {
+                warn!(
+                    "Failed to read chunk {:?} from region file {}: {}",
+                    coord,
+                    path.display(),
+                    err
+                );
+                None
+            }
+        }
+    }
+
+    /// Writes `chunks` into their region files, keeping the other chunks
+    /// already saved there.
+    ///
+    /// A region too damaged to parse would otherwise fail every store into
+    /// it, so it is moved aside to a `.corrupt` file and started over with
+    /// just the chunks being stored.
+    pub fn store_chunks<'a>(
+        &self,
+        chunks: impl IntoIterator<Item = (ChunkPos, &'a Chunk)>,
+    ) -> io::Result<()> {
+        let mut cache = self.cache.lock().expect("region cache poisoned");
+        let mut regions: HashMap<RegionCoord, Vec<(usize, Vec<u8>)>> = HashMap::new();
+        for (coord, chunk) in chunks {
+            let (region, slot) = RegionCoord::of(coord);
+            regions
+                .entry(region)
+                .or_default()
+                .push((slot, encode_blocks(chunk.blocks())));
+            // Stored chunks are the ones just unloaded, and the likeliest to
+            // come back.
+            cache.chunks.insert(coord, chunk.blocks().into());
+        }
+        if regions.is_empty() {
+            return Ok(());
+        }
+
+        fs::create_dir_all(&self.dir)?;
+        for (region, updates) in regions {
+            let path = self.region_path(region);
+            let parsed = self
+                .map_region(&mut cache, region)
+                .and_then(|map| parse_region(&map));
+            let mut payloads = match parsed {
+                Ok(payloads) => payloads,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => vec![None; REGION_CHUNKS],
+                Err(err) if err.kind() == io::ErrorKind::InvalidData => {
+                    let backup = path.with_extension("region.corrupt");
+                    warn!(
+                        "Region file {} is damaged ({}); moving it to {} and starting over",
+                        path.display(),
+                        err,
+                        backup.display()
+                    );
+                    cache.regions.remove(&region);
+                    // Chunks decoded from the old file are no longer on disk.
+                    let stored: Vec<usize> = updates.iter().map(|(slot, _)| *slot).collect();
+                    cache.chunks.retain(|coord| {
+                        let (chunk_region, slot) = RegionCoord::of(*coord);
+                        chunk_region != region || stored.contains(&slot)
+                    });
+                    fs::rename(&path, &backup)?;
+                    vec![None; REGION_CHUNKS]
+                }
+                Err(err) => return Err(err),
+            };
+            for (slot, payload) in updates {
+                payloads[slot] = Some(payload);
+            }
+            // Unmap before replacing the file, which some platforms refuse
+            // while it is mapped; the next load maps the new file.
+            cache.regions.remove(&region);
+            // Write beside the old file and swap it in, so a crash mid-write
+            // leaves the previous region intact.
+            let temp = path.with_extension("region.tmp");
+            fs::write(&temp, write_region(&payloads))?;
+            fs::rename(&temp, &path)?;
+        }
+        Ok(())
+    }
+
+    fn map_region(&self, cache: &mut RegionCache, region: RegionCoord) -> io::Result<Arc<Mmap>> {
+        if let Some(map) = cache.regions.get(&region) {
+            return Ok(Arc::clone(map));
+        }
+        let file = File::open(self.region_path(region))?;
+        if file.metadata()?.len() < HEADER_SIZE as u64 {
+            return Err(invalid_data("region file is shorter than its header"));
+        }
+        // SAFETY: region files are only written by `store_chunks`, which
+        // replaces them with a renamed copy and never modifies one in place,
+        // so a mapped file's contents do not change under the mapping.
+        let map = Arc::new(unsafe { Mmap::map(&file)? });
+        check_magic(&map)?;
+        cache.regions.insert(region, Arc::clone(&map));
+        Ok(map)
+    }
+
+    fn region_path(&self, region: RegionCoord) -> PathBuf {
+        self.dir
+            .join(format!("r.{}.{}.{}.region", region.x, region.y, region.z))
+    }
+}
+
+/// The payload of `slot` in a mapped region, or `None` for an empty slot.
+fn read_payload(region: &[u8], slot: usize) -> io::Result<Option<&[u8]>> {
+    let (offset, length) = table_entry(region, slot);
+    if length == 0 {
+        return Ok(None);
+    }
+    region
+        .get(offset as usize..offset as usize + length as usize)
+        .map(Some)
+        .ok_or_else(|| invalid_data("chunk payload runs past the end of the region"))
+}
+
+/// Every slot's payload, `None` for chunks the region does not hold.
+fn parse_region(bytes: &[u8]) -> io::Result<Vec<Option<Vec<u8>>>> {
+    if bytes.len() < HEADER_SIZE {
+        return Err(invalid_data("region file is shorter than its header"));
+    }
+    check_magic(bytes)?;
+    (0..REGION_CHUNKS)
+        .map(|slot| Ok(read_payload(bytes, slot)?.map(<[u8]>::to_vec)))
+        .collect()
+}
+
+fn write_region(payloads: &[Option<Vec<u8>>]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(HEADER_SIZE);
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&VERSION.to_le_bytes());
+    let mut offset = HEADER_SIZE as u32;
+    for payload in payloads {
+        let length = payload.as_ref().map_or(0, |payload| payload.len() as u32);
+        bytes.extend_from_slice(&offset.to_le_bytes());
+        bytes.extend_from_slice(&length.to_le_bytes());
+        offset += length;
+    }
+    for payload in payloads.iter().flatten() {
+        bytes.extend_from_slice(payload);
+    }
+    bytes
+}
+
+fn check_magic(header: &[u8]) -> io::Result<()> {
+    if &header[..4] != MAGIC {
+        return Err(invalid_data("not a region file"));
+    }
+    let version = u32::from_le_bytes(header[4..8].try_into().unwrap());
+    if version != VERSION {
+        return Err(invalid_data(format!(
+            "unsupported region version {version}"
+        )));
+    }
+    Ok(())
+}
+
+fn table_entry(header: &[u8], slot: usize) -> (u32, u32) {
+    let entry = 8 + slot * 8;
+    let read = |at: usize| u32::from_le_bytes(header[at..at + 4].try_into().unwrap());
+    (read(entry), read(entry + 4))
+}
+
+/// Runs of `(length: u16 LE, block)`; a run never exceeds one chunk.
+fn encode_blocks(blocks: &[BlockId]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut iter = blocks.iter().peekable();
+    while let Some(&block) = iter.next() {
+        let mut run: u16 = 1;
+        while iter.next_if(|&&next| next == block).is_some() {
+            run += 1;
+        }
+        bytes.extend_from_slice(&run.to_le_bytes());
+        bytes.push(block);
+    }
+    bytes
+}
+
+fn decode_blocks(bytes: &[u8]) -> io::Result<Vec<BlockId>> {
+    if !bytes.len().is_multiple_of(3) {
+        return Err(invalid_data("truncated block run"));
+    }
+    let mut blocks = Vec::with_capacity(CHUNK_VOLUME);
+    for run in bytes.chunks_exact(3) {
+        let length = u16::from_le_bytes([run[0], run[1]]) as usize;
+        if blocks.len() + length > CHUNK_VOLUME {
+            return Err(invalid_data("block runs overflow the chunk"));
+        }
+        blocks.resize(blocks.len() + length, run[2]);
+    }
+    if blocks.len() != CHUNK_VOLUME {
+        return Err(invalid_data("block runs do not fill the chunk"));
+    }
+    Ok(blocks)
+}
+
+/// A map that forgets its least recently used entry once it is full.
+struct Lru<K, V> {
+    capacity: usize,
+    /// Bumped on every access; each entry keeps the tick it was last used at.
+    tick: u64,
+    entries: HashMap<K, (V, u64)>,
+}
+
+impl<K: Copy + Eq + Hash, V> Lru<K, V> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            tick: 0,
+            entries: HashMap::new(),
+        }
+    }
+
+    fn get(&mut self, key: &K) -> Option<&V> {
+        self.tick += 1;
+        let (value, used) = self.entries.get_mut(key)?;
+        *used = self.tick;
+        Some(value)
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        self.tick += 1;
+        if self.entries.len() >= self.capacity
+            && !self.entries.contains_key(&key)
+            && let Some(oldest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, used))| *used)
+                .map(|(key, _)| *key)
+        {
+            self.entries.remove(&oldest);
+        }
+        self.entries.insert(key, (value, self.tick));
+    }
+
+    fn remove(&mut self, key: &K) {
+        self.entries.remove(key);
+    }
+
+    fn retain(&mut self, mut keep: impl FnMut(&K) -> bool) {
+        self.entries.retain(|key, _| keep(key));
+    }
+}
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::temp_dir::TempDir;
+
+    /// Chunk contents drawn from a small random palette, either cell by cell
+    /// or in runs, so both noisy and mostly uniform chunks come up.
+    fn chunk_blocks() -> impl Strategy<Value = Vec<BlockId>> {
+        prop::collection::vec(any::<BlockId>(), 1..6).prop_flat_map(|palette| {
+            let pick = prop::sample::select(palette);
+            prop_oneof![
+                prop::collection::vec(pick.clone(), CHUNK_VOLUME),
+                prop::collection::vec((pick, 1..1500usize), 1..40).prop_map(|runs| {
+                    let mut blocks: Vec<BlockId> = runs
+                        .into_iter()
+                        .flat_map(|(block, length)| std::iter::repeat_n(block, length))
+                        .collect();
+                    let last = *blocks.last().unwrap();
+                    blocks.resize(CHUNK_VOLUME, last);
+                    blocks
+                }),
+            ]
+        })
+    }
+
+    /// A few chunks on both sides of region boundaries, each coordinate at
+    /// most once.
+    fn chunk_set() -> impl Strategy<Value = HashMap<ChunkPos, Vec<BlockId>>> {
+        let coord = (-40..40i32, -2..2i32, -40..40i32).prop_map(|(x, y, z)| ChunkPos { x, y, z });
+        prop::collection::hash_map(coord, chunk_blocks(), 1..6)
+    }
+
+    fn store_all(
+        storage: &RegionStorage,
+        chunks: &HashMap<ChunkPos, Vec<BlockId>>,
+    ) -> io::Result<()> {
+        let chunks: Vec<(ChunkPos, Chunk)> = chunks
+            .iter()
+            .map(|(coord, blocks)| (*coord, Chunk::from_blocks(blocks.clone())))
+            .collect();
+        storage.store_chunks(chunks.iter().map(|(coord, chunk)| (*coord, chunk)))
+    }
+
+    /// Stores `chunks` in `dir` and returns the region files written.
+    fn store(dir: &TempDir, chunks: &HashMap<ChunkPos, Vec<BlockId>>) -> Vec<PathBuf> {
+        store_all(&RegionStorage::new(dir.path().to_path_buf()), chunks).unwrap();
+        fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect()
+    }
+
+    #[test]
+    fn storing_into_a_truncated_region_backs_it_up_and_starts_over() {
+        let dir = TempDir::new("storage");
+        let first = ChunkPos { x: 1, y: 0, z: 1 };
+        let second = ChunkPos { x: 2, y: 0, z: 1 };
+        let blocks = |block: BlockId| vec![block; CHUNK_VOLUME];
+        let path = store(&dir, &HashMap::from([(first, blocks(3))])).remove(0);
+        let bytes = fs::read(&path).unwrap();
+        fs::write(&path, &bytes[..HEADER_SIZE / 2]).unwrap();
+
+        let storage = RegionStorage::new(dir.path().to_path_buf());
+        store_all(&storage, &HashMap::from([(second, blocks(5))])).unwrap();
+
+        let backup = path.with_extension("region.corrupt");
+        assert_eq!(fs::read(&backup).unwrap(), &bytes[..HEADER_SIZE / 2]);
+        let storage = RegionStorage::new(dir.path().to_path_buf());
+        assert_eq!(
+            storage.load_chunk(second).as_ref().map(Chunk::blocks),
+            Some(blocks(5).as_slice())
+        );
+        assert!(storage.load_chunk(first).is_none());
+    }
+
+    proptest! {
+        #[test]
+        fn blocks_round_trip(blocks in chunk_blocks()) {
+            prop_assert_eq!(decode_blocks(&encode_blocks(&blocks)).unwrap(), blocks);
+        }
+
+        #[test]
+        fn decoding_arbitrary_bytes_fills_the_chunk_or_fails(
+            bytes in prop::collection::vec(any::<u8>(), 0..600),
+        ) {
+            if let Ok(blocks) = decode_blocks(&bytes) {
+                prop_assert_eq!(blocks.len(), CHUNK_VOLUME);
+            }
+        }
+
+        #[test]
+        fn parsing_arbitrary_regions_never_panics(
+            tail in prop::collection::vec(any::<u8>(), 0..HEADER_SIZE * 2),
+            with_header in any::<bool>(),
+        ) {
+            let mut bytes = Vec::new();
+            if with_header {
+                bytes.extend_from_slice(MAGIC);
+                bytes.extend_from_slice(&VERSION.to_le_bytes());
+            }
+            bytes.extend_from_slice(&tail);
+            if let Ok(payloads) = parse_region(&bytes) {
+                prop_assert_eq!(payloads.len(), REGION_CHUNKS);
+                for payload in payloads.iter().flatten() {
+                    let _ = decode_blocks(payload);
+                }
+            }
+        }
+    }
+
+    proptest! {
+        // Every case writes region files, so fewer of them.
+        #![proptest_config(ProptestConfig::with_cases(48))]
+
+        #[test]
+        fn stored_chunks_load_back_equal(chunks in chunk_set()) {
+            let dir = TempDir::new("storage");
+            store(&dir, &chunks);
+            // A fresh storage, so the chunks come from disk and not the cache.
+            let storage = RegionStorage::new(dir.path().to_path_buf());
+            for (coord, blocks) in &chunks {
+                let chunk = storage.load_chunk(*coord);
+                prop_assert_eq!(chunk.as_ref().map(Chunk::blocks), Some(blocks.as_slice()));
+            }
+            let unsaved = ChunkPos { x: 100, y: 0, z: 100 };
+            prop_assert!(storage.load_chunk(unsaved).is_none());
+        }
+
+        #[test]
+        fn truncated_regions_load_equal_chunks_or_none(
+            chunks in chunk_set(),
+            keep in 0.0..1.0f64,
+        ) {
+            let dir = TempDir::new("storage");
+            for path in store(&dir, &chunks) {
+                let bytes = fs::read(&path).unwrap();
+                let length = (bytes.len() as f64 * keep) as usize;
+                fs::write(&path, &bytes[..length]).unwrap();
+                prop_assert!(parse_region(&bytes[..length]).is_err() || length == bytes.len());
+            }
+            let storage = RegionStorage::new(dir.path().to_path_buf());
+            for (coord, blocks) in &chunks {
+                if let Some(chunk) = storage.load_chunk(*coord) {
+                    prop_assert_eq!(chunk.blocks(), blocks.as_slice());
+                }
+            }
+        }
+
+        #[test]
+        fn corrupted_regions_load_full_chunks_or_none(
+            chunks in chunk_set(),
+            flips in prop::collection::vec((any::<prop::sample::Index>(), 1..=255u8), 1..16),
+        ) {
+            let dir = TempDir::new("storage");
+            for path in store(&dir, &chunks) {
+                let mut bytes = fs::read(&path).unwrap();
+                for (index, mask) in &flips {
+                    let at = index.index(bytes.len());
+                    bytes[at] ^= mask;
+                }
+                fs::write(&path, &bytes).unwrap();
+            }
+            let storage = RegionStorage::new(dir.path().to_path_buf());
+            for coord in chunks.keys() {
+                if let Some(chunk) = storage.load_chunk(*coord) {
+                    prop_assert_eq!(chunk.blocks().len(), CHUNK_VOLUME);
+                }
+            }
+            // Storing over a damaged region leaves every stored chunk
+            // readable, replacing the region if it has to.
+            store_all(&storage, &chunks).unwrap();
+            let storage = RegionStorage::new(dir.path().to_path_buf());
+            for (coord, blocks) in &chunks {
+                let chunk = storage.load_chunk(*coord);
+                prop_assert_eq!(chunk.as_ref().map(Chunk::blocks), Some(blocks.as_slice()));
+            }
+        }
+    }
+}
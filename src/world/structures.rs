@@ -0,0 +1,168 @@
+//! Trees and other structures that grow out of the chunk they are rooted in.
+//!
+//! Structures are planned a chunk column at a time, the first time a chunk
+//! within reach of the column generates. Their blocks wait in a queue keyed
+//! by the chunk they fall in, and each chunk takes its share once it
+//! generates, so a canopy overhanging a border is whole whichever side of it
+//! generates first.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::block::BlockKind;
+use crate::coords::{BlockPos, ChunkPos, LocalPos};
+use crate::decoration::{self, TREE_RADIUS};
+use crate::terrain::TerrainGenerator;
+
+use super::{CHUNK_SIZE, Chunk};
+
+/// How many chunk columns away a structure can reach from its own.
+const COLUMN_REACH: i32 = (TREE_RADIUS + CHUNK_SIZE as i32 - 1) / CHUNK_SIZE as i32;
+
+/// Structure blocks waiting for their chunks to generate.
+///
+/// Planning is a pure function of the terrain, so a column forgotten and
+/// planned again queues exactly the same blocks.
+#[derive(Default)]
+pub(super) struct StructureQueue {
+    /// Chunk columns whose structures are queued.
+    planned: HashSet<(i32, i32)>,
+    pending: HashMap<ChunkPos, HashMap<LocalPos, BlockKind>>,
+}
+
+impl StructureQueue {
+    /// Writes the structure blocks queued for `coord` into its freshly
+    /// generated `chunk`, planning any column within reach first.
+    pub fn apply(&mut self, terrain: TerrainGenerator, coord: ChunkPos, chunk: &mut Chunk) {
+        for (column_x, column_z) in columns_around(coord.x, coord.z) {
+            if self.planned.insert((column_x, column_z)) {
+                self.plan(terrain, column_x, column_z);
+            }
+        }
+        for (local, kind) in self.pending.remove(&coord).into_iter().flatten() {
+            if replaces(kind, BlockKind::from_id(chunk.get(local))) {
+                chunk.set(local, kind.id());
+            }
+        }
+    }
+
+    /// Drops the blocks queued for `coord` and plans the columns that feed
+    /// it again when next asked, so the chunk gets them back if it is ever
+    /// regenerated.
+    pub fn forget(&mut self, coord: ChunkPos) {
+        self.pending.remove(&coord);
+        for column in columns_around(coord.x, coord.z) {
+            self.planned.remove(&column);
+        }
+    }
+
+    /// Forgets every chunk more than `radius` columns from `center`.
+    pub fn forget_outside(&mut self, center: ChunkPos, radius: i32) {
+        let outside = |x: i32, z: i32, radius: i32| {
+            (x - center.x).abs() > radius || (z - center.z).abs() > radius
+        };
+        self.pending
+            .retain(|coord, _| !outside(coord.x, coord.z, radius));
+        // Columns this close only feed chunks whose queues were kept.
+        self.planned
+            .retain(|&(x, z)| !outside(x, z, radius - COLUMN_REACH));
+    }
+
+    fn plan(&mut self, terrain: TerrainGenerator, column_x: i32, column_z: i32) {
+        decoration::column_trees(terrain, column_x, column_z, &mut |position, kind| {
+            let (coord, local) = BlockPos(position).split();
+            self.pending
+                .entry(coord)
+                .or_default()
+                .entry(local)
+                .and_modify(|queued| {
+                    // Where trees overlap, trunks win over canopies.
+                    if kind == BlockKind::Log {
+                        *queued = kind;
+                    }
+                })
+                .or_insert(kind);
+        });
+    }
+}
+
+fn columns_around(x: i32, z: i32) -> impl Iterator<Item = (i32, i32)> {
+    (-COLUMN_REACH..=COLUMN_REACH)
+        .flat_map(move |dz| (-COLUMN_REACH..=COLUMN_REACH).map(move |dx| (x + dx, z + dz)))
+}
+
+/// Whether a structure block of `kind` goes where the terrain put
+/// `existing`: canopies only fill air, and trunks also push through snow
+/// and flowers.
+fn replaces(kind: BlockKind, existing: BlockKind) -> bool {
+    match kind {
+        BlockKind::Log => matches!(
+            existing,
+            BlockKind::Air | BlockKind::SnowLayer(_) | BlockKind::Flower(_)
+        ),
+        _ => existing == BlockKind::Air,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::terrain::TerrainPreset;
+    use crate::world::{chunk_coords_in_radius, generate_chunk};
+
+    fn terrain() -> TerrainGenerator {
+        TerrainGenerator::new(TerrainPreset::Default, 0)
+    }
+
+    fn generate_in_order(
+        coords: impl Iterator<Item = ChunkPos>,
+        structures: &mut StructureQueue,
+    ) -> HashMap<ChunkPos, Chunk> {
+        coords
+            .map(|coord| {
+                (
+                    coord,
+                    generate_chunk(coord, terrain(), structures, &mut Default::default()),
+                )
+            })
+            .collect()
+    }
+
+    fn count(chunks: &HashMap<ChunkPos, Chunk>, kind: BlockKind) -> usize {
+        chunks
+            .values()
+            .flat_map(Chunk::blocks)
+            .filter(|&&block| block == kind.id())
+            .count()
+    }
+
+    #[test]
+    fn trees_are_whole_whichever_order_chunks_generate() {
+        let coords: Vec<ChunkPos> = chunk_coords_in_radius(ChunkPos::new(0, 0, 0), 2, 1).collect();
+        let forward = generate_in_order(coords.iter().copied(), &mut StructureQueue::default());
+        let backward =
+            generate_in_order(coords.iter().rev().copied(), &mut StructureQueue::default());
+
+        assert!(count(&forward, BlockKind::Log) > 0);
+        assert!(count(&forward, BlockKind::Leaves) > 0);
+        for coord in coords {
+            assert_eq!(
+                forward[&coord].blocks(),
+                backward[&coord].blocks(),
+                "{coord:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn forgotten_chunks_get_their_trees_back() {
+        let mut structures = StructureQueue::default();
+        let coords: Vec<ChunkPos> = chunk_coords_in_radius(ChunkPos::new(0, 0, 0), 1, 1).collect();
+        let chunks = generate_in_order(coords.iter().copied(), &mut structures);
+
+        for coord in coords {
+            structures.forget(coord);
+            let again = generate_chunk(coord, terrain(), &mut structures, &mut Default::default());
+            assert_eq!(chunks[&coord].blocks(), again.blocks(), "{coord:?}");
+        }
+    }
+}